@@ -83,6 +83,7 @@ export default ts.config(
       "dist/",
       "node_modules/",
       "src-tauri/target/",
+      "src/lib/bindings.ts",
       "*.config.js",
       "*.config.ts",
     ],
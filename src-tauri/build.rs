@@ -2,6 +2,274 @@
 //!
 //! This build script is run before compiling the main application.
 //! It uses `tauri_build` to generate the necessary bindings and resources.
+//!
+//! Listing the app's commands makes Tauri check every one of them against
+//! the window's capabilities (see `capabilities/` and `permissions/`), so a
+//! new command must be added here and to a permission set before any window
+//! can call it.
+
+/// Every command registered with the invoke handler.
+const COMMANDS: &[&str] = &[
+    "greet",
+    "get_api_key",
+    "set_api_key",
+    "delete_api_key",
+    "list_conversations",
+    "get_conversation",
+    "export_conversation",
+    "create_conversation",
+    "append_message",
+    "delete_conversation",
+    "list_scheduled_tasks",
+    "create_scheduled_task",
+    "update_scheduled_task",
+    "delete_scheduled_task",
+    "run_scheduled_task_now",
+    "list_workflows",
+    "save_workflow",
+    "delete_workflow",
+    "run_workflow",
+    "list_workflow_runs",
+    "get_workflow_run",
+    "show_quick_capture",
+    "hide_quick_capture",
+    "submit_quick_capture",
+    "get_tray_status",
+    "set_close_to_tray",
+    "begin_generation",
+    "end_generation",
+    "take_launch_args",
+    "take_pending_navigation",
+    "open_conversation_window",
+    "list_conversation_windows",
+    "close_conversation_window",
+    "set_current_conversation",
+    "get_notification_settings",
+    "set_notification_settings",
+    "notify_generation_complete",
+    "get_clipboard_settings",
+    "set_clipboard_settings",
+    "get_clipboard_capture",
+    "clear_clipboard_capture",
+    "ask_about_clipboard",
+    "stage_attachment",
+    "list_pending_attachments",
+    "list_conversation_attachments",
+    "attach_to_conversation",
+    "read_attachment_text",
+    "delete_attachment",
+    "capture_screenshot",
+    "list_capture_sources",
+    "get_update_settings",
+    "set_update_settings",
+    "check_for_updates",
+    "download_update",
+    "restart_to_update",
+    "get_shortcuts",
+    "set_shortcuts",
+    "reset_shortcuts",
+    "get_settings",
+    "update_settings",
+    "export_settings",
+    "preview_settings_import",
+    "import_settings",
+    "reset_settings",
+    "query_logs",
+    "open_log_folder",
+    "generate_diagnostics",
+    "get_telemetry_settings",
+    "set_telemetry_settings",
+    "preview_telemetry",
+    "purge_telemetry",
+    "list_crash_reports",
+    "delete_crash_reports",
+    "submit_crash_report",
+    "get_network_settings",
+    "set_network_settings",
+    "get_network_activity",
+    "clear_network_activity",
+    "get_network_status",
+    "enqueue_message",
+    "list_outbox",
+    "retry_outbox_entry",
+    "discard_outbox_entry",
+    "get_api_server_status",
+    "set_api_server_settings",
+    "regenerate_api_server_token",
+    "get_bridge_status",
+    "set_bridge_settings",
+    "get_browser_bridge_settings",
+    "set_origin_access",
+    "set_browser_bridge_save_conversations",
+    "list_webhooks",
+    "create_webhook",
+    "update_webhook",
+    "rotate_webhook_secret",
+    "delete_webhook",
+    "list_webhook_deliveries",
+    "open_email_draft",
+    "extract_action_items",
+    "create_action_items",
+    "list_git_repositories",
+    "grant_git_repository",
+    "revoke_git_repository",
+    "git_assist",
+    "list_plugins",
+    "install_plugin",
+    "set_plugin_enabled",
+    "set_plugin_capabilities",
+    "uninstall_plugin",
+    "call_plugin_tool",
+    "list_automations",
+    "create_automation",
+    "update_automation",
+    "delete_automation",
+    "run_automation",
+    "get_locale_strings",
+    "set_locale",
+    "render_markdown",
+    "get_highlight_css",
+    "extract_code_blocks",
+    "save_code_block",
+    "list_shares",
+    "get_share_settings",
+    "set_share_settings",
+    "create_share_link",
+    "revoke_share",
+    "deidentify_conversation",
+    "add_to_reading_list",
+    "add_clipboard_to_reading_list",
+    "list_reading_list",
+    "retry_reading_item",
+    "delete_reading_item",
+    "search_library",
+    "get_feed_settings",
+    "set_feed_settings",
+    "list_feeds",
+    "subscribe_feed",
+    "update_feed",
+    "unsubscribe_feed",
+    "list_feed_items",
+    "refresh_feeds",
+    "generate_feed_digest",
+    "get_nostr_digest_settings",
+    "set_nostr_digest_settings",
+    "generate_nostr_digest",
+    "generate_image",
+    "get_image_generation",
+    "get_stable_diffusion_settings",
+    "set_stable_diffusion_settings",
+    "list_stable_diffusion_models",
+    "cancel_local_image",
+    "edit_image_attachment",
+    "prepare_image_upload",
+    "get_routing_policy",
+    "set_routing_policy",
+    "set_conversation_sensitive",
+    "list_sensitive_conversations",
+    "check_conversation_route",
+    "get_privacy_fields",
+    "get_audit_log",
+    "export_audit_log",
+    "get_rate_limits",
+    "set_rate_limits",
+    "get_rate_limit_state",
+    "acquire_rate_limit",
+    "get_spend_caps",
+    "set_spend_caps",
+    "get_spend_summary",
+    "override_spend_cap",
+    "record_spend",
+    "get_model_metrics",
+    "record_model_metric",
+    "rank_models",
+    "get_response_cache_stats",
+    "clear_response_cache",
+    "list_prompt_tests",
+    "save_prompt_test",
+    "delete_prompt_test",
+    "run_prompt_tests",
+    "list_prompt_test_runs",
+    "get_prompt_test_run",
+    "list_experiments",
+    "save_experiment",
+    "set_experiment_active",
+    "delete_experiment",
+    "get_experiment_assignment",
+    "rate_experiment_message",
+    "get_experiment_stats",
+    "rate_message",
+    "list_feedback",
+    "export_feedback",
+    "get_conversation_tags",
+    "set_conversation_tags",
+    "export_dataset",
+    "diff_messages",
+    "stream_chat",
+    "ack_chat_stream",
+    "list_jobs",
+    "cancel_job",
+    "get_startup_status",
+    "get_messages",
+    "get_event_schema",
+    "update_session_window",
+    "get_restorable_session",
+    "restore_session",
+    "discard_session",
+    "resume_chat_stream",
+    "list_interrupted_streams",
+    "discard_interrupted_stream",
+    "list_presets",
+    "save_preset",
+    "delete_preset",
+    "start_preset_conversation",
+    "get_conversation_preset",
+    "get_command_palette_actions",
+    "record_usage",
+    "get_ranked_usage",
+    "set_favorite",
+    "get_conversation_stats",
+    "prepare_paste",
+    "get_data_tools",
+    "describe_data_attachment",
+    "query_data_attachment",
+    "describe_attached_db",
+    "query_attached_db",
+    "transform_data",
+    "calculate",
+    "convert_units",
+    "get_datetime_context",
+    "resolve_datetime",
+    "get_location_settings",
+    "set_location_settings",
+    "get_location_context",
+    "get_weather",
+    "detect_language",
+    "translate_text",
+    "translate_attachment",
+    "rewrite_text",
+    "rewrite_clipboard",
+    "take_external_input",
+    "run_external_input",
+    "install_text_services",
+    "remove_text_services",
+    "get_nostr_composer_settings",
+    "set_nostr_composer_settings",
+    "list_nostr_drafts",
+    "save_nostr_draft",
+    "delete_nostr_draft",
+    "revise_nostr_draft",
+    "publish_nostr_draft",
+    "sync_nostr_drafts",
+    "get_media_settings",
+    "set_media_settings",
+    "upload_media",
+    "upload_media_file",
+    "export_conversation_audio",
+    "generate_flashcards",
+    "export_flashcards",
+    "read_attachment_bytes",
+];
 
 /// Main entry point for the Tauri build process.
 ///
@@ -9,6 +277,11 @@
 /// - Generate resource files
 /// - Create platform-specific bindings
 /// - Set up the application bundle configuration
+/// - Generate `allow-*` permissions for the app's commands
 fn main() {
-    tauri_build::build();
+    tauri_build::try_build(
+        tauri_build::Attributes::new()
+            .app_manifest(tauri_build::AppManifest::new().commands(COMMANDS)),
+    )
+    .expect("failed to run tauri-build");
 }
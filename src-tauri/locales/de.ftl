@@ -0,0 +1,41 @@
+# Deutsche Texte.
+
+## Tray menu
+
+tray-new-chat = Neuer Chat
+tray-quick-capture = Schnellerfassung
+tray-ask-clipboard = Zur Zwischenablage fragen
+tray-pause-sync = Synchronisierung pausieren
+tray-quit = Beenden
+tray-generating = Gibber AI – generiert…
+
+## Notifications
+
+notification-reply-ready = Antwort bereit
+notification-clipboard-title = Gibber dazu fragen?
+notification-clipboard-image = Bild kopiert ({ $width }×{ $height })
+notification-task-finished = Fertig. Öffne Gibber AI, um das Ergebnis zu lesen.
+notification-workflow-finished = Fertig. Öffne Gibber AI, um die Ausgabe zu sehen.
+notification-failed = Fehlgeschlagen: { $error }
+
+## Errors, by error code
+
+error-network-error = Der Server ist nicht erreichbar. Prüfe deine Verbindung.
+error-no-api-key = Hinterlege in den Einstellungen einen OpenRouter-API-Schlüssel.
+error-invalid-api-key = Der API-Schlüssel wurde abgelehnt.
+error-rate-limited = Zu viele Anfragen. Versuche es gleich noch einmal.
+error-model-unavailable = Das Modell ist gerade nicht verfügbar.
+error-context-too-long = Die Unterhaltung ist zu lang für dieses Modell.
+error-not-found = Das gibt es nicht mehr.
+error-database = App-Daten konnten nicht gelesen oder geschrieben werden.
+error-unknown = Etwas ist schiefgelaufen.
+
+## Command palette
+
+palette-new-chat = Neuer Chat
+palette-quick-capture = Schnellerfassung
+palette-open-settings = Einstellungen öffnen
+palette-check-updates = Nach Updates suchen
+palette-pause-sync = Synchronisierung pausieren
+palette-diagnostics = Diagnosedaten erstellen
+palette-search = Unterhaltungen durchsuchen
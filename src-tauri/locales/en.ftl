@@ -0,0 +1,41 @@
+# English strings; the fallback for every other catalog.
+
+## Tray menu
+
+tray-new-chat = New chat
+tray-quick-capture = Quick capture
+tray-ask-clipboard = Ask about clipboard
+tray-pause-sync = Pause sync
+tray-quit = Quit
+tray-generating = Gibber AI — generating…
+
+## Notifications
+
+notification-reply-ready = Reply ready
+notification-clipboard-title = Ask Gibber about this?
+notification-clipboard-image = Copied image ({ $width }×{ $height })
+notification-task-finished = Finished. Open Gibber AI to read the result.
+notification-workflow-finished = Finished. Open Gibber AI to see the output.
+notification-failed = Failed: { $error }
+
+## Errors, by error code
+
+error-network-error = Couldn't reach the server. Check your connection.
+error-no-api-key = Add an OpenRouter API key in Settings.
+error-invalid-api-key = The API key was rejected.
+error-rate-limited = Too many requests. Try again in a moment.
+error-model-unavailable = The model is unavailable right now.
+error-context-too-long = The conversation is too long for this model.
+error-not-found = It no longer exists.
+error-database = Couldn't read or write app data.
+error-unknown = Something went wrong.
+
+## Command palette
+
+palette-new-chat = New chat
+palette-quick-capture = Quick capture
+palette-open-settings = Open settings
+palette-check-updates = Check for updates
+palette-pause-sync = Pause sync
+palette-diagnostics = Generate diagnostics
+palette-search = Search conversations
@@ -0,0 +1,41 @@
+# Textos en español.
+
+## Tray menu
+
+tray-new-chat = Nuevo chat
+tray-quick-capture = Captura rápida
+tray-ask-clipboard = Preguntar sobre el portapapeles
+tray-pause-sync = Pausar sincronización
+tray-quit = Salir
+tray-generating = Gibber AI — generando…
+
+## Notifications
+
+notification-reply-ready = Respuesta lista
+notification-clipboard-title = ¿Preguntar a Gibber sobre esto?
+notification-clipboard-image = Imagen copiada ({ $width }×{ $height })
+notification-task-finished = Listo. Abre Gibber AI para leer el resultado.
+notification-workflow-finished = Listo. Abre Gibber AI para ver la salida.
+notification-failed = Error: { $error }
+
+## Errors, by error code
+
+error-network-error = No se pudo conectar con el servidor. Revisa tu conexión.
+error-no-api-key = Añade una clave de API de OpenRouter en Ajustes.
+error-invalid-api-key = La clave de API fue rechazada.
+error-rate-limited = Demasiadas solicitudes. Inténtalo de nuevo en un momento.
+error-model-unavailable = El modelo no está disponible ahora mismo.
+error-context-too-long = La conversación es demasiado larga para este modelo.
+error-not-found = Ya no existe.
+error-database = No se pudieron leer ni escribir los datos de la app.
+error-unknown = Algo salió mal.
+
+## Command palette
+
+palette-new-chat = Nuevo chat
+palette-quick-capture = Captura rápida
+palette-open-settings = Abrir ajustes
+palette-check-updates = Buscar actualizaciones
+palette-pause-sync = Pausar sincronización
+palette-diagnostics = Generar diagnóstico
+palette-search = Buscar conversaciones
@@ -0,0 +1,41 @@
+# Textes en français.
+
+## Tray menu
+
+tray-new-chat = Nouvelle discussion
+tray-quick-capture = Capture rapide
+tray-ask-clipboard = Demander à propos du presse-papiers
+tray-pause-sync = Suspendre la synchronisation
+tray-quit = Quitter
+tray-generating = Gibber AI — génération…
+
+## Notifications
+
+notification-reply-ready = Réponse prête
+notification-clipboard-title = Demander à Gibber ?
+notification-clipboard-image = Image copiée ({ $width }×{ $height })
+notification-task-finished = Terminé. Ouvrez Gibber AI pour lire le résultat.
+notification-workflow-finished = Terminé. Ouvrez Gibber AI pour voir le résultat.
+notification-failed = Échec : { $error }
+
+## Errors, by error code
+
+error-network-error = Impossible de joindre le serveur. Vérifiez votre connexion.
+error-no-api-key = Ajoutez une clé d'API OpenRouter dans les réglages.
+error-invalid-api-key = La clé d'API a été refusée.
+error-rate-limited = Trop de requêtes. Réessayez dans un instant.
+error-model-unavailable = Le modèle est indisponible pour le moment.
+error-context-too-long = La conversation est trop longue pour ce modèle.
+error-not-found = Cet élément n'existe plus.
+error-database = Impossible de lire ou d'écrire les données de l'app.
+error-unknown = Une erreur s'est produite.
+
+## Command palette
+
+palette-new-chat = Nouvelle discussion
+palette-quick-capture = Capture rapide
+palette-open-settings = Ouvrir les réglages
+palette-check-updates = Rechercher des mises à jour
+palette-pause-sync = Suspendre la synchronisation
+palette-diagnostics = Générer un diagnostic
+palette-search = Rechercher dans les conversations
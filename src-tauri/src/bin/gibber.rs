@@ -0,0 +1,10 @@
+//! Gibber AI - headless command-line entry point.
+//!
+//! Runs prompts and moves conversations in and out of the app database
+//! without launching the GUI. Built with `--features cli`.
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    gibber_ai_lib::cli::main()
+}
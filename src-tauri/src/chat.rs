@@ -0,0 +1,634 @@
+//! Backend chat completion client.
+//!
+//! Most conversations run through the frontend AI client, but background
+//! features (scheduled prompts, workflows) need to reach OpenRouter without a
+//! webview in the loop. This module mirrors the request shape and error
+//! mapping of `src/lib/ai/client.ts` so both sides behave the same.
+//!
+//! [`stream`] reads the reply as server-sent events and hands each piece
+//! of text to a channel as it arrives; batching it into webview events is
+//! left to [`chat_stream`](crate::commands::chat_stream).
+
+use std::time::{Duration, Instant};
+
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::credentials;
+use crate::commands::datetime;
+use crate::commands::location;
+use crate::commands::model_metrics;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::outbox;
+use crate::commands::privacy;
+use crate::commands::response_cache;
+use crate::commands::routing_policy;
+use crate::commands::spend;
+use crate::commands::telemetry;
+use crate::commands::tray::GenerationGuard;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// OpenRouter API endpoint.
+pub const OPENROUTER_API_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+
+/// Model used when a caller does not specify one (the "standard" tier).
+pub const DEFAULT_MODEL: &str = "anthropic/claude-sonnet-4";
+
+/// Application identifiers sent to OpenRouter.
+const APP_REFERER: &str = "https://github.com/EverythingSings/gibber-ai";
+const APP_TITLE: &str = "Gibber AI";
+
+/// Receives the text of a streamed reply as it arrives.
+pub type DeltaSender = tokio::sync::mpsc::UnboundedSender<String>;
+
+/// Default completion limits, matching the frontend client.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+
+/// Role of a message in the conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    /// System instructions
+    System,
+    /// Message written by the user
+    User,
+    /// Message generated by the model
+    Assistant,
+}
+
+impl MessageRole {
+    /// Returns the wire/storage representation of the role.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::User => "user",
+            Self::Assistant => "assistant",
+        }
+    }
+
+    /// Parses a stored role string.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "system" => Some(Self::System),
+            "user" => Some(Self::User),
+            "assistant" => Some(Self::Assistant),
+            _ => None,
+        }
+    }
+}
+
+/// A single message sent to the model.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct ChatMessage {
+    /// The role of the message sender
+    pub role: MessageRole,
+    /// The message content
+    pub content: String,
+}
+
+impl ChatMessage {
+    /// Creates a message with the given role.
+    pub fn new(role: MessageRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+        }
+    }
+}
+
+/// Request body for a chat completion.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+pub struct ChatRequest {
+    /// The model ID to use
+    pub model: String,
+    /// The conversation messages
+    pub messages: Vec<ChatMessage>,
+    /// Maximum tokens to generate
+    pub max_tokens: u32,
+    /// Temperature for randomness
+    pub temperature: f32,
+    /// Structured output format, e.g. `{ "type": "json_schema", … }`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<serde_json::Value>,
+    /// Conversation the request is for, checked against the routing policy
+    #[serde(skip)]
+    pub conversation_id: Option<String>,
+}
+
+impl ChatRequest {
+    /// Builds a request with the default limits.
+    ///
+    /// `model` falls back to [`DEFAULT_MODEL`] when `None`.
+    pub fn new(model: Option<&str>, messages: Vec<ChatMessage>) -> Self {
+        Self {
+            model: model.unwrap_or(DEFAULT_MODEL).to_string(),
+            messages,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            temperature: DEFAULT_TEMPERATURE,
+            response_format: None,
+            conversation_id: None,
+        }
+    }
+
+    /// Marks the request as made for a conversation, so a sensitive
+    /// conversation is only sent where the routing policy allows.
+    #[must_use]
+    pub fn for_conversation(mut self, conversation_id: &str) -> Self {
+        self.conversation_id = Some(conversation_id.to_string());
+        self
+    }
+
+    /// Asks for a JSON reply matching `schema`.
+    ///
+    /// Models without structured output support ignore the format, so
+    /// callers should still parse the reply leniently.
+    #[must_use]
+    pub fn with_json_schema(mut self, name: &str, schema: serde_json::Value) -> Self {
+        let mut format = serde_json::json!({
+            "type": "json_schema",
+            "json_schema": { "name": name, "strict": true },
+        });
+        format["json_schema"]["schema"] = schema;
+        self.response_format = Some(format);
+        self
+    }
+}
+
+/// Token usage statistics reported by the provider.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, specta::Type)]
+pub struct TokenUsage {
+    /// Tokens in the prompt
+    pub prompt_tokens: u32,
+    /// Tokens in the completion
+    pub completion_tokens: u32,
+}
+
+/// A finished completion.
+#[derive(Debug, Clone)]
+pub struct ChatCompletion {
+    /// The generated text
+    pub content: String,
+    /// The model that actually served the request
+    pub model: String,
+    /// Token usage, when the provider reports it
+    pub usage: Option<TokenUsage>,
+    /// Cost in US dollars, when the provider reports it
+    pub cost_usd: Option<f64>,
+    /// Upstream provider that served the request, when reported
+    pub provider: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CompletionResponse {
+    model: String,
+    provider: Option<String>,
+    choices: Vec<CompletionChoice>,
+    usage: Option<ReportedUsage>,
+}
+
+/// OpenRouter's usage block, which includes the cost when asked for.
+#[derive(Deserialize)]
+struct ReportedUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    cost: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct CompletionChoice {
+    message: CompletionMessage,
+}
+
+#[derive(Deserialize)]
+struct CompletionMessage {
+    content: Option<String>,
+}
+
+/// One server-sent event of a streamed completion.
+#[derive(Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    provider: Option<String>,
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<ReportedUsage>,
+    #[serde(default)]
+    error: Option<ErrorBody>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+}
+
+#[derive(Default, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Splits a byte stream into the `data` payloads of server-sent events.
+///
+/// Only complete lines are decoded, so a multi-byte character split across
+/// chunks is never cut. Comment lines (OpenRouter's keep-alives) and other
+/// fields are skipped.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+        let Some(end) = self.buffer.iter().rposition(|&b| b == b'\n') else {
+            return Vec::new();
+        };
+        let complete: Vec<u8> = self.buffer.drain(..=end).collect();
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| data.trim().to_string())
+            .filter(|data| !data.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+/// Maps an HTTP error status to an error code, like `createErrorFromResponse`.
+fn from_status(status: u16, message: String) -> GibberError {
+    let code = match status {
+        401 => "INVALID_API_KEY",
+        429 => "RATE_LIMITED",
+        503 => "MODEL_UNAVAILABLE",
+        400 if message.contains("context_length") => "CONTEXT_TOO_LONG",
+        _ => "UNKNOWN",
+    };
+    GibberError::new(code, message)
+}
+
+/// Sends a chat completion request to OpenRouter.
+///
+/// The API key is read from the system keyring under the `openrouter` service.
+/// The tray shows a generation indicator while the request is in flight.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `POLICY_BLOCKED` if the routing policy
+/// refuses the request, `BUDGET_EXCEEDED` if it would go over a spend cap,
+/// or another code if no key is stored, the request fails, or the response
+/// cannot be parsed.
+pub async fn complete(
+    app: &AppHandle,
+    request: &ChatRequest,
+) -> Result<ChatCompletion, GibberError> {
+    let result = send(app, request, None).await;
+    observe(app, &result);
+    result
+}
+
+/// Sends a chat completion request and streams the reply.
+///
+/// Each piece of text is sent to `deltas` as it arrives, and the whole
+/// completion is returned at the end as with [`complete`]. A reply from
+/// the response cache arrives as a single piece.
+///
+/// # Errors
+///
+/// Returns a `GibberError` as [`complete`] does; text received before a
+/// failure has already been sent.
+pub async fn stream(
+    app: &AppHandle,
+    request: &ChatRequest,
+    deltas: &DeltaSender,
+) -> Result<ChatCompletion, GibberError> {
+    let result = send(app, request, Some(deltas)).await;
+    observe(app, &result);
+    result
+}
+
+fn observe(app: &AppHandle, result: &Result<ChatCompletion, GibberError>) {
+    match result {
+        Ok(_) => outbox::set_online(app, true),
+        Err(e) => {
+            telemetry::record_error(app, "chat", e.code());
+            if e.code() == "NETWORK_ERROR" {
+                outbox::set_online(app, false);
+            }
+        }
+    }
+}
+
+async fn send(
+    app: &AppHandle,
+    request: &ChatRequest,
+    deltas: Option<&DeltaSender>,
+) -> Result<ChatCompletion, GibberError> {
+    if let Some(conversation_id) = &request.conversation_id {
+        routing_policy::enforce(
+            app,
+            conversation_id,
+            routing_policy::CHAT_GATEWAY,
+            &request.model,
+        )?;
+    }
+    let (cache_key, cached) = response_cache::get(app, request);
+    if let Some(completion) = cached {
+        if let Some(deltas) = deltas {
+            // A closed receiver only means nobody is watching any more.
+            let _ = deltas.send(completion.content.clone());
+        }
+        return Ok(completion);
+    }
+    // Added after the cache lookup, so the time doesn't defeat the cache.
+    let request = location::with_context(app, datetime::with_context(app, request)).await;
+    let request = &*request;
+    spend::enforce(app, request).await?;
+    let _generating = GenerationGuard::new(app);
+    let api_key = credentials::load_api_key(app, "openrouter")
+        .await
+        .map_err(|e| GibberError::new("NO_API_KEY", e.to_string()))?
+        .ok_or_else(|| GibberError::new("NO_API_KEY", "No OpenRouter API key is stored"))?;
+
+    let client = network::client(app, Service::OpenRouter)
+        .map_err(|e| GibberError::new("NETWORK_ERROR", e.to_string()))?;
+    let zero_data_retention = privacy::zero_data_retention(&app.state::<Database>().conn());
+    let builder = completion_request(
+        &client,
+        &api_key,
+        request,
+        zero_data_retention,
+        deltas.is_some(),
+    );
+    let started = Instant::now();
+    let (result, first_token) =
+        match network_activity::send(app, Service::OpenRouter, "chat.completion", builder).await {
+            Ok(response) => match deltas {
+                Some(deltas) => read_stream(response, deltas, started).await,
+                None => (read_completion(response).await, None),
+            },
+            Err(e) => (Err(e.into()), None),
+        };
+    let mut sample = model_metrics::sample(
+        &request.model,
+        result.as_ref().ok().and_then(|c| c.provider.as_deref()),
+        started.elapsed(),
+        result
+            .as_ref()
+            .ok()
+            .and_then(|c| c.usage)
+            .map(|usage| usage.completion_tokens),
+        result.as_ref().err().map(GibberError::code),
+    );
+    sample.ttft_ms = first_token.map(|ttft| u64::try_from(ttft.as_millis()).unwrap_or(u64::MAX));
+    model_metrics::record(app, &sample);
+    let completion = result?;
+    if let Some(key) = &cache_key {
+        response_cache::insert(app, key, &completion);
+    }
+    if let Some(cost) = completion.cost_usd {
+        spend::record(
+            app,
+            &completion.model,
+            request.conversation_id.as_deref(),
+            completion.usage,
+            cost,
+        );
+    }
+    Ok(completion)
+}
+
+/// Sends a chat completion request without an app handle, as the CLI does.
+///
+/// `zero_data_retention` adds the provider opt-outs of [`privacy`].
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the request fails or the response cannot be
+/// parsed.
+#[cfg(feature = "cli")]
+pub(crate) async fn complete_with(
+    client: &Client,
+    api_key: &str,
+    request: &ChatRequest,
+    zero_data_retention: bool,
+) -> Result<ChatCompletion, GibberError> {
+    let response = completion_request(client, api_key, request, zero_data_retention, false)
+        .send()
+        .await?;
+    read_completion(response).await
+}
+
+fn completion_request(
+    client: &Client,
+    api_key: &str,
+    request: &ChatRequest,
+    zero_data_retention: bool,
+    stream: bool,
+) -> RequestBuilder {
+    let mut body = serde_json::to_value(request).unwrap_or_default();
+    // Ask for the cost, which the spend ledger records.
+    body["usage"] = serde_json::json!({ "include": true });
+    if stream {
+        body["stream"] = serde_json::Value::Bool(true);
+    }
+    if zero_data_retention {
+        privacy::apply(&mut body, "openrouter");
+    }
+    client
+        .post(OPENROUTER_API_URL)
+        .bearer_auth(api_key)
+        .header("HTTP-Referer", APP_REFERER)
+        .header("X-Title", APP_TITLE)
+        .json(&body)
+}
+
+async fn status_error(response: Response) -> GibberError {
+    let status = response.status();
+    let message = response
+        .json::<ErrorResponse>()
+        .await
+        .map_or_else(|_| status.to_string(), |body| body.error.message);
+    from_status(status.as_u16(), message)
+}
+
+async fn read_completion(response: Response) -> Result<ChatCompletion, GibberError> {
+    if !response.status().is_success() {
+        return Err(status_error(response).await);
+    }
+
+    let body: CompletionResponse = response.json().await?;
+    let content = body
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .ok_or_else(|| GibberError::new("PARSE_ERROR", "Response contained no choices"))?;
+
+    Ok(ChatCompletion {
+        content,
+        model: body.model,
+        usage: body.usage.as_ref().map(|usage| TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }),
+        cost_usd: body.usage.and_then(|usage| usage.cost),
+        provider: body.provider,
+    })
+}
+
+/// Reads a streamed completion, sending text to `deltas` as it arrives.
+///
+/// Also returns the time from `started` to the first text.
+async fn read_stream(
+    mut response: Response,
+    deltas: &DeltaSender,
+    started: Instant,
+) -> (Result<ChatCompletion, GibberError>, Option<Duration>) {
+    if !response.status().is_success() {
+        return (Err(status_error(response).await), None);
+    }
+    let mut parser = SseParser::default();
+    let mut first_token = None;
+    let mut completion = ChatCompletion {
+        content: String::new(),
+        model: String::new(),
+        usage: None,
+        cost_usd: None,
+        provider: None,
+    };
+    'events: loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => return (Err(e.into()), first_token),
+        };
+        for data in parser.push(&chunk) {
+            if data == "[DONE]" {
+                break 'events;
+            }
+            let event: StreamChunk = match serde_json::from_str(&data) {
+                Ok(event) => event,
+                Err(e) => {
+                    return (
+                        Err(GibberError::new("PARSE_ERROR", e.to_string())),
+                        first_token,
+                    )
+                }
+            };
+            if let Some(error) = event.error {
+                return (Err(GibberError::new("UNKNOWN", error.message)), first_token);
+            }
+            if let Some(model) = event.model {
+                completion.model = model;
+            }
+            if event.provider.is_some() {
+                completion.provider = event.provider;
+            }
+            if let Some(usage) = event.usage {
+                completion.usage = Some(TokenUsage {
+                    prompt_tokens: usage.prompt_tokens,
+                    completion_tokens: usage.completion_tokens,
+                });
+                completion.cost_usd = usage.cost;
+            }
+            let text = event
+                .choices
+                .into_iter()
+                .next()
+                .and_then(|choice| choice.delta.content)
+                .filter(|text| !text.is_empty());
+            if let Some(text) = text {
+                first_token.get_or_insert_with(|| started.elapsed());
+                completion.content.push_str(&text);
+                // Keep reading if nobody listens, so the completion is whole.
+                let _ = deltas.send(text);
+            }
+        }
+    }
+    if completion.model.is_empty() {
+        return (
+            Err(GibberError::new(
+                "PARSE_ERROR",
+                "The stream ended without a reply",
+            )),
+            first_token,
+        );
+    }
+    (Ok(completion), first_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_round_trips() {
+        for role in [
+            MessageRole::System,
+            MessageRole::User,
+            MessageRole::Assistant,
+        ] {
+            assert_eq!(MessageRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(MessageRole::parse("tool"), None);
+    }
+
+    #[test]
+    fn test_request_serializes_like_frontend() {
+        let request = ChatRequest::new(None, vec![ChatMessage::new(MessageRole::User, "hi")]);
+        let json = serde_json::to_value(&request).expect("Should serialize");
+        assert_eq!(json["model"], DEFAULT_MODEL);
+        assert_eq!(json["messages"][0]["role"], "user");
+        assert_eq!(json["max_tokens"], 4096);
+        assert!(json.get("response_format").is_none());
+
+        let structured = request.with_json_schema("reply", serde_json::json!({ "type": "object" }));
+        let json = serde_json::to_value(&structured).expect("Should serialize");
+        assert_eq!(json["response_format"]["type"], "json_schema");
+        assert_eq!(json["response_format"]["json_schema"]["name"], "reply");
+    }
+
+    #[test]
+    fn test_error_from_status() {
+        assert_eq!(from_status(401, String::new()).code(), "INVALID_API_KEY");
+        assert_eq!(from_status(429, String::new()).code(), "RATE_LIMITED");
+        assert_eq!(
+            from_status(400, "context_length exceeded".into()).code(),
+            "CONTEXT_TOO_LONG"
+        );
+        assert_eq!(from_status(500, String::new()).code(), "UNKNOWN");
+    }
+
+    #[test]
+    fn test_sse_parser_joins_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser
+            .push(b": OPENROUTER PROCESSING\n\ndata: {\"a\"")
+            .is_empty());
+        let tempo = "data: \"t\u{e9}mpo\"\n".as_bytes();
+        let (head, tail) = tempo.split_at(9);
+        assert_eq!(
+            parser.push(&[&b":1}\n\n"[..], head].concat()),
+            ["{\"a\":1}"]
+        );
+        assert_eq!(parser.push(tail), ["\"t\u{e9}mpo\""]);
+        assert_eq!(parser.push(b"event: x\ndata: [DONE]\n\n"), ["[DONE]"]);
+    }
+}
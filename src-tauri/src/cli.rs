@@ -0,0 +1,603 @@
+//! Headless `gibber` command-line interface.
+//!
+//! Built with the `cli` feature. The CLI opens the same database and reads
+//! the same keyring entry as the desktop app, so conversations created from
+//! scripts show up in the UI and the app's settings (proxy, certificates)
+//! apply to its requests. It never starts Tauri.
+//!
+//! ```text
+//! gibber ask [--model <id>] [--system <prompt>] [--no-save] [<prompt>...]
+//! gibber export <conversation-id> [--format <json|html>] [--deidentify] [--output <file>]
+//! gibber import <file>
+//! gibber native-host-manifest --browser <chrome|firefox> --extension-id <id>
+//! ```
+//!
+//! `ask` reads the prompt from stdin when none is given and prints the reply
+//! to stdout. When a browser launches the executable for its extension, it
+//! runs as the native-messaging host instead (see [`native_host`]).
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Deserialize;
+
+use crate::chat::{self, ChatCompletion, ChatMessage, ChatRequest, MessageRole, TokenUsage};
+use crate::commands::conversations::{self, ExportFormat, NewConversation, NewMessage};
+use crate::commands::credentials;
+use crate::commands::deidentify::{self, DeidentifyOptions};
+use crate::commands::diagrams;
+use crate::commands::markdown::Diagrams;
+use crate::commands::network::{self, Service};
+use crate::commands::privacy;
+use crate::commands::settings;
+use crate::db::{self, Database};
+use crate::native_host::{self, Browser};
+
+/// Bundle identifier from `tauri.conf.json`; names the app data directory.
+const IDENTIFIER: &str = "art.everythingsings.gibber-ai";
+
+const USAGE: &str = "Usage:
+  gibber ask [--model <id>] [--system <prompt>] [--no-save] [<prompt>...]
+  gibber export <conversation-id> [--format <json|html>] [--deidentify] [--output <file>]
+  gibber import <file>
+  gibber native-host-manifest --browser <chrome|firefox> --extension-id <id>";
+
+/// Error type for CLI operations.
+#[derive(Debug)]
+pub struct CliError {
+    /// Human-readable error message
+    pub message: String,
+    /// Error code for programmatic handling
+    pub code: String,
+}
+
+impl CliError {
+    pub(crate) fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            code: code.to_string(),
+        }
+    }
+
+    fn usage(message: impl Into<String>) -> Self {
+        Self::new("USAGE", message)
+    }
+}
+
+impl From<rusqlite::Error> for CliError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::new("DATABASE", err.to_string())
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> Self {
+        Self::new("IO", err.to_string())
+    }
+}
+
+/// A parsed command line.
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Ask {
+        prompt: Option<String>,
+        model: Option<String>,
+        system: Option<String>,
+        save: bool,
+    },
+    Export {
+        id: String,
+        format: ExportFormat,
+        deidentify: bool,
+        output: Option<PathBuf>,
+    },
+    Import {
+        path: PathBuf,
+    },
+    NativeHost,
+    NativeHostManifest {
+        browser: Browser,
+        extension_id: String,
+    },
+    Help,
+}
+
+/// The parts of an export file read back on import.
+#[derive(Deserialize)]
+struct ConversationImport {
+    format: String,
+    version: u32,
+    conversation: ImportedConversation,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportedConversation {
+    title: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    system_prompt: Option<String>,
+    #[serde(default)]
+    messages: Vec<ImportedMessage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportedMessage {
+    role: MessageRole,
+    content: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    prompt_tokens: Option<u32>,
+    #[serde(default)]
+    completion_tokens: Option<u32>,
+}
+
+fn value<'a>(flag: &str, args: &mut impl Iterator<Item = &'a String>) -> Result<String, CliError> {
+    args.next()
+        .cloned()
+        .ok_or_else(|| CliError::usage(format!("{flag} needs a value")))
+}
+
+/// Parses the arguments after the executable name.
+fn parse(args: &[String]) -> Result<Command, CliError> {
+    if native_host::caller(args).is_some() {
+        return Ok(Command::NativeHost);
+    }
+    let mut args = args.iter();
+    let Some(command) = args.next() else {
+        return Ok(Command::Help);
+    };
+    match command.as_str() {
+        "ask" => {
+            let (mut model, mut system, mut save) = (None, None, true);
+            let mut words = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--model" => model = Some(value(arg, &mut args)?),
+                    "--system" => system = Some(value(arg, &mut args)?),
+                    "--no-save" => save = false,
+                    "--" => words.extend(args.by_ref().cloned()),
+                    flag if flag.starts_with("--") => {
+                        return Err(CliError::usage(format!("Unknown option: {flag}")));
+                    }
+                    word => words.push(word.to_string()),
+                }
+            }
+            Ok(Command::Ask {
+                prompt: (!words.is_empty()).then(|| words.join(" ")),
+                model,
+                system,
+                save,
+            })
+        }
+        "export" => {
+            let (mut id, mut format, mut deidentify, mut output) =
+                (None, ExportFormat::default(), false, None);
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" | "-f" => {
+                        let name = value(arg, &mut args)?;
+                        format = ExportFormat::parse(&name).ok_or_else(|| {
+                            CliError::usage(format!("Unknown export format: {name}"))
+                        })?;
+                    }
+                    "--deidentify" => deidentify = true,
+                    "--output" | "-o" => output = Some(PathBuf::from(value(arg, &mut args)?)),
+                    other if id.is_none() => id = Some(other.to_string()),
+                    other => return Err(CliError::usage(format!("Unexpected argument: {other}"))),
+                }
+            }
+            let id = id.ok_or_else(|| CliError::usage("export needs a conversation id"))?;
+            Ok(Command::Export {
+                id,
+                format,
+                deidentify,
+                output,
+            })
+        }
+        "import" => match (args.next(), args.next()) {
+            (Some(path), None) => Ok(Command::Import {
+                path: PathBuf::from(path),
+            }),
+            _ => Err(CliError::usage("import needs exactly one file")),
+        },
+        "native-host-manifest" => {
+            let (mut browser, mut extension_id) = (None, None);
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--browser" => {
+                        let name = value(arg, &mut args)?;
+                        browser =
+                            Some(Browser::parse(&name).ok_or_else(|| {
+                                CliError::usage(format!("Unknown browser: {name}"))
+                            })?);
+                    }
+                    "--extension-id" => extension_id = Some(value(arg, &mut args)?),
+                    other => return Err(CliError::usage(format!("Unexpected argument: {other}"))),
+                }
+            }
+            match (browser, extension_id) {
+                (Some(browser), Some(extension_id)) => Ok(Command::NativeHostManifest {
+                    browser,
+                    extension_id,
+                }),
+                _ => Err(CliError::usage(
+                    "native-host-manifest needs --browser and --extension-id",
+                )),
+            }
+        }
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        other => Err(CliError::usage(format!("Unknown command: {other}"))),
+    }
+}
+
+/// Opens the app database, creating the data directory if needed.
+pub(crate) fn open_database() -> Result<Database, CliError> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| CliError::new("IO", "Could not find the data directory"))?
+        .join(IDENTIFIER);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Database::open(&dir.join(db::DATABASE_FILE))?)
+}
+
+/// Sends a completion with the stored key and network settings.
+pub(crate) async fn complete(
+    db: &Database,
+    request: &ChatRequest,
+) -> Result<ChatCompletion, CliError> {
+    let api_key = credentials::read_stored_api_key("openrouter")
+        .map_err(|e| CliError::new("NO_API_KEY", e.to_string()))?
+        .ok_or_else(|| {
+            CliError::new(
+                "NO_API_KEY",
+                "No OpenRouter API key is stored; add one in Gibber AI first",
+            )
+        })?;
+    let client = network::standalone_client(&db.conn(), Service::OpenRouter)
+        .map_err(|e| CliError::new(e.code(), e.to_string()))?;
+    let zero_data_retention = privacy::zero_data_retention(&db.conn());
+    chat::complete_with(&client, &api_key, request, zero_data_retention)
+        .await
+        .map_err(|e| CliError::new(e.code(), e.to_string()))
+}
+
+/// Stores a prompt and its reply as a new conversation.
+///
+/// # Returns
+///
+/// The conversation ID.
+pub(crate) fn save_exchange(
+    db: &Database,
+    conversation: &NewConversation,
+    prompt: String,
+    completion: &ChatCompletion,
+) -> Result<String, CliError> {
+    let conn = db.conn();
+    let conversation = conversations::insert_conversation(&conn, conversation)?;
+    for message in [
+        NewMessage {
+            role: MessageRole::User,
+            content: prompt,
+            model: None,
+            usage: None,
+        },
+        NewMessage {
+            role: MessageRole::Assistant,
+            content: completion.content.clone(),
+            model: Some(completion.model.clone()),
+            usage: completion.usage,
+        },
+    ] {
+        conversations::insert_message(&conn, &conversation.id, &message)?;
+    }
+    Ok(conversation.id)
+}
+
+async fn ask(
+    db: &Database,
+    prompt: String,
+    model: Option<String>,
+    system: Option<String>,
+    save: bool,
+) -> Result<String, CliError> {
+    let mut messages = Vec::new();
+    if let Some(system) = &system {
+        messages.push(ChatMessage::new(MessageRole::System, system.clone()));
+    }
+    messages.push(ChatMessage::new(MessageRole::User, prompt.clone()));
+    let request = ChatRequest::new(model.as_deref(), messages);
+    let completion = complete(db, &request).await?;
+    if save {
+        let conversation = NewConversation {
+            title: conversations::title_from_prompt(&prompt),
+            model,
+            system_prompt: system,
+            source: Some("cli".to_string()),
+        };
+        save_exchange(db, &conversation, prompt, &completion)?;
+    }
+    Ok(completion.content)
+}
+
+fn export(
+    db: &Database,
+    id: &str,
+    format: ExportFormat,
+    deidentify: bool,
+) -> Result<String, CliError> {
+    let mut conversation = conversations::load_conversation(&db.conn(), id)?
+        .ok_or_else(|| CliError::new("NOT_FOUND", format!("Conversation not found: {id}")))?;
+    if deidentify {
+        conversation =
+            deidentify::deidentify(&conversation, &DeidentifyOptions::default()).conversation;
+    }
+    let mut rendered = Diagrams::new();
+    if format == ExportFormat::Html {
+        let server = settings::load(&db.conn())?.diagram_server;
+        let client = network::standalone_client(&db.conn(), Service::Diagrams)
+            .map_err(|e| CliError::new(e.code(), e.to_string()))?;
+        rendered =
+            tauri::async_runtime::block_on(diagrams::render(None, &client, &server, &conversation));
+    }
+    Ok(conversations::export(&conversation, format, &rendered))
+}
+
+/// Imports an export file as a new conversation and returns its id.
+fn import(db: &Database, path: &Path) -> Result<String, CliError> {
+    let contents = std::fs::read_to_string(path)?;
+    let file: ConversationImport = serde_json::from_str(&contents)
+        .map_err(|e| CliError::new("INVALID_FILE", e.to_string()))?;
+    if file.format != conversations::EXPORT_FORMAT || file.version > conversations::EXPORT_VERSION {
+        return Err(CliError::new(
+            "INVALID_FILE",
+            format!("{} is not a supported conversation export", path.display()),
+        ));
+    }
+
+    let mut conn = db.conn();
+    let tx = conn.transaction()?;
+    let imported = file.conversation;
+    let conversation = conversations::insert_conversation(
+        &tx,
+        &NewConversation {
+            title: imported.title,
+            model: imported.model,
+            system_prompt: imported.system_prompt,
+            source: Some("import".to_string()),
+        },
+    )?;
+    for message in imported.messages {
+        let usage = message.prompt_tokens.zip(message.completion_tokens).map(
+            |(prompt_tokens, completion_tokens)| TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+            },
+        );
+        conversations::insert_message(
+            &tx,
+            &conversation.id,
+            &NewMessage {
+                role: message.role,
+                content: message.content,
+                model: message.model,
+                usage,
+            },
+        )?;
+    }
+    tx.commit()?;
+    Ok(conversation.id)
+}
+
+fn execute(command: Command) -> Result<(), CliError> {
+    let stdout = |text: &str| -> Result<(), CliError> {
+        let mut out = std::io::stdout().lock();
+        writeln!(out, "{text}")?;
+        Ok(())
+    };
+    match command {
+        Command::Help => stdout(USAGE),
+        Command::Ask {
+            prompt,
+            model,
+            system,
+            save,
+        } => {
+            let prompt = if let Some(prompt) = prompt {
+                prompt
+            } else {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input)?;
+                input
+            };
+            if prompt.trim().is_empty() {
+                return Err(CliError::usage("The prompt is empty"));
+            }
+            let db = open_database()?;
+            let reply = tauri::async_runtime::block_on(ask(&db, prompt, model, system, save))?;
+            stdout(&reply)
+        }
+        Command::Export {
+            id,
+            format,
+            deidentify,
+            output,
+        } => {
+            let exported = export(&open_database()?, &id, format, deidentify)?;
+            match output {
+                Some(path) => Ok(std::fs::write(path, exported)?),
+                None => stdout(&exported),
+            }
+        }
+        Command::Import { path } => stdout(&import(&open_database()?, &path)?),
+        Command::NativeHost => native_host::run(&open_database()?),
+        Command::NativeHostManifest {
+            browser,
+            extension_id,
+        } => {
+            let executable = std::env::current_exe()?;
+            let manifest =
+                native_host::manifest(browser, &extension_id, &executable.to_string_lossy());
+            stdout(&serde_json::to_string_pretty(&manifest).unwrap_or_default())
+        }
+    }
+}
+
+/// Runs the CLI with the process arguments.
+///
+/// Errors are printed to stderr; usage errors exit with 2, others with 1.
+pub fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse(&args).and_then(execute) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("gibber: {}", e.message);
+            if e.code == "USAGE" {
+                eprintln!("{USAGE}");
+                ExitCode::from(2)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_ask() {
+        assert_eq!(
+            parse(&args(
+                "ask --model openai/gpt-4o write a drum loop --no-save"
+            ))
+            .unwrap(),
+            Command::Ask {
+                prompt: Some("write a drum loop".to_string()),
+                model: Some("openai/gpt-4o".to_string()),
+                system: None,
+                save: false,
+            }
+        );
+        assert_eq!(
+            parse(&args("ask")).unwrap(),
+            Command::Ask {
+                prompt: None,
+                model: None,
+                system: None,
+                save: true,
+            }
+        );
+        assert_eq!(parse(&args("ask --model")).unwrap_err().code, "USAGE");
+        assert_eq!(parse(&args("ask --verbose hi")).unwrap_err().code, "USAGE");
+    }
+
+    #[test]
+    fn test_parse_export_and_import() {
+        assert_eq!(
+            parse(&args("export abc -o out.json")).unwrap(),
+            Command::Export {
+                id: "abc".to_string(),
+                format: ExportFormat::Json,
+                deidentify: false,
+                output: Some(PathBuf::from("out.json")),
+            }
+        );
+        assert_eq!(
+            parse(&args("export abc --format html --deidentify")).unwrap(),
+            Command::Export {
+                id: "abc".to_string(),
+                format: ExportFormat::Html,
+                deidentify: true,
+                output: None,
+            }
+        );
+        assert_eq!(parse(&args("export")).unwrap_err().code, "USAGE");
+        assert_eq!(
+            parse(&args("export abc --format pdf")).unwrap_err().code,
+            "USAGE"
+        );
+        assert_eq!(
+            parse(&args("import a.json b.json")).unwrap_err().code,
+            "USAGE"
+        );
+        assert_eq!(parse(&[]).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn test_parse_native_host() {
+        assert_eq!(
+            parse(&args("chrome-extension://abcdef/")).unwrap(),
+            Command::NativeHost
+        );
+        assert_eq!(
+            parse(&args(
+                "native-host-manifest --browser firefox --extension-id gibber@everythingsings.art"
+            ))
+            .unwrap(),
+            Command::NativeHostManifest {
+                browser: Browser::Firefox,
+                extension_id: "gibber@everythingsings.art".to_string(),
+            }
+        );
+        assert_eq!(
+            parse(&args(
+                "native-host-manifest --browser safari --extension-id x"
+            ))
+            .unwrap_err()
+            .code,
+            "USAGE"
+        );
+    }
+
+    #[test]
+    fn test_export_round_trips_through_import() {
+        let db = Database::open_in_memory().unwrap();
+        let source = {
+            let conn = db.conn();
+            let conversation =
+                conversations::insert_prompt_conversation(&conn, "hello", None, "chat").unwrap();
+            conversations::insert_message(
+                &conn,
+                &conversation.id,
+                &NewMessage {
+                    role: MessageRole::Assistant,
+                    content: "hi there".to_string(),
+                    model: Some("openai/gpt-4o".to_string()),
+                    usage: Some(TokenUsage {
+                        prompt_tokens: 3,
+                        completion_tokens: 2,
+                    }),
+                },
+            )
+            .unwrap();
+            conversation.id
+        };
+
+        let path = std::env::temp_dir().join(format!("gibber-export-{}.json", db::new_id()));
+        std::fs::write(
+            &path,
+            export(&db, &source, ExportFormat::Json, false).unwrap(),
+        )
+        .unwrap();
+        let imported = import(&db, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let copy = conversations::load_conversation(&db.conn(), &imported)
+            .unwrap()
+            .unwrap();
+        assert_ne!(imported, source);
+        assert_eq!(copy.conversation.source, "import");
+        assert_eq!(copy.messages.len(), 2);
+        assert_eq!(copy.messages[1].content, "hi there");
+        assert_eq!(copy.messages[1].completion_tokens, Some(2));
+    }
+}
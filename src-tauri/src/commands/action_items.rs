@@ -0,0 +1,626 @@
+//! Action items from conversations as calendar events and reminders.
+//!
+//! Extraction and creation are separate steps so the user can review the
+//! items first: [`extract_action_items`] asks a model for the tasks and
+//! appointments in a conversation (as structured output) and returns them
+//! without side effects; [`create_action_items`] adds the confirmed, possibly
+//! edited, items to the user's calendar.
+//!
+//! On macOS items go straight into Calendar and Reminders, which store them
+//! in the system EventKit database; the first write triggers the usual
+//! automation permission prompt. Elsewhere an iCalendar (`.ics`) file with
+//! `VEVENT`s and `VTODO`s is written and opened with the default calendar
+//! app, which offers to import it.
+//!
+//! Due dates are local times: `YYYY-MM-DD` for all-day items or
+//! `YYYY-MM-DDTHH:MM` with a time.
+
+use std::fmt::Write as _;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::audit::{self, AuditKind, AuditRecord};
+use crate::commands::conversations;
+use crate::commands::workflows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Folder under the cache directory holding generated `.ics` files.
+const CALENDAR_DIR: &str = "calendar";
+
+/// Length of events without an explicit duration.
+const DEFAULT_EVENT_MINUTES: u32 = 30;
+
+/// Longest iCalendar content line in octets, before folding (RFC 5545).
+const ICS_LINE_LEN: usize = 75;
+
+const EXTRACT_PROMPT: &str = "List the action items in the conversation below: tasks \
+     someone committed to or was asked to do (reminders) and meetings or appointments \
+     (events). Use a short imperative title, optional notes, and a due date in local time \
+     as YYYY-MM-DD or YYYY-MM-DDTHH:MM when one is stated or clearly implied; otherwise \
+     null. Events need a date. Return an empty list if there are none.";
+
+/// Where an item goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionItemKind {
+    /// A calendar event
+    Event,
+    /// A reminder or to-do
+    Reminder,
+}
+
+/// A task or appointment found in a conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionItem {
+    /// Short title
+    pub title: String,
+    /// Extra details
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Event or reminder
+    pub kind: ActionItemKind,
+    /// Local due date, `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM`; required for events
+    #[serde(default)]
+    pub due: Option<String>,
+    /// Event length; 30 minutes when unset
+    #[serde(default)]
+    pub duration_minutes: Option<u32>,
+}
+
+/// How items are added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarMethod {
+    /// Added through Calendar and Reminders (macOS)
+    Native,
+    /// Written to an `.ics` file opened in the calendar app
+    Ics,
+}
+
+/// Result of [`create_action_items`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+pub struct CreatedActionItems {
+    /// How the items were added
+    pub method: CalendarMethod,
+    /// Number of items added
+    pub count: usize,
+    /// The `.ics` file, for [`CalendarMethod::Ics`]
+    pub path: Option<String>,
+}
+
+/// A parsed due date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Due {
+    /// All day
+    Date(NaiveDate),
+    /// At a local time
+    Time(NaiveDateTime),
+}
+
+fn parse_due(value: &str) -> Option<Due> {
+    let value = value.trim();
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Due::Time(time));
+        }
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(Due::Time(time.with_timezone(&Local).naive_local()));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(Due::Date)
+}
+
+/// An item checked for creation.
+struct ValidItem<'a> {
+    item: &'a ActionItem,
+    due: Option<Due>,
+}
+
+impl ValidItem<'_> {
+    fn minutes(&self) -> u32 {
+        self.item
+            .duration_minutes
+            .filter(|m| *m > 0)
+            .unwrap_or(DEFAULT_EVENT_MINUTES)
+    }
+}
+
+fn validate(items: &[ActionItem]) -> Result<Vec<ValidItem<'_>>, GibberError> {
+    if items.is_empty() {
+        return Err(GibberError::new("INVALID_ITEM", "No items to add"));
+    }
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let invalid = |reason: &str| {
+                GibberError::new("INVALID_ITEM", format!("Item {}: {reason}", index + 1))
+            };
+            if item.title.trim().is_empty() {
+                return Err(invalid("the title is empty"));
+            }
+            let due = match item.due.as_deref().filter(|d| !d.trim().is_empty()) {
+                Some(due) => Some(parse_due(due).ok_or_else(|| invalid("unreadable due date"))?),
+                None => None,
+            };
+            if item.kind == ActionItemKind::Event && due.is_none() {
+                return Err(invalid("events need a date"));
+            }
+            Ok(ValidItem { item, due })
+        })
+        .collect()
+}
+
+/// JSON schema of the extraction reply.
+fn extraction_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "items": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string" },
+                        "notes": { "type": ["string", "null"] },
+                        "kind": { "type": "string", "enum": ["event", "reminder"] },
+                        "due": { "type": ["string", "null"] },
+                        "durationMinutes": { "type": ["integer", "null"] },
+                    },
+                    "required": ["title", "notes", "kind", "due", "durationMinutes"],
+                    "additionalProperties": false,
+                },
+            },
+        },
+        "required": ["items"],
+        "additionalProperties": false,
+    })
+}
+
+#[derive(Deserialize)]
+struct Extraction {
+    items: Vec<ActionItem>,
+}
+
+/// Parses the model reply, dropping untitled items and unreadable dates.
+fn parse_extraction(reply: &str) -> Result<Vec<ActionItem>, GibberError> {
+    let parse_error = || GibberError::new("PARSE_ERROR", "The model didn't return action items");
+    let value = workflows::find_json(reply).ok_or_else(parse_error)?;
+    let extraction: Extraction = serde_json::from_value(value).map_err(|_| parse_error())?;
+    Ok(extraction
+        .items
+        .into_iter()
+        .filter(|item| !item.title.trim().is_empty())
+        .map(|mut item| {
+            if item.due.as_deref().and_then(parse_due).is_none() {
+                item.due = None;
+            }
+            if item.kind == ActionItemKind::Event && item.due.is_none() {
+                item.kind = ActionItemKind::Reminder;
+            }
+            item
+        })
+        .collect())
+}
+
+/// Escapes text values (RFC 5545 §3.3.11).
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line at [`ICS_LINE_LEN`] octets and terminates it.
+fn push_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > ICS_LINE_LEN {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn ics_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn ics_time(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Builds an iCalendar document; times are floating local times.
+fn ics(items: &[ValidItem<'_>], stamp: NaiveDateTime) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//EverythingSings//Gibber AI//EN");
+    for valid in items {
+        let component = match valid.item.kind {
+            ActionItemKind::Event => "VEVENT",
+            ActionItemKind::Reminder => "VTODO",
+        };
+        push_line(&mut ics, &format!("BEGIN:{component}"));
+        push_line(&mut ics, &format!("UID:{}@gibber-ai", db::new_id()));
+        push_line(&mut ics, &format!("DTSTAMP:{}Z", ics_time(stamp)));
+        push_line(
+            &mut ics,
+            &format!("SUMMARY:{}", ics_text(valid.item.title.trim())),
+        );
+        if let Some(notes) = valid.item.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+            push_line(&mut ics, &format!("DESCRIPTION:{}", ics_text(notes)));
+        }
+        match (valid.item.kind, valid.due) {
+            (ActionItemKind::Event, Some(Due::Date(date))) => {
+                push_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", ics_date(date)));
+                push_line(&mut ics, "DURATION:P1D");
+            }
+            (ActionItemKind::Event, Some(Due::Time(time))) => {
+                push_line(&mut ics, &format!("DTSTART:{}", ics_time(time)));
+                push_line(&mut ics, &format!("DURATION:PT{}M", valid.minutes()));
+            }
+            (ActionItemKind::Reminder, Some(Due::Date(date))) => {
+                push_line(&mut ics, &format!("DUE;VALUE=DATE:{}", ics_date(date)));
+            }
+            (ActionItemKind::Reminder, Some(Due::Time(time))) => {
+                push_line(&mut ics, &format!("DUE:{}", ics_time(time)));
+            }
+            (_, None) => {}
+        }
+        push_line(&mut ics, &format!("END:{component}"));
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Quotes a string for AppleScript.
+#[cfg(any(target_os = "macos", test))]
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Sets `var` to a local date, avoiding month-length overflow on the way.
+#[cfg(any(target_os = "macos", test))]
+fn applescript_date(script: &mut String, var: &str, time: NaiveDateTime) {
+    use chrono::{Datelike, Timelike};
+    let _ = writeln!(
+        script,
+        "set {var} to current date\nset day of {var} to 1\nset year of {var} to {}\n\
+         set month of {var} to {}\nset day of {var} to {}\nset time of {var} to {}",
+        time.year(),
+        time.month(),
+        time.day(),
+        time.num_seconds_from_midnight()
+    );
+}
+
+/// Builds a script adding the items to Calendar and Reminders.
+#[cfg(any(target_os = "macos", test))]
+fn applescript(items: &[ValidItem<'_>]) -> String {
+    let mut script = String::new();
+    for valid in items {
+        let title = applescript_string(valid.item.title.trim());
+        let notes = applescript_string(valid.item.notes.as_deref().unwrap_or_default());
+        let (time, all_day) = match valid.due {
+            Some(Due::Date(date)) => (Some(date.and_time(chrono::NaiveTime::MIN)), true),
+            Some(Due::Time(time)) => (Some(time), false),
+            None => (None, false),
+        };
+        if let Some(time) = time {
+            applescript_date(&mut script, "d", time);
+        }
+        match valid.item.kind {
+            ActionItemKind::Event => {
+                let minutes = if all_day { 24 * 60 } else { valid.minutes() };
+                let _ = writeln!(
+                    script,
+                    "tell application \"Calendar\" to tell (first calendar whose writable is true) \
+                     to make new event with properties {{summary:{title}, description:{notes}, \
+                     start date:d, end date:d + {minutes} * minutes, allday event:{all_day}}}"
+                );
+            }
+            ActionItemKind::Reminder => {
+                let due = match time {
+                    Some(_) if all_day => ", allday due date:d",
+                    Some(_) => ", due date:d",
+                    None => "",
+                };
+                let _ = writeln!(
+                    script,
+                    "tell application \"Reminders\" to make new reminder with properties \
+                     {{name:{title}, body:{notes}{due}}}"
+                );
+            }
+        }
+    }
+    script
+}
+
+/// Adds the items through Calendar and Reminders.
+#[cfg(target_os = "macos")]
+fn add_natively(items: &[ValidItem<'_>]) -> Result<CreatedActionItems, GibberError> {
+    use std::io::Write as _;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("osascript")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(applescript(items).as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(GibberError::new(
+            "CALENDAR_FAILED",
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(CreatedActionItems {
+        method: CalendarMethod::Native,
+        count: items.len(),
+        path: None,
+    })
+}
+
+#[cfg(not(target_os = "macos"))]
+fn add_natively(_items: &[ValidItem<'_>]) -> Result<CreatedActionItems, GibberError> {
+    Err(GibberError::new(
+        "UNSUPPORTED",
+        "Adding items directly is only supported on macOS",
+    ))
+}
+
+/// Writes the items to an `.ics` file and opens it.
+fn open_ics(app: &AppHandle, items: &[ValidItem<'_>]) -> Result<CreatedActionItems, GibberError> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| GibberError::new("IO", e.to_string()))?
+        .join(CALENDAR_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.ics", db::new_id()));
+    std::fs::write(&path, ics(items, chrono::Utc::now().naive_utc()))?;
+    let path = path.to_string_lossy().into_owned();
+    app.opener()
+        .open_path(&path, None::<&str>)
+        .map_err(|e| GibberError::new("OPEN_FAILED", e.to_string()))?;
+    Ok(CreatedActionItems {
+        method: CalendarMethod::Ics,
+        count: items.len(),
+        path: Some(path),
+    })
+}
+
+/// Extracts action items from a conversation for review.
+///
+/// Nothing is created; pass the confirmed items to [`create_action_items`].
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, `PARSE_ERROR` if the reply holds no items, or the
+/// `GibberError` code if the request fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const items = await invoke("extract_action_items", { conversationId, model: null });
+/// // show items for editing, then:
+/// await invoke("create_action_items", { items: confirmed });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn extract_action_items(
+    app: AppHandle,
+    conversation_id: String,
+    model: Option<String>,
+) -> Result<Vec<ActionItem>, GibberError> {
+    let transcript = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let conversation =
+            conversations::load_conversation(&conn, &conversation_id)?.ok_or_else(|| {
+                GibberError::new(
+                    "NOT_FOUND",
+                    format!("Conversation not found: {conversation_id}"),
+                )
+            })?;
+        conversation
+            .messages
+            .iter()
+            .fold(String::new(), |mut transcript, message| {
+                let _ = writeln!(
+                    transcript,
+                    "{}: {}\n",
+                    message.role.as_str(),
+                    message.content
+                );
+                transcript
+            })
+    };
+    let now = Local::now().format("%A %Y-%m-%d %H:%M");
+    let request = ChatRequest::new(
+        model.as_deref(),
+        vec![
+            ChatMessage::new(
+                MessageRole::System,
+                format!("{EXTRACT_PROMPT}\n\nIt is now {now}."),
+            ),
+            ChatMessage::new(MessageRole::User, transcript),
+        ],
+    )
+    .with_json_schema("action_items", extraction_schema())
+    .for_conversation(&conversation_id);
+    let completion = chat::complete(&app, &request).await?;
+    parse_extraction(&completion.content)
+}
+
+/// Adds confirmed action items to the user's calendar.
+///
+/// `method` defaults to [`CalendarMethod::Native`] on macOS and
+/// [`CalendarMethod::Ics`] elsewhere.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_ITEM` if an item lacks a
+/// title, has an unreadable due date, or is an event without a date;
+/// `UNSUPPORTED` for the native method outside macOS; `CALENDAR_FAILED` if
+/// Calendar or Reminders refuse the items; or `IO`/`OPEN_FAILED` if the
+/// `.ics` file cannot be written or opened.
+#[tauri::command]
+#[specta::specta]
+pub async fn create_action_items(
+    app: AppHandle,
+    items: Vec<ActionItem>,
+    method: Option<CalendarMethod>,
+) -> Result<CreatedActionItems, GibberError> {
+    let method = method.unwrap_or(if cfg!(target_os = "macos") {
+        CalendarMethod::Native
+    } else {
+        CalendarMethod::Ics
+    });
+    // Apple Events block until the user answers the permission prompt.
+    tauri::async_runtime::spawn_blocking(move || {
+        let valid = validate(&items)?;
+        match method {
+            CalendarMethod::Native => {
+                let result = add_natively(&valid);
+                let error = result.as_ref().err().map(ToString::to_string);
+                audit::record(
+                    &app,
+                    &AuditRecord {
+                        actor: "action-items",
+                        kind: AuditKind::Shell,
+                        target: "osascript -",
+                        detail: Some(serde_json::json!({ "items": valid.len() })),
+                        error: error.as_deref(),
+                    },
+                );
+                result
+            }
+            CalendarMethod::Ics => open_ics(&app, &valid),
+        }
+    })
+    .await
+    .map_err(|e| GibberError::new("IO", e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: ActionItemKind, due: Option<&str>) -> ActionItem {
+        ActionItem {
+            title: "Send stems to Ana".to_string(),
+            notes: Some("Bass, drums; both at 120 BPM".to_string()),
+            kind,
+            due: due.map(str::to_string),
+            duration_minutes: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_due() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        assert_eq!(parse_due("2026-10-14"), Some(Due::Date(date)));
+        assert_eq!(
+            parse_due("2026-10-14T09:30"),
+            Some(Due::Time(date.and_hms_opt(9, 30, 0).unwrap()))
+        );
+        assert_eq!(parse_due("next Tuesday"), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[item(ActionItemKind::Reminder, None)]).is_ok());
+        let errors = [
+            vec![],
+            vec![item(ActionItemKind::Event, None)],
+            vec![item(ActionItemKind::Reminder, Some("soon"))],
+        ];
+        for items in errors {
+            assert_eq!(validate(&items).err().unwrap().code(), "INVALID_ITEM");
+        }
+    }
+
+    #[test]
+    fn test_parse_extraction_tidies_items() {
+        let reply = r#"Here you go:
+```json
+{"items": [
+  {"title": "Book studio", "notes": null, "kind": "event", "due": "2026-10-20T14:00", "durationMinutes": 120},
+  {"title": "Call Bo", "notes": null, "kind": "event", "due": "someday", "durationMinutes": null},
+  {"title": " ", "notes": null, "kind": "reminder", "due": null, "durationMinutes": null}
+]}
+```"#;
+        let items = parse_extraction(reply).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].duration_minutes, Some(120));
+        assert_eq!(items[1].kind, ActionItemKind::Reminder);
+        assert_eq!(items[1].due, None);
+        assert_eq!(parse_extraction("none").unwrap_err().code(), "PARSE_ERROR");
+    }
+
+    #[test]
+    fn test_ics() {
+        let items = [
+            item(ActionItemKind::Event, Some("2026-10-20T14:00")),
+            item(ActionItemKind::Reminder, Some("2026-10-21")),
+        ];
+        let valid = validate(&items).unwrap();
+        let stamp = NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let ics = ics(&valid, stamp);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.contains("BEGIN:VEVENT\r\n"));
+        assert!(ics.contains("DTSTAMP:20261014T080000Z\r\n"));
+        assert!(ics.contains("DTSTART:20261020T140000\r\nDURATION:PT30M\r\n"));
+        assert!(ics.contains("DESCRIPTION:Bass\\, drums\\; both at 120 BPM\r\n"));
+        assert!(ics.contains("BEGIN:VTODO\r\n"));
+        assert!(ics.contains("DUE;VALUE=DATE:20261021\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn test_long_lines_are_folded() {
+        let mut ics = String::new();
+        push_line(&mut ics, &format!("SUMMARY:{}", "é".repeat(60)));
+        let lines: Vec<&str> = ics.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= ICS_LINE_LEN));
+        assert!(lines[1].starts_with(' '));
+    }
+
+    #[test]
+    fn test_applescript_quotes_values() {
+        let mut quoted = item(ActionItemKind::Reminder, Some("2026-10-21"));
+        quoted.title = r#"Mix "final" \ master"#.to_string();
+        let items = [quoted];
+        let script = applescript(&validate(&items).unwrap());
+        assert!(script.contains(r#"name:"Mix \"final\" \\ master""#));
+        assert!(
+            script.contains("set year of d to 2026\nset month of d to 10\nset day of d to 21\n")
+        );
+        assert!(script.contains("allday due date:d"));
+    }
+}
@@ -0,0 +1,653 @@
+//! Local OpenAI-compatible API server.
+//!
+//! When enabled, the app listens on `127.0.0.1` and answers
+//! `POST /v1/chat/completions` and `GET /v1/models` in the OpenAI wire
+//! format, so editors and scripts can route requests through Gibber with
+//! the app's own OpenRouter key, proxy settings and history. Every request
+//! must carry `Authorization: Bearer <token>` with the token generated when
+//! the server is first enabled; it stays on this machine (the settings
+//! aren't exported).
+//!
+//! Completions aren't streamed token by token: with `"stream": true` the
+//! whole reply arrives as a single event followed by `[DONE]`. When
+//! `saveConversations` is on, each request's last user message and the
+//! reply are stored as a conversation with source `api`, so usage shows up
+//! in the app like any other chat.
+
+use std::io::Read;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole, DEFAULT_MODEL};
+use crate::commands::conversations::{self, NewConversation, NewMessage};
+use crate::commands::telemetry;
+use crate::commands::windows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the server preferences.
+pub const SETTINGS_KEY: &str = "apiServer";
+
+/// Port used until the user picks another.
+const DEFAULT_PORT: u16 = 4891;
+
+/// Largest accepted request body.
+const MAX_BODY_BYTES: u64 = 4 * 1024 * 1024;
+
+/// API server preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiServerSettings {
+    /// Listen for requests
+    pub enabled: bool,
+    /// Port on `127.0.0.1`
+    pub port: u16,
+    /// Bearer token clients must send; generated on first enable
+    pub token: String,
+    /// Store requests and replies as conversations
+    pub save_conversations: bool,
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: String::new(),
+            save_conversations: true,
+        }
+    }
+}
+
+/// Server state as shown in the settings UI.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerStatus {
+    /// Stored preferences
+    pub settings: ApiServerSettings,
+    /// Base URL clients should use, while the server runs
+    pub base_url: Option<String>,
+}
+
+struct RunningServer {
+    server: Arc<Server>,
+    /// Token requests must carry, swapped in place when only the token
+    /// changes
+    token: Arc<Mutex<String>>,
+    port: u16,
+}
+
+impl RunningServer {
+    fn set_token(&self, token: &str) {
+        *self.token.lock().unwrap_or_else(PoisonError::into_inner) = token.to_string();
+    }
+}
+
+/// Managed handle to the running server, if any.
+#[derive(Default)]
+pub struct ApiServerState(Mutex<Option<RunningServer>>);
+
+impl ApiServerState {
+    fn lock(&self) -> MutexGuard<'_, Option<RunningServer>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn load_settings(conn: &rusqlite::Connection) -> ApiServerSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Generates a random bearer token.
+pub(crate) fn new_token() -> String {
+    format!("gibber-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Compares tokens in time independent of where they differ.
+pub(crate) fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn bearer(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+}
+
+/// A request body in the OpenAI format.
+#[derive(Deserialize)]
+struct CompletionBody {
+    #[serde(default)]
+    model: Option<String>,
+    messages: Vec<BodyMessage>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    max_completion_tokens: Option<u32>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct BodyMessage {
+    role: String,
+    content: BodyContent,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BodyContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Deserialize)]
+struct ContentPart {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+impl BodyContent {
+    fn into_text(self) -> String {
+        match self {
+            Self::Text(text) => text,
+            Self::Parts(parts) => parts
+                .into_iter()
+                .filter_map(|part| part.text)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// An error answered in the OpenAI format.
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    kind: &'static str,
+    code: String,
+    message: String,
+}
+
+impl ApiError {
+    fn invalid(message: impl Into<String>) -> Self {
+        Self {
+            status: 400,
+            kind: "invalid_request_error",
+            code: "INVALID_REQUEST".to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn body(&self) -> String {
+        json!({
+            "error": { "message": self.message, "type": self.kind, "code": self.code }
+        })
+        .to_string()
+    }
+}
+
+impl From<GibberError> for ApiError {
+    fn from(err: GibberError) -> Self {
+        let status = match err.code() {
+            "RATE_LIMITED" => 429,
+            "CONTEXT_TOO_LONG" => 400,
+            "MODEL_UNAVAILABLE" => 503,
+            "NETWORK_ERROR" | "INVALID_API_KEY" => 502,
+            _ => 500,
+        };
+        Self {
+            status,
+            kind: "api_error",
+            code: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Converts an OpenAI request body into a chat request.
+///
+/// # Returns
+///
+/// The request and whether the client asked for a stream.
+fn parse_body(body: &str) -> Result<(ChatRequest, bool), ApiError> {
+    let body: CompletionBody =
+        serde_json::from_str(body).map_err(|e| ApiError::invalid(e.to_string()))?;
+    if body.messages.is_empty() {
+        return Err(ApiError::invalid("messages must not be empty"));
+    }
+    let messages = body
+        .messages
+        .into_iter()
+        .map(|message| {
+            let role = match message.role.as_str() {
+                "developer" => MessageRole::System,
+                other => MessageRole::parse(other)
+                    .ok_or_else(|| ApiError::invalid(format!("Unsupported role: {other}")))?,
+            };
+            Ok(ChatMessage::new(role, message.content.into_text()))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    let mut request = ChatRequest::new(body.model.as_deref(), messages);
+    if let Some(max_tokens) = body.max_completion_tokens.or(body.max_tokens) {
+        request.max_tokens = max_tokens;
+    }
+    if let Some(temperature) = body.temperature {
+        request.temperature = temperature;
+    }
+    Ok((request, body.stream))
+}
+
+fn completion_json(completion: &chat::ChatCompletion, stream: bool) -> Value {
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+    let message = json!({ "role": "assistant", "content": completion.content });
+    let usage = completion.usage.map(|usage| {
+        json!({
+            "prompt_tokens": usage.prompt_tokens,
+            "completion_tokens": usage.completion_tokens,
+            "total_tokens": usage.prompt_tokens + usage.completion_tokens,
+        })
+    });
+    if stream {
+        json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": completion.model,
+            "choices": [{ "index": 0, "delta": message, "finish_reason": "stop" }],
+            "usage": usage,
+        })
+    } else {
+        json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": created,
+            "model": completion.model,
+            "choices": [{ "index": 0, "message": message, "finish_reason": "stop" }],
+            "usage": usage,
+        })
+    }
+}
+
+/// Stores the last user message and the reply as an `api` conversation.
+fn save_exchange(
+    conn: &rusqlite::Connection,
+    request: &ChatRequest,
+    completion: &chat::ChatCompletion,
+) -> rusqlite::Result<()> {
+    let prompt = request
+        .messages
+        .iter()
+        .rev()
+        .find(|message| message.role == MessageRole::User)
+        .map(|message| message.content.clone())
+        .unwrap_or_default();
+    let conversation = conversations::insert_conversation(
+        conn,
+        &NewConversation {
+            title: conversations::title_from_prompt(&prompt),
+            model: Some(request.model.clone()),
+            system_prompt: None,
+            source: Some("api".to_string()),
+        },
+    )?;
+    for message in [
+        NewMessage {
+            role: MessageRole::User,
+            content: prompt,
+            model: None,
+            usage: None,
+        },
+        NewMessage {
+            role: MessageRole::Assistant,
+            content: completion.content.clone(),
+            model: Some(completion.model.clone()),
+            usage: completion.usage,
+        },
+    ] {
+        conversations::insert_message(conn, &conversation.id, &message)?;
+    }
+    Ok(())
+}
+
+fn chat_completion(app: &AppHandle, body: &str) -> Result<(String, bool), ApiError> {
+    let (request, stream) = parse_body(body)?;
+    let completion = tauri::async_runtime::block_on(chat::complete(app, &request))?;
+    telemetry::record_feature(app, "api_server.request");
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    if load_settings(&conn).save_conversations {
+        if let Err(e) = save_exchange(&conn, &request, &completion) {
+            tracing::error!("failed to store API conversation: {e}");
+        }
+    }
+    Ok((completion_json(&completion, stream).to_string(), stream))
+}
+
+fn respond(request: Request, status: u16, body: String, content_type: &str) {
+    let mut response = Response::from_string(body).with_status_code(status);
+    if let Ok(header) = Header::from_bytes("Content-Type", content_type) {
+        response.add_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        tracing::warn!("failed to answer API request: {e}");
+    }
+}
+
+fn handle(app: &AppHandle, mut request: Request, token: &str) {
+    if !bearer(&request).is_some_and(|given| tokens_match(token, given)) {
+        let error = ApiError {
+            status: 401,
+            kind: "invalid_request_error",
+            code: "UNAUTHORIZED".to_string(),
+            message: "Missing or invalid bearer token".to_string(),
+        };
+        respond(request, error.status, error.body(), "application/json");
+        return;
+    }
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let result = match (request.method(), path.as_str()) {
+        (&Method::Get, "/v1/models") => {
+            let models = json!({
+                "object": "list",
+                "data": [{ "id": DEFAULT_MODEL, "object": "model", "owned_by": "openrouter" }],
+            });
+            Ok((models.to_string(), false))
+        }
+        (&Method::Post, "/v1/chat/completions") => {
+            let mut body = String::new();
+            match request
+                .as_reader()
+                .take(MAX_BODY_BYTES)
+                .read_to_string(&mut body)
+            {
+                Ok(_) => chat_completion(app, &body),
+                Err(e) => Err(ApiError::invalid(e.to_string())),
+            }
+        }
+        _ => Err(ApiError {
+            status: 404,
+            kind: "invalid_request_error",
+            code: "NOT_FOUND".to_string(),
+            message: format!("No route for {path}"),
+        }),
+    };
+    match result {
+        Ok((json, true)) => respond(
+            request,
+            200,
+            format!("data: {json}\n\ndata: [DONE]\n\n"),
+            "text/event-stream",
+        ),
+        Ok((json, false)) => respond(request, 200, json, "application/json"),
+        Err(error) => respond(request, error.status, error.body(), "application/json"),
+    }
+}
+
+/// Binds the server and answers requests on background threads.
+fn serve(app: &AppHandle, settings: &ApiServerSettings) -> Result<RunningServer, GibberError> {
+    let app = app.clone();
+    let running = bind(settings, move |request, token| handle(&app, request, token))?;
+    tracing::info!(port = running.port, "API server listening");
+    Ok(running)
+}
+
+/// Binds `settings.port` and hands each request to `handle` on its own
+/// thread, with the token current when it arrived.
+fn bind<F>(settings: &ApiServerSettings, handle: F) -> Result<RunningServer, GibberError>
+where
+    F: Fn(Request, &str) + Clone + Send + 'static,
+{
+    let server = Server::http(("127.0.0.1", settings.port))
+        .map(Arc::new)
+        .map_err(|e| GibberError::new("BIND_FAILED", e.to_string()))?;
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map_or(settings.port, |addr| addr.port());
+    let token = Arc::new(Mutex::new(settings.token.clone()));
+    let (listener, current) = (server.clone(), token.clone());
+    std::thread::spawn(move || {
+        for request in listener.incoming_requests() {
+            let token = current
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            let handle = handle.clone();
+            std::thread::spawn(move || handle(request, &token));
+        }
+    });
+    Ok(RunningServer {
+        server,
+        token,
+        port,
+    })
+}
+
+/// Brings `running` in line with `settings`. A server already on the right
+/// port only gets the new token, and a new server is bound before the old
+/// one is stopped, so a failed start leaves the old server running.
+fn reconcile(
+    running: &mut Option<RunningServer>,
+    settings: &ApiServerSettings,
+    serve: impl FnOnce(&ApiServerSettings) -> Result<RunningServer, GibberError>,
+) -> Result<(), GibberError> {
+    if let Some(current) = running.as_ref() {
+        if settings.enabled && current.port == settings.port {
+            current.set_token(&settings.token);
+            return Ok(());
+        }
+    }
+    let next = if settings.enabled {
+        Some(serve(settings)?)
+    } else {
+        None
+    };
+    if let Some(previous) = std::mem::replace(running, next) {
+        previous.server.unblock();
+    }
+    Ok(())
+}
+
+/// Starts, stops, or updates the server to match `settings`.
+fn apply(app: &AppHandle, settings: &ApiServerSettings) -> Result<(), GibberError> {
+    let state = app.state::<ApiServerState>();
+    let mut running = state.lock();
+    reconcile(&mut running, settings, |settings| serve(app, settings))
+}
+
+fn status(app: &AppHandle) -> ApiServerStatus {
+    let settings = load_settings(&app.state::<Database>().conn());
+    let base_url = app
+        .state::<ApiServerState>()
+        .lock()
+        .as_ref()
+        .map(|running| format!("http://127.0.0.1:{}/v1", running.port));
+    ApiServerStatus { settings, base_url }
+}
+
+/// Manages the server state and starts the server if it is enabled.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+pub fn start(app: &AppHandle) {
+    app.manage(ApiServerState::default());
+    let settings = load_settings(&app.state::<Database>().conn());
+    if let Err(e) = apply(app, &settings) {
+        tracing::error!("failed to start API server: {e}");
+    }
+}
+
+/// Returns the server preferences and address.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn get_api_server_status(app: AppHandle) -> ApiServerStatus {
+    status(&app)
+}
+
+/// Replaces the server preferences and starts or stops the server.
+///
+/// A token is generated the first time the server is enabled. Nothing is
+/// saved if the server can't start, and a running server keeps running.
+/// Changing only the token doesn't restart the server.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, `INVALID_PORT` for ports below 1024,
+/// `BIND_FAILED` if the port is taken, or `DATABASE` if the settings
+/// cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const { settings, baseUrl } = await invoke("set_api_server_settings", {
+///   settings: { enabled: true, port: 4891, token: "", saveConversations: true },
+/// });
+/// // OPENAI_BASE_URL=baseUrl OPENAI_API_KEY=settings.token
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require handles and args by value
+pub fn set_api_server_settings(
+    window: WebviewWindow,
+    app: AppHandle,
+    mut settings: ApiServerSettings,
+) -> Result<ApiServerStatus, GibberError> {
+    windows::require_trusted(&window)?;
+    if settings.port < 1024 {
+        return Err(GibberError::new(
+            "INVALID_PORT",
+            format!("Port {} is reserved; use 1024 or above", settings.port),
+        ));
+    }
+    if settings.enabled && settings.token.is_empty() {
+        settings.token = new_token();
+    }
+    apply(&app, &settings)?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&app.state::<Database>().conn(), SETTINGS_KEY, &value)?;
+    Ok(status(&app))
+}
+
+/// Replaces the token; clients using the old one are rejected.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, or `DATABASE` if the token cannot be stored.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require handles and State by value
+pub fn regenerate_api_server_token(
+    window: WebviewWindow,
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<ApiServerStatus, GibberError> {
+    let mut settings = load_settings(&db.conn());
+    settings.token = new_token();
+    set_api_server_settings(window, app, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_body_maps_openai_fields() {
+        let (request, stream) = parse_body(
+            r#"{
+                "model": "openai/gpt-4o",
+                "messages": [
+                    { "role": "developer", "content": "Be brief" },
+                    { "role": "user", "content": [{ "type": "text", "text": "hi" }] }
+                ],
+                "max_completion_tokens": 64,
+                "stream": true
+            }"#,
+        )
+        .unwrap();
+        assert!(stream);
+        assert_eq!(request.model, "openai/gpt-4o");
+        assert_eq!(request.max_tokens, 64);
+        assert_eq!(request.messages[0].role, MessageRole::System);
+        assert_eq!(request.messages[1].content, "hi");
+    }
+
+    #[test]
+    fn test_parse_body_rejects_bad_requests() {
+        assert_eq!(parse_body("{").unwrap_err().status, 400);
+        assert_eq!(parse_body(r#"{"messages": []}"#).unwrap_err().status, 400);
+        let error = parse_body(r#"{"messages": [{"role": "tool", "content": "x"}]}"#).unwrap_err();
+        assert!(error.message.contains("tool"));
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("gibber-abc", "gibber-abc"));
+        assert!(!tokens_match("gibber-abc", "gibber-abd"));
+        assert!(!tokens_match("gibber-abc", "gibber-ab"));
+    }
+
+    #[test]
+    fn test_chat_errors_map_to_statuses() {
+        let error = ApiError::from(GibberError::new("RATE_LIMITED", "slow down"));
+        assert_eq!(error.status, 429);
+        let body: Value = serde_json::from_str(&error.body()).unwrap();
+        assert_eq!(body["error"]["code"], "RATE_LIMITED");
+    }
+
+    #[test]
+    fn test_reconcile_keeps_the_server_until_a_new_one_binds() {
+        let serve = |settings: &ApiServerSettings| bind(settings, |_, _| {});
+        let mut settings = ApiServerSettings {
+            enabled: true,
+            port: 0,
+            token: "first".to_string(),
+            ..ApiServerSettings::default()
+        };
+        let mut running = None;
+        reconcile(&mut running, &settings, serve).unwrap();
+        let server = running.as_ref().unwrap().server.clone();
+        settings.port = running.as_ref().unwrap().port;
+
+        settings.token = "second".to_string();
+        reconcile(&mut running, &settings, |_| unreachable!()).unwrap();
+        let current = running.as_ref().unwrap();
+        assert!(Arc::ptr_eq(&current.server, &server));
+        assert_eq!(*current.token.lock().unwrap(), "second");
+
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut moved = settings.clone();
+        moved.port = taken.local_addr().unwrap().port();
+        let error = reconcile(&mut running, &moved, serve).unwrap_err();
+        assert_eq!(error.code(), "BIND_FAILED");
+        assert!(Arc::ptr_eq(&running.as_ref().unwrap().server, &server));
+
+        settings.enabled = false;
+        reconcile(&mut running, &settings, serve).unwrap();
+        assert!(running.is_none());
+    }
+}
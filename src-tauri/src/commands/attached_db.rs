@@ -0,0 +1,458 @@
+//! Read-only querying of attached SQLite databases.
+//!
+//! The model answers questions about an attached `.sqlite` or `.db` file
+//! by writing SQL that runs here. The file is opened read-only and
+//! immutable, so nothing can change it or leave journal files next to it.
+//! Only single `SELECT`, `WITH`, or `VALUES` statements that SQLite itself
+//! reports as read-only are run, and `ATTACH` is therefore never reached.
+//! Each query is bounded in time, rows, and result size, and recorded in
+//! the audit log under the actor `attachment:<id>`.
+
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use rusqlite::types::ValueRef;
+use rusqlite::{Batch, Connection, ErrorCode, OpenFlags};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::attachments::{self, Attachment, AttachmentKind, AttachmentStore};
+use crate::commands::audit::{self, AuditKind, AuditRecord};
+use crate::commands::plugins::PluginTool;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Rows returned when the caller gives no limit.
+const DEFAULT_ROWS: u32 = 100;
+
+/// Most rows a query may return.
+const MAX_ROWS: u32 = 1_000;
+
+/// Most bytes of JSON a result may hold; rows past it are dropped.
+const MAX_RESULT_BYTES: usize = 256 * 1024;
+
+/// Longest text value returned, in characters.
+const MAX_TEXT_CHARS: usize = 2_000;
+
+/// How long a query may run before it is interrupted.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Statements a query may start with.
+const ALLOWED_STATEMENTS: [&str; 3] = ["SELECT", "WITH", "VALUES"];
+
+/// A table or view of an attached database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DbTable {
+    /// Table or view name
+    pub name: String,
+    /// `table` or `view`
+    pub kind: String,
+    /// `CREATE` statement, which lists the columns
+    pub sql: Option<String>,
+    /// Rows in the table; `None` for views
+    pub rows: Option<u64>,
+}
+
+/// What `describe_attached_db` returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DbSchema {
+    /// The attachment
+    pub attachment_id: String,
+    /// Original file name
+    pub file_name: String,
+    /// Tables and views by name
+    pub tables: Vec<DbTable>,
+}
+
+/// What `query_attached_db` returns.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DbQueryResult {
+    /// Column names
+    pub columns: Vec<String>,
+    /// Rows, one value per column; blobs are described, not returned
+    pub rows: Vec<Vec<Value>>,
+    /// Whether rows were left out by the row or size limit
+    pub truncated: bool,
+    /// Time the query took in milliseconds
+    pub elapsed_ms: u64,
+}
+
+/// Builds a URI opening `path` read-only and immutable.
+fn immutable_uri(path: &Path) -> String {
+    let mut path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        // Windows drive paths such as C:/… need an empty authority.
+        path.insert(0, '/');
+    }
+    let escaped = path
+        .replace('%', "%25")
+        .replace('?', "%3F")
+        .replace('#', "%23");
+    format!("file://{escaped}?mode=ro&immutable=1")
+}
+
+fn open(path: &Path) -> Result<Connection, GibberError> {
+    let conn = Connection::open_with_flags(
+        immutable_uri(path),
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    conn.pragma_update(None, "query_only", true)?;
+    Ok(conn)
+}
+
+fn invalid_query(e: impl std::fmt::Display) -> GibberError {
+    GibberError::new("INVALID_QUERY", e.to_string())
+}
+
+fn cell(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(value) => json!(value),
+        ValueRef::Real(value) => json!(value),
+        ValueRef::Text(text) => {
+            let text = String::from_utf8_lossy(text);
+            if text.chars().count() > MAX_TEXT_CHARS {
+                let cut: String = text.chars().take(MAX_TEXT_CHARS).collect();
+                json!(format!("{cut}…"))
+            } else {
+                json!(text)
+            }
+        }
+        ValueRef::Blob(bytes) => json!(format!("<{}-byte blob>", bytes.len())),
+    }
+}
+
+fn schema(conn: &Connection) -> rusqlite::Result<Vec<DbTable>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, type, sql FROM sqlite_schema
+         WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%'
+         ORDER BY name",
+    )?;
+    let mut tables = stmt
+        .query_map([], |row| {
+            Ok(DbTable {
+                name: row.get(0)?,
+                kind: row.get(1)?,
+                sql: row.get(2)?,
+                rows: None,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for table in tables.iter_mut().filter(|table| table.kind == "table") {
+        let name = table.name.replace('"', "\"\"");
+        table.rows = conn
+            .query_row(&format!("SELECT COUNT(*) FROM \"{name}\""), [], |row| {
+                row.get(0)
+            })
+            .ok();
+    }
+    Ok(tables)
+}
+
+/// Runs a read-only query, stopping at `limit` rows or the size limit.
+fn run(conn: &Connection, sql: &str, limit: u32) -> Result<DbQueryResult, GibberError> {
+    let keyword: String = sql
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect();
+    if !ALLOWED_STATEMENTS
+        .iter()
+        .any(|allowed| keyword.eq_ignore_ascii_case(allowed))
+    {
+        return Err(GibberError::new(
+            "FORBIDDEN",
+            "Only SELECT, WITH, and VALUES queries are allowed",
+        ));
+    }
+    // `prepare` ignores anything after the first statement, so a second one
+    // is refused rather than silently left out.
+    let mut statements = Batch::new(conn, sql);
+    let mut stmt = statements
+        .next()
+        .map_err(invalid_query)?
+        .ok_or_else(|| invalid_query("The query is empty"))?;
+    if statements.next().map_err(invalid_query)?.is_some() {
+        return Err(invalid_query("Only one statement can be run at a time"));
+    }
+    if !stmt.readonly() {
+        return Err(GibberError::new(
+            "FORBIDDEN",
+            "Only read-only queries are allowed",
+        ));
+    }
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let started = Instant::now();
+    // Dropping `done` ends the watchdog; otherwise it interrupts the query.
+    let (done, finished) = mpsc::channel::<()>();
+    let interrupt = conn.get_interrupt_handle();
+    std::thread::spawn(move || {
+        if finished.recv_timeout(QUERY_TIMEOUT) == Err(RecvTimeoutError::Timeout) {
+            interrupt.interrupt();
+        }
+    });
+
+    let limit = usize::try_from(limit.clamp(1, MAX_ROWS)).unwrap_or(usize::MAX);
+    let mut rows = Vec::new();
+    let mut bytes = 0;
+    let mut truncated = false;
+    let mut results = stmt.query([]).map_err(invalid_query)?;
+    loop {
+        let row = match results.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == ErrorCode::OperationInterrupted =>
+            {
+                return Err(GibberError::new(
+                    "TIMEOUT",
+                    format!(
+                        "The query took longer than {} seconds",
+                        QUERY_TIMEOUT.as_secs()
+                    ),
+                ));
+            }
+            Err(e) => return Err(invalid_query(e)),
+        };
+        if rows.len() == limit {
+            truncated = true;
+            break;
+        }
+        let values: Vec<Value> = (0..columns.len())
+            .map(|index| row.get_ref(index).map(cell))
+            .collect::<rusqlite::Result<_>>()?;
+        bytes += Value::Array(values.clone()).to_string().len();
+        if bytes > MAX_RESULT_BYTES {
+            truncated = true;
+            break;
+        }
+        rows.push(values);
+    }
+    drop(done);
+    Ok(DbQueryResult {
+        columns,
+        rows,
+        truncated,
+        elapsed_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+    })
+}
+
+/// Loads a database attachment and opens its file.
+fn load(app: &AppHandle, id: &str) -> Result<(Attachment, Connection), GibberError> {
+    let attachment = attachments::load_attachment(&app.state::<Database>().conn(), id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Attachment {id} not found")))?;
+    if attachment.kind != AttachmentKind::Database {
+        return Err(GibberError::new(
+            "NOT_DATABASE",
+            format!("{} is not a SQLite database", attachment.file_name),
+        ));
+    }
+    let conn = open(&app.state::<AttachmentStore>().file_path(&attachment))?;
+    Ok((attachment, conn))
+}
+
+/// Tool definitions for database attachments, offered with the
+/// spreadsheet tools by `get_data_tools`.
+pub(crate) fn tools() -> Vec<PluginTool> {
+    let attachment_id = json!({
+        "type": "string",
+        "description": "ID of the SQLite database attachment"
+    });
+    vec![
+        PluginTool {
+            name: "describe_db".to_string(),
+            description: "Lists the tables and views of an attached SQLite database with their \
+                          CREATE statements and row counts."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "attachmentId": attachment_id.clone() },
+                "required": ["attachmentId"]
+            })
+            .to_string(),
+        },
+        PluginTool {
+            name: "query_db".to_string(),
+            description: "Runs one read-only SQLite SELECT statement against an attached \
+                          database and returns the resulting rows."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "attachmentId": attachment_id,
+                    "sql": { "type": "string", "description": "A single SELECT statement" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_ROWS }
+                },
+                "required": ["attachmentId", "sql"]
+            })
+            .to_string(),
+        },
+    ]
+}
+
+/// Lists the tables and views of a database attachment.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment doesn't
+/// exist, `NOT_DATABASE` if it isn't a SQLite database, or `DATABASE` if
+/// its schema cannot be read.
+#[tauri::command]
+#[specta::specta]
+pub async fn describe_attached_db(app: AppHandle, id: String) -> Result<DbSchema, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<_, GibberError> {
+        let (attachment, conn) = load(&app, &id)?;
+        Ok(DbSchema {
+            tables: schema(&conn)?,
+            attachment_id: attachment.id,
+            file_name: attachment.file_name,
+        })
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+/// Runs a read-only query against a database attachment.
+///
+/// At most `limit` rows (default 100, at most 1000) and 256 KB of JSON are
+/// returned, and the query is interrupted after 10 seconds. Every query,
+/// successful or not, is recorded in the audit log.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment doesn't
+/// exist, `NOT_DATABASE` if it isn't a SQLite database, `FORBIDDEN` if the
+/// statement could write, `INVALID_QUERY` if SQLite rejects it, or
+/// `TIMEOUT` if it runs too long.
+///
+/// # Example
+///
+/// ```typescript
+/// const { columns, rows } = await invoke("query_attached_db", {
+///   id,
+///   sql: "SELECT artist, COUNT(*) FROM tracks GROUP BY artist ORDER BY 2 DESC",
+///   limit: 10,
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn query_attached_db(
+    app: AppHandle,
+    id: String,
+    sql: String,
+    limit: Option<u32>,
+) -> Result<DbQueryResult, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = load(&app, &id).and_then(|(attachment, conn)| {
+            run(&conn, &sql, limit.unwrap_or(DEFAULT_ROWS)).map(|result| (attachment, result))
+        });
+        let error = result.as_ref().err().map(ToString::to_string);
+        let detail = result.as_ref().ok().map(|(attachment, result)| {
+            json!({
+                "tool": "query_attached_db",
+                "fileName": attachment.file_name,
+                "rows": result.rows.len(),
+                "truncated": result.truncated,
+                "elapsedMs": result.elapsed_ms,
+            })
+        });
+        audit::record(
+            &app,
+            &AuditRecord {
+                actor: &format!("attachment:{id}"),
+                kind: AuditKind::Tool,
+                target: &sql,
+                detail,
+                error: error.as_deref(),
+            },
+        );
+        result.map(|(_, result)| result)
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE tracks (id INTEGER PRIMARY KEY, title TEXT, bpm REAL, art BLOB);
+             INSERT INTO tracks (title, bpm, art) VALUES
+                 ('Kick', 120, x'0102'), ('Hat', 128.5, NULL), ('Pad', 90, NULL);
+             CREATE VIEW fast AS SELECT title FROM tracks WHERE bpm > 100;",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_queries_are_read_only() {
+        let conn = fixture();
+        for sql in [
+            "DELETE FROM tracks",
+            "WITH t AS (SELECT 1) DELETE FROM tracks",
+            "ATTACH 'other.db' AS other",
+            "PRAGMA writable_schema = ON",
+        ] {
+            assert_eq!(
+                run(&conn, sql, 10).unwrap_err().code(),
+                "FORBIDDEN",
+                "{sql}"
+            );
+        }
+        assert_eq!(
+            run(&conn, "SELECT 1; DELETE FROM tracks", 10)
+                .unwrap_err()
+                .code(),
+            "INVALID_QUERY"
+        );
+        assert_eq!(run(&conn, "SELEC 1", 10).unwrap_err().code(), "FORBIDDEN");
+        assert_eq!(
+            run(&conn, "SELECT nope FROM tracks", 10)
+                .unwrap_err()
+                .code(),
+            "INVALID_QUERY"
+        );
+    }
+
+    #[test]
+    fn test_results_are_limited() {
+        let conn = fixture();
+        let result = run(&conn, "select title, bpm, art from tracks order by id", 2).unwrap();
+        assert_eq!(result.columns, vec!["title", "bpm", "art"]);
+        assert_eq!(
+            result.rows[0],
+            vec![json!("Kick"), json!(120.0), json!("<2-byte blob>")]
+        );
+        assert_eq!(result.rows.len(), 2);
+        assert!(result.truncated);
+        assert!(!run(&conn, "SELECT * FROM fast", 10).unwrap().truncated);
+
+        let tables = schema(&conn).unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(
+            (tables[1].name.as_str(), tables[1].rows),
+            ("tracks", Some(3))
+        );
+        assert_eq!(tables[0].rows, None);
+        assert_eq!(
+            immutable_uri(Path::new("/data/a?b#c.db")),
+            "file:///data/a%3Fb%23c.db?mode=ro&immutable=1"
+        );
+    }
+}
@@ -0,0 +1,866 @@
+//! Attachment store.
+//!
+//! Files dropped onto a window, opened with Gibber AI through a file
+//! association, or picked in the UI are validated and copied into the
+//! `attachments` directory under the app data directory, with a database
+//! row describing each one. [`events::ATTACHMENT_STAGED`] announces every new
+//! attachment; attachments not yet attached to a conversation stay pending
+//! so the frontend can pick them up even if it wasn't listening yet.
+
+use std::fs;
+use std::io::{Read as _, Seek as _, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::events;
+use crate::commands::instance::LaunchArgs;
+use crate::commands::jobs;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Directory under the app data directory holding attachment files.
+const ATTACHMENTS_DIR: &str = "attachments";
+
+/// Largest file accepted, in bytes.
+pub const MAX_ATTACHMENT_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Largest chunk returned by `read_attachment_bytes`, in bytes.
+const MAX_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Columns selected for [`Attachment`] rows, in `attachment_from_row` order.
+const ATTACHMENT_COLUMNS: &str =
+    "id, conversation_id, file_name, kind, mime_type, size, origin, created_at";
+
+/// Supported attachment types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AttachmentKind {
+    /// Markdown document
+    Markdown,
+    /// Plain text document
+    Text,
+    /// PDF document
+    Pdf,
+    /// PNG, JPEG, WebP, or GIF image
+    Image,
+    /// CSV, TSV, Excel, or OpenDocument spreadsheet
+    Spreadsheet,
+    /// SQLite database
+    Database,
+}
+
+impl AttachmentKind {
+    /// Determines the kind and MIME type from a file extension.
+    pub fn from_extension(extension: &str) -> Option<(Self, &'static str)> {
+        match extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some((Self::Markdown, "text/markdown")),
+            "txt" => Some((Self::Text, "text/plain")),
+            "pdf" => Some((Self::Pdf, "application/pdf")),
+            "png" => Some((Self::Image, "image/png")),
+            "jpg" | "jpeg" => Some((Self::Image, "image/jpeg")),
+            "webp" => Some((Self::Image, "image/webp")),
+            "gif" => Some((Self::Image, "image/gif")),
+            "csv" => Some((Self::Spreadsheet, "text/csv")),
+            "tsv" => Some((Self::Spreadsheet, "text/tab-separated-values")),
+            "xlsx" => Some((
+                Self::Spreadsheet,
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            )),
+            "xls" => Some((Self::Spreadsheet, "application/vnd.ms-excel")),
+            "ods" => Some((
+                Self::Spreadsheet,
+                "application/vnd.oasis.opendocument.spreadsheet",
+            )),
+            "sqlite" | "sqlite3" | "db" => Some((Self::Database, "application/vnd.sqlite3")),
+            _ => None,
+        }
+    }
+
+    /// Database representation.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Text => "text",
+            Self::Pdf => "pdf",
+            Self::Image => "image",
+            Self::Spreadsheet => "spreadsheet",
+            Self::Database => "database",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "markdown" => Self::Markdown,
+            "pdf" => Self::Pdf,
+            "image" => Self::Image,
+            "spreadsheet" => Self::Spreadsheet,
+            "database" => Self::Database,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// How an attachment entered the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AttachmentOrigin {
+    /// Dropped onto a window
+    Drop,
+    /// Opened through a file association or the command line
+    OpenWith,
+    /// Picked in the UI
+    Picker,
+    /// Captured by the app itself (e.g. a screenshot)
+    Capture,
+}
+
+impl AttachmentOrigin {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Drop => "drop",
+            Self::OpenWith => "openWith",
+            Self::Picker => "picker",
+            Self::Capture => "capture",
+        }
+    }
+}
+
+/// A stored attachment.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    /// Unique identifier
+    pub id: String,
+    /// Conversation the attachment belongs to; `None` while pending
+    pub conversation_id: Option<String>,
+    /// Original file name
+    pub file_name: String,
+    /// Attachment type
+    pub kind: AttachmentKind,
+    /// MIME type
+    pub mime_type: String,
+    /// Size in bytes
+    pub size: i64,
+    /// How the attachment entered the app (see [`AttachmentOrigin`])
+    pub origin: String,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// Payload of [`events::ATTACHMENT_STAGED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedPayload {
+    /// The staged attachment
+    pub attachment: Attachment,
+    /// Window the file was dropped onto, for drops
+    pub window: Option<String>,
+}
+
+/// Payload of [`events::ATTACHMENT_REJECTED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedPayload {
+    /// The rejected file
+    pub path: String,
+    /// Why it was rejected
+    pub message: String,
+    /// Error code for programmatic handling
+    pub code: String,
+}
+
+/// Location of attachment files.
+pub struct AttachmentStore {
+    dir: PathBuf,
+}
+
+impl AttachmentStore {
+    /// Returns the path of an attachment's file.
+    pub fn file_path(&self, attachment: &Attachment) -> PathBuf {
+        self.dir.join(&attachment.id)
+    }
+}
+
+fn attachment_from_row(row: &Row<'_>) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        file_name: row.get(2)?,
+        kind: AttachmentKind::parse(&row.get::<_, String>(3)?),
+        mime_type: row.get(4)?,
+        size: row.get(5)?,
+        origin: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+/// Checks that file contents match their declared kind.
+fn validate_contents(kind: AttachmentKind, bytes: &[u8]) -> Result<(), GibberError> {
+    let valid = match kind {
+        AttachmentKind::Markdown | AttachmentKind::Text => std::str::from_utf8(bytes).is_ok(),
+        AttachmentKind::Pdf => bytes.starts_with(b"%PDF-"),
+        AttachmentKind::Image => {
+            bytes.starts_with(b"\x89PNG")
+                || bytes.starts_with(b"\xFF\xD8\xFF")
+                || bytes.starts_with(b"GIF8")
+                || (bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(&b"WEBP"[..]))
+        }
+        // Delimited text, a ZIP (XLSX, ODS), or an OLE compound file (XLS).
+        AttachmentKind::Spreadsheet => {
+            std::str::from_utf8(bytes).is_ok()
+                || bytes.starts_with(b"PK\x03\x04")
+                || bytes.starts_with(b"\xD0\xCF\x11\xE0")
+        }
+        AttachmentKind::Database => bytes.starts_with(b"SQLite format 3\0"),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(GibberError::new(
+            "INVALID_CONTENT",
+            format!("File contents are not valid {}", kind.as_str()),
+        ))
+    }
+}
+
+/// Validates and stores attachment bytes.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNSUPPORTED_TYPE`, `TOO_LARGE`,
+/// or `INVALID_CONTENT` for rejected files, or `IO`/`DATABASE` if storing
+/// fails.
+pub fn store_bytes(
+    conn: &Connection,
+    dir: &Path,
+    file_name: &str,
+    bytes: &[u8],
+    origin: AttachmentOrigin,
+) -> Result<Attachment, GibberError> {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let (kind, mime_type) = AttachmentKind::from_extension(extension).ok_or_else(|| {
+        GibberError::new(
+            "UNSUPPORTED_TYPE",
+            format!("Unsupported file type: {file_name}"),
+        )
+    })?;
+    let size = u64::try_from(bytes.len()).unwrap_or(u64::MAX);
+    if size > MAX_ATTACHMENT_BYTES {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!(
+                "{file_name} is larger than {} MB",
+                MAX_ATTACHMENT_BYTES / 1024 / 1024
+            ),
+        ));
+    }
+    validate_contents(kind, bytes)?;
+
+    let attachment = Attachment {
+        id: db::new_id(),
+        conversation_id: None,
+        file_name: file_name.to_string(),
+        kind,
+        mime_type: mime_type.to_string(),
+        size: i64::try_from(size).unwrap_or(i64::MAX),
+        origin: origin.as_str().to_string(),
+        created_at: db::now_millis(),
+    };
+    fs::create_dir_all(dir)?;
+    let path = dir.join(&attachment.id);
+    fs::write(&path, bytes)?;
+    if let Err(e) = conn.execute(
+        &format!("INSERT INTO attachments ({ATTACHMENT_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"),
+        params![
+            attachment.id,
+            attachment.conversation_id,
+            attachment.file_name,
+            kind.as_str(),
+            attachment.mime_type,
+            attachment.size,
+            attachment.origin,
+            attachment.created_at,
+        ],
+    ) {
+        let _ = fs::remove_file(&path);
+        return Err(e.into());
+    }
+    Ok(attachment)
+}
+
+/// Validates and stores a file from disk.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if `path` is not a
+/// regular file, or any error of [`store_bytes`].
+pub fn store_file(
+    conn: &Connection,
+    dir: &Path,
+    path: &Path,
+    origin: AttachmentOrigin,
+) -> Result<Attachment, GibberError> {
+    let metadata = fs::metadata(path)
+        .ok()
+        .filter(fs::Metadata::is_file)
+        .ok_or_else(|| {
+            GibberError::new("NOT_FOUND", format!("{} is not a file", path.display()))
+        })?;
+    if metadata.len() > MAX_ATTACHMENT_BYTES {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!(
+                "{} is larger than {} MB",
+                path.display(),
+                MAX_ATTACHMENT_BYTES / 1024 / 1024
+            ),
+        ));
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    store_bytes(conn, dir, &file_name, &fs::read(path)?, origin)
+}
+
+/// Stages files and announces each result.
+///
+/// Accepted files emit [`events::ATTACHMENT_STAGED`]; rejected ones emit
+/// [`events::ATTACHMENT_REJECTED`]. `window` names the window the files were
+/// dropped on. Several files are imported as an `ingest.attachments` job (see
+/// [`jobs`]), which can be cancelled between files.
+///
+/// [`jobs`]: crate::commands::jobs
+pub fn stage_paths(
+    app: &AppHandle,
+    paths: &[PathBuf],
+    origin: AttachmentOrigin,
+    window: Option<&str>,
+) {
+    let store = app.state::<AttachmentStore>();
+    let db = app.state::<Database>();
+    let mut job = (paths.len() > 1).then(|| {
+        jobs::start(
+            app,
+            "ingest.attachments",
+            &format!("Importing {} files", paths.len()),
+        )
+    });
+    let mut staged = 0;
+    for (index, path) in paths.iter().enumerate() {
+        if let Some(job) = job.as_mut() {
+            if job.is_cancelled() {
+                break;
+            }
+            let name = path.file_name().map(|name| name.to_string_lossy());
+            job.advance(index, paths.len(), name.as_deref());
+        }
+        let result = store_file(&db.conn(), &store.dir, path, origin);
+        staged += usize::from(result.is_ok());
+        match result {
+            Ok(attachment) => events::emit_typed(
+                app,
+                events::ATTACHMENT_STAGED,
+                &StagedPayload {
+                    attachment,
+                    window: window.map(str::to_string),
+                },
+            ),
+            Err(error) => events::emit_typed(
+                app,
+                events::ATTACHMENT_REJECTED,
+                &RejectedPayload {
+                    path: path.display().to_string(),
+                    message: error.to_string(),
+                    code: error.code().to_string(),
+                },
+            ),
+        }
+    }
+    if let Some(job) = job {
+        let summary = format!("{staged} of {} files imported", paths.len());
+        job.finish(None, Some(&summary));
+    }
+}
+
+/// Removes files whose rows are gone, e.g. after their conversation was
+/// deleted (rows cascade with their conversation; files can't).
+fn prune_orphans(conn: &Connection, dir: &Path) -> Result<(), GibberError> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut stmt = conn.prepare("SELECT 1 FROM attachments WHERE id = ?1")?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !stmt.exists([&name])? {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates the attachment store.
+///
+/// # Errors
+///
+/// Returns an error if the app data directory cannot be resolved.
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let dir = app.path().app_data_dir()?.join(ATTACHMENTS_DIR);
+    app.manage(AttachmentStore { dir });
+    Ok(())
+}
+
+/// Removes orphaned files and stages files the app was launched with.
+///
+/// Walks the attachments directory, so it runs after startup. Must be
+/// called after [`init`] and after the [`LaunchArgs`] have been added to
+/// managed state.
+pub fn resume(app: &AppHandle) {
+    let store = app.state::<AttachmentStore>();
+    if let Err(e) = prune_orphans(&app.state::<Database>().conn(), &store.dir) {
+        tracing::error!("failed to prune orphaned files: {e}");
+    }
+
+    let files: Vec<PathBuf> = app
+        .state::<LaunchArgs>()
+        .files()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    stage_paths(app, &files, AttachmentOrigin::OpenWith, None);
+}
+
+/// Loads an attachment by ID.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub(crate) fn load_attachment(conn: &Connection, id: &str) -> rusqlite::Result<Option<Attachment>> {
+    conn.query_row(
+        &format!("SELECT {ATTACHMENT_COLUMNS} FROM attachments WHERE id = ?1"),
+        [id],
+        attachment_from_row,
+    )
+    .optional()
+}
+
+/// Stages a file picked in the UI.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the file is rejected or cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const attachment = await invoke("stage_attachment", { path });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn stage_attachment(
+    db: State<'_, Database>,
+    store: State<'_, AttachmentStore>,
+    path: String,
+) -> Result<Attachment, GibberError> {
+    store_file(
+        &db.conn(),
+        &store.dir,
+        Path::new(&path),
+        AttachmentOrigin::Picker,
+    )
+}
+
+/// Lists attachments not yet attached to a conversation, oldest first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_pending_attachments(db: State<'_, Database>) -> Result<Vec<Attachment>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ATTACHMENT_COLUMNS} FROM attachments
+         WHERE conversation_id IS NULL ORDER BY created_at"
+    ))?;
+    let attachments = stmt
+        .query_map([], attachment_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(attachments)
+}
+
+/// Lists the attachments of a conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_conversation_attachments(
+    db: State<'_, Database>,
+    conversation_id: &str,
+) -> Result<Vec<Attachment>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ATTACHMENT_COLUMNS} FROM attachments
+         WHERE conversation_id = ?1 ORDER BY created_at"
+    ))?;
+    let attachments = stmt
+        .query_map([conversation_id], attachment_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(attachments)
+}
+
+/// Assigns an attachment to a conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment or
+/// conversation doesn't exist.
+pub(crate) fn assign_conversation(
+    conn: &Connection,
+    attachment_id: &str,
+    conversation_id: &str,
+) -> Result<Attachment, GibberError> {
+    let updated = conn
+        .execute(
+            "UPDATE attachments SET conversation_id = ?1 WHERE id = ?2",
+            params![conversation_id, attachment_id],
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                GibberError::new(
+                    "NOT_FOUND",
+                    format!("Conversation {conversation_id} not found"),
+                )
+            }
+            other => other.into(),
+        })?;
+    if updated == 0 {
+        return Err(GibberError::new(
+            "NOT_FOUND",
+            format!("Attachment {attachment_id} not found"),
+        ));
+    }
+    load_attachment(conn, attachment_id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", "Attachment disappeared"))
+}
+
+/// Stores content produced by the app itself and announces it.
+///
+/// With a `conversation_id` the attachment is attached right away;
+/// otherwise it stays pending.
+///
+/// # Errors
+///
+/// Returns any error of [`store_bytes`] or [`assign_conversation`].
+pub fn store_captured(
+    app: &AppHandle,
+    file_name: &str,
+    bytes: &[u8],
+    conversation_id: Option<&str>,
+) -> Result<Attachment, GibberError> {
+    let store = app.state::<AttachmentStore>();
+    let db = app.state::<Database>();
+    let attachment = {
+        let conn = db.conn();
+        let attachment = store_bytes(
+            &conn,
+            &store.dir,
+            file_name,
+            bytes,
+            AttachmentOrigin::Capture,
+        )?;
+        match conversation_id {
+            Some(id) => assign_conversation(&conn, &attachment.id, id)?,
+            None => attachment,
+        }
+    };
+    events::emit_typed(
+        app,
+        events::ATTACHMENT_STAGED,
+        &StagedPayload {
+            attachment: attachment.clone(),
+            window: None,
+        },
+    );
+    Ok(attachment)
+}
+
+/// Replaces the file of an image attachment with a PNG, e.g. after editing.
+///
+/// The file name keeps its stem and gets a `.png` extension.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment
+/// doesn't exist, `NOT_IMAGE` if it isn't an image, `TOO_LARGE` or
+/// `INVALID_CONTENT` for rejected contents, or `IO` if writing fails.
+pub(crate) fn replace_image(
+    conn: &Connection,
+    store: &AttachmentStore,
+    id: &str,
+    png: &[u8],
+) -> Result<Attachment, GibberError> {
+    let attachment = load_attachment(conn, id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Attachment {id} not found")))?;
+    if attachment.kind != AttachmentKind::Image {
+        return Err(GibberError::new(
+            "NOT_IMAGE",
+            format!("{} is not an image", attachment.file_name),
+        ));
+    }
+    if u64::try_from(png.len()).unwrap_or(u64::MAX) > MAX_ATTACHMENT_BYTES {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!(
+                "The edited image is larger than {} MB",
+                MAX_ATTACHMENT_BYTES / 1024 / 1024
+            ),
+        ));
+    }
+    if !png.starts_with(b"\x89PNG") {
+        return Err(GibberError::new("INVALID_CONTENT", "Not a PNG image"));
+    }
+    let stem = Path::new(&attachment.file_name)
+        .file_stem()
+        .map_or_else(|| "image".into(), |stem| stem.to_string_lossy());
+    let updated = Attachment {
+        file_name: format!("{stem}.png"),
+        mime_type: "image/png".to_string(),
+        size: i64::try_from(png.len()).unwrap_or(i64::MAX),
+        ..attachment
+    };
+    // Write beside the original and swap, so a failed write keeps it intact.
+    let path = store.file_path(&updated);
+    let staging = path.with_extension("edit");
+    fs::write(&staging, png)?;
+    fs::rename(&staging, &path)?;
+    conn.execute(
+        "UPDATE attachments SET file_name = ?1, mime_type = ?2, size = ?3 WHERE id = ?4",
+        params![
+            updated.file_name,
+            updated.mime_type,
+            updated.size,
+            updated.id
+        ],
+    )?;
+    Ok(updated)
+}
+
+/// Attaches a staged attachment to a conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment or
+/// conversation doesn't exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn attach_to_conversation(
+    db: State<'_, Database>,
+    attachment_id: &str,
+    conversation_id: &str,
+) -> Result<Attachment, GibberError> {
+    assign_conversation(&db.conn(), attachment_id, conversation_id)
+}
+
+/// Reads the text of a Markdown or plain text attachment.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment
+/// doesn't exist or `NOT_TEXT` if it isn't a text document.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn read_attachment_text(
+    db: State<'_, Database>,
+    store: State<'_, AttachmentStore>,
+    id: &str,
+) -> Result<String, GibberError> {
+    let attachment = load_attachment(&db.conn(), id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Attachment {id} not found")))?;
+    if !matches!(
+        attachment.kind,
+        AttachmentKind::Markdown | AttachmentKind::Text
+    ) {
+        return Err(GibberError::new(
+            "NOT_TEXT",
+            format!("{} is not a text document", attachment.file_name),
+        ));
+    }
+    Ok(fs::read_to_string(store.file_path(&attachment))?)
+}
+
+/// Reads up to `length` bytes (at most 4 MB, the default) of an
+/// attachment's file, starting at `offset`.
+///
+/// Attachment listings carry only metadata, so a transcript can show its
+/// attachments without reading any files; the bytes are fetched when an
+/// attachment scrolls into view. They are returned as a raw IPC response,
+/// an `ArrayBuffer`, rather than as a JSON array of numbers.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment
+/// doesn't exist, or `IO` if its file cannot be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const bytes = await invoke("read_attachment_bytes", { id: attachment.id });
+/// const url = URL.createObjectURL(new Blob([bytes], { type: attachment.mimeType }));
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn read_attachment_bytes(
+    app: AppHandle,
+    id: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<tauri::ipc::Response, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<_, GibberError> {
+        let attachment = load_attachment(&app.state::<Database>().conn(), &id)?
+            .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Attachment {id} not found")))?;
+        let mut file = fs::File::open(app.state::<AttachmentStore>().file_path(&attachment))?;
+        file.seek(SeekFrom::Start(offset.unwrap_or(0)))?;
+        let mut bytes = Vec::new();
+        file.take(length.unwrap_or(MAX_CHUNK_BYTES).min(MAX_CHUNK_BYTES))
+            .read_to_end(&mut bytes)?;
+        Ok(tauri::ipc::Response::new(bytes))
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+/// Deletes an attachment and its file.
+///
+/// # Returns
+///
+/// Returns `true` if the attachment existed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the row cannot be deleted.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_attachment(
+    db: State<'_, Database>,
+    store: State<'_, AttachmentStore>,
+    id: &str,
+) -> Result<bool, GibberError> {
+    let conn = db.conn();
+    let Some(attachment) = load_attachment(&conn, id)? else {
+        return Ok(false);
+    };
+    conn.execute("DELETE FROM attachments WHERE id = ?1", [id])?;
+    if let Err(e) = fs::remove_file(store.file_path(&attachment)) {
+        tracing::error!("failed to remove file of {id}: {e}");
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("gibber-attachments-{}", db::new_id()))
+    }
+
+    #[test]
+    fn test_store_text_file() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = temp_dir();
+        let attachment = store_bytes(
+            &db.conn(),
+            &dir,
+            "notes.md",
+            b"# Beat ideas",
+            AttachmentOrigin::Drop,
+        )
+        .expect("Markdown should be accepted");
+        assert_eq!(attachment.kind, AttachmentKind::Markdown);
+        assert_eq!(attachment.size, 12);
+        assert!(dir.join(&attachment.id).is_file());
+        assert!(load_attachment(&db.conn(), &attachment.id)
+            .unwrap()
+            .is_some());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unsupported_and_mismatched_files() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = temp_dir();
+        let conn = db.conn();
+        let err = store_bytes(&conn, &dir, "run.exe", b"MZ", AttachmentOrigin::Drop).unwrap_err();
+        assert_eq!(err.code(), "UNSUPPORTED_TYPE");
+        let err = store_bytes(
+            &conn,
+            &dir,
+            "paper.pdf",
+            b"not a pdf",
+            AttachmentOrigin::Drop,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "INVALID_CONTENT");
+        let err = store_bytes(
+            &conn,
+            &dir,
+            "notes.txt",
+            &[0xFF, 0xFE, 0x00],
+            AttachmentOrigin::Drop,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "INVALID_CONTENT");
+    }
+
+    #[test]
+    fn test_store_file_requires_regular_file() {
+        let db = Database::open_in_memory().unwrap();
+        let err = store_file(
+            &db.conn(),
+            &temp_dir(),
+            Path::new("/definitely/not/here.md"),
+            AttachmentOrigin::OpenWith,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[test]
+    fn test_prune_removes_orphans() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = temp_dir();
+        let kept =
+            store_bytes(&db.conn(), &dir, "a.txt", b"kept", AttachmentOrigin::Picker).unwrap();
+        fs::write(dir.join("orphan"), b"gone").unwrap();
+        prune_orphans(&db.conn(), &dir).unwrap();
+        assert!(dir.join(&kept.id).is_file());
+        assert!(!dir.join("orphan").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_kind_from_extension() {
+        assert_eq!(
+            AttachmentKind::from_extension("PDF"),
+            Some((AttachmentKind::Pdf, "application/pdf"))
+        );
+        assert!(AttachmentKind::from_extension("docx").is_none());
+    }
+}
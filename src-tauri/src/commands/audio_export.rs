@@ -0,0 +1,455 @@
+//! Conversation audio export.
+//!
+//! `export_conversation_audio` turns a conversation into a podcast-style
+//! recording: every message is read aloud (see [`speech`]) in the voice
+//! picked for its role, and the parts are joined into one file:
+//!
+//! - WAV: the raw samples are written with `hound`, with a short pause
+//!   before each message
+//! - MP3 and Ogg: the encoded parts are joined back to back, as MP3
+//!   frames and as a chained Ogg Opus stream, which players handle like
+//!   one file; neither needs re-encoding on this side
+//!
+//! Markdown is read as plain text and code blocks are skipped with a
+//! short note, since neither reads well aloud. The export runs as an
+//! `export.audio` job, so it reports progress and can be cancelled.
+//!
+//! [`speech`]: crate::commands::speech
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::chat::MessageRole;
+use crate::commands::conversations::{self, ConversationWithMessages};
+use crate::commands::jobs::{self, JobHandle};
+use crate::commands::routing_policy;
+use crate::commands::speech::{self, Speaker, SpeechFormat};
+use crate::commands::telemetry;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Longest conversation read aloud, in characters of spoken text.
+pub const MAX_TOTAL_CHARS: usize = 100_000;
+
+/// Pause before each message in WAV exports, in milliseconds.
+const PAUSE_MS: u32 = 600;
+
+/// Spoken in place of a code block.
+const CODE_NOTE: &str = "(Code omitted.)";
+
+/// File formats a conversation is read into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    /// MP3
+    #[default]
+    Mp3,
+    /// Opus in an Ogg container
+    Ogg,
+    /// 16-bit mono WAV at 24 kHz
+    Wav,
+}
+
+impl AudioFormat {
+    const fn speech_format(self) -> SpeechFormat {
+        match self {
+            Self::Mp3 => SpeechFormat::Mp3,
+            Self::Ogg => SpeechFormat::Opus,
+            Self::Wav => SpeechFormat::Pcm,
+        }
+    }
+}
+
+/// The voice of each role.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoleVoices {
+    /// Voice of the user's messages
+    pub user: String,
+    /// Voice of the model's answers
+    pub assistant: String,
+    /// Voice of system prompts, when they are read
+    pub system: String,
+}
+
+impl Default for RoleVoices {
+    fn default() -> Self {
+        Self {
+            user: "nova".to_string(),
+            assistant: "onyx".to_string(),
+            system: "alloy".to_string(),
+        }
+    }
+}
+
+impl RoleVoices {
+    fn validate(&self) -> Result<(), GibberError> {
+        speech::validate_voice(&self.user)?;
+        speech::validate_voice(&self.assistant)?;
+        speech::validate_voice(&self.system)
+    }
+
+    fn for_role(&self, role: MessageRole) -> &str {
+        match role {
+            MessageRole::User => &self.user,
+            MessageRole::Assistant => &self.assistant,
+            MessageRole::System => &self.system,
+        }
+    }
+}
+
+/// What to export and how.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioExportRequest {
+    /// Conversation to read aloud
+    pub conversation_id: String,
+    /// File to write
+    pub path: String,
+    /// File format (default: MP3)
+    #[serde(default)]
+    pub format: AudioFormat,
+    /// Voice of each role
+    #[serde(default)]
+    pub voices: RoleVoices,
+    /// Speech model; `gpt-4o-mini-tts` when `None`
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Whether system prompts are read too (default: no)
+    #[serde(default)]
+    pub include_system: bool,
+}
+
+/// A finished audio export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioExport {
+    /// The file written
+    pub path: String,
+    /// Its format
+    pub format: AudioFormat,
+    /// Messages read aloud
+    pub messages: usize,
+    /// Speech requests made; long messages take several
+    pub segments: usize,
+    /// Characters read aloud
+    pub characters: usize,
+}
+
+/// One speech request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segment {
+    role: MessageRole,
+    text: String,
+    /// Whether it starts a message
+    first: bool,
+}
+
+/// The text of a Markdown message as it should be read aloud.
+fn spoken_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                text.push('\n');
+                text.push_str(CODE_NOTE);
+                text.push('\n');
+            }
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(fragment) | Event::Code(fragment) if !in_code_block => {
+                text.push_str(&fragment);
+            }
+            Event::SoftBreak | Event::End(TagEnd::TableCell) => text.push(' '),
+            Event::HardBreak
+            | Event::End(
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableRow,
+            ) => text.push('\n'),
+            _ => {}
+        }
+    }
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits the conversation into speech requests.
+fn segments(conversation: &ConversationWithMessages, include_system: bool) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    for message in &conversation.messages {
+        if message.role == MessageRole::System && !include_system {
+            continue;
+        }
+        let parts = speech::split(&spoken_text(&message.content), speech::MAX_INPUT_CHARS);
+        segments.extend(parts.into_iter().enumerate().map(|(i, text)| Segment {
+            role: message.role,
+            text,
+            first: i == 0,
+        }));
+    }
+    segments
+}
+
+/// Skips the ID3v2 tag an MP3 part may start with, so only the first part
+/// of the file carries one.
+fn strip_id3(mp3: &[u8]) -> &[u8] {
+    if mp3.len() < 10 || !mp3.starts_with(b"ID3") {
+        return mp3;
+    }
+    let size = mp3[6..10]
+        .iter()
+        .fold(0_usize, |size, byte| (size << 7) | usize::from(byte & 0x7f));
+    let footer = if mp3[5] & 0x10 == 0 { 0 } else { 10 };
+    mp3.get(10 + size + footer..).unwrap_or_default()
+}
+
+#[allow(clippy::needless_pass_by_value)] // map_err passes the error by value
+fn wav_error(e: hound::Error) -> GibberError {
+    GibberError::new("IO", e.to_string())
+}
+
+/// The file being written.
+enum Output {
+    Wav(hound::WavWriter<BufWriter<File>>),
+    Mp3(BufWriter<File>),
+    Ogg(BufWriter<File>),
+}
+
+impl Output {
+    fn create(path: &Path, format: AudioFormat) -> Result<Self, GibberError> {
+        Ok(match format {
+            AudioFormat::Wav => {
+                let spec = hound::WavSpec {
+                    channels: 1,
+                    sample_rate: speech::PCM_SAMPLE_RATE,
+                    bits_per_sample: 16,
+                    sample_format: hound::SampleFormat::Int,
+                };
+                Self::Wav(hound::WavWriter::create(path, spec).map_err(wav_error)?)
+            }
+            AudioFormat::Mp3 => Self::Mp3(BufWriter::new(File::create(path)?)),
+            AudioFormat::Ogg => Self::Ogg(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    fn append(&mut self, audio: &[u8], first: bool) -> Result<(), GibberError> {
+        match self {
+            Self::Wav(writer) => {
+                for sample in audio.chunks_exact(2) {
+                    writer
+                        .write_sample(i16::from_le_bytes([sample[0], sample[1]]))
+                        .map_err(wav_error)?;
+                }
+            }
+            Self::Mp3(file) if first => file.write_all(audio)?,
+            Self::Mp3(file) => file.write_all(strip_id3(audio))?,
+            Self::Ogg(file) => file.write_all(audio)?,
+        }
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<(), GibberError> {
+        if let Self::Wav(writer) = self {
+            for _ in 0..speech::PCM_SAMPLE_RATE * PAUSE_MS / 1000 {
+                writer.write_sample(0_i16).map_err(wav_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), GibberError> {
+        match self {
+            Self::Wav(writer) => writer.finalize().map_err(wav_error),
+            Self::Mp3(mut file) | Self::Ogg(mut file) => Ok(file.flush()?),
+        }
+    }
+}
+
+async fn write(
+    app: &AppHandle,
+    speaker: &Speaker,
+    request: &AudioExportRequest,
+    model: &str,
+    segments: &[Segment],
+    path: &Path,
+    job: &mut JobHandle,
+) -> Result<(), GibberError> {
+    let mut output = Output::create(path, request.format)?;
+    for (i, segment) in segments.iter().enumerate() {
+        if job.is_cancelled() {
+            return Err(GibberError::new(
+                "CANCELLED",
+                "The audio export was cancelled",
+            ));
+        }
+        let voice = request.voices.for_role(segment.role);
+        let audio = speaker
+            .synthesize(
+                app,
+                model,
+                voice,
+                &segment.text,
+                request.format.speech_format(),
+            )
+            .await?;
+        if segment.first && i > 0 {
+            output.pause()?;
+        }
+        output.append(&audio, i == 0)?;
+        let done = i + 1;
+        job.advance(
+            done,
+            segments.len(),
+            Some(&format!("{done} of {} parts read", segments.len())),
+        );
+    }
+    output.finish()
+}
+
+/// Reads a conversation aloud into an audio file, with a voice per role.
+///
+/// The export runs as an `export.audio` job (see [`jobs`]), so its
+/// progress arrives as `job://progress` events and it can be cancelled
+/// with `cancel_job`. The file only appears once it is complete.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, `INVALID_INPUT` for an unknown voice, `EMPTY` if there
+/// is nothing to read, `TOO_LARGE` over 100,000 characters,
+/// `POLICY_BLOCKED` if the routing policy keeps the conversation from
+/// OpenAI, `NO_API_KEY` if no OpenAI key is stored, `CANCELLED` if the job
+/// was cancelled, `IO` if the file cannot be written, or the error of a
+/// failed speech request.
+///
+/// [`jobs`]: crate::commands::jobs
+///
+/// # Example
+///
+/// ```typescript
+/// const { segments } = await invoke("export_conversation_audio", {
+///   request: {
+///     conversationId,
+///     path: "/Users/me/mixing-session.mp3",
+///     format: "mp3",
+///     voices: { user: "coral", assistant: "onyx" },
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn export_conversation_audio(
+    app: AppHandle,
+    request: AudioExportRequest,
+) -> Result<AudioExport, GibberError> {
+    request.voices.validate()?;
+    let model = request
+        .model
+        .clone()
+        .filter(|model| !model.trim().is_empty())
+        .unwrap_or_else(|| speech::DEFAULT_MODEL.to_string());
+    let id = &request.conversation_id;
+    let conversation = conversations::load_conversation(&app.state::<Database>().conn(), id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Conversation {id} not found")))?;
+    let segments = segments(&conversation, request.include_system);
+    if segments.is_empty() {
+        return Err(GibberError::new(
+            "EMPTY",
+            "The conversation has nothing to read aloud",
+        ));
+    }
+    let characters = segments.iter().map(|s| s.text.chars().count()).sum();
+    if characters > MAX_TOTAL_CHARS {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!("Conversations over {MAX_TOTAL_CHARS} characters can't be read aloud"),
+        ));
+    }
+    routing_policy::enforce(&app, id, speech::PROVIDER, &model)?;
+    let speaker = Speaker::new(&app).await?;
+
+    let path = PathBuf::from(&request.path);
+    let mut partial = path.clone().into_os_string();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let mut job = jobs::start(&app, "export.audio", "Conversation audio export");
+    let written = write(
+        &app, &speaker, &request, &model, &segments, &partial, &mut job,
+    )
+    .await;
+    if let Err(e) = written.and_then(|()| Ok(std::fs::rename(&partial, &path)?)) {
+        // Nothing to clean up if the file was never created.
+        let _ = std::fs::remove_file(&partial);
+        job.finish(Some(&e.to_string()), None);
+        return Err(e);
+    }
+    job.finish(None, Some(&format!("{} parts read", segments.len())));
+    telemetry::record_feature(&app, "export.audio");
+    Ok(AudioExport {
+        path: request.path,
+        format: request.format,
+        messages: segments.iter().filter(|segment| segment.first).count(),
+        segments: segments.len(),
+        characters,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::conversations::{NewConversation, NewMessage};
+
+    #[test]
+    fn test_spoken_text_skips_code() {
+        let markdown = "## Fixing the kick\n\nCut **200 Hz** with `eq`:\n\n\
+                        ```js\nkick.fx.add(EQ())\n```\n\n- Compress\n- [Listen](https://gibber.cc)";
+        assert_eq!(
+            spoken_text(markdown),
+            "Fixing the kick\nCut 200 Hz with eq:\n(Code omitted.)\nCompress\nListen"
+        );
+    }
+
+    #[test]
+    fn test_segments_and_id3() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let id = conversations::insert_conversation(&conn, &NewConversation::default())
+            .unwrap()
+            .id;
+        let long_answer = "Because. ".repeat(600);
+        for (role, content) in [
+            (MessageRole::System, "Be brief"),
+            (MessageRole::User, "Why?"),
+            (MessageRole::Assistant, long_answer.as_str()),
+        ] {
+            let message = NewMessage {
+                role,
+                content: content.to_string(),
+                model: None,
+                usage: None,
+            };
+            conversations::insert_message(&conn, &id, &message).unwrap();
+        }
+        let conversation = conversations::load_conversation(&conn, &id)
+            .unwrap()
+            .unwrap();
+        let segments = segments(&conversation, false);
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].role, MessageRole::User);
+        assert!(segments[1].first && !segments[2].first);
+        assert_eq!(segments[1].role, MessageRole::Assistant);
+
+        let tagged = [b"ID3\x04\x00\x00\x00\x00\x00\x02ab".as_slice(), b"\xff\xfb"].concat();
+        assert_eq!(strip_id3(&tagged), b"\xff\xfb");
+        assert_eq!(strip_id3(b"\xff\xfb\x90"), b"\xff\xfb\x90");
+    }
+}
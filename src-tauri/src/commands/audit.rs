@@ -0,0 +1,315 @@
+//! Audit log of autonomous operations.
+//!
+//! Everything a plugin, automation, or assistant feature does on the
+//! user's behalf is recorded here: tool calls, file reads and writes,
+//! shell commands, and network requests. The table is append-only;
+//! triggers reject updates and deletes, so entries can't be rewritten
+//! after the fact. `get_audit_log` pages through it newest first and
+//! `export_audit_log` writes it out as JSON Lines.
+//!
+//! Actors are named `<feature>:<id>`, e.g. `plugin:art.example.chords` or
+//! `automation:01J…`.
+
+use std::io::Write as _;
+
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Most entries returned per page.
+const MAX_PAGE: u32 = 500;
+
+/// Longest target or error kept, in characters.
+const MAX_FIELD_CHARS: usize = 2_000;
+
+const ENTRY_COLUMNS: &str = "id, actor, kind, target, detail, error, created_at";
+
+/// What kind of operation was performed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditKind {
+    /// A tool was invoked
+    Tool,
+    /// A file was read
+    FileRead,
+    /// A file was written
+    FileWrite,
+    /// A program was run
+    Shell,
+    /// A network request was sent
+    Network,
+}
+
+impl AuditKind {
+    /// Database representation.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Tool => "tool",
+            Self::FileRead => "fileRead",
+            Self::FileWrite => "fileWrite",
+            Self::Shell => "shell",
+            Self::Network => "network",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tool" => Some(Self::Tool),
+            "fileRead" => Some(Self::FileRead),
+            "fileWrite" => Some(Self::FileWrite),
+            "shell" => Some(Self::Shell),
+            "network" => Some(Self::Network),
+            _ => None,
+        }
+    }
+}
+
+/// An operation to record.
+#[derive(Debug, Clone)]
+pub struct AuditRecord<'a> {
+    /// Who performed it, as `<feature>:<id>`
+    pub actor: &'a str,
+    /// What kind of operation it was
+    pub kind: AuditKind,
+    /// Tool name, file path, command line, or URL
+    pub target: &'a str,
+    /// Extra context, e.g. the HTTP method and status
+    pub detail: Option<serde_json::Value>,
+    /// Error message if the operation failed
+    pub error: Option<&'a str>,
+}
+
+/// A recorded operation.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// Sequence number; higher is newer
+    pub id: i64,
+    /// Who performed it
+    pub actor: String,
+    /// What kind of operation it was
+    pub kind: AuditKind,
+    /// Tool name, file path, command line, or URL
+    pub target: String,
+    /// Extra context
+    pub detail: Option<serde_json::Value>,
+    /// Error message if the operation failed
+    pub error: Option<String>,
+    /// Time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// Filters for [`get_audit_log`].
+#[derive(Debug, Clone, Default, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditQuery {
+    /// Only this actor, or actors starting with it when it ends in `:`
+    pub actor: Option<String>,
+    /// Only this kind
+    pub kind: Option<AuditKind>,
+    /// Only entries older than this ID, for paging
+    pub before: Option<i64>,
+    /// Page size (default and maximum: 500)
+    pub limit: Option<u32>,
+}
+
+fn truncate(value: &str) -> String {
+    value.chars().take(MAX_FIELD_CHARS).collect()
+}
+
+fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<AuditEntry> {
+    let kind: String = row.get(2)?;
+    let detail: Option<String> = row.get(4)?;
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        actor: row.get(1)?,
+        kind: AuditKind::parse(&kind).unwrap_or(AuditKind::Tool),
+        target: row.get(3)?,
+        detail: detail.and_then(|detail| serde_json::from_str(&detail).ok()),
+        error: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+/// Appends an operation to the log.
+///
+/// # Errors
+///
+/// Returns an error if the insert fails.
+pub(crate) fn insert(conn: &Connection, record: &AuditRecord<'_>) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO audit_log (actor, kind, target, detail, error, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            record.actor,
+            record.kind.as_str(),
+            truncate(record.target),
+            record.detail.as_ref().map(ToString::to_string),
+            record.error.map(truncate),
+            db::now_millis(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Appends an operation to the log, logging rather than failing.
+pub(crate) fn record(app: &AppHandle, record: &AuditRecord<'_>) {
+    let Some(db) = app.try_state::<Database>() else {
+        return;
+    };
+    let conn = db.conn();
+    if let Err(e) = insert(&conn, record) {
+        tracing::error!(actor = record.actor, "failed to write audit entry: {e}");
+    }
+}
+
+fn query(conn: &Connection, filter: &AuditQuery) -> rusqlite::Result<Vec<AuditEntry>> {
+    let actor = filter.actor.as_deref().filter(|actor| !actor.is_empty());
+    // A trailing `:` selects every actor of a feature.
+    let (actor, prefix) = match actor {
+        Some(actor) if actor.ends_with(':') => (Some(format!("{actor}%")), true),
+        Some(actor) => (Some(actor.to_string()), false),
+        None => (None, false),
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ENTRY_COLUMNS} FROM audit_log
+         WHERE (?1 IS NULL OR (CASE WHEN ?2 THEN actor LIKE ?1 ELSE actor = ?1 END))
+           AND (?3 IS NULL OR kind = ?3)
+           AND (?4 IS NULL OR id < ?4)
+         ORDER BY id DESC LIMIT ?5"
+    ))?;
+    let entries = stmt
+        .query_map(
+            params![
+                actor,
+                prefix,
+                filter.kind.map(AuditKind::as_str),
+                filter.before,
+                filter.limit.unwrap_or(MAX_PAGE).clamp(1, MAX_PAGE),
+            ],
+            entry_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(entries)
+}
+
+/// Returns a page of the audit log, newest first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const page = await invoke("get_audit_log", { query: { actor: "plugin:", limit: 100 } });
+/// const older = await invoke("get_audit_log", { query: { before: page.at(-1).id } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn get_audit_log(
+    db: State<'_, Database>,
+    query: Option<AuditQuery>,
+) -> Result<Vec<AuditEntry>, GibberError> {
+    Ok(self::query(&db.conn(), &query.unwrap_or_default())?)
+}
+
+/// Writes the whole audit log to a JSON Lines file, oldest first.
+///
+/// # Returns
+///
+/// The number of entries written.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `IO` if the file cannot be written.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("export_audit_log", { path: "/Users/me/gibber-audit.jsonl" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn export_audit_log(app: AppHandle, path: String) -> Result<usize, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || export(&app.state::<Database>().conn(), &path))
+        .await
+        .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+fn export(conn: &Connection, path: &str) -> Result<usize, GibberError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ENTRY_COLUMNS} FROM audit_log ORDER BY id"
+    ))?;
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut count = 0;
+    for entry in stmt.query_map([], entry_from_row)? {
+        let line = serde_json::to_string(&entry?).unwrap_or_default();
+        writeln!(file, "{line}")?;
+        count += 1;
+    }
+    file.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(actor: &str, kind: AuditKind) -> AuditRecord<'_> {
+        AuditRecord {
+            actor,
+            kind,
+            target: "https://api.example/data",
+            detail: Some(serde_json::json!({ "method": "GET", "status": 200 })),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_query_filters_and_pages() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        insert(&conn, &record("plugin:a", AuditKind::Network)).unwrap();
+        insert(&conn, &record("plugin:b", AuditKind::Tool)).unwrap();
+        insert(&conn, &record("automation:c", AuditKind::FileWrite)).unwrap();
+
+        let all = query(&conn, &AuditQuery::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].actor, "automation:c");
+        assert_eq!(all[2].detail.as_ref().unwrap()["status"], 200);
+
+        let plugins = AuditQuery {
+            actor: Some("plugin:".to_string()),
+            ..AuditQuery::default()
+        };
+        assert_eq!(query(&conn, &plugins).unwrap().len(), 2);
+        let exact = AuditQuery {
+            actor: Some("plugin:a".to_string()),
+            kind: Some(AuditKind::Network),
+            ..AuditQuery::default()
+        };
+        assert_eq!(query(&conn, &exact).unwrap().len(), 1);
+        let older = AuditQuery {
+            before: Some(all[1].id),
+            ..AuditQuery::default()
+        };
+        assert_eq!(query(&conn, &older).unwrap()[0].actor, "plugin:a");
+    }
+
+    #[test]
+    fn test_log_is_append_only() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        insert(&conn, &record("plugin:a", AuditKind::Shell)).unwrap();
+        assert!(conn
+            .execute("UPDATE audit_log SET target = 'nothing'", [])
+            .is_err());
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+        assert_eq!(query(&conn, &AuditQuery::default()).unwrap().len(), 1);
+    }
+}
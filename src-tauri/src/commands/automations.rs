@@ -0,0 +1,1018 @@
+//! User automation scripts.
+//!
+//! An automation is a small [Rhai](https://rhai.rs) script with a trigger:
+//! a global hotkey, a cron schedule, every new conversation message, or
+//! only the "run" button. Scripts see nothing of the machine beyond this
+//! API:
+//!
+//! - `prompt(text)` / `prompt(text, model)`: sends a one-off prompt and
+//!   returns the reply (at most [`MAX_PROMPTS_PER_RUN`] per run)
+//! - `read_conversation(id)`: a conversation with its messages
+//! - `current_conversation()`: the ID open in the focused window, or `()`
+//! - `write_file(name, text)` / `read_file(name)`: files under the
+//!   `automations` folder in the app data directory; `name` is relative
+//!   and may not leave it
+//! - `print(value)`: adds a line to the run output
+//!
+//! The trigger is available as the `event` constant: `event.kind`, and
+//! `event.message` for new messages. Each run gets a fresh engine with
+//! caps on operations, wall time, nesting, and string and collection
+//! sizes, so a runaway script fails instead of hanging the app. `eval`
+//! and module imports are disabled. Scheduled runs are skipped while sync
+//! is paused from the tray, and a script never runs twice at once.
+//!
+//! ```rhai
+//! let message = event.message;
+//! if message.content.contains("TODO") {
+//!     let summary = prompt(`List the action items in: ${message.content}`);
+//!     write_file(`todo/${message.conversationId}.md`, summary);
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Listener, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::audit::{self, AuditKind, AuditRecord};
+use crate::commands::conversations;
+use crate::commands::events;
+use crate::commands::scheduler::{self, RunStatus};
+use crate::commands::shortcuts::ShortcutRegistry;
+use crate::commands::telemetry;
+use crate::commands::tray;
+use crate::commands::windows::CurrentConversation;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// How often the background loop checks for due scheduled automations.
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Folder under the app data directory that scripts may read and write.
+const FILES_DIR: &str = "automations";
+
+/// Prompts a single run may send.
+pub const MAX_PROMPTS_PER_RUN: usize = 20;
+
+/// Wall time a run may take, prompts included.
+const MAX_RUN_TIME: Duration = Duration::from_mins(5);
+
+/// Script operations per run; roughly one per expression evaluated.
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+/// Function call nesting allowed in a script.
+const MAX_CALL_LEVELS: usize = 32;
+
+/// Largest string a script may build or read from a file.
+const MAX_STRING_BYTES: usize = 1024 * 1024;
+
+/// Largest array or object map a script may build.
+const MAX_COLLECTION_LEN: usize = 10_000;
+
+/// Lines of `print` output kept per run.
+const MAX_OUTPUT_LINES: usize = 200;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Automation {id} not found"))
+}
+
+/// What starts an automation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AutomationTrigger {
+    /// Only the "run" button
+    Manual,
+    /// A global shortcut
+    Hotkey {
+        /// Accelerator, e.g. `CommandOrControl+Alt+T`
+        accelerator: String,
+    },
+    /// A cron schedule, as for scheduled tasks
+    Schedule {
+        /// Cron expression in local time
+        cron: String,
+    },
+    /// Every message appended to a conversation
+    NewMessage {
+        /// Only messages with this role; any role if unset
+        #[serde(default)]
+        role: Option<MessageRole>,
+    },
+}
+
+/// A saved automation script.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Automation {
+    /// Unique identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Rhai source
+    pub script: String,
+    /// What starts the script
+    pub trigger: AutomationTrigger,
+    /// Whether the trigger is active; disabled scripts can still be run by hand
+    pub enabled: bool,
+    /// Time of the last run in Unix milliseconds
+    pub last_run_at: Option<i64>,
+    /// Outcome of the last run
+    pub last_status: Option<RunStatus>,
+    /// Error message of the last failed run
+    pub last_error: Option<String>,
+    /// Next scheduled run in Unix milliseconds
+    pub next_run_at: Option<i64>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// Fields for creating or updating an automation.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationInput {
+    /// Display name
+    pub name: String,
+    /// Rhai source
+    pub script: String,
+    /// What starts the script
+    pub trigger: AutomationTrigger,
+    /// Whether the trigger is active (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+/// Outcome of one run; payload of [`events::AUTOMATIONS_RUN_COMPLETED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationRun {
+    /// The automation that ran
+    pub automation_id: String,
+    /// What started the run
+    pub trigger: &'static str,
+    /// Outcome of the run
+    pub status: RunStatus,
+    /// The script's final value, unless it was `()`
+    pub result: Option<String>,
+    /// Lines written with `print`
+    pub output: Vec<String>,
+    /// Error message, on failure
+    pub error: Option<String>,
+    /// Start time in Unix milliseconds
+    pub started_at: i64,
+    /// End time in Unix milliseconds
+    pub finished_at: i64,
+}
+
+/// Registered hotkeys and runs in flight.
+#[derive(Default)]
+pub struct AutomationState {
+    hotkeys: Mutex<HashMap<u32, (Shortcut, String)>>,
+    running: Mutex<HashSet<String>>,
+}
+
+impl AutomationState {
+    fn hotkeys(&self) -> MutexGuard<'_, HashMap<u32, (Shortcut, String)>> {
+        self.hotkeys.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn running(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.running.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+const AUTOMATION_COLUMNS: &str = "id, name, script, triggered_by, enabled, last_run_at, \
+     last_status, last_error, next_run_at, created_at";
+
+fn automation_from_row(row: &Row<'_>) -> rusqlite::Result<Automation> {
+    let trigger: String = row.get(3)?;
+    let status: Option<String> = row.get(6)?;
+    Ok(Automation {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        script: row.get(2)?,
+        trigger: serde_json::from_str(&trigger).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        enabled: row.get(4)?,
+        last_run_at: row.get(5)?,
+        last_status: status.as_deref().and_then(RunStatus::parse),
+        last_error: row.get(7)?,
+        next_run_at: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+fn load_automation(conn: &Connection, id: &str) -> Result<Automation, GibberError> {
+    conn.query_row(
+        &format!("SELECT {AUTOMATION_COLUMNS} FROM automations WHERE id = ?1"),
+        [id],
+        automation_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found(id))
+}
+
+fn query_automations(
+    conn: &Connection,
+    filter: &str,
+    param: Option<i64>,
+) -> rusqlite::Result<Vec<Automation>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {AUTOMATION_COLUMNS} FROM automations {filter} ORDER BY created_at"
+    ))?;
+    let automations = stmt
+        .query_map(rusqlite::params_from_iter(param), automation_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(automations)
+}
+
+fn enabled_automations(conn: &Connection) -> rusqlite::Result<Vec<Automation>> {
+    query_automations(conn, "WHERE enabled = 1", None)
+}
+
+fn due_automations(conn: &Connection, now: i64) -> rusqlite::Result<Vec<Automation>> {
+    query_automations(
+        conn,
+        "WHERE enabled = 1 AND next_run_at IS NOT NULL AND next_run_at <= ?1",
+        Some(now),
+    )
+}
+
+/// Computes when a schedule trigger next fires after `after_millis`.
+fn next_run(trigger: &AutomationTrigger, after_millis: i64) -> Option<i64> {
+    let AutomationTrigger::Schedule { cron } = trigger else {
+        return None;
+    };
+    scheduler::parse_schedule(cron)
+        .ok()
+        .and_then(|schedule| scheduler::next_run_after(&schedule, after_millis))
+}
+
+/// Validates input and computes the next run time for an enabled schedule.
+fn validate_input(input: &AutomationInput) -> Result<Option<i64>, GibberError> {
+    if input.name.trim().is_empty() {
+        return Err(GibberError::new(
+            "INVALID_INPUT",
+            "Automation name is required",
+        ));
+    }
+    sandboxed_engine(Instant::now())
+        .compile(&input.script)
+        .map_err(|e| GibberError::new("INVALID_SCRIPT", e.to_string()))?;
+    match &input.trigger {
+        AutomationTrigger::Hotkey { accelerator } => {
+            Shortcut::from_str(accelerator.trim()).map_err(|e| {
+                GibberError::new("INVALID_SHORTCUT", format!("\"{accelerator}\": {e}"))
+            })?;
+        }
+        AutomationTrigger::Schedule { cron } => {
+            scheduler::parse_schedule(cron)?;
+        }
+        AutomationTrigger::Manual | AutomationTrigger::NewMessage { .. } => {}
+    }
+    Ok(if input.enabled {
+        next_run(&input.trigger, db::now_millis())
+    } else {
+        None
+    })
+}
+
+/// Rejects a hotkey that an app shortcut or another automation already uses.
+fn check_hotkey_conflict(
+    app: &AppHandle,
+    conn: &Connection,
+    id: Option<&str>,
+    input: &AutomationInput,
+) -> Result<(), GibberError> {
+    let AutomationTrigger::Hotkey { accelerator } = &input.trigger else {
+        return Ok(());
+    };
+    let Ok(shortcut) = Shortcut::from_str(accelerator.trim()) else {
+        return Ok(());
+    };
+    let conflict = || {
+        GibberError::new(
+            "CONFLICT",
+            format!("\"{accelerator}\" is already bound to another shortcut"),
+        )
+    };
+    if app
+        .try_state::<ShortcutRegistry>()
+        .is_some_and(|registry| registry.is_bound(shortcut.id()))
+    {
+        return Err(conflict());
+    }
+    for other in query_automations(conn, "", None)? {
+        if Some(other.id.as_str()) == id {
+            continue;
+        }
+        if let AutomationTrigger::Hotkey { accelerator } = &other.trigger {
+            if Shortcut::from_str(accelerator.trim()).is_ok_and(|s| s.id() == shortcut.id()) {
+                return Err(conflict());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Registers the hotkeys of enabled automations, replacing earlier ones.
+///
+/// App shortcuts take precedence: a hotkey the OS refuses (typically because
+/// a shortcut or another app owns it) is logged and skipped.
+pub(crate) fn register_hotkeys(app: &AppHandle) {
+    let Some(state) = app.try_state::<AutomationState>() else {
+        return;
+    };
+    let automations = match enabled_automations(&app.state::<Database>().conn()) {
+        Ok(automations) => automations,
+        Err(e) => {
+            tracing::error!("failed to load automations: {e}");
+            return;
+        }
+    };
+    let shortcuts = app.global_shortcut();
+    let mut hotkeys = state.hotkeys();
+    for (shortcut, _) in hotkeys.values() {
+        // Rebinding app shortcuts unregisters everything, so this may
+        // already be gone.
+        let _ = shortcuts.unregister(*shortcut);
+    }
+    hotkeys.clear();
+    for automation in automations {
+        let AutomationTrigger::Hotkey { accelerator } = &automation.trigger else {
+            continue;
+        };
+        let Ok(shortcut) = Shortcut::from_str(accelerator.trim()) else {
+            continue;
+        };
+        match shortcuts.register(shortcut) {
+            Ok(()) => {
+                hotkeys.insert(shortcut.id(), (shortcut, automation.id));
+            }
+            Err(e) => {
+                tracing::warn!(id = %automation.id, "automation hotkey is unavailable: {e}");
+            }
+        }
+    }
+}
+
+/// Runs the automation bound to a pressed global shortcut, if any.
+pub(crate) fn trigger_hotkey(app: &AppHandle, shortcut_id: u32) {
+    let Some(id) = app
+        .try_state::<AutomationState>()
+        .and_then(|state| state.hotkeys().get(&shortcut_id).map(|(_, id)| id.clone()))
+    else {
+        return;
+    };
+    match load_automation(&app.state::<Database>().conn(), &id) {
+        Ok(automation) => spawn_run(app, automation, "hotkey", None),
+        Err(e) => tracing::warn!(%id, "failed to load automation: {e}"),
+    }
+}
+
+/// Runs the automations listening for a newly stored message.
+fn on_message(app: &AppHandle, payload: &str) {
+    let Ok(message) = serde_json::from_str::<Value>(payload) else {
+        return;
+    };
+    let role = message
+        .get("role")
+        .and_then(Value::as_str)
+        .and_then(MessageRole::parse);
+    let automations = match enabled_automations(&app.state::<Database>().conn()) {
+        Ok(automations) => automations,
+        Err(e) => {
+            tracing::error!("failed to load automations: {e}");
+            return;
+        }
+    };
+    for automation in automations {
+        let matches = match &automation.trigger {
+            AutomationTrigger::NewMessage { role: wanted } => wanted.is_none() || *wanted == role,
+            _ => false,
+        };
+        if matches {
+            spawn_run(app, automation, "newMessage", Some(message.clone()));
+        }
+    }
+}
+
+/// Manages automation state, listens for new messages, registers hotkeys,
+/// and starts the loop that runs scheduled automations.
+///
+/// Must be called after the [`Database`] and tray state have been added to
+/// managed state and after `shortcuts::init`.
+pub fn start(app: &AppHandle) {
+    app.manage(AutomationState::default());
+    let handle = app.clone();
+    app.listen_any(events::CONVERSATION_MESSAGE.name(), move |e| {
+        on_message(&handle, e.payload());
+    });
+    register_hotkeys(app);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if tray::is_sync_paused(&app) {
+                continue;
+            }
+            let now = db::now_millis();
+            let due = {
+                let db = app.state::<Database>();
+                let conn = db.conn();
+                due_automations(&conn, now).and_then(|automations| {
+                    // Advance before running so a slow script isn't started twice.
+                    for automation in &automations {
+                        conn.execute(
+                            "UPDATE automations SET next_run_at = ?1 WHERE id = ?2",
+                            params![next_run(&automation.trigger, now), automation.id],
+                        )?;
+                    }
+                    Ok(automations)
+                })
+            };
+            match due {
+                Ok(automations) => {
+                    for automation in automations {
+                        spawn_run(&app, automation, "schedule", None);
+                    }
+                }
+                Err(e) => tracing::error!("failed to query due automations: {e}"),
+            }
+        }
+    });
+}
+
+/// Runs an automation in the background, logging why it couldn't start.
+fn spawn_run(
+    app: &AppHandle,
+    automation: Automation,
+    trigger: &'static str,
+    message: Option<Value>,
+) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run(&app, automation, trigger, message).await {
+            tracing::debug!("automation skipped: {e}");
+        }
+    });
+}
+
+/// Runs a script on a blocking thread, records its outcome, and emits
+/// [`events::AUTOMATIONS_RUN_COMPLETED`].
+///
+/// `trigger` and `message` become the script's `event` constant.
+async fn run(
+    app: &AppHandle,
+    automation: Automation,
+    trigger: &'static str,
+    message: Option<Value>,
+) -> Result<AutomationRun, GibberError> {
+    let state = app.state::<AutomationState>();
+    if !state.running().insert(automation.id.clone()) {
+        return Err(GibberError::new(
+            "BUSY",
+            format!("{} is already running", automation.name),
+        ));
+    }
+    telemetry::record_feature(app, "automations.run");
+    let started_at = db::now_millis();
+    let event = json!({ "kind": trigger, "message": message });
+    let handle = app.clone();
+    let script = automation.script;
+    let actor = format!("automation:{}", automation.id);
+    let outcome =
+        tauri::async_runtime::spawn_blocking(move || execute(&handle, &actor, &script, &event))
+            .await;
+    state.running().remove(&automation.id);
+
+    let (result, output) = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => (Err(e.to_string()), Vec::new()),
+    };
+    let run = AutomationRun {
+        automation_id: automation.id,
+        trigger,
+        status: if result.is_ok() {
+            RunStatus::Succeeded
+        } else {
+            RunStatus::Failed
+        },
+        error: result.as_ref().err().cloned(),
+        result: result.ok().flatten(),
+        output,
+        started_at,
+        finished_at: db::now_millis(),
+    };
+    if let Err(e) = app.state::<Database>().conn().execute(
+        "UPDATE automations SET last_run_at = ?1, last_status = ?2, last_error = ?3 WHERE id = ?4",
+        params![
+            run.started_at,
+            run.status.as_str(),
+            run.error,
+            run.automation_id
+        ],
+    ) {
+        tracing::error!(id = %run.automation_id, "failed to record run: {e}");
+    }
+    events::emit_typed(app, events::AUTOMATIONS_RUN_COMPLETED, &run);
+    Ok(run)
+}
+
+/// Builds an engine with resource limits and without `eval` or imports.
+fn sandboxed_engine(deadline: Instant) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(MAX_STRING_BYTES);
+    engine.set_max_array_size(MAX_COLLECTION_LEN);
+    engine.set_max_map_size(MAX_COLLECTION_LEN);
+    engine.on_progress(move |_| {
+        if Instant::now() > deadline {
+            Some("timed out".into())
+        } else {
+            None
+        }
+    });
+    engine
+}
+
+/// Describes a script failure, naming the limit it hit.
+fn describe_error(err: &EvalAltResult) -> String {
+    match err {
+        EvalAltResult::ErrorTerminated(..) => {
+            format!(
+                "The script ran longer than {} seconds",
+                MAX_RUN_TIME.as_secs()
+            )
+        }
+        EvalAltResult::ErrorTooManyOperations(_) => {
+            "The script exceeded its operation limit".to_string()
+        }
+        _ => err.to_string(),
+    }
+}
+
+/// Resolves a script-supplied file name inside the sandbox folder.
+fn sandbox_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(name);
+    if name.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(format!(
+            "\"{name}\" is not a relative path inside the automations folder"
+        ));
+    }
+    Ok(dir.join(relative))
+}
+
+/// Records a script operation in the audit log.
+fn record_operation(
+    app: &AppHandle,
+    actor: &str,
+    kind: AuditKind,
+    target: &str,
+    detail: Value,
+    error: Option<&str>,
+) {
+    audit::record(
+        app,
+        &AuditRecord {
+            actor,
+            kind,
+            target,
+            detail: Some(detail),
+            error,
+        },
+    );
+}
+
+fn prompt(
+    app: &AppHandle,
+    actor: &str,
+    count: &AtomicUsize,
+    text: &str,
+    model: Option<&str>,
+) -> ScriptResult<String> {
+    if count.fetch_add(1, Ordering::Relaxed) >= MAX_PROMPTS_PER_RUN {
+        return Err(format!("A run may send at most {MAX_PROMPTS_PER_RUN} prompts").into());
+    }
+    let request = ChatRequest::new(model, vec![ChatMessage::new(MessageRole::User, text)]);
+    // Scripts run on a blocking thread, so waiting here is fine.
+    let result = tauri::async_runtime::block_on(chat::complete(app, &request));
+    let error = result.as_ref().err().map(ToString::to_string);
+    record_operation(
+        app,
+        actor,
+        AuditKind::Network,
+        chat::OPENROUTER_API_URL,
+        json!({ "model": request.model }),
+        error.as_deref(),
+    );
+    result
+        .map(|completion| completion.content)
+        .map_err(|e| e.to_string().into())
+}
+
+fn read_conversation(app: &AppHandle, id: &str) -> ScriptResult<Dynamic> {
+    let conversation = conversations::load_conversation(&app.state::<Database>().conn(), id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Conversation not found: {id}"))?;
+    rhai::serde::to_dynamic(&conversation)
+}
+
+fn write_file(dir: &Path, name: &str, text: &str) -> Result<(), String> {
+    let path = sandbox_path(dir, name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {name}: {e}"))
+}
+
+fn read_file(dir: &Path, name: &str) -> Result<String, String> {
+    let path = sandbox_path(dir, name)?;
+    let metadata = std::fs::metadata(&path).map_err(|e| format!("Failed to read {name}: {e}"))?;
+    if metadata.len() > MAX_STRING_BYTES as u64 {
+        return Err(format!("{name} is larger than {MAX_STRING_BYTES} bytes"));
+    }
+    std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {name}: {e}"))
+}
+
+/// Registers the script API on `engine`.
+///
+/// `actor` names the automation in the audit log.
+fn register_api(engine: &mut Engine, app: &AppHandle, actor: &str, dir: PathBuf) {
+    let actor: Arc<str> = Arc::from(actor);
+    let prompts = Arc::new(AtomicUsize::new(0));
+    let (handle, who, count) = (app.clone(), Arc::clone(&actor), Arc::clone(&prompts));
+    engine.register_fn("prompt", move |text: &str| {
+        prompt(&handle, &who, &count, text, None)
+    });
+    let (handle, who) = (app.clone(), Arc::clone(&actor));
+    engine.register_fn("prompt", move |text: &str, model: &str| {
+        prompt(&handle, &who, &prompts, text, Some(model))
+    });
+    let handle = app.clone();
+    engine.register_fn("read_conversation", move |id: &str| {
+        read_conversation(&handle, id)
+    });
+    let handle = app.clone();
+    engine.register_fn("current_conversation", move || {
+        handle
+            .try_state::<CurrentConversation>()
+            .and_then(|current| current.get())
+            .map_or(Dynamic::UNIT, Dynamic::from)
+    });
+    let (handle, who, files) = (app.clone(), Arc::clone(&actor), dir.clone());
+    engine.register_fn(
+        "write_file",
+        move |name: &str, text: &str| -> ScriptResult<()> {
+            let result = write_file(&files, name, text);
+            let detail = json!({ "bytes": text.len() });
+            let error = result.as_ref().err().map(String::as_str);
+            record_operation(&handle, &who, AuditKind::FileWrite, name, detail, error);
+            result.map_err(Into::into)
+        },
+    );
+    let handle = app.clone();
+    engine.register_fn("read_file", move |name: &str| -> ScriptResult<String> {
+        let result = read_file(&dir, name);
+        let detail = json!({ "bytes": result.as_ref().map_or(0, String::len) });
+        let error = result.as_ref().err().map(String::as_str);
+        record_operation(&handle, &actor, AuditKind::FileRead, name, detail, error);
+        result.map_err(Into::into)
+    });
+}
+
+/// Compiles and evaluates a script, returning its final value and output.
+fn execute(
+    app: &AppHandle,
+    actor: &str,
+    script: &str,
+    event: &Value,
+) -> (Result<Option<String>, String>, Vec<String>) {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let result = evaluate(app, actor, script, event, &output);
+    let output = std::mem::take(&mut *output.lock().unwrap_or_else(PoisonError::into_inner));
+    (result, output)
+}
+
+fn evaluate(
+    app: &AppHandle,
+    actor: &str,
+    script: &str,
+    event: &Value,
+    output: &Arc<Mutex<Vec<String>>>,
+) -> Result<Option<String>, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join(FILES_DIR);
+    let mut engine = sandboxed_engine(Instant::now() + MAX_RUN_TIME);
+    let lines = Arc::clone(output);
+    engine.on_print(move |line| {
+        let mut lines = lines.lock().unwrap_or_else(PoisonError::into_inner);
+        if lines.len() < MAX_OUTPUT_LINES {
+            lines.push(line.to_string());
+        }
+    });
+    register_api(&mut engine, app, actor, dir);
+
+    let ast = engine.compile(script).map_err(|e| e.to_string())?;
+    let mut scope = Scope::new();
+    scope.push_constant(
+        "event",
+        rhai::serde::to_dynamic(event).map_err(|e| describe_error(&e))?,
+    );
+    let value = engine
+        .eval_ast_with_scope::<Dynamic>(&mut scope, &ast)
+        .map_err(|e| describe_error(&e))?;
+    Ok((!value.is_unit()).then(|| value.to_string()))
+}
+
+/// Lists all automations.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const automations = await invoke("list_automations");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_automations(db: State<'_, Database>) -> Result<Vec<Automation>, GibberError> {
+    Ok(query_automations(&db.conn(), "", None)?)
+}
+
+/// Saves a new automation and activates its trigger.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT`, `INVALID_SCRIPT`
+/// (with the parse error), `INVALID_SCHEDULE`, `INVALID_SHORTCUT`, or
+/// `CONFLICT` if the hotkey is taken.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("create_automation", {
+///   input: {
+///     name: "Summarize on hotkey",
+///     script: 'let id = current_conversation(); if id != () { print(prompt("Summarize")); }',
+///     trigger: { kind: "hotkey", accelerator: "CommandOrControl+Alt+S" },
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn create_automation(
+    app: AppHandle,
+    input: AutomationInput,
+) -> Result<Automation, GibberError> {
+    let next_run_at = validate_input(&input)?;
+    let automation = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        check_hotkey_conflict(&app, &conn, None, &input)?;
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO automations (id, name, script, triggered_by, enabled, next_run_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                id,
+                input.name.trim(),
+                input.script,
+                serde_json::to_string(&input.trigger).unwrap_or_default(),
+                input.enabled,
+                next_run_at,
+                db::now_millis(),
+            ],
+        )?;
+        load_automation(&conn, &id)?
+    };
+    register_hotkeys(&app);
+    Ok(automation)
+}
+
+/// Replaces an automation's name, script, and trigger.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the automation
+/// doesn't exist, or any error of [`create_automation`].
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn update_automation(
+    app: AppHandle,
+    id: &str,
+    input: AutomationInput,
+) -> Result<Automation, GibberError> {
+    let next_run_at = validate_input(&input)?;
+    let automation = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        check_hotkey_conflict(&app, &conn, Some(id), &input)?;
+        let updated = conn.execute(
+            "UPDATE automations
+             SET name = ?1, script = ?2, triggered_by = ?3, enabled = ?4, next_run_at = ?5
+             WHERE id = ?6",
+            params![
+                input.name.trim(),
+                input.script,
+                serde_json::to_string(&input.trigger).unwrap_or_default(),
+                input.enabled,
+                next_run_at,
+                id,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(not_found(id));
+        }
+        load_automation(&conn, id)?
+    };
+    register_hotkeys(&app);
+    Ok(automation)
+}
+
+/// Deletes an automation and releases its hotkey.
+///
+/// # Returns
+///
+/// Returns `true` if the automation was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn delete_automation(app: AppHandle, id: &str) -> Result<bool, GibberError> {
+    let deleted = app
+        .state::<Database>()
+        .conn()
+        .execute("DELETE FROM automations WHERE id = ?1", [id])?;
+    register_hotkeys(&app);
+    Ok(deleted > 0)
+}
+
+/// Runs an automation immediately, whatever its trigger, and waits for it.
+///
+/// A failing script is not an error: the run is returned with status
+/// `failed`, its error, and any output printed before it failed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the automation
+/// doesn't exist or `BUSY` if it is already running.
+///
+/// # Example
+///
+/// ```typescript
+/// const run = await invoke("run_automation", { id });
+/// console.log(run.output.join("\n"));
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn run_automation(app: AppHandle, id: String) -> Result<AutomationRun, GibberError> {
+    let automation = load_automation(&app.state::<Database>().conn(), &id)?;
+    run(&app, automation, "manual", None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(script: &str, trigger: AutomationTrigger) -> AutomationInput {
+        AutomationInput {
+            name: "Daily digest".to_string(),
+            script: script.to_string(),
+            trigger,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_trigger_serialization() {
+        let trigger: AutomationTrigger = serde_json::from_str(r#"{"kind":"newMessage"}"#).unwrap();
+        assert_eq!(trigger, AutomationTrigger::NewMessage { role: None });
+        let hotkey = AutomationTrigger::Hotkey {
+            accelerator: "CommandOrControl+Alt+T".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&hotkey).unwrap(),
+            json!({ "kind": "hotkey", "accelerator": "CommandOrControl+Alt+T" })
+        );
+    }
+
+    #[test]
+    fn test_validate_input() {
+        let schedule = || AutomationTrigger::Schedule {
+            cron: "0 8 * * *".to_string(),
+        };
+        assert!(validate_input(&input("1 + 1", schedule()))
+            .unwrap()
+            .is_some());
+        assert!(validate_input(&input("1 + 1", AutomationTrigger::Manual))
+            .unwrap()
+            .is_none());
+        let err = validate_input(&input("let x = ;", AutomationTrigger::Manual)).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SCRIPT");
+        let err = validate_input(&input(
+            "1",
+            AutomationTrigger::Schedule {
+                cron: "often".to_string(),
+            },
+        ))
+        .unwrap_err();
+        assert_eq!(err.code(), "INVALID_SCHEDULE");
+        let err = validate_input(&input(
+            "1",
+            AutomationTrigger::Hotkey {
+                accelerator: "Ctrl+Banana".to_string(),
+            },
+        ))
+        .unwrap_err();
+        assert_eq!(err.code(), "INVALID_SHORTCUT");
+    }
+
+    #[test]
+    fn test_sandbox_path_stays_inside() {
+        let dir = Path::new("/data/automations");
+        assert_eq!(
+            sandbox_path(dir, "notes/today.md").unwrap(),
+            dir.join("notes/today.md")
+        );
+        for name in ["", "../secrets", "/etc/passwd", "notes/../../x", "./x"] {
+            assert!(
+                sandbox_path(dir, name).is_err(),
+                "{name} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_engine_limits() {
+        let mut engine = sandboxed_engine(Instant::now() + Duration::from_mins(1));
+        engine.set_max_operations(1000);
+        let err = engine.eval::<()>("loop {}").unwrap_err();
+        assert_eq!(
+            describe_error(&err),
+            "The script exceeded its operation limit"
+        );
+        assert!(engine.eval::<i64>(r#"eval("1")"#).is_err());
+        assert!(engine.eval::<i64>(r#"import "os" as os; 1"#).is_err());
+
+        let expired = sandboxed_engine(Instant::now());
+        let err = expired.eval::<i64>("let x = 1; x + 1").unwrap_err();
+        assert!(matches!(*err, EvalAltResult::ErrorTerminated(..)));
+    }
+
+    #[test]
+    fn test_due_automations_only_returns_enabled_past_due() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        for (id, enabled, next) in [("a", true, 10), ("b", false, 10), ("c", true, 1000)] {
+            conn.execute(
+                "INSERT INTO automations (id, name, script, triggered_by, enabled, next_run_at, created_at)
+                 VALUES (?1, 'n', '1', '{\"kind\":\"schedule\",\"cron\":\"0 8 * * *\"}', ?2, ?3, 0)",
+                params![id, enabled, next],
+            )
+            .unwrap();
+        }
+        let due = due_automations(&conn, 100).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, "a");
+        assert_eq!(enabled_automations(&conn).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,168 @@
+//! Per-origin permissions for the browser extension bridge.
+//!
+//! The companion extension talks to the `gibber` native-messaging host
+//! (built with the `cli` feature), which checks every page against the
+//! origins stored here before sending its content to a model. Origins seen
+//! for the first time are recorded as `pending` and refused until the user
+//! allows them in the app.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, WebviewWindow};
+
+use crate::commands::windows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the bridge permissions.
+pub const SETTINGS_KEY: &str = "browserBridge";
+
+/// Whether pages of an origin may be sent to Gibber.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum OriginAccess {
+    /// Pages may be sent
+    Allowed,
+    /// Pages are refused
+    Denied,
+    /// Seen but not yet decided; refused
+    Pending,
+}
+
+/// Browser bridge preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BrowserBridgeSettings {
+    /// Access by origin, e.g. `https://example.com`
+    pub origins: BTreeMap<String, OriginAccess>,
+    /// Store requests and replies as conversations
+    pub save_conversations: bool,
+}
+
+impl Default for BrowserBridgeSettings {
+    fn default() -> Self {
+        Self {
+            origins: BTreeMap::new(),
+            save_conversations: true,
+        }
+    }
+}
+
+/// Reads the stored permissions.
+pub(crate) fn load_settings(conn: &rusqlite::Connection) -> BrowserBridgeSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Stores the permissions.
+///
+/// # Errors
+///
+/// Returns an error if the statement fails.
+pub(crate) fn save_settings(
+    conn: &rusqlite::Connection,
+    settings: &BrowserBridgeSettings,
+) -> rusqlite::Result<()> {
+    let value = serde_json::to_string(settings).unwrap_or_default();
+    db::write_setting(conn, SETTINGS_KEY, &value)
+}
+
+/// Returns the origin of a page URL, e.g. `https://example.com:8080`.
+///
+/// `None` for URLs without a meaningful origin (`file:`, `data:`).
+pub(crate) fn origin_of(url: &str) -> Option<String> {
+    let origin = tauri::Url::parse(url).ok()?.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
+
+/// Returns the browser bridge permissions.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_browser_bridge_settings(db: State<'_, Database>) -> BrowserBridgeSettings {
+    load_settings(&db.conn())
+}
+
+/// Sets the access of an origin; `null` forgets it.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, `INVALID_ORIGIN` if `origin` isn't an origin, or
+/// `DATABASE` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_origin_access", {
+///   origin: "https://en.wikipedia.org",
+///   access: "allowed",
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require handles and args by value
+pub fn set_origin_access(
+    window: WebviewWindow,
+    db: State<'_, Database>,
+    origin: &str,
+    access: Option<OriginAccess>,
+) -> Result<BrowserBridgeSettings, GibberError> {
+    windows::require_trusted(&window)?;
+    if origin_of(origin).as_deref() != Some(origin) {
+        return Err(GibberError::new(
+            "INVALID_ORIGIN",
+            format!("Not an origin: {origin}"),
+        ));
+    }
+    let conn = db.conn();
+    let mut settings = load_settings(&conn);
+    match access {
+        Some(access) => settings.origins.insert(origin.to_string(), access),
+        None => settings.origins.remove(origin),
+    };
+    save_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// Sets whether bridge requests are stored as conversations.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn set_browser_bridge_save_conversations(
+    db: State<'_, Database>,
+    enabled: bool,
+) -> Result<BrowserBridgeSettings, GibberError> {
+    let conn = db.conn();
+    let mut settings = load_settings(&conn);
+    settings.save_conversations = enabled;
+    save_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_of() {
+        assert_eq!(
+            origin_of("https://en.wikipedia.org/wiki/Synth?x=1").as_deref(),
+            Some("https://en.wikipedia.org")
+        );
+        assert_eq!(
+            origin_of("http://localhost:5173/").as_deref(),
+            Some("http://localhost:5173")
+        );
+        assert_eq!(origin_of("file:///home/ana/notes.html"), None);
+        assert_eq!(origin_of("not a url"), None);
+    }
+}
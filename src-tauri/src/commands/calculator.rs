@@ -0,0 +1,1065 @@
+//! Exact arithmetic and unit conversion.
+//!
+//! Numbers the model works out itself are often wrong, so it gets a
+//! `calculate` and a `convert` tool (see `get_data_tools`) backed by
+//! arbitrary-precision decimals. `+ - * / % ^ !`, parentheses, and the
+//! functions `sqrt abs floor ceil round min max` are exact; `ln log exp
+//! sin cos tan asin acos atan` and fractional powers go through 64-bit
+//! floats, which the result reports as approximate.
+//!
+//! Units convert within their dimension through a factor, and an offset
+//! for temperatures. Currencies convert through the ECB reference rates,
+//! fetched at most twice a day and kept in the database; when the ECB
+//! can't be reached, the last rates are used and reported as stale.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use bigdecimal::{BigDecimal, FromPrimitive, One, RoundingMode, ToPrimitive, Zero};
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::plugins::PluginTool;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Longest expression accepted, in characters.
+const MAX_EXPRESSION_CHARS: usize = 1_000;
+
+/// Deepest nesting of parentheses and operators.
+const MAX_DEPTH: usize = 64;
+
+/// Largest integer exponent and factorial computed exactly.
+const MAX_EXPONENT: i64 = 10_000;
+
+/// Most digits, counting leading or trailing zeros, an exact value may
+/// have; larger results are refused before they are computed.
+const MAX_DIGITS: u64 = 100_000;
+
+/// Largest power of ten in a number literal, and largest `round` digit
+/// count.
+const MAX_LITERAL_EXPONENT: i64 = 1_000;
+
+/// Fractional digits kept in results.
+const RESULT_SCALE: i64 = 30;
+
+/// Fractional digits kept in currency conversions.
+const CURRENCY_SCALE: i64 = 6;
+
+/// Results with more characters than this are written in scientific
+/// notation.
+const MAX_PLAIN_CHARS: usize = 120;
+
+/// Daily euro reference rates of the European Central Bank.
+const ECB_RATES_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+
+/// How long fetched rates are used before they are fetched again.
+const RATES_TTL_MS: i64 = 12 * 60 * 60 * 1000;
+
+/// Time allowed for fetching the rates.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+const PI: &str = "3.14159265358979323846264338327950288419716939937510";
+const E: &str = "2.71828182845904523536028747135266249775724709369995";
+
+/// What `calculate` returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Calculation {
+    /// The value, in plain or scientific decimal notation
+    pub result: String,
+    /// Whether part of the computation used 64-bit floats
+    pub approximate: bool,
+}
+
+/// What `convert_units` returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversion {
+    /// Converted amount
+    pub result: String,
+    /// Target unit or currency as understood
+    pub unit: String,
+    /// What was converted: `length`, `currency`, ...
+    pub dimension: String,
+    /// Whether the amount or conversion used 64-bit floats
+    pub approximate: bool,
+    /// Publication date of the exchange rates used
+    pub rates_date: Option<String>,
+    /// Whether the rates are older than a day because the ECB couldn't be
+    /// reached
+    pub stale_rates: bool,
+}
+
+fn invalid(message: impl Into<String>) -> GibberError {
+    GibberError::new("INVALID_EXPRESSION", message)
+}
+
+fn decimal(text: &str) -> BigDecimal {
+    BigDecimal::from_str(text).expect("constant is a valid decimal")
+}
+
+/// Digits needed to write `value` out in full.
+fn size(value: &BigDecimal) -> u64 {
+    value
+        .digits()
+        .saturating_add(value.fractional_digit_count().unsigned_abs())
+}
+
+/// Refuses a computation whose result would have about `digits` digits.
+fn check_size(digits: u64) -> Result<(), GibberError> {
+    if digits > MAX_DIGITS {
+        return Err(invalid(format!(
+            "The result would have more than {MAX_DIGITS} digits"
+        )));
+    }
+    Ok(())
+}
+
+/// Writes a value rounded to `scale` fractional digits.
+fn format_decimal(value: &BigDecimal, scale: i64) -> String {
+    let rounded = value
+        .with_scale_round(scale, RoundingMode::HalfEven)
+        .normalized();
+    let plain = rounded.to_plain_string();
+    if plain.len() > MAX_PLAIN_CHARS {
+        rounded.to_scientific_notation()
+    } else {
+        plain
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigDecimal),
+    Name(String),
+    Operator(char),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, GibberError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut literal = String::new();
+            while let Some(&c) = chars.peek() {
+                let exponent_sign = (c == '+' || c == '-') && literal.ends_with(['e', 'E']);
+                if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                    literal.push(c);
+                } else if c != '_' {
+                    break;
+                }
+                chars.next();
+            }
+            let number = BigDecimal::from_str(&literal)
+                .map_err(|_| invalid(format!("\"{literal}\" is not a number")))?;
+            if number.fractional_digit_count().abs() > MAX_LITERAL_EXPONENT {
+                return Err(invalid(format!(
+                    "\"{literal}\" is beyond 10^±{MAX_LITERAL_EXPONENT}"
+                )));
+            }
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric()) {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name.to_lowercase()));
+        } else {
+            chars.next();
+            let token = match c {
+                '(' => Token::Open,
+                ')' => Token::Close,
+                ',' => Token::Comma,
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    Token::Operator('^')
+                }
+                '×' => Token::Operator('*'),
+                '÷' => Token::Operator('/'),
+                '−' => Token::Operator('-'),
+                '+' | '-' | '*' | '/' | '%' | '^' | '!' => Token::Operator(c),
+                other => return Err(invalid(format!("Unexpected \"{other}\""))),
+            };
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent evaluator over the tokens.
+struct Evaluator {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+    approximate: bool,
+}
+
+impl Evaluator {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn descend(&mut self) -> Result<(), GibberError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(invalid("The expression is nested too deeply"));
+        }
+        Ok(())
+    }
+
+    /// `expression := term (("+" | "-") term)*`
+    fn expression(&mut self) -> Result<BigDecimal, GibberError> {
+        self.descend()?;
+        let mut value = self.term()?;
+        loop {
+            if self.eat(&Token::Operator('+')) {
+                value += self.term()?;
+            } else if self.eat(&Token::Operator('-')) {
+                value -= self.term()?;
+            } else {
+                break;
+            }
+        }
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    /// `term := unary (("*" | "/" | "%") unary)*`
+    fn term(&mut self) -> Result<BigDecimal, GibberError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat(&Token::Operator('*')) {
+                let factor = self.unary()?;
+                check_size(size(&value).saturating_add(size(&factor)))?;
+                value *= factor;
+            } else if self.eat(&Token::Operator('/')) {
+                let divisor = self.unary()?;
+                if divisor.is_zero() {
+                    return Err(invalid("Division by zero"));
+                }
+                value = value / divisor;
+            } else if self.eat(&Token::Operator('%')) {
+                let divisor = self.unary()?;
+                if divisor.is_zero() {
+                    return Err(invalid("Division by zero"));
+                }
+                value = value % divisor;
+            } else {
+                break;
+            }
+        }
+        Ok(value)
+    }
+
+    /// `unary := ("-" | "+") unary | power`
+    fn unary(&mut self) -> Result<BigDecimal, GibberError> {
+        self.descend()?;
+        let value = if self.eat(&Token::Operator('-')) {
+            -self.unary()?
+        } else if self.eat(&Token::Operator('+')) {
+            self.unary()?
+        } else {
+            self.power()?
+        };
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    /// `power := factorial ("^" unary)?`, so powers group to the right.
+    fn power(&mut self) -> Result<BigDecimal, GibberError> {
+        let base = self.factorial()?;
+        if !self.eat(&Token::Operator('^')) {
+            return Ok(base);
+        }
+        let exponent = self.unary()?;
+        self.pow(&base, &exponent)
+    }
+
+    /// `factorial := primary "!"*`
+    fn factorial(&mut self) -> Result<BigDecimal, GibberError> {
+        let mut value = self.primary()?;
+        while self.eat(&Token::Operator('!')) {
+            let n = value
+                .to_i64()
+                .filter(|n| value.is_integer() && (0..=MAX_EXPONENT).contains(n))
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "Factorials need a whole number up to {MAX_EXPONENT}"
+                    ))
+                })?;
+            // n! has fewer digits than n times the digits of n.
+            check_size(n.unsigned_abs() * size(&value))?;
+            value = (2..=n).fold(BigDecimal::one(), |product, k| {
+                product * BigDecimal::from(k)
+            });
+        }
+        Ok(value)
+    }
+
+    /// `primary := number | name | name "(" arguments ")" | "(" expression ")"`
+    fn primary(&mut self) -> Result<BigDecimal, GibberError> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Open) => {
+                let value = self.expression()?;
+                if !self.eat(&Token::Close) {
+                    return Err(invalid("Missing \")\""));
+                }
+                Ok(value)
+            }
+            Some(Token::Name(name)) if self.eat(&Token::Open) => {
+                let mut arguments = Vec::new();
+                if !self.eat(&Token::Close) {
+                    loop {
+                        arguments.push(self.expression()?);
+                        if self.eat(&Token::Close) {
+                            break;
+                        }
+                        if !self.eat(&Token::Comma) {
+                            return Err(invalid(format!("Missing \")\" after {name}(")));
+                        }
+                    }
+                }
+                self.call(&name, arguments)
+            }
+            Some(Token::Name(name)) => match name.as_str() {
+                "pi" | "π" => Ok(decimal(PI)),
+                "e" => Ok(decimal(E)),
+                _ => Err(invalid(format!("Unknown name \"{name}\""))),
+            },
+            Some(token) => Err(invalid(format!("Unexpected {token:?}"))),
+            None => Err(invalid("The expression ends too early")),
+        }
+    }
+
+    fn pow(&mut self, base: &BigDecimal, exponent: &BigDecimal) -> Result<BigDecimal, GibberError> {
+        let whole = exponent
+            .to_i64()
+            .filter(|n| exponent.is_integer() && n.abs() <= MAX_EXPONENT);
+        let Some(n) = whole else {
+            return self.float(base, |base| {
+                base.powf(exponent.to_f64().unwrap_or(f64::NAN))
+            });
+        };
+        if n < 0 && base.is_zero() {
+            return Err(invalid("Division by zero"));
+        }
+        check_size(n.unsigned_abs().saturating_mul(size(base)))?;
+        // Square and multiply.
+        let mut result = BigDecimal::one();
+        let mut square = base.clone();
+        let mut remaining = n.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result *= &square;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                square = &square * &square;
+            }
+        }
+        Ok(if n < 0 {
+            BigDecimal::one() / result
+        } else {
+            result
+        })
+    }
+
+    /// Applies a float function, marking the result approximate.
+    fn float(
+        &mut self,
+        value: &BigDecimal,
+        function: impl Fn(f64) -> f64,
+    ) -> Result<BigDecimal, GibberError> {
+        self.approximate = true;
+        value
+            .to_f64()
+            .map(function)
+            .and_then(BigDecimal::from_f64)
+            .ok_or_else(|| invalid("The result is not a real number"))
+    }
+
+    fn call(
+        &mut self,
+        name: &str,
+        mut arguments: Vec<BigDecimal>,
+    ) -> Result<BigDecimal, GibberError> {
+        let arity = |count: usize| {
+            invalid(format!(
+                "{name} takes {count} argument{}",
+                if count == 1 { "" } else { "s" }
+            ))
+        };
+        match (name, arguments.len()) {
+            ("min" | "max", 0) => Err(invalid(format!("{name} needs at least one argument"))),
+            ("min", _) => Ok(arguments.into_iter().min().unwrap_or_default()),
+            ("max", _) => Ok(arguments.into_iter().max().unwrap_or_default()),
+            ("round", 1 | 2) => {
+                let digits = arguments
+                    .get(1)
+                    .map_or(Some(0), |digits| {
+                        digits
+                            .to_i64()
+                            .filter(|n| digits.is_integer() && n.abs() <= MAX_LITERAL_EXPONENT)
+                    })
+                    .ok_or_else(|| {
+                        invalid(format!(
+                            "round takes a whole number of digits up to {MAX_LITERAL_EXPONENT}"
+                        ))
+                    })?;
+                Ok(arguments[0].with_scale_round(digits, RoundingMode::HalfUp))
+            }
+            ("round", _) => Err(arity(2)),
+            (_, 1) => {
+                let value = arguments.swap_remove(0);
+                match name {
+                    "sqrt" if value < BigDecimal::zero() => {
+                        Err(invalid("sqrt of a negative number"))
+                    }
+                    "sqrt" => value.sqrt().ok_or_else(|| invalid("sqrt failed")),
+                    "abs" => Ok(value.abs()),
+                    "floor" => Ok(value.with_scale_round(0, RoundingMode::Floor)),
+                    "ceil" => Ok(value.with_scale_round(0, RoundingMode::Ceiling)),
+                    "ln" => self.float(&value, f64::ln),
+                    "log" => self.float(&value, f64::log10),
+                    "exp" => self.float(&value, f64::exp),
+                    "sin" => self.float(&value, f64::sin),
+                    "cos" => self.float(&value, f64::cos),
+                    "tan" => self.float(&value, f64::tan),
+                    "asin" => self.float(&value, f64::asin),
+                    "acos" => self.float(&value, f64::acos),
+                    "atan" => self.float(&value, f64::atan),
+                    _ => Err(invalid(format!("Unknown function \"{name}\""))),
+                }
+            }
+            _ if FUNCTIONS.contains(&name) => Err(arity(1)),
+            _ => Err(invalid(format!("Unknown function \"{name}\""))),
+        }
+    }
+}
+
+/// Functions of one argument.
+const FUNCTIONS: [&str; 13] = [
+    "sqrt", "abs", "floor", "ceil", "ln", "log", "exp", "sin", "cos", "tan", "asin", "acos", "atan",
+];
+
+/// Evaluates an expression, returning the value and whether it is
+/// approximate.
+fn evaluate(expression: &str) -> Result<(BigDecimal, bool), GibberError> {
+    if expression.chars().count() > MAX_EXPRESSION_CHARS {
+        return Err(invalid(format!(
+            "The expression is longer than {MAX_EXPRESSION_CHARS} characters"
+        )));
+    }
+    let mut evaluator = Evaluator {
+        tokens: tokenize(expression)?,
+        position: 0,
+        depth: 0,
+        approximate: false,
+    };
+    let value = evaluator.expression()?;
+    if let Some(token) = evaluator.peek() {
+        return Err(invalid(format!("Unexpected {token:?}")));
+    }
+    Ok((value, evaluator.approximate))
+}
+
+/// A unit: amounts convert to the dimension's base unit as
+/// `(amount + offset) * numerator / denominator`.
+struct Unit {
+    names: &'static [&'static str],
+    dimension: &'static str,
+    numerator: &'static str,
+    denominator: &'static str,
+    offset: &'static str,
+}
+
+const fn unit(
+    names: &'static [&'static str],
+    dimension: &'static str,
+    factor: &'static str,
+) -> Unit {
+    Unit {
+        names,
+        dimension,
+        numerator: factor,
+        denominator: "1",
+        offset: "0",
+    }
+}
+
+const fn ratio(
+    names: &'static [&'static str],
+    dimension: &'static str,
+    numerator: &'static str,
+    denominator: &'static str,
+) -> Unit {
+    Unit {
+        names,
+        dimension,
+        numerator,
+        denominator,
+        offset: "0",
+    }
+}
+
+/// Known units; the first name of each is the one reported.
+const UNITS: &[Unit] = &[
+    unit(&["m", "meter", "meters", "metre", "metres"], "length", "1"),
+    unit(
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        "length",
+        "1000",
+    ),
+    unit(&["cm", "centimeter", "centimeters"], "length", "0.01"),
+    unit(&["mm", "millimeter", "millimeters"], "length", "0.001"),
+    unit(
+        &["µm", "um", "micrometer", "micrometers"],
+        "length",
+        "0.000001",
+    ),
+    unit(&["nm", "nanometer", "nanometers"], "length", "0.000000001"),
+    unit(&["mi", "mile", "miles"], "length", "1609.344"),
+    unit(&["yd", "yard", "yards"], "length", "0.9144"),
+    unit(&["ft", "foot", "feet"], "length", "0.3048"),
+    unit(&["in", "inch", "inches"], "length", "0.0254"),
+    unit(
+        &["nmi", "nautical mile", "nautical miles"],
+        "length",
+        "1852",
+    ),
+    unit(&["kg", "kilogram", "kilograms"], "mass", "1"),
+    unit(&["g", "gram", "grams"], "mass", "0.001"),
+    unit(&["mg", "milligram", "milligrams"], "mass", "0.000001"),
+    unit(&["t", "tonne", "tonnes"], "mass", "1000"),
+    unit(&["lb", "lbs", "pound", "pounds"], "mass", "0.45359237"),
+    unit(&["oz", "ounce", "ounces"], "mass", "0.028349523125"),
+    unit(&["st", "stone", "stones"], "mass", "6.35029318"),
+    unit(&["s", "sec", "second", "seconds"], "time", "1"),
+    unit(&["ms", "millisecond", "milliseconds"], "time", "0.001"),
+    unit(
+        &["µs", "us", "microsecond", "microseconds"],
+        "time",
+        "0.000001",
+    ),
+    unit(&["min", "minute", "minutes"], "time", "60"),
+    unit(&["h", "hr", "hour", "hours"], "time", "3600"),
+    unit(&["d", "day", "days"], "time", "86400"),
+    unit(&["wk", "week", "weeks"], "time", "604800"),
+    unit(&["yr", "year", "years"], "time", "31557600"),
+    unit(&["m3", "m³", "cubic meter", "cubic meters"], "volume", "1"),
+    unit(
+        &["l", "liter", "liters", "litre", "litres"],
+        "volume",
+        "0.001",
+    ),
+    unit(&["cl", "centiliter", "centiliters"], "volume", "0.00001"),
+    unit(&["ml", "milliliter", "milliliters"], "volume", "0.000001"),
+    unit(&["gal", "gallon", "gallons"], "volume", "0.003785411784"),
+    unit(
+        &["imp gal", "imperial gallon", "imperial gallons"],
+        "volume",
+        "0.00454609",
+    ),
+    unit(&["qt", "quart", "quarts"], "volume", "0.000946352946"),
+    unit(&["pt", "pint", "pints"], "volume", "0.000473176473"),
+    unit(&["cup", "cups"], "volume", "0.0002365882365"),
+    unit(
+        &["fl oz", "floz", "fluid ounce", "fluid ounces"],
+        "volume",
+        "0.0000295735295625",
+    ),
+    unit(
+        &["tbsp", "tablespoon", "tablespoons"],
+        "volume",
+        "0.00001478676478125",
+    ),
+    unit(
+        &["tsp", "teaspoon", "teaspoons"],
+        "volume",
+        "0.00000492892159375",
+    ),
+    unit(&["m2", "m²", "square meter", "square meters"], "area", "1"),
+    unit(
+        &["km2", "km²", "square kilometer", "square kilometers"],
+        "area",
+        "1000000",
+    ),
+    unit(
+        &["cm2", "cm²", "square centimeter", "square centimeters"],
+        "area",
+        "0.0001",
+    ),
+    unit(&["ha", "hectare", "hectares"], "area", "10000"),
+    unit(&["acre", "acres"], "area", "4046.8564224"),
+    unit(
+        &["ft2", "ft²", "square foot", "square feet"],
+        "area",
+        "0.09290304",
+    ),
+    unit(
+        &["in2", "in²", "square inch", "square inches"],
+        "area",
+        "0.00064516",
+    ),
+    unit(
+        &["mi2", "mi²", "square mile", "square miles"],
+        "area",
+        "2589988.110336",
+    ),
+    unit(&["m/s", "meters per second"], "speed", "1"),
+    ratio(
+        &["km/h", "kph", "kilometers per hour"],
+        "speed",
+        "1000",
+        "3600",
+    ),
+    ratio(&["mph", "miles per hour"], "speed", "1609.344", "3600"),
+    ratio(&["kn", "knot", "knots"], "speed", "1852", "3600"),
+    unit(&["ft/s", "feet per second"], "speed", "0.3048"),
+    unit(&["K", "kelvin"], "temperature", "1"),
+    Unit {
+        names: &["°C", "C", "celsius"],
+        dimension: "temperature",
+        numerator: "1",
+        denominator: "1",
+        offset: "273.15",
+    },
+    Unit {
+        names: &["°F", "F", "fahrenheit"],
+        dimension: "temperature",
+        numerator: "5",
+        denominator: "9",
+        offset: "459.67",
+    },
+    unit(&["bit", "bits", "b"], "data", "1"),
+    unit(&["B", "byte", "bytes"], "data", "8"),
+    unit(&["kB", "kilobyte", "kilobytes"], "data", "8000"),
+    unit(&["MB", "megabyte", "megabytes"], "data", "8000000"),
+    unit(&["GB", "gigabyte", "gigabytes"], "data", "8000000000"),
+    unit(&["TB", "terabyte", "terabytes"], "data", "8000000000000"),
+    unit(&["KiB", "kibibyte", "kibibytes"], "data", "8192"),
+    unit(&["MiB", "mebibyte", "mebibytes"], "data", "8388608"),
+    unit(&["GiB", "gibibyte", "gibibytes"], "data", "8589934592"),
+    unit(&["TiB", "tebibyte", "tebibytes"], "data", "8796093022208"),
+    unit(&["J", "joule", "joules"], "energy", "1"),
+    unit(&["kJ", "kilojoule", "kilojoules"], "energy", "1000"),
+    unit(&["cal", "calorie", "calories"], "energy", "4.184"),
+    unit(&["kcal", "kilocalorie", "kilocalories"], "energy", "4184"),
+    unit(&["Wh", "watt hour", "watt hours"], "energy", "3600"),
+    unit(
+        &["kWh", "kilowatt hour", "kilowatt hours"],
+        "energy",
+        "3600000",
+    ),
+    unit(
+        &["eV", "electronvolt", "electronvolts"],
+        "energy",
+        "1.602176634e-19",
+    ),
+    unit(&["Pa", "pascal", "pascals"], "pressure", "1"),
+    unit(&["kPa", "kilopascal", "kilopascals"], "pressure", "1000"),
+    unit(&["bar", "bars"], "pressure", "100000"),
+    unit(&["psi"], "pressure", "6894.757293168"),
+    unit(&["atm", "atmosphere", "atmospheres"], "pressure", "101325"),
+    unit(&["mmHg"], "pressure", "133.322387415"),
+    unit(&["Hz", "hertz"], "frequency", "1"),
+    unit(&["kHz", "kilohertz"], "frequency", "1000"),
+    unit(&["MHz", "megahertz"], "frequency", "1000000"),
+    ratio(&["bpm", "beats per minute"], "frequency", "1", "60"),
+    ratio(&["rpm", "revolutions per minute"], "frequency", "1", "60"),
+];
+
+/// Finds a unit by name, matching case exactly first so `B` stays bytes
+/// and `b` bits.
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim();
+    UNITS
+        .iter()
+        .find(|unit| unit.names.contains(&name))
+        .or_else(|| {
+            UNITS
+                .iter()
+                .find(|unit| unit.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        })
+}
+
+fn convert(amount: &BigDecimal, from: &Unit, to: &Unit) -> BigDecimal {
+    // One division at the end keeps the result exact as long as possible.
+    let base = (amount + decimal(from.offset)) * decimal(from.numerator) * decimal(to.denominator);
+    base / (decimal(from.denominator) * decimal(to.numerator)) - decimal(to.offset)
+}
+
+/// Exchange rates as units of currency per euro.
+#[derive(Debug, Clone, PartialEq)]
+struct Rates {
+    per_euro: HashMap<String, BigDecimal>,
+    date: Option<String>,
+    fetched_at: i64,
+}
+
+impl Rates {
+    fn rate(&self, code: &str) -> Option<BigDecimal> {
+        if code == "EUR" {
+            Some(BigDecimal::one())
+        } else {
+            self.per_euro.get(code).cloned()
+        }
+    }
+}
+
+fn currency_code(name: &str) -> Option<String> {
+    let name = name.trim();
+    (name.len() == 3 && name.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| name.to_ascii_uppercase())
+}
+
+/// Parses the ECB's daily reference rate document.
+fn parse_rates(document: &str) -> Option<(HashMap<String, BigDecimal>, Option<String>)> {
+    static RATE: OnceLock<Regex> = OnceLock::new();
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    let rate = RATE.get_or_init(|| {
+        Regex::new(r"currency=['\x22]([A-Z]{3})['\x22]\s+rate=['\x22]([0-9.]+)['\x22]")
+            .expect("rate pattern is valid")
+    });
+    let date = DATE.get_or_init(|| {
+        Regex::new(r"time=['\x22](\d{4}-\d{2}-\d{2})['\x22]").expect("date pattern is valid")
+    });
+    let per_euro: HashMap<String, BigDecimal> = rate
+        .captures_iter(document)
+        .filter_map(|captures| {
+            let value = BigDecimal::from_str(&captures[2]).ok()?;
+            Some((captures[1].to_string(), value))
+        })
+        .collect();
+    let date = date
+        .captures(document)
+        .map(|captures| captures[1].to_string());
+    (!per_euro.is_empty()).then_some((per_euro, date))
+}
+
+fn load_rates(conn: &Connection) -> rusqlite::Result<Option<Rates>> {
+    let mut stmt =
+        conn.prepare("SELECT currency, per_euro, published, fetched_at FROM exchange_rates")?;
+    let mut rates: Option<Rates> = None;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (currency, per_euro, published, fetched_at) = row?;
+        let Ok(per_euro) = BigDecimal::from_str(&per_euro) else {
+            continue;
+        };
+        rates
+            .get_or_insert_with(|| Rates {
+                per_euro: HashMap::new(),
+                date: published,
+                fetched_at,
+            })
+            .per_euro
+            .insert(currency, per_euro);
+    }
+    Ok(rates)
+}
+
+fn store_rates(conn: &Connection, rates: &Rates) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM exchange_rates", [])?;
+    for (currency, per_euro) in &rates.per_euro {
+        conn.execute(
+            "INSERT INTO exchange_rates (currency, per_euro, published, fetched_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![currency, per_euro.to_string(), rates.date, rates.fetched_at],
+        )?;
+    }
+    Ok(())
+}
+
+async fn fetch_rates(app: &AppHandle) -> Result<Rates, GibberError> {
+    let client = network::client(app, Service::ExchangeRates)?;
+    let builder = client.get(ECB_RATES_URL).timeout(REQUEST_TIMEOUT);
+    let response =
+        network_activity::send(app, Service::ExchangeRates, "calculator.rates", builder).await?;
+    if !response.status().is_success() {
+        return Err(GibberError::new(
+            "NETWORK",
+            format!("The ECB returned HTTP {}", response.status()),
+        ));
+    }
+    let (per_euro, date) = parse_rates(&response.text().await?)
+        .ok_or_else(|| GibberError::new("PARSE_ERROR", "The ECB rates could not be read"))?;
+    Ok(Rates {
+        per_euro,
+        date,
+        fetched_at: db::now_millis(),
+    })
+}
+
+/// Returns the cached rates, fetching new ones when they are old. Old
+/// rates are returned, marked stale, if fetching fails.
+async fn rates(app: &AppHandle) -> Result<(Rates, bool), GibberError> {
+    let cached = load_rates(&app.state::<Database>().conn())?;
+    if let Some(rates) = &cached {
+        if db::now_millis() - rates.fetched_at < RATES_TTL_MS {
+            return Ok((rates.clone(), false));
+        }
+    }
+    match fetch_rates(app).await {
+        Ok(rates) => {
+            let db = app.state::<Database>();
+            let conn = db.conn();
+            let tx = conn.unchecked_transaction()?;
+            store_rates(&tx, &rates)?;
+            tx.commit()?;
+            Ok((rates, false))
+        }
+        Err(e) => match cached {
+            Some(rates) => {
+                tracing::warn!("using cached exchange rates: {e}");
+                Ok((rates, true))
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Tool definitions of `calculate` and `convert`, offered by
+/// `get_data_tools`.
+pub(crate) fn tools() -> Vec<PluginTool> {
+    vec![
+        PluginTool {
+            name: "calculate".to_string(),
+            description: "Evaluates an arithmetic expression exactly, e.g. (1.1 + 2.2) * 3^20 or \
+                          round(sqrt(2), 10). Use it for every calculation."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "expression": { "type": "string" } },
+                "required": ["expression"]
+            })
+            .to_string(),
+        },
+        PluginTool {
+            name: "convert".to_string(),
+            description: "Converts an amount between units (length, mass, time, volume, area, \
+                          speed, temperature, data, energy, pressure, frequency) or between \
+                          currencies by ISO code at the ECB reference rates."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "amount": { "type": "string", "description": "A number or expression" },
+                    "from": {
+                        "type": "string",
+                        "description": "Unit or currency, e.g. mi, °F, USD"
+                    },
+                    "to": { "type": "string" }
+                },
+                "required": ["amount", "from", "to"]
+            })
+            .to_string(),
+        },
+    ]
+}
+
+fn calculation(expression: &str) -> Result<Calculation, GibberError> {
+    let (value, approximate) = evaluate(expression)?;
+    Ok(Calculation {
+        result: format_decimal(&value, RESULT_SCALE),
+        approximate,
+    })
+}
+
+/// Evaluates an arithmetic expression with arbitrary precision.
+///
+/// Results are rounded to 30 fractional digits. Evaluation runs on a
+/// blocking thread.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_EXPRESSION` if the
+/// expression doesn't parse, divides by zero, has no real value, or would
+/// produce a result of more than 100,000 digits.
+///
+/// # Example
+///
+/// ```typescript
+/// const { result } = await invoke("calculate", { expression: "0.1 + 0.2" }); // "0.3"
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn calculate(expression: String) -> Result<Calculation, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || calculation(&expression))
+        .await
+        .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+/// Converts an amount between units or currencies.
+///
+/// `amount` may be an expression. Three-letter codes that aren't units
+/// are taken as currencies.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_EXPRESSION` if the amount
+/// doesn't evaluate, `UNKNOWN_UNIT` for units or currencies that aren't
+/// known, `INVALID_INPUT` for units of different dimensions, or a network
+/// error if exchange rates are needed and none could ever be fetched.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("convert_units", { amount: "26.2", from: "mi", to: "km" });
+/// await invoke("convert_units", { amount: "120", from: "USD", to: "EUR" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn convert_units(
+    app: AppHandle,
+    amount: String,
+    from: String,
+    to: String,
+) -> Result<Conversion, GibberError> {
+    let (amount, approximate) = evaluate(&amount)?;
+    let unknown = |name: &str| GibberError::new("UNKNOWN_UNIT", format!("Unknown unit \"{name}\""));
+    if let (Some(source), Some(target)) = (find_unit(&from), find_unit(&to)) {
+        if source.dimension != target.dimension {
+            return Err(GibberError::invalid(format!(
+                "{from} is a unit of {} and {to} of {}",
+                source.dimension, target.dimension
+            )));
+        }
+        return Ok(Conversion {
+            result: format_decimal(&convert(&amount, source, target), RESULT_SCALE),
+            unit: target.names[0].to_string(),
+            dimension: target.dimension.to_string(),
+            approximate,
+            rates_date: None,
+            stale_rates: false,
+        });
+    }
+    let source = currency_code(&from).ok_or_else(|| unknown(&from))?;
+    let target = currency_code(&to).ok_or_else(|| unknown(&to))?;
+    let (rates, stale_rates) = rates(&app).await?;
+    let source_rate = rates.rate(&source).ok_or_else(|| unknown(&from))?;
+    let target_rate = rates.rate(&target).ok_or_else(|| unknown(&to))?;
+    Ok(Conversion {
+        result: format_decimal(&(amount * target_rate / source_rate), CURRENCY_SCALE),
+        unit: target,
+        dimension: "currency".to_string(),
+        approximate,
+        rates_date: rates.date,
+        stale_rates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(expression: &str) -> String {
+        calculation(expression).unwrap().result
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        assert_eq!(calc("0.1 + 0.2"), "0.3");
+        assert_eq!(calc("2 ** 100"), "1267650600228229401496703205376");
+        assert_eq!(calc("-2^2 + (3 - 1) * 4 % 3"), "-2");
+        assert_eq!(calc("2^3^2"), "512");
+        assert_eq!(calc("1 / 3"), "0.333333333333333333333333333333");
+        assert_eq!(calc("10! / 5!"), "30240");
+        assert_eq!(
+            calc("round(2.5) + round(1.23456, 2) + max(1, 7, 3)"),
+            "11.23"
+        );
+        assert_eq!(calc("sqrt(16) + 1_000"), "1004");
+        assert!(calculation("sin(pi / 2)").unwrap().approximate);
+        for bad in ["1 / 0", "2 +", "foo(1)", "(1", "sqrt(-1)", "1 $ 2"] {
+            assert_eq!(
+                calculation(bad).unwrap_err().code(),
+                "INVALID_EXPRESSION",
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_oversized_results_are_refused() {
+        assert_eq!(calc("1e1000 / 1e999"), "10");
+        assert_eq!(calc("9^10000 / 9^9999"), "9");
+        for huge in [
+            "1e999999999",
+            "(9^10000)^10000",
+            "(10000!)^10000",
+            "10000! * 10000! * 10000!",
+            "round(1, 1000000000)",
+        ] {
+            assert_eq!(
+                calculation(huge).unwrap_err().code(),
+                "INVALID_EXPRESSION",
+                "{huge}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unit_conversion() {
+        let miles = find_unit("miles").unwrap();
+        let km = find_unit("km").unwrap();
+        assert_eq!(
+            format_decimal(&convert(&decimal("26.2"), miles, km), 30),
+            "42.1648128"
+        );
+        let fahrenheit = find_unit("°F").unwrap();
+        let celsius = find_unit("c").unwrap();
+        assert_eq!(
+            format_decimal(&convert(&decimal("212"), fahrenheit, celsius), 30),
+            "100"
+        );
+        assert_eq!(find_unit("B").unwrap().names[0], "B");
+        assert_eq!(find_unit("b").unwrap().names[0], "bit");
+        let bpm = find_unit("BPM").unwrap();
+        let hz = find_unit("hz").unwrap();
+        assert_eq!(format_decimal(&convert(&decimal("120"), bpm, hz), 30), "2");
+
+        let document = "<Cube time='2026-10-13'><Cube currency='USD' rate='1.0812'/>\
+                        <Cube currency='JPY' rate='162.5'/></Cube>";
+        let (per_euro, date) = parse_rates(document).unwrap();
+        assert_eq!(date.as_deref(), Some("2026-10-13"));
+        assert_eq!(per_euro["JPY"], decimal("162.5"));
+    }
+}
@@ -0,0 +1,417 @@
+//! Streamed chat completions for the webview.
+//!
+//! Fast models produce hundreds of tokens a second, and one IPC event per token
+//! floods the webview. `stream_chat` streams the reply in the backend and emits
+//! [`events::CHAT_DELTA`] with the text batched: a batch goes out after
+//! [`MIN_INTERVAL`] or once it holds [`MIN_CHARS`], whichever comes first.
+//!
+//! The frontend acknowledges batches with `ack_chat_stream`. When more
+//! than [`WINDOW`] batches are unacknowledged the webview is falling
+//! behind, so the interval and size double (up to [`MAX_INTERVAL`] and
+//! [`MAX_CHARS`]); once it catches up they halve back towards the minimum.
+//! A frontend that never acknowledges gets the largest batches.
+//!
+//! The text generated so far is saved as it streams, so a reply cut off by
+//! a quit, crash, or error can be picked up with `resume_chat_stream`; see
+//! [`stream_recovery`].
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole, TokenUsage};
+use crate::commands::events;
+use crate::commands::session;
+use crate::commands::stream_recovery;
+use crate::commands::usage::{self, UsageKind};
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Shortest time between batches.
+pub const MIN_INTERVAL: Duration = Duration::from_millis(30);
+
+/// Longest time between batches under backpressure.
+pub const MAX_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Batch size that flushes before the interval is up, in bytes.
+pub const MIN_CHARS: usize = 256;
+
+/// Largest batch size under backpressure, in bytes.
+pub const MAX_CHARS: usize = 4_096;
+
+/// Unacknowledged batches tolerated before backing off.
+pub const WINDOW: u64 = 4;
+
+/// Instruction appended when resuming a reply that was cut off.
+const CONTINUE_PROMPT: &str =
+    "Continue exactly where your previous reply stopped, without repeating any of it.";
+
+/// Batches acknowledged by the frontend, per stream.
+#[derive(Default)]
+pub struct StreamAcks(Mutex<HashMap<String, u64>>);
+
+impl StreamAcks {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, u64>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn acked(&self, stream_id: &str) -> u64 {
+        self.lock().get(stream_id).copied().unwrap_or(0)
+    }
+
+    /// Whether a stream is running.
+    pub fn contains(&self, stream_id: &str) -> bool {
+        self.lock().contains_key(stream_id)
+    }
+}
+
+/// What to send.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamChatRequest {
+    /// Model ID; the default model when `None`
+    #[serde(default)]
+    pub model: Option<String>,
+    /// The conversation so far
+    pub messages: Vec<ChatMessage>,
+    /// Maximum tokens to generate
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Temperature for randomness
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Conversation the request is for, checked against the routing policy
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+}
+
+/// Payload of [`events::CHAT_DELTA`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaPayload {
+    /// Stream the text belongs to
+    pub stream_id: String,
+    /// Batch number, from 1; pass it to `ack_chat_stream`
+    pub seq: u64,
+    /// Text since the previous batch
+    pub text: String,
+}
+
+/// The finished reply.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamedCompletion {
+    /// The whole generated text
+    pub content: String,
+    /// The model that served the request
+    pub model: String,
+    /// Token usage, when reported
+    pub usage: Option<TokenUsage>,
+    /// Cost in US dollars, when reported
+    pub cost_usd: Option<f64>,
+    /// Upstream provider, when reported
+    pub provider: Option<String>,
+    /// Batches emitted
+    pub batches: u64,
+}
+
+/// Collects deltas into batches and adapts their size to the webview.
+#[derive(Debug)]
+struct Batcher {
+    pending: String,
+    interval: Duration,
+    max_chars: usize,
+    last_flush: Instant,
+}
+
+impl Batcher {
+    fn new(now: Instant) -> Self {
+        Self {
+            pending: String::new(),
+            interval: MIN_INTERVAL,
+            max_chars: MIN_CHARS,
+            last_flush: now,
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        self.pending.push_str(text);
+    }
+
+    /// Whether the pending text should go out now.
+    fn due(&self, now: Instant) -> bool {
+        !self.pending.is_empty()
+            && (self.pending.len() >= self.max_chars
+                || now.duration_since(self.last_flush) >= self.interval)
+    }
+
+    /// Time left until the pending text is due by age.
+    fn wait(&self, now: Instant) -> Duration {
+        self.interval
+            .saturating_sub(now.duration_since(self.last_flush))
+    }
+
+    fn take(&mut self, now: Instant) -> String {
+        self.last_flush = now;
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Backs off while more than [`WINDOW`] batches are unacknowledged and
+    /// recovers once at most one is.
+    fn adapt(&mut self, in_flight: u64) {
+        if in_flight > WINDOW {
+            self.interval = (self.interval * 2).min(MAX_INTERVAL);
+            self.max_chars = (self.max_chars * 2).min(MAX_CHARS);
+        } else if in_flight <= 1 {
+            self.interval = (self.interval / 2).max(MIN_INTERVAL);
+            self.max_chars = (self.max_chars / 2).max(MIN_CHARS);
+        }
+    }
+}
+
+/// Emits batches of the text arriving on `deltas` until the sender is
+/// dropped. Returns the number of batches.
+async fn emit_batches(
+    app: AppHandle,
+    stream_id: String,
+    mut deltas: tokio::sync::mpsc::UnboundedReceiver<String>,
+) -> u64 {
+    let mut batcher = Batcher::new(Instant::now());
+    let mut unsaved = String::new();
+    let mut last_save = Instant::now();
+    let mut seq = 0;
+    let mut open = true;
+    while open || !batcher.pending.is_empty() {
+        if open {
+            let next = if batcher.pending.is_empty() {
+                Some(deltas.recv().await)
+            } else {
+                // A timeout just means the pending text is due.
+                tokio::time::timeout(batcher.wait(Instant::now()), deltas.recv())
+                    .await
+                    .ok()
+            };
+            match next {
+                Some(Some(text)) => batcher.push(&text),
+                Some(None) => open = false,
+                None => {}
+            }
+        }
+        let now = Instant::now();
+        if batcher.due(now) || (!open && !batcher.pending.is_empty()) {
+            seq += 1;
+            let payload = DeltaPayload {
+                stream_id: stream_id.clone(),
+                seq,
+                text: batcher.take(now),
+            };
+            events::emit_typed(&app, events::CHAT_DELTA, &payload);
+            unsaved.push_str(&payload.text);
+            if now.duration_since(last_save) >= stream_recovery::SAVE_INTERVAL {
+                let db = app.state::<Database>();
+                if let Err(e) = stream_recovery::append(&db.conn(), &stream_id, &unsaved) {
+                    tracing::warn!("failed to save partial reply of {stream_id}: {e}");
+                }
+                unsaved.clear();
+                last_save = now;
+            }
+            let acked = app.state::<StreamAcks>().acked(&stream_id);
+            batcher.adapt(seq.saturating_sub(acked));
+        }
+    }
+    seq
+}
+
+/// Streams a chat completion to the webview.
+///
+/// The text arrives in batches as [`events::CHAT_DELTA`] for `stream_id`; the
+/// returned completion holds all of it. Acknowledge each batch with
+/// `ack_chat_stream` so batches stay small while the webview keeps up.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with the codes of the backend chat client
+/// (`NO_API_KEY`, `RATE_LIMITED`, `BUDGET_EXCEEDED`, ...). Batches emitted
+/// before a failure stand.
+///
+/// # Example
+///
+/// ```typescript
+/// const streamId = crypto.randomUUID();
+/// const unlisten = await listen("chat://delta", ({ payload }) => {
+///   if (payload.streamId !== streamId) return;
+///   appendText(payload.text);
+///   invoke("ack_chat_stream", { streamId, seq: payload.seq });
+/// });
+/// const reply = await invoke("stream_chat", {
+///   streamId,
+///   request: { messages: [{ role: "user", content: "Write a chorus" }] },
+/// });
+/// unlisten();
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn stream_chat(
+    app: AppHandle,
+    stream_id: String,
+    request: StreamChatRequest,
+) -> Result<StreamedCompletion, GibberError> {
+    run(app, stream_id, request, String::new()).await
+}
+
+/// Resumes a stream that a quit or crash cut off.
+///
+/// The model is sent the original request, the partial reply, and an
+/// instruction to continue it. Only the new text arrives as
+/// [`events::CHAT_DELTA`] for `stream_id`; the returned completion starts
+/// with the partial reply.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the stream has no
+/// draft, `STREAM_RUNNING` if it is still running, or the codes of
+/// `stream_chat`.
+///
+/// # Example
+///
+/// ```typescript
+/// const [draft] = await invoke("list_interrupted_streams");
+/// const reply = await invoke("resume_chat_stream", { streamId: draft.streamId });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn resume_chat_stream(
+    app: AppHandle,
+    stream_id: String,
+) -> Result<StreamedCompletion, GibberError> {
+    if app.state::<StreamAcks>().contains(&stream_id) {
+        return Err(GibberError::new(
+            "STREAM_RUNNING",
+            format!("Stream {stream_id} is still running"),
+        ));
+    }
+    let draft = stream_recovery::load(&app.state::<Database>().conn(), &stream_id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("No draft for stream {stream_id}")))?;
+    run(app, stream_id, draft.request, draft.partial).await
+}
+
+/// Streams `request`, continuing from `partial` when it isn't empty.
+async fn run(
+    app: AppHandle,
+    stream_id: String,
+    request: StreamChatRequest,
+    partial: String,
+) -> Result<StreamedCompletion, GibberError> {
+    if let Err(e) = stream_recovery::begin(
+        &app.state::<Database>().conn(),
+        &stream_id,
+        &request,
+        &partial,
+    ) {
+        tracing::warn!("failed to record stream {stream_id}: {e}");
+    }
+    let mut messages = request.messages;
+    if !partial.is_empty() {
+        messages.push(ChatMessage::new(MessageRole::Assistant, partial.as_str()));
+        messages.push(ChatMessage::new(MessageRole::User, CONTINUE_PROMPT));
+    }
+    let mut chat_request = ChatRequest::new(request.model.as_deref(), messages);
+    if let Some(max_tokens) = request.max_tokens {
+        chat_request.max_tokens = max_tokens;
+    }
+    if let Some(temperature) = request.temperature {
+        chat_request.temperature = temperature;
+    }
+    chat_request.conversation_id = request.conversation_id;
+
+    app.state::<StreamAcks>()
+        .lock()
+        .insert(stream_id.clone(), 0);
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let emitter =
+        tauri::async_runtime::spawn(emit_batches(app.clone(), stream_id.clone(), receiver));
+    session::generation_started(&app, &stream_id, chat_request.conversation_id.as_deref());
+    let result = chat::stream(&app, &chat_request, &sender).await;
+    drop(sender);
+    let batches = emitter.await.unwrap_or_else(|e| {
+        tracing::error!("stream emitter failed: {e}");
+        0
+    });
+    app.state::<StreamAcks>().lock().remove(&stream_id);
+    session::generation_finished(&app, &stream_id);
+    // A failed stream keeps its draft so it can be resumed; one that
+    // finished or was cancelled has nothing left to resume.
+    let done = match &result {
+        Ok(_) => true,
+        Err(e) => e.code() == "CANCELLED",
+    };
+    if done {
+        if let Err(e) = stream_recovery::finish(&app.state::<Database>().conn(), &stream_id) {
+            tracing::warn!("failed to clear the draft of stream {stream_id}: {e}");
+        }
+    }
+
+    let completion = result?;
+    usage::record(&app, UsageKind::Model, chat_request.model.as_str());
+    Ok(StreamedCompletion {
+        content: partial + completion.content.as_str(),
+        model: completion.model,
+        usage: completion.usage,
+        cost_usd: completion.cost_usd,
+        provider: completion.provider,
+        batches,
+    })
+}
+
+/// Acknowledges that the webview has rendered batches of a stream up to
+/// `seq`.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn ack_chat_stream(acks: State<'_, StreamAcks>, stream_id: &str, seq: u64) {
+    if let Some(acked) = acks.lock().get_mut(stream_id) {
+        *acked = (*acked).max(seq);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_flush_by_size_or_age() {
+        let start = Instant::now();
+        let mut batcher = Batcher::new(start);
+        assert!(!batcher.due(start + MIN_INTERVAL));
+        batcher.push("Hey");
+        assert!(!batcher.due(start));
+        assert!(batcher.due(start + MIN_INTERVAL));
+        batcher.push(&"x".repeat(MIN_CHARS));
+        assert!(batcher.due(start));
+        assert_eq!(batcher.take(start).len(), MIN_CHARS + 3);
+        assert_eq!(
+            batcher.wait(start + Duration::from_millis(10)),
+            Duration::from_millis(20)
+        );
+    }
+
+    #[test]
+    fn test_backpressure_backs_off_and_recovers() {
+        let mut batcher = Batcher::new(Instant::now());
+        for _ in 0..10 {
+            batcher.adapt(WINDOW + 1);
+        }
+        assert_eq!(batcher.interval, MAX_INTERVAL);
+        assert_eq!(batcher.max_chars, MAX_CHARS);
+        batcher.adapt(WINDOW);
+        assert_eq!(batcher.interval, MAX_INTERVAL);
+        for _ in 0..10 {
+            batcher.adapt(1);
+        }
+        assert_eq!(batcher.interval, MIN_INTERVAL);
+        assert_eq!(batcher.max_chars, MIN_CHARS);
+    }
+}
@@ -0,0 +1,547 @@
+//! Opt-in clipboard watcher.
+//!
+//! When enabled, a background thread polls the clipboard for newly copied text
+//! or images. The newest capture is kept in memory only (never written to disk)
+//! and offered through [`events::CLIPBOARD_CAPTURED`], a notification, and the
+//! tray's "Ask about clipboard" item; asking turns it into a conversation.
+//!
+//! Privacy controls: the watcher is off by default, copies made while an
+//! excluded application is frontmost are ignored, and text that looks like
+//! a password or API key is skipped.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::conversations::{self, Conversation};
+use crate::commands::events;
+use crate::commands::i18n;
+use crate::commands::notifications::{self, NotificationKind};
+use crate::commands::quick_capture;
+use crate::commands::telemetry;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the clipboard preferences.
+pub const SETTINGS_KEY: &str = "clipboard";
+
+/// How often the clipboard is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Characters of copied text shown in previews.
+const PREVIEW_CHARS: usize = 120;
+
+/// Largest text accepted for a question, in characters.
+const MAX_TEXT_CHARS: usize = 32_000;
+
+/// Clipboard watcher preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardSettings {
+    /// Whether the watcher runs at all
+    pub enabled: bool,
+    /// Capture copied text
+    pub watch_text: bool,
+    /// Capture copied images
+    pub watch_images: bool,
+    /// Ignore text shorter than this many characters
+    pub min_text_chars: usize,
+    /// Application names whose copies are ignored (case-insensitive)
+    pub excluded_apps: Vec<String>,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            watch_text: true,
+            watch_images: false,
+            min_text_chars: 20,
+            excluded_apps: [
+                "1Password",
+                "Bitwarden",
+                "KeePassXC",
+                "Keychain Access",
+                "LastPass",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+impl ClipboardSettings {
+    fn excludes(&self, app_name: &str) -> bool {
+        self.excluded_apps
+            .iter()
+            .any(|excluded| excluded.eq_ignore_ascii_case(app_name))
+    }
+}
+
+/// What a question about the clipboard should do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ClipboardAction {
+    /// Explain the content
+    #[default]
+    Explain,
+    /// Summarize the content
+    Summarize,
+}
+
+impl ClipboardAction {
+    fn instruction(self) -> &'static str {
+        match self {
+            Self::Explain => "Explain the following",
+            Self::Summarize => "Summarize the following",
+        }
+    }
+}
+
+/// Content captured from the clipboard.
+#[derive(Clone)]
+enum Clip {
+    Text(String),
+    Image {
+        width: usize,
+        height: usize,
+        png: Vec<u8>,
+    },
+}
+
+/// Summary of a capture, as sent to the frontend.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CapturedClip {
+    /// Copied text
+    #[serde(rename_all = "camelCase")]
+    Text {
+        /// Start of the text
+        preview: String,
+        /// Length in characters
+        chars: usize,
+    },
+    /// Copied image
+    #[serde(rename_all = "camelCase")]
+    Image {
+        /// Width in pixels
+        width: usize,
+        /// Height in pixels
+        height: usize,
+    },
+}
+
+impl Clip {
+    fn summary(&self) -> CapturedClip {
+        match self {
+            Self::Text(text) => CapturedClip::Text {
+                preview: text.chars().take(PREVIEW_CHARS).collect(),
+                chars: text.chars().count(),
+            },
+            Self::Image { width, height, .. } => CapturedClip::Image {
+                width: *width,
+                height: *height,
+            },
+        }
+    }
+}
+
+/// Payload of [`events::CLIPBOARD_ASK`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AskPayload {
+    /// Conversation created for the question
+    pub conversation_id: String,
+    /// Copied image as a PNG data URL, for image captures
+    pub image_data_url: Option<String>,
+}
+
+#[derive(Default)]
+struct WatcherInner {
+    settings: ClipboardSettings,
+    latest: Option<Clip>,
+    last_hash: Option<u64>,
+}
+
+/// Watcher settings and the newest capture.
+#[derive(Default)]
+pub struct ClipboardWatcher(Mutex<WatcherInner>);
+
+impl ClipboardWatcher {
+    fn lock(&self) -> MutexGuard<'_, WatcherInner> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn hash_of(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns true for single tokens that look like passwords or API keys.
+fn looks_sensitive(text: &str) -> bool {
+    let text = text.trim();
+    if text.contains(char::is_whitespace) || text.contains("://") || text.len() < 16 {
+        return false;
+    }
+    let has_digit = text.chars().any(|c| c.is_ascii_digit());
+    let has_alpha = text.chars().any(|c| c.is_ascii_alphabetic());
+    has_digit && has_alpha
+}
+
+/// Name of the frontmost application, if it can be determined.
+fn frontmost_app() -> Option<String> {
+    active_win_pos_rs::get_active_window()
+        .ok()
+        .map(|window| window.app_name)
+}
+
+fn encode_png(image: &arboard::ImageData<'_>) -> Option<Vec<u8>> {
+    let buffer = image::RgbaImage::from_raw(
+        u32::try_from(image.width).ok()?,
+        u32::try_from(image.height).ok()?,
+        image.bytes.to_vec(),
+    )?;
+    let mut png = Cursor::new(Vec::new());
+    buffer.write_to(&mut png, image::ImageFormat::Png).ok()?;
+    Some(png.into_inner())
+}
+
+/// Reads the clipboard and returns content that passes the settings.
+fn read_clip(clipboard: &mut arboard::Clipboard, settings: &ClipboardSettings) -> Option<Clip> {
+    if settings.watch_text {
+        if let Ok(text) = clipboard.get_text() {
+            let text = text.trim().to_string();
+            if text.chars().count() >= settings.min_text_chars && !looks_sensitive(&text) {
+                return Some(Clip::Text(text));
+            }
+            return None;
+        }
+    }
+    if settings.watch_images {
+        if let Ok(image) = clipboard.get_image() {
+            return Some(Clip::Image {
+                width: image.width,
+                height: image.height,
+                png: encode_png(&image)?,
+            });
+        }
+    }
+    None
+}
+
+fn clip_hash(clip: &Clip) -> u64 {
+    match clip {
+        Clip::Text(text) => hash_of(text.as_bytes()),
+        Clip::Image { png, .. } => hash_of(png),
+    }
+}
+
+/// Polls the clipboard once and offers new content.
+fn poll(app: &AppHandle, clipboard: &mut arboard::Clipboard) {
+    let watcher = app.state::<ClipboardWatcher>();
+    let settings = watcher.lock().settings.clone();
+    if !settings.enabled {
+        return;
+    }
+    let Some(clip) = read_clip(clipboard, &settings) else {
+        return;
+    };
+    let hash = clip_hash(&clip);
+    {
+        let mut inner = watcher.lock();
+        if inner.last_hash == Some(hash) {
+            return;
+        }
+        inner.last_hash = Some(hash);
+    }
+    if frontmost_app().is_some_and(|name| settings.excludes(&name)) {
+        return;
+    }
+
+    let summary = clip.summary();
+    watcher.lock().latest = Some(clip);
+    events::emit_typed(app, events::CLIPBOARD_CAPTURED, &summary);
+    let body = match &summary {
+        CapturedClip::Text { preview, .. } => preview.clone(),
+        CapturedClip::Image { width, height } => i18n::text_with(
+            app,
+            "notification-clipboard-image",
+            &[
+                ("width", &width.to_string()),
+                ("height", &height.to_string()),
+            ],
+        ),
+    };
+    notifications::notify(
+        app,
+        NotificationKind::Clipboard,
+        &i18n::text(app, "notification-clipboard-title"),
+        &body,
+        None,
+    );
+}
+
+/// Loads the settings and starts the polling thread.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+pub fn start(app: &AppHandle) {
+    let settings = db::read_setting(&app.state::<Database>().conn(), SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default();
+    app.manage(ClipboardWatcher(Mutex::new(WatcherInner {
+        settings,
+        ..WatcherInner::default()
+    })));
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut clipboard = match arboard::Clipboard::new() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                tracing::error!("watcher unavailable: {e}");
+                return;
+            }
+        };
+        loop {
+            poll(&app, &mut clipboard);
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+/// Builds the question text for a capture.
+fn question(clip: &Clip, action: ClipboardAction) -> Result<String, GibberError> {
+    match clip {
+        Clip::Text(text) => {
+            if text.chars().count() > MAX_TEXT_CHARS {
+                return Err(GibberError::new(
+                    "TOO_LARGE",
+                    format!("Copied text exceeds {MAX_TEXT_CHARS} characters"),
+                ));
+            }
+            Ok(format!("{}:\n\n{text}", action.instruction()))
+        }
+        Clip::Image { .. } => Ok(format!("{} image.", action.instruction())),
+    }
+}
+
+/// Takes the newest capture if it is text.
+pub(crate) fn take_text(app: &AppHandle) -> Option<String> {
+    let watcher = app.state::<ClipboardWatcher>();
+    let mut inner = watcher.lock();
+    match inner.latest.take() {
+        Some(Clip::Text(text)) => Some(text),
+        other => {
+            inner.latest = other;
+            None
+        }
+    }
+}
+
+/// Copies `text` to the clipboard without offering it as a new capture.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `CLIPBOARD` if the clipboard can't
+/// be written.
+pub(crate) fn write_text(app: &AppHandle, text: &str) -> Result<(), GibberError> {
+    app.state::<ClipboardWatcher>().lock().last_hash = Some(hash_of(text.trim().as_bytes()));
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| GibberError::new("CLIPBOARD", e.to_string()))
+}
+
+/// Turns the newest capture into a conversation and hands it to the main window.
+///
+/// Used by the tray item and by [`ask_about_clipboard`]. The capture is
+/// cleared once asked about.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `EMPTY` if nothing has been
+/// captured, `TOO_LARGE` for oversized text, or `DATABASE` if the
+/// conversation cannot be stored.
+pub fn ask(app: &AppHandle, action: ClipboardAction) -> Result<Conversation, GibberError> {
+    use base64::Engine as _;
+
+    let clip = app
+        .state::<ClipboardWatcher>()
+        .lock()
+        .latest
+        .take()
+        .ok_or_else(|| GibberError::new("EMPTY", "Nothing has been captured"))?;
+    let prompt = question(&clip, action)?;
+    telemetry::record_feature(app, "clipboard.ask");
+    let conversation = conversations::insert_prompt_conversation(
+        &app.state::<Database>().conn(),
+        &prompt,
+        None,
+        "clipboard",
+    )?;
+    let image_data_url = match &clip {
+        Clip::Image { png, .. } => Some(format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        )),
+        Clip::Text(_) => None,
+    };
+
+    quick_capture::focus_main_window(app)?;
+    events::emit_typed_to(
+        app,
+        quick_capture::MAIN_WINDOW_LABEL,
+        events::CLIPBOARD_ASK,
+        &AskPayload {
+            conversation_id: conversation.id.clone(),
+            image_data_url,
+        },
+    )?;
+    Ok(conversation)
+}
+
+/// Stores the watcher preferences and applies them immediately.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+pub fn save_settings(
+    app: &AppHandle,
+    settings: ClipboardSettings,
+) -> Result<ClipboardSettings, GibberError> {
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&app.state::<Database>().conn(), SETTINGS_KEY, &value)?;
+    let watcher = app.state::<ClipboardWatcher>();
+    let mut inner = watcher.lock();
+    if !settings.enabled {
+        inner.latest = None;
+    }
+    inner.settings = settings.clone();
+    Ok(settings)
+}
+
+/// Returns the clipboard watcher preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_clipboard_settings(watcher: State<'_, ClipboardWatcher>) -> ClipboardSettings {
+    watcher.lock().settings.clone()
+}
+
+/// Replaces the clipboard watcher preferences; applies immediately.
+///
+/// Disabling the watcher also drops the current capture.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_clipboard_settings", { settings: { ...current, enabled: true } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn set_clipboard_settings(
+    app: AppHandle,
+    settings: ClipboardSettings,
+) -> Result<ClipboardSettings, GibberError> {
+    save_settings(&app, settings)
+}
+
+/// Returns a summary of the newest capture, if any.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_clipboard_capture(watcher: State<'_, ClipboardWatcher>) -> Option<CapturedClip> {
+    watcher.lock().latest.as_ref().map(Clip::summary)
+}
+
+/// Drops the newest capture without asking about it.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn clear_clipboard_capture(watcher: State<'_, ClipboardWatcher>) {
+    watcher.lock().latest = None;
+}
+
+/// Asks Gibber about the newest capture.
+///
+/// # Errors
+///
+/// See [`ask`].
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("ask_about_clipboard", { action: "summarize" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn ask_about_clipboard(
+    app: AppHandle,
+    action: Option<ClipboardAction>,
+) -> Result<Conversation, GibberError> {
+    ask(&app, action.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_opt_in() {
+        let settings = ClipboardSettings::default();
+        assert!(!settings.enabled);
+        assert!(settings.excludes("bitwarden"));
+        assert!(!settings.excludes("Terminal"));
+    }
+
+    #[test]
+    fn test_looks_sensitive() {
+        assert!(looks_sensitive("sk-or-v1-3f9a8b7c6d5e4f3a2b1c"));
+        assert!(looks_sensitive("hunter2hunter2hunter2"));
+        assert!(!looks_sensitive("a sentence with several words 123"));
+        assert!(!looks_sensitive("https://example.com/page?id=12345"));
+    }
+
+    #[test]
+    fn test_question_includes_text() {
+        let clip = Clip::Text("fn main() {}".to_string());
+        assert_eq!(
+            question(&clip, ClipboardAction::Summarize).unwrap(),
+            "Summarize the following:\n\nfn main() {}"
+        );
+        let huge = Clip::Text("x".repeat(MAX_TEXT_CHARS + 1));
+        assert_eq!(
+            question(&huge, ClipboardAction::Explain)
+                .unwrap_err()
+                .code(),
+            "TOO_LARGE"
+        );
+    }
+
+    #[test]
+    fn test_summary_truncates_preview() {
+        let clip = Clip::Text("y".repeat(500));
+        match clip.summary() {
+            CapturedClip::Text { preview, chars } => {
+                assert_eq!(preview.chars().count(), PREVIEW_CHARS);
+                assert_eq!(chars, 500);
+            }
+            CapturedClip::Image { .. } => panic!("Expected text"),
+        }
+    }
+}
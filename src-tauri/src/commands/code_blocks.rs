@@ -0,0 +1,208 @@
+//! Code blocks in messages.
+//!
+//! Fenced code blocks are listed with their language and the file
+//! extension that fits it, so the UI can offer copy and save buttons per
+//! block. Saving goes through the native save dialog; the file name
+//! suggested there already carries the right extension.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::commands::conversations;
+use crate::commands::markdown;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Extension used when the language is unknown or missing.
+const DEFAULT_EXTENSION: &str = "txt";
+
+/// Languages whose extension syntect doesn't know or gets wrong.
+const EXTENSIONS: [(&str, &str); 24] = [
+    ("bash", "sh"),
+    ("shell", "sh"),
+    ("sh", "sh"),
+    ("zsh", "zsh"),
+    ("powershell", "ps1"),
+    ("ps1", "ps1"),
+    ("typescript", "ts"),
+    ("ts", "ts"),
+    ("tsx", "tsx"),
+    ("jsx", "jsx"),
+    ("javascript", "js"),
+    ("js", "js"),
+    ("json", "json"),
+    ("toml", "toml"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("dockerfile", "Dockerfile"),
+    ("kotlin", "kt"),
+    ("swift", "swift"),
+    ("vue", "vue"),
+    ("svelte", "svelte"),
+    ("mermaid", "mmd"),
+    ("plantuml", "puml"),
+    ("text", "txt"),
+];
+
+/// A fenced code block of a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeBlock {
+    /// Position among the message's code blocks, from 0
+    pub index: usize,
+    /// Language from the fence info string, if any
+    pub language: Option<String>,
+    /// The code
+    pub code: String,
+    /// File extension for the language, without the dot
+    pub extension: String,
+}
+
+/// Infers a file extension from a code block language.
+pub fn extension_for(language: &str) -> String {
+    let language = language.to_ascii_lowercase();
+    if language.is_empty() {
+        return DEFAULT_EXTENSION.to_string();
+    }
+    if let Some((_, extension)) = EXTENSIONS.iter().find(|(name, _)| *name == language) {
+        return (*extension).to_string();
+    }
+    markdown::syntax_set()
+        .find_syntax_by_token(&language)
+        .and_then(|syntax| syntax.file_extensions.first())
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_EXTENSION.to_string())
+}
+
+/// Lists the fenced code blocks of a markdown document.
+pub fn code_blocks(markdown: &str) -> Vec<CodeBlock> {
+    markdown::fenced_blocks(markdown)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (language, code))| CodeBlock {
+            index,
+            extension: extension_for(&language),
+            language: (!language.is_empty()).then_some(language),
+            code,
+        })
+        .collect()
+}
+
+fn message_blocks(conn: &Connection, message_id: &str) -> Result<Vec<CodeBlock>, GibberError> {
+    let message = conversations::load_message(conn, message_id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Message {message_id} not found")))?;
+    Ok(code_blocks(&message.content))
+}
+
+/// Lists the fenced code blocks of a stored message.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the message doesn't
+/// exist, or `DATABASE` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const blocks = await invoke("extract_code_blocks", { messageId });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn extract_code_blocks(
+    db: State<'_, Database>,
+    message_id: &str,
+) -> Result<Vec<CodeBlock>, GibberError> {
+    message_blocks(&db.conn(), message_id)
+}
+
+/// Asks where to save a code block of a message and writes it there.
+///
+/// # Returns
+///
+/// The path written, or `null` if the dialog was cancelled.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the message or block
+/// doesn't exist, `DIALOG` if the dialog fails, or `IO` if the file can't
+/// be written.
+///
+/// # Example
+///
+/// ```typescript
+/// const path = await invoke("save_code_block", { messageId, index: 0 });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn save_code_block(
+    app: AppHandle,
+    message_id: String,
+    index: usize,
+) -> Result<Option<String>, GibberError> {
+    let block = message_blocks(&app.state::<Database>().conn(), &message_id)?
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("No code block {index}")))?;
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let filter = block.language.as_deref().unwrap_or("Text").to_string();
+    app.dialog()
+        .file()
+        .set_file_name(format!("snippet-{}.{}", index + 1, block.extension))
+        .add_filter(filter, &[block.extension.as_str()])
+        .save_file(move |path| {
+            let _ = sender.send(path);
+        });
+    let Some(path) = receiver
+        .await
+        .map_err(|e| GibberError::new("DIALOG", e.to_string()))?
+    else {
+        return Ok(None);
+    };
+    let path = path
+        .into_path()
+        .map_err(|e| GibberError::new("DIALOG", e.to_string()))?;
+    std::fs::write(&path, &block.code).map_err(|e| GibberError::new("IO", e.to_string()))?;
+    tracing::info!(path = %path.display(), "saved code block");
+    Ok(Some(path.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_for() {
+        assert_eq!(extension_for("rust"), "rs");
+        assert_eq!(extension_for("Python"), "py");
+        assert_eq!(extension_for("ts"), "ts");
+        assert_eq!(extension_for("bash"), "sh");
+        assert_eq!(extension_for(""), "txt");
+        assert_eq!(extension_for("no-such-language"), "txt");
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let blocks = code_blocks("Intro\n\n```rust\nfn main() {}\n```\n\n```\nplain\n```\n");
+        assert_eq!(
+            blocks,
+            vec![
+                CodeBlock {
+                    index: 0,
+                    language: Some("rust".to_string()),
+                    code: "fn main() {}\n".to_string(),
+                    extension: "rs".to_string(),
+                },
+                CodeBlock {
+                    index: 1,
+                    language: None,
+                    code: "plain\n".to_string(),
+                    extension: "txt".to_string(),
+                },
+            ]
+        );
+    }
+}
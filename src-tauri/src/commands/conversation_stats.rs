@@ -0,0 +1,253 @@
+//! Statistics for the conversation info panel.
+//!
+//! Everything is aggregated in SQL, so long conversations cost a handful
+//! of queries rather than loading their messages. Token counts come from
+//! the messages, costs from the spend ledger, and gaps are the pauses
+//! between consecutive messages.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::State;
+
+use crate::commands::conversations;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Number of gaps reported.
+const LONGEST_GAPS: u32 = 3;
+
+/// Use of one model within a conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelUsage {
+    /// Model ID
+    pub model: String,
+    /// Messages it generated
+    pub messages: u32,
+}
+
+/// A pause between two consecutive messages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageGap {
+    /// Message before the pause
+    pub after_message_id: String,
+    /// Message after the pause
+    pub before_message_id: String,
+    /// Length of the pause in milliseconds
+    pub gap_ms: i64,
+}
+
+/// What `get_conversation_stats` returns.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationStats {
+    /// The conversation
+    pub conversation_id: String,
+    /// All messages
+    pub messages: u32,
+    /// Messages written by the user
+    pub user_messages: u32,
+    /// Messages generated by a model
+    pub assistant_messages: u32,
+    /// Prompt tokens over all messages
+    pub prompt_tokens: u64,
+    /// Completion tokens over all messages
+    pub completion_tokens: u64,
+    /// Cost in US dollars recorded for the conversation
+    pub cost_usd: f64,
+    /// Models used, most used first
+    pub models: Vec<ModelUsage>,
+    /// Time of the first message in Unix milliseconds
+    pub first_message_at: Option<i64>,
+    /// Time of the last message in Unix milliseconds
+    pub last_message_at: Option<i64>,
+    /// Milliseconds from the first message to the last
+    pub duration_ms: i64,
+    /// Longest pauses, longest first
+    pub longest_gaps: Vec<MessageGap>,
+}
+
+fn stats(conn: &Connection, conversation_id: &str) -> rusqlite::Result<ConversationStats> {
+    let (
+        messages,
+        user_messages,
+        assistant_messages,
+        prompt_tokens,
+        completion_tokens,
+        first,
+        last,
+    ) = conn.query_row(
+        "SELECT COUNT(*),
+                COALESCE(SUM(role = 'user'), 0),
+                COALESCE(SUM(role = 'assistant'), 0),
+                COALESCE(SUM(prompt_tokens), 0),
+                COALESCE(SUM(completion_tokens), 0),
+                MIN(created_at),
+                MAX(created_at)
+         FROM messages WHERE conversation_id = ?1",
+        [conversation_id],
+        |row| {
+            Ok((
+                row.get::<_, u32>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, u64>(3)?,
+                row.get::<_, u64>(4)?,
+                row.get::<_, Option<i64>>(5)?,
+                row.get::<_, Option<i64>>(6)?,
+            ))
+        },
+    )?;
+    let cost_usd = conn.query_row(
+        "SELECT COALESCE(SUM(cost_usd), 0.0) FROM spend_ledger WHERE conversation_id = ?1",
+        [conversation_id],
+        |row| row.get(0),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT model, COUNT(*) FROM messages
+         WHERE conversation_id = ?1 AND model IS NOT NULL
+         GROUP BY model ORDER BY COUNT(*) DESC, model",
+    )?;
+    let models = stmt
+        .query_map([conversation_id], |row| {
+            Ok(ModelUsage {
+                model: row.get(0)?,
+                messages: row.get(1)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT previous_id, id, created_at - previous_at AS gap FROM (
+             SELECT id, created_at,
+                    LAG(id) OVER timeline AS previous_id,
+                    LAG(created_at) OVER timeline AS previous_at
+             FROM messages WHERE conversation_id = ?1
+             WINDOW timeline AS (ORDER BY created_at, rowid)
+         )
+         WHERE previous_id IS NOT NULL
+         ORDER BY gap DESC LIMIT ?2",
+    )?;
+    let longest_gaps = stmt
+        .query_map(params![conversation_id, LONGEST_GAPS], |row| {
+            Ok(MessageGap {
+                after_message_id: row.get(0)?,
+                before_message_id: row.get(1)?,
+                gap_ms: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(ConversationStats {
+        conversation_id: conversation_id.to_string(),
+        messages,
+        user_messages,
+        assistant_messages,
+        prompt_tokens,
+        completion_tokens,
+        cost_usd,
+        models,
+        first_message_at: first,
+        last_message_at: last,
+        duration_ms: first.zip(last).map_or(0, |(first, last)| last - first),
+        longest_gaps,
+    })
+}
+
+/// Returns statistics for a conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, or `DATABASE` if a query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const stats = await invoke("get_conversation_stats", { id });
+/// console.log(`${stats.messages} messages, $${stats.costUsd.toFixed(2)}`);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_conversation_stats(
+    db: State<'_, Database>,
+    id: &str,
+) -> Result<ConversationStats, GibberError> {
+    let conn = db.conn();
+    if !conversations::conversation_exists(&conn, id)? {
+        return Err(GibberError::new(
+            "NOT_FOUND",
+            format!("Conversation {id} not found"),
+        ));
+    }
+    Ok(stats(&conn, id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::conversations::NewConversation;
+
+    fn conversation(conn: &Connection) -> String {
+        let input = NewConversation {
+            title: "Jam".to_string(),
+            model: None,
+            system_prompt: None,
+            source: None,
+        };
+        conversations::insert_conversation(conn, &input).unwrap().id
+    }
+
+    fn message(conn: &Connection, conversation_id: &str, id: &str, role: &str, at: i64) {
+        let model = (role == "assistant").then_some("openai/gpt-4o");
+        conn.execute(
+            "INSERT INTO messages (id, conversation_id, role, content, model, prompt_tokens, completion_tokens, created_at)
+             VALUES (?1, ?2, ?3, '', ?4, 10, 20, ?5)",
+            params![id, conversation_id, role, model, at],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_stats_aggregate_messages_and_spend() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let id = conversation(&conn);
+        message(&conn, &id, "a", "user", 1_000);
+        message(&conn, &id, "b", "assistant", 2_000);
+        message(&conn, &id, "c", "user", 62_000);
+        message(&conn, &id, "d", "assistant", 63_000);
+        conn.execute(
+            "INSERT INTO spend_ledger (provider, model, conversation_id, cost_usd, created_at)
+             VALUES ('openai', 'openai/gpt-4o', ?1, 0.25, 0)",
+            [&id],
+        )
+        .unwrap();
+
+        let stats = stats(&conn, &id).unwrap();
+        assert_eq!(stats.messages, 4);
+        assert_eq!(stats.user_messages, 2);
+        assert_eq!(stats.completion_tokens, 80);
+        assert!((stats.cost_usd - 0.25).abs() < f64::EPSILON);
+        assert_eq!(stats.models[0].messages, 2);
+        assert_eq!(stats.duration_ms, 62_000);
+        assert_eq!(stats.longest_gaps.len(), 3);
+        assert_eq!(stats.longest_gaps[0].after_message_id, "b");
+        assert_eq!(stats.longest_gaps[0].gap_ms, 60_000);
+    }
+
+    #[test]
+    fn test_empty_conversation_has_zero_stats() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let id = conversation(&conn);
+        let stats = stats(&conn, &id).unwrap();
+        assert_eq!(stats.messages, 0);
+        assert_eq!(stats.duration_ms, 0);
+        assert!(stats.first_message_at.is_none());
+        assert!(stats.longest_gaps.is_empty());
+    }
+}
@@ -0,0 +1,1062 @@
+//! Conversation storage commands.
+//!
+//! Conversations and their messages are persisted in the application
+//! database so that results produced in the background (for example by
+//! scheduled prompts) survive restarts and can be opened from the UI.
+//!
+//! Conversations export as JSON, which imports back, or as a standalone
+//! HTML page with highlighted code, LaTeX rendered as MathML, and diagrams
+//! rendered as SVG (see [`diagrams`]). PDFs come from printing the HTML
+//! page, which has print styles for that.
+
+use std::fmt::Write as _;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{MessageRole, TokenUsage};
+use crate::commands::deidentify::{self, DeidentifyOptions};
+use crate::commands::diagrams;
+use crate::commands::events;
+use crate::commands::experiments;
+use crate::commands::markdown::{self, Diagrams, RenderOptions};
+use crate::commands::network::{self, Service};
+use crate::commands::settings;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Value of the `format` field of exported conversations.
+pub const EXPORT_FORMAT: &str = "gibber-ai-conversation";
+
+/// Version of the conversation export format.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Layout of the page in HTML exports; highlighting rules are appended.
+const EXPORT_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; line-height: 1.5; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+.meta { color: #59636e; font-size: 0.875rem; }
+.message { margin: 1rem 0; padding: 0.75rem 1rem; border-radius: 0.5rem; break-inside: avoid; }
+.message.user { background: #f6f8fa; }
+.message.system { border: 1px dashed #d0d7de; }
+.role { font-weight: 600; font-size: 0.75rem; text-transform: uppercase; color: #59636e; }
+pre { overflow-x: auto; padding: 0.75rem; background: #f6f8fa; border-radius: 0.375rem; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d7de; padding: 0.25rem 0.5rem; }
+math[display=\"block\"] { margin: 0.75rem 0; overflow-x: auto; }
+figure.diagram { margin: 0.75rem 0; text-align: center; }
+figure.diagram img { max-width: 100%; }
+@media print { body { margin: 0; max-width: none; } pre { white-space: pre-wrap; } }
+";
+
+/// File formats a conversation exports to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// The JSON export file, which can be imported again
+    #[default]
+    Json,
+    /// A standalone HTML page for reading, sharing or printing to PDF
+    Html,
+}
+
+impl ExportFormat {
+    /// Parses a format name as used on the command line.
+    #[cfg(feature = "cli")]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Conversation {id} not found"))
+}
+
+/// Summary of a stored conversation.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversation {
+    /// Unique identifier
+    pub id: String,
+    /// Display title
+    pub title: String,
+    /// Model used by default for this conversation
+    pub model: Option<String>,
+    /// System prompt for this conversation
+    pub system_prompt: Option<String>,
+    /// What created the conversation (e.g. "chat", "scheduler")
+    pub source: String,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// Last update time in Unix milliseconds
+    pub updated_at: i64,
+}
+
+/// A stored message.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredMessage {
+    /// Unique identifier
+    pub id: String,
+    /// Owning conversation
+    pub conversation_id: String,
+    /// The role of the message sender
+    pub role: MessageRole,
+    /// The message content
+    pub content: String,
+    /// Model that generated the message (assistant messages only)
+    pub model: Option<String>,
+    /// Prompt tokens consumed, when known
+    pub prompt_tokens: Option<u32>,
+    /// Completion tokens generated, when known
+    pub completion_tokens: Option<u32>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// A page of a conversation's messages, from [`get_messages`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePage {
+    /// Messages in chronological order
+    pub messages: Vec<StoredMessage>,
+    /// Cursor for the page of older messages; `None` on the first page
+    pub next_cursor: Option<String>,
+    /// Messages in the whole conversation
+    pub total: u32,
+}
+
+/// A conversation with all of its messages.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationWithMessages {
+    /// The conversation metadata
+    #[serde(flatten)]
+    pub conversation: Conversation,
+    /// Messages in chronological order
+    pub messages: Vec<StoredMessage>,
+}
+
+/// Fields for creating a conversation.
+#[derive(Debug, Clone, Default, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NewConversation {
+    /// Display title
+    pub title: String,
+    /// Default model
+    #[serde(default)]
+    pub model: Option<String>,
+    /// System prompt
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Origin of the conversation; defaults to "chat"
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Fields for appending a message.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NewMessage {
+    /// The role of the message sender
+    pub role: MessageRole,
+    /// The message content
+    pub content: String,
+    /// Model that generated the message
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Token usage for the message
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+}
+
+const CONVERSATION_COLUMNS: &str =
+    "id, title, model, system_prompt, source, created_at, updated_at";
+const MESSAGE_COLUMNS: &str =
+    "id, conversation_id, role, content, model, prompt_tokens, completion_tokens, created_at";
+
+fn conversation_from_row(row: &Row<'_>) -> rusqlite::Result<Conversation> {
+    Ok(Conversation {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        model: row.get(2)?,
+        system_prompt: row.get(3)?,
+        source: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn message_from_row(row: &Row<'_>) -> rusqlite::Result<StoredMessage> {
+    let role: String = row.get(2)?;
+    Ok(StoredMessage {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        role: MessageRole::parse(&role).unwrap_or(MessageRole::User),
+        content: row.get(3)?,
+        model: row.get(4)?,
+        prompt_tokens: row.get(5)?,
+        completion_tokens: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+/// Messages per page of [`get_messages`] unless a limit is given.
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Largest page [`get_messages`] returns.
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Maximum length of a title derived from a prompt, in characters.
+const MAX_DERIVED_TITLE_CHARS: usize = 60;
+
+/// Derives a conversation title from the first line of a prompt.
+pub(crate) fn title_from_prompt(prompt: &str) -> String {
+    let first_line = prompt.trim().lines().next().unwrap_or_default().trim();
+    if first_line.chars().count() > MAX_DERIVED_TITLE_CHARS {
+        let truncated: String = first_line.chars().take(MAX_DERIVED_TITLE_CHARS).collect();
+        format!("{}…", truncated.trim_end())
+    } else if first_line.is_empty() {
+        "New conversation".to_string()
+    } else {
+        first_line.to_string()
+    }
+}
+
+/// Inserts a new conversation.
+///
+/// # Errors
+///
+/// Returns an error if the insert fails.
+pub(crate) fn insert_conversation(
+    conn: &Connection,
+    input: &NewConversation,
+) -> rusqlite::Result<Conversation> {
+    let now = db::now_millis();
+    let conversation = Conversation {
+        id: db::new_id(),
+        title: input.title.clone(),
+        model: input.model.clone(),
+        system_prompt: input.system_prompt.clone(),
+        source: input.source.clone().unwrap_or_else(|| "chat".to_string()),
+        created_at: now,
+        updated_at: now,
+    };
+    conn.execute(
+        "INSERT INTO conversations (id, title, model, system_prompt, source, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            conversation.id,
+            conversation.title,
+            conversation.model,
+            conversation.system_prompt,
+            conversation.source,
+            conversation.created_at,
+            conversation.updated_at,
+        ],
+    )?;
+    Ok(conversation)
+}
+
+/// Appends a message to a conversation and bumps its `updated_at`.
+///
+/// # Errors
+///
+/// Returns an error if the conversation does not exist or the insert fails.
+pub(crate) fn insert_message(
+    conn: &Connection,
+    conversation_id: &str,
+    input: &NewMessage,
+) -> rusqlite::Result<StoredMessage> {
+    let message = StoredMessage {
+        id: db::new_id(),
+        conversation_id: conversation_id.to_string(),
+        role: input.role,
+        content: input.content.clone(),
+        model: input.model.clone(),
+        prompt_tokens: input.usage.map(|u| u.prompt_tokens),
+        completion_tokens: input.usage.map(|u| u.completion_tokens),
+        created_at: db::now_millis(),
+    };
+    conn.execute(
+        "INSERT INTO messages (id, conversation_id, role, content, model, prompt_tokens, completion_tokens, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            message.id,
+            message.conversation_id,
+            message.role.as_str(),
+            message.content,
+            message.model,
+            message.prompt_tokens,
+            message.completion_tokens,
+            message.created_at,
+        ],
+    )?;
+    conn.execute(
+        "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+        params![message.created_at, conversation_id],
+    )?;
+    Ok(message)
+}
+
+/// Creates a conversation whose first message is `prompt`.
+///
+/// Used by entry points outside the chat view (quick capture, deep links)
+/// that hand the main window a conversation ready to be answered.
+///
+/// # Errors
+///
+/// Returns an error if an insert fails.
+pub(crate) fn insert_prompt_conversation(
+    conn: &Connection,
+    prompt: &str,
+    model: Option<&str>,
+    source: &str,
+) -> rusqlite::Result<Conversation> {
+    let conversation = insert_conversation(
+        conn,
+        &NewConversation {
+            title: title_from_prompt(prompt),
+            model: model.map(str::to_string),
+            system_prompt: None,
+            source: Some(source.to_string()),
+        },
+    )?;
+    insert_message(
+        conn,
+        &conversation.id,
+        &NewMessage {
+            role: MessageRole::User,
+            content: prompt.to_string(),
+            model: None,
+            usage: None,
+        },
+    )?;
+    Ok(conversation)
+}
+
+/// Checks whether a conversation exists.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub(crate) fn conversation_exists(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    let exists: Option<i64> = conn
+        .query_row("SELECT 1 FROM conversations WHERE id = ?1", [id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    Ok(exists.is_some())
+}
+
+/// Loads a conversation and its messages.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub(crate) fn load_conversation(
+    conn: &Connection,
+    id: &str,
+) -> rusqlite::Result<Option<ConversationWithMessages>> {
+    let Some(conversation) = conn
+        .query_row(
+            &format!("SELECT {CONVERSATION_COLUMNS} FROM conversations WHERE id = ?1"),
+            [id],
+            conversation_from_row,
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {MESSAGE_COLUMNS} FROM messages WHERE conversation_id = ?1 ORDER BY created_at, rowid"
+    ))?;
+    let messages = stmt
+        .query_map([id], message_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Some(ConversationWithMessages {
+        conversation,
+        messages,
+    }))
+}
+
+/// Builds a `LIKE ... ESCAPE '\'` pattern matching `query` anywhere, with
+/// `%`, `_`, and `\` taken literally.
+pub(crate) fn like_pattern(query: &str) -> String {
+    let mut pattern = String::with_capacity(query.len() + 2);
+    pattern.push('%');
+    for c in query.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Finds conversations whose title or any message contains `query`,
+/// ignoring ASCII case, most recently updated first.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub(crate) fn search(
+    conn: &Connection,
+    query: &str,
+    limit: u32,
+) -> rusqlite::Result<Vec<Conversation>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CONVERSATION_COLUMNS} FROM conversations c
+         WHERE c.title LIKE ?1 ESCAPE '\\'
+            OR EXISTS (SELECT 1 FROM messages m
+                       WHERE m.conversation_id = c.id AND m.content LIKE ?1 ESCAPE '\\')
+         ORDER BY c.updated_at DESC LIMIT ?2"
+    ))?;
+    let conversations = stmt
+        .query_map(params![like_pattern(query), limit], conversation_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(conversations)
+}
+
+/// Loads one message.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub(crate) fn load_message(conn: &Connection, id: &str) -> rusqlite::Result<Option<StoredMessage>> {
+    conn.query_row(
+        &format!("SELECT {MESSAGE_COLUMNS} FROM messages WHERE id = ?1"),
+        [id],
+        message_from_row,
+    )
+    .optional()
+}
+
+/// Loads up to `limit` messages older than the message `before`, or the
+/// newest ones without a cursor.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_CURSOR` if `before` is
+/// not a message of the conversation, or `DATABASE` if a query fails.
+fn load_message_page(
+    conn: &Connection,
+    conversation_id: &str,
+    before: Option<&str>,
+    limit: u32,
+) -> Result<MessagePage, GibberError> {
+    let position: Option<(i64, i64)> = match before {
+        Some(cursor) => Some(
+            conn.query_row(
+                "SELECT created_at, rowid FROM messages WHERE id = ?1 AND conversation_id = ?2",
+                params![cursor, conversation_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| {
+                GibberError::new(
+                    "INVALID_CURSOR",
+                    format!("Message {cursor} is not in conversation {conversation_id}"),
+                )
+            })?,
+        ),
+        None => None,
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {MESSAGE_COLUMNS} FROM messages
+         WHERE conversation_id = ?1
+           AND (?2 IS NULL OR created_at < ?2 OR (created_at = ?2 AND rowid < ?3))
+         ORDER BY created_at DESC, rowid DESC LIMIT ?4"
+    ))?;
+    // One extra row tells whether there is an older page.
+    let mut messages = stmt
+        .query_map(
+            params![
+                conversation_id,
+                position.map(|(created_at, _)| created_at),
+                position.map(|(_, rowid)| rowid),
+                limit + 1
+            ],
+            message_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let has_more = messages.len() > limit as usize;
+    messages.truncate(limit as usize);
+    messages.reverse();
+    let total = conn.query_row(
+        "SELECT COUNT(*) FROM messages WHERE conversation_id = ?1",
+        [conversation_id],
+        |row| row.get(0),
+    )?;
+    Ok(MessagePage {
+        next_cursor: messages
+            .first()
+            .filter(|_| has_more)
+            .map(|message| message.id.clone()),
+        messages,
+        total,
+    })
+}
+
+/// A conversation export file.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConversationExport<'a> {
+    format: &'static str,
+    version: u32,
+    exported_at: i64,
+    conversation: &'a ConversationWithMessages,
+}
+
+/// Serializes a conversation in the export file format.
+pub(crate) fn export_json(conversation: &ConversationWithMessages) -> String {
+    let export = ConversationExport {
+        format: EXPORT_FORMAT,
+        version: EXPORT_VERSION,
+        exported_at: db::now_millis(),
+        conversation,
+    };
+    serde_json::to_string_pretty(&export).unwrap_or_default()
+}
+
+/// Renders a conversation as a standalone HTML page, showing the code
+/// blocks in `diagrams` as images.
+pub(crate) fn export_html(conversation: &ConversationWithMessages, diagrams: &Diagrams) -> String {
+    let options = RenderOptions {
+        highlight: true,
+        math: true,
+    };
+    let highlight_css = markdown::highlight_css(markdown::DEFAULT_THEME).unwrap_or_default();
+    let title = markdown::escape(&conversation.conversation.title);
+    let meta = conversation
+        .conversation
+        .model
+        .as_deref()
+        .map(|model| format!("<p class=\"meta\">{}</p>\n", markdown::escape(model)))
+        .unwrap_or_default();
+    let messages = conversation
+        .messages
+        .iter()
+        .fold(String::new(), |mut html, message| {
+            let role = message.role.as_str();
+            let _ = write!(
+                html,
+                "<section class=\"message {role}\">\n<div class=\"role\">{role}</div>\n{}</section>\n",
+                markdown::render_with_diagrams(&message.content, options, diagrams)
+            );
+            html
+        });
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\n{EXPORT_STYLE}{highlight_css}</style>\n</head>\n<body>\n\
+         <header>\n<h1>{title}</h1>\n{meta}</header>\n{messages}</body>\n</html>\n"
+    )
+}
+
+/// Serializes a conversation in an export format. JSON ignores
+/// `diagrams`.
+pub(crate) fn export(
+    conversation: &ConversationWithMessages,
+    format: ExportFormat,
+    diagrams: &Diagrams,
+) -> String {
+    match format {
+        ExportFormat::Json => export_json(conversation),
+        ExportFormat::Html => export_html(conversation, diagrams),
+    }
+}
+
+/// Lists all conversations, most recently updated first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const conversations = await invoke("list_conversations");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_conversations(db: State<'_, Database>) -> Result<Vec<Conversation>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CONVERSATION_COLUMNS} FROM conversations ORDER BY updated_at DESC"
+    ))?;
+    let conversations = stmt
+        .query_map([], conversation_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(conversations)
+}
+
+/// Retrieves a conversation with all of its messages.
+///
+/// Long conversations are better opened with [`get_messages`], which
+/// loads them a page at a time.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, or `DATABASE` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const conversation = await invoke("get_conversation", { id });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_conversation(
+    db: State<'_, Database>,
+    id: &str,
+) -> Result<ConversationWithMessages, GibberError> {
+    load_conversation(&db.conn(), id)?.ok_or_else(|| not_found(id))
+}
+
+/// Retrieves a page of a conversation's messages, newest page first.
+///
+/// Without a `cursor` this returns the latest `limit` messages (100 by
+/// default, at most 500). Pass the page's `nextCursor` to get the messages
+/// before it; it is `null` once the first message has been returned.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, `INVALID_CURSOR` if the cursor isn't one of its
+/// messages, or `DATABASE` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// let page = await invoke("get_messages", { conversationId: id, limit: 50 });
+/// while (page.nextCursor && userScrolledToTop()) {
+///   page = await invoke("get_messages", { conversationId: id, cursor: page.nextCursor });
+///   prependMessages(page.messages);
+/// }
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_messages(
+    db: State<'_, Database>,
+    conversation_id: &str,
+    cursor: Option<&str>,
+    limit: Option<u32>,
+) -> Result<MessagePage, GibberError> {
+    let conn = db.conn();
+    if !conversation_exists(&conn, conversation_id)? {
+        return Err(not_found(conversation_id));
+    }
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    load_message_page(&conn, conversation_id, cursor, limit)
+}
+
+/// Exports a conversation as JSON (the default) or as an HTML page with
+/// rendered math, code, and diagrams.
+///
+/// With `deidentify`, names, emails, IP addresses, and secrets are replaced
+/// by placeholders first; `deidentify_conversation` previews the result.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, or `DATABASE` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const html = await invoke("export_conversation", {
+///   id,
+///   format: "html",
+///   deidentify: { names: ["Ana Lopez"] },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn export_conversation(
+    app: AppHandle,
+    id: String,
+    format: Option<ExportFormat>,
+    deidentify: Option<DeidentifyOptions>,
+) -> Result<String, GibberError> {
+    let format = format.unwrap_or_default();
+    let (mut conversation, server) = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let conversation = load_conversation(&conn, &id)?.ok_or_else(|| not_found(&id))?;
+        (conversation, settings::load(&conn)?.diagram_server)
+    };
+    if let Some(options) = deidentify {
+        conversation = deidentify::deidentify(&conversation, &options).conversation;
+    }
+    let mut rendered = Diagrams::new();
+    if format == ExportFormat::Html {
+        match network::client(&app, Service::Diagrams) {
+            Ok(client) => {
+                rendered = diagrams::render(Some(&app), &client, &server, &conversation).await;
+            }
+            Err(e) => tracing::warn!("diagrams not rendered: {e}"),
+        }
+    }
+    Ok(export(&conversation, format, &rendered))
+}
+
+/// Creates an empty conversation.
+///
+/// A chat conversation without a system prompt is enrolled in the active
+/// A/B experiment, if any, and gets the prompt of its variant (see
+/// [`experiments`]).
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the insert fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const conversation = await invoke("create_conversation", {
+///   input: { title: "Ambient drone", model: "anthropic/claude-sonnet-4" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn create_conversation(
+    db: State<'_, Database>,
+    input: NewConversation,
+) -> Result<Conversation, GibberError> {
+    let conn = db.conn();
+    let mut conversation = insert_conversation(&conn, &input)?;
+    experiments::enroll(&conn, &mut conversation)?;
+    Ok(conversation)
+}
+
+/// Appends a message to an existing conversation.
+///
+/// The stored message is broadcast as [`events::CONVERSATION_MESSAGE`].
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, or `DATABASE` if the insert fails.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("append_message", {
+///   conversationId: id,
+///   message: { role: "user", content: "Add a hi-hat" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle, State and args by value
+pub fn append_message(
+    app: AppHandle,
+    db: State<'_, Database>,
+    conversation_id: &str,
+    message: NewMessage,
+) -> Result<StoredMessage, GibberError> {
+    let stored = {
+        let conn = db.conn();
+        if !conversation_exists(&conn, conversation_id)? {
+            return Err(not_found(conversation_id));
+        }
+        insert_message(&conn, conversation_id, &message)?
+    };
+    events::emit_typed(&app, events::CONVERSATION_MESSAGE, &stored);
+    Ok(stored)
+}
+
+/// Deletes a conversation and its messages.
+///
+/// # Returns
+///
+/// Returns `true` if the conversation was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_conversation(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM conversations WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+/// Normalizes tags: trimmed, lowercase, without duplicates or empties.
+pub(crate) fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Loads a conversation's tags in alphabetical order.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub(crate) fn load_tags(conn: &Connection, conversation_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT tag FROM conversation_tags WHERE conversation_id = ?1 ORDER BY tag")?;
+    let tags = stmt
+        .query_map([conversation_id], |row| row.get(0))?
+        .collect();
+    tags
+}
+
+/// Retrieves a conversation's tags.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_conversation_tags(
+    db: State<'_, Database>,
+    conversation_id: &str,
+) -> Result<Vec<String>, GibberError> {
+    Ok(load_tags(&db.conn(), conversation_id)?)
+}
+
+/// Replaces a conversation's tags. Tags are case-insensitive and stored
+/// lowercase.
+///
+/// # Returns
+///
+/// The tags as stored.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_conversation_tags", { conversationId: id, tags: ["mixing", "good"] });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_conversation_tags(
+    db: State<'_, Database>,
+    conversation_id: &str,
+    tags: Vec<String>,
+) -> Result<Vec<String>, GibberError> {
+    let mut conn = db.conn();
+    if !conversation_exists(&conn, conversation_id)? {
+        return Err(not_found(conversation_id));
+    }
+    let tags = normalize_tags(&tags);
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM conversation_tags WHERE conversation_id = ?1",
+        [conversation_id],
+    )?;
+    for tag in &tags {
+        tx.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag) VALUES (?1, ?2)",
+            params![conversation_id, tag],
+        )?;
+    }
+    tx.commit()?;
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_conversation(title: &str) -> NewConversation {
+        NewConversation {
+            title: title.to_string(),
+            ..NewConversation::default()
+        }
+    }
+
+    #[test]
+    fn test_message_pages_walk_back_from_the_newest() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let conversation = insert_conversation(&conn, &new_conversation("Long")).unwrap();
+        for n in 1..=5 {
+            let message = NewMessage {
+                role: MessageRole::User,
+                content: format!("Take {n}"),
+                model: None,
+                usage: None,
+            };
+            insert_message(&conn, &conversation.id, &message).unwrap();
+        }
+        let contents = |page: &MessagePage| -> Vec<String> {
+            page.messages.iter().map(|m| m.content.clone()).collect()
+        };
+
+        let newest = load_message_page(&conn, &conversation.id, None, 2).unwrap();
+        assert_eq!(contents(&newest), ["Take 4", "Take 5"]);
+        assert_eq!(newest.total, 5);
+        let cursor = newest.next_cursor.as_deref();
+        let middle = load_message_page(&conn, &conversation.id, cursor, 2).unwrap();
+        assert_eq!(contents(&middle), ["Take 2", "Take 3"]);
+        let cursor = middle.next_cursor.as_deref();
+        let oldest = load_message_page(&conn, &conversation.id, cursor, 2).unwrap();
+        assert_eq!(contents(&oldest), ["Take 1"]);
+        assert!(oldest.next_cursor.is_none());
+
+        let error = load_message_page(&conn, &conversation.id, Some("nope"), 2).unwrap_err();
+        assert_eq!(error.code(), "INVALID_CURSOR");
+    }
+
+    #[test]
+    fn test_insert_and_load_conversation() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let conversation = insert_conversation(&conn, &new_conversation("Test")).unwrap();
+        insert_message(
+            &conn,
+            &conversation.id,
+            &NewMessage {
+                role: MessageRole::User,
+                content: "Hello".to_string(),
+                model: None,
+                usage: None,
+            },
+        )
+        .unwrap();
+
+        let loaded = load_conversation(&conn, &conversation.id)
+            .unwrap()
+            .expect("Should exist");
+        assert_eq!(loaded.conversation.title, "Test");
+        assert_eq!(loaded.conversation.source, "chat");
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.messages[0].role, MessageRole::User);
+    }
+
+    #[test]
+    fn test_export_html_renders_math() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let conversation = insert_conversation(&conn, &new_conversation("<Physics>")).unwrap();
+        insert_message(
+            &conn,
+            &conversation.id,
+            &NewMessage {
+                role: MessageRole::Assistant,
+                content: "Mass-energy: $$E = mc^2$$".to_string(),
+                model: None,
+                usage: None,
+            },
+        )
+        .unwrap();
+        let loaded = load_conversation(&conn, &conversation.id)
+            .unwrap()
+            .expect("Should exist");
+
+        let html = export(&loaded, ExportFormat::Html, &Diagrams::new());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>&lt;Physics&gt;</title>"));
+        assert!(html.contains("<section class=\"message assistant\">"));
+        assert!(html.contains("<math"));
+        assert!(!html.contains("$$"));
+        assert!(export(&loaded, ExportFormat::Json, &Diagrams::new()).contains(EXPORT_FORMAT));
+    }
+
+    #[test]
+    fn test_search_matches_titles_and_messages() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let by_title = insert_conversation(&conn, &new_conversation("Drum patterns")).unwrap();
+        let by_message = insert_conversation(&conn, &new_conversation("Other")).unwrap();
+        insert_message(
+            &conn,
+            &by_message.id,
+            &NewMessage {
+                role: MessageRole::User,
+                content: "More DRUMS please, 100% louder".to_string(),
+                model: None,
+                usage: None,
+            },
+        )
+        .unwrap();
+
+        let found: Vec<String> = search(&conn, "drum", 10)
+            .unwrap()
+            .into_iter()
+            .map(|conversation| conversation.id)
+            .collect();
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&by_title.id) && found.contains(&by_message.id));
+        assert_eq!(search(&conn, "100%", 10).unwrap().len(), 1);
+        assert!(search(&conn, "_", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_title_from_prompt() {
+        assert_eq!(
+            title_from_prompt("  Make a beat\nwith swing"),
+            "Make a beat"
+        );
+        assert_eq!(title_from_prompt(""), "New conversation");
+        let long = "a".repeat(100);
+        assert_eq!(
+            title_from_prompt(&long).chars().count(),
+            MAX_DERIVED_TITLE_CHARS + 1
+        );
+    }
+
+    #[test]
+    fn test_load_missing_conversation() {
+        let db = Database::open_in_memory().expect("Should open");
+        assert!(load_conversation(&db.conn(), "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_messages_cascade_on_delete() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let conversation = insert_conversation(&conn, &new_conversation("Test")).unwrap();
+        insert_message(
+            &conn,
+            &conversation.id,
+            &NewMessage {
+                role: MessageRole::Assistant,
+                content: "Hi".to_string(),
+                model: Some("m".to_string()),
+                usage: None,
+            },
+        )
+        .unwrap();
+        conn.execute(
+            "DELETE FROM conversations WHERE id = ?1",
+            [&conversation.id],
+        )
+        .unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+}
@@ -0,0 +1,308 @@
+//! Local crash reports.
+//!
+//! A panic hook writes a JSON report (message, location, backtrace, app and
+//! OS versions) to the `crashes` directory before the default hook runs.
+//! Report text is redacted like diagnostics logs. Reports stay on disk until
+//! the user deletes them; one is only uploaded when the user submits it
+//! with `submit_crash_report`, and builds without a `GIBBER_CRASH_URL` can't
+//! upload at all.
+//!
+//! Only panics are reported; minidumps of native crashes (segfaults,
+//! aborts, stack overflows) are out of scope. Even with an out-of-process
+//! monitor writing the dump, the crashing process has to install a signal
+//! or exception handler, and that takes `unsafe` code, which this crate
+//! forbids. Those crashes are left to the operating system's crash
+//! reporter.
+
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::diagnostics;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db;
+use crate::error::GibberError;
+
+/// Directory inside the app data directory holding the reports.
+const CRASHES_DIR: &str = "crashes";
+
+/// Upload endpoint, provided at build time.
+const ENDPOINT: Option<&str> = option_env!("GIBBER_CRASH_URL");
+
+/// Managed state holding the reports directory.
+pub struct CrashReports {
+    dir: PathBuf,
+}
+
+/// A stored crash report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    /// Unique identifier, also the file stem
+    pub id: String,
+    /// Crash time in Unix milliseconds
+    pub created_at: i64,
+    /// App version that crashed
+    pub app_version: String,
+    /// Operating system family
+    pub os: String,
+    /// CPU architecture
+    pub arch: String,
+    /// Name of the panicking thread
+    pub thread: Option<String>,
+    /// Panic message
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    /// Captured backtrace
+    pub backtrace: String,
+    /// Upload time in Unix milliseconds, if submitted
+    #[serde(default)]
+    pub submitted_at: Option<i64>,
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| (*s).to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
+
+fn report_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{id}.json"))
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> std::io::Result<()> {
+    let contents = serde_json::to_string_pretty(report).unwrap_or_default();
+    fs::write(report_path(dir, &report.id), contents)
+}
+
+/// Builds a report for a panic, redacting user-identifying text.
+fn report_for(info: &PanicHookInfo<'_>, app_version: &str, home: Option<&str>) -> CrashReport {
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+    CrashReport {
+        id: db::new_id(),
+        created_at: db::now_millis(),
+        app_version: app_version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        thread: std::thread::current().name().map(str::to_string),
+        message: diagnostics::redact(&panic_message(info), home),
+        location: info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        backtrace: diagnostics::redact(&backtrace, home),
+        submitted_at: None,
+    }
+}
+
+/// Returns the stored reports, newest first. Unreadable files are skipped.
+fn read_reports(dir: &Path) -> std::io::Result<Vec<CrashReport>> {
+    let mut reports: Vec<CrashReport> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| serde_json::from_str(&fs::read_to_string(path).ok()?).ok())
+        .collect();
+    reports.sort_by_key(|report| std::cmp::Reverse(report.created_at));
+    Ok(reports)
+}
+
+/// Installs the panic hook and manages the reports directory.
+///
+/// The previous hook still runs after the report is written, so panics are
+/// printed as before.
+///
+/// # Errors
+///
+/// Returns an error if the reports directory cannot be created.
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = app.path().app_data_dir()?.join(CRASHES_DIR);
+    fs::create_dir_all(&dir)?;
+    let app_version = app.package_info().version.to_string();
+    let home = app
+        .path()
+        .home_dir()
+        .ok()
+        .map(|dir| dir.to_string_lossy().into_owned());
+
+    let hook_dir = dir.clone();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = report_for(info, &app_version, home.as_deref());
+        tracing::error!(
+            id = %report.id,
+            location = report.location.as_deref().unwrap_or_default(),
+            "panic: {}",
+            report.message
+        );
+        if let Err(e) = write_report(&hook_dir, &report) {
+            tracing::error!("failed to write crash report: {e}");
+        }
+        previous(info);
+    }));
+
+    app.manage(CrashReports { dir });
+    Ok(())
+}
+
+/// Lists stored crash reports, newest first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the reports directory cannot be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const reports = await invoke("list_crash_reports");
+/// if (reports.some((r) => !r.submittedAt)) askToSubmit(reports);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_crash_reports(
+    reports: State<'_, CrashReports>,
+) -> Result<Vec<CrashReport>, GibberError> {
+    Ok(read_reports(&reports.dir)?)
+}
+
+/// Deletes crash reports; all of them when `ids` is omitted.
+///
+/// # Returns
+///
+/// The number of deleted reports.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if a report file cannot be removed.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("delete_crash_reports", { ids: [report.id] });
+/// await invoke("delete_crash_reports", { ids: null });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn delete_crash_reports(
+    reports: State<'_, CrashReports>,
+    ids: Option<Vec<String>>,
+) -> Result<usize, GibberError> {
+    // Only IDs of existing reports are turned into paths.
+    let mut deleted = 0;
+    for report in read_reports(&reports.dir)? {
+        if ids.as_ref().is_none_or(|ids| ids.contains(&report.id)) {
+            fs::remove_file(report_path(&reports.dir, &report.id))?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Uploads one crash report after the user agreed to send it.
+///
+/// # Returns
+///
+/// The report with `submittedAt` set.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the report doesn't
+/// exist, `UNAVAILABLE` if this build has no upload endpoint, or
+/// `NETWORK_ERROR` if the upload fails.
+///
+/// # Example
+///
+/// ```typescript
+/// if (await confirm("Send this crash report?")) {
+///   await invoke("submit_crash_report", { id: report.id });
+/// }
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn submit_crash_report(app: AppHandle, id: String) -> Result<CrashReport, GibberError> {
+    let endpoint = ENDPOINT
+        .ok_or_else(|| GibberError::new("UNAVAILABLE", "This build can't upload crash reports"))?;
+    let dir = app.state::<CrashReports>().dir.clone();
+    let mut report = read_reports(&dir)?
+        .into_iter()
+        .find(|report| report.id == id)
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Crash report not found: {id}")))?;
+
+    let builder = network::client(&app, Service::CrashReports)?
+        .post(endpoint)
+        .json(&report);
+    let response = network_activity::send(&app, Service::CrashReports, "crash.upload", builder)
+        .await
+        .map_err(|e| GibberError::new("NETWORK_ERROR", e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(GibberError::new(
+            "NETWORK_ERROR",
+            format!("Endpoint answered {}", response.status()),
+        ));
+    }
+    report.submitted_at = Some(db::now_millis());
+    write_report(&dir, &report)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, created_at: i64) -> CrashReport {
+        CrashReport {
+            id: id.to_string(),
+            created_at,
+            app_version: "0.1.0".to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            thread: Some("main".to_string()),
+            message: "index out of bounds".to_string(),
+            location: Some("src/db.rs:1:1".to_string()),
+            backtrace: String::new(),
+            submitted_at: None,
+        }
+    }
+
+    #[test]
+    fn test_reports_round_trip_newest_first() {
+        let dir = std::env::temp_dir().join(format!("gibber-crashes-{}", db::new_id()));
+        fs::create_dir_all(&dir).unwrap();
+        write_report(&dir, &sample("old", 1)).unwrap();
+        write_report(&dir, &sample("new", 2)).unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let ids: Vec<_> = read_reports(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|report| report.id)
+            .collect();
+        assert_eq!(ids, vec!["new", "old"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_hook_captures_panic_message() {
+        let captured = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let sink = captured.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            *sink.lock().unwrap() = Some(report_for(info, "0.1.0", Some("/home/ana")));
+        }));
+        let _ = std::panic::catch_unwind(|| panic!("cannot open /home/ana/notes.md"));
+        std::panic::set_hook(previous);
+
+        let report = captured.lock().unwrap().take().unwrap();
+        assert_eq!(report.message, "cannot open ~/notes.md");
+        assert!(report.location.unwrap().contains("crash_reports.rs"));
+    }
+}
@@ -4,45 +4,34 @@
 //! API keys using the operating system's secure keyring (Keychain on macOS,
 //! Credential Manager on Windows, Secret Service on Linux).
 //!
+//! Keyring calls can block for seconds (Secret Service on Linux waits for
+//! the keyring daemon, or for the user to unlock it), so the commands are
+//! async and run them on the blocking thread pool instead of the thread
+//! that handles IPC.
+//!
 //! # Security
 //!
 //! - API keys are never logged or exposed in debug output
 //! - Only the key length is logged for debugging purposes
 //! - Keys are stored encrypted by the OS keyring
 
-use tauri::AppHandle;
+use tauri::{AppHandle, WebviewWindow};
 use tauri_plugin_keyring::KeyringExt;
 
+use crate::commands::windows;
+use crate::error::GibberError;
+
 /// The service identifier for Gibber AI in the system keyring.
 /// This groups all Gibber AI credentials together.
 const SERVICE_NAME: &str = "gibber-ai";
 
-/// Error type for credential operations.
-#[derive(Debug, serde::Serialize)]
-pub struct CredentialError {
-    /// Human-readable error message
-    pub message: String,
-    /// Error code for programmatic handling
-    pub code: String,
-}
-
-impl From<keyring::Error> for CredentialError {
-    fn from(err: keyring::Error) -> Self {
-        let code = match &err {
-            keyring::Error::NoEntry => "NO_ENTRY",
-            keyring::Error::Ambiguous(_) => "AMBIGUOUS",
-            keyring::Error::BadEncoding(_) => "BAD_ENCODING",
-            keyring::Error::TooLong(_, _) => "TOO_LONG",
-            keyring::Error::Invalid(_, _) => "INVALID",
-            keyring::Error::NoStorageAccess(_) => "NO_STORAGE_ACCESS",
-            keyring::Error::PlatformFailure(_) => "PLATFORM_FAILURE",
-            _ => "UNKNOWN",
-        };
-        Self {
-            message: err.to_string(),
-            code: code.to_string(),
-        }
-    }
+/// Runs a keyring operation on the blocking thread pool.
+async fn blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, GibberError> + Send + 'static,
+) -> Result<T, GibberError> {
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
 }
 
 /// Checks if an error indicates the credential doesn't exist.
@@ -63,7 +52,8 @@ const fn is_no_entry(err: &keyring::Error) -> bool {
 ///
 /// # Errors
 ///
-/// Returns a `CredentialError` if the keyring operation fails.
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, or if the keyring operation fails.
 ///
 /// # Example
 ///
@@ -75,8 +65,34 @@ const fn is_no_entry(err: &keyring::Error) -> bool {
 /// }
 /// ```
 #[tauri::command]
-#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
-pub fn get_api_key(app: AppHandle, service: &str) -> Result<Option<String>, CredentialError> {
+#[specta::specta]
+pub async fn get_api_key(
+    window: WebviewWindow,
+    app: AppHandle,
+    service: String,
+) -> Result<Option<String>, GibberError> {
+    windows::require_trusted(&window)?;
+    load_api_key(&app, &service).await
+}
+
+/// Reads an API key for use by other backend modules.
+///
+/// Shares the lookup logic of [`get_api_key`] without going through IPC,
+/// and likewise keeps the keyring off the calling thread.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the keyring operation fails.
+pub(crate) async fn load_api_key(
+    app: &AppHandle,
+    service: &str,
+) -> Result<Option<String>, GibberError> {
+    let app = app.clone();
+    let service = service.to_string();
+    blocking(move || read_api_key(&app, &service)).await
+}
+
+fn read_api_key(app: &AppHandle, service: &str) -> Result<Option<String>, GibberError> {
     let keyring = app.keyring();
     match keyring.get_password(SERVICE_NAME, service) {
         Ok(password) => Ok(password),
@@ -85,6 +101,23 @@ pub fn get_api_key(app: AppHandle, service: &str) -> Result<Option<String>, Cred
     }
 }
 
+/// Reads an API key straight from the OS keyring, without the plugin.
+///
+/// Used by the CLI, which runs without an app handle; entries are the same
+/// ones the keyring plugin writes.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the keyring operation fails.
+#[cfg(feature = "cli")]
+pub(crate) fn read_stored_api_key(service: &str) -> Result<Option<String>, GibberError> {
+    match keyring::Entry::new(SERVICE_NAME, service)?.get_password() {
+        Ok(password) => Ok(Some(password)),
+        Err(e) if is_no_entry(&e) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
 /// Stores an API key in the system keyring.
 ///
 /// If a key already exists for the given service, it will be overwritten.
@@ -97,7 +130,8 @@ pub fn get_api_key(app: AppHandle, service: &str) -> Result<Option<String>, Cred
 ///
 /// # Errors
 ///
-/// Returns a `CredentialError` if the keyring operation fails.
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, or if the keyring operation fails.
 ///
 /// # Example
 ///
@@ -106,11 +140,19 @@ pub fn get_api_key(app: AppHandle, service: &str) -> Result<Option<String>, Cred
 /// await invoke("set_api_key", { service: "openrouter", key: "sk-..." });
 /// ```
 #[tauri::command]
-#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
-pub fn set_api_key(app: AppHandle, service: &str, key: &str) -> Result<(), CredentialError> {
-    let keyring = app.keyring();
-    keyring.set_password(SERVICE_NAME, service, key)?;
-    Ok(())
+#[specta::specta]
+pub async fn set_api_key(
+    window: WebviewWindow,
+    app: AppHandle,
+    service: String,
+    key: String,
+) -> Result<(), GibberError> {
+    windows::require_trusted(&window)?;
+    blocking(move || {
+        app.keyring().set_password(SERVICE_NAME, &service, &key)?;
+        Ok(())
+    })
+    .await
 }
 
 /// Deletes an API key from the system keyring.
@@ -126,7 +168,9 @@ pub fn set_api_key(app: AppHandle, service: &str, key: &str) -> Result<(), Crede
 ///
 /// # Errors
 ///
-/// Returns a `CredentialError` if the keyring operation fails (other than key not found).
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, or if the keyring operation fails (other than key
+/// not found).
 ///
 /// # Example
 ///
@@ -136,14 +180,21 @@ pub fn set_api_key(app: AppHandle, service: &str, key: &str) -> Result<(), Crede
 /// console.log(deleted ? "Key deleted" : "Key not found");
 /// ```
 #[tauri::command]
-#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
-pub fn delete_api_key(app: AppHandle, service: &str) -> Result<bool, CredentialError> {
-    let keyring = app.keyring();
-    match keyring.delete_password(SERVICE_NAME, service) {
-        Ok(()) => Ok(true),
-        Err(e) if is_no_entry(&e) => Ok(false),
-        Err(e) => Err(e.into()),
-    }
+#[specta::specta]
+pub async fn delete_api_key(
+    window: WebviewWindow,
+    app: AppHandle,
+    service: String,
+) -> Result<bool, GibberError> {
+    windows::require_trusted(&window)?;
+    blocking(
+        move || match app.keyring().delete_password(SERVICE_NAME, &service) {
+            Ok(()) => Ok(true),
+            Err(e) if is_no_entry(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        },
+    )
+    .await
 }
 
 #[cfg(test)]
@@ -161,13 +212,9 @@ mod tests {
 
     #[test]
     fn test_credential_error_serializes() {
-        let error = CredentialError {
-            message: "Test error".to_string(),
-            code: "TEST_CODE".to_string(),
-        };
+        let error = GibberError::from(keyring::Error::NoEntry);
         let json = serde_json::to_string(&error).expect("Should serialize");
-        assert!(json.contains("Test error"));
-        assert!(json.contains("TEST_CODE"));
+        assert!(json.contains("NO_ENTRY"));
     }
 
     #[test]
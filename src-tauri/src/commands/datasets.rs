@@ -0,0 +1,473 @@
+//! Fine-tuning dataset builder.
+//!
+//! [`export_dataset`] turns stored conversations into JSON Lines training
+//! files in the OpenAI chat format (`{"messages": [{"role", "content"}]}`)
+//! or the ShareGPT format (`{"conversations": [{"from", "value"}]}`).
+//!
+//! Conversations are picked by ID or from the whole history, then filtered
+//! by tag, by the models that answered, and by feedback ratings (see
+//! [`feedback`]). Each one can be de-identified on the way out (see
+//! [`deidentify`]). A share of them goes to a validation file instead of
+//! the training file; which ones is decided by hashing the conversation ID
+//! with a seed, so the same selection always splits the same way.
+//!
+//! [`feedback`]: crate::commands::feedback
+
+use std::io::Write as _;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::chat::MessageRole;
+use crate::commands::conversations::{self, ConversationWithMessages};
+use crate::commands::deidentify::{self, DeidentifyOptions};
+use crate::commands::feedback::Rating;
+use crate::commands::jobs::{self, JobHandle};
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Largest share of conversations that can go to the validation file.
+const MAX_VALIDATION_FRACTION: f64 = 0.5;
+
+/// Record layout of the exported files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    /// OpenAI chat fine-tuning records
+    #[default]
+    OpenAi,
+    /// ShareGPT records, as read by most open-source trainers
+    ShareGpt,
+}
+
+/// Which conversations go into a dataset. Empty lists don't filter.
+#[derive(Debug, Clone, Default, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DatasetFilter {
+    /// Conversations to consider; all of them when empty
+    pub conversation_ids: Vec<String>,
+    /// Keep conversations with any of these tags
+    pub tags: Vec<String>,
+    /// Keep conversations answered only by these models
+    pub models: Vec<String>,
+    /// Keep conversations with at least one answer rated this way and none
+    /// rated the other way
+    pub rating: Option<Rating>,
+}
+
+/// What [`export_dataset`] writes.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetRequest {
+    /// Record layout
+    #[serde(default)]
+    pub format: DatasetFormat,
+    /// Conversation selection
+    #[serde(default)]
+    pub filter: DatasetFilter,
+    /// De-identify messages with these options; left as is when `None`
+    #[serde(default)]
+    pub redact: Option<DeidentifyOptions>,
+    /// Share of conversations for the validation file, 0 to 0.5
+    #[serde(default)]
+    pub validation_fraction: f64,
+    /// Seed of the split; change it to draw a different validation set
+    #[serde(default)]
+    pub seed: u64,
+    /// Training file
+    pub train_path: String,
+    /// Validation file; required when `validationFraction` is above 0
+    #[serde(default)]
+    pub validation_path: Option<String>,
+}
+
+/// Result of [`export_dataset`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetSummary {
+    /// Records written to the training file
+    pub train: usize,
+    /// Records written to the validation file
+    pub validation: usize,
+    /// Selected conversations left out by the filter
+    pub filtered: usize,
+    /// Conversations left out because they have no answer
+    pub empty: usize,
+    /// Values replaced by de-identification
+    pub redacted: usize,
+}
+
+/// Whether a conversation passes the tag, model, and rating filters.
+fn matches(
+    conn: &Connection,
+    conversation: &ConversationWithMessages,
+    filter: &DatasetFilter,
+) -> rusqlite::Result<bool> {
+    if !filter.tags.is_empty() {
+        let wanted = conversations::normalize_tags(&filter.tags);
+        let tags = conversations::load_tags(conn, &conversation.conversation.id)?;
+        if !tags.iter().any(|tag| wanted.contains(tag)) {
+            return Ok(false);
+        }
+    }
+    if !filter.models.is_empty() {
+        let answered_by_others = conversation
+            .messages
+            .iter()
+            .filter(|message| message.role == MessageRole::Assistant)
+            .any(|message| {
+                message
+                    .model
+                    .as_ref()
+                    .is_none_or(|model| !filter.models.contains(model))
+            });
+        if answered_by_others {
+            return Ok(false);
+        }
+    }
+    if let Some(rating) = filter.rating {
+        let (up, down): (i64, i64) = conn.query_row(
+            "SELECT COALESCE(SUM(rating > 0), 0), COALESCE(SUM(rating < 0), 0)
+             FROM message_feedback WHERE conversation_id = ?1",
+            [&conversation.conversation.id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let (wanted, unwanted) = match rating {
+            Rating::Up => (up, down),
+            Rating::Down => (down, up),
+        };
+        if wanted == 0 || unwanted > 0 {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// The training record of a conversation, or `None` without an answer.
+///
+/// Trailing messages after the last answer are dropped, since trainers
+/// learn from the assistant turns.
+fn record(conversation: &ConversationWithMessages, format: DatasetFormat) -> Option<Value> {
+    let last_answer = conversation
+        .messages
+        .iter()
+        .rposition(|message| message.role == MessageRole::Assistant)?;
+    let system = conversation
+        .conversation
+        .system_prompt
+        .as_deref()
+        .filter(|prompt| !prompt.trim().is_empty())
+        .map(|prompt| (MessageRole::System, prompt));
+    let turns = system.into_iter().chain(
+        conversation.messages[..=last_answer]
+            .iter()
+            .map(|message| (message.role, message.content.as_str())),
+    );
+    Some(match format {
+        DatasetFormat::OpenAi => {
+            let messages: Vec<Value> = turns
+                .map(|(role, content)| json!({ "role": role.as_str(), "content": content }))
+                .collect();
+            json!({ "messages": messages })
+        }
+        DatasetFormat::ShareGpt => {
+            let turns: Vec<Value> = turns
+                .map(|(role, content)| {
+                    let from = match role {
+                        MessageRole::System => "system",
+                        MessageRole::User => "human",
+                        MessageRole::Assistant => "gpt",
+                    };
+                    json!({ "from": from, "value": content })
+                })
+                .collect();
+            json!({ "conversations": turns })
+        }
+    })
+}
+
+/// Whether a conversation goes to the validation file.
+fn is_validation(conversation_id: &str, seed: u64, fraction: f64) -> bool {
+    if fraction <= 0.0 {
+        return false;
+    }
+    let digest = Sha256::digest(format!("{seed}:{conversation_id}"));
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    #[allow(clippy::cast_precision_loss)] // a fraction needs no more precision
+    let position = u64::from_be_bytes(bytes) as f64 / u64::MAX as f64;
+    position < fraction
+}
+
+fn build(
+    conn: &Connection,
+    request: &DatasetRequest,
+    train: &mut impl std::io::Write,
+    mut validation: Option<&mut dyn std::io::Write>,
+    mut job: Option<&mut JobHandle>,
+) -> Result<DatasetSummary, GibberError> {
+    let ids = if request.filter.conversation_ids.is_empty() {
+        let mut stmt = conn.prepare("SELECT id FROM conversations ORDER BY created_at")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        ids
+    } else {
+        request.filter.conversation_ids.clone()
+    };
+
+    let mut summary = DatasetSummary::default();
+    for (index, id) in ids.iter().enumerate() {
+        if let Some(job) = job.as_mut() {
+            if job.is_cancelled() {
+                return Err(GibberError::new("CANCELLED", "The export was cancelled"));
+            }
+            job.advance(index, ids.len(), None);
+        }
+        let Some(mut conversation) = conversations::load_conversation(conn, id)? else {
+            summary.filtered += 1;
+            continue;
+        };
+        if !matches(conn, &conversation, &request.filter)? {
+            summary.filtered += 1;
+            continue;
+        }
+        if let Some(options) = &request.redact {
+            let redacted = deidentify::deidentify(&conversation, options);
+            summary.redacted += redacted
+                .replacements
+                .iter()
+                .map(|replacement| replacement.occurrences)
+                .sum::<usize>();
+            conversation = redacted.conversation;
+        }
+        let Some(record) = record(&conversation, request.format) else {
+            summary.empty += 1;
+            continue;
+        };
+        match validation.as_mut() {
+            Some(file) if is_validation(id, request.seed, request.validation_fraction) => {
+                writeln!(file, "{record}")?;
+                summary.validation += 1;
+            }
+            _ => {
+                writeln!(train, "{record}")?;
+                summary.train += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Exports conversations as a fine-tuning dataset.
+///
+/// The export runs as an `export.dataset` job (see [`jobs`]), so its
+/// progress arrives as `job://progress` events and it can be cancelled
+/// with `cancel_job`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if the validation
+/// fraction is out of range or has no file, `IO` if a file cannot be
+/// written, `CANCELLED` if the job was cancelled, or `DATABASE` if a query
+/// fails.
+///
+/// [`jobs`]: crate::commands::jobs
+///
+/// # Example
+///
+/// ```typescript
+/// const summary = await invoke("export_dataset", {
+///   request: {
+///     format: "sharegpt",
+///     filter: { tags: ["mixing"], rating: "up" },
+///     redact: { names: ["Ana Lopez"] },
+///     validationFraction: 0.1,
+///     trainPath: "/Users/me/mixing.train.jsonl",
+///     validationPath: "/Users/me/mixing.val.jsonl",
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn export_dataset(
+    app: AppHandle,
+    request: DatasetRequest,
+) -> Result<DatasetSummary, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut job = jobs::start(&app, "export.dataset", "Fine-tuning dataset export");
+        let result = export(&app.state::<Database>().conn(), &request, &mut job);
+        match &result {
+            Ok(summary) => {
+                let written = summary.train + summary.validation;
+                job.finish(None, Some(&format!("{written} records written")));
+            }
+            Err(e) => job.finish(Some(&e.to_string()), None),
+        }
+        result
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+fn export(
+    conn: &Connection,
+    request: &DatasetRequest,
+    job: &mut JobHandle,
+) -> Result<DatasetSummary, GibberError> {
+    if !(0.0..=MAX_VALIDATION_FRACTION).contains(&request.validation_fraction) {
+        return Err(GibberError::new(
+            "INVALID_INPUT",
+            "The validation fraction must be between 0 and 0.5",
+        ));
+    }
+    let validation_path = match &request.validation_path {
+        Some(path) => Some(path),
+        None if request.validation_fraction > 0.0 => {
+            return Err(GibberError::new(
+                "INVALID_INPUT",
+                "A validation split needs a validation file",
+            ));
+        }
+        None => None,
+    };
+
+    let mut train = std::io::BufWriter::new(std::fs::File::create(&request.train_path)?);
+    let mut validation = validation_path
+        .map(|path| std::fs::File::create(path).map(std::io::BufWriter::new))
+        .transpose()?;
+    let summary = build(
+        conn,
+        request,
+        &mut train,
+        validation
+            .as_mut()
+            .map(|file| file as &mut dyn std::io::Write),
+        Some(job),
+    )?;
+    train.flush()?;
+    if let Some(file) = validation.as_mut() {
+        file.flush()?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::conversations::{NewConversation, NewMessage};
+
+    fn conversation(conn: &Connection, model: &str, answer: &str) -> String {
+        let conversation = conversations::insert_conversation(
+            conn,
+            &NewConversation {
+                system_prompt: Some("Be brief".to_string()),
+                ..NewConversation::default()
+            },
+        )
+        .unwrap();
+        for (role, content) in [
+            (MessageRole::User, "Who mixed this?"),
+            (MessageRole::Assistant, answer),
+            (MessageRole::User, "Thanks"),
+        ] {
+            let message = NewMessage {
+                role,
+                content: content.to_string(),
+                model: Some(model.to_string()),
+                usage: None,
+            };
+            conversations::insert_message(conn, &conversation.id, &message).unwrap();
+        }
+        conversation.id
+    }
+
+    fn request(filter: DatasetFilter) -> DatasetRequest {
+        DatasetRequest {
+            format: DatasetFormat::ShareGpt,
+            filter,
+            redact: Some(DeidentifyOptions {
+                names: vec!["Ana Lopez".to_string()],
+            }),
+            validation_fraction: 0.0,
+            seed: 0,
+            train_path: String::new(),
+            validation_path: None,
+        }
+    }
+
+    #[test]
+    fn test_sharegpt_records_are_redacted_and_trimmed() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        conversation(&conn, "openai/gpt-4o", "Ana Lopez did");
+        let mut train = Vec::new();
+        let summary = build(
+            &conn,
+            &request(DatasetFilter::default()),
+            &mut train,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(summary.train, 1);
+        assert_eq!(summary.redacted, 1);
+        let record: Value = serde_json::from_slice(&train).unwrap();
+        assert_eq!(
+            record,
+            json!({ "conversations": [
+                { "from": "system", "value": "Be brief" },
+                { "from": "human", "value": "Who mixed this?" },
+                { "from": "gpt", "value": "[NAME_1] did" },
+            ] })
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let tagged = conversation(&conn, "openai/gpt-4o", "Ana");
+        conversation(&conn, "google/gemini-2.5-pro", "Ana");
+        conn.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag) VALUES (?1, 'mixing')",
+            [&tagged],
+        )
+        .unwrap();
+
+        let count = |filter: DatasetFilter| {
+            build(&conn, &request(filter), &mut Vec::new(), None, None)
+                .unwrap()
+                .train
+        };
+        let tags = DatasetFilter {
+            tags: vec!["Mixing".to_string()],
+            ..DatasetFilter::default()
+        };
+        assert_eq!(count(tags), 1);
+        let models = DatasetFilter {
+            models: vec!["google/gemini-2.5-pro".to_string()],
+            ..DatasetFilter::default()
+        };
+        assert_eq!(count(models), 1);
+        let rated = DatasetFilter {
+            rating: Some(Rating::Up),
+            ..DatasetFilter::default()
+        };
+        assert_eq!(count(rated), 0);
+    }
+
+    #[test]
+    fn test_split_is_stable() {
+        let picks: Vec<bool> = (0..200)
+            .map(|i| is_validation(&format!("c{i}"), 7, 0.2))
+            .collect();
+        let validation = picks.iter().filter(|&&pick| pick).count();
+        assert!((20..=60).contains(&validation));
+        assert_eq!(is_validation("c1", 7, 0.2), picks[1]);
+        assert!(!is_validation("c1", 7, 0.0));
+    }
+}
@@ -0,0 +1,727 @@
+//! Date and time awareness.
+//!
+//! Models don't know when "now" is. With `datetimeContext` on in the
+//! general settings (the default), backend completions get a line in their
+//! system prompt with the local date, time, timezone, and locale; the
+//! frontend AI client adds the same line from `get_datetime_context`.
+//!
+//! `resolve_datetime`, also offered to the model as a tool, turns phrases
+//! like "next Tuesday at 3pm" or "in 2 hours" into a concrete local date
+//! and time, so the model doesn't have to count days itself.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    Offset, TimeZone, Timelike, Weekday,
+};
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{ChatMessage, ChatRequest, MessageRole};
+use crate::commands::i18n;
+use crate::commands::plugins::PluginTool;
+use crate::commands::settings;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Longest phrase `resolve_datetime` accepts, in characters.
+const MAX_PHRASE_CHARS: usize = 200;
+
+/// The moment a prompt was sent, as told to the model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DateTimeContext {
+    /// Local time as RFC 3339
+    pub now: String,
+    /// IANA timezone name, or the UTC offset if it can't be determined
+    pub timezone: String,
+    /// OS locale as a BCP 47 tag
+    pub locale: Option<String>,
+    /// The line added to the system prompt
+    pub prompt: String,
+}
+
+/// A phrase resolved by `resolve_datetime`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedDateTime {
+    /// RFC 3339 timestamp; midnight when the phrase names no time
+    pub datetime: String,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    /// `HH:MM`, when the phrase names a time
+    pub time: Option<String>,
+    /// English weekday name
+    pub weekday: String,
+    /// Timezone the phrase was resolved in
+    pub timezone: String,
+}
+
+fn timezone_name(offset: FixedOffset) -> String {
+    iana_time_zone::get_timezone().unwrap_or_else(|_| offset.to_string())
+}
+
+fn context_at(
+    now: DateTime<FixedOffset>,
+    timezone: String,
+    locale: Option<String>,
+) -> DateTimeContext {
+    let mut prompt = format!(
+        "The current date and time is {} ({timezone}, UTC{}).",
+        now.format("%A, %-d %B %Y, %H:%M"),
+        now.format("%:z"),
+    );
+    if let Some(locale) = &locale {
+        let _ = write!(prompt, " The user's locale is {locale}.");
+    }
+    DateTimeContext {
+        now: now.to_rfc3339(),
+        timezone,
+        locale,
+        prompt,
+    }
+}
+
+fn current_context() -> DateTimeContext {
+    let now = Local::now().fixed_offset();
+    context_at(now, timezone_name(*now.offset()), i18n::system_locale())
+}
+
+fn enabled(conn: &rusqlite::Connection) -> bool {
+    settings::load(conn).is_ok_and(|settings| settings.datetime_context)
+}
+
+/// Adds `prompt` to the system message of `messages`, adding one at the
+/// start if there is none.
+pub(crate) fn add_to_system_prompt(messages: &mut Vec<ChatMessage>, prompt: &str) {
+    match messages.first_mut() {
+        Some(message) if message.role == MessageRole::System => {
+            message.content = format!("{}\n\n{prompt}", message.content.trim_end());
+        }
+        _ => messages.insert(0, ChatMessage::new(MessageRole::System, prompt)),
+    }
+}
+
+/// Returns `request` with the date and time added to its system prompt,
+/// or unchanged if `datetimeContext` is off.
+pub(crate) fn with_context<'a>(app: &AppHandle, request: &'a ChatRequest) -> Cow<'a, ChatRequest> {
+    if !enabled(&app.state::<Database>().conn()) {
+        return Cow::Borrowed(request);
+    }
+    let mut request = request.clone();
+    add_to_system_prompt(&mut request.messages, &current_context().prompt);
+    Cow::Owned(request)
+}
+
+/// What a phrase has named so far.
+#[derive(Debug, Default)]
+struct Found {
+    date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+}
+
+impl Found {
+    /// Takes the date of `resolved`, and its time if it has one.
+    fn set(&mut self, resolved: Resolved) {
+        self.date = Some(resolved.date);
+        if resolved.time.is_some() {
+            self.time = resolved.time;
+        }
+    }
+}
+
+/// The date and optional time a phrase names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Resolved {
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PartOfDay {
+    Morning,
+    Afternoon,
+    Evening,
+    Night,
+}
+
+impl PartOfDay {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "morning" => Some(Self::Morning),
+            "afternoon" => Some(Self::Afternoon),
+            "evening" => Some(Self::Evening),
+            "night" | "tonight" => Some(Self::Night),
+            _ => None,
+        }
+    }
+
+    /// Time used when the phrase names no hour.
+    const fn default_hour(self) -> u32 {
+        match self {
+            Self::Morning => 9,
+            Self::Afternoon => 15,
+            Self::Evening => 19,
+            Self::Night => 21,
+        }
+    }
+}
+
+/// Words that carry no date or time.
+const FILLER: [&str; 10] = [
+    "at", "on", "the", "of", "by", "around", "about", "and", "o'clock", "from",
+];
+
+fn weekday(word: &str) -> Option<Weekday> {
+    let weekday = match word.trim_end_matches('s') {
+        "monday" | "mon" => Weekday::Mon,
+        "tuesday" | "tue" | "tues" => Weekday::Tue,
+        "wednesday" | "wed" => Weekday::Wed,
+        "thursday" | "thu" | "thur" | "thurs" => Weekday::Thu,
+        "friday" | "fri" => Weekday::Fri,
+        "saturday" | "sat" => Weekday::Sat,
+        "sunday" | "sun" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let word = word.trim_end_matches('.');
+    if word.len() < 3 {
+        return None;
+    }
+    let index = MONTHS.iter().position(|month| month.starts_with(word))?;
+    u32::try_from(index + 1).ok()
+}
+
+fn number(word: &str) -> Option<u32> {
+    const WORDS: [&str; 13] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        _ => WORDS
+            .iter()
+            .position(|w| *w == word)
+            .and_then(|n| u32::try_from(n).ok())
+            .or_else(|| word.parse().ok()),
+    }
+}
+
+/// A day of the month such as `20` or `20th`.
+fn day_of_month(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn year(word: &str) -> Option<i32> {
+    word.parse().ok().filter(|_| word.len() == 4)
+}
+
+/// The first date on or after `from` with the given month and day.
+fn next_occurrence(from: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let this_year = NaiveDate::from_ymd_opt(from.year(), month, day);
+    match this_year {
+        Some(date) if date >= from => Some(date),
+        _ => NaiveDate::from_ymd_opt(from.year() + 1, month, day),
+    }
+}
+
+/// The first `weekday` after `from`, or on it when `inclusive`.
+fn next_weekday(from: NaiveDate, weekday: Weekday, inclusive: bool) -> NaiveDate {
+    let ahead = i64::from(weekday.num_days_from_monday())
+        - i64::from(from.weekday().num_days_from_monday());
+    let ahead = match ahead.rem_euclid(7) {
+        0 if !inclusive => 7,
+        days => days,
+    };
+    from + Duration::days(ahead)
+}
+
+/// The latest `weekday` before `from`.
+fn previous_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let back = i64::from(from.weekday().num_days_from_monday())
+        - i64::from(weekday.num_days_from_monday());
+    let back = match back.rem_euclid(7) {
+        0 => 7,
+        days => days,
+    };
+    from - Duration::days(back)
+}
+
+/// `2026-10-20`, `20.10.2026`, `10/20/2026` or `10/20`, with slashed
+/// dates read month first when `month_first`.
+fn numeric_date(word: &str, today: NaiveDate, month_first: bool) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let (parts, month_first): (Vec<&str>, bool) = if word.contains('/') {
+        (word.split('/').collect(), month_first)
+    } else if word.contains('.') {
+        (
+            word.split('.').filter(|part| !part.is_empty()).collect(),
+            false,
+        )
+    } else {
+        return None;
+    };
+    let numbers: Vec<u32> = parts
+        .iter()
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let (first, second) = (*numbers.first()?, *numbers.get(1)?);
+    let (month, day) = if month_first {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    match numbers.get(2) {
+        None => next_occurrence(today, month, day),
+        Some(&year) => {
+            let year = if year < 100 { year + 2000 } else { year };
+            NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, day)
+        }
+    }
+}
+
+/// `15:30`, `3pm`, `3:30pm`, `3` and `3:30` followed by `pm`, or `3`
+/// after `at`. Returns the time, whether `am` or `pm` was given, and how
+/// many extra words were read.
+fn clock_time(word: &str, next: Option<&str>, after_at: bool) -> Option<(NaiveTime, bool, usize)> {
+    let (digits, mut meridiem) = match word
+        .strip_suffix("am")
+        .or_else(|| word.strip_suffix("a.m."))
+    {
+        Some(digits) => (digits, Some(false)),
+        None => match word
+            .strip_suffix("pm")
+            .or_else(|| word.strip_suffix("p.m."))
+        {
+            Some(digits) => (digits, Some(true)),
+            None => (word, None),
+        },
+    };
+    let mut consumed = 0;
+    if meridiem.is_none() {
+        meridiem = match next {
+            Some("am" | "a.m.") => Some(false),
+            Some("pm" | "p.m.") => Some(true),
+            _ => None,
+        };
+        if meridiem.is_some() {
+            consumed = 1;
+        }
+    }
+    let (hour, minute) = match digits.split_once([':', 'h']) {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if meridiem.is_some() || after_at => (digits.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+    Some((
+        NaiveTime::from_hms_opt(hour, minute, 0)?,
+        meridiem.is_some(),
+        consumed,
+    ))
+}
+
+/// Shifts `now` by `amount` units, returning what the shift determines:
+/// the date, and the time for units shorter than a day.
+fn shift(now: NaiveDateTime, amount: i64, unit: &str) -> Option<Resolved> {
+    let unit = unit.trim_end_matches('s');
+    let months = |count: i64| {
+        let months = Months::new(u32::try_from(count.unsigned_abs()).ok()?);
+        if count < 0 {
+            now.date().checked_sub_months(months)
+        } else {
+            now.date().checked_add_months(months)
+        }
+    };
+    let timed = |duration: Duration| {
+        let at = now.checked_add_signed(duration)?;
+        Some(Resolved {
+            date: at.date(),
+            time: Some(at.time()),
+        })
+    };
+    let dated = |date: Option<NaiveDate>| date.map(|date| Resolved { date, time: None });
+    match unit {
+        "minute" | "min" => timed(Duration::try_minutes(amount)?),
+        "hour" | "hr" | "h" => timed(Duration::try_hours(amount)?),
+        "day" => dated(now.date().checked_add_signed(Duration::try_days(amount)?)),
+        "week" | "wk" => dated(now.date().checked_add_signed(Duration::try_weeks(amount)?)),
+        "fortnight" => dated(
+            now.date()
+                .checked_add_signed(Duration::try_weeks(amount * 2)?),
+        ),
+        "month" => dated(months(amount)),
+        "year" | "yr" => dated(months(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// Resolves a phrase against `now`.
+///
+/// Bare and `next` weekdays are the first such day after today, `this`
+/// weekdays may be today, and `last` weekdays are the latest before
+/// today. A time already past today with no date given means tomorrow.
+#[allow(clippy::too_many_lines)] // one loop over the words, with a branch per kind
+fn resolve(text: &str, now: NaiveDateTime, month_first: bool) -> Result<Resolved, GibberError> {
+    let lowered = text.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|word| word.trim_end_matches(['!', '?']))
+        .filter(|word| !word.is_empty())
+        .collect();
+    let today = now.date();
+    let mut found = Found::default();
+    let mut meridiem = false;
+    let mut part: Option<PartOfDay> = None;
+    let unknown = |word: &str| GibberError::invalid(format!("Couldn't understand \"{word}\""));
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i].trim_end_matches('.');
+        let next = words.get(i + 1).copied();
+        let after_at = i > 0 && words[i - 1] == "at";
+        match word {
+            _ if FILLER.contains(&word) => {}
+            "now" | "right" if word == "now" || next == Some("now") => {
+                found.set(Resolved {
+                    date: today,
+                    time: Some(now.time()),
+                });
+                i += usize::from(word == "right");
+            }
+            "today" => found.date = Some(today),
+            "tonight" => {
+                found.date = Some(today);
+                part = Some(PartOfDay::Night);
+            }
+            "tomorrow" | "tmrw" => found.date = Some(today + Duration::days(1)),
+            "yesterday" => found.date = Some(today - Duration::days(1)),
+            "day" if next == Some("after") && words.get(i + 2) == Some(&"tomorrow") => {
+                found.date = Some(today + Duration::days(2));
+                i += 2;
+            }
+            "day" if next == Some("before") && words.get(i + 2) == Some(&"yesterday") => {
+                found.date = Some(today - Duration::days(2));
+                i += 2;
+            }
+            "noon" | "midday" => found.time = NaiveTime::from_hms_opt(12, 0, 0),
+            "midnight" => found.time = NaiveTime::from_hms_opt(0, 0, 0),
+            _ if PartOfDay::parse(word).is_some() => part = PartOfDay::parse(word),
+            "next" | "this" | "last" | "coming" => {
+                let target = next.ok_or_else(|| unknown(word))?;
+                let step = match word {
+                    "last" => -1,
+                    "this" => 0,
+                    _ => 1,
+                };
+                if let Some(weekday) = weekday(target) {
+                    found.date = Some(match word {
+                        "last" => previous_weekday(today, weekday),
+                        "this" => next_weekday(today, weekday, true),
+                        _ => next_weekday(today, weekday, false),
+                    });
+                } else if let Some(resolved) = shift(now, step, target) {
+                    found.set(Resolved {
+                        time: None,
+                        ..resolved
+                    });
+                } else {
+                    return Err(unknown(target));
+                }
+                i += 1;
+            }
+            "in" | "within" => {
+                let amount = next.and_then(number).ok_or_else(|| unknown(word))?;
+                let unit = words.get(i + 2).copied().ok_or_else(|| unknown(word))?;
+                found.set(shift(now, i64::from(amount), unit).ok_or_else(|| unknown(unit))?);
+                i += 2;
+            }
+            _ if weekday(word).is_some() => {
+                found.date = weekday(word).map(|weekday| next_weekday(today, weekday, false));
+            }
+            _ if month(word).is_some() => {
+                let month = month(word).unwrap_or(1);
+                let day = next.and_then(day_of_month);
+                let day = match day {
+                    Some(day) => {
+                        i += 1;
+                        day
+                    }
+                    None => 1,
+                };
+                found.date = match words.get(i + 1).and_then(|word| year(word)) {
+                    Some(year) => {
+                        i += 1;
+                        NaiveDate::from_ymd_opt(year, month, day)
+                    }
+                    None => next_occurrence(today, month, day),
+                };
+                if found.date.is_none() {
+                    return Err(unknown(text));
+                }
+            }
+            _ => {
+                if let Some(resolved) = numeric_date(word, today, month_first) {
+                    found.date = Some(resolved);
+                } else if let Some((clock, explicit, consumed)) = clock_time(word, next, after_at) {
+                    found.time = Some(clock);
+                    meridiem = explicit;
+                    i += consumed;
+                } else if let (Some(day), Some(month)) = (day_of_month(word), next.and_then(month))
+                {
+                    i += 1;
+                    found.date = match words.get(i + 1).and_then(|word| year(word)) {
+                        Some(year) => {
+                            i += 1;
+                            NaiveDate::from_ymd_opt(year, month, day)
+                        }
+                        None => next_occurrence(today, month, day),
+                    };
+                    if found.date.is_none() {
+                        return Err(unknown(text));
+                    }
+                } else if let (Some(amount), Some(unit)) = (number(word), next) {
+                    let amount = i64::from(amount);
+                    let (amount, consumed) = match words.get(i + 2).copied() {
+                        Some("ago" | "before" | "earlier") => (-amount, 2),
+                        Some("later" | "after" | "hence") => (amount, 2),
+                        Some("from") if words.get(i + 3) == Some(&"now") => (amount, 3),
+                        _ => return Err(unknown(word)),
+                    };
+                    found.set(shift(now, amount, unit).ok_or_else(|| unknown(unit))?);
+                    i += consumed;
+                } else {
+                    return Err(unknown(word));
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if let Some(part) = part {
+        found.time = match found.time {
+            Some(clock) if !meridiem && clock.hour() < 12 && part != PartOfDay::Morning => {
+                clock.overflowing_add_signed(Duration::hours(12)).0.into()
+            }
+            Some(clock) => Some(clock),
+            None => NaiveTime::from_hms_opt(part.default_hour(), 0, 0),
+        };
+    }
+    match (found.date, found.time) {
+        (None, None) => Err(GibberError::invalid(format!(
+            "\"{text}\" names no date or time"
+        ))),
+        (None, Some(clock)) if clock <= now.time() => Ok(Resolved {
+            date: today + Duration::days(1),
+            time: Some(clock),
+        }),
+        (None, time) => Ok(Resolved { date: today, time }),
+        (Some(date), time) => Ok(Resolved { date, time }),
+    }
+}
+
+/// Whether slashed dates are written month first in `locale`.
+fn month_first(locale: Option<&str>) -> bool {
+    match locale {
+        Some(locale) => matches!(locale, "en" | "en-US" | "en-PH" | "en-CA" | "es-US"),
+        None => true,
+    }
+}
+
+/// Tool definition of `resolve_datetime`, offered by `get_data_tools`.
+pub(crate) fn tool() -> PluginTool {
+    PluginTool {
+        name: "resolve_datetime".to_string(),
+        description: "Resolves a date or time phrase such as \"next Tuesday at 3pm\", \"in 2 \
+                      hours\" or \"March 3rd\" to an exact date and time in the user's timezone."
+            .to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": { "text": { "type": "string" } },
+            "required": ["text"]
+        })
+        .to_string(),
+    }
+}
+
+/// Returns the date and time line for system prompts, or `None` if
+/// `datetimeContext` is off in the general settings.
+///
+/// # Example
+///
+/// ```typescript
+/// const context = await invoke("get_datetime_context");
+/// if (context) system = `${system}\n\n${context.prompt}`;
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_datetime_context(db: State<'_, Database>) -> Option<DateTimeContext> {
+    enabled(&db.conn()).then(current_context)
+}
+
+/// Resolves a date or time phrase to a concrete local date and time.
+///
+/// `reference` is the RFC 3339 time to resolve against, and sets the
+/// timezone; it defaults to now in the local timezone. Slashed dates are
+/// read in the order of the OS locale.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if the phrase or
+/// `reference` can't be understood.
+///
+/// # Example
+///
+/// ```typescript
+/// const { datetime } = await invoke("resolve_datetime", { text: "next Tuesday at 3pm" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub fn resolve_datetime(
+    text: &str,
+    reference: Option<String>,
+) -> Result<ResolvedDateTime, GibberError> {
+    if text.chars().count() > MAX_PHRASE_CHARS {
+        return Err(GibberError::invalid(format!(
+            "The phrase is longer than {MAX_PHRASE_CHARS} characters"
+        )));
+    }
+    let (now, timezone, local) = if let Some(reference) = reference {
+        let now = DateTime::parse_from_rfc3339(&reference)
+            .map_err(|e| GibberError::invalid(format!("Invalid reference time: {e}")))?;
+        (now, now.offset().to_string(), false)
+    } else {
+        let now = Local::now().fixed_offset();
+        (now, timezone_name(*now.offset()), true)
+    };
+    let resolved = resolve(
+        text,
+        now.naive_local(),
+        month_first(i18n::system_locale().as_deref()),
+    )?;
+    let naive = resolved
+        .date
+        .and_time(resolved.time.unwrap_or(NaiveTime::MIN));
+    // The local offset of the resolved day, which differs from today's
+    // across a daylight saving change.
+    let offset = if local {
+        Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map_or(*now.offset(), |at| at.offset().fix())
+    } else {
+        *now.offset()
+    };
+    let datetime = offset
+        .from_local_datetime(&naive)
+        .single()
+        .ok_or_else(|| GibberError::invalid(format!("\"{text}\" is not a valid local time")))?;
+    Ok(ResolvedDateTime {
+        datetime: datetime.to_rfc3339(),
+        date: resolved.date.format("%Y-%m-%d").to_string(),
+        time: resolved.time.map(|time| time.format("%H:%M").to_string()),
+        weekday: resolved.date.format("%A").to_string(),
+        timezone,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> (String, Option<String>) {
+        // Wednesday 2026-10-14, 16:20.
+        let now = NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(16, 20, 0)
+            .unwrap();
+        let resolved = resolve(text, now, true).unwrap();
+        (
+            resolved.date.to_string(),
+            resolved.time.map(|time| time.format("%H:%M").to_string()),
+        )
+    }
+
+    #[test]
+    fn test_resolve_phrases() {
+        let date = |text| at(text).0;
+        assert_eq!(
+            at("next Tuesday at 3pm"),
+            ("2026-10-20".to_string(), Some("15:00".into()))
+        );
+        assert_eq!(
+            at("tomorrow morning"),
+            ("2026-10-15".to_string(), Some("09:00".into()))
+        );
+        assert_eq!(
+            at("in 2 hours"),
+            ("2026-10-14".to_string(), Some("18:20".into()))
+        );
+        assert_eq!(at("at 3"), ("2026-10-15".to_string(), Some("03:00".into())));
+        assert_eq!(
+            at("friday evening at 7:30"),
+            ("2026-10-16".into(), Some("19:30".into()))
+        );
+        assert_eq!(date("wednesday"), "2026-10-21");
+        assert_eq!(date("this wednesday"), "2026-10-14");
+        assert_eq!(date("last wednesday"), "2026-10-07");
+        assert_eq!(date("3 weeks ago"), "2026-09-23");
+        assert_eq!(date("next month"), "2026-11-14");
+        assert_eq!(date("March 3rd"), "2027-03-03");
+        assert_eq!(date("20 October 2025"), "2025-10-20");
+        assert_eq!(date("10/20"), "2026-10-20");
+        assert_eq!(date("20.10.2026"), "2026-10-20");
+        assert_eq!(date("the day after tomorrow"), "2026-10-16");
+        assert!(resolve("whenever", NaiveDateTime::MIN, true).is_err());
+        assert!(resolve("", NaiveDateTime::MIN, true).is_err());
+    }
+
+    #[test]
+    fn test_context_joins_system_prompt() {
+        let now = DateTime::parse_from_rfc3339("2026-10-14T16:20:00+02:00").unwrap();
+        let context = context_at(now, "Europe/Berlin".to_string(), Some("de-DE".to_string()));
+        assert_eq!(
+            context.prompt,
+            "The current date and time is Wednesday, 14 October 2026, 16:20 \
+             (Europe/Berlin, UTC+02:00). The user's locale is de-DE."
+        );
+
+        let mut messages = vec![ChatMessage::new(MessageRole::User, "hi")];
+        add_to_system_prompt(&mut messages, "Now.");
+        add_to_system_prompt(&mut messages, "Later.");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "Now.\n\nLater.");
+        assert!(!month_first(Some("de-DE")));
+    }
+}
@@ -0,0 +1,421 @@
+//! `gibber://` deep link handling.
+//!
+//! Links let launchers (Raycast, Alfred) and browsers drive the app:
+//!
+//! - `gibber://new?prompt=...&model=...` - start a conversation with a prompt
+//! - `gibber://conversation/<id>` - open an existing conversation
+//! - `gibber://import?url=...` - import content from an http(s) URL
+//! - `gibber://read-later?url=...` - save an http(s) URL to the reading list
+//! - `gibber://input?text=...&action=...` - run a quick action on text from
+//!   another app (see [`external_input`])
+//!
+//! Each link is routed to a backend handler that prepares whatever the UI needs
+//! and then emits a [`events::DEEP_LINK_NAVIGATE`] to the main window. Links
+//! that arrive before the frontend is listening (the one that launched the app)
+//! are queued and handed out by `take_pending_navigation`. Input links go to
+//! the quick-capture window instead.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, Url};
+
+use crate::commands::conversations;
+use crate::commands::events;
+use crate::commands::external_input::{self, ExternalInput, InputSource, QuickAction};
+use crate::commands::instance::URL_SCHEME;
+use crate::commands::quick_capture;
+use crate::commands::reading_list;
+use crate::db::Database;
+
+/// Longest prompt accepted from a link, in characters.
+const MAX_PROMPT_CHARS: usize = 32_000;
+
+/// A parsed deep link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    /// Start a new conversation, optionally with a prompt
+    New {
+        /// Prompt to submit; empty opens a blank conversation
+        prompt: String,
+        /// Model requested for the answer
+        model: Option<String>,
+    },
+    /// Open an existing conversation
+    OpenConversation {
+        /// Conversation ID
+        id: String,
+    },
+    /// Import content from a URL
+    Import {
+        /// The http(s) URL to import
+        url: String,
+    },
+    /// Save a URL to the reading list
+    ReadLater {
+        /// The http(s) URL to save
+        url: String,
+    },
+    /// Hand text from another app to the quick-capture window
+    Input(ExternalInput),
+}
+
+/// Navigation instruction for the frontend.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Navigation {
+    /// Show a new conversation; answer it if a prompt was given
+    #[serde(rename_all = "camelCase")]
+    NewConversation {
+        /// Conversation created for the prompt, if any
+        conversation_id: Option<String>,
+        /// The prompt from the link
+        prompt: String,
+        /// Model requested for the answer
+        model: Option<String>,
+    },
+    /// Show an existing conversation
+    #[serde(rename_all = "camelCase")]
+    OpenConversation {
+        /// Conversation ID
+        conversation_id: String,
+    },
+    /// Start an import of the given URL
+    #[serde(rename_all = "camelCase")]
+    Import {
+        /// The URL to import
+        url: String,
+    },
+    /// Show the reading list with the saved item
+    #[serde(rename_all = "camelCase")]
+    ReadingList {
+        /// Reading list item ID
+        item_id: String,
+    },
+}
+
+/// Payload of [`events::DEEP_LINK_ERROR`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+pub struct DeepLinkError {
+    /// The link that failed
+    pub url: String,
+    /// Human-readable error message
+    pub message: String,
+}
+
+/// Navigations from links that arrived before the frontend was ready.
+#[derive(Default)]
+pub struct PendingNavigation(Mutex<Vec<Navigation>>);
+
+/// Parses a `gibber://` link.
+///
+/// # Errors
+///
+/// Returns a message describing why the link is not understood.
+pub fn parse(url: &Url) -> Result<DeepLink, String> {
+    if url.scheme() != URL_SCHEME {
+        return Err(format!("Unsupported scheme \"{}\"", url.scheme()));
+    }
+    let query = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    match url.host_str() {
+        Some("new") => {
+            let prompt = query("prompt").unwrap_or_default();
+            if prompt.chars().count() > MAX_PROMPT_CHARS {
+                return Err(format!("Prompt exceeds {MAX_PROMPT_CHARS} characters"));
+            }
+            Ok(DeepLink::New {
+                prompt: prompt.trim().to_string(),
+                model: query("model").filter(|m| !m.is_empty()),
+            })
+        }
+        Some("conversation") => url
+            .path_segments()
+            .and_then(|mut segments| segments.find(|s| !s.is_empty()))
+            .map(|id| DeepLink::OpenConversation { id: id.to_string() })
+            .ok_or_else(|| "Missing conversation ID".to_string()),
+        Some("import") => {
+            let target = query("url").ok_or("Missing url parameter")?;
+            let parsed = Url::parse(&target).map_err(|e| format!("Invalid import URL: {e}"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err("Only http and https URLs can be imported".to_string());
+            }
+            Ok(DeepLink::Import {
+                url: parsed.to_string(),
+            })
+        }
+        Some("read-later") => {
+            let target = query("url").ok_or("Missing url parameter")?;
+            let parsed = Url::parse(&target).map_err(|e| format!("Invalid URL: {e}"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err("Only http and https URLs can be saved".to_string());
+            }
+            Ok(DeepLink::ReadLater {
+                url: parsed.to_string(),
+            })
+        }
+        Some("input") => {
+            let text = query("text").unwrap_or_default();
+            if text.trim().is_empty() {
+                return Err("Missing text parameter".to_string());
+            }
+            if text.chars().count() > external_input::MAX_TEXT_CHARS {
+                return Err(format!(
+                    "Text exceeds {} characters",
+                    external_input::MAX_TEXT_CHARS
+                ));
+            }
+            let action = match query("action").filter(|a| !a.is_empty()) {
+                Some(slug) => Some(
+                    QuickAction::from_slug(&slug)
+                        .ok_or_else(|| format!("Unknown quick action \"{slug}\""))?,
+                ),
+                None => None,
+            };
+            Ok(DeepLink::Input(ExternalInput {
+                text,
+                action,
+                source: InputSource::from_param(query("source").as_deref()),
+            }))
+        }
+        Some(other) => Err(format!("Unknown link action \"{other}\"")),
+        None => Err("Missing link action".to_string()),
+    }
+}
+
+/// Runs the backend side of a link and returns the resulting navigation,
+/// if the main window has anything to show.
+fn route(app: &AppHandle, link: DeepLink) -> Result<Option<Navigation>, String> {
+    let db = app.state::<Database>();
+    match link {
+        DeepLink::New { prompt, model } => {
+            let conversation_id = if prompt.is_empty() {
+                None
+            } else {
+                let conversation = conversations::insert_prompt_conversation(
+                    &db.conn(),
+                    &prompt,
+                    model.as_deref(),
+                    "deep-link",
+                )
+                .map_err(|e| e.to_string())?;
+                Some(conversation.id)
+            };
+            Ok(Some(Navigation::NewConversation {
+                conversation_id,
+                prompt,
+                model,
+            }))
+        }
+        DeepLink::OpenConversation { id } => {
+            if conversations::conversation_exists(&db.conn(), &id).map_err(|e| e.to_string())? {
+                Ok(Some(Navigation::OpenConversation {
+                    conversation_id: id,
+                }))
+            } else {
+                Err(format!("Conversation {id} not found"))
+            }
+        }
+        DeepLink::Import { url } => Ok(Some(Navigation::Import { url })),
+        DeepLink::ReadLater { url } => {
+            let item = reading_list::add(app, &url, "deep-link").map_err(|e| e.to_string())?;
+            Ok(Some(Navigation::ReadingList { item_id: item.id }))
+        }
+        DeepLink::Input(input) => {
+            external_input::receive(app, input);
+            Ok(None)
+        }
+    }
+}
+
+/// Parses, routes, and delivers a link.
+///
+/// With `queue` set, the navigation is stored for `take_pending_navigation`
+/// instead of being emitted.
+fn handle_url(app: &AppHandle, url: &Url, queue: bool) {
+    match parse(url).and_then(|link| route(app, link)) {
+        Ok(None) => {}
+        Ok(Some(navigation)) if queue => app
+            .state::<PendingNavigation>()
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(navigation),
+        Ok(Some(navigation)) => {
+            if let Err(e) = quick_capture::focus_main_window(app) {
+                tracing::warn!("failed to focus main window: {e}");
+            }
+            if let Err(e) = events::emit_typed_to(
+                app,
+                quick_capture::MAIN_WINDOW_LABEL,
+                events::DEEP_LINK_NAVIGATE,
+                &navigation,
+            ) {
+                tracing::warn!("failed to emit navigation: {e}");
+            }
+        }
+        Err(message) => {
+            let payload = DeepLinkError {
+                url: url.to_string(),
+                message,
+            };
+            events::emit_typed(app, events::DEEP_LINK_ERROR, &payload);
+        }
+    }
+}
+
+/// Registers the URL scheme and starts listening for links.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+///
+/// # Errors
+///
+/// Returns an error if the scheme cannot be registered or the launch links
+/// cannot be read.
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    app.manage(PendingNavigation::default());
+
+    // Bundled apps register the scheme at install time; this covers dev
+    // builds and portable installs on platforms that allow it at runtime.
+    #[cfg(any(windows, target_os = "linux"))]
+    app.deep_link().register_all()?;
+
+    if let Some(urls) = app.deep_link().get_current()? {
+        for url in &urls {
+            handle_url(app, url, true);
+        }
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, &url, false);
+        }
+    });
+    Ok(())
+}
+
+/// Returns navigations from links that arrived before the frontend was ready.
+///
+/// # Example
+///
+/// ```typescript
+/// for (const navigation of await invoke("take_pending_navigation")) navigate(navigation);
+/// await listen("deep-link://navigate", (e) => navigate(e.payload));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn take_pending_navigation(state: State<'_, PendingNavigation>) -> Vec<Navigation> {
+    std::mem::take(
+        &mut *state
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(url: &str) -> Result<DeepLink, String> {
+        parse(&Url::parse(url).expect("Test URL should parse"))
+    }
+
+    #[test]
+    fn test_parse_new_with_prompt() {
+        assert_eq!(
+            parse_str("gibber://new?prompt=make%20a%20beat&model=anthropic/claude-3-haiku"),
+            Ok(DeepLink::New {
+                prompt: "make a beat".to_string(),
+                model: Some("anthropic/claude-3-haiku".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_str("gibber://new"),
+            Ok(DeepLink::New {
+                prompt: String::new(),
+                model: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_conversation() {
+        assert_eq!(
+            parse_str("gibber://conversation/abc-123"),
+            Ok(DeepLink::OpenConversation {
+                id: "abc-123".to_string()
+            })
+        );
+        assert!(parse_str("gibber://conversation/").is_err());
+    }
+
+    #[test]
+    fn test_parse_import_requires_http() {
+        assert_eq!(
+            parse_str("gibber://import?url=https%3A%2F%2Fexample.com%2Fpost"),
+            Ok(DeepLink::Import {
+                url: "https://example.com/post".to_string()
+            })
+        );
+        assert!(parse_str("gibber://import?url=file%3A%2F%2F%2Fetc%2Fpasswd").is_err());
+        assert!(parse_str("gibber://import").is_err());
+    }
+
+    #[test]
+    fn test_parse_read_later() {
+        assert_eq!(
+            parse_str("gibber://read-later?url=https%3A%2F%2Fexample.com%2Fpost"),
+            Ok(DeepLink::ReadLater {
+                url: "https://example.com/post".to_string()
+            })
+        );
+        assert!(parse_str("gibber://read-later?url=javascript%3Aalert(1)").is_err());
+    }
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(
+            parse_str("gibber://input?source=services&action=fix-grammar&text=their%20going"),
+            Ok(DeepLink::Input(ExternalInput {
+                text: "their going".to_string(),
+                action: Some(QuickAction::FixGrammar),
+                source: InputSource::Services,
+            }))
+        );
+        assert!(matches!(
+            parse_str("gibber://input?text=hello"),
+            Ok(DeepLink::Input(ExternalInput {
+                action: None,
+                source: InputSource::Link,
+                ..
+            }))
+        ));
+        assert!(parse_str("gibber://input?action=ask").is_err());
+        assert!(parse_str("gibber://input?action=poeticize&text=hello").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown() {
+        assert!(parse_str("gibber://delete-everything").is_err());
+        assert!(parse_str("https://new?prompt=x").is_err());
+    }
+
+    #[test]
+    fn test_navigation_serializes_with_kind() {
+        let json = serde_json::to_value(Navigation::OpenConversation {
+            conversation_id: "abc".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json["kind"], "openConversation");
+        assert_eq!(json["conversationId"], "abc");
+    }
+}
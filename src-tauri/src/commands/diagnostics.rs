@@ -0,0 +1,429 @@
+//! Diagnostics bundle for bug reports.
+//!
+//! `generate_diagnostics` writes a zip with `report.json` (app and OS
+//! versions, keyring and database status, connectivity checks) and
+//! `logs.jsonl` (recent log entries). Nothing secret goes in: API keys are
+//! only reported as present or absent, and log text is redacted of
+//! key-like tokens, email addresses, and the home directory path.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::commands::credentials;
+use crate::commands::logs::{self, LogEntry, LogQuery, LogState};
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::telemetry;
+use crate::commands::updater;
+use crate::commands::windows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Log entries included in a bundle.
+const LOG_ENTRIES: usize = 1000;
+
+/// Time allowed for each connectivity check.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// OpenRouter endpoints used for connectivity checks.
+const OPENROUTER_MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+const OPENROUTER_KEY_URL: &str = "https://openrouter.ai/api/v1/auth/key";
+
+/// Replacement for redacted text.
+const REDACTED: &str = "[redacted]";
+
+/// Application build information.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppInfo {
+    version: String,
+    tauri_version: &'static str,
+    debug_build: bool,
+}
+
+/// Operating system information.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OsInfo {
+    name: String,
+    version: String,
+    arch: &'static str,
+}
+
+/// Whether the OS keychain works, without revealing what it holds.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyringStatus {
+    backend: &'static str,
+    available: bool,
+    openrouter_key_stored: bool,
+    error: Option<String>,
+}
+
+/// Database size, schema version, and row counts.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DatabaseStats {
+    file_size: Option<u64>,
+    schema_version: i64,
+    row_counts: Vec<(String, i64)>,
+}
+
+/// Result of reaching one endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConnectivityCheck {
+    name: &'static str,
+    url: &'static str,
+    ok: bool,
+    status: Option<u16>,
+    latency_ms: u64,
+    error: Option<String>,
+}
+
+/// Contents of `report.json`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiagnosticsReport {
+    generated_at: i64,
+    app: AppInfo,
+    os: OsInfo,
+    keyring: KeyringStatus,
+    database: DatabaseStats,
+    connectivity: Vec<ConnectivityCheck>,
+}
+
+/// A written diagnostics bundle.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsBundle {
+    /// Path of the zip file
+    pub path: String,
+    /// Size of the zip file in bytes
+    pub size: u64,
+}
+
+fn os_info() -> OsInfo {
+    let info = os_info::get();
+    OsInfo {
+        name: info.os_type().to_string(),
+        version: info.version().to_string(),
+        arch: std::env::consts::ARCH,
+    }
+}
+
+const fn keyring_backend() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "macOS Keychain"
+    } else if cfg!(target_os = "windows") {
+        "Windows Credential Manager"
+    } else {
+        "Secret Service"
+    }
+}
+
+fn keyring_status(lookup: &Result<Option<String>, GibberError>) -> KeyringStatus {
+    let (available, stored, error) = match lookup {
+        Ok(key) => (true, key.is_some(), None),
+        Err(e) => (false, false, Some(format!("{}: {e}", e.code()))),
+    };
+    KeyringStatus {
+        backend: keyring_backend(),
+        available,
+        openrouter_key_stored: stored,
+        error,
+    }
+}
+
+fn database_stats(conn: &rusqlite::Connection, file: &std::path::Path) -> DatabaseStats {
+    let schema_version = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap_or_default();
+    let tables: Vec<String> = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .unwrap_or_default();
+    let row_counts = tables
+        .into_iter()
+        .filter_map(|table| {
+            let count = conn
+                .query_row(&format!("SELECT COUNT(*) FROM \"{table}\""), [], |row| {
+                    row.get(0)
+                })
+                .ok()?;
+            Some((table, count))
+        })
+        .collect();
+    DatabaseStats {
+        file_size: std::fs::metadata(file).ok().map(|meta| meta.len()),
+        schema_version,
+        row_counts,
+    }
+}
+
+async fn check(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    service: Service,
+    name: &'static str,
+    url: &'static str,
+    bearer: Option<&str>,
+) -> ConnectivityCheck {
+    let started = Instant::now();
+    let mut request = client.get(url).timeout(CHECK_TIMEOUT);
+    if let Some(token) = bearer {
+        request = request.bearer_auth(token);
+    }
+    let result = network_activity::send(app, service, "diagnostics.check", request).await;
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    match result {
+        Ok(response) => ConnectivityCheck {
+            name,
+            url,
+            ok: response.status().is_success(),
+            status: Some(response.status().as_u16()),
+            latency_ms,
+            error: None,
+        },
+        Err(e) => ConnectivityCheck {
+            name,
+            url,
+            ok: false,
+            status: None,
+            latency_ms,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// Returns `true` for words that look like keys, tokens, or addresses.
+fn looks_sensitive(word: &str) -> bool {
+    if word.starts_with("sk-") || word.starts_with("nsec1") {
+        return true;
+    }
+    if let Some((user, domain)) = word.split_once('@') {
+        if !user.is_empty() && domain.contains('.') {
+            return true;
+        }
+    }
+    // Long opaque tokens, but not the UUIDs the app uses as record IDs.
+    word.len() >= 32
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+        && uuid::Uuid::parse_str(word).is_err()
+}
+
+/// Masks sensitive words in `text` and replaces the home directory with `~`.
+pub(crate) fn redact(text: &str, home: Option<&str>) -> String {
+    let text = match home {
+        Some(home) if !home.is_empty() => text.replace(home, "~"),
+        _ => text.to_string(),
+    };
+    text.split_inclusive(char::is_whitespace)
+        .map(|chunk| {
+            let word = chunk.trim_end();
+            let bare = word.trim_matches(|c: char| {
+                matches!(
+                    c,
+                    '"' | '\'' | ',' | '.' | ':' | ';' | '(' | ')' | '[' | ']' | '<' | '>'
+                )
+            });
+            if !bare.is_empty() && looks_sensitive(bare) {
+                chunk.replacen(bare, REDACTED, 1)
+            } else {
+                chunk.to_string()
+            }
+        })
+        .collect()
+}
+
+fn redact_entry(mut entry: LogEntry, home: Option<&str>) -> LogEntry {
+    entry.message = redact(&entry.message, home);
+    for value in entry.fields.values_mut() {
+        if let Value::String(text) = value {
+            *text = redact(text, home);
+        }
+    }
+    entry
+}
+
+fn write_bundle(
+    path: &std::path::Path,
+    report: &DiagnosticsReport,
+    entries: &[LogEntry],
+) -> Result<u64, GibberError> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("report.json", options)?;
+    zip.write_all(
+        serde_json::to_string_pretty(report)
+            .unwrap_or_default()
+            .as_bytes(),
+    )?;
+    zip.start_file("logs.jsonl", options)?;
+    for entry in entries {
+        zip.write_all(serde_json::to_string(entry).unwrap_or_default().as_bytes())?;
+        zip.write_all(b"\n")?;
+    }
+    zip.finish()?;
+    Ok(std::fs::metadata(path)?.len())
+}
+
+/// Writes a diagnostics zip for attaching to a bug report.
+///
+/// Saves to `path` if given, otherwise to the downloads folder as
+/// `gibber-ai-diagnostics-<timestamp>.zip`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, or `IO` if the bundle cannot be written. Failing
+/// checks are recorded in the report rather than returned.
+///
+/// # Example
+///
+/// ```typescript
+/// const { path } = await invoke("generate_diagnostics", { path: null });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn generate_diagnostics(
+    window: WebviewWindow,
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<DiagnosticsBundle, GibberError> {
+    windows::require_trusted(&window)?;
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => app.path().download_dir()?.join(format!(
+            "gibber-ai-diagnostics-{}.zip",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        )),
+    };
+    let home = app
+        .path()
+        .home_dir()
+        .ok()
+        .map(|dir| dir.to_string_lossy().into_owned());
+
+    let lookup = credentials::load_api_key(&app, "openrouter").await;
+    let keyring = keyring_status(&lookup);
+    let openrouter_key = lookup.ok().flatten();
+    let database = database_stats(
+        &app.state::<Database>().conn(),
+        &app.path().app_data_dir()?.join(db::DATABASE_FILE),
+    );
+    let query = LogQuery {
+        limit: Some(LOG_ENTRIES),
+        ..LogQuery::default()
+    };
+    let entries: Vec<LogEntry> = app
+        .try_state::<LogState>()
+        .and_then(|state| logs::read_entries(state.dir(), &query).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| redact_entry(entry, home.as_deref()))
+        .collect();
+
+    let openrouter = network::client(&app, Service::OpenRouter)?;
+    let updates = network::client(&app, Service::Updater)?;
+    let mut connectivity = vec![
+        check(
+            &app,
+            &openrouter,
+            Service::OpenRouter,
+            "openrouter",
+            OPENROUTER_MODELS_URL,
+            None,
+        )
+        .await,
+        check(
+            &app,
+            &updates,
+            Service::Updater,
+            "updates",
+            updater::load_settings(&app).channel.endpoint(),
+            None,
+        )
+        .await,
+    ];
+    if let Some(key) = openrouter_key.as_deref() {
+        connectivity.push(
+            check(
+                &app,
+                &openrouter,
+                Service::OpenRouter,
+                "openrouterKey",
+                OPENROUTER_KEY_URL,
+                Some(key),
+            )
+            .await,
+        );
+    }
+
+    let report = DiagnosticsReport {
+        generated_at: db::now_millis(),
+        app: AppInfo {
+            version: app.package_info().version.to_string(),
+            tauri_version: tauri::VERSION,
+            debug_build: cfg!(debug_assertions),
+        },
+        os: os_info(),
+        keyring,
+        database,
+        connectivity,
+    };
+    let size = write_bundle(&path, &report, &entries)?;
+    tracing::info!(size, "wrote diagnostics bundle");
+    telemetry::record_feature(&app, "diagnostics.generate");
+    Ok(DiagnosticsBundle {
+        path: path.display().to_string(),
+        size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_masks_secrets_and_home() {
+        let text = "key sk-or-v1-abc123 for me@example.com at /home/ana/.config (token: 9f8e7d6c5b4a39281706f5e4d3c2b1a0ffee).";
+        assert_eq!(
+            redact(text, Some("/home/ana")),
+            "key [redacted] for [redacted] at ~/.config (token: [redacted])."
+        );
+    }
+
+    #[test]
+    fn test_redact_keeps_ordinary_text_and_ids() {
+        let text = "failed to record run of 0b6f3f0e-4d0a-4c7e-9a53-6f2f1f9d3c21: locked";
+        assert_eq!(redact(text, None), text);
+        assert_eq!(redact("check the @mention", None), "check the @mention");
+    }
+
+    #[test]
+    fn test_database_stats_counts_rows() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        db::write_setting(&conn, "app", "{}").unwrap();
+        let stats = database_stats(&conn, std::path::Path::new("/nonexistent"));
+        assert!(stats.schema_version > 0);
+        assert!(stats.file_size.is_none());
+        assert!(stats.row_counts.contains(&("settings".to_string(), 1)));
+    }
+}
@@ -0,0 +1,181 @@
+//! Diagram rendering for exports.
+//!
+//! Mermaid and PlantUML code blocks are sent to a Kroki-compatible server,
+//! set as `diagramServer` in the general settings, which returns SVG. The
+//! public instance is the default; point it at a self-hosted one to keep
+//! diagrams on your network, or clear it to export diagrams as code.
+//!
+//! Rendering only happens for HTML exports. A block that fails to render
+//! is logged and stays a code block, so an export never fails because of a
+//! diagram.
+
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder};
+use tauri::AppHandle;
+
+use crate::commands::conversations::ConversationWithMessages;
+use crate::commands::markdown::{self, Diagrams};
+use crate::commands::network::Service;
+use crate::commands::network_activity;
+
+/// Kroki server used unless the settings name another.
+pub const DEFAULT_SERVER: &str = "https://kroki.io";
+
+/// Most diagrams rendered for one export; any beyond stay code blocks.
+const MAX_DIAGRAMS: usize = 50;
+
+/// Largest diagram source sent to the server.
+const MAX_SOURCE_BYTES: usize = 50_000;
+
+/// Largest SVG kept from a response.
+const MAX_SVG_BYTES: usize = 5_000_000;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Kroki diagram type for a code block language.
+fn diagram_type(language: &str) -> Option<&'static str> {
+    match language.to_ascii_lowercase().as_str() {
+        "mermaid" => Some("mermaid"),
+        "plantuml" | "puml" => Some("plantuml"),
+        _ => None,
+    }
+}
+
+/// Lists the distinct diagram blocks of a conversation as
+/// `(language, source)`, up to [`MAX_DIAGRAMS`].
+fn diagram_blocks(conversation: &ConversationWithMessages) -> Vec<(String, String)> {
+    let mut blocks: Vec<(String, String)> = Vec::new();
+    for message in &conversation.messages {
+        for block in markdown::fenced_blocks(&message.content) {
+            if diagram_type(&block.0).is_some()
+                && block.1.len() <= MAX_SOURCE_BYTES
+                && !blocks.contains(&block)
+            {
+                blocks.push(block);
+            }
+        }
+    }
+    blocks.truncate(MAX_DIAGRAMS);
+    blocks
+}
+
+/// Builds the request rendering one diagram to SVG.
+fn svg_request(client: &Client, server: &str, kind: &str, source: &str) -> RequestBuilder {
+    client
+        .post(format!("{}/{kind}/svg", server.trim_end_matches('/')))
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "text/plain")
+        .body(source.to_string())
+}
+
+/// Renders the diagram blocks of a conversation.
+///
+/// Requests are recorded in the network activity log when `app` is given;
+/// the CLI passes `None`.
+pub(crate) async fn render(
+    app: Option<&AppHandle>,
+    client: &Client,
+    server: &str,
+    conversation: &ConversationWithMessages,
+) -> Diagrams {
+    let mut diagrams = Diagrams::new();
+    if server.trim().is_empty() {
+        return diagrams;
+    }
+    for (language, source) in diagram_blocks(conversation) {
+        let Some(kind) = diagram_type(&language) else {
+            continue;
+        };
+        let builder = svg_request(client, server, kind, &source);
+        let response = match app {
+            Some(app) => {
+                network_activity::send(app, Service::Diagrams, "diagrams.render", builder).await
+            }
+            None => builder.send().await,
+        };
+        let svg = match response {
+            Ok(response) if response.status().is_success() => response.bytes().await,
+            Ok(response) => {
+                tracing::warn!(kind, status = %response.status(), "diagram not rendered");
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(kind, "diagram not rendered: {e}");
+                continue;
+            }
+        };
+        match svg {
+            Ok(svg) if svg.len() <= MAX_SVG_BYTES => {
+                diagrams.insert((language, source), svg.to_vec());
+            }
+            Ok(_) => tracing::warn!(kind, "diagram SVG too large"),
+            Err(e) => tracing::warn!(kind, "diagram not rendered: {e}"),
+        }
+    }
+    diagrams
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::MessageRole;
+    use crate::commands::conversations::{Conversation, StoredMessage};
+
+    fn conversation(contents: &[&str]) -> ConversationWithMessages {
+        ConversationWithMessages {
+            conversation: Conversation {
+                id: "c".to_string(),
+                title: "Diagrams".to_string(),
+                model: None,
+                system_prompt: None,
+                source: "chat".to_string(),
+                created_at: 0,
+                updated_at: 0,
+            },
+            messages: contents
+                .iter()
+                .map(|content| StoredMessage {
+                    id: "m".to_string(),
+                    conversation_id: "c".to_string(),
+                    role: MessageRole::Assistant,
+                    content: (*content).to_string(),
+                    model: None,
+                    prompt_tokens: None,
+                    completion_tokens: None,
+                    created_at: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diagram_type() {
+        assert_eq!(diagram_type("mermaid"), Some("mermaid"));
+        assert_eq!(diagram_type("PlantUML"), Some("plantuml"));
+        assert_eq!(diagram_type("puml"), Some("plantuml"));
+        assert_eq!(diagram_type("rust"), None);
+    }
+
+    #[test]
+    fn test_diagram_blocks_are_distinct() {
+        let mermaid = "```mermaid\ngraph TD; A-->B\n```\n";
+        let conversation = conversation(&[mermaid, mermaid, "```js\nlet a;\n```\n"]);
+        assert_eq!(
+            diagram_blocks(&conversation),
+            vec![("mermaid".to_string(), "graph TD; A-->B\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_svg_request() {
+        let request = svg_request(&Client::new(), "https://kroki.example/", "mermaid", "a")
+            .build()
+            .unwrap();
+        assert_eq!(request.url().as_str(), "https://kroki.example/mermaid/svg");
+        assert_eq!(
+            request.body().and_then(reqwest::Body::as_bytes),
+            Some(&b"a"[..])
+        );
+    }
+}
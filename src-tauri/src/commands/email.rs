@@ -0,0 +1,348 @@
+//! Email drafts in the system mail client.
+//!
+//! [`open_email_draft`] turns a model response into a draft the user reviews
+//! and sends from their own mail client; nothing is sent by the app. Plain
+//! drafts open as a `mailto:` URL (RFC 6068). Drafts with attached
+//! conversation exports, or too long for a URL, are written as an `.eml`
+//! file marked `X-Unsent: 1`, which Outlook, Apple Mail and Thunderbird open
+//! as an editable draft.
+
+use std::fmt::Write as _;
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::commands::conversations;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Folder under the cache directory holding `.eml` drafts.
+const DRAFTS_DIR: &str = "drafts";
+
+/// Longest `mailto:` URL handed to the OS; Windows truncates beyond ~2000.
+const MAX_MAILTO_LEN: usize = 2000;
+
+/// Base64 line length in MIME bodies (RFC 2045).
+const MIME_LINE_LEN: usize = 76;
+
+/// A draft to open.
+#[derive(Debug, Clone, Default, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmailDraft {
+    /// Recipients
+    pub to: Vec<String>,
+    /// Carbon-copy recipients
+    pub cc: Vec<String>,
+    /// Subject line
+    pub subject: String,
+    /// Plain-text body, usually a model response
+    pub body: String,
+    /// Conversations to attach as export files
+    pub conversation_ids: Vec<String>,
+}
+
+/// How a draft was handed to the mail client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum DraftMethod {
+    /// As a `mailto:` URL
+    Mailto,
+    /// As an `.eml` file
+    Eml,
+}
+
+/// Result of [`open_email_draft`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+pub struct OpenedDraft {
+    /// How the draft was opened
+    pub method: DraftMethod,
+    /// The written `.eml` file, for [`DraftMethod::Eml`]
+    pub path: Option<String>,
+}
+
+/// A file attached to an `.eml` draft.
+struct Attachment {
+    file_name: String,
+    content_type: &'static str,
+    data: Vec<u8>,
+}
+
+/// Rejects addresses that could inject headers or URL parameters.
+fn validate_address(address: &str) -> Result<(), GibberError> {
+    let valid = address.contains('@')
+        && !address
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || ",;<>\"?&".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(GibberError::new(
+            "INVALID_ADDRESS",
+            format!("Not an email address: {address}"),
+        ))
+    }
+}
+
+/// Percent-encodes everything but unreserved characters and `keep`.
+fn percent_encode(value: &str, keep: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || keep.as_bytes().contains(&byte)
+        {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+/// Normalizes line breaks to CRLF, as mail requires.
+fn crlf(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\n', "\r\n")
+}
+
+/// Builds the `mailto:` URL for a draft without attachments.
+fn mailto_url(draft: &EmailDraft) -> String {
+    let recipients: Vec<String> = draft.to.iter().map(|a| percent_encode(a, "@")).collect();
+    let mut url = format!("mailto:{}", recipients.join(","));
+    let mut fields = Vec::new();
+    if !draft.cc.is_empty() {
+        let cc: Vec<String> = draft.cc.iter().map(|a| percent_encode(a, "@")).collect();
+        fields.push(format!("cc={}", cc.join(",")));
+    }
+    if !draft.subject.is_empty() {
+        fields.push(format!("subject={}", percent_encode(&draft.subject, "")));
+    }
+    if !draft.body.is_empty() {
+        fields.push(format!("body={}", percent_encode(&crlf(&draft.body), "")));
+    }
+    if !fields.is_empty() {
+        url.push('?');
+        url.push_str(&fields.join("&"));
+    }
+    url
+}
+
+/// Encodes a header value as an RFC 2047 encoded word when it isn't ASCII.
+fn header_value(value: &str) -> String {
+    let value: String = value.chars().filter(|c| !c.is_control()).collect();
+    if value.is_ascii() {
+        value
+    } else {
+        format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(value)
+        )
+    }
+}
+
+/// Base64-encodes `data` in lines of [`MIME_LINE_LEN`].
+fn mime_base64(data: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / MIME_LINE_LEN * 2);
+    for (index, chunk) in encoded.as_bytes().chunks(MIME_LINE_LEN).enumerate() {
+        if index > 0 {
+            wrapped.push_str("\r\n");
+        }
+        wrapped.push_str(&String::from_utf8_lossy(chunk));
+    }
+    wrapped
+}
+
+/// Returns an ASCII file name for a conversation title.
+fn file_name(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let stem = stem.trim_matches('-');
+    if stem.is_empty() {
+        "conversation.json".to_string()
+    } else {
+        format!("{stem}.json")
+    }
+}
+
+/// Builds a multipart `.eml` draft.
+fn eml(draft: &EmailDraft, attachments: &[Attachment], boundary: &str) -> String {
+    let mut eml = String::new();
+    if !draft.to.is_empty() {
+        let _ = write!(eml, "To: {}\r\n", draft.to.join(", "));
+    }
+    if !draft.cc.is_empty() {
+        let _ = write!(eml, "Cc: {}\r\n", draft.cc.join(", "));
+    }
+    let _ = write!(eml, "Subject: {}\r\n", header_value(&draft.subject));
+    eml.push_str("X-Unsent: 1\r\nMIME-Version: 1.0\r\n");
+    let _ = write!(
+        eml,
+        "Content-Type: multipart/mixed; boundary=\"{boundary}\"\r\n\r\n"
+    );
+    let _ = write!(
+        eml,
+        "--{boundary}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+         Content-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+        mime_base64(crlf(&draft.body).as_bytes())
+    );
+    for attachment in attachments {
+        let _ = write!(
+            eml,
+            "--{boundary}\r\nContent-Type: {}; name=\"{name}\"\r\n\
+             Content-Disposition: attachment; filename=\"{name}\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            attachment.content_type,
+            mime_base64(&attachment.data),
+            name = attachment.file_name,
+        );
+    }
+    let _ = write!(eml, "--{boundary}--\r\n");
+    eml
+}
+
+/// Opens a draft in the default mail client.
+///
+/// The draft opens as a `mailto:` URL unless it has attachments or the URL
+/// would be too long, in which case an `.eml` file is written to the cache
+/// directory and opened instead.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_ADDRESS` for a malformed
+/// recipient, `NOT_FOUND` if an attached conversation doesn't exist, `IO` if
+/// the draft cannot be written, or `OPEN_FAILED` if no mail client handles
+/// it.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("open_email_draft", {
+///   draft: {
+///     to: ["band@example.com"],
+///     subject: "Setlist ideas",
+///     body: message.content,
+///     conversationIds: [conversationId],
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn open_email_draft(app: AppHandle, draft: EmailDraft) -> Result<OpenedDraft, GibberError> {
+    for address in draft.to.iter().chain(&draft.cc) {
+        validate_address(address)?;
+    }
+
+    let mut attachments = Vec::new();
+    {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        for id in &draft.conversation_ids {
+            let conversation = conversations::load_conversation(&conn, id)?.ok_or_else(|| {
+                GibberError::new("NOT_FOUND", format!("Conversation not found: {id}"))
+            })?;
+            attachments.push(Attachment {
+                file_name: file_name(&conversation.conversation.title),
+                content_type: "application/json",
+                data: conversations::export_json(&conversation).into_bytes(),
+            });
+        }
+    }
+
+    if attachments.is_empty() {
+        let url = mailto_url(&draft);
+        if url.len() <= MAX_MAILTO_LEN {
+            app.opener()
+                .open_url(url, None::<&str>)
+                .map_err(|e| GibberError::new("OPEN_FAILED", e.to_string()))?;
+            return Ok(OpenedDraft {
+                method: DraftMethod::Mailto,
+                path: None,
+            });
+        }
+    }
+
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| GibberError::new("IO", e.to_string()))?
+        .join(DRAFTS_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let id = db::new_id();
+    let path = dir.join(format!("{id}.eml"));
+    std::fs::write(&path, eml(&draft, &attachments, &format!("gibber-{id}")))?;
+    let path = path.to_string_lossy().into_owned();
+    app.opener()
+        .open_path(&path, None::<&str>)
+        .map_err(|e| GibberError::new("OPEN_FAILED", e.to_string()))?;
+    Ok(OpenedDraft {
+        method: DraftMethod::Eml,
+        path: Some(path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft() -> EmailDraft {
+        EmailDraft {
+            to: vec!["ana@example.com".to_string()],
+            cc: vec!["bo@example.com".to_string()],
+            subject: "Mix notes & ideas".to_string(),
+            body: "Hi,\nmore reverb.".to_string(),
+            conversation_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_mailto_url() {
+        assert_eq!(
+            mailto_url(&draft()),
+            "mailto:ana@example.com?cc=bo@example.com\
+             &subject=Mix%20notes%20%26%20ideas&body=Hi%2C%0D%0Amore%20reverb."
+        );
+        assert_eq!(mailto_url(&EmailDraft::default()), "mailto:");
+    }
+
+    #[test]
+    fn test_addresses_are_validated() {
+        assert!(validate_address("ana@example.com").is_ok());
+        for address in ["ana", "ana@example.com?bcc=x@y.z", "a@b.c\r\nBcc: x@y.z"] {
+            assert_eq!(
+                validate_address(address).unwrap_err().code(),
+                "INVALID_ADDRESS"
+            );
+        }
+    }
+
+    #[test]
+    fn test_eml_draft() {
+        let mut draft = draft();
+        draft.subject = "Notas de mezcla ñ".to_string();
+        let attachment = Attachment {
+            file_name: file_name("Synth bass / ideas"),
+            content_type: "application/json",
+            data: b"{}".to_vec(),
+        };
+        let eml = eml(&draft, &[attachment], "b1");
+        assert!(eml.starts_with("To: ana@example.com\r\nCc: bo@example.com\r\n"));
+        assert!(eml.contains("Subject: =?UTF-8?B?Tm90YXMgZGUgbWV6Y2xhIMOx?=\r\n"));
+        assert!(eml.contains("X-Unsent: 1\r\n"));
+        assert!(eml.contains("\r\n\r\nSGksDQptb3JlIHJldmVyYi4=\r\n"));
+        assert!(eml.contains("filename=\"Synth-bass---ideas.json\""));
+        assert!(eml.ends_with("--b1--\r\n"));
+    }
+
+    #[test]
+    fn test_mime_base64_wraps_lines() {
+        let wrapped = mime_base64(&[0u8; 100]);
+        let lines: Vec<&str> = wrapped.split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), MIME_LINE_LEN);
+    }
+}
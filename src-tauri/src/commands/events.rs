@@ -0,0 +1,258 @@
+//! Event registry.
+//!
+//! Every event that crosses the IPC boundary is declared here as an
+//! [`Event`], which ties its name to the type of its payload. The backend
+//! emits through [`emit_typed`] and [`emit_typed_to`], so a payload can't
+//! drift from its declaration. The generated TypeScript bindings type each
+//! payload through [`EventPayloads`], and `get_event_schema` lists the
+//! declarations at runtime.
+
+use std::marker::PhantomData;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::attachments;
+use crate::commands::automations;
+use crate::commands::chat_stream;
+use crate::commands::clipboard;
+use crate::commands::conversations;
+use crate::commands::deep_link;
+use crate::commands::i18n;
+use crate::commands::images;
+use crate::commands::instance;
+use crate::commands::jobs;
+use crate::commands::notifications;
+use crate::commands::outbox;
+use crate::commands::prompt_tests;
+use crate::commands::quick_capture;
+use crate::commands::rate_limit;
+use crate::commands::reading_list;
+use crate::commands::routing_policy;
+use crate::commands::scheduler;
+use crate::commands::settings;
+use crate::commands::spend;
+use crate::commands::startup;
+use crate::commands::updater;
+use crate::commands::workflows;
+
+/// An event name tied to the type of its payload.
+pub struct Event<T> {
+    name: &'static str,
+    payload: PhantomData<fn(&T)>,
+}
+
+impl<T> Event<T> {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            payload: PhantomData,
+        }
+    }
+
+    /// Returns the name the event is emitted under.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> Clone for Event<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Event<T> {}
+
+/// One declared event, as listed by `get_event_schema`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSchema {
+    /// Name the event is emitted under
+    pub name: &'static str,
+    /// Rust type of the payload
+    pub payload: &'static str,
+    /// What the event announces
+    pub description: &'static str,
+}
+
+/// Passes a type through unchanged. `specta::Type` can't derive over a
+/// type captured by another macro, but it can over a macro call.
+macro_rules! payload {
+    ($payload:ty) => {
+        $payload
+    };
+}
+
+macro_rules! events {
+    ($(#[doc = $doc:literal] $ident:ident: $payload:ty = $name:literal;)+) => {
+        $(
+            #[doc = $doc]
+            pub const $ident: Event<$payload> = Event::new($name);
+        )+
+
+        /// Payload type of every event, keyed by event name.
+        ///
+        /// Never built; it only exists so the generated bindings can type
+        /// `listen` calls.
+        #[derive(specta::Type)]
+        #[allow(dead_code, non_snake_case)]
+        pub struct EventPayloads {
+            $(
+                #[specta(rename = $name)]
+                $ident: payload!($payload),
+            )+
+        }
+
+        /// Every declared event, in declaration order.
+        pub const SCHEMA: &[EventSchema] = &[$(EventSchema {
+            name: $name,
+            payload: stringify!($payload),
+            description: $doc.trim_ascii(),
+        }),+];
+    };
+}
+
+events! {
+    /// Emitted when an attachment has been staged.
+    ATTACHMENT_STAGED: attachments::StagedPayload = "attachment://staged";
+    /// Emitted when a dropped or opened file is rejected.
+    ATTACHMENT_REJECTED: attachments::RejectedPayload = "attachment://rejected";
+    /// Emitted after every automation run, successful or not.
+    AUTOMATIONS_RUN_COMPLETED: automations::AutomationRun = "automations://run-completed";
+    /// Emitted with each batch of streamed text.
+    CHAT_DELTA: chat_stream::DeltaPayload = "chat://delta";
+    /// Emitted when new clipboard content is captured.
+    CLIPBOARD_CAPTURED: clipboard::CapturedClip = "clipboard://captured";
+    /// Emitted to the main window when a capture is turned into a question.
+    CLIPBOARD_ASK: clipboard::AskPayload = "clipboard://ask";
+    /// Emitted with each message appended from the frontend.
+    CONVERSATION_MESSAGE: conversations::StoredMessage = "conversation://message";
+    /// Emitted to the main window for each routed link.
+    DEEP_LINK_NAVIGATE: deep_link::Navigation = "deep-link://navigate";
+    /// Emitted when a link cannot be handled.
+    DEEP_LINK_ERROR: deep_link::DeepLinkError = "deep-link://error";
+    /// Emitted to the quick-capture window when text arrives from another app.
+    EXTERNAL_INPUT_RECEIVED: () = "external-input://received";
+    /// Emitted with the conversation ID when a feed digest is stored.
+    FEEDS_DIGEST: String = "feeds://digest";
+    /// Emitted with the new strings when the locale changes.
+    I18N_LOCALE_CHANGED: i18n::LocaleStrings = "i18n://locale-changed";
+    /// Emitted as an image generation advances.
+    IMAGES_PROGRESS: images::ImageProgress = "images://progress";
+    /// Emitted to the main window when a second launch forwards its arguments.
+    INSTANCE_FORWARDED: instance::ForwardedArgs = "instance://forwarded";
+    /// Emitted whenever a job starts, progresses, or finishes.
+    JOB_PROGRESS: jobs::Job = "job://progress";
+    /// Emitted whenever connectivity changes.
+    NETWORK_STATUS: outbox::NetworkStatus = "network://status";
+    /// Emitted when the app is focused from a notification.
+    NOTIFICATION_ACTIVATED: notifications::ActivatedPayload = "notification://activated";
+    /// Emitted with the conversation ID when a Nostr digest is stored.
+    NOSTR_DIGEST: String = "nostr-digest://digest";
+    /// Emitted with the full outbox after every change.
+    OUTBOX_CHANGED: Vec<outbox::OutboxEntry> = "outbox://changed";
+    /// Emitted when a queued request has been answered.
+    OUTBOX_SENT: outbox::SentPayload = "outbox://sent";
+    /// Emitted after each test and model of a prompt test run.
+    PROMPT_TESTS_PROGRESS: prompt_tests::ProgressPayload = "prompt-tests://progress";
+    /// Emitted to the main window when a quick capture prompt is submitted.
+    QUICK_CAPTURE_SUBMITTED: quick_capture::SubmittedPayload = "quick-capture://submitted";
+    /// Emitted when a request has to wait for its provider's budget.
+    RATE_LIMIT_QUEUED: rate_limit::QueuedPayload = "rate-limit://queued";
+    /// Emitted with an item whenever it is added or changes status.
+    READING_LIST_UPDATED: reading_list::ReadingItem = "reading-list://updated";
+    /// Emitted when a request is refused by the routing policy.
+    ROUTING_POLICY_BLOCKED: routing_policy::BlockedPayload = "routing-policy://blocked";
+    /// Emitted after every scheduled run, successful or not.
+    SCHEDULER_RUN_COMPLETED: scheduler::RunCompletedPayload = "scheduler://run-completed";
+    /// Emitted with the full settings document after every change.
+    SETTINGS_CHANGED: settings::Settings = "settings://changed";
+    /// Emitted to the main window when push-to-talk is pressed or released.
+    SHORTCUT_PUSH_TO_TALK: bool = "shortcut://push-to-talk";
+    /// Emitted when a request is refused for going over a spend cap.
+    BUDGET_EXCEEDED: spend::ExceededPayload = "budget://exceeded";
+    /// Emitted when a subsystem has finished initializing.
+    APP_READY: startup::ReadyPayload = "app://ready";
+    /// Emitted to the main window when "New chat" is chosen from the tray.
+    TRAY_NEW_CHAT: () = "tray://new-chat";
+    /// Emitted when sync is paused or resumed.
+    TRAY_SYNC_PAUSED: bool = "tray://sync-paused";
+    /// Emitted when the generation indicator changes.
+    TRAY_GENERATING: bool = "tray://generating";
+    /// Emitted when an update is found.
+    UPDATER_AVAILABLE: updater::UpdateInfo = "updater://available";
+    /// Emitted while an update downloads.
+    UPDATER_PROGRESS: updater::ProgressPayload = "updater://progress";
+    /// Emitted when a downloaded update is ready to install.
+    UPDATER_READY: updater::UpdateInfo = "updater://ready";
+    /// Emitted whenever a workflow step starts, retries, succeeds, or fails.
+    WORKFLOW_STEP: workflows::StepEventPayload = "workflow://step";
+    /// Emitted when a workflow run finishes.
+    WORKFLOW_RUN_COMPLETED: workflows::WorkflowRun = "workflow://run-completed";
+    /// Emitted by the frontend with each chunk of a streamed reply.
+    GENERATION_DELTA: serde_json::Value = "generation://delta";
+}
+
+/// Emits `event` to every window, logging instead of failing if it can't.
+pub fn emit_typed<T: Serialize>(app: &AppHandle, event: Event<T>, payload: &T) {
+    if let Err(e) = app.emit(event.name, payload) {
+        tracing::warn!("failed to emit {}: {e}", event.name);
+    }
+}
+
+/// Emits `event` to the window labelled `target` only.
+///
+/// # Errors
+///
+/// Returns an error if the event cannot be emitted.
+pub fn emit_typed_to<T: Serialize>(
+    app: &AppHandle,
+    target: &str,
+    event: Event<T>,
+    payload: &T,
+) -> tauri::Result<()> {
+    app.emit_to(target, event.name, payload)
+}
+
+/// Lists every event with the Rust type of its payload.
+///
+/// # Example
+///
+/// ```typescript
+/// const schema = await invoke("get_event_schema");
+/// const missing = schema.filter(({ name }) => !(name in listeners));
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub fn get_event_schema() -> &'static [EventSchema] {
+    SCHEMA
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_names_are_unique_and_scoped() {
+        let mut names: Vec<_> = SCHEMA.iter().map(|event| event.name).collect();
+        assert!(names.iter().all(|name| name.split_once("://").is_some()));
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), SCHEMA.len());
+    }
+
+    #[test]
+    fn test_schema_describes_payloads() {
+        let ready = SCHEMA
+            .iter()
+            .find(|event| event.name == APP_READY.name())
+            .unwrap();
+        assert_eq!(ready.payload, "startup::ReadyPayload");
+        assert_eq!(
+            ready.description,
+            "Emitted when a subsystem has finished initializing."
+        );
+    }
+}
@@ -0,0 +1,715 @@
+//! A/B prompt experiments.
+//!
+//! An experiment pairs two variants, each a system prompt and optionally a
+//! model. While an experiment is active, every new chat conversation that
+//! doesn't bring its own system prompt is enrolled in it: conversations
+//! alternate between the variants, and the variant's prompt and model are
+//! applied to the conversation. Thumbs-up and thumbs-down ratings of the
+//! answers in enrolled conversations are counted per variant, and
+//! [`get_experiment_stats`] compares the approval rates with a
+//! two-proportion z-test.
+//!
+//! Only one experiment is active at a time.
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::conversations::Conversation;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// |z| at or above which a difference is reported as significant (95%).
+const SIGNIFICANT_Z: f64 = 1.96;
+
+fn not_found(what: &str, id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("{what} {id} not found"))
+}
+
+/// One side of an experiment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    /// The first variant, usually the current prompt
+    A,
+    /// The second variant, usually the candidate
+    B,
+}
+
+impl Variant {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::A => "a",
+            Self::B => "b",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "a" => Some(Self::A),
+            "b" => Some(Self::B),
+            _ => None,
+        }
+    }
+}
+
+/// What a variant changes about a conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantConfig {
+    /// Short name shown in reports
+    pub label: String,
+    /// System prompt of enrolled conversations
+    pub system_prompt: String,
+    /// Model of enrolled conversations; keeps the chosen model when `None`
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// A saved experiment.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Experiment {
+    /// Unique identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// First variant
+    pub variant_a: VariantConfig,
+    /// Second variant
+    pub variant_b: VariantConfig,
+    /// Whether new conversations are enrolled
+    pub active: bool,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+}
+
+impl Experiment {
+    const fn variant(&self, variant: Variant) -> &VariantConfig {
+        match variant {
+            Variant::A => &self.variant_a,
+            Variant::B => &self.variant_b,
+        }
+    }
+}
+
+/// Fields for creating or replacing an experiment.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentInput {
+    /// Display name
+    pub name: String,
+    /// First variant
+    pub variant_a: VariantConfig,
+    /// Second variant
+    pub variant_b: VariantConfig,
+}
+
+impl ExperimentInput {
+    fn validate(&self) -> Result<(), GibberError> {
+        if self.name.trim().is_empty() {
+            return Err(GibberError::invalid("An experiment needs a name"));
+        }
+        for variant in [&self.variant_a, &self.variant_b] {
+            if variant.label.trim().is_empty() || variant.system_prompt.trim().is_empty() {
+                return Err(GibberError::invalid(
+                    "Each variant needs a label and a system prompt",
+                ));
+            }
+        }
+        if self.variant_a == self.variant_b {
+            return Err(GibberError::invalid("The variants are identical"));
+        }
+        Ok(())
+    }
+}
+
+/// The variant a conversation was given.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Assignment {
+    /// Experiment the conversation is enrolled in
+    pub experiment_id: String,
+    /// Variant it was given
+    pub variant: Variant,
+    /// Label of that variant
+    pub label: String,
+}
+
+/// Totals for one variant.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantStats {
+    /// The variant
+    pub variant: Variant,
+    /// Its label
+    pub label: String,
+    /// Conversations enrolled with it
+    pub conversations: u32,
+    /// Thumbs-up ratings
+    pub thumbs_up: u32,
+    /// Thumbs-down ratings
+    pub thumbs_down: u32,
+    /// Share of ratings that are thumbs-up, once there are any
+    pub approval: Option<f64>,
+}
+
+impl VariantStats {
+    const fn ratings(&self) -> u32 {
+        self.thumbs_up + self.thumbs_down
+    }
+}
+
+/// Result of [`get_experiment_stats`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentStats {
+    /// The experiment
+    pub experiment_id: String,
+    /// Totals for A and B
+    pub variants: [VariantStats; 2],
+    /// Two-proportion z-score of B's approval against A's
+    pub z_score: Option<f64>,
+    /// Two-sided p-value of the z-score
+    pub p_value: Option<f64>,
+    /// Whether the difference is significant at the 95% level
+    pub significant: bool,
+}
+
+const EXPERIMENT_COLUMNS: &str = "id, name, variant_a, variant_b, active, created_at";
+
+fn experiment_from_row(row: &Row<'_>) -> rusqlite::Result<Experiment> {
+    let parse = |index: usize| -> rusqlite::Result<VariantConfig> {
+        let json: String = row.get(index)?;
+        serde_json::from_str(&json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, e.into())
+        })
+    };
+    Ok(Experiment {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        variant_a: parse(2)?,
+        variant_b: parse(3)?,
+        active: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn load_experiment(conn: &Connection, id: &str) -> Result<Experiment, GibberError> {
+    conn.query_row(
+        &format!("SELECT {EXPERIMENT_COLUMNS} FROM experiments WHERE id = ?1"),
+        [id],
+        experiment_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found("Experiment", id))
+}
+
+fn active_experiment(conn: &Connection) -> rusqlite::Result<Option<Experiment>> {
+    conn.query_row(
+        &format!("SELECT {EXPERIMENT_COLUMNS} FROM experiments WHERE active = 1 LIMIT 1"),
+        [],
+        experiment_from_row,
+    )
+    .optional()
+}
+
+/// The variant with fewer conversations so far, A on a tie.
+fn next_variant(conn: &Connection, experiment_id: &str) -> rusqlite::Result<Variant> {
+    let (a, b): (i64, i64) = conn.query_row(
+        "SELECT COALESCE(SUM(variant = 'a'), 0), COALESCE(SUM(variant = 'b'), 0)
+         FROM experiment_assignments WHERE experiment_id = ?1",
+        [experiment_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(if b < a { Variant::B } else { Variant::A })
+}
+
+/// Enrolls a new conversation in the active experiment, if there is one,
+/// and applies the variant to it.
+///
+/// Conversations from other sources than the chat view and conversations
+/// with their own system prompt are left alone.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub(crate) fn enroll(
+    conn: &Connection,
+    conversation: &mut Conversation,
+) -> rusqlite::Result<Option<Assignment>> {
+    if conversation.source != "chat" || conversation.system_prompt.is_some() {
+        return Ok(None);
+    }
+    let Some(experiment) = active_experiment(conn)? else {
+        return Ok(None);
+    };
+    let variant = next_variant(conn, &experiment.id)?;
+    let config = experiment.variant(variant);
+    conversation.system_prompt = Some(config.system_prompt.clone());
+    if let Some(model) = &config.model {
+        conversation.model = Some(model.clone());
+    }
+    conn.execute(
+        "UPDATE conversations SET system_prompt = ?1, model = ?2 WHERE id = ?3",
+        params![
+            conversation.system_prompt,
+            conversation.model,
+            conversation.id
+        ],
+    )?;
+    conn.execute(
+        "INSERT INTO experiment_assignments (conversation_id, experiment_id, variant, assigned_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            conversation.id,
+            experiment.id,
+            variant.as_str(),
+            db::now_millis()
+        ],
+    )?;
+    Ok(Some(Assignment {
+        label: config.label.clone(),
+        experiment_id: experiment.id,
+        variant,
+    }))
+}
+
+fn load_assignment(
+    conn: &Connection,
+    conversation_id: &str,
+) -> rusqlite::Result<Option<Assignment>> {
+    let row: Option<(String, String)> = conn
+        .query_row(
+            "SELECT experiment_id, variant FROM experiment_assignments WHERE conversation_id = ?1",
+            [conversation_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((experiment_id, variant)) = row else {
+        return Ok(None);
+    };
+    let Some(variant) = Variant::parse(&variant) else {
+        return Ok(None);
+    };
+    let Ok(experiment) = load_experiment(conn, &experiment_id) else {
+        return Ok(None);
+    };
+    Ok(Some(Assignment {
+        label: experiment.variant(variant).label.clone(),
+        experiment_id,
+        variant,
+    }))
+}
+
+/// Standard normal cumulative distribution (Abramowitz and Stegun 7.1.26).
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Two-proportion z-test of B's approval against A's.
+fn z_test(a: &VariantStats, b: &VariantStats) -> Option<(f64, f64)> {
+    let (n_a, n_b) = (f64::from(a.ratings()), f64::from(b.ratings()));
+    if n_a == 0.0 || n_b == 0.0 {
+        return None;
+    }
+    let pooled = f64::from(a.thumbs_up + b.thumbs_up) / (n_a + n_b);
+    let error = (pooled * (1.0 - pooled) * (1.0 / n_a + 1.0 / n_b)).sqrt();
+    if error == 0.0 {
+        return None;
+    }
+    let z = (f64::from(b.thumbs_up) / n_b - f64::from(a.thumbs_up) / n_a) / error;
+    Some((z, 2.0 * (1.0 - normal_cdf(z.abs()))))
+}
+
+fn stats(conn: &Connection, experiment: &Experiment) -> rusqlite::Result<ExperimentStats> {
+    let variant_stats = |variant: Variant| -> rusqlite::Result<VariantStats> {
+        let (conversations, thumbs_up, thumbs_down): (u32, u32, u32) = conn.query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM experiment_assignments
+                 WHERE experiment_id = ?1 AND variant = ?2),
+                COALESCE(SUM(r.rating > 0), 0),
+                COALESCE(SUM(r.rating < 0), 0)
+             FROM experiment_ratings r
+             WHERE r.experiment_id = ?1 AND r.variant = ?2",
+            params![experiment.id, variant.as_str()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let ratings = thumbs_up + thumbs_down;
+        Ok(VariantStats {
+            variant,
+            label: experiment.variant(variant).label.clone(),
+            conversations,
+            thumbs_up,
+            thumbs_down,
+            approval: (ratings > 0).then(|| f64::from(thumbs_up) / f64::from(ratings)),
+        })
+    };
+    let variants = [variant_stats(Variant::A)?, variant_stats(Variant::B)?];
+    let test = z_test(&variants[0], &variants[1]);
+    Ok(ExperimentStats {
+        experiment_id: experiment.id.clone(),
+        z_score: test.map(|(z, _)| z),
+        p_value: test.map(|(_, p)| p),
+        significant: test.is_some_and(|(z, _)| z.abs() >= SIGNIFICANT_Z),
+        variants,
+    })
+}
+
+/// Lists experiments, newest first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_experiments(db: State<'_, Database>) -> Result<Vec<Experiment>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {EXPERIMENT_COLUMNS} FROM experiments ORDER BY created_at DESC"
+    ))?;
+    let experiments = stmt
+        .query_map([], experiment_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(experiments)
+}
+
+/// Creates an experiment, or replaces the variants of an existing one when
+/// `id` is given. New experiments start inactive.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if a name, label,
+/// or prompt is missing or the variants are identical, `NOT_FOUND` if `id`
+/// doesn't exist, or `DATABASE` on failure.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("save_experiment", {
+///   id: null,
+///   input: {
+///     name: "Terse producer",
+///     variantA: { label: "Current", systemPrompt: currentPrompt },
+///     variantB: { label: "Terse", systemPrompt: "Answer in three sentences or fewer." },
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn save_experiment(
+    db: State<'_, Database>,
+    id: Option<String>,
+    input: ExperimentInput,
+) -> Result<Experiment, GibberError> {
+    input.validate()?;
+    let variant_a = serde_json::to_string(&input.variant_a)
+        .map_err(|e| GibberError::new("SERIALIZATION", e.to_string()))?;
+    let variant_b = serde_json::to_string(&input.variant_b)
+        .map_err(|e| GibberError::new("SERIALIZATION", e.to_string()))?;
+    let conn = db.conn();
+    let id = if let Some(id) = id {
+        let updated = conn.execute(
+            "UPDATE experiments SET name = ?1, variant_a = ?2, variant_b = ?3 WHERE id = ?4",
+            params![input.name, variant_a, variant_b, id],
+        )?;
+        if updated == 0 {
+            return Err(not_found("Experiment", &id));
+        }
+        id
+    } else {
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO experiments (id, name, variant_a, variant_b, active, created_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+            params![id, input.name, variant_a, variant_b, db::now_millis()],
+        )?;
+        id
+    };
+    load_experiment(&conn, &id)
+}
+
+/// Starts or stops enrolling new conversations in an experiment. Starting
+/// one stops any other.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the experiment
+/// doesn't exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn set_experiment_active(
+    db: State<'_, Database>,
+    id: &str,
+    active: bool,
+) -> Result<Experiment, GibberError> {
+    let mut conn = db.conn();
+    let tx = conn.transaction()?;
+    if active {
+        tx.execute("UPDATE experiments SET active = 0 WHERE id != ?1", [id])?;
+    }
+    let updated = tx.execute(
+        "UPDATE experiments SET active = ?1 WHERE id = ?2",
+        params![active, id],
+    )?;
+    if updated == 0 {
+        return Err(not_found("Experiment", id));
+    }
+    tx.commit()?;
+    load_experiment(&conn, id)
+}
+
+/// Deletes an experiment with its enrollments and ratings. Conversations
+/// keep the prompt they were given.
+///
+/// # Returns
+///
+/// Returns `true` if the experiment was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_experiment(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM experiments WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+/// Retrieves the variant a conversation was given, if it is enrolled.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_experiment_assignment(
+    db: State<'_, Database>,
+    conversation_id: &str,
+) -> Result<Option<Assignment>, GibberError> {
+    Ok(load_assignment(&db.conn(), conversation_id)?)
+}
+
+/// Rates an answer in an enrolled conversation. `thumbs_up: null` removes
+/// the rating.
+///
+/// Ratings given with `rate_message` are counted too; this command only
+/// records the experiment side.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the message
+/// doesn't exist, `NOT_ENROLLED` if its conversation isn't in an
+/// experiment, or `INVALID_INPUT` if it isn't an assistant message.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("rate_experiment_message", { messageId: id, thumbsUp: true });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn rate_experiment_message(
+    db: State<'_, Database>,
+    message_id: &str,
+    thumbs_up: Option<bool>,
+) -> Result<(), GibberError> {
+    let conn = db.conn();
+    rate(&conn, message_id, thumbs_up)
+}
+
+fn rate(conn: &Connection, message_id: &str, thumbs_up: Option<bool>) -> Result<(), GibberError> {
+    let (conversation_id, role): (String, String) = conn
+        .query_row(
+            "SELECT conversation_id, role FROM messages WHERE id = ?1",
+            [message_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| not_found("Message", message_id))?;
+    if role != "assistant" {
+        return Err(GibberError::invalid("Only answers can be rated"));
+    }
+    if record_rating(conn, message_id, &conversation_id, thumbs_up)? {
+        Ok(())
+    } else {
+        Err(GibberError::new(
+            "NOT_ENROLLED",
+            "The conversation isn't in an experiment",
+        ))
+    }
+}
+
+/// Counts a rating of an answer towards its conversation's variant.
+/// `None` removes the rating.
+///
+/// # Returns
+///
+/// Whether the conversation is enrolled in an experiment.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub(crate) fn record_rating(
+    conn: &Connection,
+    message_id: &str,
+    conversation_id: &str,
+    thumbs_up: Option<bool>,
+) -> rusqlite::Result<bool> {
+    let Some(assignment) = load_assignment(conn, conversation_id)? else {
+        return Ok(false);
+    };
+    match thumbs_up {
+        Some(up) => conn.execute(
+            "INSERT OR REPLACE INTO experiment_ratings
+                (message_id, experiment_id, variant, rating, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                message_id,
+                assignment.experiment_id,
+                assignment.variant.as_str(),
+                if up { 1 } else { -1 },
+                db::now_millis()
+            ],
+        )?,
+        None => conn.execute(
+            "DELETE FROM experiment_ratings WHERE message_id = ?1",
+            [message_id],
+        )?,
+    };
+    Ok(true)
+}
+
+/// Compares the variants of an experiment.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the experiment
+/// doesn't exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_experiment_stats(
+    db: State<'_, Database>,
+    id: &str,
+) -> Result<ExperimentStats, GibberError> {
+    let conn = db.conn();
+    let experiment = load_experiment(&conn, id)?;
+    Ok(stats(&conn, &experiment)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::MessageRole;
+    use crate::commands::conversations::{self, NewConversation, NewMessage};
+
+    fn variant(label: &str, prompt: &str) -> VariantConfig {
+        VariantConfig {
+            label: label.to_string(),
+            system_prompt: prompt.to_string(),
+            model: None,
+        }
+    }
+
+    fn setup(conn: &Connection) -> String {
+        conn.execute(
+            "INSERT INTO experiments (id, name, variant_a, variant_b, active, created_at)
+             VALUES ('e', 'Terse', ?1, ?2, 1, 0)",
+            params![
+                serde_json::to_string(&variant("Current", "Be helpful")).unwrap(),
+                serde_json::to_string(&variant("Terse", "Be brief")).unwrap(),
+            ],
+        )
+        .unwrap();
+        "e".to_string()
+    }
+
+    fn new_chat(conn: &Connection) -> Conversation {
+        let mut conversation =
+            conversations::insert_conversation(conn, &NewConversation::default()).unwrap();
+        enroll(conn, &mut conversation).unwrap();
+        conversation
+    }
+
+    #[test]
+    fn test_enrollment_alternates() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        setup(&conn);
+        let prompts: Vec<_> = (0..3)
+            .map(|_| new_chat(&conn).system_prompt.unwrap())
+            .collect();
+        assert_eq!(prompts, ["Be helpful", "Be brief", "Be helpful"]);
+
+        let mut own = conversations::insert_conversation(
+            &conn,
+            &NewConversation {
+                system_prompt: Some("Mine".to_string()),
+                ..NewConversation::default()
+            },
+        )
+        .unwrap();
+        assert!(enroll(&conn, &mut own).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ratings_count_per_variant() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let id = setup(&conn);
+        for up in [true, false] {
+            let conversation = new_chat(&conn);
+            let answer = NewMessage {
+                role: MessageRole::Assistant,
+                content: "Sure".to_string(),
+                model: None,
+                usage: None,
+            };
+            let message = conversations::insert_message(&conn, &conversation.id, &answer).unwrap();
+            rate(&conn, &message.id, Some(up)).unwrap();
+            rate(&conn, &message.id, Some(up)).unwrap();
+        }
+        let stats = stats(&conn, &load_experiment(&conn, &id).unwrap()).unwrap();
+        assert_eq!(stats.variants[0].thumbs_up, 1);
+        assert_eq!(stats.variants[1].thumbs_down, 1);
+        assert_eq!(stats.variants[1].approval, Some(0.0));
+        assert!(!stats.significant);
+    }
+
+    #[test]
+    fn test_z_test() {
+        let stats = |up: u32, down: u32| VariantStats {
+            variant: Variant::A,
+            label: String::new(),
+            conversations: 0,
+            thumbs_up: up,
+            thumbs_down: down,
+            approval: None,
+        };
+        let (z, p) = z_test(&stats(40, 60), &stats(60, 40)).unwrap();
+        assert!((z - 2.83).abs() < 0.01);
+        assert!(p < 0.01);
+        assert!(z_test(&stats(0, 0), &stats(1, 0)).is_none());
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,621 @@
+//! Text sent to the app from other applications.
+//!
+//! Selected text in any app reaches Gibber AI through a `gibber://input`
+//! link:
+//!
+//! - `gibber://input?text=...&action=fix-grammar&source=services`
+//!
+//! On macOS, `install_text_services` adds one Services menu entry per
+//! [`QuickAction`] ("Fix Grammar with Gibber AI", ...). Each entry is a
+//! Quick Action workflow in `~/Library/Services` that opens the link with
+//! the selection, so the app doesn't need to be running. On Windows, a
+//! share target needs the package identity of an MSIX install, which the
+//! NSIS and MSI bundles don't have; launchers and scripts there can open
+//! the same link.
+//!
+//! A received input is kept until the quick-capture window takes it with
+//! `take_external_input`, then shown there; the window is told with
+//! [`events::EXTERNAL_INPUT_RECEIVED`]. The action, either the one
+//! requested by the link or one the user picks, runs with
+//! `run_external_input`.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::clipboard;
+use crate::commands::conversations;
+use crate::commands::events;
+use crate::commands::i18n;
+use crate::commands::quick_capture::{self, SubmittedPayload};
+use crate::commands::rewrite::{self, Rewrite, RewriteMode};
+use crate::commands::translation::{self, Translation, TranslationRequest};
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Longest text accepted from another app, in characters.
+pub const MAX_TEXT_CHARS: usize = rewrite::MAX_TEXT_CHARS;
+
+/// Language translations go to when none is given and the system locale
+/// is unknown.
+const FALLBACK_TARGET: &str = "English";
+
+/// What to do with text from another app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum QuickAction {
+    /// Start a conversation with the text as the prompt
+    Ask,
+    /// Correct spelling, grammar, and punctuation
+    FixGrammar,
+    /// Plainer words and shorter sentences
+    Simplify,
+    /// A formal, professional tone
+    Formalize,
+    /// Fewer words, same points
+    Shorten,
+    /// Translate into the user's language
+    Translate,
+}
+
+impl QuickAction {
+    /// Every action, in Services menu order.
+    pub const ALL: [Self; 6] = [
+        Self::Ask,
+        Self::FixGrammar,
+        Self::Simplify,
+        Self::Formalize,
+        Self::Shorten,
+        Self::Translate,
+    ];
+
+    /// Name of the action in `gibber://input` links.
+    pub const fn slug(self) -> &'static str {
+        match self {
+            Self::Ask => "ask",
+            Self::FixGrammar => "fix-grammar",
+            Self::Simplify => "simplify",
+            Self::Formalize => "formalize",
+            Self::Shorten => "shorten",
+            Self::Translate => "translate",
+        }
+    }
+
+    /// Parses a link action name.
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.slug() == slug)
+    }
+
+    #[cfg(any(target_os = "macos", test))]
+    const fn menu_title(self) -> &'static str {
+        match self {
+            Self::Ask => "Ask Gibber AI",
+            Self::FixGrammar => "Fix Grammar with Gibber AI",
+            Self::Simplify => "Simplify with Gibber AI",
+            Self::Formalize => "Formalize with Gibber AI",
+            Self::Shorten => "Shorten with Gibber AI",
+            Self::Translate => "Translate with Gibber AI",
+        }
+    }
+
+    const fn rewrite_mode(self) -> Option<RewriteMode> {
+        match self {
+            Self::FixGrammar => Some(RewriteMode::FixGrammar),
+            Self::Simplify => Some(RewriteMode::Simplify),
+            Self::Formalize => Some(RewriteMode::Formalize),
+            Self::Shorten => Some(RewriteMode::Shorten),
+            Self::Ask | Self::Translate => None,
+        }
+    }
+}
+
+/// Where text from another app came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum InputSource {
+    /// The macOS Services menu
+    Services,
+    /// A `gibber://input` link opened some other way
+    Link,
+}
+
+impl InputSource {
+    /// Parses the `source` parameter of a link; anything else is a link.
+    pub fn from_param(param: Option<&str>) -> Self {
+        match param {
+            Some("services") => Self::Services,
+            _ => Self::Link,
+        }
+    }
+}
+
+/// Text received from another app.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalInput {
+    /// The selected text
+    pub text: String,
+    /// The action requested with it; the user picks one when absent
+    pub action: Option<QuickAction>,
+    /// Where it came from
+    pub source: InputSource,
+}
+
+/// The latest input, until the quick-capture window takes it.
+#[derive(Default)]
+pub struct PendingInput(Mutex<Option<ExternalInput>>);
+
+impl PendingInput {
+    fn lock(&self) -> MutexGuard<'_, Option<ExternalInput>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A quick action to run on text from another app.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickActionRequest {
+    /// The text, possibly edited in the quick-capture window
+    pub text: String,
+    /// What to do with it
+    pub action: QuickAction,
+    /// Language to translate into; the system language when absent
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Model to use instead of the default
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Whether to copy a rewrite or translation to the clipboard
+    #[serde(default)]
+    pub copy: bool,
+}
+
+/// The outcome of a quick action.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum QuickActionResult {
+    /// The rewritten text
+    Rewrite(Rewrite),
+    /// The translated text
+    #[serde(rename_all = "camelCase")]
+    Translation {
+        /// The translation
+        translation: Translation,
+        /// Whether it was copied to the clipboard
+        copied: bool,
+    },
+    /// A conversation was started in the main window
+    #[serde(rename_all = "camelCase")]
+    Conversation {
+        /// The conversation holding the text as its prompt
+        conversation_id: String,
+    },
+}
+
+/// Stores `input` for the quick-capture window and shows the window.
+///
+/// Called by the deep-link handler; a newer input replaces one that
+/// hasn't been taken yet.
+pub fn receive(app: &AppHandle, input: ExternalInput) {
+    *app.state::<PendingInput>().lock() = Some(input);
+    if let Err(e) = quick_capture::show(app) {
+        tracing::warn!("failed to show quick capture: {e}");
+    }
+    if let Err(e) = events::emit_typed_to(
+        app,
+        quick_capture::WINDOW_LABEL,
+        events::EXTERNAL_INPUT_RECEIVED,
+        &(),
+    ) {
+        tracing::warn!("failed to announce external input: {e}");
+    }
+}
+
+/// Translations go to the language of the system locale by default.
+fn default_target() -> String {
+    i18n::system_locale()
+        .and_then(|locale| locale.split('-').next().map(str::to_string))
+        .filter(|language| !language.is_empty())
+        .unwrap_or_else(|| FALLBACK_TARGET.to_string())
+}
+
+/// Returns the text received from another app, once.
+///
+/// # Example
+///
+/// ```typescript
+/// await listen("external-input://received", async () => {
+///   const input = await invoke("take_external_input");
+///   if (input) showInput(input);
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn take_external_input(state: State<'_, PendingInput>) -> Option<ExternalInput> {
+    state.lock().take()
+}
+
+/// Runs a quick action on text from another app.
+///
+/// Rewrites and translations are returned to the caller, and copied to
+/// the clipboard when `copy` is set. `ask` creates a conversation, hides
+/// the capture window, and hands the conversation to the main window
+/// with [`events::QUICK_CAPTURE_SUBMITTED`].
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` for blank text,
+/// `TOO_LARGE` for text over 20,000 characters, `CLIPBOARD` if the result
+/// can't be copied, or an error as `rewrite_text`, `translate_text`, or
+/// `submit_quick_capture` do.
+///
+/// # Example
+///
+/// ```typescript
+/// const result = await invoke("run_external_input", {
+///   request: { text: input.text, action: "translate", target: null, copy: true },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn run_external_input(
+    app: AppHandle,
+    request: QuickActionRequest,
+) -> Result<QuickActionResult, GibberError> {
+    let text = request.text.trim().to_string();
+    if text.is_empty() {
+        return Err(GibberError::invalid("There is no text"));
+    }
+    if text.chars().count() > MAX_TEXT_CHARS {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!("The text is longer than {MAX_TEXT_CHARS} characters"),
+        ));
+    }
+    if let Some(mode) = request.action.rewrite_mode() {
+        let mut rewritten = rewrite::rewrite_text(app.clone(), text, mode, request.model).await?;
+        if request.copy {
+            clipboard::write_text(&app, &rewritten.text)?;
+            rewritten.copied = true;
+        }
+        return Ok(QuickActionResult::Rewrite(rewritten));
+    }
+    if request.action == QuickAction::Translate {
+        let target = request
+            .target
+            .filter(|target| !target.trim().is_empty())
+            .unwrap_or_else(default_target);
+        let translation = translation::translate_text(
+            app.clone(),
+            TranslationRequest {
+                text,
+                target,
+                source: None,
+                model: request.model,
+            },
+        )
+        .await?;
+        if request.copy {
+            clipboard::write_text(&app, &translation.text)?;
+        }
+        Ok(QuickActionResult::Translation {
+            translation,
+            copied: request.copy,
+        })
+    } else {
+        let conversation = conversations::insert_prompt_conversation(
+            &app.state::<Database>().conn(),
+            &text,
+            request.model.as_deref(),
+            "external-input",
+        )?;
+        quick_capture::hide(&app)?;
+        quick_capture::focus_main_window(&app)?;
+        events::emit_typed_to(
+            &app,
+            quick_capture::MAIN_WINDOW_LABEL,
+            events::QUICK_CAPTURE_SUBMITTED,
+            &SubmittedPayload {
+                conversation_id: conversation.id.clone(),
+                prompt: text,
+                model: request.model,
+            },
+        )?;
+        Ok(QuickActionResult::Conversation {
+            conversation_id: conversation.id,
+        })
+    }
+}
+
+/// Escapes text for a property list string.
+#[cfg(any(target_os = "macos", test))]
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Shell script of a Services workflow: URL-encodes the selection from
+/// stdin and opens the input link.
+#[cfg(any(target_os = "macos", test))]
+fn service_script(action: QuickAction) -> String {
+    format!(
+        "text=$(cat)\nencoded=$(osascript -l JavaScript -e 'function run(argv) {{ return \
+         encodeURIComponent(argv[0]) }}' \"$text\")\nopen \"{}://input?source=services&action={}\
+         &text=$encoded\"\n",
+        crate::commands::instance::URL_SCHEME,
+        action.slug()
+    )
+}
+
+/// `Contents/Info.plist` of a Services workflow.
+#[cfg(any(target_os = "macos", test))]
+fn service_info_plist(action: QuickAction) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>NSServices</key>
+	<array>
+		<dict>
+			<key>NSMenuItem</key>
+			<dict>
+				<key>default</key>
+				<string>{title}</string>
+			</dict>
+			<key>NSMessage</key>
+			<string>runWorkflowAsService</string>
+			<key>NSSendTypes</key>
+			<array>
+				<string>public.utf8-plain-text</string>
+			</array>
+		</dict>
+	</array>
+</dict>
+</plist>
+"#,
+        title = xml_escape(action.menu_title())
+    )
+}
+
+/// `Contents/document.wflow` of a Services workflow: a single "Run Shell
+/// Script" action that receives the selected text on stdin.
+#[cfg(any(target_os = "macos", test))]
+fn service_document(action: QuickAction) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>AMApplicationBuild</key>
+	<string>523</string>
+	<key>AMApplicationVersion</key>
+	<string>2.10</string>
+	<key>AMDocumentVersion</key>
+	<string>2</string>
+	<key>actions</key>
+	<array>
+		<dict>
+			<key>action</key>
+			<dict>
+				<key>AMAccepts</key>
+				<dict>
+					<key>Container</key>
+					<string>List</string>
+					<key>Optional</key>
+					<true/>
+					<key>Types</key>
+					<array>
+						<string>com.apple.cocoa.string</string>
+					</array>
+				</dict>
+				<key>AMActionVersion</key>
+				<string>2.0.3</string>
+				<key>AMApplication</key>
+				<array>
+					<string>Automator</string>
+				</array>
+				<key>AMProvides</key>
+				<dict>
+					<key>Container</key>
+					<string>List</string>
+					<key>Types</key>
+					<array>
+						<string>com.apple.cocoa.string</string>
+					</array>
+				</dict>
+				<key>ActionBundlePath</key>
+				<string>/System/Library/Automator/Run Shell Script.action</string>
+				<key>ActionName</key>
+				<string>Run Shell Script</string>
+				<key>ActionParameters</key>
+				<dict>
+					<key>COMMAND_STRING</key>
+					<string>{script}</string>
+					<key>CheckedForUserDefaultShell</key>
+					<true/>
+					<key>inputMethod</key>
+					<integer>0</integer>
+					<key>shell</key>
+					<string>/bin/zsh</string>
+					<key>source</key>
+					<string></string>
+				</dict>
+				<key>BundleIdentifier</key>
+				<string>com.apple.RunShellScript</string>
+				<key>CFBundleVersion</key>
+				<string>2.0.3</string>
+				<key>CanShowSelectedItemsWhenRun</key>
+				<false/>
+				<key>CanShowWhenRun</key>
+				<true/>
+				<key>Class Name</key>
+				<string>RunShellScriptAction</string>
+				<key>InputUUID</key>
+				<string>6A1B5E20-0000-0000-0000-000000000001</string>
+				<key>OutputUUID</key>
+				<string>6A1B5E20-0000-0000-0000-000000000002</string>
+				<key>UUID</key>
+				<string>6A1B5E20-0000-0000-0000-000000000003</string>
+				<key>isViewVisible</key>
+				<true/>
+			</dict>
+		</dict>
+	</array>
+	<key>connectors</key>
+	<dict/>
+	<key>workflowMetaData</key>
+	<dict>
+		<key>serviceInputTypeIdentifier</key>
+		<string>com.apple.Automator.text</string>
+		<key>serviceOutputTypeIdentifier</key>
+		<string>com.apple.Automator.nothing</string>
+		<key>serviceProcessesInput</key>
+		<integer>0</integer>
+		<key>workflowTypeIdentifier</key>
+		<string>com.apple.Automator.servicesMenu</string>
+	</dict>
+</dict>
+</plist>
+"#,
+        script = xml_escape(&service_script(action))
+    )
+}
+
+/// Directory of the Services workflow of `action`.
+#[cfg(target_os = "macos")]
+fn service_dir(app: &AppHandle, action: QuickAction) -> Result<std::path::PathBuf, GibberError> {
+    Ok(app
+        .path()
+        .home_dir()?
+        .join("Library/Services")
+        .join(format!("{}.workflow", action.menu_title())))
+}
+
+/// Makes the Services menu pick up added or removed workflows.
+#[cfg(target_os = "macos")]
+fn refresh_services() {
+    if let Err(e) = std::process::Command::new("/System/Library/CoreServices/pbs")
+        .arg("-update")
+        .status()
+    {
+        tracing::warn!("failed to refresh the Services menu: {e}");
+    }
+}
+
+/// Adds a Services menu entry for each quick action and returns their
+/// menu titles.
+///
+/// Installing again overwrites the entries, which updates them after the
+/// link format changes.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNSUPPORTED` outside macOS, or `IO`
+/// if the workflows can't be written.
+///
+/// # Example
+///
+/// ```typescript
+/// const titles = await invoke("install_text_services");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn install_text_services(app: AppHandle) -> Result<Vec<String>, GibberError> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut titles = Vec::new();
+        for action in QuickAction::ALL {
+            let contents = service_dir(&app, action)?.join("Contents");
+            std::fs::create_dir_all(&contents)?;
+            std::fs::write(contents.join("Info.plist"), service_info_plist(action))?;
+            std::fs::write(contents.join("document.wflow"), service_document(action))?;
+            titles.push(action.menu_title().to_string());
+        }
+        refresh_services();
+        Ok(titles)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = app;
+        Err(GibberError::new(
+            "UNSUPPORTED",
+            "Services menu entries are only supported on macOS",
+        ))
+    }
+}
+
+/// Removes the Services menu entries added by `install_text_services`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNSUPPORTED` outside macOS, or `IO`
+/// if a workflow can't be deleted.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("remove_text_services");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn remove_text_services(app: AppHandle) -> Result<(), GibberError> {
+    #[cfg(target_os = "macos")]
+    {
+        for action in QuickAction::ALL {
+            let dir = service_dir(&app, action)?;
+            if dir.exists() {
+                std::fs::remove_dir_all(dir)?;
+            }
+        }
+        refresh_services();
+        Ok(())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = app;
+        Err(GibberError::new(
+            "UNSUPPORTED",
+            "Services menu entries are only supported on macOS",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugs_round_trip() {
+        for action in QuickAction::ALL {
+            assert_eq!(QuickAction::from_slug(action.slug()), Some(action));
+        }
+        assert_eq!(QuickAction::from_slug("fixGrammar"), None);
+        assert_eq!(
+            QuickAction::Shorten.rewrite_mode(),
+            Some(RewriteMode::Shorten)
+        );
+        assert_eq!(QuickAction::Translate.rewrite_mode(), None);
+        assert_eq!(
+            InputSource::from_param(Some("services")),
+            InputSource::Services
+        );
+        assert_eq!(InputSource::from_param(None), InputSource::Link);
+    }
+
+    #[test]
+    fn test_service_workflow_opens_the_input_link() {
+        let document = service_document(QuickAction::FixGrammar);
+        assert!(document.contains("gibber://input?source=services&amp;action=fix-grammar"));
+        assert!(document.contains("com.apple.Automator.servicesMenu"));
+        assert!(!document.contains("{script}"));
+        let info = service_info_plist(QuickAction::FixGrammar);
+        assert!(info.contains("<string>Fix Grammar with Gibber AI</string>"));
+    }
+}
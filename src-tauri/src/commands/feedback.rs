@@ -0,0 +1,400 @@
+//! Thumbs-up and thumbs-down feedback on answers.
+//!
+//! Rating an assistant message stores the rating with what produced the
+//! answer: the model, the conversation's system prompt, and the messages
+//! before it. The snapshot keeps the feedback meaningful after the
+//! conversation moves on, and [`export_feedback`] writes it as JSON Lines
+//! in one of two dataset layouts:
+//!
+//! - `messages`: thumbs-up answers as `{"messages": [...]}` records for
+//!   supervised fine-tuning
+//! - `kto`: every rated answer as `{"prompt", "completion", "label"}`
+//!   records for preference tuning with unpaired feedback
+//!
+//! Ratings of conversations enrolled in an A/B experiment also count
+//! towards their variant (see [`experiments`]).
+
+use std::io::Write as _;
+
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{ChatMessage, MessageRole};
+use crate::commands::conversations;
+use crate::commands::experiments;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Longest comment kept with a rating, in characters.
+const MAX_COMMENT_CHARS: usize = 2_000;
+
+/// A thumbs-up or thumbs-down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    /// A good answer
+    Up,
+    /// A bad answer
+    Down,
+}
+
+impl Rating {
+    const fn as_i64(self) -> i64 {
+        match self {
+            Self::Up => 1,
+            Self::Down => -1,
+        }
+    }
+
+    const fn from_i64(value: i64) -> Self {
+        if value > 0 {
+            Self::Up
+        } else {
+            Self::Down
+        }
+    }
+}
+
+/// Stored feedback on one answer.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Feedback {
+    /// Rated assistant message
+    pub message_id: String,
+    /// Its conversation
+    pub conversation_id: String,
+    /// The rating
+    pub rating: Rating,
+    /// Optional note on what was good or bad
+    pub comment: Option<String>,
+    /// Model that wrote the answer
+    pub model: Option<String>,
+    /// System prompt of the conversation when rated
+    pub system_prompt: Option<String>,
+    /// Messages before the answer, system prompt excluded
+    pub context: Vec<ChatMessage>,
+    /// The rated answer
+    pub completion: String,
+    /// Last rating time in Unix milliseconds
+    pub rated_at: i64,
+}
+
+impl Feedback {
+    /// The prompt side of a dataset record, system prompt first.
+    fn prompt(&self) -> Vec<ChatMessage> {
+        self.system_prompt
+            .iter()
+            .map(|system| ChatMessage::new(MessageRole::System, system))
+            .chain(self.context.iter().cloned())
+            .collect()
+    }
+}
+
+/// Dataset layouts of [`export_feedback`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackFormat {
+    /// Thumbs-up answers as chat fine-tuning records
+    #[default]
+    Messages,
+    /// Every rated answer with a boolean label
+    Kto,
+}
+
+const FEEDBACK_COLUMNS: &str = "message_id, conversation_id, rating, comment, model, \
+     system_prompt, context, completion, rated_at";
+
+fn feedback_from_row(row: &Row<'_>) -> rusqlite::Result<Feedback> {
+    let context: String = row.get(6)?;
+    Ok(Feedback {
+        message_id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        rating: Rating::from_i64(row.get(2)?),
+        comment: row.get(3)?,
+        model: row.get(4)?,
+        system_prompt: row.get(5)?,
+        context: serde_json::from_str(&context).unwrap_or_default(),
+        completion: row.get(7)?,
+        rated_at: row.get(8)?,
+    })
+}
+
+/// Stores or replaces feedback on `message_id`, or removes it when
+/// `rating` is `None`.
+fn rate(
+    conn: &Connection,
+    message_id: &str,
+    rating: Option<Rating>,
+    comment: Option<&str>,
+) -> Result<Option<Feedback>, GibberError> {
+    let message = conversations::load_message(conn, message_id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Message {message_id} not found")))?;
+    if message.role != MessageRole::Assistant {
+        return Err(GibberError::new(
+            "INVALID_INPUT",
+            "Only answers can be rated",
+        ));
+    }
+    experiments::record_rating(
+        conn,
+        message_id,
+        &message.conversation_id,
+        rating.map(|rating| rating == Rating::Up),
+    )?;
+    let Some(rating) = rating else {
+        conn.execute(
+            "DELETE FROM message_feedback WHERE message_id = ?1",
+            [message_id],
+        )?;
+        return Ok(None);
+    };
+
+    let conversation = conversations::load_conversation(conn, &message.conversation_id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", "The message's conversation is gone"))?;
+    let context: Vec<ChatMessage> = conversation
+        .messages
+        .iter()
+        .take_while(|earlier| earlier.id != message.id)
+        .map(|earlier| ChatMessage::new(earlier.role, &earlier.content))
+        .collect();
+    let comment = comment
+        .map(str::trim)
+        .filter(|comment| !comment.is_empty())
+        .map(|comment| comment.chars().take(MAX_COMMENT_CHARS).collect::<String>());
+    let feedback = Feedback {
+        message_id: message.id,
+        conversation_id: message.conversation_id,
+        rating,
+        comment,
+        model: message.model,
+        system_prompt: conversation.conversation.system_prompt,
+        context,
+        completion: message.content,
+        rated_at: db::now_millis(),
+    };
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO message_feedback ({FEEDBACK_COLUMNS})
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        ),
+        params![
+            feedback.message_id,
+            feedback.conversation_id,
+            feedback.rating.as_i64(),
+            feedback.comment,
+            feedback.model,
+            feedback.system_prompt,
+            serde_json::to_string(&feedback.context).unwrap_or_default(),
+            feedback.completion,
+            feedback.rated_at,
+        ],
+    )?;
+    Ok(Some(feedback))
+}
+
+/// The dataset record of one piece of feedback, or `None` if the format
+/// leaves it out.
+fn record(feedback: &Feedback, format: FeedbackFormat) -> Option<serde_json::Value> {
+    let completion = ChatMessage::new(MessageRole::Assistant, &feedback.completion);
+    match format {
+        FeedbackFormat::Messages => (feedback.rating == Rating::Up).then(|| {
+            let mut messages = feedback.prompt();
+            messages.push(completion);
+            json!({ "messages": messages })
+        }),
+        FeedbackFormat::Kto => Some(json!({
+            "prompt": feedback.prompt(),
+            "completion": [completion],
+            "label": feedback.rating == Rating::Up,
+        })),
+    }
+}
+
+/// Rates an answer. `rating: null` removes the rating.
+///
+/// # Returns
+///
+/// The stored feedback, or `null` once removed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the message doesn't
+/// exist, `INVALID_INPUT` if it isn't an assistant message, or `DATABASE`
+/// on failure.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("rate_message", {
+///   messageId: id,
+///   rating: "down",
+///   comment: "Wrong time signature",
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn rate_message(
+    db: State<'_, Database>,
+    message_id: &str,
+    rating: Option<Rating>,
+    comment: Option<String>,
+) -> Result<Option<Feedback>, GibberError> {
+    rate(&db.conn(), message_id, rating, comment.as_deref())
+}
+
+/// Lists feedback, newest first, optionally for one conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_feedback(
+    db: State<'_, Database>,
+    conversation_id: Option<&str>,
+) -> Result<Vec<Feedback>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {FEEDBACK_COLUMNS} FROM message_feedback
+         WHERE ?1 IS NULL OR conversation_id = ?1
+         ORDER BY rated_at DESC"
+    ))?;
+    let feedback = stmt
+        .query_map([conversation_id], feedback_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(feedback)
+}
+
+/// Writes feedback to a JSON Lines dataset, oldest first.
+///
+/// # Returns
+///
+/// The number of records written.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `IO` if the file cannot be written.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("export_feedback", { path: "/Users/me/feedback.jsonl", format: "kto" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn export_feedback(
+    app: AppHandle,
+    path: String,
+    format: Option<FeedbackFormat>,
+) -> Result<usize, GibberError> {
+    let format = format.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        export(&app.state::<Database>().conn(), &path, format)
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+fn export(conn: &Connection, path: &str, format: FeedbackFormat) -> Result<usize, GibberError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {FEEDBACK_COLUMNS} FROM message_feedback ORDER BY rated_at"
+    ))?;
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut count = 0;
+    for feedback in stmt.query_map([], feedback_from_row)? {
+        if let Some(record) = record(&feedback?, format) {
+            writeln!(file, "{record}")?;
+            count += 1;
+        }
+    }
+    file.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::conversations::{NewConversation, NewMessage};
+
+    fn message(role: MessageRole, content: &str) -> NewMessage {
+        NewMessage {
+            role,
+            content: content.to_string(),
+            model: (role == MessageRole::Assistant).then(|| "openai/gpt-4o".to_string()),
+            usage: None,
+        }
+    }
+
+    fn setup(conn: &Connection) -> (String, String) {
+        let conversation = conversations::insert_conversation(
+            conn,
+            &NewConversation {
+                system_prompt: Some("Be brief".to_string()),
+                ..NewConversation::default()
+            },
+        )
+        .unwrap();
+        let question = message(MessageRole::User, "Tempo for dub?");
+        let question = conversations::insert_message(conn, &conversation.id, &question).unwrap();
+        let answer = message(MessageRole::Assistant, "Around 70 BPM");
+        let answer = conversations::insert_message(conn, &conversation.id, &answer).unwrap();
+        let later = message(MessageRole::User, "Thanks");
+        conversations::insert_message(conn, &conversation.id, &later).unwrap();
+        (question.id, answer.id)
+    }
+
+    #[test]
+    fn test_rating_snapshots_context() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let (question, answer) = setup(&conn);
+        assert_eq!(
+            rate(&conn, &question, Some(Rating::Up), None)
+                .unwrap_err()
+                .code(),
+            "INVALID_INPUT"
+        );
+        let feedback = rate(&conn, &answer, Some(Rating::Down), Some("  slow "))
+            .unwrap()
+            .unwrap();
+        assert_eq!(feedback.context.len(), 1);
+        assert_eq!(feedback.comment.as_deref(), Some("slow"));
+        assert_eq!(feedback.model.as_deref(), Some("openai/gpt-4o"));
+
+        rate(&conn, &answer, Some(Rating::Up), None).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM message_feedback", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(rate(&conn, &answer, None, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dataset_records() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let (_, answer) = setup(&conn);
+        let mut feedback = rate(&conn, &answer, Some(Rating::Up), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            record(&feedback, FeedbackFormat::Messages).unwrap(),
+            json!({ "messages": [
+                { "role": "system", "content": "Be brief" },
+                { "role": "user", "content": "Tempo for dub?" },
+                { "role": "assistant", "content": "Around 70 BPM" },
+            ] })
+        );
+        feedback.rating = Rating::Down;
+        assert!(record(&feedback, FeedbackFormat::Messages).is_none());
+        let kto = record(&feedback, FeedbackFormat::Kto).unwrap();
+        assert_eq!(kto["label"], false);
+        assert_eq!(kto["prompt"].as_array().unwrap().len(), 2);
+    }
+}
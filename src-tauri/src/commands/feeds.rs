@@ -0,0 +1,847 @@
+//! RSS and Atom feed subscriptions with a daily digest.
+//!
+//! Subscribed feeds are polled in the background every
+//! `pollIntervalMinutes`. New entries are stored once per feed, keyed by
+//! their GUID, so re-published or re-ordered feeds don't produce duplicates.
+//!
+//! Once a day, after `digestHour` local time, entries not yet digested are
+//! summarized feed by feed, each with the feed's own prompt when it has one,
+//! and the sections are stored together as a new conversation with source
+//! `"feeds"`. A digest can also be generated on demand.
+
+use std::time::Duration;
+
+use chrono::{Local, Timelike};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Url};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::conversations::{self, NewConversation, NewMessage};
+use crate::commands::events;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::reading_list;
+use crate::commands::tray;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the [`FeedSettings`] document.
+pub const SETTINGS_KEY: &str = "feeds";
+
+/// Settings key holding the local date of the last digest.
+const LAST_DIGEST_KEY: &str = "feeds.lastDigest";
+
+/// How often the background loop checks for due feeds.
+const TICK_INTERVAL: Duration = Duration::from_mins(1);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Largest feed document read, in bytes.
+const MAX_FEED_BYTES: usize = 5_000_000;
+
+/// Newest entries kept from each poll; older ones are assumed seen.
+const MAX_ITEMS_PER_POLL: usize = 30;
+
+/// Longest entry summary stored, in characters.
+const MAX_SUMMARY_CHARS: usize = 2_000;
+
+/// Most entries of one feed sent to the model for a digest.
+const MAX_DIGEST_ITEMS: u32 = 40;
+
+/// Prompt for feeds that don't set their own.
+const DEFAULT_PROMPT: &str = "Summarize the new entries of this feed in a few bullets. \
+     Group related entries, lead with the most important news, and keep each bullet to \
+     one or two sentences with a link to the entry.";
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Feed {id} not found"))
+}
+
+/// Polling and digest preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FeedSettings {
+    /// Minutes between polls of each feed
+    pub poll_interval_minutes: u32,
+    /// Whether a digest is generated every day
+    pub daily_digest: bool,
+    /// Local hour (0-23) after which the daily digest is generated
+    pub digest_hour: u32,
+    /// Model for digests; defaults to the standard tier
+    pub model: Option<String>,
+}
+
+impl Default for FeedSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval_minutes: 60,
+            daily_digest: true,
+            digest_hour: 8,
+            model: None,
+        }
+    }
+}
+
+impl FeedSettings {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        if !(5..=24 * 60).contains(&self.poll_interval_minutes) {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                "Poll interval must be between 5 minutes and a day",
+            ));
+        }
+        if self.digest_hour > 23 {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                "Digest hour must be between 0 and 23",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> FeedSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// A subscribed feed.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Feed {
+    /// Unique identifier
+    pub id: String,
+    /// Feed document URL
+    pub url: String,
+    /// Title from the feed
+    pub title: Option<String>,
+    /// Summarization prompt for digests; the default is used when `None`
+    pub prompt: Option<String>,
+    /// Whether the feed is polled
+    pub enabled: bool,
+    /// Last poll in Unix milliseconds
+    pub last_polled_at: Option<i64>,
+    /// Error of the last poll, if it failed
+    pub last_error: Option<String>,
+    /// Subscription time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// Fields that can be changed on a subscription.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedUpdate {
+    /// Summarization prompt; empty restores the default
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Whether the feed is polled
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// An entry of a feed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedItem {
+    /// Unique identifier
+    pub id: String,
+    /// Feed the entry belongs to
+    pub feed_id: String,
+    /// Entry GUID from the feed
+    pub guid: String,
+    /// Entry title
+    pub title: String,
+    /// Link to the entry
+    pub link: Option<String>,
+    /// Plain-text summary
+    pub summary: Option<String>,
+    /// Publication time in Unix milliseconds
+    pub published_at: Option<i64>,
+    /// When the entry was first seen, in Unix milliseconds
+    pub fetched_at: i64,
+    /// When the entry was included in a digest, in Unix milliseconds
+    pub digested_at: Option<i64>,
+}
+
+/// An entry parsed from a feed document, before it is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParsedItem {
+    guid: String,
+    title: String,
+    link: Option<String>,
+    summary: Option<String>,
+    published_at: Option<i64>,
+}
+
+const FEED_COLUMNS: &str =
+    "id, url, title, prompt, enabled, last_polled_at, last_error, created_at";
+
+const ITEM_COLUMNS: &str =
+    "id, feed_id, guid, title, link, summary, published_at, fetched_at, digested_at";
+
+fn feed_from_row(row: &Row<'_>) -> rusqlite::Result<Feed> {
+    Ok(Feed {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        title: row.get(2)?,
+        prompt: row.get(3)?,
+        enabled: row.get(4)?,
+        last_polled_at: row.get(5)?,
+        last_error: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+fn item_from_row(row: &Row<'_>) -> rusqlite::Result<FeedItem> {
+    Ok(FeedItem {
+        id: row.get(0)?,
+        feed_id: row.get(1)?,
+        guid: row.get(2)?,
+        title: row.get(3)?,
+        link: row.get(4)?,
+        summary: row.get(5)?,
+        published_at: row.get(6)?,
+        fetched_at: row.get(7)?,
+        digested_at: row.get(8)?,
+    })
+}
+
+fn load_feed(conn: &Connection, id: &str) -> Result<Feed, GibberError> {
+    conn.query_row(
+        &format!("SELECT {FEED_COLUMNS} FROM feeds WHERE id = ?1"),
+        [id],
+        feed_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found(id))
+}
+
+fn list(conn: &Connection) -> rusqlite::Result<Vec<Feed>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {FEED_COLUMNS} FROM feeds ORDER BY created_at"
+    ))?;
+    let feeds = stmt
+        .query_map([], feed_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(feeds)
+}
+
+/// Parses an RSS, Atom, or JSON Feed document.
+///
+/// # Returns
+///
+/// The feed title and its newest entries.
+fn parse_feed(document: &[u8]) -> Result<(Option<String>, Vec<ParsedItem>), GibberError> {
+    let feed = feed_rs::parser::parse(document)
+        .map_err(|e| GibberError::new("INVALID_FEED", format!("Not a feed: {e}")))?;
+    let title = feed
+        .title
+        .map(|text| text.content.trim().to_string())
+        .filter(|title| !title.is_empty());
+    let mut items: Vec<ParsedItem> = feed
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let summary = entry
+                .summary
+                .map(|text| text.content)
+                .or_else(|| entry.content.and_then(|content| content.body))
+                .map(|html| {
+                    reading_list::plain_text(&html)
+                        .chars()
+                        .take(MAX_SUMMARY_CHARS)
+                        .collect::<String>()
+                })
+                .filter(|summary| !summary.is_empty());
+            ParsedItem {
+                title: entry
+                    .title
+                    .map(|text| text.content.trim().to_string())
+                    .filter(|title| !title.is_empty())
+                    .unwrap_or_else(|| "Untitled".to_string()),
+                link: entry.links.into_iter().next().map(|link| link.href),
+                published_at: entry
+                    .published
+                    .or(entry.updated)
+                    .map(|time| time.timestamp_millis()),
+                guid: entry.id,
+                summary,
+            }
+        })
+        .collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.published_at));
+    items.truncate(MAX_ITEMS_PER_POLL);
+    Ok((title, items))
+}
+
+/// Stores entries not seen before.
+///
+/// # Returns
+///
+/// The number of new entries.
+fn insert_items(conn: &Connection, feed_id: &str, items: &[ParsedItem]) -> rusqlite::Result<usize> {
+    let now = db::now_millis();
+    let mut stmt = conn.prepare(
+        "INSERT INTO feed_items (id, feed_id, guid, title, link, summary, published_at, fetched_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) ON CONFLICT(feed_id, guid) DO NOTHING",
+    )?;
+    let mut inserted = 0;
+    for item in items {
+        inserted += stmt.execute(params![
+            db::new_id(),
+            feed_id,
+            item.guid,
+            item.title,
+            item.link,
+            item.summary,
+            item.published_at,
+            now,
+        ])?;
+    }
+    Ok(inserted)
+}
+
+/// Downloads and parses a feed document.
+async fn fetch(
+    app: &AppHandle,
+    url: &str,
+) -> Result<(Option<String>, Vec<ParsedItem>), GibberError> {
+    let client = network::client(app, Service::Feeds)?;
+    let builder = client.get(url).timeout(REQUEST_TIMEOUT).header(
+        reqwest::header::ACCEPT,
+        "application/rss+xml, application/atom+xml, application/feed+json, application/xml;q=0.9, */*;q=0.8",
+    );
+    let response = network_activity::send(app, Service::Feeds, "feeds.poll", builder).await?;
+    if !response.status().is_success() {
+        return Err(GibberError::new(
+            "NETWORK",
+            format!("Feed returned HTTP {}", response.status()),
+        ));
+    }
+    let document = response.bytes().await?;
+    if document.len() > MAX_FEED_BYTES {
+        return Err(GibberError::new(
+            "INVALID_FEED",
+            "Feed document is too large",
+        ));
+    }
+    parse_feed(&document)
+}
+
+/// Polls a feed, stores its new entries, and records the outcome.
+///
+/// # Returns
+///
+/// The number of new entries.
+async fn poll(app: &AppHandle, feed: &Feed) -> Result<usize, GibberError> {
+    let result = fetch(app, &feed.url).await;
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    let now = db::now_millis();
+    match result {
+        Ok((title, items)) => {
+            let inserted = insert_items(&conn, &feed.id, &items)?;
+            conn.execute(
+                "UPDATE feeds SET title = COALESCE(?2, title), last_polled_at = ?3, last_error = NULL
+                 WHERE id = ?1",
+                params![feed.id, title, now],
+            )?;
+            Ok(inserted)
+        }
+        Err(e) => {
+            tracing::warn!(id = %feed.id, "feed not polled: {e}");
+            conn.execute(
+                "UPDATE feeds SET last_polled_at = ?2, last_error = ?3 WHERE id = ?1",
+                params![feed.id, now, e.to_string()],
+            )?;
+            Err(e)
+        }
+    }
+}
+
+fn undigested_items(conn: &Connection, feed_id: &str) -> rusqlite::Result<Vec<FeedItem>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ITEM_COLUMNS} FROM feed_items WHERE feed_id = ?1 AND digested_at IS NULL
+         ORDER BY COALESCE(published_at, fetched_at) DESC LIMIT ?2"
+    ))?;
+    let items = stmt
+        .query_map(params![feed_id, MAX_DIGEST_ITEMS], item_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(items)
+}
+
+/// Formats a feed's entries as the user message of a digest request.
+fn digest_input(feed: &Feed, items: &[FeedItem]) -> String {
+    let mut input = format!("# {}\n", feed.title.as_deref().unwrap_or(&feed.url));
+    for item in items {
+        input.push_str("\n## ");
+        input.push_str(&item.title);
+        input.push('\n');
+        if let Some(link) = &item.link {
+            input.push_str(link);
+            input.push('\n');
+        }
+        if let Some(summary) = &item.summary {
+            input.push('\n');
+            input.push_str(summary);
+            input.push('\n');
+        }
+    }
+    input
+}
+
+/// Summarizes every feed with undigested entries into a new conversation.
+///
+/// # Returns
+///
+/// The conversation ID, or `None` if there was nothing new.
+async fn digest(app: &AppHandle) -> Result<Option<String>, GibberError> {
+    let (settings, batches) = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let mut batches = Vec::new();
+        for feed in list(&conn)? {
+            let items = undigested_items(&conn, &feed.id)?;
+            if !items.is_empty() {
+                batches.push((feed, items));
+            }
+        }
+        (load_settings(&conn), batches)
+    };
+    if batches.is_empty() {
+        return Ok(None);
+    }
+
+    let mut sections = Vec::new();
+    let mut model = None;
+    for (feed, items) in &batches {
+        let prompt = feed
+            .prompt
+            .as_deref()
+            .filter(|prompt| !prompt.trim().is_empty())
+            .unwrap_or(DEFAULT_PROMPT);
+        let request = ChatRequest::new(
+            settings.model.as_deref(),
+            vec![
+                ChatMessage::new(MessageRole::System, prompt),
+                ChatMessage::new(MessageRole::User, digest_input(feed, items)),
+            ],
+        );
+        let completion = chat::complete(app, &request).await?;
+        let heading = feed.title.as_deref().unwrap_or(&feed.url);
+        sections.push(format!("## {heading}\n\n{}", completion.content.trim()));
+        model = Some(completion.model);
+    }
+
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let count: usize = batches.iter().map(|(_, items)| items.len()).sum();
+    let conversation_id = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let conversation = conversations::insert_conversation(
+            &conn,
+            &NewConversation {
+                title: format!("Feed digest ({today})"),
+                model: model.clone(),
+                system_prompt: None,
+                source: Some("feeds".to_string()),
+            },
+        )?;
+        conversations::insert_message(
+            &conn,
+            &conversation.id,
+            &NewMessage {
+                role: MessageRole::User,
+                content: format!(
+                    "Digest of {count} new entries from {} feeds.",
+                    batches.len()
+                ),
+                model: None,
+                usage: None,
+            },
+        )?;
+        conversations::insert_message(
+            &conn,
+            &conversation.id,
+            &NewMessage {
+                role: MessageRole::Assistant,
+                content: sections.join("\n\n"),
+                model,
+                usage: None,
+            },
+        )?;
+        let now = db::now_millis();
+        let mut stmt = conn.prepare("UPDATE feed_items SET digested_at = ?2 WHERE id = ?1")?;
+        for (_, items) in &batches {
+            for item in items {
+                stmt.execute(params![item.id, now])?;
+            }
+        }
+        db::write_setting(&conn, LAST_DIGEST_KEY, &today)?;
+        conversation.id
+    };
+    events::emit_typed(app, events::FEEDS_DIGEST, &conversation_id);
+    Ok(Some(conversation_id))
+}
+
+/// Whether the daily digest is due at `hour` on `today`.
+fn digest_due(settings: &FeedSettings, last_digest: Option<&str>, today: &str, hour: u32) -> bool {
+    settings.daily_digest && hour >= settings.digest_hour && last_digest != Some(today)
+}
+
+/// Polls due feeds and generates the daily digest when it is due.
+async fn tick(app: &AppHandle) -> Result<(), GibberError> {
+    let (settings, feeds, last_digest) = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        (
+            load_settings(&conn),
+            list(&conn)?,
+            db::read_setting(&conn, LAST_DIGEST_KEY)?,
+        )
+    };
+    let interval = i64::from(settings.poll_interval_minutes) * 60_000;
+    let now = db::now_millis();
+    for feed in feeds.iter().filter(|feed| feed.enabled) {
+        if feed
+            .last_polled_at
+            .is_none_or(|polled| now - polled >= interval)
+        {
+            // Failures are recorded on the feed.
+            let _ = poll(app, feed).await;
+        }
+    }
+    let local = Local::now();
+    let today = local.format("%Y-%m-%d").to_string();
+    if digest_due(&settings, last_digest.as_deref(), &today, local.hour()) {
+        digest(app).await?;
+    }
+    Ok(())
+}
+
+/// Starts the background loop that polls feeds and generates digests.
+///
+/// Must be called after the [`Database`] and tray state have been added to
+/// managed state.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if tray::is_sync_paused(&app) {
+                continue;
+            }
+            if let Err(e) = tick(&app).await {
+                tracing::warn!(code = e.code(), "feed update failed: {e}");
+            }
+        }
+    });
+}
+
+/// Returns the feed preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_feed_settings(db: State<'_, Database>) -> FeedSettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the feed preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` if a value is out of
+/// range, or `DATABASE` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_feed_settings", {
+///   settings: { pollIntervalMinutes: 30, dailyDigest: true, digestHour: 7 },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_feed_settings(
+    db: State<'_, Database>,
+    settings: FeedSettings,
+) -> Result<FeedSettings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Lists subscribed feeds.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_feeds(db: State<'_, Database>) -> Result<Vec<Feed>, GibberError> {
+    Ok(list(&db.conn())?)
+}
+
+/// Subscribes to a feed and stores its current entries.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_URL` for anything but http(s)
+/// URLs, `ALREADY_SUBSCRIBED` for a known feed, `NETWORK` if it can't be
+/// downloaded, or `INVALID_FEED` if it isn't RSS, Atom, or JSON Feed.
+///
+/// # Example
+///
+/// ```typescript
+/// const feed = await invoke("subscribe_feed", {
+///   url: "https://example.com/feed.xml",
+///   prompt: "List only releases and breaking changes.",
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn subscribe_feed(
+    app: AppHandle,
+    url: String,
+    prompt: Option<String>,
+) -> Result<Feed, GibberError> {
+    let url = match Url::parse(url.trim()) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed.to_string(),
+        _ => {
+            return Err(GibberError::new(
+                "INVALID_URL",
+                format!("Not an http(s) URL: {url}"),
+            ))
+        }
+    };
+    let exists = app
+        .state::<Database>()
+        .conn()
+        .query_row("SELECT 1 FROM feeds WHERE url = ?1", [&url], |_| Ok(()))
+        .optional()?
+        .is_some();
+    if exists {
+        return Err(GibberError::new(
+            "ALREADY_SUBSCRIBED",
+            format!("Already subscribed to {url}"),
+        ));
+    }
+    let (title, items) = fetch(&app, &url).await?;
+
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    let id = db::new_id();
+    let now = db::now_millis();
+    conn.execute(
+        "INSERT INTO feeds (id, url, title, prompt, enabled, last_polled_at, created_at)
+         VALUES (?1, ?2, ?3, ?4, 1, ?5, ?5)",
+        params![
+            id,
+            url,
+            title,
+            prompt.filter(|prompt| !prompt.trim().is_empty()),
+            now
+        ],
+    )?;
+    insert_items(&conn, &id, &items)?;
+    load_feed(&conn, &id)
+}
+
+/// Changes a feed's prompt or pauses it.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the feed doesn't exist, or
+/// `DATABASE` if the update fails.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("update_feed", { id, update: { prompt: "Only security advisories." } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn update_feed(
+    db: State<'_, Database>,
+    id: &str,
+    update: FeedUpdate,
+) -> Result<Feed, GibberError> {
+    let conn = db.conn();
+    let feed = load_feed(&conn, id)?;
+    let prompt = match update.prompt {
+        Some(prompt) if prompt.trim().is_empty() => None,
+        Some(prompt) => Some(prompt),
+        None => feed.prompt,
+    };
+    conn.execute(
+        "UPDATE feeds SET prompt = ?2, enabled = ?3 WHERE id = ?1",
+        params![id, prompt, update.enabled.unwrap_or(feed.enabled)],
+    )?;
+    load_feed(&conn, id)
+}
+
+/// Unsubscribes from a feed and drops its entries.
+///
+/// # Returns
+///
+/// `true` if the feed existed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn unsubscribe_feed(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db.conn().execute("DELETE FROM feeds WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+/// Lists stored entries, newest first, of one feed or of all feeds.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const items = await invoke("list_feed_items", { feedId, limit: 50 });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn list_feed_items(
+    db: State<'_, Database>,
+    feed_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<FeedItem>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ITEM_COLUMNS} FROM feed_items WHERE ?1 IS NULL OR feed_id = ?1
+         ORDER BY COALESCE(published_at, fetched_at) DESC LIMIT ?2"
+    ))?;
+    let items = stmt
+        .query_map(params![feed_id, limit.unwrap_or(100)], item_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(items)
+}
+
+/// Polls every enabled feed now.
+///
+/// # Returns
+///
+/// The number of new entries across all feeds.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the feeds can't be listed; failures of single
+/// feeds are recorded on the feed instead.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_feeds(app: AppHandle) -> Result<usize, GibberError> {
+    let feeds = list(&app.state::<Database>().conn())?;
+    let mut inserted = 0;
+    for feed in feeds.iter().filter(|feed| feed.enabled) {
+        inserted += poll(&app, feed).await.unwrap_or(0);
+    }
+    Ok(inserted)
+}
+
+/// Generates a digest of all entries not digested yet.
+///
+/// # Returns
+///
+/// The ID of the digest conversation, or `null` if there was nothing new.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with the chat error code if a summary fails, or
+/// `DATABASE` if the digest cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const conversationId = await invoke("generate_feed_digest");
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn generate_feed_digest(app: AppHandle) -> Result<Option<String>, GibberError> {
+    digest(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Synth News</title>
+<item><guid>a</guid><title>Older</title><link>https://example.com/a</link>
+<pubDate>Mon, 01 Jan 2024 10:00:00 GMT</pubDate></item>
+<item><guid>b</guid><title>Newer</title><link>https://example.com/b</link>
+<description>&lt;p&gt;Granular &lt;b&gt;synthesis&lt;/b&gt;&lt;/p&gt;</description>
+<pubDate>Tue, 02 Jan 2024 10:00:00 GMT</pubDate></item>
+</channel></rss>"#;
+
+    #[test]
+    fn test_parse_feed() {
+        let (title, items) = parse_feed(RSS.as_bytes()).unwrap();
+        assert_eq!(title.as_deref(), Some("Synth News"));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].guid, "b");
+        assert_eq!(items[0].link.as_deref(), Some("https://example.com/b"));
+        assert_eq!(items[0].summary.as_deref(), Some("Granular synthesis"));
+        assert_eq!(
+            parse_feed(b"<html></html>").unwrap_err().code(),
+            "INVALID_FEED"
+        );
+    }
+
+    #[test]
+    fn test_insert_items_deduplicates() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        conn.execute(
+            "INSERT INTO feeds (id, url, enabled, created_at) VALUES ('f', 'https://example.com/feed', 1, 0)",
+            [],
+        )
+        .unwrap();
+        let (_, items) = parse_feed(RSS.as_bytes()).unwrap();
+        assert_eq!(insert_items(&conn, "f", &items).unwrap(), 2);
+        assert_eq!(insert_items(&conn, "f", &items).unwrap(), 0);
+        assert_eq!(undigested_items(&conn, "f").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_digest_due_once_a_day() {
+        let settings = FeedSettings::default();
+        assert!(!digest_due(&settings, None, "2024-01-02", 7));
+        assert!(digest_due(&settings, None, "2024-01-02", 8));
+        assert!(digest_due(&settings, Some("2024-01-01"), "2024-01-02", 20));
+        assert!(!digest_due(&settings, Some("2024-01-02"), "2024-01-02", 20));
+        let off = FeedSettings {
+            daily_digest: false,
+            ..FeedSettings::default()
+        };
+        assert!(!digest_due(&off, None, "2024-01-02", 20));
+    }
+
+    #[test]
+    fn test_settings_validate() {
+        assert!(FeedSettings::default().validate().is_ok());
+        let fast = FeedSettings {
+            poll_interval_minutes: 1,
+            ..FeedSettings::default()
+        };
+        assert_eq!(fast.validate().unwrap_err().code(), "INVALID_SETTINGS");
+    }
+}
@@ -0,0 +1,692 @@
+//! Flashcards from conversations and documents, for Anki.
+//!
+//! Generation and export are separate steps so the user can review the
+//! cards first: [`generate_flashcards`] asks a model for question and
+//! answer pairs about a conversation or a Markdown or plain text
+//! attachment (as structured output) and returns them without side
+//! effects; [`export_flashcards`] writes the confirmed, possibly edited,
+//! cards as a deck Anki imports:
+//!
+//! - `.apkg`: a package with its own deck and note type, built as Anki's
+//!   legacy (schema 11) collection, which every Anki version imports
+//! - `.txt`: tab-separated notes with the header lines Anki 2.1.55 and
+//!   later read the deck, note type, and tag column from
+//!
+//! Card GUIDs are derived from the deck name and the question, so
+//! importing an edited export of the same deck updates its cards instead
+//! of duplicating them.
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write as _;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::attachments::{self, AttachmentKind, AttachmentStore};
+use crate::commands::conversations;
+use crate::commands::markdown;
+use crate::commands::telemetry;
+use crate::commands::workflows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Cards asked for unless a count is given.
+const DEFAULT_COUNT: u32 = 20;
+
+/// Most cards per generation.
+const MAX_COUNT: u32 = 100;
+
+/// Most cards per export.
+const MAX_EXPORT_CARDS: usize = 5_000;
+
+/// Longest source material, in characters.
+pub const MAX_SOURCE_CHARS: usize = 100_000;
+
+/// ID of the note type in exported packages, fixed so repeated imports
+/// reuse it.
+const MODEL_ID: i64 = 1_728_000_000_000;
+
+const GENERATE_PROMPT: &str = "Write flashcards for studying the material below. Each card \
+     asks one question about a single fact or idea on the front and answers it on the back in \
+     a sentence or two. Questions must make sense without the material. Write in the language \
+     of the material, and give each card one to three short lowercase topic tags. Write at \
+     most {count} cards, fewer if the material has less worth remembering, and skip small \
+     talk.";
+
+/// Where the cards come from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FlashcardSource {
+    /// A conversation's messages
+    #[serde(rename_all = "camelCase")]
+    Conversation {
+        /// The conversation
+        conversation_id: String,
+    },
+    /// A Markdown or plain text attachment, such as a knowledge base
+    /// document
+    #[serde(rename_all = "camelCase")]
+    Document {
+        /// The attachment
+        attachment_id: String,
+    },
+}
+
+/// A question and its answer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Flashcard {
+    /// The question
+    pub front: String,
+    /// The answer
+    pub back: String,
+    /// Topic tags, without spaces
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// File formats a deck exports to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashcardFormat {
+    /// An Anki package (`.apkg`)
+    #[default]
+    Apkg,
+    /// Tab-separated text (`.txt`)
+    Tsv,
+}
+
+/// A deck to export.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FlashcardExportRequest {
+    /// The cards
+    pub cards: Vec<Flashcard>,
+    /// Deck name; `::` separates subdecks
+    pub deck: String,
+    /// File to write
+    pub path: String,
+    /// File format (default: `.apkg`)
+    #[serde(default)]
+    pub format: FlashcardFormat,
+}
+
+/// A written deck.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FlashcardExport {
+    /// The file written
+    pub path: String,
+    /// Its format
+    pub format: FlashcardFormat,
+    /// Cards in the deck
+    pub count: usize,
+}
+
+fn generation_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "cards": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "front": { "type": "string" },
+                        "back": { "type": "string" },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                    },
+                    "required": ["front", "back", "tags"],
+                    "additionalProperties": false,
+                },
+            },
+        },
+        "required": ["cards"],
+        "additionalProperties": false,
+    })
+}
+
+#[derive(Deserialize)]
+struct Generation {
+    cards: Vec<Flashcard>,
+}
+
+/// Makes a tag usable in Anki, which separates tags with spaces.
+fn normalize_tag(tag: &str) -> String {
+    tag.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+fn parse_generation(reply: &str, count: u32) -> Result<Vec<Flashcard>, GibberError> {
+    let parse_error = || GibberError::new("PARSE_ERROR", "The model didn't return flashcards");
+    let value = workflows::find_json(reply).ok_or_else(parse_error)?;
+    let generation: Generation = serde_json::from_value(value).map_err(|_| parse_error())?;
+    let mut cards: Vec<Flashcard> = Vec::new();
+    for card in generation.cards {
+        let front = card.front.trim().to_string();
+        let back = card.back.trim().to_string();
+        if front.is_empty() || back.is_empty() || cards.iter().any(|c| c.front == front) {
+            continue;
+        }
+        let mut tags: Vec<String> = Vec::new();
+        for tag in card.tags.iter().map(|tag| normalize_tag(tag)) {
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        cards.push(Flashcard { front, back, tags });
+    }
+    cards.truncate(usize::try_from(count).unwrap_or(usize::MAX));
+    Ok(cards)
+}
+
+/// Reads the material the cards are written from.
+fn source_text(app: &AppHandle, source: &FlashcardSource) -> Result<String, GibberError> {
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    match source {
+        FlashcardSource::Conversation { conversation_id } => {
+            let conversation = conversations::load_conversation(&conn, conversation_id)?
+                .ok_or_else(|| {
+                    GibberError::new(
+                        "NOT_FOUND",
+                        format!("Conversation not found: {conversation_id}"),
+                    )
+                })?;
+            Ok(conversation
+                .messages
+                .iter()
+                .filter(|message| message.role != MessageRole::System)
+                .fold(String::new(), |mut transcript, message| {
+                    let _ = writeln!(
+                        transcript,
+                        "{}: {}\n",
+                        message.role.as_str(),
+                        message.content
+                    );
+                    transcript
+                }))
+        }
+        FlashcardSource::Document { attachment_id } => {
+            let attachment =
+                attachments::load_attachment(&conn, attachment_id)?.ok_or_else(|| {
+                    GibberError::new("NOT_FOUND", format!("Attachment {attachment_id} not found"))
+                })?;
+            if !matches!(
+                attachment.kind,
+                AttachmentKind::Markdown | AttachmentKind::Text
+            ) {
+                return Err(GibberError::new(
+                    "NOT_TEXT",
+                    format!("{} is not a text document", attachment.file_name),
+                ));
+            }
+            let path = app.state::<AttachmentStore>().file_path(&attachment);
+            Ok(std::fs::read_to_string(path)?)
+        }
+    }
+}
+
+/// A field as Anki stores it: HTML, with line breaks as `<br>`.
+fn field_html(text: &str) -> String {
+    markdown::escape(text.trim())
+        .replace("\r\n", "\n")
+        .replace('\n', "<br>")
+}
+
+/// Sort field text, without markup.
+fn sort_field(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// First-field checksum Anki finds duplicates with: the first 32 bits of
+/// the SHA-1 of the sort field.
+fn checksum(sort_field: &str) -> i64 {
+    let digest = Sha1::digest(sort_field.as_bytes());
+    i64::from(u32::from_be_bytes([
+        digest[0], digest[1], digest[2], digest[3],
+    ]))
+}
+
+fn guid(deck: &str, front: &str) -> String {
+    let digest = Sha256::digest(format!("{deck}\u{1f}{}", sort_field(front)).as_bytes());
+    hex::encode(&digest[..10])
+}
+
+/// Deck ID derived from the name, so every export of a deck has the same.
+fn deck_id(deck: &str) -> i64 {
+    let digest = Sha256::digest(deck.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes[2..].copy_from_slice(&digest[..6]);
+    i64::from_be_bytes(bytes) + 2
+}
+
+fn validate(request: &FlashcardExportRequest) -> Result<(), GibberError> {
+    let deck = request.deck.trim();
+    if deck.is_empty() || deck.chars().count() > 100 {
+        return Err(GibberError::invalid(
+            "The deck name must be between 1 and 100 characters",
+        ));
+    }
+    if request.cards.is_empty() || request.cards.len() > MAX_EXPORT_CARDS {
+        return Err(GibberError::invalid(format!(
+            "A deck needs between 1 and {MAX_EXPORT_CARDS} cards"
+        )));
+    }
+    if let Some(i) = request
+        .cards
+        .iter()
+        .position(|card| card.front.trim().is_empty() || card.back.trim().is_empty())
+    {
+        return Err(GibberError::invalid(format!(
+            "Card {} needs a question and an answer",
+            i + 1
+        )));
+    }
+    Ok(())
+}
+
+fn tags(card: &Flashcard) -> Vec<String> {
+    card.tags
+        .iter()
+        .map(|tag| normalize_tag(tag))
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn tsv(deck: &str, cards: &[Flashcard]) -> String {
+    let mut out =
+        format!("#separator:tab\n#html:true\n#notetype:Basic\n#deck:{deck}\n#tags column:3\n");
+    for card in cards {
+        let _ = writeln!(
+            out,
+            "{}\t{}\t{}",
+            field_html(&card.front).replace('\t', " "),
+            field_html(&card.back).replace('\t', " "),
+            tags(card).join(" ")
+        );
+    }
+    out
+}
+
+/// Anki's legacy collection schema.
+const ANKI_SCHEMA: &str = "
+CREATE TABLE col (
+    id integer PRIMARY KEY, crt integer NOT NULL, mod integer NOT NULL,
+    scm integer NOT NULL, ver integer NOT NULL, dty integer NOT NULL, usn integer NOT NULL,
+    ls integer NOT NULL, conf text NOT NULL, models text NOT NULL, decks text NOT NULL,
+    dconf text NOT NULL, tags text NOT NULL
+);
+CREATE TABLE notes (
+    id integer PRIMARY KEY, guid text NOT NULL, mid integer NOT NULL, mod integer NOT NULL,
+    usn integer NOT NULL, tags text NOT NULL, flds text NOT NULL, sfld integer NOT NULL,
+    csum integer NOT NULL, flags integer NOT NULL, data text NOT NULL
+);
+CREATE TABLE cards (
+    id integer PRIMARY KEY, nid integer NOT NULL, did integer NOT NULL, ord integer NOT NULL,
+    mod integer NOT NULL, usn integer NOT NULL, type integer NOT NULL, queue integer NOT NULL,
+    due integer NOT NULL, ivl integer NOT NULL, factor integer NOT NULL, reps integer NOT NULL,
+    lapses integer NOT NULL, left integer NOT NULL, odue integer NOT NULL,
+    odid integer NOT NULL, flags integer NOT NULL, data text NOT NULL
+);
+CREATE TABLE revlog (
+    id integer PRIMARY KEY, cid integer NOT NULL, usn integer NOT NULL, ease integer NOT NULL,
+    ivl integer NOT NULL, lastIvl integer NOT NULL, factor integer NOT NULL,
+    time integer NOT NULL, type integer NOT NULL
+);
+CREATE TABLE graves (usn integer NOT NULL, oid integer NOT NULL, type integer NOT NULL);
+CREATE INDEX ix_notes_usn ON notes (usn);
+CREATE INDEX ix_cards_usn ON cards (usn);
+CREATE INDEX ix_revlog_usn ON revlog (usn);
+CREATE INDEX ix_cards_nid ON cards (nid);
+CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+CREATE INDEX ix_revlog_cid ON revlog (cid);
+CREATE INDEX ix_notes_csum ON notes (csum);
+";
+
+fn deck_json(id: i64, name: &str, modified: i64) -> serde_json::Value {
+    serde_json::json!({
+        "id": id, "name": name, "desc": "", "conf": 1, "dyn": 0, "collapsed": false,
+        "extendNew": 10, "extendRev": 50, "newToday": [0, 0], "revToday": [0, 0],
+        "lrnToday": [0, 0], "timeToday": [0, 0], "mod": modified, "usn": -1,
+    })
+}
+
+/// Column values of the `col` row: collection config, note types, decks,
+/// and deck options.
+fn collection_json(deck: &str, did: i64, modified: i64) -> [String; 4] {
+    let field = |name: &str, ord: u32| {
+        serde_json::json!({
+            "name": name, "ord": ord, "sticky": false, "rtl": false,
+            "font": "Arial", "size": 20, "media": [],
+        })
+    };
+    let conf = serde_json::json!({
+        "activeDecks": [1], "curDeck": 1, "curModel": MODEL_ID.to_string(), "nextPos": 1,
+        "newSpread": 0, "collapseTime": 1200, "timeLim": 0, "estTimes": true,
+        "dueCounts": true, "addToCur": true, "sortType": "noteFld", "sortBackwards": false,
+    });
+    let models = serde_json::json!({ MODEL_ID.to_string(): {
+        "id": MODEL_ID, "name": "Gibber AI Basic", "type": 0, "mod": modified, "usn": -1,
+        "sortf": 0, "did": did, "tags": [], "vers": [],
+        "flds": [field("Front", 0), field("Back", 1)],
+        "tmpls": [{
+            "name": "Card 1", "ord": 0, "did": null, "bqfmt": "", "bafmt": "",
+            "qfmt": "{{Front}}", "afmt": "{{FrontSide}}\n\n<hr id=answer>\n\n{{Back}}",
+        }],
+        "css": ".card { font-family: arial; font-size: 20px; text-align: center; color: black; \
+                background-color: white; }",
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\
+                     \\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\
+                     \\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}", "latexsvg": false,
+        "req": [[0, "any", [0]]],
+    }});
+    let decks = serde_json::json!({
+        "1": deck_json(1, "Default", modified),
+        did.to_string(): deck_json(did, deck, modified),
+    });
+    let dconf = serde_json::json!({ "1": {
+        "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "timer": 0,
+        "autoplay": true, "replayq": true,
+        "new": {
+            "bury": true, "delays": [1.0, 10.0], "initialFactor": 2500, "ints": [1, 4, 7],
+            "order": 1, "perDay": 20, "separate": true,
+        },
+        "rev": {
+            "bury": true, "ease4": 1.3, "fuzz": 0.05, "ivlFct": 1.0, "maxIvl": 36_500,
+            "minSpace": 1, "perDay": 200,
+        },
+        "lapse": { "delays": [10.0], "leechAction": 0, "leechFails": 8, "minInt": 1, "mult": 0.0 },
+    }});
+    [conf, models, decks, dconf].map(|value| value.to_string())
+}
+
+fn write_collection(
+    conn: &Connection,
+    deck: &str,
+    cards: &[Flashcard],
+    now_millis: i64,
+) -> rusqlite::Result<()> {
+    let now = now_millis / 1000;
+    let did = deck_id(deck);
+    let [config, models, decks, deck_config] = collection_json(deck, did, now);
+    conn.execute_batch(ANKI_SCHEMA)?;
+    conn.execute(
+        "INSERT INTO col (id, crt, mod, scm, ver, dty, usn, ls, conf, models, decks, dconf, tags)
+         VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![
+            now - now % 86_400,
+            now_millis,
+            config,
+            models,
+            decks,
+            deck_config
+        ],
+    )?;
+    let mut note = conn.prepare(
+        "INSERT INTO notes (id, guid, mid, mod, usn, tags, flds, sfld, csum, flags, data)
+         VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+    )?;
+    let mut card_row = conn.prepare(
+        "INSERT INTO cards (id, nid, did, ord, mod, usn, type, queue, due, ivl, factor, reps,
+                            lapses, left, odue, odid, flags, data)
+         VALUES (?1, ?1, ?2, 0, ?3, -1, 0, 0, ?4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+    )?;
+    for (position, card) in (1_i64..).zip(cards) {
+        let id = now_millis + position;
+        let tags = tags(card);
+        let tags = if tags.is_empty() {
+            String::new()
+        } else {
+            format!(" {} ", tags.join(" "))
+        };
+        let sort = sort_field(&card.front);
+        note.execute(params![
+            id,
+            guid(deck, &card.front),
+            MODEL_ID,
+            now,
+            tags,
+            format!(
+                "{}\u{1f}{}",
+                field_html(&card.front),
+                field_html(&card.back)
+            ),
+            sort,
+            checksum(&sort),
+        ])?;
+        card_row.execute(params![id, did, now, position])?;
+    }
+    Ok(())
+}
+
+fn write_apkg(
+    path: &Path,
+    deck: &str,
+    cards: &[Flashcard],
+    now_millis: i64,
+) -> Result<(), GibberError> {
+    let mut collection_path = path.as_os_str().to_owned();
+    collection_path.push(".anki2.part");
+    let collection_path = std::path::PathBuf::from(collection_path);
+    let _ = std::fs::remove_file(&collection_path);
+    let written = Connection::open(&collection_path)
+        .and_then(|conn| write_collection(&conn, deck, cards, now_millis))
+        .map_err(GibberError::from)
+        .and_then(|()| Ok(std::fs::read(&collection_path)?));
+    let _ = std::fs::remove_file(&collection_path);
+    let collection = written?;
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("collection.anki2", options)?;
+    zip.write_all(&collection)?;
+    zip.start_file("media", options)?;
+    zip.write_all(b"{}")?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Generates flashcards about a conversation or document for review.
+///
+/// Nothing is written; pass the confirmed cards to [`export_flashcards`].
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the source doesn't
+/// exist, `NOT_TEXT` if the attachment isn't a text document, `EMPTY` if
+/// the source has no text, `TOO_LARGE` over 100,000 characters,
+/// `PARSE_ERROR` if the reply holds no cards, or the `GibberError` code if
+/// the request fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const cards = await invoke("generate_flashcards", {
+///   source: { kind: "document", attachmentId },
+///   count: 30,
+///   model: null,
+/// });
+/// // show cards for editing, then:
+/// await invoke("export_flashcards", {
+///   request: { cards: confirmed, deck: "Synthesis::Filters", path: "/Users/me/filters.apkg" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn generate_flashcards(
+    app: AppHandle,
+    source: FlashcardSource,
+    count: Option<u32>,
+    model: Option<String>,
+) -> Result<Vec<Flashcard>, GibberError> {
+    let count = count.unwrap_or(DEFAULT_COUNT).clamp(1, MAX_COUNT);
+    let material = source_text(&app, &source)?;
+    if material.trim().is_empty() {
+        return Err(GibberError::new("EMPTY", "There is nothing to learn from"));
+    }
+    if material.chars().count() > MAX_SOURCE_CHARS {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!("Sources over {MAX_SOURCE_CHARS} characters are too long for flashcards"),
+        ));
+    }
+    let mut request = ChatRequest::new(
+        model.as_deref(),
+        vec![
+            ChatMessage::new(
+                MessageRole::System,
+                GENERATE_PROMPT.replacen("{count}", &count.to_string(), 1),
+            ),
+            ChatMessage::new(MessageRole::User, material),
+        ],
+    )
+    .with_json_schema("flashcards", generation_schema());
+    if let FlashcardSource::Conversation { conversation_id } = &source {
+        request = request.for_conversation(conversation_id);
+    }
+    telemetry::record_feature(&app, "flashcards.generate");
+    let completion = chat::complete(&app, &request).await?;
+    parse_generation(&completion.content, count)
+}
+
+/// Writes flashcards as an Anki deck.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` for a blank or overly
+/// long deck name, no cards or more than 5,000, or a card without a
+/// question or answer; `IO` if the file cannot be written; or `DATABASE`
+/// if the package's collection cannot be built.
+///
+/// # Example
+///
+/// ```typescript
+/// const { count } = await invoke("export_flashcards", {
+///   request: { cards, deck: "Mixing", path: "/Users/me/mixing.txt", format: "tsv" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn export_flashcards(
+    app: AppHandle,
+    request: FlashcardExportRequest,
+) -> Result<FlashcardExport, GibberError> {
+    validate(&request)?;
+    let request = tauri::async_runtime::spawn_blocking(move || {
+        let deck = request.deck.trim();
+        let path = Path::new(&request.path);
+        match request.format {
+            FlashcardFormat::Apkg => write_apkg(path, deck, &request.cards, db::now_millis())?,
+            FlashcardFormat::Tsv => std::fs::write(path, tsv(deck, &request.cards))?,
+        }
+        Ok::<_, GibberError>(request)
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))??;
+    telemetry::record_feature(&app, "flashcards.export");
+    Ok(FlashcardExport {
+        path: request.path,
+        format: request.format,
+        count: request.cards.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn card(front: &str, back: &str) -> Flashcard {
+        Flashcard {
+            front: front.to_string(),
+            back: back.to_string(),
+            tags: vec!["Signal Flow".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_parse_generation() {
+        let reply = r#"{"cards": [
+            {"front": " What does a low-pass filter do? ", "back": "Cuts highs.",
+             "tags": ["Filters", "filters", ""]},
+            {"front": "What does a low-pass filter do?", "back": "Duplicate", "tags": []},
+            {"front": "", "back": "No question", "tags": []},
+            {"front": "What is resonance?", "back": "A boost at the cutoff.",
+             "tags": ["filter basics"]}
+        ]}"#;
+        let cards = parse_generation(reply, 10).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].front, "What does a low-pass filter do?");
+        assert_eq!(cards[0].tags, vec!["filters"]);
+        assert_eq!(cards[1].tags, vec!["filter-basics"]);
+        assert_eq!(parse_generation(reply, 1).unwrap().len(), 1);
+        assert_eq!(
+            parse_generation("no cards", 5).unwrap_err().code(),
+            "PARSE_ERROR"
+        );
+
+        let text = tsv(
+            "Mixing",
+            &[card("Why <b>gain</b>\tstage?", "Headroom.\nAnd noise.")],
+        );
+        assert!(text.starts_with("#separator:tab\n#html:true\n"));
+        assert!(text.ends_with(
+            "Why &lt;b&gt;gain&lt;/b&gt; stage?\tHeadroom.<br>And noise.\tsignal-flow\n"
+        ));
+    }
+
+    #[test]
+    fn test_apkg_holds_an_anki_collection() {
+        let dir = std::env::temp_dir().join(format!("gibber-flashcards-{}", db::new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("deck.apkg");
+        let cards = [
+            card("What is a VCA?", "An amplifier"),
+            card("What is an LFO?", "A slow oscillator"),
+        ];
+        write_apkg(&path, "Synthesis::Basics", &cards, 1_700_000_000_000).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut collection = Vec::new();
+        archive
+            .by_name("collection.anki2")
+            .unwrap()
+            .read_to_end(&mut collection)
+            .unwrap();
+        let extracted = dir.join("collection.anki2");
+        std::fs::write(&extracted, collection).unwrap();
+        let conn = Connection::open(&extracted).unwrap();
+        let (flds, tags, csum): (String, String, i64) = conn
+            .query_row(
+                "SELECT flds, tags, csum FROM notes ORDER BY id LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(flds, "What is a VCA?\u{1f}An amplifier");
+        assert_eq!(tags, " signal-flow ");
+        assert_eq!(csum, checksum("What is a VCA?"));
+        let did: i64 = conn
+            .query_row("SELECT DISTINCT did FROM cards", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(did, deck_id("Synthesis::Basics"));
+        let decks: String = conn
+            .query_row("SELECT decks FROM col", [], |row| row.get(0))
+            .unwrap();
+        assert!(decks.contains("Synthesis::Basics"));
+        assert_eq!(guid("Deck", "Q"), guid("Deck", " Q "));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
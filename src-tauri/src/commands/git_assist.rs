@@ -0,0 +1,489 @@
+//! Commit messages and diff reviews for local git repositories.
+//!
+//! The user grants access to a repository first (usually picked with a
+//! folder dialog); only granted work trees are ever read. The diff comes
+//! from the `git` executable on the `PATH`, run with optional locks
+//! disabled so it never touches the index of a repository in use, and with
+//! fsmonitor, hooks, and textconv filters off so the repository's own
+//! config can't make it run other programs. Binary
+//! files are dropped from the diff, and files are cut once the diff
+//! reaches [`MAX_DIFF_BYTES`]; both are reported back with the model's
+//! answer so the UI can say what the model didn't see.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::audit::{self, AuditKind, AuditRecord};
+use crate::commands::windows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the granted repositories.
+pub const SETTINGS_KEY: &str = "gitRepositories";
+
+/// Largest diff sent to the model.
+const MAX_DIFF_BYTES: usize = 60_000;
+
+/// Recent commits shown to the model as a style reference.
+const LOG_COMMITS: u32 = 10;
+
+const COMMIT_PROMPT: &str = "Write a git commit message for the diff below. Start with an \
+     imperative subject line of at most 72 characters, then a blank line and a short body \
+     explaining what changed and why, wrapped at 72 columns. Follow the style of the recent \
+     commits if they share one. Reply with the message only.";
+
+const REVIEW_PROMPT: &str = "Review the diff below like an experienced maintainer. Point out \
+     bugs, risky or breaking changes, missing tests and unclear code, citing the file and \
+     hunk. Be brief and skip praise; say so if nothing needs changing.";
+
+/// Granted repositories.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GitSettings {
+    /// Work tree roots the app may read
+    pub repositories: Vec<String>,
+}
+
+/// What to ask the model for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum GitTask {
+    /// A commit message for the changes
+    CommitMessage,
+    /// A review of the changes
+    Review,
+}
+
+/// Which changes to read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffSelection {
+    /// Only staged changes (`git diff --staged`)
+    pub staged: bool,
+    /// A revision or range such as `main..HEAD`, instead of the work tree
+    pub range: Option<String>,
+}
+
+/// The model's answer and what it was based on.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GitAssistResult {
+    /// Commit message or review
+    pub text: String,
+    /// Model that answered
+    pub model: String,
+    /// Files included in the diff
+    pub files: Vec<String>,
+    /// Binary files left out
+    pub binary_files: Vec<String>,
+    /// Files left out because of the size cap
+    pub omitted_files: Vec<String>,
+}
+
+fn load_settings(conn: &rusqlite::Connection) -> GitSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(conn: &rusqlite::Connection, settings: &GitSettings) -> rusqlite::Result<()> {
+    let value = serde_json::to_string(settings).unwrap_or_default();
+    db::write_setting(conn, SETTINGS_KEY, &value)
+}
+
+/// Runs `git` in `repository` and returns its stdout.
+fn git(repository: &Path, args: &[&str]) -> Result<String, GibberError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args([
+            "-c",
+            "core.quotepath=off",
+            "-c",
+            "core.fsmonitor=false",
+            "-c",
+            "core.hooksPath=/dev/null",
+            "--no-pager",
+        ])
+        .args(args)
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .output()
+        .map_err(|e| GibberError::new("GIT_UNAVAILABLE", format!("Could not run git: {e}")))?;
+    if !output.status.success() {
+        return Err(GibberError::new(
+            "GIT_FAILED",
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Rejects ranges that git could read as options or that hold odd characters.
+fn validate_range(range: &str) -> Result<(), GibberError> {
+    let valid = !range.is_empty()
+        && !range.starts_with('-')
+        && range
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._/~^@{}-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(GibberError::new(
+            "INVALID_RANGE",
+            format!("Not a revision range: {range}"),
+        ))
+    }
+}
+
+/// A diff split into files, with binary files and the overflow removed.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct FilteredDiff {
+    diff: String,
+    files: Vec<String>,
+    binary_files: Vec<String>,
+    omitted_files: Vec<String>,
+}
+
+/// Returns the `b/` path of a `diff --git a/x b/x` section.
+fn section_path(section: &str) -> String {
+    let header = section.lines().next().unwrap_or_default();
+    header
+        .rsplit_once(" b/")
+        .map_or(header, |(_, path)| path)
+        .to_string()
+}
+
+fn filter_diff(diff: &str, max_bytes: usize) -> FilteredDiff {
+    let mut filtered = FilteredDiff::default();
+    let sections = diff
+        .split("\ndiff --git ")
+        .enumerate()
+        .map(|(index, section)| {
+            if index == 0 {
+                section.strip_prefix("diff --git ").unwrap_or(section)
+            } else {
+                section
+            }
+        })
+        .filter(|section| !section.trim().is_empty());
+    for section in sections {
+        let path = section_path(section);
+        let binary = section
+            .lines()
+            .any(|line| line.starts_with("Binary files ") || line == "GIT binary patch");
+        if binary {
+            filtered.binary_files.push(path);
+            continue;
+        }
+        let section = format!("diff --git {}\n", section.trim_end_matches('\n'));
+        if filtered.diff.len() + section.len() > max_bytes {
+            filtered.omitted_files.push(path);
+            continue;
+        }
+        filtered.diff.push_str(&section);
+        filtered.files.push(path);
+    }
+    filtered
+}
+
+/// Checks that `path` is a granted repository and returns it.
+fn granted(db: &Database, path: &str) -> Result<PathBuf, GibberError> {
+    if load_settings(&db.conn())
+        .repositories
+        .iter()
+        .any(|r| r == path)
+    {
+        Ok(PathBuf::from(path))
+    } else {
+        Err(GibberError::new(
+            "NOT_GRANTED",
+            format!("Grant access to {path} first"),
+        ))
+    }
+}
+
+/// Runs `git` like [`git`] and records the command in the audit log.
+fn audited_git(app: &AppHandle, repository: &Path, args: &[&str]) -> Result<String, GibberError> {
+    let result = git(repository, args);
+    let error = result.as_ref().err().map(ToString::to_string);
+    audit::record(
+        app,
+        &AuditRecord {
+            actor: "git-assist",
+            kind: AuditKind::Shell,
+            target: &format!("git {}", args.join(" ")),
+            detail: Some(serde_json::json!({ "repository": repository })),
+            error: error.as_deref(),
+        },
+    );
+    result
+}
+
+/// Reads the selected diff and recent history.
+fn read_changes(
+    app: &AppHandle,
+    repository: &Path,
+    selection: &DiffSelection,
+) -> Result<(FilteredDiff, String), GibberError> {
+    let mut args = vec![
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-textconv",
+        "--find-renames",
+    ];
+    if selection.staged {
+        args.push("--staged");
+    }
+    if let Some(range) = selection.range.as_deref() {
+        validate_range(range)?;
+        args.push(range);
+    }
+    args.push("--");
+    let diff = filter_diff(&audited_git(app, repository, &args)?, MAX_DIFF_BYTES);
+    let log = audited_git(
+        app,
+        repository,
+        &[
+            "log",
+            "--no-color",
+            &format!("-{LOG_COMMITS}"),
+            "--format=%s",
+        ],
+    )
+    // A repository without commits has no log.
+    .unwrap_or_default();
+    Ok((diff, log))
+}
+
+/// Lists the repositories the app may read.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_git_repositories(db: State<'_, Database>) -> GitSettings {
+    load_settings(&db.conn())
+}
+
+/// Grants access to the git work tree containing `path`.
+///
+/// The work tree root is stored, so any folder inside the repository works.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, `NOT_A_REPOSITORY` if `path` isn't inside a git
+/// work tree, `GIT_UNAVAILABLE` if git isn't installed, or `DATABASE` if
+/// the grant cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const folder = await open({ directory: true });
+/// await invoke("grant_git_repository", { path: folder });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn grant_git_repository(
+    window: WebviewWindow,
+    app: AppHandle,
+    path: String,
+) -> Result<GitSettings, GibberError> {
+    windows::require_trusted(&window)?;
+    let root = tauri::async_runtime::spawn_blocking(move || {
+        git(Path::new(&path), &["rev-parse", "--show-toplevel"]).map_err(|e| {
+            if e.code() == "GIT_FAILED" {
+                GibberError::new(
+                    "NOT_A_REPOSITORY",
+                    format!("{path} is not in a git repository"),
+                )
+            } else {
+                e
+            }
+        })
+    })
+    .await
+    .map_err(|e| GibberError::new("GIT_FAILED", e.to_string()))??;
+    let root = root.trim().to_string();
+
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    let mut settings = load_settings(&conn);
+    if !settings.repositories.contains(&root) {
+        settings.repositories.push(root);
+        save_settings(&conn, &settings)?;
+    }
+    Ok(settings)
+}
+
+/// Revokes access to a repository.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn revoke_git_repository(
+    db: State<'_, Database>,
+    path: &str,
+) -> Result<GitSettings, GibberError> {
+    let conn = db.conn();
+    let mut settings = load_settings(&conn);
+    settings.repositories.retain(|r| r != path);
+    save_settings(&conn, &settings)?;
+    Ok(settings)
+}
+
+/// Asks the model for a commit message or a review of a repository's changes.
+///
+/// Without a selection the unstaged work tree changes are used.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_GRANTED` if the repository hasn't
+/// been granted, `INVALID_RANGE` for a malformed range, `GIT_FAILED` if git
+/// reports an error, `NO_CHANGES` if the diff is empty after filtering, or
+/// the `GibberError` code if the request fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const result = await invoke("git_assist", {
+///   repository: "/Users/ana/code/gibber-ai",
+///   task: "commitMessage",
+///   selection: { staged: true },
+///   model: null,
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn git_assist(
+    app: AppHandle,
+    repository: String,
+    task: GitTask,
+    selection: Option<DiffSelection>,
+    model: Option<String>,
+) -> Result<GitAssistResult, GibberError> {
+    let repository = granted(&app.state::<Database>(), &repository)?;
+    let selection = selection.unwrap_or_default();
+    let handle = app.clone();
+    let (diff, log) = tauri::async_runtime::spawn_blocking(move || {
+        read_changes(&handle, &repository, &selection)
+    })
+    .await
+    .map_err(|e| GibberError::new("GIT_FAILED", e.to_string()))??;
+    if diff.files.is_empty() {
+        return Err(GibberError::new(
+            "NO_CHANGES",
+            "There are no text changes to send",
+        ));
+    }
+
+    let (system, mut content) = match task {
+        GitTask::CommitMessage => (COMMIT_PROMPT, format!("Recent commits:\n{log}\n")),
+        GitTask::Review => (REVIEW_PROMPT, String::new()),
+    };
+    if !diff.omitted_files.is_empty() {
+        let _ = writeln!(
+            content,
+            "Left out for size: {}",
+            diff.omitted_files.join(", ")
+        );
+    }
+    content.push_str("\n```diff\n");
+    content.push_str(&diff.diff);
+    content.push_str("```\n");
+
+    let request = ChatRequest::new(
+        model.as_deref(),
+        vec![
+            ChatMessage::new(MessageRole::System, system),
+            ChatMessage::new(MessageRole::User, content),
+        ],
+    );
+    let completion = chat::complete(&app, &request).await?;
+    Ok(GitAssistResult {
+        text: completion.content.trim().to_string(),
+        model: completion.model,
+        files: diff.files,
+        binary_files: diff.binary_files,
+        omitted_files: diff.omitted_files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-fn main() {}
++fn main() { println!(\"hi\"); }
+diff --git a/assets/logo.png b/assets/logo.png
+index 3333333..4444444 100644
+Binary files a/assets/logo.png and b/assets/logo.png differ
+diff --git a/README.md b/README.md
+index 5555555..6666666 100644
+--- a/README.md
++++ b/README.md
+@@ -1 +1 @@
+-# Old
++# New
+";
+
+    #[test]
+    fn test_filter_diff_drops_binary_files() {
+        let filtered = filter_diff(DIFF, MAX_DIFF_BYTES);
+        assert_eq!(filtered.files, ["src/main.rs", "README.md"]);
+        assert_eq!(filtered.binary_files, ["assets/logo.png"]);
+        assert!(filtered.omitted_files.is_empty());
+        assert!(filtered.diff.starts_with("diff --git a/src/main.rs"));
+        assert!(filtered
+            .diff
+            .contains("\ndiff --git a/README.md b/README.md\n"));
+        assert!(!filtered.diff.contains("logo.png"));
+    }
+
+    #[test]
+    fn test_filter_diff_caps_size() {
+        let filtered = filter_diff(DIFF, 250);
+        assert_eq!(filtered.files, ["src/main.rs"]);
+        assert_eq!(filtered.omitted_files, ["README.md"]);
+        assert!(filtered.diff.len() <= 250);
+        assert_eq!(filter_diff("", MAX_DIFF_BYTES), FilteredDiff::default());
+    }
+
+    #[test]
+    fn test_validate_range() {
+        for range in ["HEAD~3", "main..feature/x", "v1.2.0...HEAD", "HEAD@{1}"] {
+            assert!(validate_range(range).is_ok(), "{range}");
+        }
+        for range in ["", "--output=/tmp/x", "HEAD; rm -rf /", "a b"] {
+            assert_eq!(validate_range(range).unwrap_err().code(), "INVALID_RANGE");
+        }
+    }
+
+    #[test]
+    fn test_only_granted_repositories_are_read() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(granted(&db, "/code/app").unwrap_err().code(), "NOT_GRANTED");
+        let settings = GitSettings {
+            repositories: vec!["/code/app".to_string()],
+        };
+        save_settings(&db.conn(), &settings).unwrap();
+        assert_eq!(
+            granted(&db, "/code/app").unwrap(),
+            PathBuf::from("/code/app")
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Localized strings for the UI and the backend.
+//!
+//! Catalogs are [Fluent](https://projectfluent.org) files in `locales/`,
+//! compiled into the binary; English is complete and fills in whatever
+//! another catalog lacks. The `language` setting picks the catalog: a
+//! BCP 47 tag, or `system` to follow the OS locale detected at startup. A
+//! tag without its own catalog falls back to its language (`de-AT` uses
+//! `de`) and then to English.
+//!
+//! The backend formats its own user-facing text (tray menu, notifications)
+//! with [`text`] and [`text_with`]. Errors keep their English `message`;
+//! the UI translates them by `code` with the `error-<code>` entries, code
+//! lowercased with dashes (`NO_API_KEY` is `error-no-api-key`).
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use fluent_syntax::ast::Entry;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use unic_langid::LanguageIdentifier;
+
+use crate::commands::events;
+use crate::commands::settings;
+use crate::commands::tray;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// `language` setting value that follows the OS locale.
+pub const SYSTEM_LANGUAGE: &str = "system";
+
+/// Catalog used when nothing else matches.
+const DEFAULT_LOCALE: &str = "en";
+
+/// A bundled catalog.
+struct CatalogSource {
+    tag: &'static str,
+    name: &'static str,
+    source: &'static str,
+}
+
+/// Bundled catalogs, English first.
+const CATALOGS: [CatalogSource; 4] = [
+    CatalogSource {
+        tag: "en",
+        name: "English",
+        source: include_str!("../../locales/en.ftl"),
+    },
+    CatalogSource {
+        tag: "de",
+        name: "Deutsch",
+        source: include_str!("../../locales/de.ftl"),
+    },
+    CatalogSource {
+        tag: "es",
+        name: "Español",
+        source: include_str!("../../locales/es.ftl"),
+    },
+    CatalogSource {
+        tag: "fr",
+        name: "Français",
+        source: include_str!("../../locales/fr.ftl"),
+    },
+];
+
+/// A catalog the user can choose.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    /// BCP 47 tag
+    pub tag: &'static str,
+    /// Name in its own language
+    pub name: &'static str,
+}
+
+/// Every string of a locale, for the UI.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleStrings {
+    /// Catalog in use
+    pub locale: &'static str,
+    /// The `language` setting: a tag or `system`
+    pub language: String,
+    /// Locale reported by the OS, if any
+    pub system_locale: Option<String>,
+    /// Catalogs that can be chosen
+    pub available: Vec<LocaleInfo>,
+    /// Messages by ID; variables are left as `{$name}` for the UI to fill
+    pub strings: BTreeMap<String, String>,
+}
+
+/// One locale's bundle followed by the English fallback.
+struct Catalog {
+    locale: &'static str,
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+fn parse_source(source: &CatalogSource) -> FluentResource {
+    FluentResource::try_new(source.source.to_string()).unwrap_or_else(|(resource, errors)| {
+        tracing::error!(locale = source.tag, ?errors, "catalog has syntax errors");
+        resource
+    })
+}
+
+fn bundle(source: &CatalogSource) -> FluentBundle<FluentResource> {
+    let langid = source.tag.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Bidi isolation marks show up as stray boxes in notifications.
+    bundle.set_use_isolating(false);
+    if let Err(errors) = bundle.add_resource(parse_source(source)) {
+        tracing::error!(
+            locale = source.tag,
+            ?errors,
+            "catalog has duplicate entries"
+        );
+    }
+    bundle
+}
+
+impl Catalog {
+    fn new(locale: &'static str) -> Self {
+        let bundles = CATALOGS
+            .iter()
+            .filter(|source| source.tag == locale || source.tag == DEFAULT_LOCALE)
+            // The requested locale first, English after it.
+            .rev()
+            .map(bundle)
+            .collect();
+        Self { locale, bundles }
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs<'_>>) -> Option<String> {
+        self.bundles.iter().find_map(|bundle| {
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = Vec::new();
+            Some(
+                bundle
+                    .format_pattern(pattern, args, &mut errors)
+                    .into_owned(),
+            )
+        })
+    }
+
+    fn strings(&self) -> BTreeMap<String, String> {
+        parse_source(&CATALOGS[0])
+            .entries()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name.to_string()),
+                _ => None,
+            })
+            .filter_map(|id| {
+                let text = self.format(&id, None)?;
+                Some((id, text))
+            })
+            .collect()
+    }
+}
+
+/// Picks the catalog for a BCP 47 tag, by exact tag, then by language.
+fn negotiate(tag: &str) -> &'static str {
+    let Ok(requested) = tag.replace('_', "-").parse::<LanguageIdentifier>() else {
+        return DEFAULT_LOCALE;
+    };
+    CATALOGS
+        .iter()
+        .find(|source| {
+            source
+                .tag
+                .parse::<LanguageIdentifier>()
+                .is_ok_and(|id| id == requested)
+        })
+        .or_else(|| {
+            CATALOGS
+                .iter()
+                .find(|source| source.tag == requested.language.as_str())
+        })
+        .map_or(DEFAULT_LOCALE, |source| source.tag)
+}
+
+/// Returns whether `tag` matches a bundled catalog, at least by language.
+fn is_supported(tag: &str) -> bool {
+    tag == SYSTEM_LANGUAGE
+        || tag
+            .parse::<LanguageIdentifier>()
+            .is_ok_and(|id| CATALOGS.iter().any(|s| s.tag == id.language.as_str()))
+}
+
+/// Returns the OS locale as a BCP 47 tag, without encoding suffixes such
+/// as `.UTF-8`.
+pub(crate) fn system_locale() -> Option<String> {
+    let locale = sys_locale::get_locale()?;
+    let tag = locale.split(['.', '@']).next().unwrap_or_default();
+    Some(tag.replace('_', "-")).filter(|tag| !tag.is_empty())
+}
+
+/// The catalog in use and the OS locale it was resolved with.
+pub struct Localizer {
+    catalog: RwLock<Arc<Catalog>>,
+    system_locale: Option<String>,
+}
+
+impl Default for Localizer {
+    /// Detects the OS locale and starts in that catalog.
+    fn default() -> Self {
+        let system_locale = system_locale();
+        let locale = system_locale.as_deref().map_or(DEFAULT_LOCALE, negotiate);
+        Self {
+            catalog: RwLock::new(Arc::new(Catalog::new(locale))),
+            system_locale,
+        }
+    }
+}
+
+impl Localizer {
+    fn catalog(&self) -> Arc<Catalog> {
+        Arc::clone(&self.catalog.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn resolve(&self, language: &str) -> &'static str {
+        if language == SYSTEM_LANGUAGE {
+            self.system_locale
+                .as_deref()
+                .map_or(DEFAULT_LOCALE, negotiate)
+        } else {
+            negotiate(language)
+        }
+    }
+
+    fn strings(&self, language: &str) -> LocaleStrings {
+        let locale = self.resolve(language);
+        let current = self.catalog();
+        let strings = if current.locale == locale {
+            current.strings()
+        } else {
+            Catalog::new(locale).strings()
+        };
+        LocaleStrings {
+            locale,
+            language: language.to_string(),
+            system_locale: self.system_locale.clone(),
+            available: CATALOGS
+                .iter()
+                .map(|source| LocaleInfo {
+                    tag: source.tag,
+                    name: source.name,
+                })
+                .collect(),
+            strings,
+        }
+    }
+}
+
+/// Returns a message in the current locale, or its ID if no catalog has it.
+pub fn text(app: &AppHandle, id: &str) -> String {
+    text_with(app, id, &[])
+}
+
+/// Returns a message in the current locale with its variables filled in.
+pub fn text_with(app: &AppHandle, id: &str, args: &[(&str, &str)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, *value);
+    }
+    let catalog = app.state::<Localizer>().catalog();
+    catalog.format(id, Some(&fluent_args)).unwrap_or_else(|| {
+        tracing::warn!(id, "missing localized string");
+        id.to_string()
+    })
+}
+
+/// Switches to the catalog for a `language` setting; called when the
+/// setting changes.
+///
+/// The tray menu is relabeled and [`events::I18N_LOCALE_CHANGED`] emitted when
+/// the catalog actually changes.
+pub fn set_language(app: &AppHandle, language: &str) {
+    let localizer = app.state::<Localizer>();
+    let locale = localizer.resolve(language);
+    {
+        let mut catalog = localizer
+            .catalog
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if catalog.locale == locale {
+            return;
+        }
+        *catalog = Arc::new(Catalog::new(locale));
+    }
+    tray::refresh_menu(app);
+    events::emit_typed(
+        app,
+        events::I18N_LOCALE_CHANGED,
+        &localizer.strings(language),
+    );
+}
+
+/// Returns every string of a locale.
+///
+/// Without `language`, returns the locale in use.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const { locale, strings } = await invoke("get_locale_strings");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn get_locale_strings(
+    db: State<'_, Database>,
+    localizer: State<'_, Localizer>,
+    language: Option<String>,
+) -> Result<LocaleStrings, GibberError> {
+    let language = match language {
+        Some(language) => language,
+        None => settings::load(&db.conn())?.language,
+    };
+    Ok(localizer.strings(&language))
+}
+
+/// Sets the UI language and returns its strings.
+///
+/// Shorthand for updating the `language` setting.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNSUPPORTED_LOCALE` if no catalog
+/// matches the tag, or a settings error code if it cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_locale", { language: "system" });
+/// await listen("i18n://locale-changed", (e) => applyStrings(e.payload.strings));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle, State and args by value
+pub fn set_locale(
+    app: AppHandle,
+    localizer: State<'_, Localizer>,
+    language: String,
+) -> Result<LocaleStrings, GibberError> {
+    if !is_supported(&language) {
+        return Err(GibberError::new(
+            "UNSUPPORTED_LOCALE",
+            format!("No translation for {language}"),
+        ));
+    }
+    settings::update(&app, &serde_json::json!({ "language": language }))?;
+    Ok(localizer.strings(&language))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_parse_and_cover_english() {
+        let english = Catalog::new(DEFAULT_LOCALE).strings();
+        assert!(english.contains_key("tray-quit"));
+        for source in &CATALOGS {
+            assert!(
+                FluentResource::try_new(source.source.to_string()).is_ok(),
+                "{} should parse",
+                source.tag
+            );
+            let strings = Catalog {
+                locale: source.tag,
+                bundles: vec![bundle(source)],
+            }
+            .strings();
+            let missing: Vec<_> = english
+                .keys()
+                .filter(|id| !strings.contains_key(*id))
+                .collect();
+            assert!(missing.is_empty(), "{} lacks {missing:?}", source.tag);
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("de"), "de");
+        assert_eq!(negotiate("de-AT"), "de");
+        assert_eq!(negotiate("fr_CA"), "fr");
+        assert_eq!(negotiate("ja-JP"), "en");
+        assert_eq!(negotiate("not a tag"), "en");
+        assert!(is_supported("es-MX"));
+        assert!(is_supported(SYSTEM_LANGUAGE));
+        assert!(!is_supported("ja"));
+    }
+
+    #[test]
+    fn test_format_with_args() {
+        let catalog = Catalog::new("de");
+        let mut args = FluentArgs::new();
+        args.set("error", "Zeitüberschreitung");
+        assert_eq!(
+            catalog.format("notification-failed", Some(&args)).unwrap(),
+            "Fehlgeschlagen: Zeitüberschreitung"
+        );
+        assert_eq!(catalog.format("tray-quit", None).unwrap(), "Beenden");
+        assert!(catalog.format("no-such-message", None).is_none());
+    }
+
+    #[test]
+    fn test_strings_leave_variables_for_the_ui() {
+        let strings = Catalog::new("en").strings();
+        assert_eq!(strings["notification-failed"], "Failed: {$error}");
+    }
+}
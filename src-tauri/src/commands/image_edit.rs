@@ -0,0 +1,352 @@
+//! Image edits before an attachment is sent.
+//!
+//! `edit_image_attachment` applies a list of operations to an image
+//! attachment in place: crop, resize, rotate, and redact. Redaction paints
+//! solid boxes rather than blurring, since blurred text can often be
+//! recovered. The result is stored as a PNG so no compression artifacts
+//! reveal what was covered.
+
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageFormat, Rgba};
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::attachments::{self, Attachment, AttachmentStore};
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Largest width or height an image can be resized to.
+const MAX_DIMENSION: u32 = 8192;
+
+/// Redaction color when none is given.
+const DEFAULT_REDACT_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// A rectangle in image pixels, relative to the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, specta::Type)]
+pub struct Rect {
+    /// Left edge
+    pub x: u32,
+    /// Top edge
+    pub y: u32,
+    /// Width
+    pub width: u32,
+    /// Height
+    pub height: u32,
+}
+
+/// One edit, applied in list order.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum ImageOperation {
+    /// Keep only a rectangle
+    Crop {
+        /// Area to keep
+        #[serde(flatten)]
+        rect: Rect,
+    },
+    /// Scale; with one dimension the other keeps the aspect ratio
+    Resize {
+        /// New width
+        width: Option<u32>,
+        /// New height
+        height: Option<u32>,
+    },
+    /// Rotate clockwise by a multiple of 90 degrees
+    Rotate {
+        /// Angle; negative values rotate counterclockwise
+        degrees: i32,
+    },
+    /// Paint solid boxes over regions
+    Redact {
+        /// Areas to cover; parts outside the image are ignored
+        regions: Vec<Rect>,
+        /// `#RRGGBB` color (default: black)
+        #[serde(default)]
+        color: Option<String>,
+    },
+}
+
+fn parse_color(color: &str) -> Result<Rgba<u8>, GibberError> {
+    let invalid = || GibberError::new("INVALID_OPERATION", format!("Invalid color \"{color}\""));
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 {
+        return Err(invalid());
+    }
+    let channel = |index: usize| {
+        hex.get(index..index + 2)
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            .ok_or_else(invalid)
+    };
+    Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, 255]))
+}
+
+fn crop(image: &DynamicImage, rect: Rect) -> Result<DynamicImage, GibberError> {
+    let fits = rect.width > 0
+        && rect.height > 0
+        && rect
+            .x
+            .checked_add(rect.width)
+            .is_some_and(|right| right <= image.width())
+        && rect
+            .y
+            .checked_add(rect.height)
+            .is_some_and(|bottom| bottom <= image.height());
+    if !fits {
+        return Err(GibberError::new(
+            "INVALID_OPERATION",
+            format!(
+                "Crop {}×{} at ({}, {}) is outside the {}×{} image",
+                rect.width,
+                rect.height,
+                rect.x,
+                rect.y,
+                image.width(),
+                image.height()
+            ),
+        ));
+    }
+    Ok(image.crop_imm(rect.x, rect.y, rect.width, rect.height))
+}
+
+fn resize(
+    image: &DynamicImage,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<DynamicImage, GibberError> {
+    let scaled = |target: u32, from: u32, to: u32| {
+        let value = u64::from(target) * u64::from(to) / u64::from(from.max(1));
+        u32::try_from(value).unwrap_or(u32::MAX).max(1)
+    };
+    let (width, height) = match (width, height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, scaled(width, image.width(), image.height())),
+        (None, Some(height)) => (scaled(height, image.height(), image.width()), height),
+        (None, None) => {
+            return Err(GibberError::new(
+                "INVALID_OPERATION",
+                "Resize needs a width or a height",
+            ))
+        }
+    };
+    let valid = |dimension: u32| (1..=MAX_DIMENSION).contains(&dimension);
+    if !valid(width) || !valid(height) {
+        return Err(GibberError::new(
+            "INVALID_OPERATION",
+            format!("Images can be resized to between 1 and {MAX_DIMENSION} pixels per side"),
+        ));
+    }
+    Ok(image.resize_exact(width, height, image::imageops::FilterType::Lanczos3))
+}
+
+fn rotate(image: &DynamicImage, degrees: i32) -> Result<DynamicImage, GibberError> {
+    match degrees.rem_euclid(360) {
+        0 => Ok(image.clone()),
+        90 => Ok(image.rotate90()),
+        180 => Ok(image.rotate180()),
+        270 => Ok(image.rotate270()),
+        _ => Err(GibberError::new(
+            "INVALID_OPERATION",
+            format!("Rotation must be a multiple of 90 degrees, not {degrees}"),
+        )),
+    }
+}
+
+fn redact(image: DynamicImage, regions: &[Rect], color: Rgba<u8>) -> DynamicImage {
+    let mut pixels = image.into_rgba8();
+    let (width, height) = pixels.dimensions();
+    for region in regions {
+        let right = region.x.saturating_add(region.width).min(width);
+        let bottom = region.y.saturating_add(region.height).min(height);
+        for y in region.y..bottom {
+            for x in region.x..right {
+                pixels.put_pixel(x, y, color);
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(pixels)
+}
+
+/// Applies `operations` in order.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_OPERATION` for an
+/// operation that doesn't fit the image.
+pub fn apply(
+    mut image: DynamicImage,
+    operations: &[ImageOperation],
+) -> Result<DynamicImage, GibberError> {
+    for operation in operations {
+        image = match operation {
+            ImageOperation::Crop { rect } => crop(&image, *rect)?,
+            ImageOperation::Resize { width, height } => resize(&image, *width, *height)?,
+            ImageOperation::Rotate { degrees } => rotate(&image, *degrees)?,
+            ImageOperation::Redact { regions, color } => {
+                let color = color
+                    .as_deref()
+                    .map(parse_color)
+                    .transpose()?
+                    .unwrap_or(DEFAULT_REDACT_COLOR);
+                redact(image, regions, color)
+            }
+        };
+    }
+    Ok(image)
+}
+
+fn edit(
+    app: &AppHandle,
+    id: &str,
+    operations: &[ImageOperation],
+) -> Result<Attachment, GibberError> {
+    let store = app.state::<AttachmentStore>();
+    let db = app.state::<Database>();
+    let attachment = attachments::load_attachment(&db.conn(), id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Attachment {id} not found")))?;
+    let bytes = std::fs::read(store.file_path(&attachment))?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| GibberError::new("DECODE_FAILED", e.to_string()))?;
+    let edited = apply(image, operations)?;
+    let mut png = Cursor::new(Vec::new());
+    edited
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| GibberError::new("ENCODE_FAILED", e.to_string()))?;
+    let attachment = attachments::replace_image(&db.conn(), &store, id, &png.into_inner())?;
+    Ok(attachment)
+}
+
+/// Edits an image attachment in place and returns it updated.
+///
+/// The edited image replaces the original, which isn't kept.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment
+/// doesn't exist, `NOT_IMAGE` if it isn't an image, `INVALID_OPERATION` for
+/// an operation that doesn't fit the image, or `DECODE_FAILED` if the image
+/// can't be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const attachment = await invoke("edit_image_attachment", {
+///   id,
+///   operations: [
+///     { op: "crop", x: 0, y: 80, width: 1280, height: 640 },
+///     { op: "redact", regions: [{ x: 40, y: 12, width: 300, height: 28 }] },
+///     { op: "resize", width: 1024 },
+///   ],
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub async fn edit_image_attachment(
+    app: AppHandle,
+    id: String,
+    operations: Vec<ImageOperation>,
+) -> Result<Attachment, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || edit(&app, &id, &operations))
+        .await
+        .map_err(|e| GibberError::new("EDIT_FAILED", e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            width,
+            height,
+            Rgba([255, 255, 255, 255]),
+        ))
+    }
+
+    #[test]
+    fn test_crop_rotate_resize() {
+        let edited = apply(
+            image(400, 300),
+            &[
+                ImageOperation::Crop {
+                    rect: Rect {
+                        x: 100,
+                        y: 0,
+                        width: 200,
+                        height: 100,
+                    },
+                },
+                ImageOperation::Rotate { degrees: -90 },
+                ImageOperation::Resize {
+                    width: Some(50),
+                    height: None,
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!((edited.width(), edited.height()), (50, 100));
+    }
+
+    #[test]
+    fn test_rejects_bad_operations() {
+        let crop = ImageOperation::Crop {
+            rect: Rect {
+                x: 350,
+                y: 0,
+                width: 100,
+                height: 10,
+            },
+        };
+        let rotate = ImageOperation::Rotate { degrees: 45 };
+        let resize = ImageOperation::Resize {
+            width: None,
+            height: None,
+        };
+        for operation in [crop, rotate, resize] {
+            let err = apply(image(400, 300), &[operation]).unwrap_err();
+            assert_eq!(err.code(), "INVALID_OPERATION");
+        }
+    }
+
+    #[test]
+    fn test_redact_clamps_and_colors() {
+        let edited = apply(
+            image(10, 10),
+            &[ImageOperation::Redact {
+                regions: vec![Rect {
+                    x: 8,
+                    y: 8,
+                    width: 20,
+                    height: 20,
+                }],
+                color: Some("#ff0000".to_string()),
+            }],
+        )
+        .unwrap()
+        .into_rgba8();
+        assert_eq!(edited.get_pixel(9, 9), &Rgba([255, 0, 0, 255]));
+        assert_eq!(edited.get_pixel(7, 7), &Rgba([255, 255, 255, 255]));
+        assert_eq!(parse_color("red").unwrap_err().code(), "INVALID_OPERATION");
+    }
+
+    #[test]
+    fn test_operations_deserialize() {
+        let operations: Vec<ImageOperation> = serde_json::from_value(serde_json::json!([
+            { "op": "crop", "x": 1, "y": 2, "width": 3, "height": 4 },
+            { "op": "redact", "regions": [] },
+        ]))
+        .unwrap();
+        assert_eq!(
+            operations[0],
+            ImageOperation::Crop {
+                rect: Rect {
+                    x: 1,
+                    y: 2,
+                    width: 3,
+                    height: 4
+                }
+            }
+        );
+    }
+}
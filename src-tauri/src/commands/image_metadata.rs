@@ -0,0 +1,291 @@
+//! Image metadata removal.
+//!
+//! Photos and screenshots carry EXIF data, often including GPS coordinates,
+//! device serials, and capture times. Before an image attachment is sent
+//! to a remote provider, [`strip`] removes that metadata without
+//! re-encoding the pixels:
+//!
+//! - JPEG: APP1 (EXIF, XMP), APP13 (IPTC), and comment segments
+//! - PNG: `eXIf`, `tEXt`, `zTXt`, `iTXt`, and `tIME` chunks
+//! - WebP: `EXIF` and `XMP ` chunks, with their `VP8X` flags cleared
+//!
+//! Color profiles are kept. GIFs have no standard metadata and pass
+//! through. Stripping is on unless `stripImageMetadata` is turned off in
+//! the general settings.
+
+use base64::Engine as _;
+use serde::Serialize;
+use tauri::State;
+
+use crate::commands::attachments::{self, AttachmentKind, AttachmentStore};
+use crate::commands::settings;
+use crate::db::Database;
+use crate::error::GibberError;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// PNG chunks that describe the image rather than draw it.
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// JPEG markers of metadata segments: APP1, APP13, and COM.
+const JPEG_METADATA_MARKERS: [u8; 3] = [0xE1, 0xED, 0xFE];
+
+/// `VP8X` flag bits announcing EXIF and XMP chunks.
+const WEBP_METADATA_FLAGS: u8 = 0x08 | 0x04;
+
+fn malformed(format: &str) -> GibberError {
+    GibberError::new("INVALID_IMAGE", format!("Malformed {format} image"))
+}
+
+fn u16_be(bytes: &[u8], at: usize) -> Option<usize> {
+    Some(usize::from(u16::from_be_bytes(
+        bytes.get(at..at + 2)?.try_into().ok()?,
+    )))
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> Option<usize> {
+    usize::try_from(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?)).ok()
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<usize> {
+    usize::try_from(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?)).ok()
+}
+
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(bytes.get(..2)?);
+    let mut pos = 2;
+    loop {
+        if *bytes.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(pos + 1)?;
+        match marker {
+            // Fill byte before a marker.
+            0xFF => pos += 1,
+            // Standalone markers without a length.
+            0x01 | 0xD0..=0xD8 => {
+                out.extend_from_slice(&bytes[pos..pos + 2]);
+                pos += 2;
+            }
+            // Start of scan: the rest is image data.
+            0xDA => {
+                out.extend_from_slice(&bytes[pos..]);
+                return Some(out);
+            }
+            0xD9 => {
+                out.extend_from_slice(&bytes[pos..pos + 2]);
+                return Some(out);
+            }
+            _ => {
+                let end = pos + 2 + u16_be(bytes, pos + 2)?;
+                let segment = bytes.get(pos..end)?;
+                if !JPEG_METADATA_MARKERS.contains(&marker) {
+                    out.extend_from_slice(segment);
+                }
+                pos = end;
+            }
+        }
+    }
+}
+
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(PNG_SIGNATURE);
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < bytes.len() {
+        // Length, type, data, and CRC.
+        let end = pos.checked_add(12)?.checked_add(u32_be(bytes, pos)?)?;
+        let chunk = bytes.get(pos..end)?;
+        let kind = &chunk[4..8];
+        if !PNG_METADATA_CHUNKS.iter().any(|name| &name[..] == kind) {
+            out.extend_from_slice(chunk);
+        }
+        pos = end;
+        if kind == b"IEND" {
+            break;
+        }
+    }
+    Some(out)
+}
+
+fn strip_webp(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(bytes.get(..12)?);
+    let mut pos = 12;
+    while pos < bytes.len() {
+        let size = u32_le(bytes, pos + 4)?;
+        // Chunks are padded to an even size.
+        let end = pos
+            .checked_add(8)?
+            .checked_add(size + size % 2)?
+            .min(bytes.len());
+        let chunk = bytes.get(pos..end)?;
+        match &chunk[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(chunk);
+                if let Some(flags) = out.get_mut(start + 8) {
+                    *flags &= !WEBP_METADATA_FLAGS;
+                }
+            }
+            _ => out.extend_from_slice(chunk),
+        }
+        pos = end;
+    }
+    let riff_size = u32::try_from(out.len() - 8).ok()?;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
+
+/// Removes metadata from JPEG, PNG, and WebP images.
+///
+/// Other formats are returned unchanged.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_IMAGE` if the image
+/// structure can't be followed, so nothing unchecked is sent.
+pub fn strip(bytes: &[u8]) -> Result<Vec<u8>, GibberError> {
+    if bytes.starts_with(b"\xFF\xD8") {
+        strip_jpeg(bytes).ok_or_else(|| malformed("JPEG"))
+    } else if bytes.starts_with(PNG_SIGNATURE) {
+        strip_png(bytes).ok_or_else(|| malformed("PNG"))
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(&b"WEBP"[..]) {
+        strip_webp(bytes).ok_or_else(|| malformed("WebP"))
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// An image attachment ready to send to a provider.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageUpload {
+    /// Attachment the image came from
+    pub attachment_id: String,
+    /// MIME type
+    pub mime_type: String,
+    /// Image as a `data:` URL
+    pub data_url: String,
+    /// Whether metadata was removed
+    pub metadata_stripped: bool,
+}
+
+/// Returns an image attachment as sent to vision models, with metadata
+/// removed unless the user turned that off.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment
+/// doesn't exist, `NOT_IMAGE` if it isn't an image, or `INVALID_IMAGE` if
+/// its metadata can't be removed.
+///
+/// # Example
+///
+/// ```typescript
+/// const { dataUrl } = await invoke("prepare_image_upload", { id: attachment.id });
+/// content.push({ type: "image_url", image_url: { url: dataUrl } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn prepare_image_upload(
+    db: State<'_, Database>,
+    store: State<'_, AttachmentStore>,
+    id: String,
+) -> Result<ImageUpload, GibberError> {
+    let (attachment, strip_metadata) = {
+        let conn = db.conn();
+        let attachment = attachments::load_attachment(&conn, &id)?
+            .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Attachment {id} not found")))?;
+        (attachment, settings::load(&conn)?.strip_image_metadata)
+    };
+    if attachment.kind != AttachmentKind::Image {
+        return Err(GibberError::new(
+            "NOT_IMAGE",
+            format!("{} is not an image", attachment.file_name),
+        ));
+    }
+    let path = store.file_path(&attachment);
+    let encoded = tauri::async_runtime::spawn_blocking(move || {
+        let bytes = std::fs::read(path)?;
+        let bytes = if strip_metadata {
+            strip(&bytes)?
+        } else {
+            bytes
+        };
+        Ok::<_, GibberError>(base64::engine::general_purpose::STANDARD.encode(&bytes))
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))??;
+    Ok(ImageUpload {
+        data_url: format!("data:{};base64,{encoded}", attachment.mime_type),
+        attachment_id: attachment.id,
+        mime_type: attachment.mime_type,
+        metadata_stripped: strip_metadata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_chunk(kind: [u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = u32::try_from(data.len()).unwrap().to_be_bytes().to_vec();
+        chunk.extend_from_slice(&kind);
+        chunk.extend_from_slice(data);
+        // CRCs aren't checked while stripping.
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    #[test]
+    fn test_strip_jpeg_drops_exif_keeps_icc() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x04, b'J', b'F']);
+        jpeg.extend_from_slice(&[0xFF, 0xE1, 0x00, 0x06, b'E', b'x', b'i', b'f']);
+        jpeg.extend_from_slice(&[0xFF, 0xE2, 0x00, 0x04, b'I', b'C']);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9]);
+        let stripped = strip(&jpeg).unwrap();
+        assert!(!stripped.windows(4).any(|w| w == b"Exif"));
+        assert!(stripped.windows(2).any(|w| w == b"IC"));
+        assert!(stripped.ends_with(&[0x12, 0x34, 0xFF, 0xD9]));
+        assert_eq!(strip(&jpeg[..9]).unwrap_err().code(), "INVALID_IMAGE");
+    }
+
+    #[test]
+    fn test_strip_png_drops_text_chunks() {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(png_chunk(*b"IHDR", &[0; 13]));
+        png.extend(png_chunk(*b"tEXt", b"Author\0me"));
+        png.extend(png_chunk(*b"eXIf", b"MM\0*"));
+        png.extend(png_chunk(*b"IDAT", &[1, 2, 3]));
+        png.extend(png_chunk(*b"IEND", &[]));
+        let mut expected = PNG_SIGNATURE.to_vec();
+        expected.extend(png_chunk(*b"IHDR", &[0; 13]));
+        expected.extend(png_chunk(*b"IDAT", &[1, 2, 3]));
+        expected.extend(png_chunk(*b"IEND", &[]));
+        assert_eq!(strip(&png).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_strip_webp_clears_flags_and_size() {
+        let mut chunks = b"VP8X".to_vec();
+        chunks.extend_from_slice(&10u32.to_le_bytes());
+        chunks.extend_from_slice(&[0x0C, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        chunks.extend_from_slice(b"EXIF");
+        chunks.extend_from_slice(&3u32.to_le_bytes());
+        chunks.extend_from_slice(&[1, 2, 3, 0]);
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&u32::try_from(chunks.len() + 4).unwrap().to_le_bytes());
+        webp.extend_from_slice(b"WEBP");
+        webp.extend(chunks);
+
+        let stripped = strip(&webp).unwrap();
+        assert_eq!(stripped.len(), 12 + 18);
+        assert_eq!(u32_le(&stripped, 4), Some(stripped.len() - 8));
+        assert_eq!(stripped[20], 0);
+        assert_eq!(strip(b"GIF89a").unwrap(), b"GIF89a");
+    }
+}
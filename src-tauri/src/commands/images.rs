@@ -0,0 +1,813 @@
+//! Image generation.
+//!
+//! `generate_image` sends a prompt to one of the supported providers:
+//!
+//! - OpenRouter, through chat completions with image output
+//! - OpenAI, through the Images API
+//! - Stability AI, through the Stable Image API
+//! - A local Stable Diffusion server, see [`stable_diffusion`]
+//!
+//! API keys are read from the keyring under `openrouter`, `openai`, and
+//! `stability`; the local server needs none. Each image is stored in the attachment store and attached
+//! to the conversation, and an assistant message records the generation.
+//! The parameters used are stored with that message so the image can be
+//! reproduced or varied later.
+//!
+//! Cloud providers don't report progress while they render, so
+//! [`events::IMAGES_PROGRESS`] announces each stage and each finished image.
+//! Local renders also report their queue position and progress.
+
+use std::time::Duration;
+
+use base64::Engine;
+use reqwest::{Client, Response};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::MessageRole;
+use crate::commands::attachments::{self, Attachment};
+use crate::commands::conversations::{self, NewMessage};
+use crate::commands::credentials;
+use crate::commands::events;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::privacy;
+use crate::commands::routing_policy;
+use crate::commands::stable_diffusion::{self, LocalProgress};
+use crate::commands::tray::GenerationGuard;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+const OPENROUTER_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+const OPENAI_URL: &str = "https://api.openai.com/v1/images/generations";
+const STABILITY_URL: &str = "https://api.stability.ai/v2beta/stable-image/generate";
+
+/// Rendering can take a while, especially several images at once.
+const REQUEST_TIMEOUT: Duration = Duration::from_mins(3);
+
+/// Most images per request.
+const MAX_COUNT: u32 = 4;
+
+/// Most sampling steps for local renders.
+const MAX_STEPS: u32 = 150;
+
+/// Longest prompt accepted, in characters.
+const MAX_PROMPT_CHARS: usize = 4_000;
+
+/// Aspect ratios the Stability API accepts.
+const STABILITY_RATIOS: [(&str, f64); 9] = [
+    ("21:9", 21.0 / 9.0),
+    ("16:9", 16.0 / 9.0),
+    ("3:2", 3.0 / 2.0),
+    ("5:4", 5.0 / 4.0),
+    ("1:1", 1.0),
+    ("4:5", 4.0 / 5.0),
+    ("2:3", 2.0 / 3.0),
+    ("9:16", 9.0 / 16.0),
+    ("9:21", 9.0 / 21.0),
+];
+
+/// Services that generate images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProvider {
+    /// OpenRouter models with image output
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+    /// OpenAI Images API
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Stability AI Stable Image API
+    Stability,
+    /// Local Stable Diffusion server
+    Local,
+}
+
+impl ImageProvider {
+    /// Database representation, also the keyring service of the API key.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::OpenRouter => "openrouter",
+            Self::OpenAi => "openai",
+            Self::Stability => "stability",
+            Self::Local => "local",
+        }
+    }
+
+    /// Model used when the request doesn't name one.
+    pub const fn default_model(self) -> &'static str {
+        match self {
+            Self::OpenRouter => "google/gemini-2.5-flash-image-preview",
+            Self::OpenAi => "gpt-image-1",
+            Self::Stability => "core",
+            // The checkpoint the server has loaded.
+            Self::Local => "default",
+        }
+    }
+
+    const fn service(self) -> Service {
+        match self {
+            Self::OpenRouter => Service::OpenRouter,
+            Self::OpenAi | Self::Stability => Service::Images,
+            Self::Local => Service::StableDiffusion,
+        }
+    }
+}
+
+/// Parameters of an image generation, stored with its message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageParams {
+    /// What to draw
+    pub prompt: String,
+    /// Service to generate with
+    pub provider: ImageProvider,
+    /// Model; the provider's default when `None`
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Size as `WIDTHxHEIGHT`, e.g. `1024x1024`
+    #[serde(default)]
+    pub size: Option<String>,
+    /// Number of images, 1 to 4 (default: 1)
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// What to avoid, for providers that support it
+    #[serde(default)]
+    pub negative_prompt: Option<String>,
+    /// Seed, for providers that support it
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Sampling steps, for the local provider
+    #[serde(default)]
+    pub steps: Option<u32>,
+    /// Sampler name, for the local provider
+    #[serde(default)]
+    pub sampler: Option<String>,
+}
+
+impl ImageParams {
+    fn count(&self) -> u32 {
+        self.count.unwrap_or(1)
+    }
+
+    fn model(&self) -> &str {
+        self.model
+            .as_deref()
+            .filter(|model| !model.is_empty())
+            .unwrap_or_else(|| self.provider.default_model())
+    }
+
+    fn validate(&self) -> Result<(), GibberError> {
+        if self.prompt.trim().is_empty() {
+            return Err(GibberError::new("INVALID_INPUT", "A prompt is required"));
+        }
+        if self.prompt.chars().count() > MAX_PROMPT_CHARS {
+            return Err(GibberError::new(
+                "INVALID_INPUT",
+                format!("Prompt exceeds {MAX_PROMPT_CHARS} characters"),
+            ));
+        }
+        if !(1..=MAX_COUNT).contains(&self.count()) {
+            return Err(GibberError::new(
+                "INVALID_INPUT",
+                format!("Between 1 and {MAX_COUNT} images can be generated at once"),
+            ));
+        }
+        if let Some(size) = &self.size {
+            parse_size(size)?;
+        }
+        if self
+            .steps
+            .is_some_and(|steps| !(1..=MAX_STEPS).contains(&steps))
+        {
+            return Err(GibberError::new(
+                "INVALID_INPUT",
+                format!("Steps must be between 1 and {MAX_STEPS}"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A stored generation.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageGeneration {
+    /// Unique identifier
+    pub id: String,
+    /// Conversation the images belong to
+    pub conversation_id: String,
+    /// Assistant message recording the generation
+    pub message_id: String,
+    /// Parameters used
+    pub params: ImageParams,
+    /// Model that served the request
+    pub model: String,
+    /// The generated images
+    pub attachment_ids: Vec<String>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// Stage of a generation, reported by [`events::IMAGES_PROGRESS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageStage {
+    /// The request is being sent
+    Started,
+    /// Waiting in the local server's queue
+    Queued,
+    /// The local server is rendering
+    Rendering,
+    /// An image was received and stored
+    Received,
+    /// All images are stored
+    Completed,
+    /// The generation failed
+    Failed,
+}
+
+/// Payload of [`events::IMAGES_PROGRESS`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageProgress {
+    /// Conversation the generation is for
+    pub conversation_id: String,
+    /// Current stage
+    pub stage: ImageStage,
+    /// Images stored so far
+    pub completed: u32,
+    /// Images requested
+    pub total: u32,
+    /// Newest stored image, for `received`
+    pub attachment: Option<Attachment>,
+    /// Error message, for `failed`
+    pub error: Option<String>,
+    /// Jobs ahead in the local queue, for `queued`
+    pub queue_position: Option<u32>,
+    /// Rendered fraction from 0 to 1, for `rendering` when known
+    pub fraction: Option<f64>,
+}
+
+/// Parses a `WIDTHxHEIGHT` size.
+fn parse_size(size: &str) -> Result<(u32, u32), GibberError> {
+    size.split_once(['x', 'X'])
+        .and_then(|(width, height)| Some((width.trim().parse().ok()?, height.trim().parse().ok()?)))
+        .filter(|&(width, height): &(u32, u32)| {
+            (64..=4096).contains(&width) && (64..=4096).contains(&height)
+        })
+        .ok_or_else(|| GibberError::new("INVALID_INPUT", format!("Invalid image size \"{size}\"")))
+}
+
+/// The Stability aspect ratio closest to a size.
+fn aspect_ratio(size: Option<&str>) -> &'static str {
+    let Some((width, height)) = size.and_then(|size| parse_size(size).ok()) else {
+        return "1:1";
+    };
+    let ratio = f64::from(width) / f64::from(height);
+    STABILITY_RATIOS
+        .iter()
+        .min_by(|a, b| (a.1 - ratio).abs().total_cmp(&(b.1 - ratio).abs()))
+        .map_or("1:1", |(name, _)| *name)
+}
+
+/// File extension for image bytes, by their signature.
+fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG") {
+        Some("png")
+    } else if bytes.starts_with(b"\xFF\xD8\xFF") {
+        Some("jpg")
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(&b"WEBP"[..]) {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>, GibberError> {
+    // Data URLs carry a `data:image/png;base64,` prefix.
+    let data = data.rsplit_once(',').map_or(data, |(_, data)| data);
+    base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| GibberError::new("PARSE_ERROR", format!("Invalid image data: {e}")))
+}
+
+async fn error_message(response: Response) -> GibberError {
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body
+        .pointer("/error/message")
+        .or_else(|| body.get("errors").and_then(|errors| errors.get(0)))
+        .or_else(|| body.get("message"))
+        .and_then(serde_json::Value::as_str)
+        .map_or_else(|| status.to_string(), str::to_string);
+    let code = match status.as_u16() {
+        401 | 403 => "UNAUTHORIZED",
+        402 => "INSUFFICIENT_CREDITS",
+        429 => "RATE_LIMITED",
+        400 | 422 => "REJECTED",
+        _ => "PROVIDER_ERROR",
+    };
+    GibberError::new(code, message)
+}
+
+/// Generates with OpenRouter; each image arrives as a data URL.
+async fn openrouter(
+    app: &AppHandle,
+    client: &Client,
+    api_key: &str,
+    params: &ImageParams,
+) -> Result<Vec<Vec<u8>>, GibberError> {
+    let zero_data_retention = privacy::zero_data_retention(&app.state::<Database>().conn());
+    let mut images = Vec::new();
+    for _ in 0..params.count() {
+        let mut body = serde_json::json!({
+            "model": params.model(),
+            "modalities": ["image", "text"],
+            "messages": [{ "role": "user", "content": params.prompt }],
+        });
+        if zero_data_retention {
+            privacy::apply(&mut body, "openrouter");
+        }
+        let builder = client
+            .post(OPENROUTER_URL)
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(api_key)
+            .json(&body);
+        let response =
+            network_activity::send(app, Service::OpenRouter, "images.generate", builder).await?;
+        if !response.status().is_success() {
+            return Err(error_message(response).await);
+        }
+        let body: serde_json::Value = response.json().await?;
+        let urls = body
+            .pointer("/choices/0/message/images")
+            .and_then(serde_json::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|image| image.pointer("/image_url/url")?.as_str());
+        for url in urls {
+            images.push(decode_base64(url)?);
+        }
+    }
+    Ok(images)
+}
+
+/// Generates with the OpenAI Images API.
+async fn openai(
+    app: &AppHandle,
+    client: &Client,
+    api_key: &str,
+    params: &ImageParams,
+) -> Result<Vec<Vec<u8>>, GibberError> {
+    let model = params.model();
+    let mut body = serde_json::json!({
+        "model": model,
+        "prompt": params.prompt,
+        "n": params.count(),
+        "size": params.size.as_deref().unwrap_or("1024x1024"),
+    });
+    // GPT image models always return base64; DALL·E defaults to URLs.
+    if model.starts_with("dall-e") {
+        body["response_format"] = serde_json::json!("b64_json");
+    }
+    let builder = client
+        .post(OPENAI_URL)
+        .timeout(REQUEST_TIMEOUT)
+        .bearer_auth(api_key)
+        .json(&body);
+    let response = network_activity::send(app, Service::Images, "images.generate", builder).await?;
+    if !response.status().is_success() {
+        return Err(error_message(response).await);
+    }
+    let body: serde_json::Value = response.json().await?;
+    body.get("data")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|image| image.get("b64_json")?.as_str())
+        .map(decode_base64)
+        .collect()
+}
+
+/// Generates with the Stability API, one image per request.
+async fn stability(
+    app: &AppHandle,
+    client: &Client,
+    api_key: &str,
+    params: &ImageParams,
+) -> Result<Vec<Vec<u8>>, GibberError> {
+    let model = params.model();
+    // `sd3*` models share one endpoint; `core` and `ultra` have their own.
+    let (endpoint, sd3_model) = if model.starts_with("sd3") {
+        ("sd3", Some(model.to_string()))
+    } else {
+        (model, None)
+    };
+    let mut images = Vec::new();
+    for index in 0..params.count() {
+        let mut form = reqwest::multipart::Form::new()
+            .text("prompt", params.prompt.clone())
+            .text("output_format", "png")
+            .text("aspect_ratio", aspect_ratio(params.size.as_deref()));
+        if let Some(model) = &sd3_model {
+            form = form.text("model", model.clone());
+        }
+        if let Some(negative) = params.negative_prompt.as_ref().filter(|n| !n.is_empty()) {
+            form = form.text("negative_prompt", negative.clone());
+        }
+        if let Some(seed) = params.seed {
+            // Consecutive seeds keep a batch reproducible but varied.
+            form = form.text("seed", (seed + u64::from(index)).to_string());
+        }
+        let builder = client
+            .post(format!("{STABILITY_URL}/{endpoint}"))
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(api_key)
+            .header(reqwest::header::ACCEPT, "image/*")
+            .multipart(form);
+        let response =
+            network_activity::send(app, Service::Images, "images.generate", builder).await?;
+        if !response.status().is_success() {
+            return Err(error_message(response).await);
+        }
+        images.push(response.bytes().await?.to_vec());
+    }
+    Ok(images)
+}
+
+const GENERATION_COLUMNS: &str =
+    "id, conversation_id, message_id, params, model, attachment_ids, created_at";
+
+fn generation_from_row(row: &Row<'_>) -> rusqlite::Result<ImageGeneration> {
+    let params: String = row.get(3)?;
+    let attachment_ids: String = row.get(5)?;
+    let invalid = |index, e: serde_json::Error| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    };
+    Ok(ImageGeneration {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        message_id: row.get(2)?,
+        params: serde_json::from_str(&params).map_err(|e| invalid(3, e))?,
+        model: row.get(4)?,
+        attachment_ids: serde_json::from_str(&attachment_ids).map_err(|e| invalid(5, e))?,
+        created_at: row.get(6)?,
+    })
+}
+
+/// Records a finished generation as an assistant message.
+fn store_generation(
+    conn: &Connection,
+    conversation_id: &str,
+    params: &ImageParams,
+    attachments: &[Attachment],
+) -> rusqlite::Result<ImageGeneration> {
+    let model = format!("{}/{}", params.provider.as_str(), params.model());
+    let message = conversations::insert_message(
+        conn,
+        conversation_id,
+        &NewMessage {
+            role: MessageRole::Assistant,
+            content: format!(
+                "Generated {} image{} for \"{}\".",
+                attachments.len(),
+                if attachments.len() == 1 { "" } else { "s" },
+                params.prompt.trim()
+            ),
+            model: Some(model.clone()),
+            usage: None,
+        },
+    )?;
+    let generation = ImageGeneration {
+        id: db::new_id(),
+        conversation_id: conversation_id.to_string(),
+        message_id: message.id,
+        params: params.clone(),
+        model,
+        attachment_ids: attachments.iter().map(|a| a.id.clone()).collect(),
+        created_at: db::now_millis(),
+    };
+    conn.execute(
+        &format!("INSERT INTO image_generations ({GENERATION_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"),
+        params![
+            generation.id,
+            generation.conversation_id,
+            generation.message_id,
+            serde_json::to_string(&generation.params).unwrap_or_default(),
+            generation.model,
+            serde_json::to_string(&generation.attachment_ids).unwrap_or_default(),
+            generation.created_at,
+        ],
+    )?;
+    Ok(generation)
+}
+
+fn emit_progress(app: &AppHandle, progress: &ImageProgress) {
+    events::emit_typed(app, events::IMAGES_PROGRESS, progress);
+}
+
+#[allow(clippy::too_many_lines)] // one branch per provider, each reporting progress
+async fn run(
+    app: &AppHandle,
+    conversation_id: &str,
+    params: &ImageParams,
+) -> Result<ImageGeneration, GibberError> {
+    params.validate()?;
+    if !conversations::conversation_exists(&app.state::<Database>().conn(), conversation_id)? {
+        return Err(GibberError::new(
+            "NOT_FOUND",
+            format!("Conversation {conversation_id} not found"),
+        ));
+    }
+    let provider = params.provider;
+    routing_policy::enforce(app, conversation_id, provider.as_str(), params.model())?;
+    let remote = if provider == ImageProvider::Local {
+        None
+    } else {
+        let api_key = credentials::load_api_key(app, provider.as_str())
+            .await
+            .map_err(|e| GibberError::new("NO_API_KEY", e.to_string()))?
+            .ok_or_else(|| {
+                GibberError::new(
+                    "NO_API_KEY",
+                    format!("No {} API key is stored", provider.as_str()),
+                )
+            })?;
+        let client = network::client(app, provider.service())
+            .map_err(|e| GibberError::new("NETWORK", e.to_string()))?;
+        Some((client, api_key))
+    };
+
+    let _generating = GenerationGuard::new(app);
+    let total = params.count();
+    emit_progress(
+        app,
+        &ImageProgress {
+            conversation_id: conversation_id.to_string(),
+            stage: ImageStage::Started,
+            completed: 0,
+            total,
+            attachment: None,
+            error: None,
+            queue_position: None,
+            fraction: None,
+        },
+    );
+    let images = match (provider, &remote) {
+        (ImageProvider::OpenRouter, Some((client, api_key))) => {
+            openrouter(app, client, api_key, params).await?
+        }
+        (ImageProvider::OpenAi, Some((client, api_key))) => {
+            openai(app, client, api_key, params).await?
+        }
+        (ImageProvider::Stability, Some((client, api_key))) => {
+            stability(app, client, api_key, params).await?
+        }
+        _ => {
+            let report = |progress: LocalProgress| {
+                emit_progress(
+                    app,
+                    &ImageProgress {
+                        conversation_id: conversation_id.to_string(),
+                        stage: if progress.queue_position.unwrap_or_default() > 0 {
+                            ImageStage::Queued
+                        } else {
+                            ImageStage::Rendering
+                        },
+                        completed: 0,
+                        total,
+                        attachment: None,
+                        error: None,
+                        queue_position: progress.queue_position,
+                        fraction: progress.fraction,
+                    },
+                );
+            };
+            stable_diffusion::generate(app, params, &report).await?
+        }
+    };
+    if images.is_empty() {
+        return Err(GibberError::new(
+            "NO_IMAGE",
+            "The provider returned no images",
+        ));
+    }
+
+    let mut stored = Vec::new();
+    for (index, bytes) in images.iter().enumerate() {
+        let extension = image_extension(bytes).ok_or_else(|| {
+            GibberError::new(
+                "PARSE_ERROR",
+                "The provider returned an unknown image format",
+            )
+        })?;
+        let file_name = format!("image-{}.{extension}", index + 1);
+        let attachment =
+            attachments::store_captured(app, &file_name, bytes, Some(conversation_id))?;
+        stored.push(attachment.clone());
+        emit_progress(
+            app,
+            &ImageProgress {
+                conversation_id: conversation_id.to_string(),
+                stage: ImageStage::Received,
+                completed: u32::try_from(stored.len()).unwrap_or(u32::MAX),
+                total,
+                attachment: Some(attachment),
+                error: None,
+                queue_position: None,
+                fraction: None,
+            },
+        );
+    }
+    let generation = store_generation(
+        &app.state::<Database>().conn(),
+        conversation_id,
+        params,
+        &stored,
+    )?;
+    emit_progress(
+        app,
+        &ImageProgress {
+            conversation_id: conversation_id.to_string(),
+            stage: ImageStage::Completed,
+            completed: u32::try_from(stored.len()).unwrap_or(u32::MAX),
+            total,
+            attachment: None,
+            error: None,
+            queue_position: None,
+            fraction: None,
+        },
+    );
+    Ok(generation)
+}
+
+/// Generates images for a conversation.
+///
+/// Images are attached to the conversation as they are stored, and an
+/// assistant message with the parameters records the generation. Progress
+/// arrives via `images://progress`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` for bad parameters,
+/// `NOT_FOUND` if the conversation doesn't exist, `NO_API_KEY` if the
+/// provider's key isn't stored, `UNAUTHORIZED`, `RATE_LIMITED`, `REJECTED`,
+/// or `PROVIDER_ERROR` if the provider refuses, `NETWORK` if it can't be
+/// reached, `POLICY_BLOCKED` if the conversation is sensitive and the
+/// provider isn't allowed to receive it, or `UNAVAILABLE`, `BUSY`, or
+/// `CANCELLED` for local renders.
+///
+/// # Example
+///
+/// ```typescript
+/// await listen("images://progress", (e) => updateProgress(e.payload));
+/// const generation = await invoke("generate_image", {
+///   conversationId,
+///   params: { prompt: "A modular synth at dusk", provider: "openai", size: "1536x1024" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn generate_image(
+    app: AppHandle,
+    conversation_id: String,
+    params: ImageParams,
+) -> Result<ImageGeneration, GibberError> {
+    let result = run(&app, &conversation_id, &params).await;
+    if let Err(e) = &result {
+        tracing::warn!(
+            provider = params.provider.as_str(),
+            code = e.code(),
+            "image generation failed: {e}"
+        );
+        emit_progress(
+            &app,
+            &ImageProgress {
+                conversation_id,
+                stage: ImageStage::Failed,
+                completed: 0,
+                total: params.count(),
+                attachment: None,
+                error: Some(e.to_string()),
+                queue_position: None,
+                fraction: None,
+            },
+        );
+    }
+    result
+}
+
+/// Returns the generation recorded by a message, if it has one.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const generation = await invoke("get_image_generation", { messageId });
+/// if (generation) await invoke("generate_image", { conversationId, params: generation.params });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_image_generation(
+    db: State<'_, Database>,
+    message_id: &str,
+) -> Result<Option<ImageGeneration>, GibberError> {
+    let generation = db
+        .conn()
+        .query_row(
+            &format!("SELECT {GENERATION_COLUMNS} FROM image_generations WHERE message_id = ?1"),
+            [message_id],
+            generation_from_row,
+        )
+        .optional()?;
+    Ok(generation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::conversations::NewConversation;
+
+    fn params(provider: ImageProvider) -> ImageParams {
+        ImageParams {
+            prompt: "A modular synth at dusk".to_string(),
+            provider,
+            model: None,
+            size: None,
+            count: None,
+            negative_prompt: None,
+            seed: None,
+            steps: None,
+            sampler: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_params() {
+        assert!(params(ImageProvider::OpenAi).validate().is_ok());
+        let too_many = ImageParams {
+            count: Some(5),
+            ..params(ImageProvider::OpenAi)
+        };
+        assert_eq!(too_many.validate().unwrap_err().code(), "INVALID_INPUT");
+        let bad_size = ImageParams {
+            size: Some("huge".to_string()),
+            ..params(ImageProvider::OpenAi)
+        };
+        assert_eq!(bad_size.validate().unwrap_err().code(), "INVALID_INPUT");
+        assert_eq!(params(ImageProvider::Stability).model(), "core");
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        assert_eq!(aspect_ratio(None), "1:1");
+        assert_eq!(aspect_ratio(Some("1024x1024")), "1:1");
+        assert_eq!(aspect_ratio(Some("1920x1080")), "16:9");
+        assert_eq!(aspect_ratio(Some("1024x1536")), "2:3");
+    }
+
+    #[test]
+    fn test_decode_and_detect() {
+        let png = decode_base64("data:image/png;base64,iVBORw0KGgo=").unwrap();
+        assert_eq!(image_extension(&png), Some("png"));
+        assert_eq!(image_extension(b"GIF89a"), None);
+    }
+
+    #[test]
+    fn test_store_generation_round_trips() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let conversation = conversations::insert_conversation(
+            &conn,
+            &NewConversation {
+                title: "Images".to_string(),
+                model: None,
+                system_prompt: None,
+                source: None,
+            },
+        )
+        .unwrap();
+        let params = ImageParams {
+            seed: Some(7),
+            ..params(ImageProvider::Stability)
+        };
+        let generation = store_generation(&conn, &conversation.id, &params, &[]).unwrap();
+        assert_eq!(generation.model, "stability/core");
+        let loaded = conn
+            .query_row(
+                &format!(
+                    "SELECT {GENERATION_COLUMNS} FROM image_generations WHERE message_id = ?1"
+                ),
+                [&generation.message_id],
+                generation_from_row,
+            )
+            .unwrap();
+        assert_eq!(loaded.params, params);
+    }
+}
@@ -0,0 +1,188 @@
+//! Single-instance enforcement and argument forwarding.
+//!
+//! Only one Gibber AI process runs at a time. When the app is launched again
+//! (from a file association, a shell, or a link), the single-instance plugin
+//! hands the new process's arguments to the running one, which focuses its main
+//! window and emits [`events::INSTANCE_FORWARDED`]. Files passed on the command
+//! line (including "Open with Gibber AI") are staged as attachments.
+//!
+//! Arguments from the very first launch are kept in [`LaunchArgs`] so the
+//! frontend can collect them with `take_launch_args` once it is ready.
+//!
+//! `gibber://` links are left out: the deep-link plugin receives them from
+//! both first and forwarded launches and routes them itself.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::commands::attachments::{self, AttachmentOrigin};
+use crate::commands::events;
+use crate::commands::quick_capture;
+
+/// URL scheme handled by the app.
+pub const URL_SCHEME: &str = "gibber";
+
+/// Arguments of a launch, classified.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardedArgs {
+    /// File paths, made absolute against the launching working directory
+    pub files: Vec<String>,
+    /// Flags such as `--minimized`, passed through verbatim
+    pub flags: Vec<String>,
+}
+
+impl ForwardedArgs {
+    /// Returns true if the launch carried nothing to act on.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.flags.is_empty()
+    }
+}
+
+/// Arguments of the first launch, until the frontend takes them.
+#[derive(Default)]
+pub struct LaunchArgs(Mutex<Option<ForwardedArgs>>);
+
+impl LaunchArgs {
+    /// Stores the arguments of the current process.
+    pub fn from_env() -> Self {
+        let argv: Vec<String> = std::env::args().collect();
+        let cwd = std::env::current_dir()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let forwarded = parse_args(&argv, &cwd);
+        Self(Mutex::new((!forwarded.is_empty()).then_some(forwarded)))
+    }
+
+    /// Returns the file paths of the first launch, if not yet taken.
+    pub fn files(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .as_ref()
+            .map(|args| args.files.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Classifies a raw argument vector.
+///
+/// The first element (the executable) and `gibber://` links are skipped.
+pub fn parse_args(argv: &[String], cwd: &str) -> ForwardedArgs {
+    let scheme_prefix = format!("{URL_SCHEME}://");
+    let mut forwarded = ForwardedArgs::default();
+    for arg in argv.iter().skip(1) {
+        if arg.starts_with(&scheme_prefix) {
+            continue;
+        }
+        if arg.starts_with('-') {
+            forwarded.flags.push(arg.clone());
+        } else if !arg.is_empty() {
+            let path = Path::new(arg);
+            let absolute = if path.is_absolute() || cwd.is_empty() {
+                path.to_path_buf()
+            } else {
+                Path::new(cwd).join(path)
+            };
+            forwarded
+                .files
+                .push(absolute.to_string_lossy().into_owned());
+        }
+    }
+    forwarded
+}
+
+/// Handles a second launch: focuses the main window, stages any files as
+/// attachments, and forwards its arguments.
+pub fn handle_second_instance(app: &AppHandle, argv: &[String], cwd: &str) {
+    if let Err(e) = quick_capture::focus_main_window(app) {
+        tracing::warn!("failed to focus main window: {e}");
+    }
+    let forwarded = parse_args(argv, cwd);
+    if forwarded.is_empty() {
+        return;
+    }
+    let files: Vec<PathBuf> = forwarded.files.iter().map(PathBuf::from).collect();
+    attachments::stage_paths(app, &files, AttachmentOrigin::OpenWith, None);
+    if let Err(e) = events::emit_typed_to(
+        app,
+        quick_capture::MAIN_WINDOW_LABEL,
+        events::INSTANCE_FORWARDED,
+        &forwarded,
+    ) {
+        tracing::warn!("failed to forward arguments: {e}");
+    }
+}
+
+/// Returns the arguments of the first launch, once.
+///
+/// Later calls return `None`, so a reloaded frontend doesn't act on the same
+/// launch twice. Arguments of later launches arrive via
+/// [`events::INSTANCE_FORWARDED`].
+///
+/// # Example
+///
+/// ```typescript
+/// const args = await invoke("take_launch_args");
+/// if (args) handleLaunch(args);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn take_launch_args(state: State<'_, LaunchArgs>) -> Option<ForwardedArgs> {
+    state
+        .0
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .take()
+}
+
+/// Installs the single-instance plugin. Must be the first plugin registered.
+#[cfg(desktop)]
+pub fn plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri_plugin_single_instance::init(|app, argv, cwd| {
+        handle_second_instance(app, &argv, &cwd);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        std::iter::once("gibber-ai")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_classifies_arguments() {
+        let args = parse_args(
+            &argv(&["gibber://new?prompt=hi", "--minimized", "/tmp/notes.md"]),
+            "/home/user",
+        );
+        assert_eq!(args.flags, vec!["--minimized"]);
+        assert_eq!(args.files, vec!["/tmp/notes.md"]);
+    }
+
+    #[test]
+    fn test_parse_resolves_relative_paths() {
+        let args = parse_args(&argv(&["notes.md"]), "/home/user");
+        assert_eq!(
+            args.files,
+            vec![Path::new("/home/user")
+                .join("notes.md")
+                .to_string_lossy()
+                .into_owned()]
+        );
+    }
+
+    #[test]
+    fn test_parse_skips_executable() {
+        assert!(parse_args(&argv(&[]), "/").is_empty());
+    }
+}
@@ -0,0 +1,423 @@
+//! Background jobs.
+//!
+//! Long-running work (dataset exports, importing dropped files, ...) runs as
+//! a job so the UI can show one progress list for all of it and cancel any
+//! entry. A job is started with [`start`]; the returned [`JobHandle`]
+//! reports progress, which is emitted as [`events::JOB_PROGRESS`], and is polled
+//! by the work itself for cancellation.
+//!
+//! Jobs are recorded in the `jobs` table when they start and finish. The
+//! progress of running jobs is only kept in memory, since the work usually
+//! holds the database connection while it runs; jobs still marked running
+//! at startup belonged to a previous session and are marked interrupted.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::events;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Jobs returned by [`list_jobs`] unless a limit is given.
+const DEFAULT_LIMIT: u32 = 50;
+
+/// Columns selected for [`Job`] rows, in `job_from_row` order.
+const JOB_COLUMNS: &str =
+    "id, kind, label, status, progress, message, error, created_at, finished_at";
+
+/// Where a job is in its life.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Still working
+    Running,
+    /// Finished successfully
+    Completed,
+    /// Stopped with an error
+    Failed,
+    /// Stopped at the user's request
+    Cancelled,
+    /// Still running when the app quit
+    Interrupted,
+}
+
+impl JobStatus {
+    /// Returns the status as stored in the database.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "running" => Self::Running,
+            "completed" => Self::Completed,
+            "cancelled" => Self::Cancelled,
+            "interrupted" => Self::Interrupted,
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// A background job.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    /// Unique identifier
+    pub id: String,
+    /// What kind of work this is, e.g. `export.dataset`
+    pub kind: String,
+    /// Description for the UI
+    pub label: String,
+    /// Current status
+    pub status: JobStatus,
+    /// Percent done, 0 to 100
+    pub progress: f64,
+    /// What the job is doing now, or what it did
+    pub message: Option<String>,
+    /// Why the job failed
+    pub error: Option<String>,
+    /// Start time (milliseconds since the Unix epoch)
+    pub created_at: i64,
+    /// End time (milliseconds since the Unix epoch)
+    pub finished_at: Option<i64>,
+}
+
+fn job_from_row(row: &Row<'_>) -> rusqlite::Result<Job> {
+    Ok(Job {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        label: row.get(2)?,
+        status: JobStatus::parse(&row.get::<_, String>(3)?),
+        progress: row.get(4)?,
+        message: row.get(5)?,
+        error: row.get(6)?,
+        created_at: row.get(7)?,
+        finished_at: row.get(8)?,
+    })
+}
+
+/// A running job and its cancellation flag.
+struct Live {
+    job: Job,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Jobs running in this session.
+#[derive(Default)]
+pub struct Jobs(Mutex<HashMap<String, Live>>);
+
+impl Jobs {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Live>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn emit(app: &AppHandle, job: &Job) {
+    events::emit_typed(app, events::JOB_PROGRESS, job);
+}
+
+/// Handle through which a job reports progress and learns of cancellation.
+///
+/// Dropping the handle without [`JobHandle::finish`] marks the job failed,
+/// so a job whose work panics doesn't stay running forever.
+pub struct JobHandle {
+    app: AppHandle,
+    id: String,
+    cancelled: Arc<AtomicBool>,
+    /// Last progress emitted, in whole percent
+    reported: Option<f64>,
+    finished: bool,
+}
+
+impl JobHandle {
+    /// Whether the user asked to cancel the job. Check it between units of
+    /// work and stop early when it is set.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Reports that `done` of `total` units are done.
+    ///
+    /// An event is only emitted when the whole percentage changes, so this
+    /// can be called for every unit.
+    pub fn advance(&mut self, done: usize, total: usize, message: Option<&str>) {
+        #[allow(clippy::cast_precision_loss)] // unit counts are far below 2^52
+        let percent = if total == 0 {
+            0.0
+        } else {
+            (done as f64 / total as f64 * 100.0).clamp(0.0, 100.0)
+        };
+        let whole = percent.floor();
+        if self.reported == Some(whole) {
+            return;
+        }
+        self.reported = Some(whole);
+        let jobs = self.app.state::<Jobs>();
+        let mut live = jobs.lock();
+        if let Some(entry) = live.get_mut(&self.id) {
+            entry.job.progress = whole;
+            entry.job.message = message.map(str::to_string);
+            emit(&self.app, &entry.job);
+        }
+    }
+
+    /// Ends the job, as failed with `error` or as completed, unless it was
+    /// cancelled. `message` summarizes the outcome.
+    pub fn finish(mut self, error: Option<&str>, message: Option<&str>) {
+        let status = if self.is_cancelled() {
+            JobStatus::Cancelled
+        } else if error.is_some() {
+            JobStatus::Failed
+        } else {
+            JobStatus::Completed
+        };
+        self.end(status, error, message);
+    }
+
+    fn end(&mut self, status: JobStatus, error: Option<&str>, message: Option<&str>) {
+        self.finished = true;
+        let Some(Live { mut job, .. }) = self.app.state::<Jobs>().lock().remove(&self.id) else {
+            return;
+        };
+        job.status = status;
+        if status == JobStatus::Completed {
+            job.progress = 100.0;
+        }
+        job.message = message.map(str::to_string).or(job.message.take());
+        job.error = error.map(str::to_string);
+        job.finished_at = Some(db::now_millis());
+        if let Err(e) = update(&self.app.state::<Database>().conn(), &job) {
+            tracing::error!("failed to record job {}: {e}", job.id);
+        }
+        emit(&self.app, &job);
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.end(
+                JobStatus::Failed,
+                Some("The job stopped unexpectedly"),
+                None,
+            );
+        }
+    }
+}
+
+fn insert(conn: &Connection, job: &Job) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!("INSERT INTO jobs ({JOB_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"),
+        params![
+            job.id,
+            job.kind,
+            job.label,
+            job.status.as_str(),
+            job.progress,
+            job.message,
+            job.error,
+            job.created_at,
+            job.finished_at
+        ],
+    )?;
+    Ok(())
+}
+
+fn update(conn: &Connection, job: &Job) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE jobs SET status = ?1, progress = ?2, message = ?3, error = ?4, finished_at = ?5
+         WHERE id = ?6",
+        params![
+            job.status.as_str(),
+            job.progress,
+            job.message,
+            job.error,
+            job.finished_at,
+            job.id
+        ],
+    )?;
+    Ok(())
+}
+
+/// Marks jobs left running by a previous session as interrupted.
+fn interrupt_stale(conn: &Connection, now: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE jobs SET status = 'interrupted', finished_at = ?1 WHERE status = 'running'",
+        [now],
+    )
+}
+
+fn query(conn: &Connection, status: Option<JobStatus>, limit: u32) -> rusqlite::Result<Vec<Job>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {JOB_COLUMNS} FROM jobs WHERE ?1 IS NULL OR status = ?1
+         ORDER BY created_at DESC, rowid DESC LIMIT ?2"
+    ))?;
+    let jobs = stmt
+        .query_map(params![status.map(JobStatus::as_str), limit], job_from_row)?
+        .collect();
+    jobs
+}
+
+/// Starts a job of `kind` described by `label` and announces it.
+///
+/// The job must be started while the database connection is free, since
+/// it is recorded right away.
+pub fn start(app: &AppHandle, kind: &str, label: &str) -> JobHandle {
+    let job = Job {
+        id: db::new_id(),
+        kind: kind.to_string(),
+        label: label.to_string(),
+        status: JobStatus::Running,
+        progress: 0.0,
+        message: None,
+        error: None,
+        created_at: db::now_millis(),
+        finished_at: None,
+    };
+    if let Err(e) = insert(&app.state::<Database>().conn(), &job) {
+        tracing::error!("failed to record job {}: {e}", job.id);
+    }
+    emit(app, &job);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let handle = JobHandle {
+        app: app.clone(),
+        id: job.id.clone(),
+        cancelled: Arc::clone(&cancelled),
+        reported: None,
+        finished: false,
+    };
+    app.state::<Jobs>()
+        .lock()
+        .insert(job.id.clone(), Live { job, cancelled });
+    handle
+}
+
+/// Creates the job registry and marks jobs of a previous session as
+/// interrupted.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+pub fn init(app: &AppHandle) {
+    match interrupt_stale(&app.state::<Database>().conn(), db::now_millis()) {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("marked {count} jobs of the previous session interrupted"),
+        Err(e) => tracing::error!("failed to mark interrupted jobs: {e}"),
+    }
+    app.manage(Jobs::default());
+}
+
+/// Lists jobs, newest first. Running jobs carry their live progress.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const running = await invoke("list_jobs", { status: "running" });
+/// await listen("job://progress", ({ payload }) => updateJob(payload));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_jobs(
+    db: State<'_, Database>,
+    jobs: State<'_, Jobs>,
+    status: Option<JobStatus>,
+    limit: Option<u32>,
+) -> Result<Vec<Job>, GibberError> {
+    let mut listed = query(&db.conn(), status, limit.unwrap_or(DEFAULT_LIMIT))?;
+    let live = jobs.lock();
+    for job in &mut listed {
+        if let Some(entry) = live.get(&job.id) {
+            job.clone_from(&entry.job);
+        }
+    }
+    Ok(listed)
+}
+
+/// Asks a running job to stop.
+///
+/// The job stops at its next checkpoint and is then reported as
+/// cancelled. Returns `false` if no job with this ID is running.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("cancel_job", { id: job.id });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn cancel_job(jobs: State<'_, Jobs>, id: &str) -> bool {
+    let live = jobs.lock();
+    let Some(entry) = live.get(id) else {
+        return false;
+    };
+    entry.cancelled.store(true, Ordering::Relaxed);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, status: JobStatus, created_at: i64) -> Job {
+        Job {
+            id: id.to_string(),
+            kind: "export.dataset".to_string(),
+            label: "Dataset export".to_string(),
+            status,
+            progress: 0.0,
+            message: None,
+            error: None,
+            created_at,
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn test_jobs_round_trip_newest_first() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        insert(&conn, &job("a", JobStatus::Running, 1)).unwrap();
+        let mut done = job("b", JobStatus::Running, 2);
+        insert(&conn, &done).unwrap();
+        done.status = JobStatus::Completed;
+        done.progress = 100.0;
+        done.message = Some("12 records".to_string());
+        done.finished_at = Some(3);
+        update(&conn, &done).unwrap();
+
+        let listed = query(&conn, None, 10).unwrap();
+        assert_eq!(listed, vec![done.clone(), job("a", JobStatus::Running, 1)]);
+        let completed = query(&conn, Some(JobStatus::Completed), 10).unwrap();
+        assert_eq!(completed, vec![done]);
+    }
+
+    #[test]
+    fn test_stale_running_jobs_are_interrupted() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        insert(&conn, &job("a", JobStatus::Running, 1)).unwrap();
+        insert(&conn, &job("b", JobStatus::Cancelled, 2)).unwrap();
+        assert_eq!(interrupt_stale(&conn, 5).unwrap(), 1);
+        let interrupted = query(&conn, Some(JobStatus::Interrupted), 10).unwrap();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].id, "a");
+        assert_eq!(interrupted[0].finished_at, Some(5));
+    }
+}
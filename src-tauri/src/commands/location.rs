@@ -0,0 +1,418 @@
+//! Coarse location context, off unless the user turns it on.
+//!
+//! With [`LocationSettings::enabled`], backend completions get a line in
+//! their system prompt naming the town the user is in or near, and the
+//! weather tool (see [`weather`](crate::commands::weather)) defaults to it;
+//! the frontend AI client adds the same line from `get_location_context`.
+//!
+//! The location is the place set in the settings, looked up with the
+//! Open-Meteo geocoder, or else approximated from the public IP address.
+//! Coordinates are rounded to a tenth of a degree (about 10 km) before
+//! they are kept or used, and the result is cached in memory for
+//! [`CACHE_TTL`].
+
+use std::borrow::Cow;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::ChatRequest;
+use crate::commands::datetime;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the location preferences.
+pub const SETTINGS_KEY: &str = "location";
+
+/// How long a looked-up location is reused.
+pub const CACHE_TTL: Duration = Duration::from_hours(6);
+
+/// Place search of the Open-Meteo geocoder.
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+
+/// Approximate location of the caller's IP address.
+const IP_LOOKUP_URL: &str = "https://ipapi.co/json/";
+
+/// Time allowed for a lookup; prompts wait on it the first time.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Location preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocationSettings {
+    /// Share the approximate location with models and the weather tool
+    pub enabled: bool,
+    /// Town or city to use; approximated from the IP address when `None`
+    pub place: Option<String>,
+}
+
+/// Where a location came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum LocationSource {
+    /// The place set in the settings
+    Manual,
+    /// The public IP address
+    Ip,
+    /// A place named in a request
+    Named,
+}
+
+/// A coarse location.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    /// Town or city
+    pub name: String,
+    /// State or region
+    pub region: Option<String>,
+    /// Country name
+    pub country: Option<String>,
+    /// Latitude, rounded to 0.1°
+    pub latitude: f64,
+    /// Longitude, rounded to 0.1°
+    pub longitude: f64,
+    /// Where the location came from
+    pub source: LocationSource,
+}
+
+impl Location {
+    /// The name with its region and country, e.g. `Lyon, Auvergne-Rhône-Alpes, France`.
+    pub fn label(&self) -> String {
+        let mut parts = vec![self.name.as_str()];
+        for part in [&self.region, &self.country].into_iter().flatten() {
+            if !parts.contains(&part.as_str()) {
+                parts.push(part);
+            }
+        }
+        parts.join(", ")
+    }
+}
+
+/// What `get_location_context` returns.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationContext {
+    /// The location told to models
+    pub location: Location,
+    /// The line added to the system prompt
+    pub prompt: String,
+}
+
+struct Cached {
+    place: Option<String>,
+    location: Location,
+    at: Instant,
+}
+
+/// The last looked-up location.
+#[derive(Default)]
+pub struct LocationCache(Mutex<Option<Cached>>);
+
+impl LocationCache {
+    fn lock(&self) -> MutexGuard<'_, Option<Cached>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn get(&self, place: Option<&str>) -> Option<Location> {
+        self.lock()
+            .as_ref()
+            .filter(|cached| cached.place.as_deref() == place && cached.at.elapsed() < CACHE_TTL)
+            .map(|cached| cached.location.clone())
+    }
+}
+
+/// Loads the location preferences, falling back to defaults.
+///
+/// # Errors
+///
+/// Returns an error if the settings query fails.
+pub fn load_settings(conn: &Connection) -> rusqlite::Result<LocationSettings> {
+    Ok(db::read_setting(conn, SETTINGS_KEY)?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+/// Stores the location preferences.
+///
+/// # Errors
+///
+/// Returns an error if the settings cannot be stored.
+pub fn save_settings(conn: &Connection, settings: &LocationSettings) -> rusqlite::Result<()> {
+    let value = serde_json::to_string(settings).unwrap_or_default();
+    db::write_setting(conn, SETTINGS_KEY, &value)
+}
+
+/// Rounds a coordinate to a tenth of a degree.
+fn coarse(degrees: f64) -> f64 {
+    (degrees * 10.0).round() / 10.0
+}
+
+fn text(value: &Value, key: &str) -> Option<String> {
+    value[key]
+        .as_str()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+/// Reads the first result of a geocoder response.
+fn parse_geocoding(body: &Value, source: LocationSource) -> Option<Location> {
+    let place = body["results"].get(0)?;
+    Some(Location {
+        name: text(place, "name")?,
+        region: text(place, "admin1"),
+        country: text(place, "country"),
+        latitude: coarse(place["latitude"].as_f64()?),
+        longitude: coarse(place["longitude"].as_f64()?),
+        source,
+    })
+}
+
+/// Reads an IP lookup response.
+fn parse_ip_lookup(body: &Value) -> Option<Location> {
+    if body["error"].as_bool() == Some(true) {
+        return None;
+    }
+    Some(Location {
+        name: text(body, "city")?,
+        region: text(body, "region"),
+        country: text(body, "country_name"),
+        latitude: coarse(body["latitude"].as_f64()?),
+        longitude: coarse(body["longitude"].as_f64()?),
+        source: LocationSource::Ip,
+    })
+}
+
+async fn get_json(
+    app: &AppHandle,
+    purpose: &'static str,
+    builder: reqwest::RequestBuilder,
+) -> Result<Value, GibberError> {
+    let response = network_activity::send(
+        app,
+        Service::Weather,
+        purpose,
+        builder.timeout(REQUEST_TIMEOUT),
+    )
+    .await?;
+    if !response.status().is_success() {
+        return Err(GibberError::new(
+            "NETWORK",
+            format!("The location service returned HTTP {}", response.status()),
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+/// Looks up a place by name.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if no place has the
+/// name, or a network error if the geocoder can't be reached.
+pub(crate) async fn geocode(
+    app: &AppHandle,
+    name: &str,
+    source: LocationSource,
+) -> Result<Location, GibberError> {
+    let client = network::client(app, Service::Weather)?;
+    let builder =
+        client
+            .get(GEOCODING_URL)
+            .query(&[("name", name), ("count", "1"), ("format", "json")]);
+    let body = get_json(app, "location.geocode", builder).await?;
+    parse_geocoding(&body, source)
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("No place called \"{name}\"")))
+}
+
+async fn lookup_ip(app: &AppHandle) -> Result<Location, GibberError> {
+    let client = network::client(app, Service::Weather)?;
+    let body = get_json(app, "location.ip", client.get(IP_LOOKUP_URL)).await?;
+    parse_ip_lookup(&body)
+        .ok_or_else(|| GibberError::new("NOT_FOUND", "The IP address couldn't be located"))
+}
+
+/// Returns the user's location, or `None` if location context is off.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings can't be read or the lookup
+/// fails.
+pub(crate) async fn current(app: &AppHandle) -> Result<Option<Location>, GibberError> {
+    let settings = load_settings(&app.state::<Database>().conn())?;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let place = settings
+        .place
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+    let cache = app.state::<LocationCache>();
+    if let Some(location) = cache.get(place) {
+        return Ok(Some(location));
+    }
+    let location = match place {
+        Some(place) => geocode(app, place, LocationSource::Manual).await?,
+        None => lookup_ip(app).await?,
+    };
+    *cache.lock() = Some(Cached {
+        place: place.map(str::to_string),
+        location: location.clone(),
+        at: Instant::now(),
+    });
+    Ok(Some(location))
+}
+
+fn prompt(location: &Location) -> String {
+    format!("The user is in or near {}.", location.label())
+}
+
+/// Returns `request` with the user's location added to its system prompt,
+/// or unchanged if location context is off or the location is unknown.
+pub(crate) async fn with_context<'a>(
+    app: &AppHandle,
+    mut request: Cow<'a, ChatRequest>,
+) -> Cow<'a, ChatRequest> {
+    match current(app).await {
+        Ok(Some(location)) => {
+            datetime::add_to_system_prompt(&mut request.to_mut().messages, &prompt(&location));
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("location not added to the prompt: {e}"),
+    }
+    request
+}
+
+/// Returns the location preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be read.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_location_settings(db: State<'_, Database>) -> Result<LocationSettings, GibberError> {
+    Ok(load_settings(&db.conn())?)
+}
+
+/// Replaces the location preferences and forgets the cached location.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_location_settings", { settings: { enabled: true, place: "Lyon" } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_location_settings(
+    db: State<'_, Database>,
+    cache: State<'_, LocationCache>,
+    settings: LocationSettings,
+) -> Result<LocationSettings, GibberError> {
+    let settings = LocationSettings {
+        place: settings
+            .place
+            .map(|place| place.trim().to_string())
+            .filter(|place| !place.is_empty()),
+        ..settings
+    };
+    save_settings(&db.conn(), &settings)?;
+    *cache.lock() = None;
+    Ok(settings)
+}
+
+/// Returns the location line for system prompts, or `None` if location
+/// context is off.
+///
+/// The first call after a settings change looks the location up.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the place or IP
+/// address can't be located, or a network error.
+///
+/// # Example
+///
+/// ```typescript
+/// const context = await invoke("get_location_context");
+/// if (context) system = `${system}\n\n${context.prompt}`;
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn get_location_context(app: AppHandle) -> Result<Option<LocationContext>, GibberError> {
+    Ok(current(&app).await?.map(|location| LocationContext {
+        prompt: prompt(&location),
+        location,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lookups_rounds_coordinates() {
+        let body = serde_json::json!({
+            "results": [{
+                "name": "Lyon",
+                "latitude": 45.748_46,
+                "longitude": 4.846_71,
+                "admin1": "Auvergne-Rhône-Alpes",
+                "country": "France"
+            }]
+        });
+        let lyon = parse_geocoding(&body, LocationSource::Manual).unwrap();
+        assert!((lyon.latitude - 45.7).abs() < 1e-9);
+        assert!((lyon.longitude - 4.8).abs() < 1e-9);
+        assert_eq!(
+            prompt(&lyon),
+            "The user is in or near Lyon, Auvergne-Rhône-Alpes, France."
+        );
+        assert!(parse_geocoding(&serde_json::json!({}), LocationSource::Manual).is_none());
+
+        let body = serde_json::json!({
+            "city": "Singapore",
+            "region": "Singapore",
+            "country_name": "Singapore",
+            "latitude": 1.289_87,
+            "longitude": 103.850_07
+        });
+        assert_eq!(parse_ip_lookup(&body).unwrap().label(), "Singapore");
+        assert!(parse_ip_lookup(&serde_json::json!({ "error": true })).is_none());
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let db = Database::open_in_memory().expect("Should open");
+        assert!(!load_settings(&db.conn()).unwrap().enabled);
+
+        let cache = LocationCache::default();
+        *cache.lock() = Some(Cached {
+            place: Some("Lyon".to_string()),
+            location: Location {
+                name: "Lyon".to_string(),
+                region: None,
+                country: None,
+                latitude: 45.7,
+                longitude: 4.8,
+                source: LocationSource::Manual,
+            },
+            at: Instant::now(),
+        });
+        assert!(cache.get(Some("Lyon")).is_some());
+        assert!(cache.get(None).is_none());
+    }
+}
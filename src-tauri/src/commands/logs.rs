@@ -0,0 +1,376 @@
+//! Structured logging and the log viewer.
+//!
+//! Everything logged through `tracing` is written as JSON lines to a daily
+//! file in the app log directory; the last [`MAX_LOG_FILES`] days are kept.
+//! In debug builds the same events are also printed to stderr. The level
+//! defaults to `info` and can be overridden with the `GIBBER_LOG` variable
+//! using `tracing` filter syntax (e.g. `GIBBER_LOG=debug`).
+//!
+//! `query_logs` lets the frontend show recent entries, and `open_log_folder`
+//! reveals the files so users can send them along with a support request.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::error::GibberError;
+
+/// Prefix of log file names; the date is appended.
+const LOG_FILE_PREFIX: &str = "gibber-ai";
+
+/// Extension of log file names.
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Number of daily log files kept before the oldest is deleted.
+const MAX_LOG_FILES: usize = 7;
+
+/// Environment variable overriding the log filter.
+const FILTER_ENV: &str = "GIBBER_LOG";
+
+/// Entries returned by `query_logs` unless a limit is given.
+const DEFAULT_QUERY_LIMIT: usize = 200;
+
+/// Most entries `query_logs` returns at once.
+const MAX_QUERY_LIMIT: usize = 5000;
+
+/// Managed state holding the log directory and the background writer.
+///
+/// Dropping the guard flushes pending entries, so it lives as long as the
+/// app does.
+pub struct LogState {
+    dir: PathBuf,
+    _guard: WorkerGuard,
+}
+
+impl LogState {
+    /// Directory holding the log files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// Severity of a log entry.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, specta::Type,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Very verbose diagnostics
+    Trace,
+    /// Diagnostics
+    Debug,
+    /// Normal operation
+    Info,
+    /// Something went wrong but the app carried on
+    Warn,
+    /// An operation failed
+    Error,
+}
+
+impl LogLevel {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "trace" => Some(Self::Trace),
+            "debug" => Some(Self::Debug),
+            "info" => Some(Self::Info),
+            "warn" => Some(Self::Warn),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// One log entry.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// RFC 3339 timestamp
+    pub timestamp: String,
+    /// Severity
+    pub level: LogLevel,
+    /// Rust module path that logged the entry
+    pub target: String,
+    /// Log message
+    pub message: String,
+    /// Structured fields besides the message
+    pub fields: Map<String, Value>,
+}
+
+impl LogEntry {
+    /// Parses a line written by the JSON formatter.
+    fn parse(line: &str) -> Option<Self> {
+        let Value::Object(mut entry) = serde_json::from_str(line).ok()? else {
+            return None;
+        };
+        let mut fields = match entry.remove("fields") {
+            Some(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        };
+        let message = match fields.remove("message") {
+            Some(Value::String(message)) => message,
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+        Some(Self {
+            timestamp: entry.get("timestamp")?.as_str()?.to_string(),
+            level: LogLevel::parse(entry.get("level")?.as_str()?)?,
+            target: entry
+                .get("target")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            message,
+            fields,
+        })
+    }
+
+    /// Returns `true` if the entry was logged by `module`, matched against
+    /// the segments of its target (`updater` matches
+    /// `gibber_ai_lib::commands::updater`).
+    fn is_from(&self, module: &str) -> bool {
+        self.target.split("::").any(|segment| segment == module)
+    }
+}
+
+/// Filter for `query_logs`.
+#[derive(Debug, Clone, Default, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogQuery {
+    /// Only entries at this severity or above
+    pub level: Option<LogLevel>,
+    /// Only entries from this module (e.g. `"scheduler"`)
+    pub module: Option<String>,
+    /// Only entries whose message contains this text, ignoring case
+    pub search: Option<String>,
+    /// Maximum number of entries (default 200, at most 5000)
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        self.level.is_none_or(|level| entry.level >= level)
+            && self
+                .module
+                .as_deref()
+                .is_none_or(|module| entry.is_from(module))
+            && self.search.as_deref().is_none_or(|search| {
+                entry
+                    .message
+                    .to_lowercase()
+                    .contains(&search.to_lowercase())
+            })
+    }
+}
+
+/// Installs the global subscriber writing to the app log directory.
+///
+/// Must be called before anything else logs; earlier events are lost.
+///
+/// # Errors
+///
+/// Returns an error if the log directory cannot be created or a subscriber
+/// is already installed.
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = app.path().app_log_dir()?;
+    fs::create_dir_all(&dir)?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let filter = EnvFilter::try_from_env(FILTER_ENV).unwrap_or_else(|_| EnvFilter::new("info"));
+    let file_layer = tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_writer(writer);
+    let stderr_layer = cfg!(debug_assertions)
+        .then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(stderr_layer)
+        .try_init()?;
+
+    app.manage(LogState { dir, _guard: guard });
+    tracing::info!(
+        version = %app.package_info().version,
+        os = std::env::consts::OS,
+        "Gibber AI starting"
+    );
+    Ok(())
+}
+
+/// Returns the log files in `dir`, newest first.
+fn log_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(LOG_FILE_PREFIX) && name.ends_with(LOG_FILE_SUFFIX)
+                })
+        })
+        .collect();
+    // Names end in the date, so lexical order is chronological.
+    files.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(files)
+}
+
+/// Reads the newest entries in `dir` matching `query`, newest first.
+pub(crate) fn read_entries(dir: &Path, query: &LogQuery) -> std::io::Result<Vec<LogEntry>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_QUERY_LIMIT)
+        .min(MAX_QUERY_LIMIT);
+    let mut entries = Vec::new();
+    for file in log_files(dir)? {
+        let contents = fs::read_to_string(&file)?;
+        for entry in contents.lines().rev().filter_map(LogEntry::parse) {
+            if query.matches(&entry) {
+                entries.push(entry);
+                if entries.len() >= limit {
+                    return Ok(entries);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns recent log entries, newest first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `IO` if the log files cannot be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const entries = await invoke("query_logs", {
+///   query: { level: "warn", module: "updater", limit: 50 },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn query_logs(
+    logs: State<'_, LogState>,
+    query: LogQuery,
+) -> Result<Vec<LogEntry>, GibberError> {
+    Ok(read_entries(&logs.dir, &query)?)
+}
+
+/// Opens the log folder in the system file manager.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `OPEN_FAILED` if the folder cannot be
+/// opened.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("open_log_folder");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and State by value
+pub fn open_log_folder(app: AppHandle, logs: State<'_, LogState>) -> Result<(), GibberError> {
+    app.opener()
+        .open_path(logs.dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| GibberError::new("OPEN_FAILED", e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(level: &str, target: &str, message: &str) -> String {
+        format!(
+            r#"{{"timestamp":"2026-10-14T09:00:00.000000Z","level":"{level}","fields":{{"message":"{message}","task_id":"t1"}},"target":"{target}"}}"#
+        )
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let entry = LogEntry::parse(&line(
+            "WARN",
+            "gibber_ai_lib::commands::scheduler",
+            "failed",
+        ))
+        .unwrap();
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message, "failed");
+        assert_eq!(entry.fields["task_id"], "t1");
+        assert!(entry.is_from("scheduler"));
+        assert!(!entry.is_from("sched"));
+        assert!(LogEntry::parse("not json").is_none());
+    }
+
+    #[test]
+    fn test_query_filters_level_module_and_search() {
+        let query = LogQuery {
+            level: Some(LogLevel::Warn),
+            module: Some("updater".to_string()),
+            search: Some("DOWNLOAD".to_string()),
+            limit: None,
+        };
+        let target = "gibber_ai_lib::commands::updater";
+        let matching = LogEntry::parse(&line("ERROR", target, "download failed")).unwrap();
+        assert!(query.matches(&matching));
+        let too_low = LogEntry::parse(&line("INFO", target, "download started")).unwrap();
+        assert!(!query.matches(&too_low));
+        let other_module =
+            LogEntry::parse(&line("ERROR", "gibber_ai_lib::commands::tray", "download")).unwrap();
+        assert!(!query.matches(&other_module));
+    }
+
+    #[test]
+    fn test_read_entries_newest_first_across_files() {
+        let dir = std::env::temp_dir().join(format!("gibber-logs-{}", crate::db::new_id()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = "gibber_ai_lib::commands::tray";
+        fs::write(
+            dir.join("gibber-ai.2026-10-13.log"),
+            format!(
+                "{}\n{}\n",
+                line("INFO", target, "a"),
+                line("INFO", target, "b")
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("gibber-ai.2026-10-14.log"),
+            format!("{}\n", line("INFO", target, "c")),
+        )
+        .unwrap();
+        fs::write(dir.join("other.txt"), "ignored").unwrap();
+
+        let query = LogQuery {
+            limit: Some(2),
+            ..LogQuery::default()
+        };
+        let messages: Vec<_> = read_entries(&dir, &query)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect();
+        assert_eq!(messages, vec!["c", "b"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
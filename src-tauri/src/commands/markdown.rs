@@ -0,0 +1,514 @@
+//! Markdown rendering to sanitized HTML.
+//!
+//! Model output goes through one pipeline no matter which window shows it:
+//! CommonMark with tables, footnotes, strikethrough, and task lists is
+//! rendered by pulldown-cmark, fenced code blocks are highlighted by
+//! syntect, and the result is cleaned by ammonia. Raw HTML in the source
+//! survives only as far as the sanitizer allows: no scripts, event
+//! handlers, styles, or `javascript:` links.
+//!
+//! Highlighting emits `hl-` classes rather than inline colors, so themes
+//! are plain CSS from [`get_highlight_css`] and work in light and dark mode.
+//!
+//! With [`RenderOptions::math`], `$...$` and `$$...$$` are LaTeX, rendered
+//! to MathML so the output needs no script to display formulas. A formula
+//! that doesn't convert is kept as its source in a `language-math` code
+//! span.
+//!
+//! Diagram code blocks can be swapped for pre-rendered SVGs with
+//! [`render_with_diagrams`]. The SVGs are embedded as `data:` images, so
+//! any scripts inside them never run.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use base64::Engine as _;
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::error::GibberError;
+
+/// Prefix of highlighting classes.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// Class prefixes kept by the sanitizer: highlighting, code languages,
+/// footnotes, and diagrams.
+const ALLOWED_CLASS_PREFIXES: [&str; 4] = ["hl-", "language-", "footnote-", "diagram"];
+
+/// Only `data:` URLs the sanitizer keeps, on `img` elements.
+const SVG_DATA_URL: &str = "data:image/svg+xml;base64,";
+
+/// Theme used when none is named.
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+
+/// Largest code block that is highlighted; bigger ones are shown plain.
+const MAX_HIGHLIGHT_BYTES: usize = 100_000;
+
+/// MathML elements produced for formulas.
+const MATHML_TAGS: [&str; 24] = [
+    "math",
+    "semantics",
+    "annotation",
+    "mrow",
+    "mi",
+    "mn",
+    "mo",
+    "ms",
+    "mtext",
+    "mspace",
+    "mfrac",
+    "msqrt",
+    "mroot",
+    "msub",
+    "msup",
+    "msubsup",
+    "munder",
+    "mover",
+    "munderover",
+    "mtable",
+    "mtr",
+    "mtd",
+    "mstyle",
+    "mpadded",
+];
+
+/// Presentation attributes kept on MathML elements.
+const MATHML_ATTRIBUTES: [&str; 12] = [
+    "display",
+    "mathvariant",
+    "stretchy",
+    "fence",
+    "separator",
+    "accent",
+    "accentunder",
+    "lspace",
+    "rspace",
+    "linethickness",
+    "columnalign",
+    "encoding",
+];
+
+/// Rendered SVGs by code block language and source.
+pub type Diagrams = HashMap<(String, String), Vec<u8>>;
+
+/// What to render beyond CommonMark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Highlight fenced code blocks
+    pub highlight: bool,
+    /// Render `$...$` and `$$...$$` as LaTeX math
+    pub math: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            highlight: true,
+            math: false,
+        }
+    }
+}
+
+/// Escapes text for use in HTML content or a quoted attribute.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Highlights a code block, or returns `None` for unknown languages.
+fn highlight(language: &str, code: &str) -> Option<String> {
+    if language.is_empty() || code.len() > MAX_HIGHLIGHT_BYTES {
+        return None;
+    }
+    let syntaxes = syntax_set();
+    let syntax = syntaxes.find_syntax_by_token(language)?;
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, syntaxes, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        generator
+            .parse_html_for_line_which_includes_newline(line)
+            .ok()?;
+    }
+    Some(format!(
+        "<pre class=\"hl-code\"><code class=\"language-{language}\">{}</code></pre>\n",
+        generator.finalize()
+    ))
+}
+
+/// Keeps only the attribute values the rendered markup needs.
+fn filter_attribute<'u>(element: &str, attribute: &str, value: &'u str) -> Option<Cow<'u, str>> {
+    match (element, attribute) {
+        (_, "class") => {
+            let kept: Vec<&str> = value
+                .split_whitespace()
+                .filter(|class| {
+                    ALLOWED_CLASS_PREFIXES
+                        .iter()
+                        .any(|prefix| class.starts_with(prefix))
+                })
+                .collect();
+            (!kept.is_empty()).then(|| kept.join(" ").into())
+        }
+        ("input", "type") => (value == "checkbox").then_some(value.into()),
+        (_, "src" | "href") if value.starts_with("data:") => {
+            (element == "img" && value.starts_with(SVG_DATA_URL)).then_some(value.into())
+        }
+        ("th" | "td", "style") => matches!(
+            value,
+            "text-align: left" | "text-align: center" | "text-align: right"
+        )
+        .then_some(value.into()),
+        _ => Some(value.into()),
+    }
+}
+
+fn sanitize(html: &str) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tags(&["input"])
+        .add_tag_attributes("input", &["type", "checked", "disabled"])
+        .add_tag_attributes("th", &["style"])
+        .add_tag_attributes("td", &["style"])
+        .add_generic_attributes(&["class"])
+        .add_tags(MATHML_TAGS)
+        .add_url_schemes(&["data"])
+        .attribute_filter(filter_attribute);
+    for tag in MATHML_TAGS {
+        builder.add_tag_attributes(tag, MATHML_ATTRIBUTES);
+    }
+    builder.clean(html).to_string()
+}
+
+/// Language of a fenced code block: the first word of its info string.
+fn fence_language<'a>(tag: &'a Tag<'_>) -> &'a str {
+    match tag {
+        Tag::CodeBlock(CodeBlockKind::Fenced(info)) => {
+            info.split_whitespace().next().unwrap_or_default()
+        }
+        _ => "",
+    }
+}
+
+/// Lists the fenced code blocks of a document as `(language, source)`.
+pub fn fenced_blocks(markdown: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for event in Parser::new_ext(markdown, Options::empty()) {
+        match event {
+            Event::Start(tag @ Tag::CodeBlock(CodeBlockKind::Fenced(_))) => {
+                current = Some((fence_language(&tag).to_string(), String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = &mut current {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// Emits a collected code block as its diagram if one was rendered,
+/// otherwise highlighted if wanted and its language is known.
+fn push_code_block<'a>(
+    events: &mut Vec<Event<'a>>,
+    tag: Tag<'a>,
+    code: String,
+    highlight_code: bool,
+    diagrams: &Diagrams,
+) {
+    let language = fence_language(&tag).to_string();
+    if let Some(svg) = diagrams.get(&(language.clone(), code.clone())) {
+        let data = base64::engine::general_purpose::STANDARD.encode(svg);
+        events.push(Event::Html(
+            format!(
+                "<figure class=\"diagram\"><img src=\"{SVG_DATA_URL}{data}\" alt=\"{} diagram\"></figure>\n",
+                escape(&language)
+            )
+            .into(),
+        ));
+        return;
+    }
+    let highlighted = highlight_code
+        .then(|| highlight(&language, &code))
+        .flatten();
+    match highlighted {
+        Some(html) => events.push(Event::Html(html.into())),
+        None => events.extend([
+            Event::Start(tag),
+            Event::Text(code.into()),
+            Event::End(TagEnd::CodeBlock),
+        ]),
+    }
+}
+
+/// Renders a formula to MathML, or to its escaped source if it doesn't
+/// convert.
+fn math_html(latex: &str, display: DisplayStyle) -> String {
+    latex_to_mathml(latex, display).unwrap_or_else(|e| {
+        tracing::debug!("formula kept as source: {e}");
+        format!("<code class=\"language-math\">{}</code>", escape(latex))
+    })
+}
+
+/// Renders markdown to sanitized HTML.
+pub fn render(markdown: &str, render_options: RenderOptions) -> String {
+    render_with_diagrams(markdown, render_options, &Diagrams::new())
+}
+
+/// Renders markdown to sanitized HTML, showing code blocks found in
+/// `diagrams` as their rendered SVG.
+pub fn render_with_diagrams(
+    markdown: &str,
+    render_options: RenderOptions,
+    diagrams: &Diagrams,
+) -> String {
+    let mut options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    if render_options.math {
+        options |= Options::ENABLE_MATH;
+    }
+    let mut events = Vec::new();
+    let mut code_block: Option<(Tag<'_>, String)> = None;
+    for event in Parser::new_ext(markdown, options) {
+        if let Some((_, code)) = &mut code_block {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => {
+                    if let Some((tag, code)) = code_block.take() {
+                        push_code_block(&mut events, tag, code, render_options.highlight, diagrams);
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(tag @ Tag::CodeBlock(_))
+                if render_options.highlight || !diagrams.is_empty() =>
+            {
+                code_block = Some((tag, String::new()));
+            }
+            Event::InlineMath(latex) => {
+                events.push(Event::Html(math_html(&latex, DisplayStyle::Inline).into()));
+            }
+            Event::DisplayMath(latex) => {
+                events.push(Event::Html(math_html(&latex, DisplayStyle::Block).into()));
+            }
+            event => events.push(event),
+        }
+    }
+    let mut html = String::with_capacity(markdown.len() * 3 / 2);
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    sanitize(&html)
+}
+
+/// Renders markdown, such as model output, to HTML that is safe to insert
+/// into the page.
+///
+/// Code blocks are highlighted unless `highlight` is `false`; LaTeX math is
+/// rendered when `math` is `true`.
+///
+/// # Example
+///
+/// ```typescript
+/// element.innerHTML = await invoke("render_markdown", { markdown: message.content, math: true });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn render_markdown(
+    markdown: String,
+    highlight: Option<bool>,
+    math: Option<bool>,
+) -> String {
+    render(
+        &markdown,
+        RenderOptions {
+            highlight: highlight.unwrap_or(true),
+            math: math.unwrap_or(false),
+        },
+    )
+}
+
+/// Returns the stylesheet for a highlighting theme.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNKNOWN_THEME`, listing the
+/// available themes, if there is no theme by that name.
+///
+/// # Example
+///
+/// ```typescript
+/// style.textContent = await invoke("get_highlight_css", { theme: "base16-ocean.dark" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require args by value
+pub fn get_highlight_css(theme: Option<String>) -> Result<String, GibberError> {
+    highlight_css(theme.as_deref().unwrap_or(DEFAULT_THEME))
+}
+
+/// Returns the stylesheet for a highlighting theme.
+///
+/// # Errors
+///
+/// See [`get_highlight_css`].
+pub fn highlight_css(name: &str) -> Result<String, GibberError> {
+    let themes = ThemeSet::load_defaults();
+    let Some(theme) = themes.themes.get(name) else {
+        let available: Vec<&str> = themes.themes.keys().map(String::as_str).collect();
+        return Err(GibberError::new(
+            "UNKNOWN_THEME",
+            format!("No theme named {name}; available: {}", available.join(", ")),
+        ));
+    };
+    css_for_theme_with_class_style(theme, CLASS_STYLE)
+        .map_err(|e| GibberError::new("UNKNOWN_THEME", e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_commonmark_extensions() {
+        let html = render(
+            "# Beat\n\n~~old~~ **new**\n\n- [x] kick\n\n| a | b |\n|:-:|---|\n| 1 | 2 |\n",
+            RenderOptions::default(),
+        );
+        assert!(html.contains("<h1>Beat</h1>"));
+        assert!(html.contains("<del>old</del>"));
+        assert!(html.contains("type=\"checkbox\""));
+        assert!(html.contains("<th style=\"text-align: center\">a</th>"));
+    }
+
+    #[test]
+    fn test_strips_scripts_and_handlers() {
+        let html = render(
+            "<script>alert(1)</script>\n\n<img src=x onerror=alert(1)>\n\n[x](javascript:alert(1)) [ok](https://gibber.cc)\n\n<p style=\"color:red\" class=\"evil\">hi</p>",
+            RenderOptions::default(),
+        );
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("style="));
+        assert!(!html.contains("evil"));
+        assert!(html.contains("rel=\"noopener noreferrer\""));
+    }
+
+    #[test]
+    fn test_highlights_known_languages() {
+        let html = render("```js\nconst beat = 1;\n```\n", RenderOptions::default());
+        assert!(html.contains("<code class=\"language-js\">"));
+        assert!(html.contains("hl-"));
+
+        let plain = render(
+            "```js\nconst beat = 1;\n```\n",
+            RenderOptions {
+                highlight: false,
+                math: false,
+            },
+        );
+        assert!(!plain.contains("hl-"));
+        assert!(plain.contains("const beat = 1;"));
+
+        let unknown = render("```nope\n<b>&</b>\n```\n", RenderOptions::default());
+        assert!(unknown.contains("&lt;b&gt;&amp;&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn test_renders_math_when_enabled() {
+        let math = RenderOptions {
+            highlight: true,
+            math: true,
+        };
+        let html = render("Energy $E = mc^2$\n\n$$\\frac{a}{b}$$\n", math);
+        assert!(html.contains("<math"));
+        assert!(html.contains("<mfrac>"));
+        assert!(html.contains("display=\"block\""));
+
+        let plain = render("Energy $E = mc^2$\n", RenderOptions::default());
+        assert!(!plain.contains("<math"));
+        assert!(plain.contains("$E = mc^2$"));
+
+        let broken = render("$\\frac{$\n", math);
+        assert!(!broken.contains("<math"));
+    }
+
+    #[test]
+    fn test_renders_diagrams() {
+        let source = "flowchart LR\n  A --> B\n";
+        let markdown = format!("```mermaid\n{source}```\n\n```js\nlet a;\n```\n");
+        assert_eq!(
+            fenced_blocks(&markdown),
+            vec![
+                ("mermaid".to_string(), source.to_string()),
+                ("js".to_string(), "let a;\n".to_string()),
+            ]
+        );
+
+        let mut diagrams = Diagrams::new();
+        diagrams.insert(
+            ("mermaid".to_string(), source.to_string()),
+            b"<svg/>".to_vec(),
+        );
+        let html = render_with_diagrams(&markdown, RenderOptions::default(), &diagrams);
+        assert!(html
+            .contains("<figure class=\"diagram\"><img src=\"data:image/svg+xml;base64,PHN2Zy8+\""));
+        assert!(!html.contains("flowchart"));
+        assert!(html.contains("hl-code"));
+    }
+
+    #[test]
+    fn test_filter_attribute() {
+        assert_eq!(
+            filter_attribute("span", "class", "hl-keyword evil hl-js").as_deref(),
+            Some("hl-keyword hl-js")
+        );
+        assert!(filter_attribute("span", "class", "evil").is_none());
+        assert!(filter_attribute("input", "type", "text").is_none());
+        assert!(filter_attribute("td", "style", "position: fixed").is_none());
+        assert!(filter_attribute("a", "href", "data:text/html,<script>").is_none());
+        assert!(filter_attribute("img", "src", "data:text/html;base64,PA==").is_none());
+        assert!(filter_attribute("img", "src", "data:image/svg+xml;base64,PA==").is_some());
+        assert_eq!(
+            filter_attribute("a", "href", "https://gibber.cc").as_deref(),
+            Some("https://gibber.cc")
+        );
+    }
+
+    #[test]
+    fn test_highlight_css() {
+        assert!(get_highlight_css(None).unwrap().contains(".hl-"));
+        assert_eq!(
+            get_highlight_css(Some("nope".to_string()))
+                .unwrap_err()
+                .code(),
+            "UNKNOWN_THEME"
+        );
+    }
+}
@@ -0,0 +1,573 @@
+//! Media uploads to Nostr media servers.
+//!
+//! Images and audio go to the servers in the media settings, tried in
+//! order until one takes the file:
+//!
+//! - NIP-96 servers, whose upload endpoint is read from
+//!   `/.well-known/nostr/nip96.json`, with a NIP-98 authorization
+//! - Blossom servers, with `PUT /upload` and a kind 24242 authorization
+//!
+//! Before a URL is returned, the SHA-256 the server reports for the file
+//! is checked against the file's own, so a server can't store something
+//! else in its place. The result carries a NIP-92 `imeta` tag, so notes,
+//! DMs, and shares can embed the media with its type and hash.
+//!
+//! Authorizations are signed with the user's Nostr secret key (see
+//! [`nostr::signing_key`]).
+
+use std::path::Path;
+use std::time::Duration;
+
+use k256::schnorr::SigningKey;
+use reqwest::Client;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, State, Url};
+
+use crate::commands::attachments::{self, AttachmentStore};
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::nostr;
+use crate::commands::telemetry;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key of the media preferences.
+pub const SETTINGS_KEY: &str = "media";
+
+/// Server used when the user hasn't picked any.
+pub const DEFAULT_SERVER: &str = "https://nostr.build";
+
+/// Largest file uploaded, in bytes.
+pub const MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Most servers in the settings.
+const MAX_SERVERS: usize = 5;
+
+/// Time allowed for server discovery.
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Time allowed for an upload.
+const UPLOAD_TIMEOUT: Duration = Duration::from_mins(5);
+
+/// How long a Blossom authorization stays valid, in seconds.
+const AUTH_LIFETIME_SECS: i64 = 300;
+
+/// File types that can be uploaded, by extension.
+const MEDIA_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("ogg", "audio/ogg"),
+    ("opus", "audio/opus"),
+    ("wav", "audio/wav"),
+    ("flac", "audio/flac"),
+];
+
+/// Upload protocol of a media server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaProtocol {
+    /// NIP-96 HTTP file storage
+    Nip96,
+    /// Blossom blob storage
+    Blossom,
+}
+
+/// A media server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaServer {
+    /// Base URL, e.g. `https://nostr.build`
+    pub url: String,
+    /// How files are uploaded to it
+    pub protocol: MediaProtocol,
+}
+
+/// Media preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MediaSettings {
+    /// Servers to upload to, in order of preference
+    pub servers: Vec<MediaServer>,
+}
+
+impl Default for MediaSettings {
+    fn default() -> Self {
+        Self {
+            servers: vec![MediaServer {
+                url: DEFAULT_SERVER.to_string(),
+                protocol: MediaProtocol::Nip96,
+            }],
+        }
+    }
+}
+
+impl MediaSettings {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        if self.servers.is_empty() || self.servers.len() > MAX_SERVERS {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                format!("Between one and {MAX_SERVERS} media servers are required"),
+            ));
+        }
+        for server in &self.servers {
+            server_url(&server.url)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> MediaSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// An uploaded file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedMedia {
+    /// Public URL of the file
+    pub url: String,
+    /// Hex SHA-256 of the file as uploaded
+    pub sha256: String,
+    /// MIME type
+    pub mime_type: String,
+    /// Size in bytes
+    pub size: u64,
+    /// The server that stored it
+    pub server: String,
+    /// NIP-92 `imeta` tag describing the file, for the event embedding it
+    pub imeta: Vec<String>,
+}
+
+/// A file about to be uploaded.
+struct MediaFile {
+    bytes: Vec<u8>,
+    file_name: String,
+    mime_type: String,
+    sha256: String,
+}
+
+/// Parses a media server's base URL, which must be https.
+fn server_url(url: &str) -> Result<Url, GibberError> {
+    match Url::parse(url.trim()) {
+        Ok(url) if url.scheme() == "https" && url.host_str().is_some() => Ok(url),
+        _ => Err(GibberError::new(
+            "INVALID_SETTINGS",
+            format!("Not an https media server URL: {url}"),
+        )),
+    }
+}
+
+/// MIME type of an uploadable file, from its extension.
+fn media_type(file_name: &str) -> Option<&'static str> {
+    let extension = Path::new(file_name)
+        .extension()?
+        .to_str()?
+        .to_ascii_lowercase();
+    MEDIA_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime_type)| *mime_type)
+}
+
+/// Reads the upload endpoint from a NIP-96 discovery document.
+fn nip96_endpoint(server: &Url, document: &Value) -> Option<Url> {
+    server.join(document["api_url"].as_str()?).ok()
+}
+
+/// Reads the file URL and the original file's hash (`ox`) from a NIP-96
+/// upload response.
+fn parse_nip96_response(response: &Value) -> Option<(String, Option<String>)> {
+    let tags = response["nip94_event"]["tags"].as_array()?;
+    let tag = |name: &str| {
+        tags.iter()
+            .filter_map(Value::as_array)
+            .find(|tag| tag.first().and_then(Value::as_str) == Some(name))
+            .and_then(|tag| tag.get(1))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    Some((tag("url")?, tag("ox")))
+}
+
+/// Reads the URL and hash from a Blossom blob descriptor.
+fn parse_blob_descriptor(response: &Value) -> Option<(String, String)> {
+    Some((
+        response["url"].as_str()?.to_string(),
+        response["sha256"].as_str()?.to_string(),
+    ))
+}
+
+/// Fails unless the server's hash matches the file's.
+fn verify_hash(server: &Url, reported: Option<&str>, expected: &str) -> Result<(), GibberError> {
+    match reported {
+        Some(hash) if hash.eq_ignore_ascii_case(expected) => Ok(()),
+        Some(hash) => Err(GibberError::new(
+            "HASH_MISMATCH",
+            format!("{server} stored a file with hash {hash}, not {expected}"),
+        )),
+        None => Err(GibberError::new(
+            "HASH_MISMATCH",
+            format!("{server} didn't report the hash of the stored file"),
+        )),
+    }
+}
+
+/// NIP-92 `imeta` tag of an uploaded file. Blossom serves the exact bytes
+/// (`x`); NIP-96 servers may transform them, so the hash is of the
+/// original (`ox`).
+fn imeta(url: &str, file: &MediaFile, protocol: MediaProtocol) -> Vec<String> {
+    let hash_field = match protocol {
+        MediaProtocol::Nip96 => "ox",
+        MediaProtocol::Blossom => "x",
+    };
+    vec![
+        "imeta".to_string(),
+        format!("url {url}"),
+        format!("m {}", file.mime_type),
+        format!("{hash_field} {}", file.sha256),
+        format!("size {}", file.bytes.len()),
+    ]
+}
+
+async fn upload_nip96(
+    app: &AppHandle,
+    client: &Client,
+    key: &SigningKey,
+    server: &Url,
+    file: &MediaFile,
+) -> Result<String, GibberError> {
+    let well_known = server
+        .join("/.well-known/nostr/nip96.json")
+        .map_err(|e| GibberError::new("INVALID_SETTINGS", e.to_string()))?;
+    let builder = client.get(well_known).timeout(DISCOVERY_TIMEOUT);
+    let response =
+        network_activity::send(app, Service::MediaServers, "media.discover", builder).await?;
+    let document: Value = response.json().await?;
+    let endpoint = nip96_endpoint(server, &document).ok_or_else(|| {
+        GibberError::new(
+            "UPLOAD_FAILED",
+            format!("{server} doesn't support NIP-96 uploads"),
+        )
+    })?;
+
+    let authorization = nostr::http_auth(key, endpoint.as_str(), "POST", Some(&file.sha256))?;
+    let part = reqwest::multipart::Part::bytes(file.bytes.clone())
+        .file_name(file.file_name.clone())
+        .mime_str(&file.mime_type)?;
+    let form = reqwest::multipart::Form::new()
+        .text("content_type", file.mime_type.clone())
+        .text("size", file.bytes.len().to_string())
+        .part("file", part);
+    let builder = client
+        .post(endpoint)
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .multipart(form)
+        .timeout(UPLOAD_TIMEOUT);
+    let response =
+        network_activity::send(app, Service::MediaServers, "media.upload", builder).await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    let parsed = parse_nip96_response(&body).filter(|_| status.is_success());
+    let Some((url, original_hash)) = parsed else {
+        let message = body["message"].as_str().unwrap_or("no file URL returned");
+        return Err(GibberError::new(
+            "UPLOAD_FAILED",
+            format!("{server} refused the file (HTTP {status}): {message}"),
+        ));
+    };
+    verify_hash(server, original_hash.as_deref(), &file.sha256)?;
+    Ok(url)
+}
+
+async fn upload_blossom(
+    app: &AppHandle,
+    client: &Client,
+    key: &SigningKey,
+    server: &Url,
+    file: &MediaFile,
+) -> Result<String, GibberError> {
+    let endpoint = server
+        .join("/upload")
+        .map_err(|e| GibberError::new("INVALID_SETTINGS", e.to_string()))?;
+    let expiration = chrono::Utc::now().timestamp() + AUTH_LIFETIME_SECS;
+    let authorization = nostr::blossom_auth(
+        key,
+        &file.sha256,
+        expiration,
+        &format!("Upload {}", file.file_name),
+    )?;
+    let builder = client
+        .put(endpoint)
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .header(reqwest::header::CONTENT_TYPE, &file.mime_type)
+        .header("X-SHA-256", &file.sha256)
+        .body(file.bytes.clone())
+        .timeout(UPLOAD_TIMEOUT);
+    let response =
+        network_activity::send(app, Service::MediaServers, "media.upload", builder).await?;
+    let status = response.status();
+    if !status.is_success() {
+        let reason = response
+            .headers()
+            .get("X-Reason")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("no reason given")
+            .to_string();
+        return Err(GibberError::new(
+            "UPLOAD_FAILED",
+            format!("{server} refused the file (HTTP {status}): {reason}"),
+        ));
+    }
+    let body: Value = response.json().await?;
+    let (url, hash) = parse_blob_descriptor(&body).ok_or_else(|| {
+        GibberError::new(
+            "UPLOAD_FAILED",
+            format!("{server} returned no blob descriptor"),
+        )
+    })?;
+    verify_hash(server, Some(&hash), &file.sha256)?;
+    Ok(url)
+}
+
+/// Uploads a file to the first media server that takes it.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNSUPPORTED_TYPE` for files other
+/// than images and audio, `TOO_LARGE` over 50 MB, `NO_KEY` if no Nostr
+/// secret key is stored, or the error of the last server tried, such as
+/// `UPLOAD_FAILED` or `HASH_MISMATCH`.
+pub(crate) async fn upload(
+    app: &AppHandle,
+    file_name: &str,
+    bytes: Vec<u8>,
+) -> Result<UploadedMedia, GibberError> {
+    let mime_type = media_type(file_name).ok_or_else(|| {
+        GibberError::new(
+            "UNSUPPORTED_TYPE",
+            format!("{file_name} is not an image or audio file"),
+        )
+    })?;
+    if u64::try_from(bytes.len()).unwrap_or(u64::MAX) > MAX_UPLOAD_BYTES {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            "Media files can be at most 50 MB",
+        ));
+    }
+    let file = MediaFile {
+        sha256: hex::encode(Sha256::digest(&bytes)),
+        bytes,
+        file_name: file_name.to_string(),
+        mime_type: mime_type.to_string(),
+    };
+    let settings = load_settings(&app.state::<Database>().conn());
+    let key = nostr::signing_key(app).await?;
+    let client = network::client(app, Service::MediaServers)?;
+
+    let mut last_error = None;
+    for server in &settings.servers {
+        let url = server_url(&server.url)?;
+        let uploaded = match server.protocol {
+            MediaProtocol::Nip96 => upload_nip96(app, &client, &key, &url, &file).await,
+            MediaProtocol::Blossom => upload_blossom(app, &client, &key, &url, &file).await,
+        };
+        match uploaded {
+            Ok(media_url) => {
+                telemetry::record_feature(app, "media.upload");
+                return Ok(UploadedMedia {
+                    imeta: imeta(&media_url, &file, server.protocol),
+                    url: media_url,
+                    sha256: file.sha256,
+                    mime_type: file.mime_type,
+                    size: u64::try_from(file.bytes.len()).unwrap_or(u64::MAX),
+                    server: url.to_string(),
+                });
+            }
+            Err(e) => {
+                tracing::warn!(server = %url, code = e.code(), "media upload failed: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| GibberError::new("UPLOAD_FAILED", "No media servers configured")))
+}
+
+/// Returns the media preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_media_settings(db: State<'_, Database>) -> MediaSettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the media preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` naming the bad
+/// server, or `DATABASE` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_media_settings", {
+///   settings: {
+///     servers: [
+///       { url: "https://blossom.primal.net", protocol: "blossom" },
+///       { url: "https://nostr.build", protocol: "nip96" },
+///     ],
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_media_settings(
+    db: State<'_, Database>,
+    settings: MediaSettings,
+) -> Result<MediaSettings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Uploads an image attachment and returns its URL.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment
+/// doesn't exist, or an error as [`upload_media_file`] does.
+///
+/// # Example
+///
+/// ```typescript
+/// const { url, imeta } = await invoke("upload_media", { attachmentId });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn upload_media(
+    app: AppHandle,
+    attachment_id: String,
+) -> Result<UploadedMedia, GibberError> {
+    let attachment = attachments::load_attachment(&app.state::<Database>().conn(), &attachment_id)?
+        .ok_or_else(|| {
+            GibberError::new("NOT_FOUND", format!("Attachment {attachment_id} not found"))
+        })?;
+    let path = app.state::<AttachmentStore>().file_path(&attachment);
+    let bytes = std::fs::read(path)?;
+    upload(&app, &attachment.file_name, bytes).await
+}
+
+/// Uploads an image or audio file from disk and returns its URL.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNSUPPORTED_TYPE` for other files,
+/// `TOO_LARGE` over 50 MB, `IO` if the file can't be read, `NO_KEY` if no
+/// Nostr secret key is stored, or `UPLOAD_FAILED` or `HASH_MISMATCH` if
+/// no server stored it intact.
+///
+/// # Example
+///
+/// ```typescript
+/// const { url } = await invoke("upload_media_file", { path: "/Users/me/loop.ogg" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn upload_media_file(app: AppHandle, path: String) -> Result<UploadedMedia, GibberError> {
+    let path = Path::new(&path);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if media_type(&file_name).is_none() {
+        return Err(GibberError::new(
+            "UNSUPPORTED_TYPE",
+            format!("{file_name} is not an image or audio file"),
+        ));
+    }
+    if std::fs::metadata(path)?.len() > MAX_UPLOAD_BYTES {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            "Media files can be at most 50 MB",
+        ));
+    }
+    let bytes = std::fs::read(path)?;
+    upload(&app, &file_name, bytes).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parses_server_responses() {
+        let server = Url::parse("https://media.example").unwrap();
+        let endpoint = nip96_endpoint(&server, &json!({ "api_url": "/api/v2/media" })).unwrap();
+        assert_eq!(endpoint.as_str(), "https://media.example/api/v2/media");
+        let nip96 = json!({
+            "status": "success",
+            "nip94_event": { "tags": [["ox", "abc"], ["url", "https://media.example/abc.png"]] }
+        });
+        assert_eq!(
+            parse_nip96_response(&nip96),
+            Some((
+                "https://media.example/abc.png".to_string(),
+                Some("abc".to_string())
+            ))
+        );
+        assert_eq!(parse_nip96_response(&json!({ "status": "error" })), None);
+        let blob = json!({ "url": "https://cdn.example/abc.ogg", "sha256": "abc", "size": 3 });
+        assert_eq!(
+            parse_blob_descriptor(&blob),
+            Some(("https://cdn.example/abc.ogg".to_string(), "abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_hashes_are_verified() {
+        let server = Url::parse("https://media.example").unwrap();
+        assert!(verify_hash(&server, Some("ABC"), "abc").is_ok());
+        assert_eq!(
+            verify_hash(&server, Some("def"), "abc").unwrap_err().code(),
+            "HASH_MISMATCH"
+        );
+        assert!(verify_hash(&server, None, "abc").is_err());
+
+        assert_eq!(media_type("Loop.OGG"), Some("audio/ogg"));
+        assert_eq!(media_type("notes.pdf"), None);
+        let file = MediaFile {
+            bytes: vec![1, 2, 3],
+            file_name: "a.png".to_string(),
+            mime_type: "image/png".to_string(),
+            sha256: "abc".to_string(),
+        };
+        assert_eq!(
+            imeta("https://cdn.example/abc.png", &file, MediaProtocol::Blossom),
+            vec![
+                "imeta",
+                "url https://cdn.example/abc.png",
+                "m image/png",
+                "x abc",
+                "size 3"
+            ]
+        );
+        assert!(MediaSettings::default().validate().is_ok());
+        assert!(server_url("http://media.example").is_err());
+    }
+}
@@ -3,4 +3,88 @@
 //! This module organizes all IPC commands that the frontend can invoke.
 //! Each submodule handles a specific domain of functionality.
 
+pub mod action_items;
+pub mod api_server;
+pub mod attached_db;
+pub mod attachments;
+pub mod audio_export;
+pub mod audit;
+pub mod automations;
+pub mod browser_bridge;
+pub mod calculator;
+pub mod chat_stream;
+pub mod clipboard;
+pub mod code_blocks;
+pub mod conversation_stats;
+pub mod conversations;
+pub mod crash_reports;
 pub mod credentials;
+pub mod datasets;
+pub mod datetime;
+pub mod deep_link;
+pub mod deidentify;
+pub mod diagnostics;
+pub mod diagrams;
+pub mod email;
+pub mod events;
+pub mod experiments;
+pub mod external_input;
+pub mod feedback;
+pub mod feeds;
+pub mod flashcards;
+pub mod git_assist;
+pub mod i18n;
+pub mod image_edit;
+pub mod image_metadata;
+pub mod images;
+pub mod instance;
+pub mod jobs;
+pub mod location;
+pub mod logs;
+pub mod markdown;
+pub mod media;
+pub mod model_metrics;
+pub mod network;
+pub mod network_activity;
+pub mod nostr;
+pub mod nostr_composer;
+pub mod nostr_digest;
+pub mod nostr_encryption;
+pub mod notifications;
+pub mod outbox;
+pub mod palette;
+pub mod paste;
+pub mod plugins;
+pub mod presets;
+pub mod privacy;
+pub mod prompt_tests;
+pub mod quick_capture;
+pub mod rate_limit;
+pub mod reading_list;
+pub mod response_cache;
+pub mod rewrite;
+pub mod routing_policy;
+pub mod scheduler;
+pub mod screenshot;
+pub mod session;
+pub mod settings;
+pub mod sharing;
+pub mod shortcuts;
+pub mod speech;
+pub mod spend;
+pub mod spreadsheets;
+pub mod stable_diffusion;
+pub mod startup;
+pub mod stream_recovery;
+pub mod telemetry;
+pub mod text_diff;
+pub mod transform;
+pub mod translation;
+pub mod tray;
+pub mod updater;
+pub mod usage;
+pub mod weather;
+pub mod webhooks;
+pub mod windows;
+pub mod workflows;
+pub mod ws_bridge;
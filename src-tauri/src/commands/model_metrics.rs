@@ -0,0 +1,381 @@
+//! Latency and reliability metrics per model.
+//!
+//! Each completion leaves a sample in `model_metrics`: how long the first
+//! token took (streamed requests only), how long the whole request took,
+//! how many tokens came back, and the error code if it failed. Backend
+//! completions are sampled in [`chat`](crate::chat); the frontend AI client
+//! reports its streamed requests with `record_model_metric`. Samples older
+//! than [`RETENTION_DAYS`] are dropped.
+//!
+//! `get_model_metrics` summarizes the samples per model and provider, and
+//! `rank_models` orders failover candidates from healthiest to least
+//! healthy.
+
+use std::time::Duration;
+
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Days of samples kept.
+pub const RETENTION_DAYS: i64 = 30;
+
+/// Default window of `get_model_metrics`, in days.
+const DEFAULT_WINDOW_DAYS: u32 = 7;
+
+/// Samples a model needs before its error rate counts for ranking.
+const MIN_RANKED_SAMPLES: u32 = 5;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Error codes that say something about the route rather than the caller.
+const ROUTE_ERRORS: &[&str] = &[
+    "NETWORK_ERROR",
+    "RATE_LIMITED",
+    "MODEL_UNAVAILABLE",
+    "PARSE_ERROR",
+    "UNKNOWN",
+];
+
+/// One completion's measurements.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    /// Model requested
+    pub model: String,
+    /// Upstream provider that served it, when known
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Time to the first streamed token
+    #[serde(default)]
+    pub ttft_ms: Option<u64>,
+    /// Time until the response was complete or failed
+    pub duration_ms: u64,
+    /// Tokens generated
+    #[serde(default)]
+    pub completion_tokens: Option<u32>,
+    /// Error code if the request failed
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Sample {
+    /// Generation speed, timed from the first token when known.
+    fn tokens_per_second(&self) -> Option<f64> {
+        let tokens = self.completion_tokens.filter(|tokens| *tokens > 0)?;
+        let generating = self.duration_ms.saturating_sub(self.ttft_ms.unwrap_or(0));
+        #[allow(clippy::cast_precision_loss)] // durations are far below 2^52 ms
+        let seconds = generating as f64 / 1000.0;
+        (seconds > 0.0).then(|| f64::from(tokens) / seconds)
+    }
+}
+
+/// Summary of a model's samples through one provider.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelMetrics {
+    /// Model ID
+    pub model: String,
+    /// Upstream provider, if reported
+    pub provider: Option<String>,
+    /// Requests sampled
+    pub requests: u32,
+    /// Requests that failed because of the route
+    pub errors: u32,
+    /// `errors / requests`
+    pub error_rate: f64,
+    /// Mean time to first token of streamed requests
+    pub avg_ttft_ms: Option<f64>,
+    /// Mean request duration
+    pub avg_duration_ms: f64,
+    /// Mean generation speed
+    pub avg_tokens_per_second: Option<f64>,
+    /// Time of the last sample in Unix milliseconds
+    pub last_seen_at: i64,
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Builds a sample of a backend completion.
+pub(crate) fn sample(
+    model: &str,
+    provider: Option<&str>,
+    elapsed: Duration,
+    completion_tokens: Option<u32>,
+    error: Option<&str>,
+) -> Sample {
+    Sample {
+        model: model.to_string(),
+        provider: provider.map(str::to_string),
+        ttft_ms: None,
+        duration_ms: millis(elapsed),
+        completion_tokens,
+        error: error.map(str::to_string),
+    }
+}
+
+/// Adds a sample and drops expired ones.
+///
+/// # Errors
+///
+/// Returns an error if the insert fails.
+pub(crate) fn insert(conn: &Connection, sample: &Sample) -> rusqlite::Result<()> {
+    let now = db::now_millis();
+    let clamp = |ms: u64| i64::try_from(ms).unwrap_or(i64::MAX);
+    conn.execute(
+        "INSERT INTO model_metrics
+            (model, provider, ttft_ms, duration_ms, completion_tokens, tokens_per_second,
+             error, route_error, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            sample.model,
+            sample.provider,
+            sample.ttft_ms.map(clamp),
+            clamp(sample.duration_ms),
+            sample.completion_tokens,
+            sample.tokens_per_second(),
+            sample.error,
+            sample
+                .error
+                .as_deref()
+                .is_some_and(|code| ROUTE_ERRORS.contains(&code)),
+            now,
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM model_metrics WHERE created_at < ?1",
+        [now - RETENTION_DAYS * DAY_MS],
+    )?;
+    Ok(())
+}
+
+/// Adds a sample, logging rather than failing.
+pub(crate) fn record(app: &AppHandle, sample: &Sample) {
+    let Some(db) = app.try_state::<Database>() else {
+        return;
+    };
+    let conn = db.conn();
+    if let Err(e) = insert(&conn, sample) {
+        tracing::error!(model = %sample.model, "failed to record model metrics: {e}");
+    }
+}
+
+fn metrics_from_row(row: &Row<'_>) -> rusqlite::Result<ModelMetrics> {
+    let requests: u32 = row.get(2)?;
+    let errors: u32 = row.get(3)?;
+    Ok(ModelMetrics {
+        model: row.get(0)?,
+        provider: row.get(1)?,
+        requests,
+        errors,
+        error_rate: f64::from(errors) / f64::from(requests.max(1)),
+        avg_ttft_ms: row.get(4)?,
+        avg_duration_ms: row.get(5)?,
+        avg_tokens_per_second: row.get(6)?,
+        last_seen_at: row.get(7)?,
+    })
+}
+
+fn summarize(
+    conn: &Connection,
+    since: i64,
+    model: Option<&str>,
+) -> rusqlite::Result<Vec<ModelMetrics>> {
+    let mut stmt = conn.prepare(
+        "SELECT model, provider, COUNT(*), SUM(route_error), AVG(ttft_ms),
+                AVG(duration_ms), AVG(tokens_per_second), MAX(created_at)
+         FROM model_metrics
+         WHERE created_at >= ?1 AND (?2 IS NULL OR model = ?2)
+         GROUP BY model, provider
+         ORDER BY model, COUNT(*) DESC",
+    )?;
+    let metrics = stmt
+        .query_map(params![since, model], metrics_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(metrics)
+}
+
+/// Orders `candidates` healthiest first: lowest error rate, then fastest
+/// first token, then fastest overall. Models with too few samples keep
+/// their place after the ranked ones.
+fn rank(candidates: &[String], metrics: &[ModelMetrics]) -> Vec<String> {
+    let health = |model: &str| {
+        let samples: Vec<_> = metrics.iter().filter(|m| m.model == model).collect();
+        let requests: u32 = samples.iter().map(|m| m.requests).sum();
+        if requests < MIN_RANKED_SAMPLES {
+            return None;
+        }
+        let errors: u32 = samples.iter().map(|m| m.errors).sum();
+        let weighted = |value: fn(&ModelMetrics) -> f64| {
+            samples
+                .iter()
+                .map(|m| value(m) * f64::from(m.requests))
+                .sum::<f64>()
+                / f64::from(requests)
+        };
+        Some((
+            f64::from(errors) / f64::from(requests),
+            weighted(|m| m.avg_ttft_ms.unwrap_or(m.avg_duration_ms)),
+            weighted(|m| m.avg_duration_ms),
+        ))
+    };
+    let mut ranked: Vec<_> = candidates
+        .iter()
+        .enumerate()
+        .map(|(index, model)| (health(model), index, model.clone()))
+        .collect();
+    ranked.sort_by(|(a, a_index, _), (b, b_index, _)| match (a, b) {
+        (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a_index.cmp(b_index),
+    });
+    ranked.into_iter().map(|(_, _, model)| model).collect()
+}
+
+/// Summarizes the samples of the last `days` days (default: 7), per model
+/// and provider.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const metrics = await invoke("get_model_metrics", { days: 30 });
+/// const flaky = metrics.filter((m) => m.requests >= 10 && m.errorRate > 0.1);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn get_model_metrics(
+    db: State<'_, Database>,
+    days: Option<u32>,
+    model: Option<String>,
+) -> Result<Vec<ModelMetrics>, GibberError> {
+    let days = i64::from(days.unwrap_or(DEFAULT_WINDOW_DAYS).max(1));
+    let since = db::now_millis() - days * DAY_MS;
+    Ok(summarize(&db.conn(), since, model.as_deref())?)
+}
+
+/// Adds a sample of a completion the frontend made.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if the model is
+/// missing, or `DATABASE` if the sample cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("record_model_metric", {
+///   sample: { model, provider: chunk.provider, ttftMs, durationMs, completionTokens },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn record_model_metric(db: State<'_, Database>, sample: Sample) -> Result<(), GibberError> {
+    if sample.model.trim().is_empty() {
+        return Err(GibberError::new("INVALID_INPUT", "A model is required"));
+    }
+    Ok(insert(&db.conn(), &sample)?)
+}
+
+/// Orders failover candidates by their health over the last week.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const [first, ...fallbacks] = await invoke("rank_models", {
+///   models: ["anthropic/claude-sonnet-4", "openai/gpt-4o", "google/gemini-2.5-pro"],
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn rank_models(
+    db: State<'_, Database>,
+    models: Vec<String>,
+) -> Result<Vec<String>, GibberError> {
+    let since = db::now_millis() - i64::from(DEFAULT_WINDOW_DAYS) * DAY_MS;
+    let metrics = summarize(&db.conn(), since, None)?;
+    Ok(rank(&models, &metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(model: &str, duration_ms: u64, error: Option<&str>) -> Sample {
+        Sample {
+            model: model.to_string(),
+            provider: Some("Anthropic".to_string()),
+            ttft_ms: Some(500),
+            duration_ms,
+            completion_tokens: Some(100),
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_tokens_per_second_excludes_first_token_wait() {
+        assert_eq!(sample("a", 2_500, None).tokens_per_second(), Some(50.0));
+        let unstreamed = Sample {
+            ttft_ms: None,
+            ..sample("a", 2_000, None)
+        };
+        assert_eq!(unstreamed.tokens_per_second(), Some(50.0));
+    }
+
+    #[test]
+    fn test_summary_counts_route_errors_only() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        insert(&conn, &sample("a", 1_000, None)).unwrap();
+        insert(&conn, &sample("a", 3_000, Some("MODEL_UNAVAILABLE"))).unwrap();
+        insert(&conn, &sample("a", 2_000, Some("CONTEXT_TOO_LONG"))).unwrap();
+
+        let metrics = summarize(&conn, 0, Some("a")).unwrap();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!((metrics[0].requests, metrics[0].errors), (3, 1));
+        assert!((metrics[0].avg_duration_ms - 2_000.0).abs() < 1e-9);
+        assert_eq!(metrics[0].avg_ttft_ms, Some(500.0));
+    }
+
+    #[test]
+    fn test_rank_prefers_healthy_models() {
+        let metric = |model: &str, requests, errors, ttft| ModelMetrics {
+            model: model.to_string(),
+            provider: None,
+            requests,
+            errors,
+            error_rate: 0.0,
+            avg_ttft_ms: Some(ttft),
+            avg_duration_ms: 1_000.0,
+            avg_tokens_per_second: None,
+            last_seen_at: 0,
+        };
+        let metrics = [
+            metric("flaky", 10, 4, 200.0),
+            metric("slow", 10, 0, 900.0),
+            metric("fast", 10, 0, 300.0),
+            metric("new", 2, 0, 100.0),
+        ];
+        let candidates = ["new", "flaky", "unknown", "slow", "fast"].map(String::from);
+        assert_eq!(
+            rank(&candidates, &metrics),
+            ["fast", "slow", "flaky", "new", "unknown"]
+        );
+    }
+}
@@ -0,0 +1,436 @@
+//! Proxy and TLS configuration for outbound requests.
+//!
+//! Every backend HTTP call gets its client from [`client`], which applies
+//! the stored [`NetworkSettings`]: a proxy (the system one by default,
+//! direct, or a manual HTTP/HTTPS/SOCKS5 URL), per-service overrides, and
+//! extra CA certificates for networks that intercept TLS. Clients are built
+//! once per service and rebuilt when the settings change.
+//!
+//! The updater plugin makes its own requests and only honours manual
+//! proxies; it doesn't see the extra CA certificates.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use reqwest::redirect::Policy;
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Url};
+
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the network preferences.
+pub const SETTINGS_KEY: &str = "network";
+
+/// Time allowed to establish a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `User-Agent` sent with every request.
+const USER_AGENT: &str = concat!("GibberAI/", env!("CARGO_PKG_VERSION"));
+
+/// Proxy URL schemes reqwest can route through.
+const PROXY_SCHEMES: [&str; 4] = ["http", "https", "socks5", "socks5h"];
+
+/// Something the backend talks to, with its own client.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, specta::Type,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum Service {
+    /// OpenRouter chat completions
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+    /// Update checks and downloads
+    Updater,
+    /// Nostr relays and media servers
+    Nostr,
+    /// Telemetry batches
+    Telemetry,
+    /// Crash report uploads
+    CrashReports,
+    /// Diagnostics connectivity checks
+    Diagnostics,
+    /// Webhook deliveries
+    Webhooks,
+    /// Requests made by plugins
+    Plugins,
+    /// Diagram rendering for exports
+    Diagrams,
+    /// Encrypted conversation uploads
+    Sharing,
+    /// Pages fetched for the reading list
+    ReadingList,
+    /// RSS and Atom feed polling
+    Feeds,
+    /// Image generation providers other than OpenRouter
+    Images,
+    /// Local Stable Diffusion server
+    StableDiffusion,
+    /// Currency exchange rates for the calculator
+    ExchangeRates,
+    /// Weather forecasts, place lookups, and IP geolocation
+    Weather,
+    /// NIP-96 and Blossom media servers for image and audio uploads
+    MediaServers,
+    /// Text-to-speech for audio exports
+    Speech,
+}
+
+/// How to reach the network.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum ProxyConfig {
+    /// Use the OS proxy settings and `HTTP(S)_PROXY` variables
+    #[default]
+    System,
+    /// Connect directly, ignoring any system proxy
+    Direct,
+    /// Route through a proxy URL
+    #[serde(rename_all = "camelCase")]
+    Manual {
+        /// `http://`, `https://`, `socks5://` or `socks5h://` URL, optionally
+        /// with `user:password@`
+        url: String,
+        /// Comma-separated hosts to reach directly (e.g. `localhost,.corp`)
+        #[serde(default)]
+        no_proxy: Option<String>,
+    },
+}
+
+impl ProxyConfig {
+    fn validate(&self) -> Result<(), GibberError> {
+        let Self::Manual { url, .. } = self else {
+            return Ok(());
+        };
+        let parsed = Url::parse(url)
+            .map_err(|e| GibberError::new("INVALID_PROXY", format!("{url}: {e}")))?;
+        if !PROXY_SCHEMES.contains(&parsed.scheme()) {
+            return Err(GibberError::new(
+                "INVALID_PROXY",
+                format!("Unsupported proxy scheme \"{}\"", parsed.scheme()),
+            ));
+        }
+        if parsed.host_str().is_none() {
+            return Err(GibberError::new("INVALID_PROXY", "Proxy URL has no host"));
+        }
+        Ok(())
+    }
+}
+
+/// Network preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkSettings {
+    /// Proxy for every service without an override
+    pub proxy: ProxyConfig,
+    /// Per-service proxies
+    pub overrides: BTreeMap<Service, ProxyConfig>,
+    /// Paths of PEM files with extra trusted CA certificates
+    pub ca_certificates: Vec<String>,
+}
+
+impl NetworkSettings {
+    /// Returns the proxy that applies to `service`.
+    pub fn proxy_for(&self, service: Service) -> &ProxyConfig {
+        self.overrides.get(&service).unwrap_or(&self.proxy)
+    }
+
+    /// Checks the proxies and loads the CA certificates.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GibberError` with code `INVALID_PROXY` or
+    /// `INVALID_CERTIFICATE` naming the offending entry.
+    pub fn validate(&self) -> Result<Vec<Certificate>, GibberError> {
+        self.proxy.validate()?;
+        for proxy in self.overrides.values() {
+            proxy.validate()?;
+        }
+        self.ca_certificates
+            .iter()
+            .map(|path| load_certificate(path))
+            .collect()
+    }
+}
+
+fn load_certificate(path: &str) -> Result<Certificate, GibberError> {
+    let invalid =
+        |e: &dyn std::fmt::Display| GibberError::new("INVALID_CERTIFICATE", format!("{path}: {e}"));
+    let pem = std::fs::read(path).map_err(|e| invalid(&e))?;
+    Certificate::from_pem(&pem).map_err(|e| invalid(&e))
+}
+
+fn build_client(
+    proxy: &ProxyConfig,
+    certificates: &[Certificate],
+    redirect: Policy,
+) -> Result<Client, GibberError> {
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .redirect(redirect);
+    match proxy {
+        ProxyConfig::System => {}
+        ProxyConfig::Direct => builder = builder.no_proxy(),
+        ProxyConfig::Manual { url, no_proxy } => {
+            let proxy = Proxy::all(url.as_str())?
+                .no_proxy(no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+    }
+    for certificate in certificates {
+        builder = builder.add_root_certificate(certificate.clone());
+    }
+    Ok(builder.build()?)
+}
+
+struct ClientsInner {
+    settings: NetworkSettings,
+    certificates: Vec<Certificate>,
+    clients: HashMap<Service, Client>,
+}
+
+/// Managed cache of configured clients.
+pub struct HttpClients(Mutex<ClientsInner>);
+
+impl HttpClients {
+    fn lock(&self) -> MutexGuard<'_, ClientsInner> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn replace(&self, settings: NetworkSettings, certificates: Vec<Certificate>) {
+        let mut inner = self.lock();
+        inner.settings = settings;
+        inner.certificates = certificates;
+        inner.clients.clear();
+    }
+}
+
+fn load_settings(conn: &rusqlite::Connection) -> NetworkSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Loads the stored settings and manages the client cache.
+///
+/// A CA file that can no longer be read is logged and skipped. Proxies are
+/// kept even if invalid, so requests fail rather than silently bypass the
+/// proxy. Must be called after the [`Database`] has been added to managed
+/// state.
+pub fn init(app: &AppHandle) {
+    let settings = load_settings(&app.state::<Database>().conn());
+    let certificates = settings
+        .ca_certificates
+        .iter()
+        .filter_map(|path| match load_certificate(path) {
+            Ok(certificate) => Some(certificate),
+            Err(e) => {
+                tracing::error!("skipping CA certificate: {e}");
+                None
+            }
+        })
+        .collect();
+    app.manage(HttpClients(Mutex::new(ClientsInner {
+        settings,
+        certificates,
+        clients: HashMap::new(),
+    })));
+}
+
+/// Returns the HTTP client configured for `service`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `CLIENT` if the client cannot be
+/// built (e.g. the TLS backend rejects a certificate).
+pub fn client(app: &AppHandle, service: Service) -> Result<Client, GibberError> {
+    let clients = app.state::<HttpClients>();
+    let mut inner = clients.lock();
+    if let Some(client) = inner.clients.get(&service) {
+        return Ok(client.clone());
+    }
+    let client = build_client(
+        inner.settings.proxy_for(service),
+        &inner.certificates,
+        Policy::default(),
+    )?;
+    inner.clients.insert(service, client.clone());
+    Ok(client)
+}
+
+/// Builds a client for `service` that follows redirects only as `redirect`
+/// allows. These clients aren't cached, since each caller brings its own
+/// policy.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `CLIENT` if the client cannot be
+/// built.
+pub fn client_with_redirects(
+    app: &AppHandle,
+    service: Service,
+    redirect: Policy,
+) -> Result<Client, GibberError> {
+    let clients = app.state::<HttpClients>();
+    let inner = clients.lock();
+    build_client(
+        inner.settings.proxy_for(service),
+        &inner.certificates,
+        redirect,
+    )
+}
+
+/// Builds a client for `service` from the stored settings, for code running
+/// without an app handle such as the CLI.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the stored settings are invalid or the client
+/// cannot be built.
+#[cfg(feature = "cli")]
+pub(crate) fn standalone_client(
+    conn: &rusqlite::Connection,
+    service: Service,
+) -> Result<Client, GibberError> {
+    let settings = load_settings(conn);
+    let certificates = settings.validate()?;
+    build_client(
+        settings.proxy_for(service),
+        &certificates,
+        Policy::default(),
+    )
+}
+
+/// Returns the manual proxy for `service`, if one applies.
+///
+/// For connections that can't use [`client`], like the updater plugin's
+/// requests and WebSocket relays.
+pub fn manual_proxy(app: &AppHandle, service: Service) -> Option<Url> {
+    let clients = app.state::<HttpClients>();
+    let inner = clients.lock();
+    match inner.settings.proxy_for(service) {
+        ProxyConfig::Manual { url, .. } => Url::parse(url).ok(),
+        ProxyConfig::System | ProxyConfig::Direct => None,
+    }
+}
+
+/// Returns the manual proxy for the updater plugin, if one applies.
+pub fn updater_proxy(app: &AppHandle) -> Option<Url> {
+    manual_proxy(app, Service::Updater)
+}
+
+/// Returns the network preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_network_settings(clients: State<'_, HttpClients>) -> NetworkSettings {
+    clients.lock().settings.clone()
+}
+
+/// Replaces the network preferences; applies to the next request.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_PROXY` or
+/// `INVALID_CERTIFICATE` if the settings are rejected (nothing is saved in
+/// that case), or `DATABASE` if they cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_network_settings", {
+///   settings: {
+///     proxy: { mode: "manual", url: "socks5h://127.0.0.1:9050" },
+///     overrides: { updater: { mode: "direct" } },
+///     caCertificates: ["/etc/ssl/corp-root.pem"],
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_network_settings(
+    db: State<'_, Database>,
+    clients: State<'_, HttpClients>,
+    settings: NetworkSettings,
+) -> Result<NetworkSettings, GibberError> {
+    let certificates = settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    clients.replace(settings.clone(), certificates);
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(url: &str) -> ProxyConfig {
+        ProxyConfig::Manual {
+            url: url.to_string(),
+            no_proxy: None,
+        }
+    }
+
+    #[test]
+    fn test_proxy_validation() {
+        assert!(manual("http://proxy.local:8080").validate().is_ok());
+        assert!(manual("socks5h://user:pw@127.0.0.1:9050")
+            .validate()
+            .is_ok());
+        assert_eq!(
+            manual("ftp://proxy.local").validate().unwrap_err().code(),
+            "INVALID_PROXY"
+        );
+        assert!(manual("not a url").validate().is_err());
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let mut settings = NetworkSettings {
+            proxy: manual("http://proxy.local:8080"),
+            ..NetworkSettings::default()
+        };
+        settings
+            .overrides
+            .insert(Service::Updater, ProxyConfig::Direct);
+        assert_eq!(settings.proxy_for(Service::Updater), &ProxyConfig::Direct);
+        assert_eq!(
+            settings.proxy_for(Service::OpenRouter),
+            &manual("http://proxy.local:8080")
+        );
+    }
+
+    #[test]
+    fn test_settings_deserialize() {
+        let settings: NetworkSettings = serde_json::from_str(
+            r#"{"proxy":{"mode":"direct"},"overrides":{"openrouter":{"mode":"manual","url":"http://p:1","noProxy":"localhost"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(settings.proxy, ProxyConfig::Direct);
+        assert_eq!(
+            settings.proxy_for(Service::OpenRouter),
+            &ProxyConfig::Manual {
+                url: "http://p:1".to_string(),
+                no_proxy: Some("localhost".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_certificate_is_rejected() {
+        let settings = NetworkSettings {
+            ca_certificates: vec!["/nonexistent/ca.pem".to_string()],
+            ..NetworkSettings::default()
+        };
+        assert_eq!(
+            settings.validate().unwrap_err().code(),
+            "INVALID_CERTIFICATE"
+        );
+    }
+}
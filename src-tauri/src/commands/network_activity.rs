@@ -0,0 +1,249 @@
+//! Network activity monitor.
+//!
+//! Every outbound request the backend makes goes through [`send`], which
+//! records its service, purpose, method, host, status, sizes and duration
+//! in an in-memory ring buffer of the last [`CAPACITY`] requests. Bodies,
+//! paths, query strings and headers are never recorded. Update checks and
+//! downloads run inside the updater plugin and are recorded afterwards with
+//! [`record`].
+//!
+//! Requests to an AI provider first wait for its rate limit budget, except
+//! probes in [`UNTHROTTLED`]; see [`rate_limit`].
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, Url};
+
+use crate::commands::network::Service;
+use crate::commands::rate_limit;
+use crate::db;
+
+/// Number of requests kept.
+pub const CAPACITY: usize = 500;
+
+/// Purposes that don't spend a provider's budget: connectivity probes and
+/// checks only need an answer, and shouldn't queue behind real work.
+pub const UNTHROTTLED: [&str; 2] = ["diagnostics.check", "outbox.probe"];
+
+/// A recorded outbound request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkRequest {
+    /// Start time in Unix milliseconds
+    pub started_at: i64,
+    /// Client the request went through
+    pub service: Service,
+    /// What the request was for, e.g. `chat.completion`
+    pub purpose: &'static str,
+    /// HTTP method
+    pub method: String,
+    /// Host name contacted
+    pub host: String,
+    /// HTTP status, if a response arrived
+    pub status: Option<u16>,
+    /// Request body size
+    pub bytes_sent: u64,
+    /// Response size announced by the server
+    pub bytes_received: Option<u64>,
+    /// Time until the response headers arrived
+    pub duration_ms: u64,
+    /// Transport error, if the request failed
+    pub error: Option<String>,
+}
+
+/// Managed ring buffer of recent requests.
+#[derive(Default)]
+pub struct NetworkActivity(Mutex<VecDeque<NetworkRequest>>);
+
+impl NetworkActivity {
+    fn lock(&self) -> MutexGuard<'_, VecDeque<NetworkRequest>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn push(&self, request: NetworkRequest) {
+        let mut requests = self.lock();
+        if requests.len() == CAPACITY {
+            requests.pop_front();
+        }
+        requests.push_back(request);
+    }
+}
+
+fn host(url: &Url) -> String {
+    url.host_str().unwrap_or_default().to_string()
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Records a request made outside [`send`].
+pub fn record(
+    app: &AppHandle,
+    service: Service,
+    purpose: &'static str,
+    url: &Url,
+    started: Instant,
+    bytes_received: Option<u64>,
+    error: Option<String>,
+) {
+    let duration_ms = elapsed_ms(started);
+    if let Some(activity) = app.try_state::<NetworkActivity>() {
+        activity.push(NetworkRequest {
+            started_at: db::now_millis() - i64::try_from(duration_ms).unwrap_or(0),
+            service,
+            purpose,
+            method: "GET".to_string(),
+            host: host(url),
+            status: None,
+            bytes_sent: 0,
+            bytes_received,
+            duration_ms,
+            error,
+        });
+    }
+}
+
+/// Sends a request built from a [`network::client`](super::network::client)
+/// and records it.
+///
+/// # Errors
+///
+/// Returns the `reqwest` error if the request cannot be built or sent.
+pub async fn send(
+    app: &AppHandle,
+    service: Service,
+    purpose: &'static str,
+    builder: RequestBuilder,
+) -> reqwest::Result<Response> {
+    let (client, request) = builder.build_split();
+    let request = request?;
+    let mut entry = NetworkRequest {
+        started_at: db::now_millis(),
+        service,
+        purpose,
+        method: request.method().to_string(),
+        host: host(request.url()),
+        status: None,
+        bytes_sent: request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .map_or(0, |body| u64::try_from(body.len()).unwrap_or(u64::MAX)),
+        bytes_received: None,
+        duration_ms: 0,
+        error: None,
+    };
+    let provider = rate_limit::provider_for_host(&entry.host);
+    if let Some(provider) = provider.filter(|_| !UNTHROTTLED.contains(&purpose)) {
+        rate_limit::throttle(app, provider).await;
+    }
+    let started = Instant::now();
+    let result = client.execute(request).await;
+    entry.duration_ms = elapsed_ms(started);
+    match &result {
+        Ok(response) => {
+            entry.status = Some(response.status().as_u16());
+            entry.bytes_received = response.content_length();
+            if let Some(provider) =
+                provider.filter(|_| response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS)
+            {
+                rate_limit::rate_limited(app, provider, retry_after(response));
+            }
+        }
+        // The URL may carry a path or query; only the host is kept.
+        Err(e) => entry.error = Some(strip_url(e)),
+    }
+    if let Some(activity) = app.try_state::<NetworkActivity>() {
+        activity.push(entry);
+    }
+    result
+}
+
+/// Delay from a `Retry-After` header given in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+fn strip_url(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    if let Some(url) = error.url() {
+        message = message.replace(url.as_str(), &host(url));
+    }
+    message
+}
+
+/// Returns recent outbound requests, newest first.
+///
+/// # Example
+///
+/// ```typescript
+/// const requests = await invoke("get_network_activity");
+/// const hosts = new Set(requests.map((r) => r.host));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_network_activity(activity: State<'_, NetworkActivity>) -> Vec<NetworkRequest> {
+    activity.lock().iter().rev().cloned().collect()
+}
+
+/// Forgets all recorded requests.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn clear_network_activity(activity: State<'_, NetworkActivity>) {
+    activity.lock().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(purpose: &'static str) -> NetworkRequest {
+        NetworkRequest {
+            started_at: 0,
+            service: Service::OpenRouter,
+            purpose,
+            method: "POST".to_string(),
+            host: "openrouter.ai".to_string(),
+            status: Some(200),
+            bytes_sent: 10,
+            bytes_received: None,
+            duration_ms: 5,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let activity = NetworkActivity::default();
+        activity.push(request("first"));
+        for _ in 1..CAPACITY {
+            activity.push(request("middle"));
+        }
+        activity.push(request("last"));
+
+        let requests = activity.lock();
+        assert_eq!(requests.len(), CAPACITY);
+        assert_eq!(requests.front().unwrap().purpose, "middle");
+        assert_eq!(requests.back().unwrap().purpose, "last");
+    }
+
+    #[test]
+    fn test_host_ignores_path_and_query() {
+        let url = Url::parse("https://openrouter.ai/api/v1/models?token=secret").unwrap();
+        assert_eq!(host(&url), "openrouter.ai");
+    }
+}
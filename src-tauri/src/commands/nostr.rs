@@ -0,0 +1,645 @@
+//! Minimal Nostr relay client.
+//!
+//! Implements the parts of NIP-01 the backend needs to read from and write
+//! to relays: events with id and signature checks, subscription filters, a
+//! one-shot query that collects stored events from several relays until
+//! each sends `EOSE`, and publishing signed events. Public keys are
+//! accepted as hex or NIP-19 `npub`; the user's secret key is kept in the
+//! keyring as hex or `nsec` and is only needed to sign.
+//!
+//! Relays are reached over WebSocket, which doesn't go through the HTTP
+//! clients in [`network`]. When a manual proxy applies to
+//! [`Service::Nostr`], queries fail instead of bypassing it.
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::time::{Duration, Instant};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use futures_util::{SinkExt, StreamExt};
+use k256::schnorr::SigningKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Url};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::commands::credentials;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::error::GibberError;
+
+/// Relays used when the user hasn't picked any.
+pub const DEFAULT_RELAYS: [&str; 3] = [
+    "wss://relay.damus.io",
+    "wss://nos.lol",
+    "wss://relay.primal.net",
+];
+
+/// Kind of profile metadata events.
+pub const KIND_METADATA: u32 = 0;
+
+/// Kind of short text notes.
+pub const KIND_TEXT_NOTE: u32 = 1;
+
+/// Kind of contact lists.
+pub const KIND_CONTACTS: u32 = 3;
+
+/// Kind of NIP-98 HTTP authorization events.
+pub const KIND_HTTP_AUTH: u32 = 27_235;
+
+/// Kind of Blossom (BUD-01) authorization events.
+pub const KIND_BLOSSOM_AUTH: u32 = 24_242;
+
+/// Kind of NIP-23 long-form articles.
+pub const KIND_LONG_FORM: u32 = 30_023;
+
+/// Kind of NIP-37 draft wraps.
+pub const KIND_DRAFT: u32 = 31_234;
+
+/// Keyring entry of the user's secret key.
+pub const SECRET_KEY_SERVICE: &str = "nostr";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for a relay to send its stored events.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Most events kept from one relay per query.
+const MAX_EVENTS_PER_RELAY: usize = 5_000;
+
+/// Time allowed for a relay to accept or reject a published event.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A signed Nostr event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub struct Event {
+    /// Hex SHA-256 of the serialized event
+    pub id: String,
+    /// Hex public key of the author
+    pub pubkey: String,
+    /// Creation time in Unix seconds
+    pub created_at: i64,
+    /// Event kind
+    pub kind: u32,
+    /// Tags, each a name followed by values
+    pub tags: Vec<Vec<String>>,
+    /// Event content
+    pub content: String,
+    /// Hex Schnorr signature of the id
+    pub sig: String,
+}
+
+impl Event {
+    /// Creates an event by the owner of `key` and signs it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GibberError` with code `SIGNING` if signing fails.
+    pub fn sign(
+        key: &SigningKey,
+        created_at: i64,
+        kind: u32,
+        tags: Vec<Vec<String>>,
+        content: String,
+    ) -> Result<Self, GibberError> {
+        let mut event = Self {
+            id: String::new(),
+            pubkey: public_key(key),
+            created_at,
+            kind,
+            tags,
+            content,
+            sig: String::new(),
+        };
+        let digest = event.digest();
+        let mut aux_rand = [0u8; 32];
+        OsRng.fill_bytes(&mut aux_rand);
+        let signature = key
+            .sign_raw(&digest, &aux_rand)
+            .map_err(|e| GibberError::new("SIGNING", e.to_string()))?;
+        event.id = hex::encode(digest);
+        event.sig = hex::encode(signature.to_bytes());
+        Ok(event)
+    }
+
+    fn digest(&self) -> [u8; 32] {
+        let serialized = serde_json::json!([
+            0,
+            self.pubkey,
+            self.created_at,
+            self.kind,
+            self.tags,
+            self.content
+        ])
+        .to_string();
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&Sha256::digest(serialized.as_bytes()));
+        digest
+    }
+
+    /// Computes the event id from its fields.
+    pub fn compute_id(&self) -> String {
+        hex::encode(self.digest())
+    }
+
+    /// Checks that the id matches the fields and the author signed it.
+    pub fn verify(&self) -> bool {
+        if self.compute_id() != self.id {
+            return false;
+        }
+        let (Ok(pubkey), Ok(id), Ok(sig)) = (
+            hex::decode(&self.pubkey),
+            hex::decode(&self.id),
+            hex::decode(&self.sig),
+        ) else {
+            return false;
+        };
+        let Ok(key) = k256::schnorr::VerifyingKey::from_bytes(&pubkey) else {
+            return false;
+        };
+        let Ok(signature) = k256::schnorr::Signature::try_from(sig.as_slice()) else {
+            return false;
+        };
+        key.verify_raw(&id, &signature).is_ok()
+    }
+
+    /// Values of the tags named `name`, first value only.
+    pub fn tag_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.tags
+            .iter()
+            .filter(move |tag| tag.first().is_some_and(|tag_name| tag_name == name))
+            .filter_map(|tag| tag.get(1).map(String::as_str))
+    }
+}
+
+/// A NIP-01 subscription filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, specta::Type)]
+pub struct Filter {
+    /// Author public keys
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    /// Event kinds
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<u32>,
+    /// Oldest creation time, in Unix seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    /// Most events a relay should return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Parses a public key given as 64 hex characters or an `npub`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_KEY` if it is neither.
+pub fn parse_pubkey(value: &str) -> Result<String, GibberError> {
+    let value = value.trim();
+    let invalid = || GibberError::new("INVALID_KEY", format!("Not a Nostr public key: {value}"));
+    if value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Ok(value.to_ascii_lowercase());
+    }
+    let (hrp, data) = bech32::decode(value).map_err(|_| invalid())?;
+    if hrp.as_str() != "npub" || data.len() != 32 {
+        return Err(invalid());
+    }
+    Ok(hex::encode(data))
+}
+
+/// Parses a secret key given as 64 hex characters or an `nsec`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_KEY` if it is neither. The
+/// message doesn't repeat the value.
+pub fn parse_secret_key(value: &str) -> Result<SigningKey, GibberError> {
+    let value = value.trim();
+    let invalid = || GibberError::new("INVALID_KEY", "Not a Nostr secret key");
+    let bytes = if value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        hex::decode(value).map_err(|_| invalid())?
+    } else {
+        let (hrp, data) = bech32::decode(value).map_err(|_| invalid())?;
+        if hrp.as_str() != "nsec" || data.len() != 32 {
+            return Err(invalid());
+        }
+        data
+    };
+    SigningKey::from_bytes(&bytes).map_err(|_| invalid())
+}
+
+/// Hex public key of `key`.
+pub fn public_key(key: &SigningKey) -> String {
+    hex::encode(key.verifying_key().to_bytes())
+}
+
+/// Reads the user's secret key from the keyring.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NO_KEY` if none is stored,
+/// `INVALID_KEY` if the stored value isn't a key, or a keyring error.
+pub(crate) async fn signing_key(app: &AppHandle) -> Result<SigningKey, GibberError> {
+    let secret = credentials::load_api_key(app, SECRET_KEY_SERVICE)
+        .await?
+        .ok_or_else(|| GibberError::new("NO_KEY", "No Nostr secret key has been added"))?;
+    parse_secret_key(&secret)
+}
+
+/// Builds a NIP-98 `Authorization` header value for an HTTP request to
+/// `url`, optionally committing to the SHA-256 of the request body.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `SIGNING` if signing fails.
+pub fn http_auth(
+    key: &SigningKey,
+    url: &str,
+    method: &str,
+    payload_sha256: Option<&str>,
+) -> Result<String, GibberError> {
+    let mut tags = vec![
+        vec!["u".to_string(), url.to_string()],
+        vec!["method".to_string(), method.to_string()],
+    ];
+    if let Some(hash) = payload_sha256 {
+        tags.push(vec!["payload".to_string(), hash.to_string()]);
+    }
+    let event = Event::sign(
+        key,
+        chrono::Utc::now().timestamp(),
+        KIND_HTTP_AUTH,
+        tags,
+        String::new(),
+    )?;
+    let json = serde_json::to_string(&event).unwrap_or_default();
+    Ok(format!("Nostr {}", BASE64_STANDARD.encode(json)))
+}
+
+/// Builds a Blossom `Authorization` header allowing the upload of the
+/// blob with hex SHA-256 `sha256` until `expiration` (Unix seconds).
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `SIGNING` if signing fails.
+pub fn blossom_auth(
+    key: &SigningKey,
+    sha256: &str,
+    expiration: i64,
+    description: &str,
+) -> Result<String, GibberError> {
+    let tags = vec![
+        vec!["t".to_string(), "upload".to_string()],
+        vec!["x".to_string(), sha256.to_string()],
+        vec!["expiration".to_string(), expiration.to_string()],
+    ];
+    let event = Event::sign(
+        key,
+        chrono::Utc::now().timestamp(),
+        KIND_BLOSSOM_AUTH,
+        tags,
+        description.to_string(),
+    )?;
+    let json = serde_json::to_string(&event).unwrap_or_default();
+    Ok(format!("Nostr {}", BASE64_STANDARD.encode(json)))
+}
+
+/// Checks that `relay` is a ws(s) URL.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_RELAY` otherwise.
+pub fn validate_relay(relay: &str) -> Result<Url, GibberError> {
+    match Url::parse(relay) {
+        Ok(url) if matches!(url.scheme(), "wss" | "ws") && url.host_str().is_some() => Ok(url),
+        _ => Err(GibberError::new(
+            "INVALID_RELAY",
+            format!("Not a ws(s) relay URL: {relay}"),
+        )),
+    }
+}
+
+/// Collects the stored events matching `filters` from one relay.
+async fn query_relay(url: &Url, filters: &[Filter]) -> Result<Vec<Event>, String> {
+    let (mut socket, _) = tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        tokio_tungstenite::connect_async(url.as_str()),
+    )
+    .await
+    .map_err(|_| "connection timed out".to_string())?
+    .map_err(|e| e.to_string())?;
+
+    let subscription = crate::db::new_id();
+    let mut request = vec![serde_json::json!("REQ"), serde_json::json!(subscription)];
+    request.extend(filters.iter().map(|filter| serde_json::json!(filter)));
+    socket
+        .send(Message::text(serde_json::Value::Array(request).to_string()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut events = Vec::new();
+    let deadline = tokio::time::Instant::now() + QUERY_TIMEOUT;
+    while events.len() < MAX_EVENTS_PER_RELAY {
+        let message = match tokio::time::timeout_at(deadline, socket.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => text,
+            Ok(Some(Ok(_))) => continue,
+            Ok(Some(Err(e))) => return Err(e.to_string()),
+            // Closed or out of time: keep what arrived.
+            Ok(None) | Err(_) => break,
+        };
+        let Ok(serde_json::Value::Array(parts)) = serde_json::from_str(&message) else {
+            continue;
+        };
+        match parts.first().and_then(serde_json::Value::as_str) {
+            Some("EVENT") => {
+                if let Some(Ok(event)) = parts.get(2).cloned().map(serde_json::from_value::<Event>)
+                {
+                    events.push(event);
+                }
+            }
+            Some("EOSE") => break,
+            Some("CLOSED") => {
+                let reason = parts
+                    .get(2)
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or_default();
+                return Err(format!("relay closed the subscription: {reason}"));
+            }
+            _ => {}
+        }
+    }
+    let close = serde_json::json!(["CLOSE", subscription]).to_string();
+    let _ = socket.send(Message::text(close)).await;
+    let _ = socket.close(None).await;
+    Ok(events)
+}
+
+/// Queries several relays and merges their answers.
+///
+/// Events are deduplicated by id, and those with a wrong id or signature
+/// are dropped. Relays that fail are logged and skipped.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `PROXY` if a manual proxy applies to
+/// Nostr, `INVALID_RELAY` for a bad relay URL, or `NETWORK` if no relay
+/// could be queried.
+pub(crate) async fn query(
+    app: &AppHandle,
+    relays: &[String],
+    filters: &[Filter],
+) -> Result<Vec<Event>, GibberError> {
+    if network::manual_proxy(app, Service::Nostr).is_some() {
+        return Err(GibberError::new(
+            "PROXY",
+            "Nostr relays can't be reached through a manual proxy",
+        ));
+    }
+    let urls = relays
+        .iter()
+        .map(|relay| validate_relay(relay))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut events: HashMap<String, Event> = HashMap::new();
+    let mut last_error = None;
+    let mut answered = false;
+    for url in &urls {
+        let started = Instant::now();
+        match query_relay(url, filters).await {
+            Ok(found) => {
+                let count = u64::try_from(found.len()).unwrap_or(u64::MAX);
+                network_activity::record(
+                    app,
+                    Service::Nostr,
+                    "nostr.query",
+                    url,
+                    started,
+                    Some(count),
+                    None,
+                );
+                answered = true;
+                for event in found {
+                    if let Entry::Vacant(entry) = events.entry(event.id.clone()) {
+                        if event.verify() {
+                            entry.insert(event);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!(relay = %url, "relay query failed: {e}");
+                network_activity::record(
+                    app,
+                    Service::Nostr,
+                    "nostr.query",
+                    url,
+                    started,
+                    None,
+                    Some(e.clone()),
+                );
+                last_error = Some(e);
+            }
+        }
+    }
+    if !answered {
+        return Err(GibberError::new(
+            "NETWORK",
+            last_error.unwrap_or_else(|| "No relays configured".to_string()),
+        ));
+    }
+    Ok(events.into_values().collect())
+}
+
+/// What a relay said about a published event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+pub struct RelayResult {
+    /// The relay URL
+    pub relay: String,
+    /// Whether the relay stored the event
+    pub accepted: bool,
+    /// The relay's message, or the connection error
+    pub message: String,
+}
+
+/// Sends `event` to one relay and waits for its `OK`.
+async fn publish_to_relay(url: &Url, event: &Event) -> Result<(bool, String), String> {
+    let (mut socket, _) = tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        tokio_tungstenite::connect_async(url.as_str()),
+    )
+    .await
+    .map_err(|_| "connection timed out".to_string())?
+    .map_err(|e| e.to_string())?;
+
+    let request = serde_json::json!(["EVENT", event]).to_string();
+    socket
+        .send(Message::text(request))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let deadline = tokio::time::Instant::now() + PUBLISH_TIMEOUT;
+    let answer = loop {
+        let message = match tokio::time::timeout_at(deadline, socket.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => text,
+            Ok(Some(Ok(_))) => continue,
+            Ok(Some(Err(e))) => break Err(e.to_string()),
+            Ok(None) => break Err("relay closed the connection".to_string()),
+            Err(_) => break Err("relay didn't answer in time".to_string()),
+        };
+        let Ok(serde_json::Value::Array(parts)) = serde_json::from_str(&message) else {
+            continue;
+        };
+        let is_ok = parts.first().and_then(serde_json::Value::as_str) == Some("OK");
+        if is_ok && parts.get(1).and_then(serde_json::Value::as_str) == Some(event.id.as_str()) {
+            let accepted = parts
+                .get(2)
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(false);
+            let reason = parts
+                .get(3)
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default();
+            break Ok((accepted, reason.to_string()));
+        }
+    };
+    let _ = socket.close(None).await;
+    answer
+}
+
+/// Publishes a signed event to several relays.
+///
+/// Relays are tried one after another; each one's answer is returned.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `PROXY` if a manual proxy applies to
+/// Nostr, `INVALID_RELAY` for a bad relay URL, or `PUBLISH_FAILED` if no
+/// relay accepted the event.
+pub(crate) async fn publish(
+    app: &AppHandle,
+    relays: &[String],
+    event: &Event,
+) -> Result<Vec<RelayResult>, GibberError> {
+    if network::manual_proxy(app, Service::Nostr).is_some() {
+        return Err(GibberError::new(
+            "PROXY",
+            "Nostr relays can't be reached through a manual proxy",
+        ));
+    }
+    let urls = relays
+        .iter()
+        .map(|relay| validate_relay(relay))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = Vec::with_capacity(urls.len());
+    for url in &urls {
+        let started = Instant::now();
+        let outcome = publish_to_relay(url, event).await;
+        let error = match &outcome {
+            Ok((true, _)) => None,
+            Ok((false, reason)) => Some(format!("rejected: {reason}")),
+            Err(e) => Some(e.clone()),
+        };
+        if let Some(error) = &error {
+            tracing::warn!(relay = %url, "publishing failed: {error}");
+        }
+        network_activity::record(
+            app,
+            Service::Nostr,
+            "nostr.publish",
+            url,
+            started,
+            None,
+            error,
+        );
+        let (accepted, message) = outcome.unwrap_or_else(|e| (false, e));
+        results.push(RelayResult {
+            relay: url.to_string(),
+            accepted,
+            message,
+        });
+    }
+    if !results.iter().any(|result| result.accepted) {
+        let message = results
+            .last()
+            .map_or_else(|| "No relays configured".to_string(), |r| r.message.clone());
+        return Err(GibberError::new(
+            "PUBLISH_FAILED",
+            format!("No relay accepted the event: {message}"),
+        ));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e";
+
+    #[test]
+    fn test_parse_pubkey() {
+        assert_eq!(
+            parse_pubkey("npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg")
+                .unwrap(),
+            PUBKEY
+        );
+        assert_eq!(parse_pubkey(&PUBKEY.to_ascii_uppercase()).unwrap(), PUBKEY);
+        assert_eq!(parse_pubkey("npub1nope").unwrap_err().code(), "INVALID_KEY");
+    }
+
+    #[test]
+    fn test_compute_id_and_verify() {
+        let mut event = Event {
+            id: "4529cb13b2a29215b13ee1c2561e1fe20c52e0ae83dd6bc8ab207327f9cb17b3".to_string(),
+            pubkey: PUBKEY.to_string(),
+            created_at: 1_700_000_000,
+            kind: KIND_TEXT_NOTE,
+            tags: vec![vec!["e".to_string(), "aa".to_string()]],
+            content: "gm\nnostr".to_string(),
+            sig: "00".repeat(64),
+        };
+        assert_eq!(event.compute_id(), event.id);
+        assert_eq!(event.tag_values("e").collect::<Vec<_>>(), vec!["aa"]);
+        // Right id, bogus signature.
+        assert!(!event.verify());
+        event.content.push('!');
+        assert_ne!(event.compute_id(), event.id);
+    }
+
+    #[test]
+    fn test_signed_events_verify() {
+        let key = parse_secret_key(&"7e".repeat(32)).unwrap();
+        let event = Event::sign(
+            &key,
+            1_700_000_000,
+            KIND_LONG_FORM,
+            vec![vec!["d".to_string(), "synths".to_string()]],
+            "# Synths".to_string(),
+        )
+        .unwrap();
+        assert_eq!(event.pubkey, public_key(&key));
+        assert!(event.verify());
+        assert!(parse_secret_key(PUBKEY.get(..10).unwrap()).is_err());
+        assert!(parse_secret_key(
+            "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_filter_serializes_set_fields() {
+        let filter = Filter {
+            kinds: vec![KIND_CONTACTS],
+            limit: Some(1),
+            ..Filter::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&filter).unwrap(),
+            serde_json::json!({ "kinds": [3], "limit": 1 })
+        );
+        assert!(validate_relay("wss://relay.example").is_ok());
+        assert!(validate_relay("https://relay.example").is_err());
+    }
+}
@@ -0,0 +1,955 @@
+//! Long-form article composer for Nostr authors.
+//!
+//! Drafts are stored locally and can be revised by a model (improve,
+//! proofread, shorten, expand, or suggest a title or summary) without
+//! leaving the composer. `publish_nostr_draft` signs the draft as a
+//! NIP-23 article (kind 30023) with the user's secret key and sends it to
+//! the composer's relays; republishing replaces the article, since its `d`
+//! tag stays the same. Images are uploaded with `upload_media` (see
+//! [`media`](crate::commands::media)) and referenced by URL.
+//!
+//! With draft sync on, `sync_nostr_drafts` publishes changed drafts as
+//! NIP-37 draft wraps (kind 31234) encrypted to the author's own key with
+//! NIP-44, and takes in newer drafts written on other devices. Relays
+//! only ever see the ciphertext.
+
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::nostr::{self, Event, Filter, RelayResult};
+use crate::commands::nostr_encryption;
+use crate::commands::settings;
+use crate::commands::telemetry;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key of the composer preferences.
+pub const SETTINGS_KEY: &str = "nostrComposer";
+
+/// Longest article accepted, in characters.
+pub const MAX_CONTENT_CHARS: usize = 200_000;
+
+const REVISE_PROMPT: &str = "You are an editor helping a writer with a long-form article \
+     for Nostr, written in Markdown. {instruction} Keep the author's voice and language, and \
+     keep Markdown, links, and images intact. Reply with the result only, without comments or \
+     quotation marks.";
+
+/// Composer preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ComposerSettings {
+    /// Relays articles and synced drafts are published to
+    pub relays: Vec<String>,
+    /// Whether drafts are synced through the relays, encrypted
+    pub sync_drafts: bool,
+    /// Model for revisions; the default model when `None`
+    pub model: Option<String>,
+}
+
+impl Default for ComposerSettings {
+    fn default() -> Self {
+        Self {
+            relays: nostr::DEFAULT_RELAYS.map(str::to_string).to_vec(),
+            sync_drafts: false,
+            model: None,
+        }
+    }
+}
+
+impl ComposerSettings {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        if self.relays.is_empty() || self.relays.len() > 10 {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                "Between one and ten relays are required",
+            ));
+        }
+        for relay in &self.relays {
+            nostr::validate_relay(relay)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> ComposerSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// A stored article draft.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Draft {
+    /// Unique identifier
+    pub id: String,
+    /// The article's NIP-23 `d` tag, stable across edits and devices
+    pub identifier: String,
+    /// Article title
+    pub title: String,
+    /// Short summary shown in previews
+    pub summary: String,
+    /// URL of the header image
+    pub image: Option<String>,
+    /// The article, in Markdown
+    pub content: String,
+    /// Hashtags, without `#`
+    pub hashtags: Vec<String>,
+    /// ID of the last published version
+    pub published_event_id: Option<String>,
+    /// First publication time in Unix seconds
+    pub published_at: Option<i64>,
+    /// Last sync time in Unix milliseconds
+    pub synced_at: Option<i64>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// Last update time in Unix milliseconds
+    pub updated_at: i64,
+}
+
+/// Fields for creating or replacing a draft.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DraftInput {
+    /// Article title
+    #[serde(default)]
+    pub title: String,
+    /// Short summary shown in previews
+    #[serde(default)]
+    pub summary: String,
+    /// URL of the header image
+    #[serde(default)]
+    pub image: Option<String>,
+    /// The article, in Markdown
+    #[serde(default)]
+    pub content: String,
+    /// Hashtags, with or without `#`
+    #[serde(default)]
+    pub hashtags: Vec<String>,
+}
+
+/// How a model should revise a draft.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Revision {
+    /// Clearer writing, better flow and structure
+    Improve,
+    /// Spelling, grammar, and punctuation only
+    Proofread,
+    /// Fewer words, same points
+    Shorten,
+    /// More detail where the text is thin
+    Expand,
+    /// Suggest a title
+    Title,
+    /// Suggest a preview summary
+    Summary,
+    /// Follow the writer's own instruction
+    Custom {
+        /// What to change
+        instruction: String,
+    },
+}
+
+impl Revision {
+    fn instruction(&self) -> &str {
+        match self {
+            Self::Improve => "Improve the clarity, flow, and structure of the text.",
+            Self::Proofread => {
+                "Correct the spelling, grammar, and punctuation and change nothing else."
+            }
+            Self::Shorten => "Make the text noticeably shorter, keeping every essential point.",
+            Self::Expand => {
+                "Expand the text with more detail and examples where it is thin, without padding."
+            }
+            Self::Title => "Write one title for the article, under 80 characters.",
+            Self::Summary => "Write a one or two sentence summary of the article for previews.",
+            Self::Custom { instruction } => instruction,
+        }
+    }
+
+    /// Titles and summaries describe the whole article, never a selection.
+    const fn describes_article(&self) -> bool {
+        matches!(self, Self::Title | Self::Summary)
+    }
+
+    const fn temperature(&self) -> f32 {
+        match self {
+            Self::Proofread => 0.0,
+            _ => 0.4,
+        }
+    }
+}
+
+/// A model's revision, for the writer to accept or discard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DraftRevision {
+    /// The revised text, title, or summary
+    pub text: String,
+    /// The model that wrote it
+    pub model: String,
+}
+
+/// A published article.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedArticle {
+    /// The draft, with its publication recorded
+    pub draft: Draft,
+    /// ID of the signed event
+    pub event_id: String,
+    /// NIP-19 `naddr` of the article, for links that survive edits
+    pub naddr: String,
+    /// What each relay answered
+    pub relays: Vec<RelayResult>,
+}
+
+/// Outcome of a draft sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Drafts published to the relays
+    pub uploaded: usize,
+    /// Drafts created or updated from the relays
+    pub downloaded: usize,
+    /// Drafts removed because they were deleted on another device
+    pub removed: usize,
+    /// Drafts too large to encrypt, left out
+    pub skipped: usize,
+}
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Draft {id} not found"))
+}
+
+/// Trims the input, drops an empty image, and normalizes hashtags to
+/// lowercase without `#`.
+fn normalize(input: DraftInput) -> Result<DraftInput, GibberError> {
+    if input.content.chars().count() > MAX_CONTENT_CHARS {
+        return Err(GibberError::invalid(format!(
+            "The article is longer than {MAX_CONTENT_CHARS} characters"
+        )));
+    }
+    let mut hashtags: Vec<String> = Vec::with_capacity(input.hashtags.len());
+    for tag in input.hashtags {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !hashtags.contains(&tag) {
+            hashtags.push(tag);
+        }
+    }
+    Ok(DraftInput {
+        title: input.title.trim().to_string(),
+        summary: input.summary.trim().to_string(),
+        image: input
+            .image
+            .map(|image| image.trim().to_string())
+            .filter(|image| !image.is_empty()),
+        content: input.content,
+        hashtags,
+    })
+}
+
+const DRAFT_COLUMNS: &str = "id, identifier, title, summary, image, content, hashtags, \
+     published_event_id, published_at, synced_at, created_at, updated_at";
+
+fn draft_from_row(row: &Row<'_>) -> rusqlite::Result<Draft> {
+    let hashtags: String = row.get(6)?;
+    Ok(Draft {
+        id: row.get(0)?,
+        identifier: row.get(1)?,
+        title: row.get(2)?,
+        summary: row.get(3)?,
+        image: row.get(4)?,
+        content: row.get(5)?,
+        hashtags: serde_json::from_str(&hashtags)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, Type::Text, Box::new(e)))?,
+        published_event_id: row.get(7)?,
+        published_at: row.get(8)?,
+        synced_at: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+fn load_draft(conn: &Connection, id: &str) -> Result<Draft, GibberError> {
+    conn.query_row(
+        &format!("SELECT {DRAFT_COLUMNS} FROM nostr_drafts WHERE id = ?1"),
+        [id],
+        draft_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found(id))
+}
+
+fn list(conn: &Connection) -> rusqlite::Result<Vec<Draft>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {DRAFT_COLUMNS} FROM nostr_drafts ORDER BY updated_at DESC"
+    ))?;
+    let drafts = stmt
+        .query_map([], draft_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(drafts)
+}
+
+fn save(conn: &Connection, id: Option<String>, input: DraftInput) -> Result<Draft, GibberError> {
+    let input = normalize(input)?;
+    let hashtags = serde_json::to_string(&input.hashtags).unwrap_or_default();
+    let now = db::now_millis();
+    let id = if let Some(id) = id {
+        let updated = conn.execute(
+            "UPDATE nostr_drafts SET title = ?1, summary = ?2, image = ?3, content = ?4,
+             hashtags = ?5, updated_at = ?6 WHERE id = ?7",
+            params![
+                input.title,
+                input.summary,
+                input.image,
+                input.content,
+                hashtags,
+                now,
+                id
+            ],
+        )?;
+        if updated == 0 {
+            return Err(not_found(&id));
+        }
+        id
+    } else {
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO nostr_drafts
+             (id, identifier, title, summary, image, content, hashtags, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+            params![
+                id,
+                db::new_id(),
+                input.title,
+                input.summary,
+                input.image,
+                input.content,
+                hashtags,
+                now
+            ],
+        )?;
+        id
+    };
+    load_draft(conn, &id)
+}
+
+/// Tags of the NIP-23 article for `draft`; `published_at` is left out of
+/// drafts that were never published.
+fn article_tags(draft: &Draft, published_at: Option<i64>) -> Vec<Vec<String>> {
+    let tag = |name: &str, value: &str| vec![name.to_string(), value.to_string()];
+    let mut tags = vec![tag("d", &draft.identifier), tag("title", &draft.title)];
+    if !draft.summary.is_empty() {
+        tags.push(tag("summary", &draft.summary));
+    }
+    if let Some(image) = &draft.image {
+        tags.push(tag("image", image));
+    }
+    if let Some(published_at) = published_at {
+        tags.push(tag("published_at", &published_at.to_string()));
+    }
+    tags.extend(draft.hashtags.iter().map(|hashtag| tag("t", hashtag)));
+    tags
+}
+
+/// Encodes the NIP-19 `naddr` of an addressable event.
+fn naddr(identifier: &str, pubkey: &str, kind: u32, relays: &[String]) -> String {
+    let mut tlv = Vec::new();
+    let mut push = |kind: u8, value: &[u8]| {
+        if let Ok(len) = u8::try_from(value.len()) {
+            tlv.push(kind);
+            tlv.push(len);
+            tlv.extend_from_slice(value);
+        }
+    };
+    push(0, identifier.as_bytes());
+    for relay in relays.iter().take(2) {
+        push(1, relay.as_bytes());
+    }
+    push(2, &hex::decode(pubkey).unwrap_or_default());
+    push(3, &kind.to_be_bytes());
+    bech32::encode::<bech32::Bech32>(bech32::Hrp::parse_unchecked("naddr"), &tlv)
+        .unwrap_or_default()
+}
+
+/// The unsigned article a draft wrap carries.
+fn draft_payload(draft: &Draft, pubkey: &str) -> String {
+    json!({
+        "kind": nostr::KIND_LONG_FORM,
+        "pubkey": pubkey,
+        "created_at": draft.updated_at / 1000,
+        "tags": article_tags(draft, draft.published_at),
+        "content": draft.content,
+    })
+    .to_string()
+}
+
+/// A draft read back from a decrypted draft wrap.
+#[derive(Debug, PartialEq, Eq)]
+struct SyncedDraft {
+    identifier: String,
+    input: DraftInput,
+    published_at: Option<i64>,
+}
+
+/// Reads the article of a decrypted draft wrap; `None` if it isn't one.
+fn parse_draft_payload(identifier: &str, payload: &str) -> Option<SyncedDraft> {
+    let article: Value = serde_json::from_str(payload).ok()?;
+    if article["kind"].as_u64() != Some(u64::from(nostr::KIND_LONG_FORM)) {
+        return None;
+    }
+    let tags: Vec<Vec<String>> = serde_json::from_value(article["tags"].clone()).ok()?;
+    let first = |name: &str| {
+        tags.iter()
+            .find(|tag| tag.first().is_some_and(|tag_name| tag_name == name))
+            .and_then(|tag| tag.get(1).cloned())
+    };
+    Some(SyncedDraft {
+        identifier: identifier.to_string(),
+        input: DraftInput {
+            title: first("title").unwrap_or_default(),
+            summary: first("summary").unwrap_or_default(),
+            image: first("image"),
+            content: article["content"].as_str()?.to_string(),
+            hashtags: tags
+                .iter()
+                .filter(|tag| tag.first().is_some_and(|name| name == "t"))
+                .filter_map(|tag| tag.get(1).cloned())
+                .collect(),
+        },
+        published_at: first("published_at").and_then(|value| value.parse().ok()),
+    })
+}
+
+/// Takes in a draft from another device if it is newer than the local one.
+fn merge_synced(
+    conn: &Connection,
+    synced: SyncedDraft,
+    updated_at: i64,
+) -> Result<bool, GibberError> {
+    let local: Option<(String, i64)> = conn
+        .query_row(
+            "SELECT id, updated_at FROM nostr_drafts WHERE identifier = ?1",
+            [&synced.identifier],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let input = normalize(synced.input)?;
+    let hashtags = serde_json::to_string(&input.hashtags).unwrap_or_default();
+    match local {
+        Some((_, local_updated)) if local_updated >= updated_at => Ok(false),
+        Some((id, _)) => {
+            conn.execute(
+                "UPDATE nostr_drafts SET title = ?1, summary = ?2, image = ?3, content = ?4,
+                 hashtags = ?5, updated_at = ?6, synced_at = ?6 WHERE id = ?7",
+                params![
+                    input.title,
+                    input.summary,
+                    input.image,
+                    input.content,
+                    hashtags,
+                    updated_at,
+                    id
+                ],
+            )?;
+            Ok(true)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO nostr_drafts
+                 (id, identifier, title, summary, image, content, hashtags, published_at,
+                  synced_at, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9, ?9)",
+                params![
+                    db::new_id(),
+                    synced.identifier,
+                    input.title,
+                    input.summary,
+                    input.image,
+                    input.content,
+                    hashtags,
+                    synced.published_at,
+                    updated_at
+                ],
+            )?;
+            Ok(true)
+        }
+    }
+}
+
+/// The model for revisions: the requested one, the composer's, or the
+/// default model.
+fn revision_model(conn: &Connection, requested: Option<String>) -> Result<String, GibberError> {
+    if let Some(model) = requested.filter(|model| !model.trim().is_empty()) {
+        return Ok(model);
+    }
+    match load_settings(conn).model {
+        Some(model) => Ok(model),
+        None => Ok(settings::load(conn)?.default_model),
+    }
+}
+
+/// Returns the composer preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_nostr_composer_settings(db: State<'_, Database>) -> ComposerSettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the composer preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_RELAY` or
+/// `INVALID_SETTINGS` naming the bad value, or `DATABASE` if the settings
+/// cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_nostr_composer_settings", {
+///   settings: { relays: ["wss://nos.lol"], syncDrafts: true },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_nostr_composer_settings(
+    db: State<'_, Database>,
+    settings: ComposerSettings,
+) -> Result<ComposerSettings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Lists the drafts, most recently edited first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_nostr_drafts(db: State<'_, Database>) -> Result<Vec<Draft>, GibberError> {
+    Ok(list(&db.conn())?)
+}
+
+/// Creates a draft, or replaces an existing one when `id` is given.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if the article is
+/// over 200,000 characters, `NOT_FOUND` if `id` doesn't exist, or
+/// `DATABASE` on failure.
+///
+/// # Example
+///
+/// ```typescript
+/// const draft = await invoke("save_nostr_draft", {
+///   id: null,
+///   input: { title: "Patching a West Coast voice", content: "# Patching…", hashtags: ["synths"] },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn save_nostr_draft(
+    db: State<'_, Database>,
+    id: Option<String>,
+    input: DraftInput,
+) -> Result<Draft, GibberError> {
+    save(&db.conn(), id, input)
+}
+
+/// Deletes a draft locally.
+///
+/// A published article stays on the relays, and so does a synced copy of
+/// the draft until the next sync from this device blanks it.
+///
+/// # Returns
+///
+/// Returns `true` if the draft was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_nostr_draft(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let conn = db.conn();
+    let identifier: Option<(String, Option<i64>)> = conn
+        .query_row(
+            "SELECT identifier, synced_at FROM nostr_drafts WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((identifier, synced_at)) = identifier else {
+        return Ok(false);
+    };
+    if synced_at.is_some() {
+        conn.execute(
+            "INSERT OR IGNORE INTO nostr_deleted_drafts (identifier, deleted_at) VALUES (?1, ?2)",
+            params![identifier, db::now_millis()],
+        )?;
+    }
+    conn.execute("DELETE FROM nostr_drafts WHERE id = ?1", [id])?;
+    Ok(true)
+}
+
+/// Asks a model to revise a draft, or the `selection` within it, and
+/// returns the result without changing the draft.
+///
+/// Titles and summaries are always written for the whole article.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the draft doesn't
+/// exist, `INVALID_INPUT` if there is no text to revise or a custom
+/// instruction is blank, or the code of the failed completion.
+///
+/// # Example
+///
+/// ```typescript
+/// const { text } = await invoke("revise_nostr_draft", {
+///   id: draft.id,
+///   revision: { kind: "custom", instruction: "Add a short conclusion" },
+///   selection: null,
+///   model: null,
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn revise_nostr_draft(
+    app: AppHandle,
+    id: String,
+    revision: Revision,
+    selection: Option<String>,
+    model: Option<String>,
+) -> Result<DraftRevision, GibberError> {
+    if let Revision::Custom { instruction } = &revision {
+        if instruction.trim().is_empty() {
+            return Err(GibberError::invalid("The instruction is empty"));
+        }
+    }
+    let (draft, model) = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        (load_draft(&conn, &id)?, revision_model(&conn, model)?)
+    };
+    let text = match selection.filter(|s| !s.trim().is_empty() && !revision.describes_article()) {
+        Some(selection) => selection,
+        None if draft.title.is_empty() => draft.content,
+        None => format!("# {}\n\n{}", draft.title, draft.content),
+    };
+    if text.trim().is_empty() {
+        return Err(GibberError::invalid("There is no text to revise"));
+    }
+    let mut request = ChatRequest::new(
+        Some(&model),
+        vec![
+            ChatMessage::new(
+                MessageRole::System,
+                REVISE_PROMPT.replacen("{instruction}", revision.instruction(), 1),
+            ),
+            ChatMessage::new(MessageRole::User, text),
+        ],
+    );
+    request.temperature = revision.temperature();
+    telemetry::record_feature(&app, "nostrComposer.revise");
+    let completion = chat::complete(&app, &request).await?;
+    Ok(DraftRevision {
+        text: completion.content.trim().to_string(),
+        model: completion.model,
+    })
+}
+
+/// Signs a draft as a NIP-23 article and publishes it to the composer's
+/// relays.
+///
+/// The first publication time is kept, so republishing an edited draft
+/// updates the article in place.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the draft doesn't
+/// exist, `INVALID_INPUT` if it has no title or content, `NO_KEY` if no
+/// secret key is stored, or `PUBLISH_FAILED` if no relay accepted it.
+///
+/// # Example
+///
+/// ```typescript
+/// const { naddr, relays } = await invoke("publish_nostr_draft", { id: draft.id });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn publish_nostr_draft(
+    app: AppHandle,
+    id: String,
+) -> Result<PublishedArticle, GibberError> {
+    let (draft, settings) = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        (load_draft(&conn, &id)?, load_settings(&conn))
+    };
+    if draft.title.is_empty() || draft.content.trim().is_empty() {
+        return Err(GibberError::invalid(
+            "An article needs a title and some content",
+        ));
+    }
+    let key = nostr::signing_key(&app).await?;
+    let now = chrono::Utc::now().timestamp();
+    let published_at = draft.published_at.unwrap_or(now);
+    let event = Event::sign(
+        &key,
+        now,
+        nostr::KIND_LONG_FORM,
+        article_tags(&draft, Some(published_at)),
+        draft.content.clone(),
+    )?;
+    let relays = nostr::publish(&app, &settings.relays, &event).await?;
+    let draft = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        conn.execute(
+            "UPDATE nostr_drafts SET published_event_id = ?1, published_at = ?2 WHERE id = ?3",
+            params![event.id, published_at, id],
+        )?;
+        load_draft(&conn, &id)?
+    };
+    telemetry::record_feature(&app, "nostrComposer.publish");
+    Ok(PublishedArticle {
+        naddr: naddr(
+            &draft.identifier,
+            &event.pubkey,
+            nostr::KIND_LONG_FORM,
+            &settings.relays,
+        ),
+        draft,
+        event_id: event.id,
+        relays,
+    })
+}
+
+/// Syncs drafts with other devices through the relays.
+///
+/// Drafts changed since the last sync are published as draft wraps
+/// encrypted to the user's own key, and deleted drafts are blanked.
+/// Newer drafts from the relays replace local ones, and drafts blanked
+/// elsewhere are removed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` if draft sync is off,
+/// `NO_KEY` if no secret key is stored, or a relay error.
+///
+/// # Example
+///
+/// ```typescript
+/// const { uploaded, downloaded } = await invoke("sync_nostr_drafts");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_lines)] // uploads, deletions, and downloads in one pass
+pub async fn sync_nostr_drafts(app: AppHandle) -> Result<SyncReport, GibberError> {
+    let (settings, deleted) = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let settings = load_settings(&conn);
+        let mut stmt = conn.prepare("SELECT identifier FROM nostr_deleted_drafts")?;
+        let deleted = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        (settings, deleted)
+    };
+    if !settings.sync_drafts {
+        return Err(GibberError::new("FORBIDDEN", "Draft sync is turned off"));
+    }
+    let key = nostr::signing_key(&app).await?;
+    let pubkey = nostr::public_key(&key);
+    let conversation_key = nostr_encryption::conversation_key(&key, &pubkey)?;
+    let wrap = |identifier: &str, content: String, created_at: i64| {
+        Event::sign(
+            &key,
+            created_at,
+            nostr::KIND_DRAFT,
+            vec![
+                vec!["d".to_string(), identifier.to_string()],
+                vec!["k".to_string(), nostr::KIND_LONG_FORM.to_string()],
+            ],
+            content,
+        )
+    };
+    let mut report = SyncReport::default();
+
+    // Pull first, so a newer remote draft isn't overwritten by this push.
+    let remote = nostr::query(
+        &app,
+        &settings.relays,
+        &[Filter {
+            authors: vec![pubkey.clone()],
+            kinds: vec![nostr::KIND_DRAFT],
+            ..Filter::default()
+        }],
+    )
+    .await?;
+    for event in &remote {
+        let Some(identifier) = event.tag_values("d").next() else {
+            continue;
+        };
+        if deleted.iter().any(|d| d == identifier) {
+            continue;
+        }
+        if event.content.is_empty() {
+            // Blanked on another device; drop the local copy unless it changed since.
+            let db = app.state::<Database>();
+            let conn = db.conn();
+            report.removed += conn.execute(
+                "DELETE FROM nostr_drafts WHERE identifier = ?1 AND updated_at <= ?2",
+                params![identifier, event.created_at * 1000],
+            )?;
+            continue;
+        }
+        let Ok(payload) = nostr_encryption::decrypt(&conversation_key, &event.content) else {
+            tracing::warn!(identifier, "skipping a draft that can't be decrypted");
+            continue;
+        };
+        if let Some(synced) = parse_draft_payload(identifier, &payload) {
+            let db = app.state::<Database>();
+            let conn = db.conn();
+            if merge_synced(&conn, synced, event.created_at * 1000)? {
+                report.downloaded += 1;
+            }
+        }
+    }
+
+    let drafts = list(&app.state::<Database>().conn())?;
+    let now = chrono::Utc::now().timestamp();
+    let mut events = Vec::new();
+    let mut uploaded = Vec::new();
+    for draft in &drafts {
+        let changed = match draft.synced_at {
+            Some(synced_at) => draft.updated_at > synced_at,
+            None => true,
+        };
+        if !changed {
+            continue;
+        }
+        let payload = draft_payload(draft, &pubkey);
+        if payload.len() > nostr_encryption::MAX_PLAINTEXT_BYTES {
+            report.skipped += 1;
+            continue;
+        }
+        let content = nostr_encryption::encrypt(&conversation_key, &payload)?;
+        events.push(wrap(&draft.identifier, content, draft.updated_at / 1000)?);
+        uploaded.push(draft.id.clone());
+    }
+    for identifier in &deleted {
+        events.push(wrap(identifier, String::new(), now)?);
+    }
+    for event in &events {
+        nostr::publish(&app, &settings.relays, event).await?;
+    }
+
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    let synced_at = db::now_millis();
+    for id in &uploaded {
+        conn.execute(
+            "UPDATE nostr_drafts SET synced_at = ?1 WHERE id = ?2",
+            params![synced_at, id],
+        )?;
+    }
+    conn.execute("DELETE FROM nostr_deleted_drafts", [])?;
+    report.uploaded = uploaded.len();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(title: &str, content: &str, hashtags: &[&str]) -> DraftInput {
+        DraftInput {
+            title: title.to_string(),
+            content: content.to_string(),
+            hashtags: hashtags.iter().map(|tag| (*tag).to_string()).collect(),
+            ..DraftInput::default()
+        }
+    }
+
+    #[test]
+    fn test_drafts_round_trip_through_a_draft_wrap() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let draft = save(
+            &conn,
+            None,
+            input(
+                " Patching ",
+                "# Patching\n\nStart with a VCO.",
+                &["#Synths", "synths"],
+            ),
+        )
+        .unwrap();
+        assert_eq!(draft.title, "Patching");
+        assert_eq!(draft.hashtags, vec!["synths"]);
+
+        let pubkey = "7e".repeat(32);
+        let payload = draft_payload(&draft, &pubkey);
+        let synced = parse_draft_payload(&draft.identifier, &payload).unwrap();
+        assert_eq!(
+            synced.input,
+            normalize(input("Patching", &draft.content, &["synths"])).unwrap()
+        );
+
+        // An older remote copy is ignored; a newer one replaces the draft.
+        assert!(!merge_synced(&conn, synced, draft.updated_at - 1).unwrap());
+        let newer = SyncedDraft {
+            identifier: draft.identifier.clone(),
+            input: input("Patching, revised", "New text", &[]),
+            published_at: None,
+        };
+        assert!(merge_synced(&conn, newer, draft.updated_at + 1).unwrap());
+        let merged = load_draft(&conn, &draft.id).unwrap();
+        assert_eq!(merged.title, "Patching, revised");
+        assert_eq!(merged.synced_at, Some(draft.updated_at + 1));
+    }
+
+    #[test]
+    fn test_article_tags_and_naddr() {
+        let draft = Draft {
+            id: "1".to_string(),
+            identifier: "patching".to_string(),
+            title: "Patching".to_string(),
+            summary: String::new(),
+            image: Some("https://image.example/a.png".to_string()),
+            content: "Text".to_string(),
+            hashtags: vec!["synths".to_string()],
+            published_event_id: None,
+            published_at: None,
+            synced_at: None,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let tags = article_tags(&draft, Some(1_700_000_000));
+        assert_eq!(tags[0], vec!["d", "patching"]);
+        assert!(tags.contains(&vec!["image".to_string(), draft.image.clone().unwrap()]));
+        assert!(tags.contains(&vec!["published_at".to_string(), "1700000000".to_string()]));
+        assert!(!tags.iter().any(|tag| tag[0] == "summary"));
+        assert!(
+            naddr("patching", &"7e".repeat(32), nostr::KIND_LONG_FORM, &[]).starts_with("naddr1")
+        );
+    }
+}
@@ -0,0 +1,574 @@
+//! "What happened today" digests of the user's Nostr follows.
+//!
+//! The contact list (kind 3) of the configured public key is read from the
+//! relays, then the last day's text notes (kind 1) of everyone on it. Notes
+//! are clustered into threads by their NIP-10 root, the busiest threads are
+//! summarized, and the summary is appended to one recurring conversation,
+//! so each day's digest follows the previous one.
+//!
+//! With the daily digest enabled, it runs once a day after `digestHour`
+//! local time; it can also be generated on demand.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use chrono::{Local, TimeZone, Timelike};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::conversations::{self, NewConversation, NewMessage};
+use crate::commands::events;
+use crate::commands::nostr::{self, Event, Filter};
+use crate::commands::tray;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the [`NostrDigestSettings`] document.
+pub const SETTINGS_KEY: &str = "nostrDigest";
+
+/// Settings key holding the ID of the recurring digest conversation.
+const CONVERSATION_KEY: &str = "nostrDigest.conversation";
+
+/// Settings key holding the local date of the last digest.
+const LAST_DIGEST_KEY: &str = "nostrDigest.last";
+
+/// How often the background loop checks whether the digest is due.
+const TICK_INTERVAL: Duration = Duration::from_mins(5);
+
+/// Period covered by a digest, in seconds.
+const DIGEST_PERIOD_SECS: i64 = 24 * 60 * 60;
+
+/// Most follows whose notes are read.
+const MAX_FOLLOWS: usize = 1_000;
+
+/// Authors per filter; relays reject very long author lists.
+const AUTHORS_PER_FILTER: usize = 250;
+
+/// Threads sent to the model, busiest first.
+const MAX_THREADS: usize = 30;
+
+/// Notes of one thread sent to the model.
+const MAX_NOTES_PER_THREAD: usize = 8;
+
+/// Longest note sent to the model, in characters.
+const MAX_NOTE_CHARS: usize = 600;
+
+const DEFAULT_PROMPT: &str = "You are given today's notes from the people the user follows on \
+     Nostr, grouped into threads. Write a short \"what happened today\" digest: the main \
+     topics and discussions first, then notable one-off posts. Mention who said what by \
+     name, keep it to a few short paragraphs or bullets, and don't invent anything that \
+     isn't in the notes.";
+
+/// Digest preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NostrDigestSettings {
+    /// Public key whose follows are digested, as hex or `npub`
+    pub pubkey: String,
+    /// Relays to read from
+    pub relays: Vec<String>,
+    /// Whether a digest is generated every day
+    pub daily_digest: bool,
+    /// Local hour (0-23) after which the daily digest is generated
+    pub digest_hour: u32,
+    /// Model for digests; defaults to the standard tier
+    pub model: Option<String>,
+    /// Instructions replacing the default digest prompt
+    pub prompt: Option<String>,
+}
+
+impl Default for NostrDigestSettings {
+    fn default() -> Self {
+        Self {
+            pubkey: String::new(),
+            relays: nostr::DEFAULT_RELAYS.map(str::to_string).to_vec(),
+            daily_digest: false,
+            digest_hour: 20,
+            model: None,
+            prompt: None,
+        }
+    }
+}
+
+impl NostrDigestSettings {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        if !self.pubkey.trim().is_empty() {
+            nostr::parse_pubkey(&self.pubkey)?;
+        } else if self.daily_digest {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                "A public key is required for the daily digest",
+            ));
+        }
+        if self.relays.is_empty() || self.relays.len() > 10 {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                "Between one and ten relays are required",
+            ));
+        }
+        for relay in &self.relays {
+            nostr::validate_relay(relay)?;
+        }
+        if self.digest_hour > 23 {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                "Digest hour must be between 0 and 23",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> NostrDigestSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Notes sharing a thread root.
+#[derive(Debug, PartialEq, Eq)]
+struct Thread<'a> {
+    root: &'a str,
+    notes: Vec<&'a Event>,
+}
+
+impl Thread<'_> {
+    fn latest(&self) -> i64 {
+        self.notes
+            .iter()
+            .map(|note| note.created_at)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// The root of a note's thread: the NIP-10 `root` marker, else the first
+/// `e` tag of the older positional form, else the note itself.
+fn thread_root(event: &Event) -> &str {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.len() >= 4 && tag[0] == "e" && tag[3] == "root")
+        .map(|tag| tag[1].as_str())
+        .or_else(|| event.tag_values("e").next())
+        .unwrap_or(&event.id)
+}
+
+/// Clusters notes into threads, busiest and then most recent first.
+fn threads(notes: &[Event]) -> Vec<Thread<'_>> {
+    let mut by_root: HashMap<&str, Vec<&Event>> = HashMap::new();
+    for note in notes {
+        by_root.entry(thread_root(note)).or_default().push(note);
+    }
+    let mut threads: Vec<Thread<'_>> = by_root
+        .into_iter()
+        .map(|(root, mut notes)| {
+            notes.sort_by_key(|note| note.created_at);
+            Thread { root, notes }
+        })
+        .collect();
+    threads.sort_by(|a, b| {
+        b.notes
+            .len()
+            .cmp(&a.notes.len())
+            .then_with(|| b.latest().cmp(&a.latest()))
+            .then_with(|| a.root.cmp(b.root))
+    });
+    threads
+}
+
+/// Display names from kind-0 metadata, newest profile per author.
+fn display_names(profiles: &[Event]) -> HashMap<String, String> {
+    let mut newest: HashMap<&str, &Event> = HashMap::new();
+    for profile in profiles {
+        let entry = newest.entry(profile.pubkey.as_str()).or_insert(profile);
+        if profile.created_at > entry.created_at {
+            *entry = profile;
+        }
+    }
+    newest
+        .into_iter()
+        .filter_map(|(pubkey, profile)| {
+            let metadata: serde_json::Value = serde_json::from_str(&profile.content).ok()?;
+            let name = ["display_name", "name"]
+                .iter()
+                .filter_map(|field| metadata.get(field)?.as_str())
+                .map(str::trim)
+                .find(|name| !name.is_empty())?;
+            Some((pubkey.to_string(), name.to_string()))
+        })
+        .collect()
+}
+
+/// Formats the busiest threads as the user message of a digest request.
+fn digest_input(threads: &[Thread<'_>], names: &HashMap<String, String>) -> String {
+    let mut input = String::new();
+    for (index, thread) in threads.iter().take(MAX_THREADS).enumerate() {
+        let _ = writeln!(
+            input,
+            "## Thread {} ({} notes)",
+            index + 1,
+            thread.notes.len()
+        );
+        for note in thread.notes.iter().take(MAX_NOTES_PER_THREAD) {
+            let author = names
+                .get(&note.pubkey)
+                .cloned()
+                .unwrap_or_else(|| format!("{}…", &note.pubkey[..8.min(note.pubkey.len())]));
+            let time = Local
+                .timestamp_opt(note.created_at, 0)
+                .single()
+                .map(|time| time.format("%H:%M").to_string())
+                .unwrap_or_default();
+            let content: String = note.content.trim().chars().take(MAX_NOTE_CHARS).collect();
+            let _ = writeln!(input, "[{time}] {author}: {content}");
+        }
+        input.push('\n');
+    }
+    input
+}
+
+/// Reads the contact list and the last day of notes from the follows.
+async fn fetch_notes(
+    app: &AppHandle,
+    settings: &NostrDigestSettings,
+) -> Result<(Vec<Event>, Vec<Event>), GibberError> {
+    let pubkey = nostr::parse_pubkey(&settings.pubkey)?;
+    let contact_lists = nostr::query(
+        app,
+        &settings.relays,
+        &[Filter {
+            authors: vec![pubkey],
+            kinds: vec![nostr::KIND_CONTACTS],
+            limit: Some(1),
+            ..Filter::default()
+        }],
+    )
+    .await?;
+    let Some(contacts) = contact_lists.iter().max_by_key(|event| event.created_at) else {
+        return Err(GibberError::new(
+            "NO_CONTACTS",
+            "No contact list found for this public key",
+        ));
+    };
+    let mut follows: Vec<String> = contacts
+        .tag_values("p")
+        .filter_map(|pubkey| nostr::parse_pubkey(pubkey).ok())
+        .collect();
+    follows.sort_unstable();
+    follows.dedup();
+    follows.truncate(MAX_FOLLOWS);
+    if follows.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let since = db::now_millis() / 1000 - DIGEST_PERIOD_SECS;
+    let filters: Vec<Filter> = follows
+        .chunks(AUTHORS_PER_FILTER)
+        .map(|authors| Filter {
+            authors: authors.to_vec(),
+            kinds: vec![nostr::KIND_TEXT_NOTE],
+            since: Some(since),
+            limit: Some(2_000),
+        })
+        .collect();
+    let notes = nostr::query(app, &settings.relays, &filters).await?;
+
+    let mut authors: Vec<String> = notes.iter().map(|note| note.pubkey.clone()).collect();
+    authors.sort_unstable();
+    authors.dedup();
+    let profile_filters: Vec<Filter> = authors
+        .chunks(AUTHORS_PER_FILTER)
+        .map(|authors| Filter {
+            authors: authors.to_vec(),
+            kinds: vec![nostr::KIND_METADATA],
+            ..Filter::default()
+        })
+        .collect();
+    let profiles = if profile_filters.is_empty() {
+        Vec::new()
+    } else {
+        // Names are a nicety; fall back to key prefixes without them.
+        nostr::query(app, &settings.relays, &profile_filters)
+            .await
+            .unwrap_or_default()
+    };
+    Ok((notes, profiles))
+}
+
+/// Returns the recurring digest conversation, creating it if needed.
+fn digest_conversation(conn: &Connection, model: Option<&str>) -> rusqlite::Result<String> {
+    if let Some(id) = db::read_setting(conn, CONVERSATION_KEY)? {
+        if conversations::conversation_exists(conn, &id)? {
+            return Ok(id);
+        }
+    }
+    let conversation = conversations::insert_conversation(
+        conn,
+        &NewConversation {
+            title: "Nostr digest".to_string(),
+            model: model.map(str::to_string),
+            system_prompt: None,
+            source: Some("nostr-digest".to_string()),
+        },
+    )?;
+    db::write_setting(conn, CONVERSATION_KEY, &conversation.id)?;
+    Ok(conversation.id)
+}
+
+/// Generates today's digest and appends it to the recurring conversation.
+///
+/// # Returns
+///
+/// The conversation ID, or `None` if the follows posted nothing.
+async fn digest(app: &AppHandle) -> Result<Option<String>, GibberError> {
+    let settings = load_settings(&app.state::<Database>().conn());
+    let (notes, profiles) = fetch_notes(app, &settings).await?;
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    if notes.is_empty() {
+        db::write_setting(&app.state::<Database>().conn(), LAST_DIGEST_KEY, &today)?;
+        return Ok(None);
+    }
+
+    let threads = threads(&notes);
+    let prompt = settings
+        .prompt
+        .as_deref()
+        .filter(|prompt| !prompt.trim().is_empty())
+        .unwrap_or(DEFAULT_PROMPT);
+    let request = ChatRequest::new(
+        settings.model.as_deref(),
+        vec![
+            ChatMessage::new(MessageRole::System, prompt),
+            ChatMessage::new(
+                MessageRole::User,
+                digest_input(&threads, &display_names(&profiles)),
+            ),
+        ],
+    );
+    let completion = chat::complete(app, &request).await?;
+
+    let conversation_id = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let conversation_id = digest_conversation(&conn, Some(&completion.model))?;
+        conversations::insert_message(
+            &conn,
+            &conversation_id,
+            &NewMessage {
+                role: MessageRole::User,
+                content: format!(
+                    "What happened on Nostr today ({today})? {} notes in {} threads.",
+                    notes.len(),
+                    threads.len()
+                ),
+                model: None,
+                usage: None,
+            },
+        )?;
+        conversations::insert_message(
+            &conn,
+            &conversation_id,
+            &NewMessage {
+                role: MessageRole::Assistant,
+                content: completion.content.trim().to_string(),
+                model: Some(completion.model.clone()),
+                usage: completion.usage,
+            },
+        )?;
+        db::write_setting(&conn, LAST_DIGEST_KEY, &today)?;
+        conversation_id
+    };
+    events::emit_typed(app, events::NOSTR_DIGEST, &conversation_id);
+    Ok(Some(conversation_id))
+}
+
+/// Whether the daily digest is due at `hour` on `today`.
+fn digest_due(
+    settings: &NostrDigestSettings,
+    last_digest: Option<&str>,
+    today: &str,
+    hour: u32,
+) -> bool {
+    settings.daily_digest && hour >= settings.digest_hour && last_digest != Some(today)
+}
+
+/// Starts the background loop that generates the daily digest.
+///
+/// Must be called after the [`Database`] and tray state have been added to
+/// managed state.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if tray::is_sync_paused(&app) {
+                continue;
+            }
+            let due = {
+                let db = app.state::<Database>();
+                let conn = db.conn();
+                let last_digest = db::read_setting(&conn, LAST_DIGEST_KEY).ok().flatten();
+                let local = Local::now();
+                digest_due(
+                    &load_settings(&conn),
+                    last_digest.as_deref(),
+                    &local.format("%Y-%m-%d").to_string(),
+                    local.hour(),
+                )
+            };
+            if due {
+                if let Err(e) = digest(&app).await {
+                    tracing::warn!(code = e.code(), "nostr digest failed: {e}");
+                }
+            }
+        }
+    });
+}
+
+/// Returns the Nostr digest preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_nostr_digest_settings(db: State<'_, Database>) -> NostrDigestSettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the Nostr digest preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_KEY`, `INVALID_RELAY`,
+/// or `INVALID_SETTINGS` naming the bad value, or `DATABASE` if the
+/// settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_nostr_digest_settings", {
+///   settings: { pubkey: "npub1…", relays: ["wss://nos.lol"], dailyDigest: true, digestHour: 21 },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_nostr_digest_settings(
+    db: State<'_, Database>,
+    settings: NostrDigestSettings,
+) -> Result<NostrDigestSettings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Generates a digest of the follows' notes from the last day now.
+///
+/// # Returns
+///
+/// The ID of the recurring digest conversation, or `null` if the follows
+/// posted nothing.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_KEY` if no valid public
+/// key is set, `NO_CONTACTS` if its contact list can't be found, `NETWORK`
+/// or `PROXY` if the relays can't be reached, or the chat error code if the
+/// summary fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const conversationId = await invoke("generate_nostr_digest");
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn generate_nostr_digest(app: AppHandle) -> Result<Option<String>, GibberError> {
+    digest(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(id: &str, pubkey: &str, created_at: i64, tags: &[&[&str]]) -> Event {
+        Event {
+            id: id.to_string(),
+            pubkey: pubkey.to_string(),
+            created_at,
+            kind: nostr::KIND_TEXT_NOTE,
+            tags: tags
+                .iter()
+                .map(|tag| tag.iter().map(|value| (*value).to_string()).collect())
+                .collect(),
+            content: format!("note {id}"),
+            sig: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_threads_cluster_by_root() {
+        let notes = vec![
+            note("a", "alice", 10, &[]),
+            note(
+                "b",
+                "bob",
+                20,
+                &[&["e", "a", "", "root"], &["e", "x", "", "reply"]],
+            ),
+            note("c", "carol", 30, &[&["e", "a"]]),
+            note("d", "dave", 40, &[]),
+        ];
+        let threads = threads(&notes);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].root, "a");
+        assert_eq!(
+            threads[0]
+                .notes
+                .iter()
+                .map(|n| n.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(threads[1].root, "d");
+    }
+
+    #[test]
+    fn test_display_names_prefer_newest_profile() {
+        let mut old = note("p1", "alice", 1, &[]);
+        old.content = r#"{"name":"old"}"#.to_string();
+        let mut new = note("p2", "alice", 2, &[]);
+        new.content = r#"{"display_name":"","name":"Alice"}"#.to_string();
+        let names = display_names(&[new, old]);
+        assert_eq!(names.get("alice").map(String::as_str), Some("Alice"));
+
+        let notes = vec![
+            note("a", "alice", 0, &[]),
+            note("b", "0123456789ab", 0, &[]),
+        ];
+        let input = digest_input(&threads(&notes), &names);
+        assert!(input.contains("Alice: note a"));
+        assert!(input.contains("01234567…: note b"));
+    }
+
+    #[test]
+    fn test_settings_validate() {
+        assert!(NostrDigestSettings::default().validate().is_ok());
+        let no_key = NostrDigestSettings {
+            daily_digest: true,
+            ..NostrDigestSettings::default()
+        };
+        assert_eq!(no_key.validate().unwrap_err().code(), "INVALID_SETTINGS");
+        let bad_relay = NostrDigestSettings {
+            relays: vec!["https://relay.example".to_string()],
+            ..NostrDigestSettings::default()
+        };
+        assert_eq!(bad_relay.validate().unwrap_err().code(), "INVALID_RELAY");
+    }
+}
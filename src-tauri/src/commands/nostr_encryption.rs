@@ -0,0 +1,241 @@
+//! NIP-44 (version 2) payload encryption.
+//!
+//! Keeps private Nostr events private, such as the composer's drafts,
+//! which are encrypted to the author's own key before they go to relays.
+//! A conversation key is derived from the two keys with ECDH and HKDF;
+//! each message is padded, encrypted with ChaCha20 under keys derived
+//! from a random nonce, and authenticated with HMAC-SHA256.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use k256::schnorr::SigningKey;
+use sha2::Sha256;
+
+use crate::error::GibberError;
+
+/// Payload format version.
+const VERSION: u8 = 2;
+
+/// HKDF salt of the conversation key.
+const SALT: &[u8] = b"nip44-v2";
+
+/// Longest plaintext, in bytes.
+pub const MAX_PLAINTEXT_BYTES: usize = 65_535;
+
+/// Shortest valid payload: version, nonce, the smallest padded message,
+/// and the MAC.
+const MIN_PAYLOAD_BYTES: usize = 1 + 32 + 2 + 32 + 32;
+
+fn error(message: &str) -> GibberError {
+    GibberError::new("ENCRYPTION", message)
+}
+
+/// Derives the conversation key between `secret` and the hex public key
+/// `pubkey`. It is the same from either side.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_KEY` if `pubkey` isn't a
+/// valid x-only public key.
+pub fn conversation_key(secret: &SigningKey, pubkey: &str) -> Result<[u8; 32], GibberError> {
+    let invalid = || GibberError::new("INVALID_KEY", format!("Not a Nostr public key: {pubkey}"));
+    let x = hex::decode(pubkey)
+        .ok()
+        .filter(|x| x.len() == 32)
+        .ok_or_else(invalid)?;
+    let mut sec1 = Vec::with_capacity(33);
+    sec1.push(0x02);
+    sec1.extend_from_slice(&x);
+    let public = k256::PublicKey::from_sec1_bytes(&sec1).map_err(|_| invalid())?;
+    let shared = k256::ecdh::diffie_hellman(secret.as_nonzero_scalar(), public.as_affine());
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(SALT), shared.raw_secret_bytes());
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&prk);
+    Ok(key)
+}
+
+/// Length a message of `len` bytes is padded to.
+const fn padded_len(len: usize) -> usize {
+    if len <= 32 {
+        return 32;
+    }
+    let next_power = 1_usize << (usize::BITS - (len - 1).leading_zeros());
+    let chunk = if next_power <= 256 {
+        32
+    } else {
+        next_power / 8
+    };
+    chunk * ((len - 1) / chunk + 1)
+}
+
+/// Keys of one message, derived from the conversation key and its nonce.
+struct MessageKeys {
+    cipher: [u8; 32],
+    nonce: [u8; 12],
+    mac: [u8; 32],
+}
+
+fn message_keys(conversation_key: &[u8; 32], nonce: &[u8; 32]) -> Result<MessageKeys, GibberError> {
+    let hkdf = Hkdf::<Sha256>::from_prk(conversation_key)
+        .map_err(|_| error("Invalid conversation key"))?;
+    let mut okm = [0u8; 76];
+    hkdf.expand(nonce, &mut okm)
+        .map_err(|_| error("Key derivation failed"))?;
+    let mut keys = MessageKeys {
+        cipher: [0; 32],
+        nonce: [0; 12],
+        mac: [0; 32],
+    };
+    keys.cipher.copy_from_slice(&okm[..32]);
+    keys.nonce.copy_from_slice(&okm[32..44]);
+    keys.mac.copy_from_slice(&okm[44..]);
+    Ok(keys)
+}
+
+fn mac(key: &[u8; 32], nonce: &[u8], ciphertext: &[u8]) -> Result<Hmac<Sha256>, GibberError> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key).map_err(|_| error("Invalid authentication key"))?;
+    mac.update(nonce);
+    mac.update(ciphertext);
+    Ok(mac)
+}
+
+fn encrypt_with_nonce(
+    conversation_key: &[u8; 32],
+    plaintext: &str,
+    nonce: &[u8; 32],
+) -> Result<String, GibberError> {
+    let bytes = plaintext.as_bytes();
+    let len = u16::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len > 0)
+        .ok_or_else(|| error("Messages must be between 1 and 65,535 bytes"))?;
+    let keys = message_keys(conversation_key, nonce)?;
+    let mut buffer = Vec::with_capacity(2 + padded_len(bytes.len()));
+    buffer.extend_from_slice(&len.to_be_bytes());
+    buffer.extend_from_slice(bytes);
+    buffer.resize(2 + padded_len(bytes.len()), 0);
+    ChaCha20::new(
+        chacha20::Key::from_slice(&keys.cipher),
+        chacha20::Nonce::from_slice(&keys.nonce),
+    )
+    .apply_keystream(&mut buffer);
+    let tag = mac(&keys.mac, nonce, &buffer)?.finalize().into_bytes();
+
+    let mut payload = Vec::with_capacity(1 + nonce.len() + buffer.len() + tag.len());
+    payload.push(VERSION);
+    payload.extend_from_slice(nonce);
+    payload.extend_from_slice(&buffer);
+    payload.extend_from_slice(&tag);
+    Ok(BASE64_STANDARD.encode(payload))
+}
+
+/// Encrypts `plaintext` with a fresh random nonce.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `ENCRYPTION` if the plaintext is
+/// empty or over 65,535 bytes.
+pub fn encrypt(conversation_key: &[u8; 32], plaintext: &str) -> Result<String, GibberError> {
+    let mut nonce = [0u8; 32];
+    OsRng.fill_bytes(&mut nonce);
+    encrypt_with_nonce(conversation_key, plaintext, &nonce)
+}
+
+/// Decrypts a payload made by [`encrypt`] or another NIP-44 client.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `ENCRYPTION` if the payload is
+/// malformed, of another version, or fails authentication.
+pub fn decrypt(conversation_key: &[u8; 32], payload: &str) -> Result<String, GibberError> {
+    let data = BASE64_STANDARD
+        .decode(payload.trim())
+        .map_err(|_| error("The payload isn't base64"))?;
+    if data.len() < MIN_PAYLOAD_BYTES {
+        return Err(error("The payload is too short"));
+    }
+    if data[0] != VERSION {
+        return Err(error("Unsupported encryption version"));
+    }
+    let (nonce, rest) = data[1..].split_at(32);
+    let (ciphertext, tag) = rest.split_at(rest.len() - 32);
+    let mut nonce_bytes = [0u8; 32];
+    nonce_bytes.copy_from_slice(nonce);
+    let keys = message_keys(conversation_key, &nonce_bytes)?;
+    mac(&keys.mac, nonce, ciphertext)?
+        .verify_slice(tag)
+        .map_err(|_| error("The payload failed authentication"))?;
+
+    let mut buffer = ciphertext.to_vec();
+    ChaCha20::new(
+        chacha20::Key::from_slice(&keys.cipher),
+        chacha20::Nonce::from_slice(&keys.nonce),
+    )
+    .apply_keystream(&mut buffer);
+    let len = usize::from(u16::from_be_bytes([buffer[0], buffer[1]]));
+    if len == 0 || buffer.len() != 2 + padded_len(len) {
+        return Err(error("Invalid padding"));
+    }
+    buffer.truncate(2 + len);
+    buffer.drain(..2);
+    String::from_utf8(buffer).map_err(|_| error("The message isn't UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(last: u8) -> SigningKey {
+        let mut bytes = [0u8; 32];
+        bytes[31] = last;
+        SigningKey::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_matches_the_nip44_vector() {
+        let pubkey = "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        let conversation = conversation_key(&key(1), pubkey).unwrap();
+        assert_eq!(
+            hex::encode(conversation),
+            "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d"
+        );
+        let mut nonce = [0u8; 32];
+        nonce[31] = 1;
+        let payload = encrypt_with_nonce(&conversation, "a", &nonce).unwrap();
+        assert_eq!(
+            payload,
+            "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABee0G5VSK0/9YypIObAtDKfYEAjD35uVkHyB0F4Dwr\
+             cNaCXlCWZKaArsGrY6M9wnuTMxWfp1RTN9Xga8no+kF5Vsb"
+        );
+        assert_eq!(decrypt(&conversation, &payload).unwrap(), "a");
+    }
+
+    #[test]
+    fn test_round_trip_and_tampering() {
+        let own = key(7);
+        let pubkey = hex::encode(own.verifying_key().to_bytes());
+        let conversation = conversation_key(&own, &pubkey).unwrap();
+        let text = "A draft about modular synths. ".repeat(20);
+        let payload = encrypt(&conversation, &text).unwrap();
+        assert_eq!(decrypt(&conversation, &payload).unwrap(), text);
+
+        let mut data = BASE64_STANDARD.decode(&payload).unwrap();
+        data[40] ^= 1;
+        let tampered = BASE64_STANDARD.encode(data);
+        assert_eq!(
+            decrypt(&conversation, &tampered).unwrap_err().code(),
+            "ENCRYPTION"
+        );
+        assert!(encrypt(&conversation, "").is_err());
+        assert_eq!(
+            [padded_len(1), padded_len(33), padded_len(257)],
+            [32, 64, 320]
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Native desktop notifications for finished work.
+//!
+//! Completed generations, scheduled tasks, and workflow runs raise an OS
+//! notification when the app is in the background. Each event type can be
+//! switched off in [`NotificationSettings`].
+//!
+//! The notification plugin doesn't report clicks on desktop, so a click is
+//! inferred: when the app regains focus shortly after a notification was shown,
+//! [`events::NOTIFICATION_ACTIVATED`] is emitted to the window showing the
+//! related conversation so it can open it.
+
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::events;
+use crate::commands::windows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the notification preferences.
+pub const SETTINGS_KEY: &str = "notifications";
+
+/// How long after a notification focusing the app counts as clicking it.
+const ACTIVATION_WINDOW: Duration = Duration::from_mins(2);
+
+/// Longest notification body, in characters.
+const MAX_BODY_CHARS: usize = 160;
+
+/// The kind of event a notification reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationKind {
+    /// A chat generation finished
+    Generation,
+    /// A scheduled task ran
+    ScheduledTask,
+    /// A workflow run finished
+    Workflow,
+    /// New clipboard content can be asked about
+    Clipboard,
+}
+
+/// Per-event notification preferences.
+#[allow(clippy::struct_excessive_bools)] // one switch per event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    /// Master switch for all notifications
+    pub enabled: bool,
+    /// Only notify while no app window is focused
+    pub only_when_unfocused: bool,
+    /// Notify when a chat generation finishes
+    pub generation: bool,
+    /// Notify when a scheduled task runs
+    pub scheduled_task: bool,
+    /// Notify when a workflow run finishes
+    pub workflow: bool,
+    /// Offer to ask about newly copied content
+    pub clipboard: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            only_when_unfocused: true,
+            generation: true,
+            scheduled_task: true,
+            workflow: true,
+            clipboard: true,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Returns whether notifications of `kind` are enabled.
+    pub fn allows(&self, kind: NotificationKind) -> bool {
+        self.enabled
+            && match kind {
+                NotificationKind::Generation => self.generation,
+                NotificationKind::ScheduledTask => self.scheduled_task,
+                NotificationKind::Workflow => self.workflow,
+                NotificationKind::Clipboard => self.clipboard,
+            }
+    }
+}
+
+/// Payload of [`events::NOTIFICATION_ACTIVATED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivatedPayload {
+    /// Kind of the notification that was followed
+    pub kind: NotificationKind,
+    /// Conversation the notification was about, if any
+    pub conversation_id: Option<String>,
+}
+
+struct Shown {
+    payload: ActivatedPayload,
+    at: Instant,
+}
+
+/// The most recent notification, until the app is focused.
+#[derive(Default)]
+pub struct LastNotification(Mutex<Option<Shown>>);
+
+/// Loads the notification preferences, falling back to defaults.
+///
+/// # Errors
+///
+/// Returns an error if the settings query fails.
+pub fn load_settings(conn: &Connection) -> rusqlite::Result<NotificationSettings> {
+    Ok(db::read_setting(conn, SETTINGS_KEY)?
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+/// Stores the notification preferences.
+///
+/// # Errors
+///
+/// Returns an error if the settings cannot be stored.
+pub fn save_settings(conn: &Connection, settings: &NotificationSettings) -> rusqlite::Result<()> {
+    let value = serde_json::to_string(settings).unwrap_or_default();
+    db::write_setting(conn, SETTINGS_KEY, &value)
+}
+
+/// Shortens `text` to a single-line notification body.
+fn preview(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= MAX_BODY_CHARS {
+        line
+    } else {
+        let cut: String = line.chars().take(MAX_BODY_CHARS - 1).collect();
+        format!("{}…", cut.trim_end())
+    }
+}
+
+fn app_focused(app: &AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+/// Shows a notification if the user's settings allow it.
+///
+/// Failures are logged rather than returned: a missing notification should
+/// never fail the work it reports on.
+pub fn notify(
+    app: &AppHandle,
+    kind: NotificationKind,
+    title: &str,
+    body: &str,
+    conversation_id: Option<&str>,
+) {
+    let settings = match load_settings(&app.state::<Database>().conn()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::error!("failed to load settings: {e}");
+            return;
+        }
+    };
+    if !settings.allows(kind) || (settings.only_when_unfocused && app_focused(app)) {
+        return;
+    }
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(preview(body))
+        .show()
+    {
+        tracing::error!("failed to show notification: {e}");
+        return;
+    }
+    *app.state::<LastNotification>()
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Shown {
+        payload: ActivatedPayload {
+            kind,
+            conversation_id: conversation_id.map(str::to_string),
+        },
+        at: Instant::now(),
+    });
+}
+
+/// Handles an app window gaining focus.
+///
+/// If a notification was shown recently, the focus is treated as a click on it
+/// and [`events::NOTIFICATION_ACTIVATED`] is sent to the window showing its
+/// conversation.
+pub fn handle_focus(app: &AppHandle) {
+    let shown = app
+        .state::<LastNotification>()
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    let Some(shown) = shown.filter(|s| s.at.elapsed() <= ACTIVATION_WINDOW) else {
+        return;
+    };
+    let conversation_id = shown.payload.conversation_id.clone().unwrap_or_default();
+    if let Err(e) = windows::emit_to_conversation(
+        app,
+        &conversation_id,
+        events::NOTIFICATION_ACTIVATED,
+        &shown.payload,
+    ) {
+        tracing::warn!("failed to emit activation: {e}");
+    }
+}
+
+/// Returns the notification preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be read.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_notification_settings(
+    db: State<'_, Database>,
+) -> Result<NotificationSettings, GibberError> {
+    Ok(load_settings(&db.conn())?)
+}
+
+/// Replaces the notification preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_notification_settings", {
+///   settings: { ...current, scheduledTask: false },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_notification_settings(
+    db: State<'_, Database>,
+    settings: NotificationSettings,
+) -> Result<NotificationSettings, GibberError> {
+    save_settings(&db.conn(), &settings)?;
+    Ok(settings)
+}
+
+/// Reports a finished frontend generation so it can be notified.
+///
+/// Does nothing when generation notifications are disabled or the app is
+/// focused.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("notify_generation_complete", { conversationId, title, content });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn notify_generation_complete(
+    app: AppHandle,
+    conversation_id: String,
+    title: String,
+    content: String,
+) {
+    notify(
+        &app,
+        NotificationKind::Generation,
+        &title,
+        &content,
+        Some(&conversation_id),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_gate_by_kind() {
+        let mut settings = NotificationSettings::default();
+        assert!(settings.allows(NotificationKind::Generation));
+        settings.workflow = false;
+        assert!(!settings.allows(NotificationKind::Workflow));
+        settings.enabled = false;
+        assert!(!settings.allows(NotificationKind::Generation));
+    }
+
+    #[test]
+    fn test_settings_fill_missing_fields() {
+        let settings: NotificationSettings =
+            serde_json::from_str(r#"{"scheduledTask":false}"#).unwrap();
+        assert!(!settings.scheduled_task);
+        assert!(settings.generation);
+    }
+
+    #[test]
+    fn test_settings_persist() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        assert_eq!(
+            load_settings(&conn).unwrap(),
+            NotificationSettings::default()
+        );
+        let settings = NotificationSettings {
+            generation: false,
+            ..NotificationSettings::default()
+        };
+        save_settings(&conn, &settings).unwrap();
+        assert_eq!(load_settings(&conn).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_preview_truncates() {
+        assert_eq!(preview("a\n  b"), "a b");
+        let long = "x".repeat(500);
+        assert_eq!(preview(&long).chars().count(), MAX_BODY_CHARS);
+    }
+}
@@ -0,0 +1,592 @@
+//! Offline mode and the request outbox.
+//!
+//! A background probe tracks whether OpenRouter is reachable and reports
+//! every change as [`events::NETWORK_STATUS`]; a chat request failing with
+//! `NETWORK_ERROR` marks the app offline right away. While offline the user
+//! can keep writing: `enqueue_message` stores the message in its
+//! conversation and queues a request for the reply in a persistent outbox.
+//! When connectivity returns, queued requests are sent oldest first and the
+//! replies are stored in their conversations.
+//!
+//! Each change to the outbox is broadcast as [`events::OUTBOX_CHANGED`] with
+//! the full list, and each delivered reply as [`events::OUTBOX_SENT`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::conversations::{self, NewMessage, StoredMessage};
+use crate::commands::events;
+use crate::commands::i18n;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::notifications::{self, NotificationKind};
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// URL probed to decide whether the app is online.
+const PROBE_URL: &str = "https://openrouter.ai/api/v1/models";
+
+/// Delay between probes.
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Time allowed for a probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Outbox entry not found: {id}"))
+}
+
+/// Delivery state of a queued request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    /// Waiting for connectivity
+    Queued,
+    /// Being sent now
+    Sending,
+    /// Rejected by the provider; needs a retry or discard
+    Failed,
+}
+
+impl OutboxStatus {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Sending => "sending",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "sending" => Self::Sending,
+            "failed" => Self::Failed,
+            _ => Self::Queued,
+        }
+    }
+}
+
+/// A queued request for a reply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEntry {
+    /// Unique identifier
+    pub id: String,
+    /// Conversation to answer in
+    pub conversation_id: String,
+    /// The user message awaiting a reply
+    pub message_id: String,
+    /// Model override; the conversation's model otherwise
+    pub model: Option<String>,
+    /// Delivery state
+    pub status: OutboxStatus,
+    /// Delivery attempts so far
+    pub attempts: u32,
+    /// Error of the last attempt
+    pub last_error: Option<String>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// Payload of [`events::NETWORK_STATUS`].
+#[derive(Debug, Clone, Copy, Serialize, specta::Type)]
+pub struct NetworkStatus {
+    /// Whether OpenRouter is reachable
+    pub online: bool,
+}
+
+/// Payload of [`events::OUTBOX_SENT`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SentPayload {
+    /// The delivered entry
+    pub entry_id: String,
+    /// The stored reply
+    pub message: StoredMessage,
+}
+
+/// Managed connectivity state.
+pub struct Connectivity {
+    online: AtomicBool,
+    flushing: AtomicBool,
+}
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        Self {
+            online: AtomicBool::new(true),
+            flushing: AtomicBool::new(false),
+        }
+    }
+}
+
+const ENTRY_COLUMNS: &str =
+    "id, conversation_id, message_id, model, status, attempts, last_error, created_at";
+
+fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<OutboxEntry> {
+    let status: String = row.get(4)?;
+    Ok(OutboxEntry {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        message_id: row.get(2)?,
+        model: row.get(3)?,
+        status: OutboxStatus::parse(&status),
+        attempts: row.get(5)?,
+        last_error: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+fn list_entries(conn: &Connection) -> rusqlite::Result<Vec<OutboxEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ENTRY_COLUMNS} FROM outbox ORDER BY created_at, rowid"
+    ))?;
+    let entries = stmt.query_map([], entry_from_row)?.collect();
+    entries
+}
+
+fn load_entry(conn: &Connection, id: &str) -> rusqlite::Result<Option<OutboxEntry>> {
+    conn.query_row(
+        &format!("SELECT {ENTRY_COLUMNS} FROM outbox WHERE id = ?1"),
+        [id],
+        entry_from_row,
+    )
+    .optional()
+}
+
+fn set_status(
+    conn: &Connection,
+    id: &str,
+    status: OutboxStatus,
+    error: Option<&str>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE outbox SET status = ?1, last_error = ?2, updated_at = ?3 WHERE id = ?4",
+        params![status.as_str(), error, db::now_millis(), id],
+    )?;
+    Ok(())
+}
+
+/// Builds the request answering `entry` from its conversation history.
+fn request_for(conn: &Connection, entry: &OutboxEntry) -> Result<ChatRequest, GibberError> {
+    let conversation = conversations::load_conversation(conn, &entry.conversation_id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", "The conversation no longer exists"))?;
+    let mut messages = Vec::new();
+    if let Some(system) = conversation
+        .conversation
+        .system_prompt
+        .as_deref()
+        .filter(|s| !s.is_empty())
+    {
+        messages.push(ChatMessage::new(MessageRole::System, system));
+    }
+    for message in &conversation.messages {
+        messages.push(ChatMessage::new(message.role, message.content.clone()));
+        if message.id == entry.message_id {
+            break;
+        }
+    }
+    let model = entry
+        .model
+        .as_deref()
+        .or(conversation.conversation.model.as_deref());
+    Ok(ChatRequest::new(model, messages).for_conversation(&entry.conversation_id))
+}
+
+fn emit_changed(app: &AppHandle) {
+    let entries = list_entries(&app.state::<Database>().conn());
+    match entries {
+        Ok(entries) => {
+            events::emit_typed(app, events::OUTBOX_CHANGED, &entries);
+        }
+        Err(e) => tracing::error!("failed to list outbox: {e}"),
+    }
+}
+
+/// Records the outcome of a request to OpenRouter.
+///
+/// Going online sends whatever is queued.
+pub(crate) fn set_online(app: &AppHandle, online: bool) {
+    let Some(connectivity) = app.try_state::<Connectivity>() else {
+        return;
+    };
+    if connectivity.online.swap(online, Ordering::Relaxed) == online {
+        return;
+    }
+    tracing::info!(online, "connectivity changed");
+    events::emit_typed(app, events::NETWORK_STATUS, &NetworkStatus { online });
+    if online {
+        flush(app);
+    }
+}
+
+/// Returns `true` unless connectivity is known to be lost.
+pub fn is_online(app: &AppHandle) -> bool {
+    app.try_state::<Connectivity>()
+        .is_none_or(|connectivity| connectivity.online.load(Ordering::Relaxed))
+}
+
+async fn probe(app: &AppHandle) -> bool {
+    let Ok(client) = network::client(app, Service::OpenRouter) else {
+        return false;
+    };
+    // Any HTTP answer means the network works.
+    let builder = client.head(PROBE_URL).timeout(PROBE_TIMEOUT);
+    network_activity::send(app, Service::OpenRouter, "outbox.probe", builder)
+        .await
+        .is_ok()
+}
+
+/// Sends one entry.
+///
+/// # Returns
+///
+/// `false` if the network failed and sending should stop.
+async fn deliver(app: &AppHandle, entry: &OutboxEntry) -> bool {
+    let request = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let request = request_for(&conn, entry);
+        if request.is_ok() {
+            if let Err(e) = conn.execute(
+                "UPDATE outbox SET status = 'sending', attempts = attempts + 1, updated_at = ?1 WHERE id = ?2",
+                params![db::now_millis(), entry.id],
+            ) {
+                tracing::error!("failed to update outbox entry: {e}");
+            }
+        }
+        request
+    };
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => {
+            if let Err(e) = set_status(
+                &app.state::<Database>().conn(),
+                &entry.id,
+                OutboxStatus::Failed,
+                Some(&e.to_string()),
+            ) {
+                tracing::error!("failed to update outbox entry: {e}");
+            }
+            return true;
+        }
+    };
+    emit_changed(app);
+
+    let result = chat::complete(app, &request).await;
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    let (stored, keep_going) = match result {
+        Ok(completion) => {
+            let stored = conversations::insert_message(
+                &conn,
+                &entry.conversation_id,
+                &NewMessage {
+                    role: MessageRole::Assistant,
+                    content: completion.content,
+                    model: Some(completion.model),
+                    usage: completion.usage,
+                },
+            )
+            .and_then(|message| {
+                conn.execute("DELETE FROM outbox WHERE id = ?1", [&entry.id])?;
+                Ok(message)
+            });
+            match stored {
+                Ok(message) => (Some(message), true),
+                Err(e) => {
+                    tracing::error!("failed to store outbox reply: {e}");
+                    (None, true)
+                }
+            }
+        }
+        Err(e) if e.code() == "NETWORK_ERROR" => {
+            if let Err(e) = set_status(&conn, &entry.id, OutboxStatus::Queued, Some(&e.to_string()))
+            {
+                tracing::error!("failed to update outbox entry: {e}");
+            }
+            (None, false)
+        }
+        Err(e) => {
+            if let Err(e) = set_status(&conn, &entry.id, OutboxStatus::Failed, Some(&e.to_string()))
+            {
+                tracing::error!("failed to update outbox entry: {e}");
+            }
+            (None, true)
+        }
+    };
+    drop(conn);
+    if let Some(message) = stored {
+        notifications::notify(
+            app,
+            NotificationKind::Generation,
+            &i18n::text(app, "notification-reply-ready"),
+            &message.content,
+            Some(&entry.conversation_id),
+        );
+        let payload = SentPayload {
+            entry_id: entry.id.clone(),
+            message,
+        };
+        events::emit_typed(app, events::OUTBOX_SENT, &payload);
+    }
+    emit_changed(app);
+    keep_going
+}
+
+/// Sends queued entries in the background, unless a flush is running.
+fn flush(app: &AppHandle) {
+    let connectivity = app.state::<Connectivity>();
+    if connectivity.flushing.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let queued: Vec<OutboxEntry> = list_entries(&app.state::<Database>().conn())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| entry.status == OutboxStatus::Queued)
+            .collect();
+        for entry in queued {
+            if !is_online(&app) || !deliver(&app, &entry).await {
+                break;
+            }
+        }
+        app.state::<Connectivity>()
+            .flushing
+            .store(false, Ordering::Relaxed);
+    });
+}
+
+/// Manages connectivity state and starts the probe loop.
+///
+/// Entries left `sending` by a previous run are queued again. Must be
+/// called after the [`Database`] has been added to managed state.
+pub fn start(app: &AppHandle) {
+    if let Err(e) = app.state::<Database>().conn().execute(
+        "UPDATE outbox SET status = 'queued' WHERE status = 'sending'",
+        [],
+    ) {
+        tracing::error!("failed to requeue outbox: {e}");
+    }
+    app.manage(Connectivity::default());
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PROBE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let online = probe(&app).await;
+            set_online(&app, online);
+            if online {
+                flush(&app);
+            }
+        }
+    });
+}
+
+/// Returns whether the app is online.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn get_network_status(app: AppHandle) -> NetworkStatus {
+    NetworkStatus {
+        online: is_online(&app),
+    }
+}
+
+/// Stores a user message and queues the request for its reply.
+///
+/// The request is sent right away when online, otherwise as soon as
+/// connectivity returns.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation
+/// doesn't exist, or `DATABASE` if the message cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const entry = await invoke("enqueue_message", {
+///   conversationId,
+///   content: "Make the bassline swing more",
+///   model: null,
+/// });
+/// await listen("outbox://sent", (e) => appendMessage(e.payload.message));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn enqueue_message(
+    app: AppHandle,
+    conversation_id: String,
+    content: String,
+    model: Option<String>,
+) -> Result<OutboxEntry, GibberError> {
+    let entry = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        if !conversations::conversation_exists(&conn, &conversation_id)? {
+            return Err(GibberError::new(
+                "NOT_FOUND",
+                format!("Conversation not found: {conversation_id}"),
+            ));
+        }
+        let message = conversations::insert_message(
+            &conn,
+            &conversation_id,
+            &NewMessage {
+                role: MessageRole::User,
+                content,
+                model: None,
+                usage: None,
+            },
+        )?;
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO outbox (id, conversation_id, message_id, model, status, attempts, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, 'queued', 0, ?5, ?5)",
+            params![id, conversation_id, message.id, model, message.created_at],
+        )?;
+        load_entry(&conn, &id)?.ok_or_else(|| not_found(&id))?
+    };
+    emit_changed(&app);
+    if is_online(&app) {
+        flush(&app);
+    }
+    Ok(entry)
+}
+
+/// Lists queued requests, oldest first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_outbox(db: State<'_, Database>) -> Result<Vec<OutboxEntry>, GibberError> {
+    Ok(list_entries(&db.conn())?)
+}
+
+/// Queues a failed request again.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the entry doesn't exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn retry_outbox_entry(app: AppHandle, id: &str) -> Result<OutboxEntry, GibberError> {
+    let entry = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        set_status(&conn, id, OutboxStatus::Queued, None)?;
+        load_entry(&conn, id)?.ok_or_else(|| not_found(id))?
+    };
+    emit_changed(&app);
+    if is_online(&app) {
+        flush(&app);
+    }
+    Ok(entry)
+}
+
+/// Removes a request from the outbox; its user message stays in the
+/// conversation.
+///
+/// # Returns
+///
+/// Returns `true` if the entry was removed, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn discard_outbox_entry(app: AppHandle, id: &str) -> Result<bool, GibberError> {
+    let deleted = app
+        .state::<Database>()
+        .conn()
+        .execute("DELETE FROM outbox WHERE id = ?1", [id])?;
+    emit_changed(&app);
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::conversations::NewConversation;
+
+    fn queued(conn: &Connection, conversation_id: &str, content: &str) -> OutboxEntry {
+        let message = conversations::insert_message(
+            conn,
+            conversation_id,
+            &NewMessage {
+                role: MessageRole::User,
+                content: content.to_string(),
+                model: None,
+                usage: None,
+            },
+        )
+        .unwrap();
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO outbox (id, conversation_id, message_id, model, status, attempts, created_at, updated_at)
+             VALUES (?1, ?2, ?3, NULL, 'queued', 0, ?4, ?4)",
+            params![id, conversation_id, message.id, message.created_at],
+        )
+        .unwrap();
+        load_entry(conn, &id).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_request_stops_at_queued_message() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let conversation = conversations::insert_conversation(
+            &conn,
+            &NewConversation {
+                title: "Offline".to_string(),
+                model: Some("openai/gpt-4o".to_string()),
+                system_prompt: Some("Be brief".to_string()),
+                source: None,
+            },
+        )
+        .unwrap();
+        let first = queued(&conn, &conversation.id, "first");
+        queued(&conn, &conversation.id, "second");
+
+        let request = request_for(&conn, &first).unwrap();
+        assert_eq!(request.model, "openai/gpt-4o");
+        let contents: Vec<_> = request
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["Be brief", "first"]);
+    }
+
+    #[test]
+    fn test_entries_cascade_with_conversation() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let conversation =
+            conversations::insert_prompt_conversation(&conn, "hello", None, "chat").unwrap();
+        let entry = queued(&conn, &conversation.id, "are you there?");
+        assert_eq!(entry.status, OutboxStatus::Queued);
+        assert_eq!(list_entries(&conn).unwrap().len(), 1);
+
+        conn.execute(
+            "DELETE FROM conversations WHERE id = ?1",
+            [&conversation.id],
+        )
+        .unwrap();
+        assert!(list_entries(&conn).unwrap().is_empty());
+    }
+}
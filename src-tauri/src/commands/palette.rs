@@ -0,0 +1,233 @@
+//! Data for the command palette.
+//!
+//! The palette lists conversations, presets (which carry the personas and
+//! serve as templates), and app actions. With thousands of conversations,
+//! filtering in the webview on every keystroke gets slow, so the items are
+//! gathered and fuzzy-matched here and only the best come back.
+//!
+//! A query matches an item when its characters appear in the title in
+//! order, ignoring case and whitespace in the query. Matches at word starts
+//! and runs of consecutive characters rank higher; ties keep the unfiltered
+//! order of actions, presets, then conversations by recent activity.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::i18n;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Items returned when no limit is given.
+const DEFAULT_LIMIT: u32 = 50;
+
+/// App actions as (ID, message ID of the title).
+const ACTIONS: [(&str, &str); 7] = [
+    ("new-chat", "palette-new-chat"),
+    ("search", "palette-search"),
+    ("quick-capture", "palette-quick-capture"),
+    ("open-settings", "palette-open-settings"),
+    ("pause-sync", "palette-pause-sync"),
+    ("check-updates", "palette-check-updates"),
+    ("diagnostics", "palette-diagnostics"),
+];
+
+/// What a palette item refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum PaletteKind {
+    /// An app action; the ID names it
+    Action,
+    /// A new-chat preset; the ID is the preset's
+    Preset,
+    /// A conversation; the ID is the conversation's
+    Conversation,
+}
+
+/// An entry of the command palette.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteItem {
+    /// What the item refers to
+    pub kind: PaletteKind,
+    /// Action name or record ID
+    pub id: String,
+    /// Text shown and matched
+    pub title: String,
+    /// Secondary text, such as a preset's persona
+    pub subtitle: Option<String>,
+    /// Character indices of `title` matched by the query, for highlighting
+    pub matches: Vec<u32>,
+}
+
+impl PaletteItem {
+    fn new(kind: PaletteKind, id: String, title: String, subtitle: Option<String>) -> Self {
+        Self {
+            kind,
+            id,
+            title,
+            subtitle,
+            matches: Vec::new(),
+        }
+    }
+}
+
+fn same_char(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Matches `query` against `text`, returning the score and the matched
+/// character indices.
+fn fuzzy_match(query: &str, text: &str) -> Option<(u32, Vec<u32>)> {
+    let text: Vec<char> = text.chars().collect();
+    let mut matches = Vec::new();
+    let mut score = 0;
+    let mut next = 0;
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let offset = text[next..].iter().position(|&c| same_char(c, wanted))?;
+        let index = next + offset;
+        score += 1;
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if offset == 0 && index > 0 && !matches.is_empty() {
+            score += 4;
+        }
+        matches.push(u32::try_from(index).unwrap_or(u32::MAX));
+        next = index + 1;
+    }
+    Some((score, matches))
+}
+
+/// Keeps the items matching `query`, best first, at most `limit`.
+fn rank(items: Vec<PaletteItem>, query: &str, limit: usize) -> Vec<PaletteItem> {
+    if query.trim().is_empty() {
+        return items.into_iter().take(limit).collect();
+    }
+    let mut scored: Vec<(u32, PaletteItem)> = items
+        .into_iter()
+        .filter_map(|mut item| {
+            let (score, matches) = fuzzy_match(query, &item.title)?;
+            item.matches = matches;
+            Some((score, item))
+        })
+        .collect();
+    // A stable sort keeps the unfiltered order among equal scores.
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, item)| item)
+        .collect()
+}
+
+/// Loads presets and conversations, presets first.
+fn records(conn: &Connection) -> rusqlite::Result<Vec<PaletteItem>> {
+    let mut items = Vec::new();
+    let mut stmt =
+        conn.prepare("SELECT id, name, persona FROM presets ORDER BY name COLLATE NOCASE")?;
+    for preset in stmt.query_map([], |row| {
+        let persona: Option<String> = row.get(2)?;
+        Ok(PaletteItem::new(
+            PaletteKind::Preset,
+            row.get(0)?,
+            row.get(1)?,
+            persona.and_then(|p| p.lines().next().map(str::to_string)),
+        ))
+    })? {
+        items.push(preset?);
+    }
+    let mut stmt = conn.prepare("SELECT id, title FROM conversations ORDER BY updated_at DESC")?;
+    for conversation in stmt.query_map([], |row| {
+        Ok(PaletteItem::new(
+            PaletteKind::Conversation,
+            row.get(0)?,
+            row.get(1)?,
+            None,
+        ))
+    })? {
+        items.push(conversation?);
+    }
+    Ok(items)
+}
+
+/// Returns the command palette entries matching `query`.
+///
+/// Without a query, the actions come first, then presets by name, then
+/// conversations by recent activity.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `DATABASE` if the records cannot be
+/// read.
+///
+/// # Example
+///
+/// ```typescript
+/// const items = await invoke("get_command_palette_actions", { query: "bss", limit: 20 });
+/// items.forEach((item) => render(item.title, item.matches));
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn get_command_palette_actions(
+    app: AppHandle,
+    query: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<PaletteItem>, GibberError> {
+    let mut items: Vec<PaletteItem> = ACTIONS
+        .iter()
+        .map(|(id, message)| {
+            PaletteItem::new(
+                PaletteKind::Action,
+                (*id).to_string(),
+                i18n::text(&app, message),
+                None,
+            )
+        })
+        .collect();
+    let limit = usize::try_from(limit.unwrap_or(DEFAULT_LIMIT)).unwrap_or(usize::MAX);
+    tauri::async_runtime::spawn_blocking(move || -> Result<_, GibberError> {
+        items.extend(records(&app.state::<Database>().conn())?);
+        Ok(rank(items, query.as_deref().unwrap_or_default(), limit))
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str) -> PaletteItem {
+        PaletteItem::new(
+            PaletteKind::Conversation,
+            title.to_string(),
+            title.to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_fuzzy_match_prefers_word_starts() {
+        assert_eq!(fuzzy_match("bl", "Bass line").unwrap().1, vec![0, 5]);
+        assert!(fuzzy_match("xyz", "Bass line").is_none());
+        assert!(fuzzy_match("ÉTÉ", "Un été").is_some());
+        let (word_starts, _) = fuzzy_match("bl", "Bass line").unwrap();
+        let (inside, _) = fuzzy_match("bl", "Dub cables").unwrap();
+        assert!(word_starts > inside);
+    }
+
+    #[test]
+    fn test_rank_filters_and_keeps_order_on_ties() {
+        let items = vec![item("Drum fill"), item("Dub delay"), item("Chords")];
+        let ranked = rank(items.clone(), "d", 10);
+        assert_eq!(
+            ranked.iter().map(|i| i.title.as_str()).collect::<Vec<_>>(),
+            vec!["Drum fill", "Dub delay", "Chords"]
+        );
+        let ranked = rank(items.clone(), "dly", 10);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].matches, vec![0, 6, 8]);
+        assert_eq!(rank(items, "", 2).len(), 2);
+    }
+}
@@ -0,0 +1,466 @@
+//! Smart paste.
+//!
+//! Large pastes reach the model in better shape when they are marked up:
+//! `prepare_paste` tells JSON, CSV, logs, and code apart from prose, wraps
+//! them in a fence of the right language (pretty-printing JSON and turning
+//! CSV into a Markdown table), and cuts text that doesn't fit the context
+//! budget, leaving a notice of what was left out. Logs keep their end,
+//! where the latest lines are; everything else keeps its start.
+//!
+//! Short single-line pastes and text that already has fences are left
+//! alone. Tokens are estimated at four characters each.
+
+use std::fmt::Write as _;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::commands::markdown;
+use crate::error::GibberError;
+
+/// Pastes shorter than this on a single line are never reformatted.
+const MIN_CHARS: usize = 80;
+
+/// Budget used when the caller doesn't give one, in tokens.
+const DEFAULT_BUDGET: u32 = 8_000;
+
+/// Rough characters per token for estimates.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Most rows turned into a Markdown table; longer CSV stays fenced.
+const MAX_TABLE_ROWS: usize = 200;
+
+/// Markers counted to guess the language of code, as (language, markers).
+const LANGUAGE_MARKERS: [(&str, &[&str]); 11] = [
+    (
+        "rust",
+        &[
+            "fn ", "let mut ", "impl ", "pub fn", "#[derive", "::new(", "-> ",
+        ],
+    ),
+    (
+        "python",
+        &[
+            "def ", "import ", "self.", "elif ", "print(", "__init__", "lambda ",
+        ],
+    ),
+    (
+        "typescript",
+        &[
+            "interface ",
+            ": string",
+            ": number",
+            "export type",
+            "readonly ",
+        ],
+    ),
+    (
+        "javascript",
+        &[
+            "const ",
+            "function ",
+            "=> ",
+            "console.log",
+            "require(",
+            "let ",
+        ],
+    ),
+    ("go", &["package ", "func ", ":= ", "fmt.", "err != nil"]),
+    (
+        "java",
+        &[
+            "public class",
+            "System.out",
+            "private ",
+            "void ",
+            "@Override",
+        ],
+    ),
+    (
+        "cpp",
+        &["#include", "std::", "int main", "nullptr", "template<"],
+    ),
+    (
+        "sql",
+        &[
+            "SELECT ",
+            "FROM ",
+            "WHERE ",
+            "INSERT INTO",
+            "CREATE TABLE",
+            "JOIN ",
+        ],
+    ),
+    (
+        "html",
+        &["<div", "<html", "</", "<!DOCTYPE", "<span", "class=\""],
+    ),
+    (
+        "css",
+        &["px;", "color:", "margin:", "padding:", "display:", "@media"],
+    ),
+    (
+        "bash",
+        &["#!/bin/", "echo ", "$(", "fi\n", "sudo ", "export "],
+    ),
+];
+
+/// Markers a paste needs before it counts as code.
+const MIN_MARKERS: usize = 3;
+
+/// What a paste was recognized as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteKind {
+    /// Prose or anything unrecognized; left as is
+    Plain,
+    /// Source code
+    Code,
+    /// A JSON document
+    Json,
+    /// Comma-, tab-, or semicolon-separated values
+    Csv,
+    /// Log output
+    Log,
+}
+
+/// What `prepare_paste` returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PreparedPaste {
+    /// What the paste was recognized as
+    pub kind: PasteKind,
+    /// Language of the fence, if one was added
+    pub language: Option<String>,
+    /// Text to insert into the prompt
+    pub text: String,
+    /// Whether part of the paste was cut to fit the budget
+    pub truncated: bool,
+    /// Estimated tokens of `text`
+    pub estimated_tokens: u32,
+}
+
+/// Matches log lines, which start with a timestamp or a level.
+fn log_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(concat!(
+            r"^\s*\[?(\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}|\d{2}:\d{2}:\d{2}",
+            r"|(?:TRACE|DEBUG|INFO|WARN(?:ING)?|ERROR|FATAL)\b)",
+        ))
+        .expect("log pattern is valid")
+    })
+}
+
+fn estimate_tokens(text: &str) -> u32 {
+    u32::try_from(text.chars().count().div_ceil(CHARS_PER_TOKEN)).unwrap_or(u32::MAX)
+}
+
+/// Guesses the language of code, if it looks like code at all.
+///
+/// A first line syntect recognizes, such as a shebang or `<?php`, decides
+/// outright; otherwise the language with the most markers wins.
+fn detect_language(text: &str) -> Option<String> {
+    let first_line = text.lines().next().unwrap_or_default();
+    if let Some(syntax) = markdown::syntax_set().find_syntax_by_first_line(first_line) {
+        if let Some(extension) = syntax.file_extensions.first() {
+            return Some(extension.clone());
+        }
+    }
+    let mut best: Option<(usize, &str)> = None;
+    for (language, markers) in LANGUAGE_MARKERS {
+        let hits = markers
+            .iter()
+            .filter(|marker| text.contains(*marker))
+            .count();
+        // Earlier languages win ties, so TypeScript beats JavaScript.
+        if hits >= MIN_MARKERS && !matches!(best, Some((most, _)) if most >= hits) {
+            best = Some((hits, language));
+        }
+    }
+    best.map(|(_, language)| language.to_string())
+}
+
+/// Splits a CSV line on `delimiter`, honoring double quotes.
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Parses text as a table if every line has the same number of fields.
+fn parse_csv(text: &str) -> Option<Vec<Vec<String>>> {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    if lines.len() < 2 {
+        return None;
+    }
+    [',', '\t', ';'].into_iter().find_map(|delimiter| {
+        let rows: Vec<Vec<String>> = lines
+            .iter()
+            .map(|line| split_csv_line(line, delimiter))
+            .collect();
+        let columns = rows[0].len();
+        (columns >= 2 && rows.iter().all(|row| row.len() == columns)).then_some(rows)
+    })
+}
+
+fn markdown_table(rows: &[Vec<String>]) -> String {
+    let cell = |value: &str| value.trim().replace('|', "\\|");
+    let line = |row: &Vec<String>| {
+        let cells: Vec<String> = row.iter().map(|value| cell(value)).collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+    let mut table = line(&rows[0]);
+    let _ = writeln!(table, "|{}", " --- |".repeat(rows[0].len()));
+    for row in &rows[1..] {
+        table.push_str(&line(row));
+    }
+    table
+}
+
+fn is_log(text: &str) -> bool {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    lines.len() >= 2
+        && lines
+            .iter()
+            .filter(|line| log_pattern().is_match(line))
+            .count()
+            * 2
+            > lines.len()
+}
+
+/// Cuts `text` to `max_chars`, keeping the start or, for logs, the end.
+fn truncate(text: &str, max_chars: usize, keep_end: bool) -> Option<String> {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return None;
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let mut kept = Vec::new();
+    let mut used = 0;
+    let ordered: Box<dyn Iterator<Item = &&str>> = if keep_end {
+        Box::new(lines.iter().rev())
+    } else {
+        Box::new(lines.iter())
+    };
+    for line in ordered {
+        let len = line.chars().count() + 1;
+        if used + len > max_chars {
+            break;
+        }
+        used += len;
+        kept.push(*line);
+    }
+    if kept.is_empty() {
+        // A single line over budget, such as minified code, is cut mid-line.
+        let cut: String = if keep_end {
+            text.chars().skip(total - max_chars).collect()
+        } else {
+            text.chars().take(max_chars).collect()
+        };
+        let notice = format!(
+            "[… {} characters omitted to fit the context budget]",
+            total - max_chars
+        );
+        return Some(if keep_end {
+            format!("{notice}\n{cut}")
+        } else {
+            format!("{cut}\n{notice}")
+        });
+    }
+    let omitted = lines.len() - kept.len();
+    let notice = format!(
+        "[… {omitted} of {} lines omitted to fit the context budget]",
+        lines.len()
+    );
+    if keep_end {
+        kept.reverse();
+        Some(format!("{notice}\n{}", kept.join("\n")))
+    } else {
+        Some(format!("{}\n{notice}", kept.join("\n")))
+    }
+}
+
+fn fence(language: &str, body: &str) -> String {
+    format!("```{language}\n{}\n```", body.trim_end_matches('\n'))
+}
+
+fn prepare(text: &str, budget: u32) -> PreparedPaste {
+    let plain = |text: String, truncated: bool| PreparedPaste {
+        kind: PasteKind::Plain,
+        language: None,
+        estimated_tokens: estimate_tokens(&text),
+        text,
+        truncated,
+    };
+    // Room for the fence and the notice.
+    let max_chars = usize::try_from(budget)
+        .unwrap_or(usize::MAX)
+        .saturating_mul(CHARS_PER_TOKEN)
+        .saturating_sub(120);
+    let short = text.chars().count() < MIN_CHARS && !text.trim().contains('\n');
+    if short || text.contains("```") {
+        return match truncate(text, max_chars, false) {
+            Some(cut) => plain(cut, true),
+            None => plain(text.to_string(), false),
+        };
+    }
+
+    let (kind, language, body, keep_end) = if let Some(value) =
+        serde_json::from_str::<serde_json::Value>(text.trim())
+            .ok()
+            .filter(|value| value.is_object() || value.is_array())
+    {
+        let pretty = serde_json::to_string_pretty(&value).unwrap_or_else(|_| text.to_string());
+        (PasteKind::Json, Some("json".to_string()), pretty, false)
+    } else if is_log(text) {
+        (
+            PasteKind::Log,
+            Some("log".to_string()),
+            text.to_string(),
+            true,
+        )
+    } else if let Some(rows) = parse_csv(text) {
+        let table = markdown_table(&rows);
+        if rows.len() <= MAX_TABLE_ROWS && table.chars().count() <= max_chars {
+            return PreparedPaste {
+                kind: PasteKind::Csv,
+                language: None,
+                estimated_tokens: estimate_tokens(&table),
+                text: table,
+                truncated: false,
+            };
+        }
+        (
+            PasteKind::Csv,
+            Some("csv".to_string()),
+            text.to_string(),
+            false,
+        )
+    } else if let Some(language) = detect_language(text) {
+        (PasteKind::Code, Some(language), text.to_string(), false)
+    } else {
+        (PasteKind::Plain, None, text.to_string(), false)
+    };
+
+    let (body, truncated) = match truncate(&body, max_chars, keep_end) {
+        Some(cut) => (cut, true),
+        None => (body, false),
+    };
+    let text = match &language {
+        Some(language) => fence(language, &body),
+        None => body,
+    };
+    PreparedPaste {
+        kind,
+        language,
+        estimated_tokens: estimate_tokens(&text),
+        text,
+        truncated,
+    }
+}
+
+/// Formats pasted text for a prompt.
+///
+/// `budget` is the most tokens the paste may use; 8000 when `None`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `TASK_FAILED` if the background task
+/// fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const pasted = event.clipboardData.getData("text/plain");
+/// const { text, truncated } = await invoke("prepare_paste", { text: pasted, budget: 4000 });
+/// insertAtCursor(text);
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn prepare_paste(
+    text: String,
+    budget: Option<u32>,
+) -> Result<PreparedPaste, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || prepare(&text, budget.unwrap_or(DEFAULT_BUDGET)))
+        .await
+        .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognizes_structured_pastes() {
+        let json = prepare("{\"tempo\": 120,\n\"swing\": 0.1}", 1_000);
+        assert_eq!(json.kind, PasteKind::Json);
+        assert!(json.text.starts_with("```json\n{\n  \"swing\": 0.1,"));
+
+        let csv = prepare("name,bpm\nkick,120\nhat|open,240\n", 1_000);
+        assert_eq!(csv.kind, PasteKind::Csv);
+        assert_eq!(
+            csv.text,
+            "| name | bpm |\n| --- | --- |\n| kick | 120 |\n| hat\\|open | 240 |\n"
+        );
+
+        let log = prepare(
+            "2026-10-14 09:00:01 INFO started\n2026-10-14 09:00:02 WARN slow disk\n",
+            1_000,
+        );
+        assert_eq!(log.kind, PasteKind::Log);
+
+        let rust = prepare(
+            "pub fn main() {\n    let mut x = Synth::new();\n    x.play();\n}\n",
+            1_000,
+        );
+        assert_eq!(rust.language.as_deref(), Some("rust"));
+        assert!(rust.text.starts_with("```rust\n"));
+
+        let prose = prepare("Short question?", 1_000);
+        assert_eq!(prose.kind, PasteKind::Plain);
+        assert_eq!(prose.text, "Short question?");
+    }
+
+    #[test]
+    fn test_truncates_to_budget() {
+        let lines: Vec<String> = (0..1_000)
+            .map(|i| format!("2026-10-14 09:00:00 INFO line {i}"))
+            .collect();
+        let log = prepare(&lines.join("\n"), 200);
+        assert!(log.truncated);
+        assert!(log.estimated_tokens <= 200);
+        assert!(log.text.contains("line 999"));
+        assert!(!log.text.contains("line 0\n"));
+        assert!(log.text.contains("lines omitted"));
+    }
+
+    #[test]
+    fn test_split_csv_line_honors_quotes() {
+        assert_eq!(
+            split_csv_line(r#"a,"b,c","say ""hi""""#, ','),
+            vec!["a", "b,c", r#"say "hi""#]
+        );
+    }
+}
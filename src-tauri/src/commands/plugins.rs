@@ -0,0 +1,930 @@
+//! Third-party plugins shipped as WebAssembly components.
+//!
+//! A plugin is a folder with a `plugin.json` manifest and a `plugin.wasm`
+//! component implementing the `plugin` world in `wit/plugin.wit`: it lists
+//! its tools and runs them on JSON input. Plugins see nothing of the
+//! machine beyond the host functions of that world, and those are
+//! capability-checked:
+//!
+//! - `network`: hosts the plugin may send HTTP requests to, exact names or
+//!   `*.example.com` for any subdomain
+//! - `storage`: a private key-value namespace in the app database
+//!
+//! The manifest declares what the plugin wants; the user grants any subset
+//! of it, and can narrow the grant at any time. Newly installed plugins are
+//! disabled with nothing granted. Each call runs in a fresh instance with
+//! capped memory and fuel, so a runaway plugin traps instead of hanging
+//! the app.
+//!
+//! ```json
+//! {
+//!   "id": "art.example.chords",
+//!   "name": "Chord lookup",
+//!   "version": "1.0.0",
+//!   "description": "Looks up chord voicings",
+//!   "capabilities": { "network": ["api.chords.example"], "storage": true }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::commands::audit::{self, AuditKind, AuditRecord};
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+mod bindings {
+    #![allow(clippy::all, clippy::pedantic)]
+    wasmtime::component::bindgen!({
+        path: "wit/plugin.wit",
+        world: "plugin",
+    });
+}
+
+use bindings::gibber::plugin::types::{HttpRequest, HttpResponse};
+use bindings::Plugin;
+
+/// Folder under the app data directory holding installed plugins.
+const PLUGINS_DIR: &str = "plugins";
+
+/// Manifest file name inside a plugin folder.
+const MANIFEST_FILE: &str = "plugin.json";
+
+/// Component file name inside a plugin folder.
+const COMPONENT_FILE: &str = "plugin.wasm";
+
+/// Linear memory a plugin instance may grow to.
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Fuel for one call; roughly one unit per instruction.
+const FUEL_PER_CALL: u64 = 5_000_000_000;
+
+/// Time allowed for a plugin's HTTP request.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest HTTP response body handed to a plugin.
+const MAX_HTTP_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Redirects followed for a plugin's HTTP request.
+const MAX_REDIRECTS: usize = 10;
+
+/// Largest value in a plugin's storage namespace.
+const MAX_STORAGE_VALUE_BYTES: usize = 64 * 1024;
+
+/// Keys a plugin may keep in its storage namespace.
+const MAX_STORAGE_KEYS: u32 = 1000;
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Plugin not found: {id}"))
+}
+
+fn trapped(err: &wasmtime::Error) -> GibberError {
+    GibberError::new("PLUGIN_TRAPPED", format!("{err:#}"))
+}
+
+/// What a plugin may do beyond computing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Capabilities {
+    /// Hosts reachable over HTTP; `*.example.com` matches subdomains
+    pub network: Vec<String>,
+    /// Access to a private key-value namespace
+    pub storage: bool,
+}
+
+impl Capabilities {
+    fn allows_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.network.iter().any(|pattern| {
+            let pattern = pattern.to_ascii_lowercase();
+            match pattern.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.ends_with('.')),
+                None => host == pattern,
+            }
+        })
+    }
+
+    fn allows_url(&self, url: &tauri::Url) -> bool {
+        matches!(url.scheme(), "http" | "https")
+            && url.host_str().is_some_and(|host| self.allows_host(host))
+    }
+
+    fn is_within(&self, requested: &Self) -> bool {
+        (!self.storage || requested.storage)
+            && self
+                .network
+                .iter()
+                .all(|host| requested.network.contains(host))
+    }
+
+    fn intersect(&self, requested: &Self) -> Self {
+        Self {
+            network: self
+                .network
+                .iter()
+                .filter(|host| requested.network.contains(host))
+                .cloned()
+                .collect(),
+            storage: self.storage && requested.storage,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginManifest {
+    id: String,
+    name: String,
+    version: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    capabilities: Capabilities,
+}
+
+/// A tool offered by a plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginTool {
+    /// Name, unique within the plugin
+    pub name: String,
+    /// What the tool does
+    pub description: String,
+    /// JSON schema of the input
+    pub input_schema: String,
+}
+
+/// An installed plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    /// Reverse-domain identifier from the manifest
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Version from the manifest
+    pub version: String,
+    /// Short description
+    pub description: String,
+    /// Capabilities the manifest asks for
+    pub requested: Capabilities,
+    /// Capabilities the user granted
+    pub granted: Capabilities,
+    /// Tools the plugin offers
+    pub tools: Vec<PluginTool>,
+    /// Whether its tools can be called
+    pub enabled: bool,
+    /// Install or update time in Unix milliseconds
+    pub installed_at: i64,
+}
+
+/// Managed plugin runtime.
+///
+/// The WebAssembly engine takes a while to set up, so it is created on
+/// first use (or by [`warm_up`] after startup) rather than at launch.
+pub struct PluginHost {
+    engine: OnceLock<Engine>,
+    dir: PathBuf,
+    components: Mutex<HashMap<String, Component>>,
+}
+
+impl PluginHost {
+    fn engine(&self) -> Result<&Engine, GibberError> {
+        if let Some(engine) = self.engine.get() {
+            return Ok(engine);
+        }
+        let mut config = Config::new();
+        config.wasm_component_model(true).consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(|e| GibberError::new("ENGINE_FAILED", format!("{e:#}")))?;
+        // Another thread may have won the race; either engine works.
+        Ok(self.engine.get_or_init(|| engine))
+    }
+
+    fn component(&self, id: &str) -> Result<Component, GibberError> {
+        let mut components = self
+            .components
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(component) = components.get(id) {
+            return Ok(component.clone());
+        }
+        let path = self.dir.join(id).join(COMPONENT_FILE);
+        let component = Component::from_file(self.engine()?, &path)
+            .map_err(|e| GibberError::new("INVALID_PLUGIN", format!("{e:#}")))?;
+        components.insert(id.to_string(), component.clone());
+        Ok(component)
+    }
+
+    fn forget(&self, id: &str) {
+        self.components
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+    }
+}
+
+/// Follows a redirect only to a host in `granted`, checked on every hop.
+fn redirect_policy(granted: Capabilities) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error(format!("More than {MAX_REDIRECTS} redirects"))
+        } else if granted.allows_url(attempt.url()) {
+            attempt.follow()
+        } else {
+            attempt.error("The request was redirected to a host that is not granted")
+        }
+    })
+}
+
+/// Store data of one plugin instance.
+struct PluginState {
+    app: AppHandle,
+    plugin_id: String,
+    granted: Capabilities,
+    limits: StoreLimits,
+}
+
+impl bindings::gibber::plugin::types::Host for PluginState {}
+
+impl PluginState {
+    fn actor(&self) -> String {
+        format!("plugin:{}", self.plugin_id)
+    }
+
+    fn send_http(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        let url = tauri::Url::parse(&request.url).map_err(|e| e.to_string())?;
+        if !self.granted.allows_url(&url) {
+            return Err(format!(
+                "Network access to {} is not granted",
+                url.host_str().unwrap_or(url.as_str())
+            ));
+        }
+        let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())
+            .map_err(|e| e.to_string())?;
+        let client = network::client_with_redirects(
+            &self.app,
+            Service::Plugins,
+            redirect_policy(self.granted.clone()),
+        )
+        .map_err(|e| e.to_string())?;
+        let mut builder = client.request(method, url).timeout(HTTP_TIMEOUT);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+
+        // Calls run on a blocking thread, so waiting here is fine.
+        tauri::async_runtime::block_on(async {
+            let response =
+                network_activity::send(&self.app, Service::Plugins, "plugins.http", builder)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            let too_large = || format!("The response is larger than {MAX_HTTP_BODY_BYTES} bytes");
+            if response
+                .content_length()
+                .is_some_and(|length| length > MAX_HTTP_BODY_BYTES as u64)
+            {
+                return Err(too_large());
+            }
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+            let body = response.bytes().await.map_err(|e| e.to_string())?;
+            if body.len() > MAX_HTTP_BODY_BYTES {
+                return Err(too_large());
+            }
+            Ok(HttpResponse {
+                status,
+                headers,
+                body: body.to_vec(),
+            })
+        })
+    }
+}
+
+impl bindings::gibber::plugin::host::Host for PluginState {
+    fn fetch(&mut self, request: HttpRequest) -> Result<HttpResponse, String> {
+        let url = request.url.clone();
+        let method = request.method.to_uppercase();
+        let result = self.send_http(request);
+        audit::record(
+            &self.app,
+            &AuditRecord {
+                actor: &self.actor(),
+                kind: AuditKind::Network,
+                target: &url,
+                detail: Some(serde_json::json!({
+                    "method": method,
+                    "status": result.as_ref().ok().map(|response| response.status),
+                })),
+                error: result.as_ref().err().map(String::as_str),
+            },
+        );
+        result
+    }
+
+    fn storage_get(&mut self, key: String) -> Result<Option<String>, String> {
+        if !self.granted.storage {
+            return Err("Storage is not granted".to_string());
+        }
+        self.app
+            .state::<Database>()
+            .conn()
+            .query_row(
+                "SELECT value FROM plugin_storage WHERE plugin_id = ?1 AND key = ?2",
+                params![self.plugin_id, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+    }
+
+    fn storage_set(&mut self, key: String, value: Option<String>) -> Result<(), String> {
+        if !self.granted.storage {
+            return Err("Storage is not granted".to_string());
+        }
+        let db = self.app.state::<Database>();
+        let conn = db.conn();
+        storage_set(&conn, &self.plugin_id, &key, value.as_deref())
+    }
+
+    fn log(&mut self, message: String) {
+        let message: String = message.chars().take(1000).collect();
+        tracing::info!(plugin = %self.plugin_id, "{message}");
+    }
+}
+
+fn storage_set(
+    conn: &Connection,
+    plugin_id: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<(), String> {
+    let Some(value) = value else {
+        conn.execute(
+            "DELETE FROM plugin_storage WHERE plugin_id = ?1 AND key = ?2",
+            params![plugin_id, key],
+        )
+        .map_err(|e| e.to_string())?;
+        return Ok(());
+    };
+    if value.len() > MAX_STORAGE_VALUE_BYTES {
+        return Err(format!(
+            "Values are limited to {MAX_STORAGE_VALUE_BYTES} bytes"
+        ));
+    }
+    let (keys, exists): (u32, bool) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(key = ?2), 0) > 0 FROM plugin_storage WHERE plugin_id = ?1",
+            params![plugin_id, key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    if !exists && keys >= MAX_STORAGE_KEYS {
+        return Err(format!("Storage is limited to {MAX_STORAGE_KEYS} keys"));
+    }
+    conn.execute(
+        "INSERT INTO plugin_storage (plugin_id, key, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT(plugin_id, key) DO UPDATE SET value = excluded.value",
+        params![plugin_id, key, value],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Instantiates a plugin in a fresh, limited store and runs `f` on it.
+fn with_instance<T>(
+    app: &AppHandle,
+    plugin_id: &str,
+    granted: Capabilities,
+    f: impl FnOnce(&Plugin, &mut Store<PluginState>) -> wasmtime::Result<T>,
+) -> Result<T, GibberError> {
+    let host = app.state::<PluginHost>();
+    let component = host.component(plugin_id)?;
+    let engine = host.engine()?;
+    let mut linker = Linker::new(engine);
+    Plugin::add_to_linker(&mut linker, |state: &mut PluginState| state).map_err(|e| trapped(&e))?;
+    let state = PluginState {
+        app: app.clone(),
+        plugin_id: plugin_id.to_string(),
+        granted,
+        limits: StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .instances(1)
+            .build(),
+    };
+    let mut store = Store::new(engine, state);
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(FUEL_PER_CALL).map_err(|e| trapped(&e))?;
+    let plugin = Plugin::instantiate(&mut store, &component, &linker).map_err(|e| trapped(&e))?;
+    f(&plugin, &mut store).map_err(|e| trapped(&e))
+}
+
+/// Rejects ids that aren't safe folder names.
+fn validate_id(id: &str) -> Result<(), GibberError> {
+    let valid = !id.is_empty()
+        && id.len() <= 64
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(GibberError::new(
+            "INVALID_PLUGIN",
+            format!("Plugin ids use lowercase letters, digits, '.', '_' and '-': {id}"),
+        ))
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<PluginManifest, GibberError> {
+    let contents = std::fs::read_to_string(dir.join(MANIFEST_FILE))?;
+    let manifest: PluginManifest = serde_json::from_str(&contents)
+        .map_err(|e| GibberError::new("INVALID_PLUGIN", format!("Invalid {MANIFEST_FILE}: {e}")))?;
+    validate_id(&manifest.id)?;
+    if manifest.name.trim().is_empty() {
+        return Err(GibberError::new("INVALID_PLUGIN", "The plugin has no name"));
+    }
+    Ok(manifest)
+}
+
+const PLUGIN_COLUMNS: &str =
+    "id, name, version, description, requested, granted, tools, enabled, installed_at";
+
+fn plugin_from_row(row: &Row<'_>) -> rusqlite::Result<PluginInfo> {
+    let json = |index: usize| -> rusqlite::Result<String> { row.get(index) };
+    Ok(PluginInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        version: row.get(2)?,
+        description: row.get(3)?,
+        requested: serde_json::from_str(&json(4)?).unwrap_or_default(),
+        granted: serde_json::from_str(&json(5)?).unwrap_or_default(),
+        tools: serde_json::from_str(&json(6)?).unwrap_or_default(),
+        enabled: row.get(7)?,
+        installed_at: row.get(8)?,
+    })
+}
+
+fn load_plugin(conn: &Connection, id: &str) -> rusqlite::Result<Option<PluginInfo>> {
+    conn.query_row(
+        &format!("SELECT {PLUGIN_COLUMNS} FROM plugins WHERE id = ?1"),
+        [id],
+        plugin_from_row,
+    )
+    .optional()
+}
+
+/// Stores an installed or updated plugin.
+///
+/// An update keeps the enabled flag and whatever of the grant the new
+/// manifest still asks for.
+fn upsert_plugin(
+    conn: &Connection,
+    manifest: &PluginManifest,
+    tools: &[PluginTool],
+) -> rusqlite::Result<PluginInfo> {
+    let existing = load_plugin(conn, &manifest.id)?;
+    let (granted, enabled) = existing.map_or_else(
+        || (Capabilities::default(), false),
+        |plugin| {
+            (
+                plugin.granted.intersect(&manifest.capabilities),
+                plugin.enabled,
+            )
+        },
+    );
+    conn.execute(
+        "INSERT OR REPLACE INTO plugins (id, name, version, description, requested, granted, tools, enabled, installed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            manifest.id,
+            manifest.name.trim(),
+            manifest.version,
+            manifest.description,
+            serde_json::to_string(&manifest.capabilities).unwrap_or_default(),
+            serde_json::to_string(&granted).unwrap_or_default(),
+            serde_json::to_string(tools).unwrap_or_default(),
+            enabled,
+            db::now_millis(),
+        ],
+    )?;
+    load_plugin(conn, &manifest.id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+/// Registers the plugin runtime. The engine itself is created lazily.
+///
+/// # Errors
+///
+/// Returns an error if the app data directory cannot be resolved.
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let dir = app.path().app_data_dir()?.join(PLUGINS_DIR);
+    app.manage(PluginHost {
+        engine: OnceLock::new(),
+        dir,
+        components: Mutex::new(HashMap::new()),
+    });
+    Ok(())
+}
+
+/// Creates the WebAssembly engine ahead of the first plugin call.
+pub fn warm_up(app: &AppHandle) {
+    if let Err(e) = app.state::<PluginHost>().engine() {
+        tracing::error!("failed to create plugin engine: {e}");
+    }
+}
+
+/// Lists installed plugins.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_plugins(db: State<'_, Database>) -> Result<Vec<PluginInfo>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {PLUGIN_COLUMNS} FROM plugins ORDER BY name COLLATE NOCASE"
+    ))?;
+    let plugins = stmt
+        .query_map([], plugin_from_row)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(plugins)
+}
+
+/// Installs or updates a plugin from a folder with `plugin.json` and
+/// `plugin.wasm`.
+///
+/// New plugins start disabled with no capabilities granted.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_PLUGIN` if the manifest or
+/// component is invalid, `PLUGIN_TRAPPED` if listing its tools fails, or
+/// `IO` if the files cannot be copied.
+///
+/// # Example
+///
+/// ```typescript
+/// const folder = await open({ directory: true });
+/// const plugin = await invoke("install_plugin", { path: folder });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn install_plugin(app: AppHandle, path: String) -> Result<PluginInfo, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let source = PathBuf::from(path);
+        let manifest = read_manifest(&source)?;
+        let is_update = load_plugin(&app.state::<Database>().conn(), &manifest.id)?.is_some();
+        let host = app.state::<PluginHost>();
+        let target = host.dir.join(&manifest.id);
+        std::fs::create_dir_all(&target)?;
+        std::fs::copy(source.join(MANIFEST_FILE), target.join(MANIFEST_FILE))?;
+        std::fs::copy(source.join(COMPONENT_FILE), target.join(COMPONENT_FILE))?;
+        host.forget(&manifest.id);
+
+        let described = with_instance(
+            &app,
+            &manifest.id,
+            Capabilities::default(),
+            |plugin, store| plugin.call_describe(store),
+        );
+        let described = match described {
+            Ok(tools) => tools,
+            Err(e) => {
+                host.forget(&manifest.id);
+                if !is_update {
+                    let _ = std::fs::remove_dir_all(&target);
+                }
+                return Err(e);
+            }
+        };
+        let tools = described
+            .into_iter()
+            .map(|tool| PluginTool {
+                name: tool.name,
+                description: tool.description,
+                input_schema: tool.input_schema,
+            })
+            .collect::<Vec<_>>();
+
+        let plugin = upsert_plugin(&app.state::<Database>().conn(), &manifest, &tools)?;
+        tracing::info!(plugin = %plugin.id, version = %plugin.version, "installed plugin");
+        Ok(plugin)
+    })
+    .await
+    .map_err(|e| GibberError::new("IO", e.to_string()))?
+}
+
+/// Enables or disables a plugin.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the plugin isn't installed.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn set_plugin_enabled(
+    db: State<'_, Database>,
+    id: &str,
+    enabled: bool,
+) -> Result<PluginInfo, GibberError> {
+    let conn = db.conn();
+    conn.execute(
+        "UPDATE plugins SET enabled = ?1 WHERE id = ?2",
+        params![enabled, id],
+    )?;
+    load_plugin(&conn, id)?.ok_or_else(|| not_found(id))
+}
+
+/// Sets the capabilities granted to a plugin.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the plugin isn't
+/// installed, or `NOT_REQUESTED` if the grant exceeds what its manifest
+/// asks for.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_plugin_capabilities", {
+///   id: plugin.id,
+///   granted: { network: [], storage: true },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_plugin_capabilities(
+    db: State<'_, Database>,
+    id: &str,
+    granted: Capabilities,
+) -> Result<PluginInfo, GibberError> {
+    let conn = db.conn();
+    let plugin = load_plugin(&conn, id)?.ok_or_else(|| not_found(id))?;
+    if !granted.is_within(&plugin.requested) {
+        return Err(GibberError::new(
+            "NOT_REQUESTED",
+            "Only capabilities the plugin asks for can be granted",
+        ));
+    }
+    conn.execute(
+        "UPDATE plugins SET granted = ?1 WHERE id = ?2",
+        params![serde_json::to_string(&granted).unwrap_or_default(), id],
+    )?;
+    load_plugin(&conn, id)?.ok_or_else(|| not_found(id))
+}
+
+/// Removes a plugin, its files and its storage.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the plugin isn't
+/// installed, or `IO` if its files cannot be removed.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn uninstall_plugin(app: AppHandle, id: &str) -> Result<(), GibberError> {
+    validate_id(id)?;
+    let deleted = app
+        .state::<Database>()
+        .conn()
+        .execute("DELETE FROM plugins WHERE id = ?1", [id])?;
+    if deleted == 0 {
+        return Err(not_found(id));
+    }
+    let host = app.state::<PluginHost>();
+    host.forget(id);
+    match std::fs::remove_dir_all(host.dir.join(id)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Runs a tool of an enabled plugin.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the plugin or tool
+/// doesn't exist, `DISABLED` if the plugin is disabled, `PLUGIN_FAILED` if
+/// the tool reports an error or returns invalid JSON, or `PLUGIN_TRAPPED`
+/// if it crashes or runs out of fuel or memory.
+///
+/// # Example
+///
+/// ```typescript
+/// const voicings = await invoke("call_plugin_tool", {
+///   id: "art.example.chords",
+///   tool: "lookup-chords",
+///   input: { chord: "Cmaj7" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn call_plugin_tool(
+    app: AppHandle,
+    id: String,
+    tool: String,
+    input: serde_json::Value,
+) -> Result<serde_json::Value, GibberError> {
+    let plugin =
+        load_plugin(&app.state::<Database>().conn(), &id)?.ok_or_else(|| not_found(&id))?;
+    if !plugin.enabled {
+        return Err(GibberError::new(
+            "DISABLED",
+            format!("{} is disabled", plugin.name),
+        ));
+    }
+    if !plugin.tools.iter().any(|t| t.name == tool) {
+        return Err(GibberError::new(
+            "NOT_FOUND",
+            format!("{} has no tool named {tool}", plugin.name),
+        ));
+    }
+    let actor = format!("plugin:{id}");
+    let handle = app.clone();
+    let name = tool.clone();
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        with_instance(&handle, &id, plugin.granted, |plugin, store| {
+            plugin.call_call(store, &name, &input.to_string())
+        })
+    })
+    .await
+    .map_err(|e| GibberError::new("PLUGIN_TRAPPED", e.to_string()))
+    .and_then(|result| result)
+    .and_then(|result| result.map_err(|e| GibberError::new("PLUGIN_FAILED", e)))
+    .and_then(|output| {
+        serde_json::from_str(&output).map_err(|e| {
+            GibberError::new(
+                "PLUGIN_FAILED",
+                format!("The tool returned invalid JSON: {e}"),
+            )
+        })
+    });
+    let error = output.as_ref().err().map(ToString::to_string);
+    audit::record(
+        &app,
+        &AuditRecord {
+            actor: &actor,
+            kind: AuditKind::Tool,
+            target: &tool,
+            detail: None,
+            error: error.as_deref(),
+        },
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(capabilities: Capabilities) -> PluginManifest {
+        PluginManifest {
+            id: "art.example.chords".to_string(),
+            name: "Chord lookup".to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            capabilities,
+        }
+    }
+
+    #[test]
+    fn test_network_patterns() {
+        let granted = Capabilities {
+            network: vec!["api.example.com".to_string(), "*.cdn.example".to_string()],
+            storage: false,
+        };
+        assert!(granted.allows_host("api.example.com"));
+        assert!(granted.allows_host("API.example.com"));
+        assert!(granted.allows_host("eu.cdn.example"));
+        assert!(!granted.allows_host("cdn.example"));
+        assert!(!granted.allows_host("evilcdn.example"));
+        assert!(!granted.allows_host("example.com"));
+        assert!(!Capabilities::default().allows_host("api.example.com"));
+    }
+
+    #[test]
+    fn test_redirects_stay_on_granted_hosts() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let location = match request.url() {
+                    "/away" => format!("http://localhost:{port}/done"),
+                    "/here" => format!("http://127.0.0.1:{port}/done"),
+                    _ => {
+                        let _ = request.respond(tiny_http::Response::from_string("ok"));
+                        continue;
+                    }
+                };
+                let header = tiny_http::Header::from_bytes("Location", location).unwrap();
+                let _ = request.respond(tiny_http::Response::empty(302).with_header(header));
+            }
+        });
+        let granted = Capabilities {
+            network: vec!["127.0.0.1".to_string()],
+            storage: false,
+        };
+        let client = reqwest::Client::builder()
+            .redirect(redirect_policy(granted))
+            .build()
+            .unwrap();
+        let get = |path: &str| {
+            tauri::async_runtime::block_on(
+                client.get(format!("http://127.0.0.1:{port}{path}")).send(),
+            )
+        };
+        assert_eq!(get("/here").unwrap().status(), 200);
+        assert!(get("/away").unwrap_err().is_redirect());
+    }
+
+    #[test]
+    fn test_grants_stay_within_requests() {
+        let requested = Capabilities {
+            network: vec!["api.example.com".to_string()],
+            storage: true,
+        };
+        assert!(requested.is_within(&requested));
+        assert!(Capabilities::default().is_within(&requested));
+        let broader = Capabilities {
+            network: vec!["other.example.com".to_string()],
+            storage: false,
+        };
+        assert!(!broader.is_within(&requested));
+        assert!(!requested.is_within(&Capabilities::default()));
+    }
+
+    #[test]
+    fn test_validate_id() {
+        assert!(validate_id("art.example.chords").is_ok());
+        for id in ["", "../evil", "Chords", ".hidden", "a/b"] {
+            assert_eq!(validate_id(id).unwrap_err().code(), "INVALID_PLUGIN");
+        }
+    }
+
+    #[test]
+    fn test_updates_keep_requested_grants() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let requested = Capabilities {
+            network: vec!["api.example.com".to_string()],
+            storage: true,
+        };
+        let installed = upsert_plugin(&conn, &manifest(requested.clone()), &[]).unwrap();
+        assert!(!installed.enabled);
+        assert_eq!(installed.granted, Capabilities::default());
+
+        conn.execute(
+            "UPDATE plugins SET granted = ?1, enabled = 1",
+            [serde_json::to_string(&requested).unwrap()],
+        )
+        .unwrap();
+        let narrower = Capabilities {
+            network: Vec::new(),
+            storage: true,
+        };
+        let updated = upsert_plugin(&conn, &manifest(narrower.clone()), &[]).unwrap();
+        assert!(updated.enabled);
+        assert_eq!(updated.granted, narrower);
+    }
+
+    #[test]
+    fn test_storage_limits() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        upsert_plugin(&conn, &manifest(Capabilities::default()), &[]).unwrap();
+        let id = "art.example.chords";
+        storage_set(&conn, id, "key", Some("value")).unwrap();
+        let big = "x".repeat(MAX_STORAGE_VALUE_BYTES + 1);
+        assert!(storage_set(&conn, id, "key", Some(&big)).is_err());
+        storage_set(&conn, id, "key", None).unwrap();
+        let count: u32 = conn
+            .query_row("SELECT COUNT(*) FROM plugin_storage", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+}
@@ -0,0 +1,384 @@
+//! New-chat presets.
+//!
+//! A preset bundles what a recurring kind of conversation is set up with:
+//! a persona (the system prompt), a model, a knowledge base, and the tools
+//! the model may call. `start_preset_conversation` creates a conversation
+//! from one and remembers which preset it came from, so reopening the
+//! conversation later restores the knowledge base and tools too. Knowledge
+//! bases and tools are referenced by the identifiers the frontend uses;
+//! tools are plugin tools named `<plugin id>/<tool name>`.
+
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::conversations::{self, Conversation, NewConversation};
+use crate::commands::usage::{self, UsageKind};
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Source recorded on conversations started from a preset.
+const PRESET_SOURCE: &str = "preset";
+
+/// A saved preset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Preset {
+    /// Unique identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// System prompt describing the persona
+    pub persona: Option<String>,
+    /// Model ID; the default model when `None`
+    pub model: Option<String>,
+    /// Knowledge base the conversation draws on
+    pub knowledge_base: Option<String>,
+    /// Tools the model may call
+    pub tools: Vec<String>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// Last update time in Unix milliseconds
+    pub updated_at: i64,
+}
+
+/// Fields for creating or replacing a preset.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetInput {
+    /// Display name
+    pub name: String,
+    /// System prompt describing the persona
+    #[serde(default)]
+    pub persona: Option<String>,
+    /// Model ID
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Knowledge base the conversation draws on
+    #[serde(default)]
+    pub knowledge_base: Option<String>,
+    /// Tools the model may call
+    #[serde(default)]
+    pub tools: Vec<String>,
+}
+
+/// A conversation started from a preset.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetConversation {
+    /// The new conversation
+    pub conversation: Conversation,
+    /// The preset it was started from
+    pub preset: Preset,
+}
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Preset {id} not found"))
+}
+
+/// Trims the input and drops empty optional fields and duplicate tools.
+fn normalize(input: PresetInput) -> Result<PresetInput, GibberError> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err(GibberError::invalid("Preset name is required"));
+    }
+    let optional = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let mut tools: Vec<String> = Vec::with_capacity(input.tools.len());
+    for tool in input.tools {
+        let tool = tool.trim().to_string();
+        if !tool.contains('/') {
+            return Err(GibberError::invalid(format!(
+                "Tool \"{tool}\" must be named <plugin id>/<tool name>"
+            )));
+        }
+        if !tools.contains(&tool) {
+            tools.push(tool);
+        }
+    }
+    Ok(PresetInput {
+        name,
+        persona: optional(input.persona),
+        model: optional(input.model),
+        knowledge_base: optional(input.knowledge_base),
+        tools,
+    })
+}
+
+const PRESET_COLUMNS: &str =
+    "id, name, persona, model, knowledge_base, tools, created_at, updated_at";
+
+fn preset_from_row(row: &Row<'_>) -> rusqlite::Result<Preset> {
+    let tools: String = row.get(5)?;
+    Ok(Preset {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        persona: row.get(2)?,
+        model: row.get(3)?,
+        knowledge_base: row.get(4)?,
+        tools: serde_json::from_str(&tools)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(5, Type::Text, Box::new(e)))?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+fn load_preset(conn: &Connection, id: &str) -> Result<Preset, GibberError> {
+    conn.query_row(
+        &format!("SELECT {PRESET_COLUMNS} FROM presets WHERE id = ?1"),
+        [id],
+        preset_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found(id))
+}
+
+fn save(conn: &Connection, id: Option<String>, input: PresetInput) -> Result<Preset, GibberError> {
+    let input = normalize(input)?;
+    let tools = serde_json::to_string(&input.tools).unwrap_or_default();
+    let now = db::now_millis();
+    let id = if let Some(id) = id {
+        let updated = conn.execute(
+            "UPDATE presets SET name = ?1, persona = ?2, model = ?3, knowledge_base = ?4,
+             tools = ?5, updated_at = ?6 WHERE id = ?7",
+            params![
+                input.name,
+                input.persona,
+                input.model,
+                input.knowledge_base,
+                tools,
+                now,
+                id
+            ],
+        )?;
+        if updated == 0 {
+            return Err(not_found(&id));
+        }
+        id
+    } else {
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO presets
+             (id, name, persona, model, knowledge_base, tools, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![
+                id,
+                input.name,
+                input.persona,
+                input.model,
+                input.knowledge_base,
+                tools,
+                now
+            ],
+        )?;
+        id
+    };
+    load_preset(conn, &id)
+}
+
+fn start(
+    conn: &Connection,
+    preset_id: &str,
+    title: Option<String>,
+) -> Result<PresetConversation, GibberError> {
+    let preset = load_preset(conn, preset_id)?;
+    let input = NewConversation {
+        title: title
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or_else(|| preset.name.clone()),
+        model: preset.model.clone(),
+        system_prompt: preset.persona.clone(),
+        source: Some(PRESET_SOURCE.to_string()),
+    };
+    let conversation = conversations::insert_conversation(conn, &input)?;
+    conn.execute(
+        "INSERT INTO conversation_presets (conversation_id, preset_id) VALUES (?1, ?2)",
+        params![conversation.id, preset.id],
+    )?;
+    usage::bump(conn, UsageKind::Preset, &preset.id, db::now_millis())?;
+    Ok(PresetConversation {
+        conversation,
+        preset,
+    })
+}
+
+/// Lists all presets by name.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_presets(db: State<'_, Database>) -> Result<Vec<Preset>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {PRESET_COLUMNS} FROM presets ORDER BY name COLLATE NOCASE"
+    ))?;
+    let presets = stmt
+        .query_map([], preset_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(presets)
+}
+
+/// Creates a preset, or replaces an existing one when `id` is given.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if the name is empty
+/// or a tool isn't named `<plugin id>/<tool name>`, `NOT_FOUND` if `id`
+/// doesn't exist, or `DATABASE` on failure.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("save_preset", {
+///   id: null,
+///   input: {
+///     name: "Code review",
+///     persona: "You are a meticulous reviewer of Gibber patches.",
+///     model: "anthropic/claude-sonnet-4",
+///     tools: ["dev.gibber.git/diff"],
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn save_preset(
+    db: State<'_, Database>,
+    id: Option<String>,
+    input: PresetInput,
+) -> Result<Preset, GibberError> {
+    save(&db.conn(), id, input)
+}
+
+/// Deletes a preset.
+///
+/// Conversations started from it keep their persona and model.
+///
+/// # Returns
+///
+/// Returns `true` if the preset was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_preset(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM presets WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+/// Starts a conversation configured by a preset.
+///
+/// The conversation is titled `title`, or after the preset when `None`.
+/// It isn't enrolled in a prompt experiment, whose variant would override
+/// the preset's persona or model.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the preset doesn't
+/// exist, or `DATABASE` on failure.
+///
+/// # Example
+///
+/// ```typescript
+/// const { conversation, preset } = await invoke("start_preset_conversation", {
+///   presetId: id,
+///   title: null,
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn start_preset_conversation(
+    db: State<'_, Database>,
+    preset_id: &str,
+    title: Option<String>,
+) -> Result<PresetConversation, GibberError> {
+    start(&db.conn(), preset_id, title)
+}
+
+/// Returns the preset a conversation was started from, if it still exists.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_conversation_preset(
+    db: State<'_, Database>,
+    conversation_id: &str,
+) -> Result<Option<Preset>, GibberError> {
+    let conn = db.conn();
+    let preset = conn
+        .query_row(
+            &format!(
+                "SELECT {PRESET_COLUMNS} FROM presets
+                 WHERE id = (SELECT preset_id FROM conversation_presets WHERE conversation_id = ?1)"
+            ),
+            [conversation_id],
+            preset_from_row,
+        )
+        .optional()?;
+    Ok(preset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(name: &str) -> PresetInput {
+        PresetInput {
+            name: name.to_string(),
+            persona: Some("You write basslines.".to_string()),
+            model: Some(" ".to_string()),
+            knowledge_base: None,
+            tools: vec!["dev.gibber.synth/render".to_string(); 2],
+        }
+    }
+
+    #[test]
+    fn test_presets_are_normalized() {
+        let normalized = normalize(input(" Bass ")).unwrap();
+        assert_eq!(normalized.name, "Bass");
+        assert_eq!(normalized.model, None);
+        assert_eq!(normalized.tools, vec!["dev.gibber.synth/render"]);
+        assert!(normalize(input(" ")).is_err());
+        let mut unqualified = input("Bass");
+        unqualified.tools = vec!["render".to_string()];
+        assert_eq!(normalize(unqualified).unwrap_err().code(), "INVALID_INPUT");
+    }
+
+    #[test]
+    fn test_conversations_remember_their_preset() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let preset = save(&conn, None, input("Bass")).unwrap();
+        let started = start(&conn, &preset.id, None).unwrap();
+        assert_eq!(started.conversation.title, "Bass");
+        assert_eq!(
+            started.conversation.system_prompt.as_deref(),
+            Some("You write basslines.")
+        );
+        assert_eq!(started.conversation.source, PRESET_SOURCE);
+
+        let renamed = save(&conn, Some(preset.id.clone()), input("Bass lines")).unwrap();
+        assert_eq!(renamed.name, "Bass lines");
+        assert_eq!(
+            save(&conn, Some("missing".to_string()), input("x"))
+                .unwrap_err()
+                .code(),
+            "NOT_FOUND"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Provider privacy flags.
+//!
+//! With `zeroDataRetention` on in the general settings, every request asks
+//! its provider not to keep or train on the data, in whatever form the
+//! provider understands:
+//!
+//! - OpenRouter: `provider.data_collection = "deny"` and `provider.zdr`,
+//!   which route only to endpoints that don't store prompts
+//! - OpenAI: `store: false`
+//!
+//! Providers without such a flag get the request unchanged. The frontend
+//! AI client merges the same fields through `get_privacy_fields`.
+
+use serde_json::{Map, Value};
+use tauri::State;
+
+use crate::commands::settings;
+use crate::db::Database;
+
+/// Returns whether zero data retention is on, failing closed if the
+/// settings can't be read.
+pub(crate) fn zero_data_retention(conn: &rusqlite::Connection) -> bool {
+    settings::load(conn).map_or(true, |settings| settings.zero_data_retention)
+}
+
+/// Request body fields that opt out of retention at `provider`.
+pub fn request_fields(provider: &str) -> Map<String, Value> {
+    let fields = match provider {
+        "openrouter" => serde_json::json!({
+            "provider": { "data_collection": "deny", "zdr": true }
+        }),
+        "openai" => serde_json::json!({ "store": false }),
+        _ => Value::Null,
+    };
+    match fields {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    }
+}
+
+/// Adds the opt-out fields for `provider` to a request body.
+///
+/// Objects are merged, so routing preferences already in the body stay.
+pub fn apply(body: &mut Value, provider: &str) {
+    let Value::Object(body) = body else {
+        return;
+    };
+    for (key, value) in request_fields(provider) {
+        match (body.get_mut(&key), value) {
+            (Some(Value::Object(existing)), Value::Object(value)) => existing.extend(value),
+            (_, value) => {
+                body.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Returns the fields the frontend should add to requests for `provider`,
+/// empty unless zero data retention is on.
+///
+/// # Example
+///
+/// ```typescript
+/// const privacy = await invoke("get_privacy_fields", { provider: "openrouter" });
+/// const body = { ...request, ...privacy };
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_privacy_fields(db: State<'_, Database>, provider: &str) -> Map<String, Value> {
+    if zero_data_retention(&db.conn()) {
+        request_fields(provider)
+    } else {
+        Map::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_fields_per_provider() {
+        assert_eq!(
+            Value::Object(request_fields("openai")),
+            serde_json::json!({ "store": false })
+        );
+        assert_eq!(
+            request_fields("openrouter")["provider"]["data_collection"],
+            "deny"
+        );
+        assert!(request_fields("stability").is_empty());
+    }
+
+    #[test]
+    fn test_apply_merges_provider_preferences() {
+        let mut body = serde_json::json!({
+            "model": "openai/gpt-4o",
+            "provider": { "order": ["openai"] },
+        });
+        apply(&mut body, "openrouter");
+        assert_eq!(body["provider"]["order"][0], "openai");
+        assert_eq!(body["provider"]["zdr"], true);
+        assert_eq!(body["model"], "openai/gpt-4o");
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let db = Database::open_in_memory().expect("Should open");
+        assert!(!zero_data_retention(&db.conn()));
+    }
+}
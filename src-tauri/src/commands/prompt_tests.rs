@@ -0,0 +1,773 @@
+//! Prompt regression tests.
+//!
+//! A prompt test is a prompt (with an optional system prompt) and a list of
+//! criteria its answer must meet: text it must or must not contain, a
+//! pattern, an exact answer, a length limit, or valid JSON.
+//! `run_prompt_tests` sends every selected test to every selected model at
+//! temperature 0 and checks the answers. Each result is compared with the
+//! same test and model in the previous run: a pass turning into a failure
+//! is flagged as a regression, and a changed answer comes with a line diff.
+//!
+//! Tests, runs, and results are stored in the local database.
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::events;
+use crate::commands::telemetry;
+use crate::commands::text_diff::{self, DiffSpan};
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Most models in one run.
+const MAX_MODELS: usize = 8;
+
+/// Most criteria per test.
+const MAX_CRITERIA: usize = 20;
+
+fn not_found(what: &str, id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("{what} {id} not found"))
+}
+
+/// A condition an answer must meet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Criterion {
+    /// The answer contains `text`
+    #[serde(rename_all = "camelCase")]
+    Contains {
+        /// Text to find
+        text: String,
+        /// Match case (default: ignore it)
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// The answer doesn't contain `text`
+    #[serde(rename_all = "camelCase")]
+    NotContains {
+        /// Text that must be absent
+        text: String,
+        /// Match case (default: ignore it)
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+    /// The answer matches a regular expression
+    Matches {
+        /// Pattern in `regex` crate syntax
+        pattern: String,
+    },
+    /// The answer is exactly `text`, ignoring surrounding whitespace
+    Equals {
+        /// Expected answer
+        text: String,
+    },
+    /// The answer is at most `chars` characters long
+    MaxChars {
+        /// Length limit
+        chars: usize,
+    },
+    /// The answer parses as JSON, optionally inside a code fence
+    Json,
+}
+
+fn contains(haystack: &str, needle: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        haystack.contains(needle)
+    } else {
+        haystack.to_lowercase().contains(&needle.to_lowercase())
+    }
+}
+
+/// Strips a surrounding Markdown code fence.
+fn unfence(text: &str) -> &str {
+    let trimmed = text.trim();
+    trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.split_once('\n'))
+        .and_then(|(_, body)| body.trim_end().strip_suffix("```"))
+        .unwrap_or(trimmed)
+}
+
+impl Criterion {
+    fn validate(&self) -> Result<(), GibberError> {
+        match self {
+            Self::Contains { text, .. } | Self::NotContains { text, .. } if text.is_empty() => {
+                Err(GibberError::invalid("Text criteria need some text"))
+            }
+            Self::Matches { pattern } => regex::Regex::new(pattern)
+                .map(|_| ())
+                .map_err(|e| GibberError::invalid(format!("Invalid pattern: {e}"))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Why `output` fails this criterion, or `None` if it passes.
+    pub fn failure(&self, output: &str) -> Option<String> {
+        match self {
+            Self::Contains {
+                text,
+                case_sensitive,
+            } => (!contains(output, text, *case_sensitive))
+                .then(|| format!("Expected the answer to contain \"{text}\"")),
+            Self::NotContains {
+                text,
+                case_sensitive,
+            } => contains(output, text, *case_sensitive)
+                .then(|| format!("Expected the answer not to contain \"{text}\"")),
+            Self::Matches { pattern } => match regex::Regex::new(pattern) {
+                Ok(re) if re.is_match(output) => None,
+                Ok(_) => Some(format!("Expected the answer to match /{pattern}/")),
+                Err(e) => Some(format!("Invalid pattern: {e}")),
+            },
+            Self::Equals { text } => (output.trim() != text.trim())
+                .then(|| format!("Expected the answer to be \"{}\"", text.trim())),
+            Self::MaxChars { chars } => {
+                let length = output.chars().count();
+                (length > *chars)
+                    .then(|| format!("Expected at most {chars} characters, got {length}"))
+            }
+            Self::Json => serde_json::from_str::<serde_json::Value>(unfence(output))
+                .err()
+                .map(|e| format!("Expected JSON: {e}")),
+        }
+    }
+}
+
+/// A saved prompt test.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTest {
+    /// Unique identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// System prompt, if any
+    pub system: Option<String>,
+    /// User prompt
+    pub prompt: String,
+    /// Conditions the answer must meet
+    pub criteria: Vec<Criterion>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// Last update time in Unix milliseconds
+    pub updated_at: i64,
+}
+
+/// Fields for creating or replacing a prompt test.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTestInput {
+    /// Display name
+    pub name: String,
+    /// System prompt, if any
+    #[serde(default)]
+    pub system: Option<String>,
+    /// User prompt
+    pub prompt: String,
+    /// Conditions the answer must meet
+    pub criteria: Vec<Criterion>,
+}
+
+impl PromptTestInput {
+    fn validate(&self) -> Result<(), GibberError> {
+        if self.name.trim().is_empty() {
+            return Err(GibberError::invalid("A test needs a name"));
+        }
+        if self.prompt.trim().is_empty() {
+            return Err(GibberError::invalid("A test needs a prompt"));
+        }
+        if self.criteria.is_empty() || self.criteria.len() > MAX_CRITERIA {
+            return Err(GibberError::invalid(format!(
+                "A test needs between 1 and {MAX_CRITERIA} criteria"
+            )));
+        }
+        self.criteria.iter().try_for_each(Criterion::validate)
+    }
+}
+
+/// One test answered by one model.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTestResult {
+    /// Test that was run
+    pub test_id: String,
+    /// Model that answered
+    pub model: String,
+    /// The answer, if the request succeeded
+    pub output: Option<String>,
+    /// Whether every criterion passed
+    pub passed: bool,
+    /// Why criteria failed
+    pub failures: Vec<String>,
+    /// Request error, if the model couldn't be reached
+    pub error: Option<String>,
+    /// Request duration
+    pub duration_ms: i64,
+    /// Outcome in the previous run with this test and model
+    pub previously_passed: Option<bool>,
+    /// Passed last time, failed now
+    pub regression: bool,
+    /// Line diff against the previous answer, when it changed
+    pub diff: Option<Vec<DiffSpan>>,
+}
+
+/// A batch of prompt tests.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTestRun {
+    /// Unique identifier
+    pub id: String,
+    /// Models the tests were sent to
+    pub models: Vec<String>,
+    /// Results that passed
+    pub passed: u32,
+    /// Results that failed
+    pub failed: u32,
+    /// Start time in Unix milliseconds
+    pub started_at: i64,
+    /// End time in Unix milliseconds, once finished
+    pub finished_at: Option<i64>,
+    /// Per-test results; empty in run listings
+    pub results: Vec<PromptTestResult>,
+}
+
+/// Payload of [`events::PROMPT_TESTS_PROGRESS`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+#[specta(rename = "PromptTestProgress")]
+pub struct ProgressPayload {
+    /// Run in progress
+    pub run_id: String,
+    /// Test and model pairs finished
+    pub completed: usize,
+    /// Test and model pairs in the run
+    pub total: usize,
+}
+
+const TEST_COLUMNS: &str = "id, name, system, prompt, criteria, created_at, updated_at";
+const RUN_COLUMNS: &str = "id, models, passed, failed, started_at, finished_at";
+
+fn test_from_row(row: &Row<'_>) -> rusqlite::Result<PromptTest> {
+    let criteria: String = row.get(4)?;
+    Ok(PromptTest {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        system: row.get(2)?,
+        prompt: row.get(3)?,
+        criteria: serde_json::from_str(&criteria).unwrap_or_default(),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn run_from_row(row: &Row<'_>) -> rusqlite::Result<PromptTestRun> {
+    let models: String = row.get(1)?;
+    Ok(PromptTestRun {
+        id: row.get(0)?,
+        models: serde_json::from_str(&models).unwrap_or_default(),
+        passed: row.get(2)?,
+        failed: row.get(3)?,
+        started_at: row.get(4)?,
+        finished_at: row.get(5)?,
+        results: Vec::new(),
+    })
+}
+
+fn load_test(conn: &Connection, id: &str) -> Result<PromptTest, GibberError> {
+    conn.query_row(
+        &format!("SELECT {TEST_COLUMNS} FROM prompt_tests WHERE id = ?1"),
+        [id],
+        test_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found("Prompt test", id))
+}
+
+fn load_tests(conn: &Connection) -> rusqlite::Result<Vec<PromptTest>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TEST_COLUMNS} FROM prompt_tests ORDER BY name"
+    ))?;
+    let tests = stmt.query_map([], test_from_row)?.collect();
+    tests
+}
+
+/// The latest result of `test_id` on `model` from a run started before
+/// `before`.
+fn previous_result(
+    conn: &Connection,
+    test_id: &str,
+    model: &str,
+    before: i64,
+) -> rusqlite::Result<Option<(bool, Option<String>)>> {
+    conn.query_row(
+        "SELECT r.passed, r.output FROM prompt_test_results r
+         JOIN prompt_test_runs run ON run.id = r.run_id
+         WHERE r.test_id = ?1 AND r.model = ?2 AND run.started_at < ?3
+         ORDER BY run.started_at DESC LIMIT 1",
+        params![test_id, model, before],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+/// Fills in the comparison with the previous run.
+fn compare(result: &mut PromptTestResult, previous: Option<(bool, Option<String>)>) {
+    let Some((passed, output)) = previous else {
+        return;
+    };
+    result.previously_passed = Some(passed);
+    result.regression = passed && !result.passed;
+    if let (Some(old), Some(new)) = (output.as_deref(), result.output.as_deref()) {
+        let spans = text_diff::diff(&text_diff::lines(old), &text_diff::lines(new));
+        result.diff = text_diff::has_changes(&spans).then_some(spans);
+    }
+}
+
+fn load_results(conn: &Connection, run: &PromptTestRun) -> rusqlite::Result<Vec<PromptTestResult>> {
+    let mut stmt = conn.prepare(
+        "SELECT test_id, model, output, passed, failures, error, duration_ms
+         FROM prompt_test_results WHERE run_id = ?1 ORDER BY position",
+    )?;
+    let mut results = stmt
+        .query_map([&run.id], |row| {
+            let failures: String = row.get(4)?;
+            Ok(PromptTestResult {
+                test_id: row.get(0)?,
+                model: row.get(1)?,
+                output: row.get(2)?,
+                passed: row.get(3)?,
+                failures: serde_json::from_str(&failures).unwrap_or_default(),
+                error: row.get(5)?,
+                duration_ms: row.get(6)?,
+                previously_passed: None,
+                regression: false,
+                diff: None,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for result in &mut results {
+        let previous = previous_result(conn, &result.test_id, &result.model, run.started_at)?;
+        compare(result, previous);
+    }
+    Ok(results)
+}
+
+fn load_run(conn: &Connection, run_id: &str) -> Result<PromptTestRun, GibberError> {
+    let mut run = conn
+        .query_row(
+            &format!("SELECT {RUN_COLUMNS} FROM prompt_test_runs WHERE id = ?1"),
+            [run_id],
+            run_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| not_found("Prompt test run", run_id))?;
+    run.results = load_results(conn, &run)?;
+    Ok(run)
+}
+
+/// Sends one test to one model and checks the answer.
+async fn run_one(app: &AppHandle, test: &PromptTest, model: &str) -> PromptTestResult {
+    let mut messages = Vec::new();
+    if let Some(system) = test.system.as_deref().filter(|s| !s.trim().is_empty()) {
+        messages.push(ChatMessage::new(MessageRole::System, system));
+    }
+    messages.push(ChatMessage::new(MessageRole::User, &test.prompt));
+    let mut request = ChatRequest::new(Some(model), messages);
+    request.temperature = 0.0;
+
+    let started = db::now_millis();
+    let completion = chat::complete(app, &request).await;
+    let duration_ms = db::now_millis() - started;
+    match completion {
+        Ok(completion) => {
+            let failures: Vec<String> = test
+                .criteria
+                .iter()
+                .filter_map(|criterion| criterion.failure(&completion.content))
+                .collect();
+            PromptTestResult {
+                test_id: test.id.clone(),
+                model: model.to_string(),
+                passed: failures.is_empty(),
+                output: Some(completion.content),
+                failures,
+                error: None,
+                duration_ms,
+                previously_passed: None,
+                regression: false,
+                diff: None,
+            }
+        }
+        Err(e) => PromptTestResult {
+            test_id: test.id.clone(),
+            model: model.to_string(),
+            output: None,
+            passed: false,
+            failures: Vec::new(),
+            error: Some(e.to_string()),
+            duration_ms,
+            previously_passed: None,
+            regression: false,
+            diff: None,
+        },
+    }
+}
+
+fn insert_result(
+    conn: &Connection,
+    run_id: &str,
+    position: usize,
+    result: &PromptTestResult,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO prompt_test_results
+            (run_id, test_id, model, position, output, passed, failures, error, duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            run_id,
+            result.test_id,
+            result.model,
+            i64::try_from(position).unwrap_or(i64::MAX),
+            result.output,
+            result.passed,
+            serde_json::to_string(&result.failures).unwrap_or_default(),
+            result.error,
+            result.duration_ms,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Lists all prompt tests by name.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_prompt_tests(db: State<'_, Database>) -> Result<Vec<PromptTest>, GibberError> {
+    Ok(load_tests(&db.conn())?)
+}
+
+/// Creates a prompt test, or replaces an existing one when `id` is given.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if the test has no
+/// name, prompt, or criteria or has an invalid pattern, `NOT_FOUND` if `id`
+/// doesn't exist, or `DATABASE` on failure.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("save_prompt_test", {
+///   id: null,
+///   input: {
+///     name: "Tempo as JSON",
+///     prompt: "Reply with the tempo of a typical house track as {\"bpm\": n}",
+///     criteria: [{ kind: "json" }, { kind: "matches", pattern: "1[12][0-9]" }],
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn save_prompt_test(
+    db: State<'_, Database>,
+    id: Option<String>,
+    input: PromptTestInput,
+) -> Result<PromptTest, GibberError> {
+    input.validate()?;
+    let criteria = serde_json::to_string(&input.criteria)
+        .map_err(|e| GibberError::new("SERIALIZATION", e.to_string()))?;
+    let system = input.system.filter(|s| !s.trim().is_empty());
+    let now = db::now_millis();
+    let conn = db.conn();
+    let id = if let Some(id) = id {
+        let updated = conn.execute(
+            "UPDATE prompt_tests SET name = ?1, system = ?2, prompt = ?3, criteria = ?4,
+             updated_at = ?5 WHERE id = ?6",
+            params![input.name, system, input.prompt, criteria, now, id],
+        )?;
+        if updated == 0 {
+            return Err(not_found("Prompt test", &id));
+        }
+        id
+    } else {
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO prompt_tests
+                (id, name, system, prompt, criteria, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            params![id, input.name, system, input.prompt, criteria, now],
+        )?;
+        id
+    };
+    load_test(&conn, &id)
+}
+
+/// Deletes a prompt test and its results.
+///
+/// # Returns
+///
+/// Returns `true` if the test was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_prompt_test(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM prompt_tests WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+/// Runs prompt tests against one or more models.
+///
+/// `test_ids` defaults to every test. Progress is reported through
+/// [`events::PROMPT_TESTS_PROGRESS`]; request failures are reported in the
+/// results rather than as an error.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if no model or
+/// more than eight are given or there are no tests, `NOT_FOUND` for an
+/// unknown test, or `DATABASE` if the run cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const run = await invoke("run_prompt_tests", {
+///   testIds: null,
+///   models: ["anthropic/claude-sonnet-4", "openai/gpt-4o-mini"],
+/// });
+/// const regressions = run.results.filter((r) => r.regression);
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn run_prompt_tests(
+    app: AppHandle,
+    test_ids: Option<Vec<String>>,
+    models: Vec<String>,
+) -> Result<PromptTestRun, GibberError> {
+    let mut models: Vec<String> = models
+        .into_iter()
+        .map(|model| model.trim().to_string())
+        .filter(|model| !model.is_empty())
+        .collect();
+    models.dedup();
+    if models.is_empty() || models.len() > MAX_MODELS {
+        return Err(GibberError::invalid(format!(
+            "Choose between 1 and {MAX_MODELS} models"
+        )));
+    }
+    let tests = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        match test_ids {
+            Some(ids) => ids
+                .iter()
+                .map(|id| load_test(&conn, id))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => load_tests(&conn)?,
+        }
+    };
+    if tests.is_empty() {
+        return Err(GibberError::invalid("There are no prompt tests to run"));
+    }
+    telemetry::record_feature(&app, "prompt_tests.run");
+
+    let run_id = db::new_id();
+    let started_at = db::now_millis();
+    app.state::<Database>().conn().execute(
+        "INSERT INTO prompt_test_runs (id, models, passed, failed, started_at)
+         VALUES (?1, ?2, 0, 0, ?3)",
+        params![
+            run_id,
+            serde_json::to_string(&models).unwrap_or_default(),
+            started_at
+        ],
+    )?;
+
+    let total = tests.len() * models.len();
+    let mut results = Vec::with_capacity(total);
+    for test in &tests {
+        for model in &models {
+            let mut result = run_one(&app, test, model).await;
+            {
+                let db = app.state::<Database>();
+                let conn = db.conn();
+                insert_result(&conn, &run_id, results.len(), &result)?;
+                let previous = previous_result(&conn, &test.id, model, started_at)?;
+                compare(&mut result, previous);
+            }
+            results.push(result);
+            let payload = ProgressPayload {
+                run_id: run_id.clone(),
+                completed: results.len(),
+                total,
+            };
+            events::emit_typed(&app, events::PROMPT_TESTS_PROGRESS, &payload);
+        }
+    }
+
+    let passed = u32::try_from(results.iter().filter(|r| r.passed).count()).unwrap_or(u32::MAX);
+    let failed = u32::try_from(results.len()).unwrap_or(u32::MAX) - passed;
+    let finished_at = db::now_millis();
+    app.state::<Database>().conn().execute(
+        "UPDATE prompt_test_runs SET passed = ?1, failed = ?2, finished_at = ?3 WHERE id = ?4",
+        params![passed, failed, finished_at, run_id],
+    )?;
+    Ok(PromptTestRun {
+        id: run_id,
+        models,
+        passed,
+        failed,
+        started_at,
+        finished_at: Some(finished_at),
+        results,
+    })
+}
+
+/// Lists past runs, newest first, without their results.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_prompt_test_runs(db: State<'_, Database>) -> Result<Vec<PromptTestRun>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {RUN_COLUMNS} FROM prompt_test_runs ORDER BY started_at DESC"
+    ))?;
+    let runs = stmt
+        .query_map([], run_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(runs)
+}
+
+/// Retrieves a run with its results, compared with the run before it.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the run doesn't
+/// exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_prompt_test_run(
+    db: State<'_, Database>,
+    run_id: &str,
+) -> Result<PromptTestRun, GibberError> {
+    load_run(&db.conn(), run_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(test_id: &str, passed: bool, output: &str) -> PromptTestResult {
+        PromptTestResult {
+            test_id: test_id.to_string(),
+            model: "openai/gpt-4o".to_string(),
+            output: Some(output.to_string()),
+            passed,
+            failures: Vec::new(),
+            error: None,
+            duration_ms: 10,
+            previously_passed: None,
+            regression: false,
+            diff: None,
+        }
+    }
+
+    #[test]
+    fn test_criteria() {
+        let contains = Criterion::Contains {
+            text: "BPM".to_string(),
+            case_sensitive: false,
+        };
+        assert_eq!(contains.failure("128 bpm"), None);
+        assert!(contains.failure("fast").is_some());
+        let absent = Criterion::NotContains {
+            text: "sorry".to_string(),
+            case_sensitive: false,
+        };
+        assert!(absent.failure("Sorry, I can't").is_some());
+        let pattern = Criterion::Matches {
+            pattern: r"^\d+$".to_string(),
+        };
+        assert_eq!(pattern.failure("128"), None);
+        assert_eq!(
+            Criterion::Json.failure("```json\n{\"bpm\": 128}\n```"),
+            None
+        );
+        assert!(Criterion::Json.failure("bpm: 128").is_some());
+        assert!(Criterion::MaxChars { chars: 3 }.failure("four").is_some());
+        assert_eq!(
+            Criterion::Equals {
+                text: "yes".to_string()
+            }
+            .failure(" yes\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_input_validation() {
+        let input = PromptTestInput {
+            name: "Tempo".to_string(),
+            system: None,
+            prompt: "Tempo?".to_string(),
+            criteria: vec![Criterion::Matches {
+                pattern: "(".to_string(),
+            }],
+        };
+        assert_eq!(input.validate().unwrap_err().code(), "INVALID_INPUT");
+        let empty = PromptTestInput {
+            criteria: Vec::new(),
+            ..input
+        };
+        assert_eq!(empty.validate().unwrap_err().code(), "INVALID_INPUT");
+    }
+
+    #[test]
+    fn test_runs_compare_with_previous() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        conn.execute(
+            "INSERT INTO prompt_tests (id, name, prompt, criteria, created_at, updated_at)
+             VALUES ('t', 'Tempo', 'Tempo?', '[]', 0, 0)",
+            [],
+        )
+        .unwrap();
+        for (run_id, started_at) in [("r1", 1), ("r2", 2)] {
+            conn.execute(
+                "INSERT INTO prompt_test_runs (id, models, passed, failed, started_at)
+                 VALUES (?1, '[\"openai/gpt-4o\"]', 0, 0, ?2)",
+                params![run_id, started_at],
+            )
+            .unwrap();
+        }
+        insert_result(&conn, "r1", 0, &result("t", true, "128 BPM\n")).unwrap();
+        insert_result(&conn, "r2", 0, &result("t", false, "Fast\n")).unwrap();
+
+        let run = load_run(&conn, "r2").unwrap();
+        let latest = &run.results[0];
+        assert_eq!(latest.previously_passed, Some(true));
+        assert!(latest.regression);
+        assert!(latest.diff.is_some());
+        assert_eq!(
+            load_run(&conn, "r1").unwrap().results[0].previously_passed,
+            None
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Global quick-capture window.
+//!
+//! A global shortcut (see `shortcuts`) toggles a small, undecorated,
+//! always-on-top window where the user can type a prompt from anywhere.
+//! Submitting it creates a conversation holding the prompt, hides the capture
+//! window, and focuses the main window, which receives
+//! [`events::QUICK_CAPTURE_SUBMITTED`] and starts the answer.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::commands::conversations::{self, Conversation};
+use crate::commands::events;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Window label of the quick-capture window.
+pub const WINDOW_LABEL: &str = "quick-capture";
+
+/// Window label of the main application window.
+pub const MAIN_WINDOW_LABEL: &str = "main";
+
+/// Default shortcut that toggles the quick-capture window.
+pub const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+/// Size of the capture window in logical pixels.
+const WINDOW_WIDTH: f64 = 640.0;
+const WINDOW_HEIGHT: f64 = 96.0;
+
+/// Payload of [`events::QUICK_CAPTURE_SUBMITTED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmittedPayload {
+    /// The conversation created for the prompt
+    pub conversation_id: String,
+    /// The submitted prompt
+    pub prompt: String,
+    /// Model requested for the answer, if any
+    pub model: Option<String>,
+}
+
+/// Returns the capture window, creating it hidden on first use.
+fn capture_window(app: &AppHandle) -> tauri::Result<WebviewWindow> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        return Ok(window);
+    }
+    WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App("quick-capture".into()))
+        .title("Quick Capture")
+        .inner_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .visible(false)
+        .build()
+}
+
+/// Shows and focuses the capture window.
+///
+/// # Errors
+///
+/// Returns an error if the window cannot be created or shown.
+pub fn show(app: &AppHandle) -> tauri::Result<()> {
+    let window = capture_window(app)?;
+    window.center()?;
+    window.show()?;
+    window.set_focus()
+}
+
+/// Hides the capture window if it exists.
+///
+/// # Errors
+///
+/// Returns an error if the window cannot be hidden.
+pub fn hide(app: &AppHandle) -> tauri::Result<()> {
+    match app.get_webview_window(WINDOW_LABEL) {
+        Some(window) => window.hide(),
+        None => Ok(()),
+    }
+}
+
+/// Shows the capture window if hidden, hides it otherwise.
+///
+/// # Errors
+///
+/// Returns an error if the window cannot be shown or hidden.
+pub fn toggle(app: &AppHandle) -> tauri::Result<()> {
+    let visible = app
+        .get_webview_window(WINDOW_LABEL)
+        .map(|window| window.is_visible())
+        .transpose()?
+        .unwrap_or(false);
+    if visible {
+        hide(app)
+    } else {
+        show(app)
+    }
+}
+
+/// Brings the main window to the front.
+///
+/// # Errors
+///
+/// Returns an error if the window cannot be shown or focused.
+pub fn focus_main_window(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) {
+        window.unminimize()?;
+        window.show()?;
+        window.set_focus()?;
+    }
+    Ok(())
+}
+
+/// Shows the quick-capture window.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the window cannot be shown.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn show_quick_capture(app: AppHandle) -> Result<(), GibberError> {
+    Ok(show(&app)?)
+}
+
+/// Hides the quick-capture window.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the window cannot be hidden.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn hide_quick_capture(app: AppHandle) -> Result<(), GibberError> {
+    Ok(hide(&app)?)
+}
+
+/// Submits a prompt typed into the quick-capture window.
+///
+/// Creates a conversation containing the prompt, hides the capture window,
+/// focuses the main window, and emits [`events::QUICK_CAPTURE_SUBMITTED`] to it
+/// so it can stream the answer.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `EMPTY_PROMPT` if the prompt is
+/// blank, `DATABASE` if the conversation cannot be stored, or `WINDOW` if the
+/// windows cannot be updated.
+///
+/// # Example
+///
+/// ```typescript
+/// // From the quick-capture window:
+/// await invoke("submit_quick_capture", { prompt: "A slow ambient pad" });
+///
+/// // In the main window:
+/// await listen("quick-capture://submitted", (e) => openAndAnswer(e.payload.conversationId));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and State by value
+pub fn submit_quick_capture(
+    app: AppHandle,
+    db: State<'_, Database>,
+    prompt: String,
+    model: Option<String>,
+) -> Result<Conversation, GibberError> {
+    let prompt = prompt.trim().to_string();
+    if prompt.is_empty() {
+        return Err(GibberError::new("EMPTY_PROMPT", "Prompt is empty"));
+    }
+
+    let conversation = conversations::insert_prompt_conversation(
+        &db.conn(),
+        &prompt,
+        model.as_deref(),
+        "quick-capture",
+    )?;
+
+    hide(&app)?;
+    focus_main_window(&app)?;
+    events::emit_typed_to(
+        &app,
+        MAIN_WINDOW_LABEL,
+        events::QUICK_CAPTURE_SUBMITTED,
+        &SubmittedPayload {
+            conversation_id: conversation.id.clone(),
+            prompt,
+            model,
+        },
+    )?;
+    Ok(conversation)
+}
@@ -0,0 +1,457 @@
+//! Per-provider rate limits.
+//!
+//! Each AI provider gets a token bucket that holds up to `burst` requests
+//! and refills at `requestsPerMinute`. [`network_activity::send`] takes a
+//! token before every request to a provider's host; when the bucket is
+//! empty the request waits its turn instead of being sent and turned away
+//! with a 429. A 429 that gets through anyway (e.g. because another app
+//! shares the key) empties the bucket until its `Retry-After` passes.
+//!
+//! Waits are announced as [`events::RATE_LIMIT_QUEUED`] so the UI can explain a
+//! slow reply, and `get_rate_limit_state` shows what is left of each budget.
+//! The frontend AI client takes its tokens with `acquire_rate_limit`.
+//!
+//! [`network_activity::send`]: super::network_activity::send
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::events;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the [`RateLimitSettings`] document.
+pub const SETTINGS_KEY: &str = "rateLimits";
+
+/// Longest a frontend request waits before giving up.
+const MAX_WAIT: Duration = Duration::from_mins(2);
+
+/// Providers and the API hosts they answer on.
+const PROVIDER_HOSTS: &[(&str, &str)] = &[
+    ("openrouter", "openrouter.ai"),
+    ("openai", "api.openai.com"),
+    ("stability", "api.stability.ai"),
+];
+
+/// A provider's budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    /// Sustained requests per minute
+    pub requests_per_minute: u32,
+    /// Requests that may be sent at once after a quiet period
+    pub burst: u32,
+}
+
+impl RateLimit {
+    fn per_second(self) -> f64 {
+        f64::from(self.requests_per_minute) / 60.0
+    }
+}
+
+/// Rate limits by provider name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitSettings {
+    /// Budget per provider; providers without one aren't limited
+    pub limits: BTreeMap<String, RateLimit>,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        let limit = |requests_per_minute, burst| RateLimit {
+            requests_per_minute,
+            burst,
+        };
+        Self {
+            limits: BTreeMap::from([
+                ("openrouter".to_string(), limit(60, 10)),
+                ("openai".to_string(), limit(50, 5)),
+                ("stability".to_string(), limit(150, 10)),
+            ]),
+        }
+    }
+}
+
+impl RateLimitSettings {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        for (provider, limit) in &self.limits {
+            if limit.requests_per_minute == 0 || limit.burst == 0 {
+                return Err(GibberError::new(
+                    "INVALID_SETTINGS",
+                    format!("The {provider} limit must allow at least one request"),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn load_settings(conn: &rusqlite::Connection) -> RateLimitSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Provider whose API is served from `host`.
+pub fn provider_for_host(host: &str) -> Option<&'static str> {
+    PROVIDER_HOSTS
+        .iter()
+        .find(|(_, api)| api.eq_ignore_ascii_case(host))
+        .map(|(provider, _)| *provider)
+}
+
+/// What is left of a provider's budget.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitState {
+    /// Provider name
+    pub provider: String,
+    /// Configured budget
+    pub limit: RateLimit,
+    /// Requests that can be sent right now
+    pub available: u32,
+    /// Requests waiting for a token
+    pub queued: u32,
+    /// Time until the next token, or until a provider-imposed pause ends
+    pub wait_ms: u64,
+}
+
+/// Payload of [`events::RATE_LIMIT_QUEUED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPayload {
+    /// Provider name
+    pub provider: String,
+    /// How long the request waits
+    pub wait_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    /// Tokens left; negative while requests are queued
+    tokens: f64,
+    updated: Instant,
+    /// Pause imposed by a 429
+    paused_until: Option<Instant>,
+    queued: u32,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            updated: now,
+            paused_until: None,
+            queued: 0,
+        }
+    }
+
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let start = self
+            .paused_until
+            .map_or(self.updated, |until| until.max(self.updated));
+        if now > start {
+            let gained = now.duration_since(start).as_secs_f64() * limit.per_second();
+            self.tokens = (self.tokens + gained).min(f64::from(limit.burst));
+        }
+        self.updated = now;
+        if self.paused_until.is_some_and(|until| until <= now) {
+            self.paused_until = None;
+        }
+    }
+
+    /// Time until a token taken now becomes available.
+    fn wait(&self, limit: RateLimit, now: Instant) -> Duration {
+        let pause = self
+            .paused_until
+            .map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+        let deficit = if self.tokens < 1.0 {
+            Duration::from_secs_f64((1.0 - self.tokens) / limit.per_second())
+        } else {
+            Duration::ZERO
+        };
+        pause + deficit
+    }
+}
+
+/// Managed token buckets, one per provider.
+#[derive(Default)]
+pub struct RateLimiter(Mutex<HashMap<String, Bucket>>);
+
+impl RateLimiter {
+    fn buckets(&self) -> MutexGuard<'_, HashMap<String, Bucket>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes a token and returns how long to wait before using it.
+    fn reserve(&self, provider: &str, limit: RateLimit, now: Instant) -> Duration {
+        let mut buckets = self.buckets();
+        let bucket = buckets
+            .entry(provider.to_string())
+            .or_insert_with(|| Bucket::full(limit, now));
+        bucket.refill(limit, now);
+        let wait = bucket.wait(limit, now);
+        bucket.tokens -= 1.0;
+        if !wait.is_zero() {
+            bucket.queued += 1;
+        }
+        wait
+    }
+
+    fn release_queued(&self, provider: &str) {
+        if let Some(bucket) = self.buckets().get_mut(provider) {
+            bucket.queued = bucket.queued.saturating_sub(1);
+        }
+    }
+
+    /// Returns a token that was reserved but not used.
+    fn refund(&self, provider: &str) {
+        if let Some(bucket) = self.buckets().get_mut(provider) {
+            bucket.tokens += 1.0;
+            bucket.queued = bucket.queued.saturating_sub(1);
+        }
+    }
+
+    /// Stops sending to `provider` for `pause` after it answered 429.
+    fn pause(&self, provider: &str, limit: RateLimit, pause: Duration, now: Instant) {
+        let mut buckets = self.buckets();
+        let bucket = buckets
+            .entry(provider.to_string())
+            .or_insert_with(|| Bucket::full(limit, now));
+        bucket.refill(limit, now);
+        bucket.tokens = bucket.tokens.min(0.0);
+        let until = now + pause;
+        bucket.paused_until = Some(
+            bucket
+                .paused_until
+                .map_or(until, |current| current.max(until)),
+        );
+    }
+
+    fn state(&self, provider: &str, limit: RateLimit, now: Instant) -> RateLimitState {
+        let mut buckets = self.buckets();
+        let bucket = buckets
+            .entry(provider.to_string())
+            .or_insert_with(|| Bucket::full(limit, now));
+        bucket.refill(limit, now);
+        let available = if bucket.paused_until.is_some() {
+            0.0
+        } else {
+            bucket.tokens.max(0.0).floor()
+        };
+        RateLimitState {
+            provider: provider.to_string(),
+            limit,
+            // Bounded by `burst`, so the conversion can't truncate.
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            available: available as u32,
+            queued: bucket.queued,
+            wait_ms: u64::try_from(bucket.wait(limit, now).as_millis()).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+fn limit_for(app: &AppHandle, provider: &str) -> Option<RateLimit> {
+    let db = app.try_state::<Database>()?;
+    let settings = load_settings(&db.conn());
+    settings.limits.get(provider).copied()
+}
+
+async fn take(app: &AppHandle, provider: &str, max_wait: Duration) -> Result<(), GibberError> {
+    let (Some(limiter), Some(limit)) = (app.try_state::<RateLimiter>(), limit_for(app, provider))
+    else {
+        return Ok(());
+    };
+    let wait = limiter.reserve(provider, limit, Instant::now());
+    if wait.is_zero() {
+        return Ok(());
+    }
+    if wait > max_wait {
+        limiter.refund(provider);
+        return Err(GibberError::new(
+            "RATE_LIMITED",
+            format!(
+                "The {provider} budget is used up for the next {} seconds",
+                wait.as_secs()
+            ),
+        ));
+    }
+    let payload = QueuedPayload {
+        provider: provider.to_string(),
+        wait_ms: u64::try_from(wait.as_millis()).unwrap_or(u64::MAX),
+    };
+    events::emit_typed(app, events::RATE_LIMIT_QUEUED, &payload);
+    tokio::time::sleep(wait).await;
+    limiter.release_queued(provider);
+    Ok(())
+}
+
+/// Waits, however long it takes, until `provider` may receive another
+/// request.
+pub(crate) async fn throttle(app: &AppHandle, provider: &str) {
+    // Without a maximum the wait can't fail.
+    let _ = take(app, provider, Duration::MAX).await;
+}
+
+/// Notes a provider's 429, pausing it for `retry_after` (default: one
+/// minute).
+pub(crate) fn rate_limited(app: &AppHandle, provider: &str, retry_after: Option<Duration>) {
+    let (Some(limiter), Some(limit)) = (app.try_state::<RateLimiter>(), limit_for(app, provider))
+    else {
+        return;
+    };
+    let pause = retry_after.unwrap_or(Duration::from_mins(1)).min(MAX_WAIT);
+    limiter.pause(provider, limit, pause, Instant::now());
+}
+
+/// Returns the rate limits.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_rate_limits(db: State<'_, Database>) -> RateLimitSettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the rate limits.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` for a limit of
+/// zero, or `DATABASE` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_rate_limits", {
+///   settings: { limits: { openrouter: { requestsPerMinute: 20, burst: 5 } } },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_rate_limits(
+    db: State<'_, Database>,
+    settings: RateLimitSettings,
+) -> Result<RateLimitSettings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Returns what is left of each provider's budget.
+///
+/// # Example
+///
+/// ```typescript
+/// const budgets = await invoke("get_rate_limit_state");
+/// const openrouter = budgets.find((b) => b.provider === "openrouter");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_rate_limit_state(
+    db: State<'_, Database>,
+    limiter: State<'_, RateLimiter>,
+) -> Vec<RateLimitState> {
+    let settings = load_settings(&db.conn());
+    let now = Instant::now();
+    settings
+        .limits
+        .iter()
+        .map(|(provider, limit)| limiter.state(provider, *limit, now))
+        .collect()
+}
+
+/// Waits for a token of `provider`'s budget before the frontend sends a
+/// request there.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `RATE_LIMITED` if the budget won't
+/// allow a request within two minutes.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("acquire_rate_limit", { provider: "openrouter" });
+/// const response = await fetch(url, init);
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn acquire_rate_limit(app: AppHandle, provider: String) -> Result<(), GibberError> {
+    take(&app, &provider, MAX_WAIT).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        requests_per_minute: 60,
+        burst: 2,
+    };
+
+    #[test]
+    fn test_burst_then_queue() {
+        let limiter = RateLimiter::default();
+        let now = Instant::now();
+        assert_eq!(limiter.reserve("openrouter", LIMIT, now), Duration::ZERO);
+        assert_eq!(limiter.reserve("openrouter", LIMIT, now), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve("openrouter", LIMIT, now),
+            Duration::from_secs(1)
+        );
+        // Queued requests line up behind each other.
+        assert_eq!(
+            limiter.reserve("openrouter", LIMIT, now),
+            Duration::from_secs(2)
+        );
+        let state = limiter.state("openrouter", LIMIT, now);
+        assert_eq!((state.available, state.queued), (0, 2));
+
+        let later = limiter.state("openrouter", LIMIT, now + Duration::from_secs(4));
+        assert_eq!(later.available, 2);
+    }
+
+    #[test]
+    fn test_pause_after_429() {
+        let limiter = RateLimiter::default();
+        let now = Instant::now();
+        limiter.pause("openai", LIMIT, Duration::from_secs(30), now);
+        let wait = limiter.reserve("openai", LIMIT, now + Duration::from_secs(10));
+        assert_eq!(wait, Duration::from_secs(21));
+        assert_eq!(limiter.state("openai", LIMIT, now).available, 0);
+    }
+
+    #[test]
+    fn test_settings_and_hosts() {
+        let db = Database::open_in_memory().expect("Should open");
+        assert_eq!(
+            load_settings(&db.conn()).limits["openrouter"],
+            RateLimit {
+                requests_per_minute: 60,
+                burst: 10
+            }
+        );
+        assert_eq!(provider_for_host("API.openai.com"), Some("openai"));
+        assert_eq!(provider_for_host("example.com"), None);
+        let zero = RateLimitSettings {
+            limits: BTreeMap::from([(
+                "openai".to_string(),
+                RateLimit {
+                    requests_per_minute: 0,
+                    burst: 1,
+                },
+            )]),
+        };
+        assert_eq!(zero.validate().unwrap_err().code(), "INVALID_SETTINGS");
+    }
+}
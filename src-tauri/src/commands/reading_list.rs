@@ -0,0 +1,762 @@
+//! Read-it-later queue with summaries.
+//!
+//! URLs arrive from the UI, `gibber://read-later?url=...` links, or the
+//! clipboard watcher's latest capture. A background loop picks up queued
+//! items, fetches each page, extracts its title and readable text, and asks
+//! the model for a summary. Failed items are retried a few times before
+//! they are marked failed. Pages are read up to [`MAX_PAGE_BYTES`], and
+//! URLs the user didn't enter themselves are only fetched from public
+//! addresses, so a link can't make the app probe local services.
+//!
+//! Titles, summaries, and extracted text are searched together with
+//! conversations by [`search_library`].
+
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Url};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::clipboard;
+use crate::commands::conversations;
+use crate::commands::events;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::tray;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// How often the background loop looks for queued items.
+const TICK_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Items summarized per tick, so a long queue doesn't hog the model.
+const BATCH_SIZE: u32 = 3;
+
+/// Attempts before an item is marked failed.
+const MAX_ATTEMPTS: u32 = 3;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Largest page read, in bytes.
+const MAX_PAGE_BYTES: usize = 2_000_000;
+
+/// Extracted text kept and sent to the model, in characters.
+const MAX_TEXT_CHARS: usize = 24_000;
+
+/// Hits returned by a search, per kind.
+const SEARCH_LIMIT: u32 = 50;
+
+const SUMMARY_PROMPT: &str = "Summarize the article below for someone deciding whether to \
+     read it in full. Start with one sentence on what it is about, then list the key points \
+     as three to six short bullets. Reply in the article's language.";
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Reading list item {id} not found"))
+}
+
+/// Where an item is in the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingStatus {
+    /// Waiting to be fetched and summarized
+    Queued,
+    /// Summarized
+    Summarized,
+    /// Gave up after repeated failures
+    Failed,
+}
+
+impl ReadingStatus {
+    /// Database representation.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Summarized => "summarized",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "summarized" => Self::Summarized,
+            "failed" => Self::Failed,
+            _ => Self::Queued,
+        }
+    }
+}
+
+/// A saved URL.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingItem {
+    /// Unique identifier
+    pub id: String,
+    /// The page URL
+    pub url: String,
+    /// Page title, once fetched
+    pub title: Option<String>,
+    /// How the URL arrived: "manual", "deep-link", or "clipboard"
+    pub source: String,
+    /// Queue status
+    pub status: ReadingStatus,
+    /// Model summary
+    pub summary: Option<String>,
+    /// Extracted page text
+    pub content: Option<String>,
+    /// Last failure
+    pub error: Option<String>,
+    /// Fetch and summary attempts so far
+    pub attempts: u32,
+    /// When the URL was added, in Unix milliseconds
+    pub added_at: i64,
+    /// When the summary was stored, in Unix milliseconds
+    pub summarized_at: Option<i64>,
+}
+
+/// A search result.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SearchHit {
+    /// A matching conversation
+    #[serde(rename_all = "camelCase")]
+    Conversation {
+        /// Conversation ID
+        id: String,
+        /// Conversation title
+        title: String,
+        /// Last update in Unix milliseconds
+        updated_at: i64,
+    },
+    /// A matching reading list item
+    #[serde(rename_all = "camelCase")]
+    ReadingItem {
+        /// Item ID
+        id: String,
+        /// Page title, or the URL before it is fetched
+        title: String,
+        /// The page URL
+        url: String,
+        /// Last update in Unix milliseconds
+        updated_at: i64,
+    },
+}
+
+impl SearchHit {
+    const fn updated_at(&self) -> i64 {
+        match self {
+            Self::Conversation { updated_at, .. } | Self::ReadingItem { updated_at, .. } => {
+                *updated_at
+            }
+        }
+    }
+}
+
+const ITEM_COLUMNS: &str = "id, url, title, source, status, summary, content, error, attempts, \
+     added_at, summarized_at";
+
+fn item_from_row(row: &Row<'_>) -> rusqlite::Result<ReadingItem> {
+    let status: String = row.get(4)?;
+    Ok(ReadingItem {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        title: row.get(2)?,
+        source: row.get(3)?,
+        status: ReadingStatus::parse(&status),
+        summary: row.get(5)?,
+        content: row.get(6)?,
+        error: row.get(7)?,
+        attempts: row.get(8)?,
+        added_at: row.get(9)?,
+        summarized_at: row.get(10)?,
+    })
+}
+
+fn load_item(conn: &Connection, id: &str) -> rusqlite::Result<Option<ReadingItem>> {
+    conn.query_row(
+        &format!("SELECT {ITEM_COLUMNS} FROM reading_list WHERE id = ?1"),
+        [id],
+        item_from_row,
+    )
+    .optional()
+}
+
+/// Checks that `url` is an http(s) URL and normalizes it.
+fn normalize_url(url: &str) -> Result<String, GibberError> {
+    match Url::parse(url.trim()) {
+        Ok(mut parsed) if matches!(parsed.scheme(), "http" | "https") => {
+            parsed.set_fragment(None);
+            Ok(parsed.to_string())
+        }
+        _ => Err(GibberError::new(
+            "INVALID_URL",
+            format!("Not an http(s) URL: {url}"),
+        )),
+    }
+}
+
+/// Whether `ip` belongs to this machine or the local network.
+fn is_local(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+        }
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                || ip
+                    .to_ipv4_mapped()
+                    .is_some_and(|ip| is_local(IpAddr::V4(ip)))
+        }
+    }
+}
+
+/// Most redirects followed for URLs from outside the app.
+const MAX_REDIRECTS: usize = 10;
+
+/// Follows redirects unless they lead to `localhost` or a local address.
+fn public_redirects() -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(|attempt| {
+        let host = attempt
+            .url()
+            .host_str()
+            .unwrap_or_default()
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error(format!("More than {MAX_REDIRECTS} redirects"))
+        } else if host.eq_ignore_ascii_case("localhost") || host.parse().is_ok_and(is_local) {
+            attempt.error("The page redirected to a local address")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// Resolves the host of `url` and refuses it if any address is local.
+async fn require_public_host(url: &str) -> Result<(), String> {
+    let parsed = Url::parse(url).map_err(|e| e.to_string())?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "The URL has no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let target = host.clone();
+    let addresses = tauri::async_runtime::spawn_blocking(move || {
+        (target.as_str(), port)
+            .to_socket_addrs()
+            .map(Iterator::collect::<Vec<_>>)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("Could not resolve {host}: {e}"))?;
+    if addresses.iter().any(|address| is_local(address.ip())) {
+        return Err(format!("{host} points to a local address"));
+    }
+    Ok(())
+}
+
+/// Queues a URL, or returns the existing item if it was saved before.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_URL` for anything but
+/// http(s) URLs, or `DATABASE` if the insert fails.
+pub(crate) fn insert_item(
+    conn: &Connection,
+    url: &str,
+    source: &str,
+) -> Result<ReadingItem, GibberError> {
+    let url = normalize_url(url)?;
+    conn.execute(
+        "INSERT INTO reading_list (id, url, source, status, attempts, added_at)
+         VALUES (?1, ?2, ?3, 'queued', 0, ?4) ON CONFLICT(url) DO NOTHING",
+        params![db::new_id(), url, source, db::now_millis()],
+    )?;
+    let item = conn.query_row(
+        &format!("SELECT {ITEM_COLUMNS} FROM reading_list WHERE url = ?1"),
+        [&url],
+        item_from_row,
+    )?;
+    Ok(item)
+}
+
+/// Queues a URL and announces it.
+///
+/// # Errors
+///
+/// See [`insert_item`].
+pub(crate) fn add(app: &AppHandle, url: &str, source: &str) -> Result<ReadingItem, GibberError> {
+    let item = insert_item(&app.state::<Database>().conn(), url, source)?;
+    events::emit_typed(app, events::READING_LIST_UPDATED, &item);
+    Ok(item)
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn title_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?is)<title[^>]*>(.*?)</title>").expect("title pattern is valid")
+    })
+}
+
+fn main_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?is)<(article|main)[\s>].*?</(?:article|main)>")
+            .expect("main content pattern is valid")
+    })
+}
+
+/// Strips the markup from an HTML fragment, keeping one line per source
+/// line of text.
+pub(crate) fn plain_text(html: &str) -> String {
+    // Dropping every tag leaves text only; script and style contents go too.
+    let text = ammonia::Builder::default()
+        .tags(std::collections::HashSet::new())
+        .clean(html)
+        .to_string();
+    unescape(&text)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Extracts the title and readable text of a page.
+///
+/// Text comes from the `<article>` or `<main>` element when the page has
+/// one, so navigation and footers are mostly left out.
+fn extract(html: &str) -> (Option<String>, String) {
+    let title = title_pattern()
+        .captures(html)
+        .map(|captures| {
+            unescape(
+                captures[1]
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .as_str(),
+            )
+        })
+        .filter(|title| !title.is_empty());
+    let body = main_pattern()
+        .find(html)
+        .map_or(html, |found| found.as_str());
+    let text = plain_text(body).chars().take(MAX_TEXT_CHARS).collect();
+    (title, text)
+}
+
+/// Fetches a page and returns its title and text. Only public hosts are
+/// fetched for URLs from a `source` other than `"manual"`.
+async fn fetch(
+    app: &AppHandle,
+    url: &str,
+    source: &str,
+) -> Result<(Option<String>, String), String> {
+    let client = if source == "manual" {
+        network::client(app, Service::ReadingList)
+    } else {
+        require_public_host(url).await?;
+        network::client_with_redirects(app, Service::ReadingList, public_redirects())
+    }
+    .map_err(|e| e.to_string())?;
+    let builder = client.get(url).timeout(REQUEST_TIMEOUT);
+    let mut response =
+        network_activity::send(app, Service::ReadingList, "reading-list.fetch", builder)
+            .await
+            .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("text/html")
+        .to_ascii_lowercase();
+    if !content_type.starts_with("text/") && !content_type.contains("xhtml") {
+        return Err(format!("Unsupported content type {content_type}"));
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_PAGE_BYTES as u64)
+    {
+        return Err(format!(
+            "The page is larger than {} MB",
+            MAX_PAGE_BYTES / 1_000_000
+        ));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        let room = MAX_PAGE_BYTES - bytes.len();
+        bytes.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if bytes.len() == MAX_PAGE_BYTES {
+            break;
+        }
+    }
+    let page = String::from_utf8_lossy(&bytes);
+    let (title, text) = if content_type.starts_with("text/plain") {
+        (None, page.chars().take(MAX_TEXT_CHARS).collect())
+    } else {
+        extract(&page)
+    };
+    if text.trim().is_empty() {
+        return Err("The page has no readable text".to_string());
+    }
+    Ok((title, text))
+}
+
+/// Fetches and summarizes one item, recording the outcome.
+async fn process(app: &AppHandle, item: ReadingItem) {
+    let result = match fetch(app, &item.url, &item.source).await {
+        Ok((title, text)) => {
+            let heading = title.as_deref().unwrap_or(&item.url);
+            let request = ChatRequest::new(
+                None,
+                vec![
+                    ChatMessage::new(MessageRole::System, SUMMARY_PROMPT),
+                    ChatMessage::new(MessageRole::User, format!("# {heading}\n\n{text}")),
+                ],
+            );
+            chat::complete(app, &request)
+                .await
+                .map(|completion| (title, text, completion.content.trim().to_string()))
+                .map_err(|e| e.to_string())
+        }
+        Err(e) => Err(e),
+    };
+
+    let attempts = item.attempts + 1;
+    let now = db::now_millis();
+    let updated = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let outcome = match result {
+            Ok((title, text, summary)) => conn.execute(
+                "UPDATE reading_list
+                 SET title = ?2, content = ?3, summary = ?4, status = 'summarized',
+                     error = NULL, attempts = ?5, summarized_at = ?6
+                 WHERE id = ?1",
+                params![item.id, title, text, summary, attempts, now],
+            ),
+            Err(error) => {
+                tracing::warn!(id = %item.id, attempts, "reading list item not summarized: {error}");
+                let status = if attempts >= MAX_ATTEMPTS {
+                    ReadingStatus::Failed
+                } else {
+                    ReadingStatus::Queued
+                };
+                conn.execute(
+                    "UPDATE reading_list SET status = ?2, error = ?3, attempts = ?4 WHERE id = ?1",
+                    params![item.id, status.as_str(), error, attempts],
+                )
+            }
+        };
+        outcome.and_then(|_| load_item(&conn, &item.id))
+    };
+    match updated {
+        Ok(Some(item)) => {
+            events::emit_typed(app, events::READING_LIST_UPDATED, &item);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::error!(id = %item.id, "failed to record reading list item: {e}"),
+    }
+}
+
+fn queued_items(conn: &Connection) -> rusqlite::Result<Vec<ReadingItem>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ITEM_COLUMNS} FROM reading_list WHERE status = 'queued'
+         ORDER BY attempts, added_at LIMIT ?1"
+    ))?;
+    let items = stmt
+        .query_map([BATCH_SIZE], item_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(items)
+}
+
+/// Starts the background loop that summarizes queued items.
+///
+/// Must be called after the [`Database`] and tray state have been added to
+/// managed state.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if tray::is_sync_paused(&app) {
+                continue;
+            }
+            let queued = queued_items(&app.state::<Database>().conn());
+            match queued {
+                Ok(items) => {
+                    for item in items {
+                        process(&app, item).await;
+                    }
+                }
+                Err(e) => tracing::error!("failed to query reading list: {e}"),
+            }
+        }
+    });
+}
+
+/// Saves a URL to read later; it is summarized in the background.
+///
+/// Saving a URL again returns the existing item.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_URL` for anything but
+/// http(s) URLs, or `DATABASE` if the insert fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const item = await invoke("add_to_reading_list", { url: "https://example.com/post" });
+/// await listen("reading-list://updated", (e) => upsertItem(e.payload));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn add_to_reading_list(app: AppHandle, url: &str) -> Result<ReadingItem, GibberError> {
+    add(&app, url, "manual")
+}
+
+/// Saves the URL in the clipboard watcher's latest capture.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `EMPTY` if no text has been
+/// captured, or `INVALID_URL` if the text isn't an http(s) URL; the
+/// capture is used up either way.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn add_clipboard_to_reading_list(app: AppHandle) -> Result<ReadingItem, GibberError> {
+    let text = clipboard::take_text(&app)
+        .ok_or_else(|| GibberError::new("EMPTY", "No copied text has been captured"))?;
+    add(&app, &text, "clipboard")
+}
+
+/// Lists saved items, newest first, optionally only those with `status`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const unread = await invoke("list_reading_list", { status: "summarized" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_reading_list(
+    db: State<'_, Database>,
+    status: Option<ReadingStatus>,
+) -> Result<Vec<ReadingItem>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ITEM_COLUMNS} FROM reading_list
+         WHERE ?1 IS NULL OR status = ?1 ORDER BY added_at DESC"
+    ))?;
+    let items = stmt
+        .query_map([status.map(ReadingStatus::as_str)], item_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(items)
+}
+
+/// Queues an item again, for example after it failed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the item doesn't
+/// exist, or `DATABASE` if the update fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn retry_reading_item(db: State<'_, Database>, id: &str) -> Result<ReadingItem, GibberError> {
+    let conn = db.conn();
+    conn.execute(
+        "UPDATE reading_list SET status = 'queued', attempts = 0, error = NULL WHERE id = ?1",
+        [id],
+    )?;
+    load_item(&conn, id)?.ok_or_else(|| not_found(id))
+}
+
+/// Removes an item.
+///
+/// # Returns
+///
+/// `true` if the item existed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_reading_item(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM reading_list WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+fn search_items(conn: &Connection, query: &str) -> rusqlite::Result<Vec<ReadingItem>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {ITEM_COLUMNS} FROM reading_list
+         WHERE url LIKE ?1 ESCAPE '\\' OR title LIKE ?1 ESCAPE '\\'
+            OR summary LIKE ?1 ESCAPE '\\' OR content LIKE ?1 ESCAPE '\\'
+         ORDER BY COALESCE(summarized_at, added_at) DESC LIMIT ?2"
+    ))?;
+    let items = stmt
+        .query_map(
+            params![conversations::like_pattern(query), SEARCH_LIMIT],
+            item_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(items)
+}
+
+/// Searches conversations and reading list items together.
+///
+/// # Returns
+///
+/// Hits of both kinds, most recently updated first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if a query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const hits = await invoke("search_library", { query: "granular synthesis" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn search_library(db: State<'_, Database>, query: &str) -> Result<Vec<SearchHit>, GibberError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let conn = db.conn();
+    let mut hits: Vec<SearchHit> = conversations::search(&conn, query, SEARCH_LIMIT)?
+        .into_iter()
+        .map(|conversation| SearchHit::Conversation {
+            id: conversation.id,
+            title: conversation.title,
+            updated_at: conversation.updated_at,
+        })
+        .collect();
+    hits.extend(
+        search_items(&conn, query)?
+            .into_iter()
+            .map(|item| SearchHit::ReadingItem {
+                title: item.title.unwrap_or_else(|| item.url.clone()),
+                updated_at: item.summarized_at.unwrap_or(item.added_at),
+                id: item.id,
+                url: item.url,
+            }),
+    );
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.updated_at()));
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_item_deduplicates_urls() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let first = insert_item(&conn, "https://example.com/post#comments", "manual").unwrap();
+        let second = insert_item(&conn, " https://example.com/post ", "clipboard").unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.url, "https://example.com/post");
+        assert_eq!(second.source, "manual");
+        assert_eq!(first.status, ReadingStatus::Queued);
+        assert_eq!(
+            insert_item(&conn, "file:///etc/passwd", "manual")
+                .unwrap_err()
+                .code(),
+            "INVALID_URL"
+        );
+    }
+
+    #[test]
+    fn test_local_addresses() {
+        for local in [
+            "127.0.0.1",
+            "10.0.0.8",
+            "192.168.1.20",
+            "169.254.169.254",
+            "::1",
+            "fd00::1",
+        ] {
+            assert!(is_local(local.parse().unwrap()), "{local}");
+        }
+        assert!(is_local("::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!is_local("93.184.216.34".parse().unwrap()));
+        assert!(!is_local("2606:2800:220:1::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_extract_prefers_article() {
+        let html = "<html><head><title>  Tape &amp; Loops </title><style>p{}</style></head>\
+            <body><nav>Home</nav><article><h1>Loops</h1><p>Cut the   tape.</p>\
+            <script>track()</script></article><footer>(c)</footer></body></html>";
+        let (title, text) = extract(html);
+        assert_eq!(title.as_deref(), Some("Tape & Loops"));
+        assert!(text.contains("Loops"));
+        assert!(text.contains("Cut the tape."));
+        assert!(!text.contains("Home"));
+        assert!(!text.contains("track()"));
+        assert!(!text.contains("(c)"));
+    }
+
+    #[test]
+    fn test_queued_items_skip_finished() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let queued = insert_item(&conn, "https://example.com/a", "manual").unwrap();
+        let done = insert_item(&conn, "https://example.com/b", "manual").unwrap();
+        conn.execute(
+            "UPDATE reading_list SET status = 'summarized', summary = 'About tape' WHERE id = ?1",
+            [&done.id],
+        )
+        .unwrap();
+        let ids: Vec<String> = queued_items(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(ids, vec![queued.id]);
+        assert_eq!(search_items(&conn, "tape").unwrap()[0].id, done.id);
+    }
+}
@@ -0,0 +1,243 @@
+//! Cache of deterministic completions.
+//!
+//! With `cacheDeterministicResponses` on in the general settings, backend
+//! completions requested at temperature 0 are stored under the SHA-256 of
+//! the request (model, messages, token limit, and response format). An
+//! identical request later is answered from the cache without reaching
+//! OpenRouter, so template runs and prompt regression tests return at once
+//! and cost nothing. The least recently used entries are evicted beyond
+//! [`MAX_ENTRIES`].
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{ChatCompletion, ChatRequest, TokenUsage};
+use crate::commands::settings;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Entries kept before the least recently used are evicted.
+pub const MAX_ENTRIES: i64 = 2_000;
+
+/// Size and usefulness of the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// Stored responses
+    pub entries: i64,
+    /// Requests answered from the cache
+    pub hits: i64,
+    /// Size of the stored response text
+    pub bytes: i64,
+}
+
+/// Cache key of `request`, or `None` if it isn't deterministic.
+pub fn key(request: &ChatRequest) -> Option<String> {
+    if request.temperature != 0.0 {
+        return None;
+    }
+    // The conversation isn't serialized, so the same prompt in another
+    // conversation hits the same entry.
+    let body = serde_json::to_vec(request).ok()?;
+    Some(hex::encode(Sha256::digest(body)))
+}
+
+fn enabled(conn: &Connection) -> bool {
+    settings::load(conn).is_ok_and(|settings| settings.cache_deterministic_responses)
+}
+
+fn lookup(conn: &Connection, key: &str) -> rusqlite::Result<Option<ChatCompletion>> {
+    let completion = conn
+        .query_row(
+            "SELECT content, model, provider, prompt_tokens, completion_tokens
+             FROM response_cache WHERE key = ?1",
+            [key],
+            |row| {
+                let prompt_tokens: Option<u32> = row.get(3)?;
+                let completion_tokens: Option<u32> = row.get(4)?;
+                Ok(ChatCompletion {
+                    content: row.get(0)?,
+                    model: row.get(1)?,
+                    provider: row.get(2)?,
+                    usage: prompt_tokens.zip(completion_tokens).map(
+                        |(prompt_tokens, completion_tokens)| TokenUsage {
+                            prompt_tokens,
+                            completion_tokens,
+                        },
+                    ),
+                    cost_usd: None,
+                })
+            },
+        )
+        .optional()?;
+    if completion.is_some() {
+        conn.execute(
+            "UPDATE response_cache SET hits = hits + 1, last_hit_at = ?1 WHERE key = ?2",
+            params![db::now_millis(), key],
+        )?;
+    }
+    Ok(completion)
+}
+
+fn store(conn: &Connection, key: &str, completion: &ChatCompletion) -> rusqlite::Result<()> {
+    let now = db::now_millis();
+    conn.execute(
+        "INSERT OR REPLACE INTO response_cache
+            (key, content, model, provider, prompt_tokens, completion_tokens, hits,
+             created_at, last_hit_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7, ?7)",
+        params![
+            key,
+            completion.content,
+            completion.model,
+            completion.provider,
+            completion.usage.map(|usage| usage.prompt_tokens),
+            completion.usage.map(|usage| usage.completion_tokens),
+            now,
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM response_cache WHERE key IN (
+            SELECT key FROM response_cache ORDER BY last_hit_at DESC LIMIT -1 OFFSET ?1
+        )",
+        [MAX_ENTRIES],
+    )?;
+    Ok(())
+}
+
+/// Returns the cached completion of `request`, if caching is on and the
+/// request is deterministic. The key is returned for [`insert`] on a miss.
+pub(crate) fn get(
+    app: &AppHandle,
+    request: &ChatRequest,
+) -> (Option<String>, Option<ChatCompletion>) {
+    let Some(db) = app.try_state::<Database>() else {
+        return (None, None);
+    };
+    let conn = db.conn();
+    let Some(key) = key(request).filter(|_| enabled(&conn)) else {
+        return (None, None);
+    };
+    match lookup(&conn, &key) {
+        Ok(completion) => (Some(key), completion),
+        Err(e) => {
+            tracing::error!("failed to read the response cache: {e}");
+            (Some(key), None)
+        }
+    }
+}
+
+/// Stores a completion under `key`, logging rather than failing.
+pub(crate) fn insert(app: &AppHandle, key: &str, completion: &ChatCompletion) {
+    let Some(db) = app.try_state::<Database>() else {
+        return;
+    };
+    let conn = db.conn();
+    if let Err(e) = store(&conn, key, completion) {
+        tracing::error!("failed to write the response cache: {e}");
+    }
+}
+
+/// Returns the size of the response cache.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_response_cache_stats(db: State<'_, Database>) -> Result<CacheStats, GibberError> {
+    Ok(db.conn().query_row(
+        "SELECT COUNT(*), COALESCE(SUM(hits), 0),
+                COALESCE(SUM(LENGTH(CAST(content AS BLOB))), 0)
+         FROM response_cache",
+        [],
+        |row| {
+            Ok(CacheStats {
+                entries: row.get(0)?,
+                hits: row.get(1)?,
+                bytes: row.get(2)?,
+            })
+        },
+    )?)
+}
+
+/// Empties the response cache.
+///
+/// # Returns
+///
+/// The number of entries removed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn clear_response_cache(db: State<'_, Database>) -> Result<usize, GibberError> {
+    Ok(db.conn().execute("DELETE FROM response_cache", [])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ChatMessage, MessageRole};
+
+    fn request(prompt: &str) -> ChatRequest {
+        let mut request = ChatRequest::new(None, vec![ChatMessage::new(MessageRole::User, prompt)]);
+        request.temperature = 0.0;
+        request
+    }
+
+    fn completion() -> ChatCompletion {
+        ChatCompletion {
+            content: "Four".to_string(),
+            model: "anthropic/claude-sonnet-4".to_string(),
+            usage: Some(TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 1,
+            }),
+            cost_usd: Some(0.0001),
+            provider: Some("Anthropic".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_key_covers_request_and_skips_sampling() {
+        let first = key(&request("2 + 2?")).unwrap();
+        assert_eq!(first.len(), 64);
+        assert_eq!(key(&request("2 + 2?")), Some(first.clone()));
+        assert_ne!(key(&request("2 + 3?")), Some(first.clone()));
+        let mut longer = request("2 + 2?");
+        longer.max_tokens = 10;
+        assert_ne!(key(&longer), Some(first));
+        let mut sampled = request("2 + 2?");
+        sampled.temperature = 0.7;
+        assert_eq!(key(&sampled), None);
+    }
+
+    #[test]
+    fn test_store_and_lookup_count_hits() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        assert!(lookup(&conn, "abc").unwrap().is_none());
+        store(&conn, "abc", &completion()).unwrap();
+        let cached = lookup(&conn, "abc").unwrap().unwrap();
+        assert_eq!(cached.content, "Four");
+        assert_eq!(cached.usage.unwrap().prompt_tokens, 12);
+        // Cached answers cost nothing.
+        assert_eq!(cached.cost_usd, None);
+        let hits: i64 = conn
+            .query_row("SELECT hits FROM response_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let db = Database::open_in_memory().expect("Should open");
+        assert!(!enabled(&db.conn()));
+    }
+}
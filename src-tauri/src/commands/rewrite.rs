@@ -0,0 +1,219 @@
+//! Grammar and style quick actions.
+//!
+//! `rewrite_text` fixes, simplifies, formalizes, or shortens a piece of
+//! text with one completion and returns the result directly, without
+//! creating a conversation. The quick-capture window calls it on typed or
+//! pasted text; `rewrite_clipboard` does the same for the clipboard
+//! watcher's latest capture and can copy the result back.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::clipboard;
+use crate::commands::settings;
+use crate::commands::telemetry;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Longest text accepted, in characters.
+pub const MAX_TEXT_CHARS: usize = 20_000;
+
+const REWRITE_PROMPT: &str = "You rewrite text. {instruction} Keep the language of the \
+     original, its formatting (line breaks, lists, Markdown), names, numbers, and links. Reply \
+     with the rewritten text only, without comments or quotation marks.";
+
+/// How to rewrite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum RewriteMode {
+    /// Correct spelling, grammar, and punctuation only
+    FixGrammar,
+    /// Plainer words and shorter sentences
+    Simplify,
+    /// A formal, professional tone
+    Formalize,
+    /// Fewer words, same points
+    Shorten,
+}
+
+impl RewriteMode {
+    const fn instruction(self) -> &'static str {
+        match self {
+            Self::FixGrammar => {
+                "Correct the spelling, grammar, and punctuation and change nothing else; if \
+                 the text is already correct, return it unchanged."
+            }
+            Self::Simplify => {
+                "Make it easier to read with plain words and shorter sentences, keeping the \
+                 meaning."
+            }
+            Self::Formalize => "Give it a formal, professional tone without changing what it says.",
+            Self::Shorten => "Make it noticeably shorter, keeping every essential point.",
+        }
+    }
+
+    /// Corrections should be reproducible; the style modes get some room.
+    const fn temperature(self) -> f32 {
+        match self {
+            Self::FixGrammar => 0.0,
+            Self::Simplify | Self::Formalize | Self::Shorten => 0.3,
+        }
+    }
+
+    const fn feature(self) -> &'static str {
+        match self {
+            Self::FixGrammar => "rewrite.fixGrammar",
+            Self::Simplify => "rewrite.simplify",
+            Self::Formalize => "rewrite.formalize",
+            Self::Shorten => "rewrite.shorten",
+        }
+    }
+}
+
+/// A rewritten text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Rewrite {
+    /// The rewritten text
+    pub text: String,
+    /// How it was rewritten
+    pub mode: RewriteMode,
+    /// The model that rewrote it
+    pub model: String,
+    /// Whether the text differs from the original, ignoring surrounding
+    /// whitespace
+    pub changed: bool,
+    /// Whether the result was copied to the clipboard
+    pub copied: bool,
+}
+
+fn prompt(mode: RewriteMode) -> String {
+    REWRITE_PROMPT.replacen("{instruction}", mode.instruction(), 1)
+}
+
+async fn rewrite(
+    app: &AppHandle,
+    text: &str,
+    mode: RewriteMode,
+    model: Option<String>,
+) -> Result<Rewrite, GibberError> {
+    let original = text.trim();
+    if original.is_empty() {
+        return Err(GibberError::invalid("There is no text to rewrite"));
+    }
+    if original.chars().count() > MAX_TEXT_CHARS {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!("The text is longer than {MAX_TEXT_CHARS} characters"),
+        ));
+    }
+    let model = match model.filter(|model| !model.trim().is_empty()) {
+        Some(model) => model,
+        None => settings::load(&app.state::<Database>().conn())?.default_model,
+    };
+    let mut request = ChatRequest::new(
+        Some(&model),
+        vec![
+            ChatMessage::new(MessageRole::System, prompt(mode)),
+            ChatMessage::new(MessageRole::User, original),
+        ],
+    );
+    request.temperature = mode.temperature();
+    telemetry::record_feature(app, mode.feature());
+    let completion = chat::complete(app, &request).await?;
+    let rewritten = completion.content.trim().to_string();
+    Ok(Rewrite {
+        changed: rewritten != original,
+        text: rewritten,
+        mode,
+        model: completion.model,
+        copied: false,
+    })
+}
+
+/// Rewrites `text` in the given mode with the default model, or `model`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` for blank text,
+/// `TOO_LARGE` for text over 20,000 characters, or the code of the
+/// failed completion.
+///
+/// # Example
+///
+/// ```typescript
+/// const { text, changed } = await invoke("rewrite_text", {
+///   text: "their going to the studio tomorow",
+///   mode: "fixGrammar",
+///   model: null,
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn rewrite_text(
+    app: AppHandle,
+    text: String,
+    mode: RewriteMode,
+    model: Option<String>,
+) -> Result<Rewrite, GibberError> {
+    rewrite(&app, &text, mode, model).await
+}
+
+/// Rewrites the clipboard watcher's latest text capture, copying the
+/// result back to the clipboard when `copy` is set.
+///
+/// The capture is used up, and a copied result isn't offered again as a
+/// new capture.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `EMPTY` if no text has been
+/// captured, `CLIPBOARD` if the result can't be copied, or an error as
+/// [`rewrite_text`] does.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("rewrite_clipboard", { mode: "shorten", copy: true });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn rewrite_clipboard(
+    app: AppHandle,
+    mode: RewriteMode,
+    copy: bool,
+) -> Result<Rewrite, GibberError> {
+    let text = clipboard::take_text(&app)
+        .ok_or_else(|| GibberError::new("EMPTY", "No copied text has been captured"))?;
+    let mut rewritten = rewrite(&app, &text, mode, None).await?;
+    if copy {
+        clipboard::write_text(&app, &rewritten.text)?;
+        rewritten.copied = true;
+    }
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes_serialize_in_camel_case() {
+        let mode: RewriteMode = serde_json::from_str("\"fixGrammar\"").unwrap();
+        assert_eq!(mode, RewriteMode::FixGrammar);
+        assert_eq!(
+            serde_json::to_string(&RewriteMode::Shorten).unwrap(),
+            "\"shorten\""
+        );
+        assert!(serde_json::from_str::<RewriteMode>("\"poeticize\"").is_err());
+    }
+
+    #[test]
+    fn test_prompt_names_the_instruction() {
+        let formal = prompt(RewriteMode::Formalize);
+        assert!(formal.starts_with("You rewrite text. Give it a formal, professional tone"));
+        assert!(!formal.contains("{instruction}"));
+        assert!(RewriteMode::FixGrammar.temperature() < RewriteMode::Simplify.temperature());
+    }
+}
@@ -0,0 +1,359 @@
+//! Sensitive-data routing policy.
+//!
+//! Users can tag conversations as sensitive and mark providers (e.g. `openai`,
+//! `openrouter`) or individual models (e.g. `google/gemini-2.5-pro`) as not
+//! allowed to receive sensitive data, for instance because of their retention
+//! terms. Every backend request made for a tagged conversation is checked with
+//! [`enforce`]; refusals fail with code `POLICY_BLOCKED` and are announced as
+//! [`events::ROUTING_POLICY_BLOCKED`]. The frontend AI client checks its own
+//! requests with `check_conversation_route`.
+//!
+//! A request is routed through a gateway (OpenRouter for chat, the image
+//! provider for images) to the provider named by the model ID prefix, and
+//! both must be allowed.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::events;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the [`RoutingPolicy`] document.
+pub const SETTINGS_KEY: &str = "routingPolicy";
+
+/// Gateway of chat completions.
+pub const CHAT_GATEWAY: &str = "openrouter";
+
+/// Providers and models that must not receive sensitive conversations.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoutingPolicy {
+    /// Provider or gateway names, e.g. `openai`
+    pub blocked_providers: Vec<String>,
+    /// Model IDs, e.g. `openai/gpt-4o`; variants like `:online` match too
+    pub blocked_models: Vec<String>,
+}
+
+impl RoutingPolicy {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        if let Some(entry) = self
+            .blocked_providers
+            .iter()
+            .chain(&self.blocked_models)
+            .find(|entry| entry.trim().is_empty())
+        {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                format!("Invalid policy entry \"{entry}\""),
+            ));
+        }
+        if let Some(provider) = self.blocked_providers.iter().find(|p| p.contains('/')) {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                format!("\"{provider}\" is a model, not a provider"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Why sending sensitive data to `model` through `gateway` is refused,
+    /// or `None` if it is allowed.
+    pub fn refusal(&self, gateway: &str, model: &str) -> Option<String> {
+        let base = model.split_once(':').map_or(model, |(base, _)| base);
+        let provider = base.split_once('/').map(|(provider, _)| provider);
+        let matches = |entry: &String, value: &str| entry.trim().eq_ignore_ascii_case(value);
+        if let Some(entry) = self.blocked_models.iter().find(|m| matches(m, base)) {
+            return Some(format!("{} must not receive sensitive data", entry.trim()));
+        }
+        [Some(gateway), provider]
+            .into_iter()
+            .flatten()
+            .find(|name| self.blocked_providers.iter().any(|p| matches(p, name)))
+            .map(|name| format!("{name} must not receive sensitive data"))
+    }
+}
+
+pub(crate) fn load_policy(conn: &Connection) -> RoutingPolicy {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Payload of [`events::ROUTING_POLICY_BLOCKED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedPayload {
+    /// Conversation the request was for
+    pub conversation_id: String,
+    /// Gateway the request would have gone through
+    pub gateway: String,
+    /// Model the request was for
+    pub model: String,
+    /// Why it was refused
+    pub message: String,
+}
+
+/// Returns whether a conversation is tagged as sensitive.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub(crate) fn is_sensitive(conn: &Connection, conversation_id: &str) -> rusqlite::Result<bool> {
+    let sensitive: Option<i64> = conn
+        .query_row(
+            "SELECT 1 FROM sensitive_conversations WHERE conversation_id = ?1",
+            [conversation_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(sensitive.is_some())
+}
+
+fn check(
+    conn: &Connection,
+    conversation_id: &str,
+    gateway: &str,
+    model: &str,
+) -> Result<Option<String>, GibberError> {
+    if !is_sensitive(conn, conversation_id)? {
+        return Ok(None);
+    }
+    Ok(load_policy(conn).refusal(gateway, model))
+}
+
+/// Refuses a request for a sensitive conversation that the policy doesn't
+/// allow, announcing the refusal as [`events::ROUTING_POLICY_BLOCKED`].
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `POLICY_BLOCKED` if the request
+/// is refused.
+pub(crate) fn enforce(
+    app: &AppHandle,
+    conversation_id: &str,
+    gateway: &str,
+    model: &str,
+) -> Result<(), GibberError> {
+    let refusal = check(
+        &app.state::<Database>().conn(),
+        conversation_id,
+        gateway,
+        model,
+    )?;
+    let Some(message) = refusal else {
+        return Ok(());
+    };
+    tracing::info!(gateway, model, "refused to route a sensitive conversation");
+    let payload = BlockedPayload {
+        conversation_id: conversation_id.to_string(),
+        gateway: gateway.to_string(),
+        model: model.to_string(),
+        message: message.clone(),
+    };
+    events::emit_typed(app, events::ROUTING_POLICY_BLOCKED, &payload);
+    Err(GibberError::new(
+        "POLICY_BLOCKED",
+        format!("This conversation is sensitive and {message}"),
+    ))
+}
+
+/// Returns the routing policy.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_routing_policy(db: State<'_, Database>) -> RoutingPolicy {
+    load_policy(&db.conn())
+}
+
+/// Replaces the routing policy.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` for an empty
+/// entry or a model listed as a provider, or `DATABASE` if the policy
+/// cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_routing_policy", {
+///   policy: { blockedProviders: ["openai"], blockedModels: ["google/gemini-2.5-pro"] },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_routing_policy(
+    db: State<'_, Database>,
+    policy: RoutingPolicy,
+) -> Result<RoutingPolicy, GibberError> {
+    policy.validate()?;
+    let value = serde_json::to_string(&policy).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(policy)
+}
+
+/// Tags or untags a conversation as sensitive.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the
+/// conversation doesn't exist.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_conversation_sensitive", { conversationId, sensitive: true });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn set_conversation_sensitive(
+    db: State<'_, Database>,
+    conversation_id: &str,
+    sensitive: bool,
+) -> Result<(), GibberError> {
+    let conn = db.conn();
+    if !crate::commands::conversations::conversation_exists(&conn, conversation_id)? {
+        return Err(GibberError::new(
+            "NOT_FOUND",
+            format!("Conversation {conversation_id} not found"),
+        ));
+    }
+    if sensitive {
+        conn.execute(
+            "INSERT OR IGNORE INTO sensitive_conversations (conversation_id, tagged_at)
+             VALUES (?1, ?2)",
+            params![conversation_id, db::now_millis()],
+        )?;
+    } else {
+        conn.execute(
+            "DELETE FROM sensitive_conversations WHERE conversation_id = ?1",
+            [conversation_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Lists the IDs of the conversations tagged as sensitive.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_sensitive_conversations(db: State<'_, Database>) -> Result<Vec<String>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn
+        .prepare("SELECT conversation_id FROM sensitive_conversations ORDER BY tagged_at DESC")?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(ids)
+}
+
+/// Checks that a chat request from the frontend may be sent.
+///
+/// Refusals are also announced as `routing-policy://blocked`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `POLICY_BLOCKED` if the
+/// conversation is sensitive and the model isn't allowed to receive it.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("check_conversation_route", { conversationId, model });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn check_conversation_route(
+    app: AppHandle,
+    conversation_id: &str,
+    model: &str,
+) -> Result<(), GibberError> {
+    enforce(&app, conversation_id, CHAT_GATEWAY, model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::conversations::{self, NewConversation};
+
+    #[test]
+    fn test_refusal_matches_gateway_provider_and_model() {
+        let policy = RoutingPolicy {
+            blocked_providers: vec!["OpenAI".to_string()],
+            blocked_models: vec!["google/gemini-2.5-pro".to_string()],
+        };
+        assert!(policy.refusal("openrouter", "openai/gpt-4o").is_some());
+        assert!(policy
+            .refusal("openrouter", "google/gemini-2.5-pro:online")
+            .is_some());
+        assert!(policy.refusal("openai", "gpt-image-1").is_some());
+        assert!(policy
+            .refusal("openrouter", "anthropic/claude-sonnet-4")
+            .is_none());
+    }
+
+    #[test]
+    fn test_validate_policy() {
+        assert!(RoutingPolicy::default().validate().is_ok());
+        let model_as_provider = RoutingPolicy {
+            blocked_providers: vec!["openai/gpt-4o".to_string()],
+            ..RoutingPolicy::default()
+        };
+        assert_eq!(
+            model_as_provider.validate().unwrap_err().code(),
+            "INVALID_SETTINGS"
+        );
+    }
+
+    #[test]
+    fn test_check_only_applies_to_sensitive_conversations() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        let conversation = conversations::insert_conversation(
+            &conn,
+            &NewConversation {
+                title: "Medical".to_string(),
+                model: None,
+                system_prompt: None,
+                source: None,
+            },
+        )
+        .unwrap();
+        let policy = RoutingPolicy {
+            blocked_providers: vec![CHAT_GATEWAY.to_string()],
+            ..RoutingPolicy::default()
+        };
+        db::write_setting(
+            &conn,
+            SETTINGS_KEY,
+            &serde_json::to_string(&policy).unwrap(),
+        )
+        .unwrap();
+        assert!(
+            check(&conn, &conversation.id, CHAT_GATEWAY, "openai/gpt-4o")
+                .unwrap()
+                .is_none()
+        );
+        conn.execute(
+            "INSERT INTO sensitive_conversations (conversation_id, tagged_at) VALUES (?1, 0)",
+            [&conversation.id],
+        )
+        .unwrap();
+        assert!(
+            check(&conn, &conversation.id, CHAT_GATEWAY, "openai/gpt-4o")
+                .unwrap()
+                .is_some()
+        );
+    }
+}
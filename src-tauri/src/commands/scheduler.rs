@@ -0,0 +1,665 @@
+//! Scheduled and recurring prompts.
+//!
+//! Users save a prompt together with a cron expression; a background loop
+//! checks for due tasks, runs each prompt through the backend chat client,
+//! stores the exchange as a new conversation, and optionally sends a desktop
+//! notification when the run completes. A task can instead run a saved
+//! workflow with the prompt as its input; the run is recorded in the
+//! workflow's history rather than as a conversation. Scheduled runs are
+//! skipped while sync is paused from the tray; "run now" still works.
+//!
+//! Cron expressions accept the standard five fields (`min hour dom mon dow`)
+//! or the six/seven-field form with seconds and year. Times are evaluated in
+//! the user's local timezone.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::conversations::{self, NewConversation, NewMessage};
+use crate::commands::events;
+use crate::commands::i18n;
+use crate::commands::notifications::{self, NotificationKind};
+use crate::commands::telemetry;
+use crate::commands::tray;
+use crate::commands::webhooks::{self, WebhookEvent};
+use crate::commands::workflows::{self, WorkflowStatus};
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// How often the background loop checks for due tasks.
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Scheduled task {id} not found"))
+}
+
+/// Outcome of the most recent run of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// The prompt ran and its result was stored
+    Succeeded,
+    /// The prompt could not be completed
+    Failed,
+}
+
+impl RunStatus {
+    pub(crate) const fn as_str(self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "succeeded" => Some(Self::Succeeded),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// A saved prompt that runs on a schedule.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTask {
+    /// Unique identifier
+    pub id: String,
+    /// Display name, also used as the title of result conversations
+    pub name: String,
+    /// The user prompt sent to the model
+    pub prompt: String,
+    /// Optional system prompt
+    pub system_prompt: Option<String>,
+    /// Model override; defaults to the standard tier
+    pub model: Option<String>,
+    /// Cron expression describing when the task runs
+    pub cron: String,
+    /// Whether the task is active
+    pub enabled: bool,
+    /// Whether to send a desktop notification on completion
+    pub notify: bool,
+    /// Time of the last run in Unix milliseconds
+    pub last_run_at: Option<i64>,
+    /// Outcome of the last run
+    pub last_status: Option<RunStatus>,
+    /// Error message of the last failed run
+    pub last_error: Option<String>,
+    /// Conversation produced by the last successful run
+    pub last_conversation_id: Option<String>,
+    /// Next scheduled run in Unix milliseconds
+    pub next_run_at: Option<i64>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// Workflow run with the prompt as its input, instead of the model
+    pub workflow_id: Option<String>,
+    /// Workflow run started by the last run
+    pub last_workflow_run_id: Option<String>,
+}
+
+/// Fields for creating or updating a scheduled task.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTaskInput {
+    /// Display name
+    pub name: String,
+    /// The user prompt sent to the model
+    pub prompt: String,
+    /// Optional system prompt
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Model override
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Cron expression
+    pub cron: String,
+    /// Whether the task is active (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Whether to notify on completion (default: true)
+    #[serde(default = "default_true")]
+    pub notify: bool,
+    /// Workflow to run instead of asking the model; the prompt becomes its
+    /// input and may be empty
+    #[serde(default)]
+    pub workflow_id: Option<String>,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+/// Payload of [`events::SCHEDULER_RUN_COMPLETED`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RunCompletedPayload {
+    /// The task that ran
+    pub task_id: String,
+    /// Outcome of the run
+    pub status: RunStatus,
+    /// Conversation holding the result, on success
+    pub conversation_id: Option<String>,
+    /// Workflow run, for tasks that run a workflow
+    pub workflow_run_id: Option<String>,
+    /// Error message, on failure
+    pub error: Option<String>,
+}
+
+/// Parses a cron expression, accepting the standard five-field form.
+pub(crate) fn parse_schedule(expr: &str) -> Result<cron::Schedule, GibberError> {
+    let normalized = if expr.split_whitespace().count() == 5 {
+        format!("0 {expr}")
+    } else {
+        expr.to_string()
+    };
+    cron::Schedule::from_str(&normalized)
+        .map_err(|e| GibberError::new("INVALID_SCHEDULE", format!("Invalid cron expression: {e}")))
+}
+
+/// Computes the first run strictly after `after_millis` in local time.
+pub(crate) fn next_run_after(schedule: &cron::Schedule, after_millis: i64) -> Option<i64> {
+    let after = Local.timestamp_millis_opt(after_millis).single()?;
+    schedule
+        .after(&after)
+        .next()
+        .map(|time| time.timestamp_millis())
+}
+
+const TASK_COLUMNS: &str = "id, name, prompt, system_prompt, model, cron, enabled, notify, \
+     last_run_at, last_status, last_error, last_conversation_id, next_run_at, created_at, \
+     workflow_id, last_workflow_run_id";
+
+fn task_from_row(row: &Row<'_>) -> rusqlite::Result<ScheduledTask> {
+    let status: Option<String> = row.get(9)?;
+    Ok(ScheduledTask {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        prompt: row.get(2)?,
+        system_prompt: row.get(3)?,
+        model: row.get(4)?,
+        cron: row.get(5)?,
+        enabled: row.get(6)?,
+        notify: row.get(7)?,
+        last_run_at: row.get(8)?,
+        last_status: status.as_deref().and_then(RunStatus::parse),
+        last_error: row.get(10)?,
+        last_conversation_id: row.get(11)?,
+        next_run_at: row.get(12)?,
+        created_at: row.get(13)?,
+        workflow_id: row.get(14)?,
+        last_workflow_run_id: row.get(15)?,
+    })
+}
+
+fn load_task(conn: &Connection, id: &str) -> Result<ScheduledTask, GibberError> {
+    conn.query_row(
+        &format!("SELECT {TASK_COLUMNS} FROM scheduled_tasks WHERE id = ?1"),
+        [id],
+        task_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found(id))
+}
+
+fn due_tasks(conn: &Connection, now: i64) -> rusqlite::Result<Vec<ScheduledTask>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TASK_COLUMNS} FROM scheduled_tasks
+         WHERE enabled = 1 AND next_run_at IS NOT NULL AND next_run_at <= ?1"
+    ))?;
+    let tasks = stmt
+        .query_map([now], task_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(tasks)
+}
+
+/// Validates input and computes the next run time for an enabled task.
+fn validate_input(input: &ScheduledTaskInput) -> Result<Option<i64>, GibberError> {
+    if input.name.trim().is_empty() {
+        return Err(GibberError::new("INVALID_INPUT", "Task name is required"));
+    }
+    if input.workflow_id.is_none() && input.prompt.trim().is_empty() {
+        return Err(GibberError::new("INVALID_INPUT", "Task prompt is required"));
+    }
+    let schedule = parse_schedule(&input.cron)?;
+    Ok(if input.enabled {
+        next_run_after(&schedule, db::now_millis())
+    } else {
+        None
+    })
+}
+
+/// Starts the background loop that runs due tasks.
+///
+/// Must be called after the [`Database`] and tray state have been added to
+/// managed state.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if tray::is_sync_paused(&app) {
+                continue;
+            }
+            let due = {
+                let db = app.state::<Database>();
+                let conn = db.conn();
+                due_tasks(&conn, db::now_millis())
+            };
+            match due {
+                Ok(tasks) => {
+                    for task in tasks {
+                        run_task(&app, &task).await;
+                    }
+                }
+                Err(e) => tracing::error!("failed to query due tasks: {e}"),
+            }
+        }
+    });
+}
+
+/// Runs a single task, records its outcome, and reports it to the user.
+async fn run_task(app: &AppHandle, task: &ScheduledTask) -> RunCompletedPayload {
+    telemetry::record_feature(app, "scheduler.run");
+    let payload = match &task.workflow_id {
+        Some(workflow_id) => run_workflow(app, task, workflow_id).await,
+        None => run_prompt(app, task).await,
+    };
+    let now = db::now_millis();
+    let next_run_at = parse_schedule(&task.cron)
+        .ok()
+        .and_then(|schedule| next_run_after(&schedule, now));
+
+    if let Err(e) = app.state::<Database>().conn().execute(
+        "UPDATE scheduled_tasks
+         SET last_run_at = ?1, last_status = ?2, last_error = ?3,
+             last_conversation_id = COALESCE(?4, last_conversation_id),
+             last_workflow_run_id = COALESCE(?5, last_workflow_run_id), next_run_at = ?6
+         WHERE id = ?7",
+        params![
+            now,
+            payload.status.as_str(),
+            payload.error,
+            payload.conversation_id,
+            payload.workflow_run_id,
+            next_run_at,
+            task.id,
+        ],
+    ) {
+        tracing::error!(task_id = %task.id, "failed to record run: {e}");
+    }
+
+    events::emit_typed(app, events::SCHEDULER_RUN_COMPLETED, &payload);
+    let event = match payload.status {
+        RunStatus::Succeeded => WebhookEvent::TaskSucceeded,
+        RunStatus::Failed => WebhookEvent::TaskFailed,
+    };
+    webhooks::dispatch(app, event, &payload);
+    if task.notify {
+        let body = match payload.status {
+            RunStatus::Succeeded => i18n::text(app, "notification-task-finished"),
+            RunStatus::Failed => i18n::text_with(
+                app,
+                "notification-failed",
+                &[("error", payload.error.as_deref().unwrap_or("unknown error"))],
+            ),
+        };
+        notifications::notify(
+            app,
+            NotificationKind::ScheduledTask,
+            &task.name,
+            &body,
+            payload.conversation_id.as_deref(),
+        );
+    }
+    payload
+}
+
+/// Sends the task's prompt to the model and stores the exchange.
+async fn run_prompt(app: &AppHandle, task: &ScheduledTask) -> RunCompletedPayload {
+    let mut messages = Vec::new();
+    if let Some(system) = task.system_prompt.as_deref().filter(|s| !s.is_empty()) {
+        messages.push(ChatMessage::new(MessageRole::System, system));
+    }
+    messages.push(ChatMessage::new(MessageRole::User, task.prompt.clone()));
+    let request = ChatRequest::new(task.model.as_deref(), messages);
+
+    let result = chat::complete(app, &request).await;
+    let outcome = result.map_err(|e| e.to_string()).and_then(|completion| {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        store_result(&conn, task, &request, &completion, db::now_millis())
+            .map_err(|e| e.to_string())
+    });
+    let mut payload = completed(task, outcome.as_ref().err().map(String::as_str));
+    payload.conversation_id = outcome.ok();
+    payload
+}
+
+/// Runs the task's workflow with the prompt as its input.
+async fn run_workflow(
+    app: &AppHandle,
+    task: &ScheduledTask,
+    workflow_id: &str,
+) -> RunCompletedPayload {
+    match workflows::run_scheduled(app, workflow_id, task.prompt.clone()).await {
+        Ok(run) => {
+            let error = match run.status {
+                WorkflowStatus::Succeeded => None,
+                _ => Some(run.error.unwrap_or_else(|| "Workflow failed".to_string())),
+            };
+            let mut payload = completed(task, error.as_deref());
+            payload.workflow_run_id = Some(run.id);
+            payload
+        }
+        Err(e) => completed(task, Some(e.to_string().as_str())),
+    }
+}
+
+/// Payload of a finished run, failed if there is an `error`.
+fn completed(task: &ScheduledTask, error: Option<&str>) -> RunCompletedPayload {
+    RunCompletedPayload {
+        task_id: task.id.clone(),
+        status: if error.is_some() {
+            RunStatus::Failed
+        } else {
+            RunStatus::Succeeded
+        },
+        conversation_id: None,
+        workflow_run_id: None,
+        error: error.map(str::to_string),
+    }
+}
+
+/// Stores a completed run as a new conversation and returns its ID.
+fn store_result(
+    conn: &Connection,
+    task: &ScheduledTask,
+    request: &ChatRequest,
+    completion: &chat::ChatCompletion,
+    now: i64,
+) -> rusqlite::Result<String> {
+    let started = Local
+        .timestamp_millis_opt(now)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let conversation = conversations::insert_conversation(
+        conn,
+        &NewConversation {
+            title: format!("{} ({started})", task.name),
+            model: Some(request.model.clone()),
+            system_prompt: task.system_prompt.clone(),
+            source: Some("scheduler".to_string()),
+        },
+    )?;
+    conversations::insert_message(
+        conn,
+        &conversation.id,
+        &NewMessage {
+            role: MessageRole::User,
+            content: task.prompt.clone(),
+            model: None,
+            usage: None,
+        },
+    )?;
+    conversations::insert_message(
+        conn,
+        &conversation.id,
+        &NewMessage {
+            role: MessageRole::Assistant,
+            content: completion.content.clone(),
+            model: Some(completion.model.clone()),
+            usage: completion.usage,
+        },
+    )?;
+    Ok(conversation.id)
+}
+
+/// Lists all scheduled tasks.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const tasks = await invoke("list_scheduled_tasks");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_scheduled_tasks(db: State<'_, Database>) -> Result<Vec<ScheduledTask>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TASK_COLUMNS} FROM scheduled_tasks ORDER BY created_at"
+    ))?;
+    let tasks = stmt
+        .query_map([], task_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(tasks)
+}
+
+/// Creates a scheduled task.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SCHEDULE` if the cron
+/// expression doesn't parse, `INVALID_INPUT` for empty fields, `NOT_FOUND`
+/// if the workflow doesn't exist, or `DATABASE` if the insert fails.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("create_scheduled_task", {
+///   input: { name: "Morning idea", prompt: "Suggest a drum pattern", cron: "0 8 * * *" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn create_scheduled_task(
+    db: State<'_, Database>,
+    input: ScheduledTaskInput,
+) -> Result<ScheduledTask, GibberError> {
+    let next_run_at = validate_input(&input)?;
+    let id = db::new_id();
+    let conn = db.conn();
+    if let Some(workflow_id) = &input.workflow_id {
+        workflows::load_workflow(&conn, workflow_id)?;
+    }
+    conn.execute(
+        "INSERT INTO scheduled_tasks
+             (id, name, prompt, system_prompt, model, cron, enabled, notify, next_run_at,
+              created_at, workflow_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            id,
+            input.name,
+            input.prompt,
+            input.system_prompt,
+            input.model,
+            input.cron,
+            input.enabled,
+            input.notify,
+            next_run_at,
+            db::now_millis(),
+            input.workflow_id,
+        ],
+    )?;
+    load_task(&conn, &id)
+}
+
+/// Replaces the configuration of a scheduled task.
+///
+/// The next run time is recomputed from the new cron expression.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the task doesn't
+/// exist, or the same validation errors as [`create_scheduled_task`].
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn update_scheduled_task(
+    db: State<'_, Database>,
+    id: &str,
+    input: ScheduledTaskInput,
+) -> Result<ScheduledTask, GibberError> {
+    let next_run_at = validate_input(&input)?;
+    let conn = db.conn();
+    if let Some(workflow_id) = &input.workflow_id {
+        workflows::load_workflow(&conn, workflow_id)?;
+    }
+    let updated = conn.execute(
+        "UPDATE scheduled_tasks
+         SET name = ?1, prompt = ?2, system_prompt = ?3, model = ?4, cron = ?5,
+             enabled = ?6, notify = ?7, next_run_at = ?8, workflow_id = ?9
+         WHERE id = ?10",
+        params![
+            input.name,
+            input.prompt,
+            input.system_prompt,
+            input.model,
+            input.cron,
+            input.enabled,
+            input.notify,
+            next_run_at,
+            input.workflow_id,
+            id,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(not_found(id));
+    }
+    load_task(&conn, id)
+}
+
+/// Deletes a scheduled task. Conversations it produced are kept.
+///
+/// # Returns
+///
+/// Returns `true` if the task was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_scheduled_task(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM scheduled_tasks WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+/// Runs a scheduled task immediately, outside of its schedule.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the task doesn't exist.
+/// Failures of the run itself are reported in the returned payload.
+///
+/// # Example
+///
+/// ```typescript
+/// const result = await invoke("run_scheduled_task_now", { id });
+/// if (result.status === "succeeded") openConversation(result.conversationId);
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn run_scheduled_task_now(
+    app: AppHandle,
+    id: String,
+) -> Result<RunCompletedPayload, GibberError> {
+    let task = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        load_task(&conn, &id)?
+    };
+    Ok(run_task(&app, &task).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(cron: &str) -> ScheduledTaskInput {
+        ScheduledTaskInput {
+            name: "Daily".to_string(),
+            prompt: "Suggest a chord progression".to_string(),
+            system_prompt: None,
+            model: None,
+            cron: cron.to_string(),
+            enabled: true,
+            notify: true,
+            workflow_id: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_schedule_accepts_five_fields() {
+        assert!(parse_schedule("0 8 * * *").is_ok());
+        assert!(parse_schedule("0 0 8 * * *").is_ok());
+    }
+
+    #[test]
+    fn test_parse_schedule_rejects_garbage() {
+        let err = parse_schedule("every morning").unwrap_err();
+        assert_eq!(err.code(), "INVALID_SCHEDULE");
+    }
+
+    #[test]
+    fn test_next_run_is_in_the_future() {
+        let schedule = parse_schedule("*/5 * * * *").unwrap();
+        let now = db::now_millis();
+        let next = next_run_after(&schedule, now).expect("Should have a next run");
+        assert!(next > now);
+        assert!(next - now <= 5 * 60 * 1000);
+    }
+
+    #[test]
+    fn test_validate_input() {
+        assert!(validate_input(&input("0 8 * * *")).unwrap().is_some());
+        let disabled = ScheduledTaskInput {
+            enabled: false,
+            ..input("0 8 * * *")
+        };
+        assert!(validate_input(&disabled).unwrap().is_none());
+        let empty = ScheduledTaskInput {
+            prompt: "  ".to_string(),
+            ..input("0 8 * * *")
+        };
+        assert_eq!(validate_input(&empty).unwrap_err().code(), "INVALID_INPUT");
+        let workflow = ScheduledTaskInput {
+            workflow_id: Some("w".to_string()),
+            ..empty
+        };
+        assert!(validate_input(&workflow).is_ok());
+    }
+
+    #[test]
+    fn test_due_tasks_only_returns_enabled_past_due() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        for (id, enabled, next) in [("a", true, 10), ("b", false, 10), ("c", true, 1000)] {
+            conn.execute(
+                "INSERT INTO scheduled_tasks (id, name, prompt, cron, enabled, next_run_at, created_at)
+                 VALUES (?1, 'n', 'p', '0 8 * * *', ?2, ?3, 0)",
+                params![id, enabled, next],
+            )
+            .unwrap();
+        }
+        let due = due_tasks(&conn, 100).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, "a");
+    }
+}
@@ -0,0 +1,293 @@
+//! Screenshot capture into chat.
+//!
+//! `capture_screenshot` grabs a whole screen, a single window, or a region
+//! of a screen, stores the PNG in the attachment store, and attaches it to
+//! a conversation (the current one unless another is given). A global
+//! shortcut captures the primary screen into the current conversation.
+
+use std::io::Cursor;
+
+use image::{ImageFormat, RgbaImage};
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::attachments::{self, Attachment};
+use crate::commands::telemetry;
+use crate::commands::windows::CurrentConversation;
+use crate::error::GibberError;
+
+/// Default shortcut that captures the primary screen.
+pub const DEFAULT_SHORTCUT: &str = "CommandOrControl+Alt+S";
+
+/// Application name of our own windows, skipped when picking a window.
+const OWN_APP_NAME: &str = "Gibber AI";
+
+/// What to capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum CaptureTarget {
+    /// A whole screen; the primary one unless `monitor` is given
+    Screen {
+        /// Monitor ID
+        monitor: Option<u32>,
+    },
+    /// A single window; the topmost non-Gibber window unless `window` is given
+    Window {
+        /// Window ID
+        window: Option<u32>,
+    },
+    /// A rectangle of a screen, in physical pixels relative to its top-left
+    #[serde(rename_all = "camelCase")]
+    Region {
+        /// Monitor ID; the primary one if omitted
+        monitor: Option<u32>,
+        /// Left edge
+        x: u32,
+        /// Top edge
+        y: u32,
+        /// Width
+        width: u32,
+        /// Height
+        height: u32,
+    },
+}
+
+fn find_monitor(id: Option<u32>) -> Result<xcap::Monitor, GibberError> {
+    let monitors = xcap::Monitor::all()?;
+    let found = match id {
+        Some(id) => monitors.into_iter().find(|m| m.id() == id),
+        None => monitors.into_iter().find(xcap::Monitor::is_primary),
+    };
+    found.ok_or_else(|| GibberError::new("NOT_FOUND", "Screen not found"))
+}
+
+fn find_window(id: Option<u32>) -> Result<xcap::Window, GibberError> {
+    let windows = xcap::Window::all()?;
+    let found = match id {
+        Some(id) => windows.into_iter().find(|w| w.id() == id),
+        None => windows
+            .into_iter()
+            .find(|w| !w.is_minimized() && w.app_name() != OWN_APP_NAME),
+    };
+    found.ok_or_else(|| GibberError::new("NOT_FOUND", "Window not found"))
+}
+
+/// Crops `image` to a region, rejecting regions outside it.
+fn crop(
+    image: &RgbaImage,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Result<RgbaImage, GibberError> {
+    let fits = width > 0
+        && height > 0
+        && x.checked_add(width)
+            .is_some_and(|right| right <= image.width())
+        && y.checked_add(height)
+            .is_some_and(|bottom| bottom <= image.height());
+    if !fits {
+        return Err(GibberError::new(
+            "INVALID_REGION",
+            format!(
+                "Region {width}×{height} at ({x}, {y}) is outside the {}×{} screen",
+                image.width(),
+                image.height()
+            ),
+        ));
+    }
+    Ok(image::imageops::crop_imm(image, x, y, width, height).to_image())
+}
+
+/// Captures the target as an image.
+fn capture(target: CaptureTarget) -> Result<RgbaImage, GibberError> {
+    match target {
+        CaptureTarget::Screen { monitor } => Ok(find_monitor(monitor)?.capture_image()?),
+        CaptureTarget::Window { window } => Ok(find_window(window)?.capture_image()?),
+        CaptureTarget::Region {
+            monitor,
+            x,
+            y,
+            width,
+            height,
+        } => crop(
+            &find_monitor(monitor)?.capture_image()?,
+            x,
+            y,
+            width,
+            height,
+        ),
+    }
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, GibberError> {
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| GibberError::new("ENCODE_FAILED", e.to_string()))?;
+    Ok(png.into_inner())
+}
+
+/// Captures a screenshot and stores it as an attachment.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the capture or storing fails.
+pub fn capture_to_attachment(
+    app: &AppHandle,
+    target: CaptureTarget,
+    conversation_id: Option<&str>,
+) -> Result<Attachment, GibberError> {
+    let png = encode_png(&capture(target)?)?;
+    telemetry::record_feature(app, "screenshot.capture");
+    let file_name = format!(
+        "screenshot-{}.png",
+        chrono::Local::now().format("%Y-%m-%d-%H%M%S")
+    );
+    attachments::store_captured(app, &file_name, &png, conversation_id)
+}
+
+/// Captures the primary screen into the current conversation.
+///
+/// Runs on its own thread, since capturing blocks for a while.
+pub fn capture_current(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let conversation_id = app.state::<CurrentConversation>().get();
+        if let Err(e) = capture_to_attachment(
+            &app,
+            CaptureTarget::Screen { monitor: None },
+            conversation_id.as_deref(),
+        ) {
+            tracing::error!("capture failed: {e}");
+        }
+    });
+}
+
+/// Captures a screenshot into a conversation.
+///
+/// Attaches to `conversationId` if given, otherwise to the current
+/// conversation; with neither, the screenshot stays pending.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the screen or window
+/// doesn't exist, `INVALID_REGION` for regions outside the screen, or
+/// `CAPTURE_FAILED` if the platform refuses the capture (e.g. missing
+/// screen-recording permission on macOS).
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("capture_screenshot", { target: { mode: "screen" } });
+/// await invoke("capture_screenshot", {
+///   target: { mode: "region", x: 0, y: 0, width: 800, height: 600 },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub async fn capture_screenshot(
+    app: AppHandle,
+    target: CaptureTarget,
+    conversation_id: Option<String>,
+) -> Result<Attachment, GibberError> {
+    let conversation_id = conversation_id.or_else(|| app.state::<CurrentConversation>().get());
+    tauri::async_runtime::spawn_blocking(move || {
+        capture_to_attachment(&app, target, conversation_id.as_deref())
+    })
+    .await
+    .map_err(|e| GibberError::new("CAPTURE_FAILED", e.to_string()))?
+}
+
+/// A screen or window that can be captured.
+#[derive(Debug, Clone, serde::Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureSource {
+    /// Monitor or window ID
+    pub id: u32,
+    /// Display name
+    pub name: String,
+    /// Width in physical pixels
+    pub width: u32,
+    /// Height in physical pixels
+    pub height: u32,
+}
+
+/// Screens and windows that can be captured.
+#[derive(Debug, Clone, serde::Serialize, specta::Type)]
+pub struct CaptureSources {
+    /// Connected screens
+    pub screens: Vec<CaptureSource>,
+    /// Visible windows of other applications, topmost first
+    pub windows: Vec<CaptureSource>,
+}
+
+/// Lists capturable screens and windows.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if they cannot be enumerated.
+#[tauri::command]
+#[specta::specta]
+pub fn list_capture_sources() -> Result<CaptureSources, GibberError> {
+    let screens = xcap::Monitor::all()?
+        .iter()
+        .map(|m| CaptureSource {
+            id: m.id(),
+            name: m.name().to_string(),
+            width: m.width(),
+            height: m.height(),
+        })
+        .collect();
+    let windows = xcap::Window::all()?
+        .iter()
+        .filter(|w| !w.is_minimized() && w.app_name() != OWN_APP_NAME)
+        .map(|w| CaptureSource {
+            id: w.id(),
+            name: format!("{} — {}", w.app_name(), w.title()),
+            width: w.width(),
+            height: w.height(),
+        })
+        .collect();
+    Ok(CaptureSources { screens, windows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crop_within_bounds() {
+        let image = RgbaImage::new(100, 50);
+        let cropped = crop(&image, 10, 10, 50, 20).unwrap();
+        assert_eq!(cropped.dimensions(), (50, 20));
+    }
+
+    #[test]
+    fn test_crop_rejects_out_of_bounds() {
+        let image = RgbaImage::new(100, 50);
+        assert_eq!(
+            crop(&image, 60, 0, 50, 10).unwrap_err().code(),
+            "INVALID_REGION"
+        );
+        assert!(crop(&image, 0, 0, 0, 10).is_err());
+        assert!(crop(&image, u32::MAX, 0, 2, 2).is_err());
+    }
+
+    #[test]
+    fn test_target_deserializes() {
+        let target: CaptureTarget =
+            serde_json::from_str(r#"{"mode":"region","x":1,"y":2,"width":3,"height":4}"#).unwrap();
+        assert_eq!(
+            target,
+            CaptureTarget::Region {
+                monitor: None,
+                x: 1,
+                y: 2,
+                width: 3,
+                height: 4
+            }
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Session restore after a crash.
+//!
+//! The open windows, the conversation and scroll position of each, and the
+//! generations streaming into them are saved as they change, and a clean
+//! exit marks the saved session closed. A session still open at the next
+//! launch was cut short by a crash or a kill: `get_restorable_session`
+//! returns it, and `restore_session` reopens its windows and reports the
+//! generations that were streaming as interrupted.
+//!
+//! Windows report what they show with `update_session_window`; call it
+//! when the conversation changes and, debounced, when the view scrolls.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+
+use crate::commands::quick_capture::MAIN_WINDOW_LABEL;
+use crate::commands::windows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the current session.
+pub const SETTINGS_KEY: &str = "session";
+
+/// A window of a session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWindow {
+    /// Window label
+    pub label: String,
+    /// Conversation shown in the window
+    pub conversation_id: Option<String>,
+    /// Scroll offset of the conversation in CSS pixels
+    pub scroll_top: f64,
+}
+
+/// A generation streaming when the session was saved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionGeneration {
+    /// Stream identifier chosen by the webview
+    pub stream_id: String,
+    /// Conversation the reply belongs to
+    pub conversation_id: Option<String>,
+    /// Start time in Unix milliseconds
+    pub started_at: i64,
+}
+
+/// The stored session document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedSession {
+    windows: Vec<SessionWindow>,
+    generations: Vec<SessionGeneration>,
+    closed: bool,
+    updated_at: i64,
+}
+
+impl SavedSession {
+    fn set_window(&mut self, window: SessionWindow) {
+        match self.windows.iter_mut().find(|w| w.label == window.label) {
+            Some(existing) => *existing = window,
+            None => self.windows.push(window),
+        }
+    }
+
+    fn remove_window(&mut self, label: &str) -> bool {
+        let before = self.windows.len();
+        self.windows.retain(|w| w.label != label);
+        self.windows.len() != before
+    }
+
+    fn finish_generation(&mut self, stream_id: &str) -> bool {
+        let before = self.generations.len();
+        self.generations.retain(|g| g.stream_id != stream_id);
+        self.generations.len() != before
+    }
+}
+
+/// A session cut short by a crash.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RestorableSession {
+    /// Windows that were open, the main window first
+    pub windows: Vec<SessionWindow>,
+    /// Generations that were streaming and are lost
+    pub interrupted: Vec<SessionGeneration>,
+    /// Last save in Unix milliseconds
+    pub saved_at: i64,
+}
+
+impl From<SavedSession> for RestorableSession {
+    fn from(mut saved: SavedSession) -> Self {
+        saved
+            .windows
+            .sort_by_key(|window| window.label != MAIN_WINDOW_LABEL);
+        Self {
+            windows: saved.windows,
+            interrupted: saved.generations,
+            saved_at: saved.updated_at,
+        }
+    }
+}
+
+/// Managed state holding the current session and the one to restore.
+pub struct Session {
+    current: Mutex<SavedSession>,
+    previous: Mutex<Option<RestorableSession>>,
+}
+
+impl Session {
+    /// Loads the previous session and starts a new one.
+    fn open(conn: &Connection) -> rusqlite::Result<Self> {
+        let previous = db::read_setting(conn, SETTINGS_KEY)?
+            .and_then(|value| serde_json::from_str::<SavedSession>(&value).ok())
+            .filter(|saved| !saved.closed && !saved.windows.is_empty())
+            .map(RestorableSession::from);
+        let session = Self {
+            current: Mutex::new(SavedSession::default()),
+            previous: Mutex::new(previous),
+        };
+        save(conn, &mut session.lock())?;
+        Ok(session)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SavedSession> {
+        self.current.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn take_previous(&self) -> Option<RestorableSession> {
+        self.previous
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+}
+
+fn save(conn: &Connection, session: &mut SavedSession) -> rusqlite::Result<()> {
+    session.updated_at = db::now_millis();
+    let value = serde_json::to_string(session).unwrap_or_default();
+    db::write_setting(conn, SETTINGS_KEY, &value)
+}
+
+/// Applies `change` to the current session and saves it if it reports a
+/// change.
+fn update(app: &AppHandle, change: impl FnOnce(&mut SavedSession) -> bool) {
+    let Some(session) = app.try_state::<Session>() else {
+        return;
+    };
+    let mut current = session.lock();
+    if !change(&mut current) {
+        return;
+    }
+    if let Err(e) = save(&app.state::<Database>().conn(), &mut current) {
+        tracing::error!("failed to save the session: {e}");
+    }
+}
+
+/// Records that a generation started streaming.
+pub fn generation_started(app: &AppHandle, stream_id: &str, conversation_id: Option<&str>) {
+    update(app, |session| {
+        session.generations.push(SessionGeneration {
+            stream_id: stream_id.to_string(),
+            conversation_id: conversation_id.map(str::to_string),
+            started_at: db::now_millis(),
+        });
+        true
+    });
+}
+
+/// Records that a generation finished, successfully or not.
+pub fn generation_finished(app: &AppHandle, stream_id: &str) {
+    update(app, |session| session.finish_generation(stream_id));
+}
+
+/// Forgets a window that has been destroyed.
+pub fn window_destroyed(app: &AppHandle, label: &str) {
+    update(app, |session| session.remove_window(label));
+}
+
+/// Marks the session closed when the app exits cleanly.
+pub fn mark_closed(app: &AppHandle) {
+    update(app, |session| {
+        session.closed = true;
+        true
+    });
+}
+
+/// Loads the previous session and starts saving the new one.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+///
+/// # Errors
+///
+/// Returns an error if the session cannot be read or saved.
+pub fn init(app: &AppHandle) -> rusqlite::Result<()> {
+    let session = Session::open(&app.state::<Database>().conn())?;
+    app.manage(session);
+    Ok(())
+}
+
+/// Records what the calling window shows.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` for windows other than
+/// the main and conversation windows.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("update_session_window", { conversationId: id, scrollTop: list.scrollTop });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require handles and args by value
+pub fn update_session_window(
+    window: WebviewWindow,
+    app: AppHandle,
+    conversation_id: Option<String>,
+    scroll_top: f64,
+) -> Result<(), GibberError> {
+    windows::require_trusted(&window)?;
+    let recorded = SessionWindow {
+        label: window.label().to_string(),
+        conversation_id,
+        scroll_top,
+    };
+    update(&app, |session| {
+        if session.windows.contains(&recorded) {
+            return false;
+        }
+        session.set_window(recorded);
+        true
+    });
+    Ok(())
+}
+
+/// Returns the session cut short by a crash, if the previous run ended
+/// that way and it hasn't been restored or discarded.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_restorable_session(session: State<'_, Session>) -> Option<RestorableSession> {
+    session
+        .previous
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Reopens the windows of the session cut short by a crash.
+///
+/// Conversation windows open at their saved scroll position. The main
+/// window applies its own entry, the first of the returned windows.
+///
+/// # Returns
+///
+/// The restored session, or `None` if there was nothing to restore.
+/// Conversations deleted since are skipped.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `WINDOW` if a window cannot be
+/// created.
+///
+/// # Example
+///
+/// ```typescript
+/// const restored = await invoke("restore_session");
+/// const main = restored?.windows.find((w) => w.label === "main");
+/// if (main?.conversationId) openConversation(main.conversationId, main.scrollTop);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and State by value
+pub fn restore_session(
+    app: AppHandle,
+    session: State<'_, Session>,
+) -> Result<Option<RestorableSession>, GibberError> {
+    let Some(mut previous) = session.take_previous() else {
+        return Ok(None);
+    };
+    let mut restored = Vec::with_capacity(previous.windows.len());
+    for window in previous.windows {
+        if window.label != MAIN_WINDOW_LABEL {
+            let Some(conversation_id) = &window.conversation_id else {
+                continue;
+            };
+            match windows::show_conversation(&app, conversation_id, Some(window.scroll_top)) {
+                Ok(_) => {}
+                Err(e) if e.code() == "NOT_FOUND" => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        restored.push(window);
+    }
+    previous.windows = restored;
+    Ok(Some(previous))
+}
+
+/// Discards the session cut short by a crash without restoring it.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn discard_session(session: State<'_, Session>) {
+    session.take_previous();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(label: &str, scroll_top: f64) -> SessionWindow {
+        SessionWindow {
+            label: label.to_string(),
+            conversation_id: Some("c".to_string()),
+            scroll_top,
+        }
+    }
+
+    #[test]
+    fn test_windows_are_replaced_by_label() {
+        let mut session = SavedSession::default();
+        session.set_window(window("conversation-c", 10.0));
+        session.set_window(window(MAIN_WINDOW_LABEL, 0.0));
+        session.set_window(window("conversation-c", 250.0));
+        assert_eq!(session.windows.len(), 2);
+        assert!(session.remove_window("conversation-c"));
+        assert!(!session.remove_window("conversation-c"));
+
+        session.set_window(window("conversation-c", 250.0));
+        let restorable = RestorableSession::from(session);
+        assert_eq!(restorable.windows[0].label, MAIN_WINDOW_LABEL);
+        assert!((restorable.windows[1].scroll_top - 250.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_only_unclosed_sessions_are_restorable() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let mut crashed = SavedSession {
+            windows: vec![window(MAIN_WINDOW_LABEL, 40.0)],
+            generations: vec![SessionGeneration {
+                stream_id: "s".to_string(),
+                conversation_id: Some("c".to_string()),
+                started_at: 1,
+            }],
+            ..SavedSession::default()
+        };
+        save(&conn, &mut crashed).unwrap();
+        let previous = Session::open(&conn).unwrap().take_previous().unwrap();
+        assert_eq!(previous.interrupted.len(), 1);
+
+        crashed.closed = true;
+        save(&conn, &mut crashed).unwrap();
+        assert!(Session::open(&conn).unwrap().take_previous().is_none());
+    }
+}
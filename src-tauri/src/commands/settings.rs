@@ -0,0 +1,886 @@
+//! Application settings.
+//!
+//! General preferences live in one typed [`Settings`] document stored in the
+//! database. Missing fields take their defaults, unknown fields are rejected,
+//! and values are range-checked before anything is saved. Every change is
+//! broadcast as [`events::SETTINGS_CHANGED`] so all windows stay in sync.
+//!
+//! Feature-specific preferences (notifications, shortcuts, routing, spend
+//! caps, feeds, and so on) keep their own documents next to this one.
+//!
+//! All documents can be exported to one JSON file, imported back after a
+//! preview of what would change, or reset to factory defaults. Secrets live
+//! in the OS keychain and are never part of an export.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+
+use crate::chat;
+use crate::commands::browser_bridge::{self, BrowserBridgeSettings};
+use crate::commands::clipboard::{self, ClipboardSettings};
+use crate::commands::diagrams;
+use crate::commands::events;
+use crate::commands::feeds::{self, FeedSettings};
+use crate::commands::i18n;
+use crate::commands::location::{self, LocationSettings};
+use crate::commands::media::{self, MediaSettings};
+use crate::commands::nostr_composer::{self, ComposerSettings};
+use crate::commands::nostr_digest::{self, NostrDigestSettings};
+use crate::commands::notifications::{self, NotificationSettings};
+use crate::commands::rate_limit::{self, RateLimitSettings};
+use crate::commands::routing_policy::{self, RoutingPolicy};
+use crate::commands::sharing::{self, ShareSettings};
+use crate::commands::shortcuts::{self, ShortcutSettings};
+use crate::commands::spend::{self, SpendCaps};
+use crate::commands::stable_diffusion::{self, StableDiffusionSettings};
+use crate::commands::telemetry;
+use crate::commands::tray;
+use crate::commands::updater::{self, UpdateSettings};
+use crate::commands::windows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the document.
+const SETTINGS_KEY: &str = "app";
+
+/// Highest accepted `maxTokens`.
+const MAX_TOKENS_LIMIT: u32 = 200_000;
+
+/// `format` field identifying an export file.
+const EXPORT_FORMAT: &str = "gibber-ai-settings";
+
+/// Newest export file version this build understands.
+const EXPORT_VERSION: u32 = 1;
+
+fn invalid(message: impl Into<String>) -> GibberError {
+    GibberError::new("INVALID_SETTINGS", message)
+}
+
+/// Color theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum Theme {
+    /// Follow the OS
+    #[default]
+    System,
+    /// Light theme
+    Light,
+    /// Dark theme
+    Dark,
+}
+
+/// The general settings document.
+#[allow(clippy::struct_excessive_bools)] // independent preferences, not a state machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Settings {
+    /// Color theme
+    pub theme: Theme,
+    /// UI language as a BCP 47 tag, or `system` to follow the OS
+    pub language: String,
+    /// Model used for new conversations
+    pub default_model: String,
+    /// Sampling temperature for new conversations
+    pub temperature: f32,
+    /// Response token limit for new conversations
+    pub max_tokens: u32,
+    /// Hide the main window to the tray instead of quitting on close
+    pub close_to_tray: bool,
+    /// Send the message on Enter (Shift+Enter inserts a newline)
+    pub send_on_enter: bool,
+    /// Kroki server rendering diagrams in HTML exports; empty exports them
+    /// as code
+    pub diagram_server: String,
+    /// Remove EXIF, GPS, and text metadata from images sent to providers
+    pub strip_image_metadata: bool,
+    /// Ask providers not to store or train on requests
+    pub zero_data_retention: bool,
+    /// Answer repeated temperature-0 requests from a local cache
+    pub cache_deterministic_responses: bool,
+    /// Tell models the current date, time, timezone, and locale
+    pub datetime_context: bool,
+    /// Model used for translations; empty uses `default_model`
+    pub translation_model: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::System,
+            language: i18n::SYSTEM_LANGUAGE.to_string(),
+            default_model: chat::DEFAULT_MODEL.to_string(),
+            temperature: 0.7,
+            max_tokens: 4096,
+            close_to_tray: true,
+            send_on_enter: true,
+            diagram_server: diagrams::DEFAULT_SERVER.to_string(),
+            strip_image_metadata: true,
+            zero_data_retention: false,
+            cache_deterministic_responses: false,
+            datetime_context: true,
+            translation_model: String::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Checks value ranges that serde can't express.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GibberError` with code `INVALID_SETTINGS` naming the
+    /// first offending field.
+    pub fn validate(&self) -> Result<(), GibberError> {
+        if self.default_model.trim().is_empty() {
+            return Err(invalid("defaultModel must not be empty"));
+        }
+        if self.language.trim().is_empty() {
+            return Err(invalid("language must not be empty"));
+        }
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err(invalid("temperature must be between 0 and 2"));
+        }
+        if !(1..=MAX_TOKENS_LIMIT).contains(&self.max_tokens) {
+            return Err(invalid(format!(
+                "maxTokens must be between 1 and {MAX_TOKENS_LIMIT}"
+            )));
+        }
+        if !self.diagram_server.is_empty()
+            && !["http://", "https://"]
+                .iter()
+                .any(|scheme| self.diagram_server.starts_with(scheme))
+        {
+            return Err(invalid("diagramServer must be an http(s) URL or empty"));
+        }
+        Ok(())
+    }
+
+    /// Returns a copy with the fields of `patch` applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GibberError` with code `INVALID_SETTINGS` if the patch
+    /// isn't an object, has unknown fields or wrong types, or produces
+    /// out-of-range values.
+    pub fn merge(&self, patch: &Value) -> Result<Self, GibberError> {
+        let Value::Object(fields) = patch else {
+            return Err(invalid("Settings patch must be an object"));
+        };
+        let mut document = serde_json::to_value(self).unwrap_or_default();
+        if let Value::Object(current) = &mut document {
+            for (key, value) in fields {
+                current.insert(key.clone(), value.clone());
+            }
+        }
+        let merged: Self = serde_json::from_value(document).map_err(|e| invalid(e.to_string()))?;
+        merged.validate()?;
+        Ok(merged)
+    }
+}
+
+/// Loads the stored document, falling back to defaults if it is missing or
+/// no longer valid.
+///
+/// # Errors
+///
+/// Returns an error if the settings query fails.
+pub fn load(conn: &rusqlite::Connection) -> rusqlite::Result<Settings> {
+    Ok(db::read_setting(conn, SETTINGS_KEY)?
+        .and_then(|value| serde_json::from_str::<Settings>(&value).ok())
+        .filter(|settings| settings.validate().is_ok())
+        .unwrap_or_default())
+}
+
+/// Stores a validated document, applies it, and broadcasts the change.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the document is invalid or cannot be stored.
+pub fn save(app: &AppHandle, settings: Settings) -> Result<Settings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&app.state::<Database>().conn(), SETTINGS_KEY, &value)?;
+    apply(app, &settings);
+    events::emit_typed(app, events::SETTINGS_CHANGED, &settings);
+    Ok(settings)
+}
+
+/// Applies a patch to the stored document.
+///
+/// # Errors
+///
+/// See [`Settings::merge`] and [`save`].
+pub fn update(app: &AppHandle, patch: &Value) -> Result<Settings, GibberError> {
+    let current = load(&app.state::<Database>().conn())?;
+    save(app, current.merge(patch)?)
+}
+
+/// Pushes settings that backend subsystems act on into their state.
+pub fn apply(app: &AppHandle, settings: &Settings) {
+    tray::set_close_to_tray_flag(app, settings.close_to_tray);
+    i18n::set_language(app, &settings.language);
+}
+
+/// Returns the settings document.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const settings = await invoke("get_settings");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_settings(db: State<'_, Database>) -> Result<Settings, GibberError> {
+    Ok(load(&db.conn())?)
+}
+
+/// Updates some settings, leaving the rest unchanged.
+///
+/// # Returns
+///
+/// The full document after the update.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` if the patch is
+/// rejected; nothing is saved in that case.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("update_settings", { patch: { theme: "dark", temperature: 0.4 } });
+/// await listen("settings://changed", (e) => applySettings(e.payload));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn update_settings(app: AppHandle, patch: Value) -> Result<Settings, GibberError> {
+    update(&app, &patch)
+}
+
+/// One typed settings document, as handled by export, import, and reset.
+#[derive(Debug, Clone, PartialEq)]
+enum Section {
+    App(Settings),
+    Notifications(NotificationSettings),
+    Clipboard(ClipboardSettings),
+    Updater(UpdateSettings),
+    Shortcuts(ShortcutSettings),
+    RoutingPolicy(RoutingPolicy),
+    RateLimits(RateLimitSettings),
+    SpendCaps(SpendCaps),
+    Feeds(FeedSettings),
+    Media(MediaSettings),
+    Sharing(ShareSettings),
+    StableDiffusion(StableDiffusionSettings),
+    Location(LocationSettings),
+    NostrComposer(ComposerSettings),
+    NostrDigest(NostrDigestSettings),
+    BrowserBridge(BrowserBridgeSettings),
+}
+
+/// Keys of the documents export, import, and reset handle, in the order
+/// they are applied. Shortcuts go first: the OS may refuse a binding, and
+/// nothing else should change when it does.
+///
+/// Everything else stays out: documents that belong to this machine (the
+/// update-rollout install ID, telemetry consent, which must be given on
+/// each machine, the network setup, the local API server and WebSocket
+/// bridge with their tokens, granted git repositories, the saved session)
+/// and the markers features keep about their own progress.
+const SECTION_KEYS: [&str; 16] = [
+    shortcuts::SETTINGS_KEY,
+    SETTINGS_KEY,
+    notifications::SETTINGS_KEY,
+    clipboard::SETTINGS_KEY,
+    updater::SETTINGS_KEY,
+    routing_policy::SETTINGS_KEY,
+    rate_limit::SETTINGS_KEY,
+    spend::SETTINGS_KEY,
+    feeds::SETTINGS_KEY,
+    media::SETTINGS_KEY,
+    sharing::SETTINGS_KEY,
+    stable_diffusion::SETTINGS_KEY,
+    location::SETTINGS_KEY,
+    nostr_composer::SETTINGS_KEY,
+    nostr_digest::SETTINGS_KEY,
+    browser_bridge::SETTINGS_KEY,
+];
+
+fn parse_as<T: DeserializeOwned>(key: &str, value: &Value) -> Result<T, GibberError> {
+    serde_json::from_value(value.clone()).map_err(|e| invalid(format!("{key}: {e}")))
+}
+
+/// Parses the document under `key` and runs its validator on it.
+fn parse_checked<T: DeserializeOwned>(
+    key: &str,
+    value: &Value,
+    validate: impl FnOnce(&T) -> Result<(), GibberError>,
+) -> Result<T, GibberError> {
+    let document = parse_as(key, value)?;
+    validate(&document).map_err(|e| GibberError::new(e.code(), format!("{key}: {e}")))?;
+    Ok(document)
+}
+
+impl Section {
+    /// Parses and validates the document stored under `key`.
+    ///
+    /// Returns `None` for keys without a known document type.
+    fn parse(key: &str, value: &Value) -> Option<Result<Self, GibberError>> {
+        let section = match key {
+            SETTINGS_KEY => parse_as::<Settings>(key, value).and_then(|settings| {
+                settings
+                    .validate()
+                    .map_err(|e| invalid(format!("{key}: {e}")))?;
+                Ok(Self::App(settings))
+            }),
+            notifications::SETTINGS_KEY => parse_as(key, value).map(Self::Notifications),
+            clipboard::SETTINGS_KEY => parse_as(key, value).map(Self::Clipboard),
+            updater::SETTINGS_KEY => parse_as(key, value).map(Self::Updater),
+            shortcuts::SETTINGS_KEY => {
+                parse_as::<ShortcutSettings>(key, value).and_then(|settings| {
+                    shortcuts::validate(&settings)
+                        .map_err(|e| GibberError::new(e.code(), format!("{key}: {e}")))?;
+                    Ok(Self::Shortcuts(settings))
+                })
+            }
+            routing_policy::SETTINGS_KEY => {
+                parse_checked(key, value, RoutingPolicy::validate).map(Self::RoutingPolicy)
+            }
+            rate_limit::SETTINGS_KEY => {
+                parse_checked(key, value, RateLimitSettings::validate).map(Self::RateLimits)
+            }
+            spend::SETTINGS_KEY => {
+                parse_checked(key, value, SpendCaps::validate).map(Self::SpendCaps)
+            }
+            feeds::SETTINGS_KEY => {
+                parse_checked(key, value, FeedSettings::validate).map(Self::Feeds)
+            }
+            media::SETTINGS_KEY => {
+                parse_checked(key, value, MediaSettings::validate).map(Self::Media)
+            }
+            sharing::SETTINGS_KEY => {
+                parse_checked(key, value, ShareSettings::validate).map(Self::Sharing)
+            }
+            stable_diffusion::SETTINGS_KEY => {
+                parse_checked(key, value, StableDiffusionSettings::validate)
+                    .map(Self::StableDiffusion)
+            }
+            location::SETTINGS_KEY => parse_as(key, value).map(Self::Location),
+            nostr_composer::SETTINGS_KEY => {
+                parse_checked(key, value, ComposerSettings::validate).map(Self::NostrComposer)
+            }
+            nostr_digest::SETTINGS_KEY => {
+                parse_checked(key, value, NostrDigestSettings::validate).map(Self::NostrDigest)
+            }
+            browser_bridge::SETTINGS_KEY => parse_as(key, value).map(Self::BrowserBridge),
+            _ => return None,
+        };
+        Some(section)
+    }
+
+    /// Factory defaults for `key`, if it has a known document type.
+    fn default_for(key: &str) -> Option<Self> {
+        let section = match key {
+            SETTINGS_KEY => Self::App(Settings::default()),
+            notifications::SETTINGS_KEY => Self::Notifications(NotificationSettings::default()),
+            clipboard::SETTINGS_KEY => Self::Clipboard(ClipboardSettings::default()),
+            updater::SETTINGS_KEY => Self::Updater(UpdateSettings::default()),
+            shortcuts::SETTINGS_KEY => Self::Shortcuts(ShortcutSettings::default()),
+            routing_policy::SETTINGS_KEY => Self::RoutingPolicy(RoutingPolicy::default()),
+            rate_limit::SETTINGS_KEY => Self::RateLimits(RateLimitSettings::default()),
+            spend::SETTINGS_KEY => Self::SpendCaps(SpendCaps::default()),
+            feeds::SETTINGS_KEY => Self::Feeds(FeedSettings::default()),
+            media::SETTINGS_KEY => Self::Media(MediaSettings::default()),
+            sharing::SETTINGS_KEY => Self::Sharing(ShareSettings::default()),
+            stable_diffusion::SETTINGS_KEY => {
+                Self::StableDiffusion(StableDiffusionSettings::default())
+            }
+            location::SETTINGS_KEY => Self::Location(LocationSettings::default()),
+            nostr_composer::SETTINGS_KEY => Self::NostrComposer(ComposerSettings::default()),
+            nostr_digest::SETTINGS_KEY => Self::NostrDigest(NostrDigestSettings::default()),
+            browser_bridge::SETTINGS_KEY => Self::BrowserBridge(BrowserBridgeSettings::default()),
+            _ => return None,
+        };
+        Some(section)
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            Self::App(settings) => serde_json::to_value(settings),
+            Self::Notifications(settings) => serde_json::to_value(settings),
+            Self::Clipboard(settings) => serde_json::to_value(settings),
+            Self::Updater(settings) => serde_json::to_value(settings),
+            Self::Shortcuts(settings) => serde_json::to_value(settings),
+            Self::RoutingPolicy(policy) => serde_json::to_value(policy),
+            Self::RateLimits(settings) => serde_json::to_value(settings),
+            Self::SpendCaps(caps) => serde_json::to_value(caps),
+            Self::Feeds(settings) => serde_json::to_value(settings),
+            Self::Media(settings) => serde_json::to_value(settings),
+            Self::Sharing(settings) => serde_json::to_value(settings),
+            Self::StableDiffusion(settings) => serde_json::to_value(settings),
+            Self::Location(settings) => serde_json::to_value(settings),
+            Self::NostrComposer(settings) => serde_json::to_value(settings),
+            Self::NostrDigest(settings) => serde_json::to_value(settings),
+            Self::BrowserBridge(settings) => serde_json::to_value(settings),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Stores the document through the same path as its setter, so the
+    /// running app picks it up too.
+    fn apply(self, app: &AppHandle) -> Result<(), GibberError> {
+        match self {
+            Self::App(settings) => {
+                save(app, settings)?;
+            }
+            Self::Notifications(settings) => {
+                notifications::save_settings(&app.state::<Database>().conn(), &settings)?;
+            }
+            Self::Clipboard(settings) => {
+                clipboard::save_settings(app, settings)?;
+            }
+            Self::Updater(settings) => {
+                updater::save_settings(app, settings)?;
+            }
+            Self::Shortcuts(settings) => {
+                shortcuts::rebind(app, settings)?;
+            }
+            Self::RoutingPolicy(policy) => {
+                routing_policy::set_routing_policy(app.state(), policy)?;
+            }
+            Self::RateLimits(settings) => {
+                rate_limit::set_rate_limits(app.state(), settings)?;
+            }
+            Self::SpendCaps(caps) => {
+                spend::set_spend_caps(app.state(), caps)?;
+            }
+            Self::Feeds(settings) => {
+                feeds::set_feed_settings(app.state(), settings)?;
+            }
+            Self::Media(settings) => {
+                media::set_media_settings(app.state(), settings)?;
+            }
+            Self::Sharing(settings) => {
+                sharing::set_share_settings(app.state(), settings)?;
+            }
+            Self::StableDiffusion(settings) => {
+                stable_diffusion::set_stable_diffusion_settings(app.state(), settings)?;
+            }
+            Self::Location(settings) => {
+                location::set_location_settings(app.state(), app.state(), settings)?;
+            }
+            Self::NostrComposer(settings) => {
+                nostr_composer::set_nostr_composer_settings(app.state(), settings)?;
+            }
+            Self::NostrDigest(settings) => {
+                nostr_digest::set_nostr_digest_settings(app.state(), settings)?;
+            }
+            Self::BrowserBridge(settings) => {
+                browser_bridge::save_settings(&app.state::<Database>().conn(), &settings)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Contents of a settings export file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsExport {
+    /// Always [`EXPORT_FORMAT`]
+    format: String,
+    /// File version, at most [`EXPORT_VERSION`]
+    version: u32,
+    /// Export time in Unix milliseconds
+    #[serde(default)]
+    exported_at: i64,
+    /// Documents by settings key
+    settings: BTreeMap<String, Value>,
+}
+
+/// Returns the stored documents that belong in an export.
+fn exportable(conn: &rusqlite::Connection) -> rusqlite::Result<BTreeMap<String, Value>> {
+    Ok(db::list_settings(conn)?
+        .into_iter()
+        .filter(|(key, _)| SECTION_KEYS.contains(&key.as_str()))
+        .filter_map(|(key, value)| Some((key, serde_json::from_str(&value).ok()?)))
+        .collect())
+}
+
+/// Returns the document currently in effect for a known `key`.
+fn current_value(conn: &rusqlite::Connection, key: &str) -> rusqlite::Result<Option<Value>> {
+    let stored = db::read_setting(conn, key)?
+        .and_then(|value| serde_json::from_str::<Value>(&value).ok())
+        .and_then(|value| Section::parse(key, &value))
+        .and_then(Result::ok);
+    Ok(stored
+        .or_else(|| Section::default_for(key))
+        .map(|s| s.to_value()))
+}
+
+fn read_export(path: &Path) -> Result<SettingsExport, GibberError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| GibberError::new("IO", e.to_string()))?;
+    let export: SettingsExport = serde_json::from_str(&contents)
+        .map_err(|e| GibberError::new("INVALID_FILE", format!("Not a settings export: {e}")))?;
+    if export.format != EXPORT_FORMAT {
+        return Err(GibberError::new(
+            "INVALID_FILE",
+            "Not a Gibber AI settings export",
+        ));
+    }
+    if export.version > EXPORT_VERSION {
+        return Err(GibberError::new(
+            "INVALID_FILE",
+            format!(
+                "Export version {} is newer than this version of Gibber AI supports",
+                export.version
+            ),
+        ));
+    }
+    Ok(export)
+}
+
+/// A document an import would replace.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChange {
+    /// Settings key
+    pub key: String,
+    /// Document in effect now
+    pub current: Option<Value>,
+    /// Document from the file
+    pub incoming: Value,
+}
+
+/// What an import changes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPlan {
+    /// Documents that differ from the current ones
+    pub changes: Vec<SettingsChange>,
+    /// Keys in the file that this version doesn't recognize and won't import
+    pub skipped: Vec<String>,
+}
+
+/// Validates every document in an export and compares it to what is in
+/// effect, without changing anything.
+fn plan_import(
+    conn: &rusqlite::Connection,
+    export: &SettingsExport,
+) -> Result<(ImportPlan, Vec<Section>), GibberError> {
+    let mut plan = ImportPlan::default();
+    let mut sections = Vec::new();
+    for key in SECTION_KEYS {
+        let Some(value) = export.settings.get(key) else {
+            continue;
+        };
+        let Some(section) = Section::parse(key, value) else {
+            continue;
+        };
+        let section = section?;
+        let incoming = section.to_value();
+        let current = current_value(conn, key)?;
+        if current.as_ref() != Some(&incoming) {
+            plan.changes.push(SettingsChange {
+                key: key.to_string(),
+                current,
+                incoming,
+            });
+            sections.push(section);
+        }
+    }
+    plan.skipped = export
+        .settings
+        .keys()
+        .filter(|key| !SECTION_KEYS.contains(&key.as_str()))
+        .cloned()
+        .collect();
+    Ok((plan, sections))
+}
+
+/// Writes all non-secret settings to a JSON file.
+///
+/// # Returns
+///
+/// The keys of the exported documents.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, or `IO` if the file cannot be written.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("export_settings", { path: "/Users/me/gibber-settings.json" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn export_settings(
+    window: WebviewWindow,
+    app: AppHandle,
+    path: String,
+) -> Result<Vec<String>, GibberError> {
+    windows::require_trusted(&window)?;
+    let export = SettingsExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        exported_at: db::now_millis(),
+        settings: exportable(&app.state::<Database>().conn())?,
+    };
+    let contents = serde_json::to_string_pretty(&export).unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || std::fs::write(&path, contents))
+        .await
+        .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+        .map_err(|e| GibberError::new("IO", e.to_string()))?;
+    Ok(export.settings.into_keys().collect())
+}
+
+/// Shows what importing a settings file would change, without applying it.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `IO` if the file cannot be read,
+/// `INVALID_FILE` if it isn't a settings export, or `INVALID_SETTINGS`
+/// (`INVALID_SHORTCUT`, `CONFLICT` for shortcuts) if a document in it is
+/// invalid.
+///
+/// # Example
+///
+/// ```typescript
+/// const { changes, skipped } = await invoke("preview_settings_import", { path });
+/// for (const { key, current, incoming } of changes) showDiff(key, current, incoming);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn preview_settings_import(
+    db: State<'_, Database>,
+    path: String,
+) -> Result<ImportPlan, GibberError> {
+    let export = read_export(Path::new(&path))?;
+    Ok(plan_import(&db.conn(), &export)?.0)
+}
+
+/// Imports a settings file and applies it immediately.
+///
+/// The whole file is validated first; nothing changes if any document is
+/// invalid.
+///
+/// # Returns
+///
+/// The changes that were applied.
+///
+/// # Errors
+///
+/// See [`preview_settings_import`]. Also fails with `FORBIDDEN` outside the
+/// main and conversation windows, or if the OS refuses one of the imported
+/// shortcuts, in which case nothing else is changed.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("import_settings", { path });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require handles and args by value
+pub fn import_settings(
+    window: WebviewWindow,
+    app: AppHandle,
+    path: String,
+) -> Result<ImportPlan, GibberError> {
+    windows::require_trusted(&window)?;
+    let export = read_export(Path::new(&path))?;
+    let (plan, sections) = plan_import(&app.state::<Database>().conn(), &export)?;
+    for section in sections {
+        section.apply(&app)?;
+    }
+    telemetry::record_feature(&app, "settings.import");
+    Ok(plan)
+}
+
+/// Restores every setting to its factory default.
+///
+/// Credentials in the OS keychain and machine-local documents (telemetry
+/// consent, network setup) are left alone.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, or if the defaults cannot be stored or the default
+/// shortcuts cannot be registered.
+///
+/// # Example
+///
+/// ```typescript
+/// const settings = await invoke("reset_settings");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require handles by value
+pub fn reset_settings(window: WebviewWindow, app: AppHandle) -> Result<Settings, GibberError> {
+    windows::require_trusted(&window)?;
+    for key in SECTION_KEYS {
+        if let Some(section) = Section::default_for(key) {
+            section.apply(&app)?;
+        }
+    }
+    Ok(load(&app.state::<Database>().conn())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_missing_fields_take_defaults() {
+        let settings: Settings = serde_json::from_str(r#"{"theme":"dark"}"#).unwrap();
+        assert_eq!(settings.theme, Theme::Dark);
+        assert_eq!(settings.max_tokens, Settings::default().max_tokens);
+    }
+
+    #[test]
+    fn test_merge_applies_patch() {
+        let merged = Settings::default()
+            .merge(&json!({ "temperature": 0.2, "sendOnEnter": false }))
+            .unwrap();
+        assert!((merged.temperature - 0.2).abs() < f32::EPSILON);
+        assert!(!merged.send_on_enter);
+        assert!(merged.close_to_tray);
+    }
+
+    #[test]
+    fn test_merge_rejects_bad_patches() {
+        let settings = Settings::default();
+        assert!(settings.merge(&json!({ "unknownField": 1 })).is_err());
+        assert!(settings.merge(&json!({ "theme": "neon" })).is_err());
+        assert!(settings.merge(&json!({ "temperature": 3.0 })).is_err());
+        assert!(settings.merge(&json!({ "maxTokens": 0 })).is_err());
+        assert!(settings
+            .merge(&json!({ "diagramServer": "ftp://x" }))
+            .is_err());
+        assert!(settings.merge(&json!({ "diagramServer": "" })).is_ok());
+        assert!(settings.merge(&json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_load_ignores_invalid_stored_document() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        db::write_setting(&conn, SETTINGS_KEY, r#"{"temperature":9}"#).unwrap();
+        assert_eq!(load(&conn).unwrap(), Settings::default());
+    }
+
+    fn export_of(settings: Value) -> SettingsExport {
+        SettingsExport {
+            format: EXPORT_FORMAT.to_string(),
+            version: EXPORT_VERSION,
+            exported_at: 0,
+            settings: serde_json::from_value(settings).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_export_leaves_out_local_keys_and_markers() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        db::write_setting(&conn, SETTINGS_KEY, r#"{"theme":"dark"}"#).unwrap();
+        db::write_setting(&conn, spend::SETTINGS_KEY, r#"{"dailyUsd":2.0}"#).unwrap();
+        db::write_setting(&conn, updater::INSTALL_ID_KEY, r#""abc""#).unwrap();
+        db::write_setting(&conn, telemetry::SETTINGS_KEY, r#"{"enabled":true}"#).unwrap();
+        db::write_setting(&conn, "feeds.lastDigest", "1700000000000").unwrap();
+        db::write_setting(&conn, "nostrDigest.last", "1700000000000").unwrap();
+        let keys: Vec<_> = exportable(&conn).unwrap().into_keys().collect();
+        assert_eq!(
+            keys,
+            vec![SETTINGS_KEY.to_string(), spend::SETTINGS_KEY.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_every_section_default_round_trips() {
+        for key in SECTION_KEYS {
+            let section = Section::default_for(key).unwrap();
+            let parsed = Section::parse(key, &section.to_value()).unwrap().unwrap();
+            assert_eq!(parsed, section, "{key}");
+        }
+    }
+
+    #[test]
+    fn test_plan_import_reports_changes_and_skipped_keys() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let export = export_of(json!({
+            "app": { "theme": "dark" },
+            "notifications": NotificationSettings::default(),
+            "somethingElse": true,
+        }));
+        let (plan, sections) = plan_import(&conn, &export).unwrap();
+        assert_eq!(plan.changes.len(), 1);
+        assert_eq!(plan.changes[0].key, SETTINGS_KEY);
+        assert_eq!(plan.changes[0].incoming["theme"], "dark");
+        assert_eq!(plan.changes[0].current.as_ref().unwrap()["theme"], "system");
+        assert_eq!(plan.skipped, vec!["somethingElse".to_string()]);
+        assert_eq!(sections.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_import_rejects_invalid_documents() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let bad_app = export_of(json!({ "app": { "temperature": 5.0 } }));
+        assert_eq!(
+            plan_import(&conn, &bad_app).unwrap_err().code(),
+            "INVALID_SETTINGS"
+        );
+        let conflict = export_of(json!({
+            "shortcuts": { "quickCapture": "CommandOrControl+K", "screenshot": "CommandOrControl+K" },
+        }));
+        assert_eq!(
+            plan_import(&conn, &conflict).unwrap_err().code(),
+            "CONFLICT"
+        );
+        let bad_limit = export_of(json!({
+            "rateLimits": { "limits": { "openrouter": { "requestsPerMinute": 0, "burst": 1 } } },
+        }));
+        assert_eq!(
+            plan_import(&conn, &bad_limit).unwrap_err().code(),
+            "INVALID_SETTINGS"
+        );
+    }
+
+    #[test]
+    fn test_read_export_checks_format() {
+        let dir = std::env::temp_dir().join(format!("gibber-settings-{}", db::new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("export.json");
+        std::fs::write(&path, r#"{"format":"other","version":1,"settings":{}}"#).unwrap();
+        assert_eq!(read_export(&path).unwrap_err().code(), "INVALID_FILE");
+        std::fs::write(
+            &path,
+            format!(r#"{{"format":"{EXPORT_FORMAT}","version":99,"settings":{{}}}}"#),
+        )
+        .unwrap();
+        assert_eq!(read_export(&path).unwrap_err().code(), "INVALID_FILE");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,453 @@
+//! Share links for conversations.
+//!
+//! A conversation is serialized in the export format, encrypted with a
+//! fresh AES-256-GCM key, and uploaded to a paste service speaking the
+//! 0x0.st protocol (multipart `file` upload, URL in the body, management
+//! token in `X-Token`). The key never leaves the machine except in the
+//! fragment of the returned link, which browsers don't send to servers:
+//!
+//! ```text
+//! https://0x0.st/abc.bin#<base64url key>
+//! ```
+//!
+//! The uploaded file is the 12-byte nonce followed by the ciphertext and
+//! tag. Every share is recorded with its management token so it can be
+//! revoked later, even after the conversation itself has been deleted.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::Aes256Gcm;
+use base64::Engine as _;
+use reqwest::multipart::{Form, Part};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::conversations;
+use crate::commands::deidentify::{self, DeidentifyOptions};
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the share backend.
+pub const SETTINGS_KEY: &str = "sharing";
+
+/// Paste service used unless the settings name another.
+pub const DEFAULT_PASTE_URL: &str = "https://0x0.st";
+
+/// Longest lifetime a share can ask for, in hours.
+const MAX_EXPIRES_HOURS: u32 = 24 * 365;
+
+/// Where encrypted conversations are uploaded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ShareBackend {
+    /// A 0x0.st-compatible paste service
+    Paste {
+        /// Base URL of the service
+        url: String,
+    },
+}
+
+impl ShareBackend {
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Paste { .. } => "paste",
+        }
+    }
+}
+
+/// Sharing preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShareSettings {
+    /// Upload target for new shares
+    pub backend: ShareBackend,
+}
+
+impl Default for ShareSettings {
+    fn default() -> Self {
+        Self {
+            backend: ShareBackend::Paste {
+                url: DEFAULT_PASTE_URL.to_string(),
+            },
+        }
+    }
+}
+
+impl ShareSettings {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        let ShareBackend::Paste { url } = &self.backend;
+        match tauri::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+            _ => Err(GibberError::new(
+                "INVALID_SETTINGS",
+                format!("Not an http(s) URL: {url}"),
+            )),
+        }
+    }
+}
+
+/// A conversation shared as an encrypted upload.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Share {
+    /// Unique identifier
+    pub id: String,
+    /// Conversation that was shared; it may have been deleted since
+    pub conversation_id: String,
+    /// Backend kind, e.g. "paste"
+    pub backend: String,
+    /// URL of the encrypted upload
+    pub url: String,
+    /// Link to hand out, with the key in the fragment
+    pub link: String,
+    /// When the backend drops the upload, in Unix milliseconds
+    pub expires_at: Option<i64>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// When the share was revoked, in Unix milliseconds
+    pub revoked_at: Option<i64>,
+    #[serde(skip)]
+    delete_token: Option<String>,
+}
+
+const SHARE_COLUMNS: &str =
+    "id, conversation_id, backend, url, link, delete_token, expires_at, created_at, revoked_at";
+
+fn share_from_row(row: &Row<'_>) -> rusqlite::Result<Share> {
+    Ok(Share {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        backend: row.get(2)?,
+        url: row.get(3)?,
+        link: row.get(4)?,
+        delete_token: row.get(5)?,
+        expires_at: row.get(6)?,
+        created_at: row.get(7)?,
+        revoked_at: row.get(8)?,
+    })
+}
+
+fn load_share(conn: &rusqlite::Connection, id: &str) -> Result<Share, GibberError> {
+    conn.query_row(
+        &format!("SELECT {SHARE_COLUMNS} FROM shares WHERE id = ?1"),
+        [id],
+        share_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Share {id} not found")))
+}
+
+/// Reads the stored preferences.
+pub(crate) fn load_settings(conn: &rusqlite::Connection) -> ShareSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Encrypts `plaintext` with a new key.
+///
+/// # Returns
+///
+/// The nonce followed by the ciphertext, and the key.
+fn encrypt(plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>), GibberError> {
+    let key = Aes256Gcm::generate_key(OsRng);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(&key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| GibberError::new("CRYPTO", e.to_string()))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok((sealed, key.to_vec()))
+}
+
+/// Appends the key to an upload URL as its fragment.
+fn share_link(url: &str, key: &[u8]) -> String {
+    let key = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(key);
+    format!("{url}#{key}")
+}
+
+/// Lists shares, newest first, optionally only those of one conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const shares = await invoke("list_shares", { conversationId });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn list_shares(
+    db: State<'_, Database>,
+    conversation_id: Option<String>,
+) -> Result<Vec<Share>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {SHARE_COLUMNS} FROM shares
+         WHERE ?1 IS NULL OR conversation_id = ?1 ORDER BY created_at DESC"
+    ))?;
+    let shares = stmt
+        .query_map([conversation_id], share_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(shares)
+}
+
+/// Returns the sharing preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_share_settings(db: State<'_, Database>) -> ShareSettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the sharing preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` if the backend URL
+/// isn't http(s), or `DATABASE` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_share_settings", {
+///   settings: { backend: { kind: "paste", url: "https://paste.example.org" } },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_share_settings(
+    db: State<'_, Database>,
+    settings: ShareSettings,
+) -> Result<ShareSettings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Encrypts a conversation, uploads it, and records the share.
+///
+/// The upload expires after `expiresInHours` when the backend supports it.
+/// With `deidentify`, identifying values are replaced before encryption.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation doesn't
+/// exist, `NETWORK` or `UPLOAD` if the backend can't be reached or refuses
+/// the file, or `DATABASE` if a query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const share = await invoke("create_share_link", { conversationId, expiresInHours: 72 });
+/// await navigator.clipboard.writeText(share.link);
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn create_share_link(
+    app: AppHandle,
+    conversation_id: String,
+    expires_in_hours: Option<u32>,
+    deidentify: Option<DeidentifyOptions>,
+) -> Result<Share, GibberError> {
+    let (conversation, settings) = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let conversation =
+            conversations::load_conversation(&conn, &conversation_id)?.ok_or_else(|| {
+                GibberError::new(
+                    "NOT_FOUND",
+                    format!("Conversation {conversation_id} not found"),
+                )
+            })?;
+        (conversation, load_settings(&conn))
+    };
+    let conversation = match deidentify {
+        Some(options) => deidentify::deidentify(&conversation, &options).conversation,
+        None => conversation,
+    };
+    let (sealed, key) = encrypt(conversations::export_json(&conversation).as_bytes())?;
+    let expires_in_hours = expires_in_hours.map(|hours| hours.clamp(1, MAX_EXPIRES_HOURS));
+
+    let ShareBackend::Paste { url: base_url } = &settings.backend;
+    let mut form = Form::new()
+        .part("file", Part::bytes(sealed).file_name("conversation.bin"))
+        .text("secret", "");
+    if let Some(hours) = expires_in_hours {
+        form = form.text("expires", hours.to_string());
+    }
+    let client = network::client(&app, Service::Sharing)?;
+    let builder = client.post(base_url).multipart(form);
+    let response =
+        network_activity::send(&app, Service::Sharing, "sharing.upload", builder).await?;
+    let status = response.status();
+    let delete_token = response
+        .headers()
+        .get("X-Token")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response.text().await?;
+    let url = body.trim();
+    if !status.is_success() || !url.starts_with("http") {
+        return Err(GibberError::new(
+            "UPLOAD",
+            format!("Upload refused (HTTP {status}): {url}"),
+        ));
+    }
+
+    let now = db::now_millis();
+    let share = Share {
+        id: db::new_id(),
+        conversation_id,
+        backend: settings.backend.name().to_string(),
+        url: url.to_string(),
+        link: share_link(url, &key),
+        expires_at: expires_in_hours.map(|hours| now + i64::from(hours) * 3_600_000),
+        created_at: now,
+        revoked_at: None,
+        delete_token,
+    };
+    app.state::<Database>().conn().execute(
+        &format!(
+            "INSERT INTO shares ({SHARE_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        ),
+        params![
+            share.id,
+            share.conversation_id,
+            share.backend,
+            share.url,
+            share.link,
+            share.delete_token,
+            share.expires_at,
+            share.created_at,
+            share.revoked_at,
+        ],
+    )?;
+    tracing::info!(id = %share.id, backend = share.backend, "conversation shared");
+    Ok(share)
+}
+
+/// Deletes a shared upload from its backend and marks the share revoked.
+///
+/// Uploads the backend no longer has count as revoked.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the share doesn't exist,
+/// `REVOKE` if the backend refuses the deletion or no management token was
+/// given at upload, `NETWORK` if it can't be reached, or `DATABASE` if a
+/// query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const share = await invoke("revoke_share", { id });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn revoke_share(app: AppHandle, id: String) -> Result<Share, GibberError> {
+    let mut share = load_share(&app.state::<Database>().conn(), &id)?;
+    if share.revoked_at.is_some() {
+        return Ok(share);
+    }
+    let Some(token) = share.delete_token.clone() else {
+        return Err(GibberError::new(
+            "REVOKE",
+            "The backend gave no token to delete this upload",
+        ));
+    };
+
+    let client = network::client(&app, Service::Sharing)?;
+    let builder = client
+        .post(&share.url)
+        .multipart(Form::new().text("token", token).text("delete", ""));
+    let response =
+        network_activity::send(&app, Service::Sharing, "sharing.revoke", builder).await?;
+    let status = response.status();
+    if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+        return Err(GibberError::new(
+            "REVOKE",
+            format!("Deletion refused (HTTP {status})"),
+        ));
+    }
+
+    let revoked_at = db::now_millis();
+    app.state::<Database>().conn().execute(
+        "UPDATE shares SET revoked_at = ?2 WHERE id = ?1",
+        params![id, revoked_at],
+    )?;
+    share.revoked_at = Some(revoked_at);
+    tracing::info!(id = %share.id, "share revoked");
+    Ok(share)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decrypt(sealed: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+        let (nonce, ciphertext) = sealed.split_at(12);
+        Aes256Gcm::new_from_slice(key)
+            .ok()?
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+            .ok()
+    }
+
+    #[test]
+    fn test_encrypt_round_trips() {
+        let (sealed, key) = encrypt(b"secret beats").unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(sealed.len(), 12 + b"secret beats".len() + 16);
+        assert_eq!(decrypt(&sealed, &key).unwrap(), b"secret beats");
+
+        let (_, other_key) = encrypt(b"").unwrap();
+        assert!(decrypt(&sealed, &other_key).is_none());
+    }
+
+    #[test]
+    fn test_share_link_puts_key_in_fragment() {
+        assert_eq!(
+            share_link("https://0x0.st/abc.bin", &[0xfb, 0xff]),
+            "https://0x0.st/abc.bin#-_8"
+        );
+    }
+
+    #[test]
+    fn test_settings_validate() {
+        assert!(ShareSettings::default().validate().is_ok());
+        let ftp = ShareSettings {
+            backend: ShareBackend::Paste {
+                url: "ftp://paste.example".to_string(),
+            },
+        };
+        assert_eq!(ftp.validate().unwrap_err().code(), "INVALID_SETTINGS");
+    }
+
+    #[test]
+    fn test_load_share() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        for (id, conversation_id) in [("s1", "a"), ("s2", "b")] {
+            conn.execute(
+                "INSERT INTO shares (id, conversation_id, backend, url, link, created_at)
+                 VALUES (?1, ?2, 'paste', 'https://p/x', 'https://p/x#k', 0)",
+                params![id, conversation_id],
+            )
+            .unwrap();
+        }
+        assert_eq!(load_share(&conn, "s1").unwrap().conversation_id, "a");
+        assert_eq!(
+            load_share(&conn, "missing").unwrap_err().code(),
+            "NOT_FOUND"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! User-configurable global shortcuts.
+//!
+//! Each [`ShortcutAction`] can be bound to one accelerator (for example
+//! `CommandOrControl+Shift+Space`) or left unbound. Bindings are stored in
+//! the settings table, registered at startup, and can be changed at runtime
+//! with `set_shortcuts`, which validates the new set before swapping it in
+//! and restores the previous one if the OS refuses a registration.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::commands::events;
+use crate::commands::{automations, quick_capture, screenshot};
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the shortcut bindings.
+pub const SETTINGS_KEY: &str = "shortcuts";
+
+/// Something a global shortcut can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutAction {
+    /// Toggle the quick-capture window
+    QuickCapture,
+    /// Hold to talk; press and release are forwarded to the main window
+    PushToTalk,
+    /// Capture the primary screen into the current conversation
+    Screenshot,
+    /// Show or hide the main window
+    ToggleWindow,
+}
+
+/// Shortcut bindings; `None` leaves an action unbound.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShortcutSettings {
+    /// Binding for [`ShortcutAction::QuickCapture`]
+    pub quick_capture: Option<String>,
+    /// Binding for [`ShortcutAction::PushToTalk`]
+    pub push_to_talk: Option<String>,
+    /// Binding for [`ShortcutAction::Screenshot`]
+    pub screenshot: Option<String>,
+    /// Binding for [`ShortcutAction::ToggleWindow`]
+    pub toggle_window: Option<String>,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            quick_capture: Some(quick_capture::DEFAULT_SHORTCUT.to_string()),
+            push_to_talk: None,
+            screenshot: Some(screenshot::DEFAULT_SHORTCUT.to_string()),
+            toggle_window: None,
+        }
+    }
+}
+
+impl ShortcutSettings {
+    fn bindings(&self) -> [(ShortcutAction, Option<&str>); 4] {
+        [
+            (ShortcutAction::QuickCapture, self.quick_capture.as_deref()),
+            (ShortcutAction::PushToTalk, self.push_to_talk.as_deref()),
+            (ShortcutAction::Screenshot, self.screenshot.as_deref()),
+            (ShortcutAction::ToggleWindow, self.toggle_window.as_deref()),
+        ]
+    }
+}
+
+/// Parses every binding and rejects invalid or duplicate accelerators.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SHORTCUT` for unparsable
+/// accelerators or `CONFLICT` if two actions share one.
+pub fn validate(
+    settings: &ShortcutSettings,
+) -> Result<Vec<(ShortcutAction, Shortcut)>, GibberError> {
+    let mut parsed: Vec<(ShortcutAction, Shortcut)> = Vec::new();
+    for (action, binding) in settings.bindings() {
+        let Some(binding) = binding.map(str::trim).filter(|b| !b.is_empty()) else {
+            continue;
+        };
+        let shortcut = Shortcut::from_str(binding)
+            .map_err(|e| GibberError::new("INVALID_SHORTCUT", format!("\"{binding}\": {e}")))?;
+        if let Some((other, _)) = parsed.iter().find(|(_, s)| s.id() == shortcut.id()) {
+            return Err(GibberError::new(
+                "CONFLICT",
+                format!("\"{binding}\" is bound to both {other:?} and {action:?}"),
+            ));
+        }
+        parsed.push((action, shortcut));
+    }
+    Ok(parsed)
+}
+
+/// Active bindings, keyed by shortcut ID.
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    settings: Mutex<ShortcutSettings>,
+    actions: Mutex<HashMap<u32, ShortcutAction>>,
+}
+
+impl ShortcutRegistry {
+    fn actions(&self) -> MutexGuard<'_, HashMap<u32, ShortcutAction>> {
+        self.actions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn settings(&self) -> MutexGuard<'_, ShortcutSettings> {
+        self.settings.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns whether an app action is bound to the shortcut with this ID.
+    pub(crate) fn is_bound(&self, shortcut_id: u32) -> bool {
+        self.actions().contains_key(&shortcut_id)
+    }
+}
+
+fn toggle_main_window(app: &AppHandle) -> tauri::Result<()> {
+    let Some(window) = app.get_webview_window(quick_capture::MAIN_WINDOW_LABEL) else {
+        return Ok(());
+    };
+    if window.is_visible()? && window.is_focused()? {
+        window.hide()
+    } else {
+        quick_capture::focus_main_window(app)
+    }
+}
+
+fn dispatch(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    let action = app
+        .try_state::<ShortcutRegistry>()
+        .and_then(|registry| registry.actions().get(&shortcut.id()).copied());
+    let pressed = event.state() == ShortcutState::Pressed;
+    let result = match action {
+        Some(ShortcutAction::PushToTalk) => events::emit_typed_to(
+            app,
+            quick_capture::MAIN_WINDOW_LABEL,
+            events::SHORTCUT_PUSH_TO_TALK,
+            &pressed,
+        ),
+        Some(_) if !pressed => Ok(()),
+        Some(ShortcutAction::QuickCapture) => quick_capture::toggle(app),
+        Some(ShortcutAction::Screenshot) => {
+            screenshot::capture_current(app);
+            Ok(())
+        }
+        Some(ShortcutAction::ToggleWindow) => toggle_main_window(app),
+        None => {
+            if pressed {
+                automations::trigger_hotkey(app, shortcut.id());
+            }
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        tracing::warn!("{action:?} failed: {e}");
+    }
+}
+
+/// Unregisters the current bindings and registers `parsed` in their place.
+fn apply(app: &AppHandle, parsed: &[(ShortcutAction, Shortcut)]) -> Result<(), GibberError> {
+    let registry = app.state::<ShortcutRegistry>();
+    let shortcuts = app.global_shortcut();
+    let mut actions = registry.actions();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| GibberError::new("UNAVAILABLE", e.to_string()))?;
+    actions.clear();
+    for (action, shortcut) in parsed {
+        shortcuts.register(*shortcut).map_err(|e| {
+            GibberError::new(
+                "UNAVAILABLE",
+                format!("{action:?} shortcut is unavailable: {e}"),
+            )
+        })?;
+        actions.insert(shortcut.id(), *action);
+    }
+    Ok(())
+}
+
+/// Validates, registers, and stores a set of bindings.
+///
+/// If the OS refuses any registration (typically because another app owns
+/// the shortcut), the previous bindings are restored.
+///
+/// # Errors
+///
+/// See [`set_shortcuts`].
+pub fn rebind(
+    app: &AppHandle,
+    settings: ShortcutSettings,
+) -> Result<ShortcutSettings, GibberError> {
+    let parsed = validate(&settings)?;
+    let registry = app.state::<ShortcutRegistry>();
+    let previous = registry.settings().clone();
+    let applied = apply(app, &parsed);
+    if applied.is_err() {
+        if let Err(e) = validate(&previous).and_then(|old| apply(app, &old)) {
+            tracing::error!("failed to restore previous bindings: {e}");
+        }
+    }
+    // `apply` unregisters every global shortcut, automation hotkeys included.
+    automations::register_hotkeys(app);
+    applied?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&app.state::<Database>().conn(), SETTINGS_KEY, &value)?;
+    *registry.settings() = settings.clone();
+    Ok(settings)
+}
+
+/// Installs the global-shortcut plugin and registers the stored bindings.
+///
+/// A binding the OS refuses is logged and skipped, so one taken shortcut
+/// doesn't disable the others. Must be called after the [`Database`] has
+/// been added to managed state.
+///
+/// # Errors
+///
+/// Returns an error if the plugin cannot be installed.
+#[cfg(desktop)]
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    app.plugin(
+        tauri_plugin_global_shortcut::Builder::new()
+            .with_handler(dispatch)
+            .build(),
+    )?;
+
+    let settings: ShortcutSettings =
+        db::read_setting(&app.state::<Database>().conn(), SETTINGS_KEY)?
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default();
+    let registry = ShortcutRegistry::default();
+    match validate(&settings) {
+        Ok(parsed) => {
+            let mut actions = registry.actions();
+            for (action, shortcut) in parsed {
+                match app.global_shortcut().register(shortcut) {
+                    Ok(()) => {
+                        actions.insert(shortcut.id(), action);
+                    }
+                    Err(e) => tracing::warn!("{action:?} shortcut is unavailable: {e}"),
+                }
+            }
+        }
+        Err(e) => tracing::warn!("stored bindings are invalid: {e}"),
+    }
+    *registry.settings() = settings;
+    app.manage(registry);
+    Ok(())
+}
+
+/// Returns the current shortcut bindings.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_shortcuts(registry: State<'_, ShortcutRegistry>) -> ShortcutSettings {
+    registry.settings().clone()
+}
+
+/// Replaces the shortcut bindings without a restart.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SHORTCUT` or `CONFLICT` if the
+/// bindings are rejected before anything changes, or `UNAVAILABLE` if the OS
+/// refuses one (the previous bindings are then restored).
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_shortcuts", {
+///   settings: { ...current, pushToTalk: "CommandOrControl+Shift+Period" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn set_shortcuts(
+    app: AppHandle,
+    settings: ShortcutSettings,
+) -> Result<ShortcutSettings, GibberError> {
+    rebind(&app, settings)
+}
+
+/// Restores the default shortcut bindings.
+///
+/// # Errors
+///
+/// See [`set_shortcuts`].
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn reset_shortcuts(app: AppHandle) -> Result<ShortcutSettings, GibberError> {
+    rebind(&app, ShortcutSettings::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let parsed = validate(&ShortcutSettings::default()).expect("Defaults should parse");
+        assert_eq!(parsed.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_invalid_accelerator() {
+        let settings = ShortcutSettings {
+            toggle_window: Some("Ctrl+Banana".to_string()),
+            ..ShortcutSettings::default()
+        };
+        assert_eq!(validate(&settings).unwrap_err().code(), "INVALID_SHORTCUT");
+    }
+
+    #[test]
+    fn test_rejects_conflicts() {
+        let settings = ShortcutSettings {
+            toggle_window: Some(quick_capture::DEFAULT_SHORTCUT.to_string()),
+            ..ShortcutSettings::default()
+        };
+        assert_eq!(validate(&settings).unwrap_err().code(), "CONFLICT");
+    }
+
+    #[test]
+    fn test_blank_bindings_are_unbound() {
+        let settings = ShortcutSettings {
+            quick_capture: Some("  ".to_string()),
+            screenshot: None,
+            ..ShortcutSettings::default()
+        };
+        assert!(validate(&settings).unwrap().is_empty());
+    }
+}
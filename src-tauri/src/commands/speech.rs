@@ -0,0 +1,213 @@
+//! Text-to-speech.
+//!
+//! A [`Speaker`] reads text aloud with OpenAI's speech API, using the API
+//! key stored under `openai` in the keyring. One request takes at most
+//! [`MAX_INPUT_CHARS`], so longer text is split at sentence and paragraph
+//! boundaries with [`split`] and sent part by part.
+
+use std::time::Duration;
+
+use reqwest::Client;
+use tauri::AppHandle;
+
+use crate::commands::credentials;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::error::GibberError;
+
+const SPEECH_URL: &str = "https://api.openai.com/v1/audio/speech";
+
+/// Keyring service of the API key.
+pub const PROVIDER: &str = "openai";
+
+/// Model used when the caller doesn't name one.
+pub const DEFAULT_MODEL: &str = "gpt-4o-mini-tts";
+
+/// Voices the speech models offer.
+pub const VOICES: [&str; 11] = [
+    "alloy", "ash", "ballad", "coral", "echo", "fable", "nova", "onyx", "sage", "shimmer", "verse",
+];
+
+/// Longest text per request, in characters; the API allows 4,096.
+pub const MAX_INPUT_CHARS: usize = 4_000;
+
+/// Sample rate of [`SpeechFormat::Pcm`] audio: 16-bit little-endian mono.
+pub const PCM_SAMPLE_RATE: u32 = 24_000;
+
+/// Synthesizing a long part can take a while.
+const REQUEST_TIMEOUT: Duration = Duration::from_mins(2);
+
+/// Encoding of synthesized audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeechFormat {
+    /// MP3
+    Mp3,
+    /// Opus in an Ogg container
+    Opus,
+    /// Raw samples at [`PCM_SAMPLE_RATE`], without a header
+    Pcm,
+}
+
+impl SpeechFormat {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Opus => "opus",
+            Self::Pcm => "pcm",
+        }
+    }
+}
+
+/// Checks that `voice` is one of [`VOICES`].
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` otherwise.
+pub fn validate_voice(voice: &str) -> Result<(), GibberError> {
+    if VOICES.contains(&voice) {
+        Ok(())
+    } else {
+        Err(GibberError::invalid(format!(
+            "Unknown voice {voice}; choose one of {}",
+            VOICES.join(", ")
+        )))
+    }
+}
+
+/// Splits `text` into parts of at most `max_chars` characters, breaking
+/// after sentences and lines where possible.
+pub fn split(text: &str, max_chars: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+    for piece in text.split_inclusive(['.', '!', '?', '\n']) {
+        let chars = piece.chars().count();
+        if current_chars + chars > max_chars {
+            if !current.trim().is_empty() {
+                parts.push(current.trim().to_string());
+            }
+            current.clear();
+            current_chars = 0;
+        }
+        if chars > max_chars {
+            let piece: Vec<char> = piece.chars().collect();
+            parts.extend(
+                piece
+                    .chunks(max_chars)
+                    .map(|chunk| chunk.iter().collect::<String>().trim().to_string())
+                    .filter(|chunk| !chunk.is_empty()),
+            );
+            continue;
+        }
+        current.push_str(piece);
+        current_chars += chars;
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/// A client for the speech API with its key loaded.
+pub(crate) struct Speaker {
+    client: Client,
+    api_key: String,
+}
+
+impl Speaker {
+    /// Loads the API key and the network client.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GibberError` with code `NO_API_KEY` if no OpenAI key is
+    /// stored, or `NETWORK` if the client can't be built.
+    pub(crate) async fn new(app: &AppHandle) -> Result<Self, GibberError> {
+        let api_key = credentials::load_api_key(app, PROVIDER)
+            .await
+            .map_err(|e| GibberError::new("NO_API_KEY", e.to_string()))?
+            .ok_or_else(|| GibberError::new("NO_API_KEY", "No openai API key is stored"))?;
+        let client = network::client(app, Service::Speech)
+            .map_err(|e| GibberError::new("NETWORK", e.to_string()))?;
+        Ok(Self { client, api_key })
+    }
+
+    /// Reads `text` aloud in `voice` and returns the encoded audio.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GibberError` with code `TOO_LARGE` for text over
+    /// [`MAX_INPUT_CHARS`], `UNAUTHORIZED`, `RATE_LIMITED`, `REJECTED`, or
+    /// `PROVIDER_ERROR` if the API refuses, or a network error.
+    pub(crate) async fn synthesize(
+        &self,
+        app: &AppHandle,
+        model: &str,
+        voice: &str,
+        text: &str,
+        format: SpeechFormat,
+    ) -> Result<Vec<u8>, GibberError> {
+        if text.chars().count() > MAX_INPUT_CHARS {
+            return Err(GibberError::new(
+                "TOO_LARGE",
+                format!("Speech input is limited to {MAX_INPUT_CHARS} characters"),
+            ));
+        }
+        let body = serde_json::json!({
+            "model": model,
+            "voice": voice,
+            "input": text,
+            "response_format": format.as_str(),
+        });
+        let builder = self
+            .client
+            .post(SPEECH_URL)
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(&self.api_key)
+            .json(&body);
+        let response =
+            network_activity::send(app, Service::Speech, "speech.synthesize", builder).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            let message = body
+                .pointer("/error/message")
+                .and_then(serde_json::Value::as_str)
+                .map_or_else(|| status.to_string(), str::to_string);
+            let code = match status.as_u16() {
+                401 | 403 => "UNAUTHORIZED",
+                429 => "RATE_LIMITED",
+                400 | 422 => "REJECTED",
+                _ => "PROVIDER_ERROR",
+            };
+            return Err(GibberError::new(code, message));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_breaks_after_sentences() {
+        let text = "The kick is muddy. Cut 200 Hz!\nThen compress the bus gently?";
+        assert_eq!(
+            split(text, 32),
+            vec![
+                "The kick is muddy. Cut 200 Hz!",
+                "Then compress the bus gently?"
+            ]
+        );
+        assert_eq!(split(text, 1_000), vec![text]);
+        assert_eq!(split("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert!(split("  \n ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_validate_voice() {
+        assert!(validate_voice("nova").is_ok());
+        assert_eq!(validate_voice("Nova").unwrap_err().code(), "INVALID_INPUT");
+        assert_eq!(SpeechFormat::Opus.as_str(), "opus");
+    }
+}
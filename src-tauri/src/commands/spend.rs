@@ -0,0 +1,543 @@
+//! Spend ledger and caps.
+//!
+//! Every completion's cost, as reported by OpenRouter, is written to the
+//! `spend_ledger` table; the frontend AI client adds its own with
+//! `record_spend`. Users can set a daily and a monthly cap in US dollars.
+//! Before a backend request is sent, [`enforce`] projects its cost from the
+//! model's prices (the prompt estimated at four characters a token plus the
+//! full `max_tokens` completion) and refuses it with code `BUDGET_EXCEEDED` if
+//! the spend so far plus that projection would go over a cap. Refusals are
+//! announced as [`events::BUDGET_EXCEEDED`]; the user can then lift the caps
+//! for a while with `override_spend_cap`.
+//!
+//! Days and months follow the local time zone.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, Local, TimeZone};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{ChatRequest, TokenUsage};
+use crate::commands::events;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the [`SpendCaps`] document.
+pub const SETTINGS_KEY: &str = "spendCaps";
+
+const MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+
+/// How long fetched model prices are reused.
+const PRICING_TTL: Duration = Duration::from_hours(24);
+
+/// Longest override, in minutes.
+const MAX_OVERRIDE_MINUTES: u32 = 24 * 60;
+
+/// Spending limits in US dollars; `None` means no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SpendCaps {
+    /// Limit per local calendar day
+    pub daily_usd: Option<f64>,
+    /// Limit per local calendar month
+    pub monthly_usd: Option<f64>,
+}
+
+impl SpendCaps {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        let valid = |cap: Option<f64>| cap.is_none_or(|cap| cap.is_finite() && cap >= 0.0);
+        if !valid(self.daily_usd) || !valid(self.monthly_usd) {
+            return Err(GibberError::new(
+                "INVALID_SETTINGS",
+                "Spend caps must be zero or more dollars",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load_caps(conn: &Connection) -> SpendCaps {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Which cap a request would break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SpendPeriod {
+    /// The daily cap
+    Daily,
+    /// The monthly cap
+    Monthly,
+}
+
+/// Payload of [`events::BUDGET_EXCEEDED`].
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceededPayload {
+    /// Cap that would be broken
+    pub period: SpendPeriod,
+    /// The cap
+    pub cap_usd: f64,
+    /// Spent so far in the period
+    pub spent_usd: f64,
+    /// Projected cost of the refused request
+    pub projected_usd: f64,
+    /// Model the request was for
+    pub model: String,
+    /// Conversation the request was for
+    pub conversation_id: Option<String>,
+}
+
+/// Spend so far and the caps.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendSummary {
+    /// Spent today
+    pub today_usd: f64,
+    /// Spent this month
+    pub month_usd: f64,
+    /// The caps
+    pub caps: SpendCaps,
+    /// When an override ends, in Unix milliseconds
+    pub override_until: Option<i64>,
+}
+
+/// Per-token prices in US dollars.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelPricing {
+    /// Price of a prompt token
+    pub prompt: f64,
+    /// Price of a completion token
+    pub completion: f64,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<ListedModel>,
+}
+
+#[derive(Deserialize)]
+struct ListedModel {
+    id: String,
+    pricing: ListedPricing,
+}
+
+/// OpenRouter gives prices as decimal strings.
+#[derive(Deserialize)]
+struct ListedPricing {
+    prompt: String,
+    completion: String,
+}
+
+/// Managed spend state: cached model prices and the active override.
+#[derive(Default)]
+pub struct SpendGuard {
+    pricing: Mutex<Option<(Instant, HashMap<String, ModelPricing>)>>,
+    /// Override end in Unix milliseconds
+    override_until: Mutex<Option<i64>>,
+}
+
+impl SpendGuard {
+    fn pricing(&self) -> MutexGuard<'_, Option<(Instant, HashMap<String, ModelPricing>)>> {
+        self.pricing.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn override_until(&self) -> Option<i64> {
+        let mut until = self
+            .override_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if until.is_some_and(|until| until <= db::now_millis()) {
+            *until = None;
+        }
+        *until
+    }
+
+    fn set_override(&self, until: Option<i64>) {
+        *self
+            .override_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = until;
+    }
+}
+
+fn local_millis(date: chrono::NaiveDate) -> i64 {
+    Local
+        .from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+        .earliest()
+        .map_or(0, |start| start.timestamp_millis())
+}
+
+/// Starts of the current local day and month in Unix milliseconds.
+fn period_starts() -> (i64, i64) {
+    let today = Local::now().date_naive();
+    let month = today.with_day(1).unwrap_or(today);
+    (local_millis(today), local_millis(month))
+}
+
+fn spent_since(conn: &Connection, since: i64) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(cost_usd), 0) FROM spend_ledger WHERE created_at >= ?1",
+        [since],
+        |row| row.get(0),
+    )
+}
+
+/// Adds a cost to the ledger.
+///
+/// # Errors
+///
+/// Returns an error if the insert fails.
+pub(crate) fn insert(
+    conn: &Connection,
+    provider: &str,
+    model: &str,
+    conversation_id: Option<&str>,
+    usage: Option<TokenUsage>,
+    cost_usd: f64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO spend_ledger
+            (provider, model, conversation_id, prompt_tokens, completion_tokens, cost_usd, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            provider,
+            model,
+            conversation_id,
+            usage.map(|usage| usage.prompt_tokens),
+            usage.map(|usage| usage.completion_tokens),
+            cost_usd,
+            db::now_millis(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Records a completion's cost, logging rather than failing.
+pub(crate) fn record(
+    app: &AppHandle,
+    model: &str,
+    conversation_id: Option<&str>,
+    usage: Option<TokenUsage>,
+    cost_usd: f64,
+) {
+    let Some(db) = app.try_state::<Database>() else {
+        return;
+    };
+    let conn = db.conn();
+    if let Err(e) = insert(&conn, "openrouter", model, conversation_id, usage, cost_usd) {
+        tracing::error!(model, "failed to record spend: {e}");
+    }
+}
+
+/// Projected cost of `request` at `pricing`.
+pub fn project(request: &ChatRequest, pricing: ModelPricing) -> f64 {
+    let prompt_chars: usize = request.messages.iter().map(|m| m.content.len()).sum();
+    #[allow(clippy::cast_precision_loss)] // far below 2^52 characters
+    let prompt_tokens = (prompt_chars as f64 / 4.0).ceil();
+    prompt_tokens * pricing.prompt + f64::from(request.max_tokens) * pricing.completion
+}
+
+/// The cap that `projected` would break, given the spend so far.
+fn breach(
+    caps: SpendCaps,
+    today: f64,
+    month: f64,
+    projected: f64,
+) -> Option<(SpendPeriod, f64, f64)> {
+    [
+        (SpendPeriod::Daily, caps.daily_usd, today),
+        (SpendPeriod::Monthly, caps.monthly_usd, month),
+    ]
+    .into_iter()
+    .find_map(|(period, cap, spent)| {
+        // A zero-cost projection still stops once the cap is reached.
+        cap.filter(|cap| spent + projected > *cap || spent >= *cap)
+            .map(|cap| (period, cap, spent))
+    })
+}
+
+async fn fetch_pricing(app: &AppHandle) -> Option<HashMap<String, ModelPricing>> {
+    let client = network::client(app, Service::OpenRouter).ok()?;
+    let response = network_activity::send(
+        app,
+        Service::OpenRouter,
+        "chat.pricing",
+        client.get(MODELS_URL),
+    )
+    .await
+    .ok()?
+    .error_for_status()
+    .ok()?;
+    let list: ModelList = response.json().await.ok()?;
+    Some(
+        list.data
+            .into_iter()
+            .filter_map(|model| {
+                let pricing = ModelPricing {
+                    prompt: model.pricing.prompt.parse().ok()?,
+                    completion: model.pricing.completion.parse().ok()?,
+                };
+                Some((model.id, pricing))
+            })
+            .collect(),
+    )
+}
+
+/// Prices of `model`, fetched at most once a day. `None` if unknown.
+async fn pricing_for(app: &AppHandle, model: &str) -> Option<ModelPricing> {
+    let guard = app.try_state::<SpendGuard>()?;
+    let fresh = guard
+        .pricing()
+        .as_ref()
+        .filter(|(fetched, _)| fetched.elapsed() < PRICING_TTL)
+        .map(|(_, prices)| prices.get(model).copied());
+    if let Some(pricing) = fresh {
+        return pricing;
+    }
+    let prices = fetch_pricing(app).await?;
+    let pricing = prices.get(model).copied();
+    *guard.pricing() = Some((Instant::now(), prices));
+    pricing
+}
+
+/// Refuses `request` if it would take spending over a cap.
+///
+/// Without caps this returns at once; without known prices only the spend
+/// so far counts.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `BUDGET_EXCEEDED` and emits
+/// [`events::BUDGET_EXCEEDED`] if a cap would be broken.
+pub(crate) async fn enforce(app: &AppHandle, request: &ChatRequest) -> Result<(), GibberError> {
+    let Some(db) = app.try_state::<Database>() else {
+        return Ok(());
+    };
+    let caps = load_caps(&db.conn());
+    if caps == SpendCaps::default() {
+        return Ok(());
+    }
+    if app
+        .try_state::<SpendGuard>()
+        .is_some_and(|guard| guard.override_until().is_some())
+    {
+        return Ok(());
+    }
+    let projected = pricing_for(app, &request.model)
+        .await
+        .map_or(0.0, |pricing| project(request, pricing));
+    let (day_start, month_start) = period_starts();
+    let (today, month) = {
+        let conn = db.conn();
+        (
+            spent_since(&conn, day_start)?,
+            spent_since(&conn, month_start)?,
+        )
+    };
+    let Some((period, cap_usd, spent_usd)) = breach(caps, today, month, projected) else {
+        return Ok(());
+    };
+    let payload = ExceededPayload {
+        period,
+        cap_usd,
+        spent_usd,
+        projected_usd: projected,
+        model: request.model.clone(),
+        conversation_id: request.conversation_id.clone(),
+    };
+    events::emit_typed(app, events::BUDGET_EXCEEDED, &payload);
+    let period = match period {
+        SpendPeriod::Daily => "daily",
+        SpendPeriod::Monthly => "monthly",
+    };
+    Err(GibberError::new(
+        "BUDGET_EXCEEDED",
+        format!(
+            "This request could cost ${projected:.4}, which would take spending past the \
+             {period} cap of ${cap_usd:.2} (${spent_usd:.2} spent)"
+        ),
+    ))
+}
+
+/// Returns the spend caps.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_spend_caps(db: State<'_, Database>) -> SpendCaps {
+    load_caps(&db.conn())
+}
+
+/// Replaces the spend caps.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` for a negative cap,
+/// or `DATABASE` if the caps cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_spend_caps", { caps: { dailyUsd: 2, monthlyUsd: 25 } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_spend_caps(db: State<'_, Database>, caps: SpendCaps) -> Result<SpendCaps, GibberError> {
+    caps.validate()?;
+    let value = serde_json::to_string(&caps).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(caps)
+}
+
+/// Returns today's and this month's spending with the caps.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the ledger cannot be read.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_spend_summary(
+    db: State<'_, Database>,
+    guard: State<'_, SpendGuard>,
+) -> Result<SpendSummary, GibberError> {
+    let conn = db.conn();
+    let (day_start, month_start) = period_starts();
+    Ok(SpendSummary {
+        today_usd: spent_since(&conn, day_start)?,
+        month_usd: spent_since(&conn, month_start)?,
+        caps: load_caps(&conn),
+        override_until: guard.override_until(),
+    })
+}
+
+/// Lets requests through despite the caps for `minutes` (default: 60,
+/// at most a day), or ends an override when `minutes` is 0.
+///
+/// # Returns
+///
+/// When the override ends, in Unix milliseconds.
+///
+/// # Example
+///
+/// ```typescript
+/// await listen("budget://exceeded", async ({ payload }) => {
+///   if (await confirm(`Go over the ${payload.period} cap for an hour?`)) {
+///     await invoke("override_spend_cap", { minutes: 60 });
+///   }
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn override_spend_cap(guard: State<'_, SpendGuard>, minutes: Option<u32>) -> Option<i64> {
+    let minutes = minutes.unwrap_or(60).min(MAX_OVERRIDE_MINUTES);
+    let until = (minutes > 0).then(|| db::now_millis() + i64::from(minutes) * 60_000);
+    guard.set_override(until);
+    until
+}
+
+/// Adds the cost of a completion the frontend made to the ledger.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` for a negative cost, or
+/// `DATABASE` if the entry cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("record_spend", {
+///   model: response.model,
+///   conversationId,
+///   usage: { prompt_tokens: 812, completion_tokens: 240 },
+///   costUsd: response.usage.cost,
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn record_spend(
+    db: State<'_, Database>,
+    model: String,
+    conversation_id: Option<String>,
+    usage: Option<TokenUsage>,
+    cost_usd: f64,
+) -> Result<(), GibberError> {
+    if !cost_usd.is_finite() || cost_usd < 0.0 {
+        return Err(GibberError::new("INVALID_INPUT", "Costs can't be negative"));
+    }
+    insert(
+        &db.conn(),
+        "openrouter",
+        &model,
+        conversation_id.as_deref(),
+        usage,
+        cost_usd,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ChatMessage, MessageRole};
+
+    #[test]
+    fn test_project_counts_prompt_and_full_completion() {
+        let mut request = ChatRequest::new(
+            Some("openai/gpt-4o"),
+            vec![ChatMessage::new(MessageRole::User, "a".repeat(400))],
+        );
+        request.max_tokens = 1000;
+        let pricing = ModelPricing {
+            prompt: 0.000_002,
+            completion: 0.000_01,
+        };
+        assert!((project(&request, pricing) - 0.0102).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_breach_checks_both_caps() {
+        let caps = SpendCaps {
+            daily_usd: Some(1.0),
+            monthly_usd: Some(10.0),
+        };
+        assert_eq!(breach(caps, 0.5, 5.0, 0.25), None);
+        assert_eq!(
+            breach(caps, 0.9, 5.0, 0.25),
+            Some((SpendPeriod::Daily, 1.0, 0.9))
+        );
+        assert_eq!(
+            breach(caps, 0.1, 9.9, 0.25),
+            Some((SpendPeriod::Monthly, 10.0, 9.9))
+        );
+        // Unknown prices project nothing, but a reached cap still holds.
+        assert_eq!(
+            breach(caps, 1.0, 5.0, 0.0),
+            Some((SpendPeriod::Daily, 1.0, 1.0))
+        );
+        assert_eq!(breach(SpendCaps::default(), 100.0, 100.0, 1.0), None);
+    }
+
+    #[test]
+    fn test_ledger_sums_since() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        insert(&conn, "openrouter", "openai/gpt-4o", None, None, 0.25).unwrap();
+        insert(&conn, "openrouter", "openai/gpt-4o", None, None, 0.5).unwrap();
+        assert!((spent_since(&conn, 0).unwrap() - 0.75).abs() < 1e-9);
+        assert!(spent_since(&conn, i64::MAX).unwrap().abs() < 1e-9);
+    }
+}
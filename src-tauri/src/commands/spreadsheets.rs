@@ -0,0 +1,1077 @@
+//! Data tools for spreadsheet attachments.
+//!
+//! A CSV or Excel file is usually far too large for a prompt, and models
+//! are poor at arithmetic over raw rows anyway. Instead the model is given
+//! two tools (see `get_data_tools`): one describes each sheet's columns
+//! with their types and a few sample rows, the other runs a query — rows
+//! matching filters, group-by aggregations, or per-column summaries — and
+//! returns only the result. Queries are computed here, not by the model.
+//!
+//! The first row of every sheet holds the column names. Excel dates are
+//! read as `YYYY-MM-DD HH:MM:SS` text, which sorts and compares in order.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::path::Path;
+
+use calamine::{Data, Reader as _};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::attached_db;
+use crate::commands::attachments::{self, Attachment, AttachmentKind, AttachmentStore};
+use crate::commands::calculator;
+use crate::commands::datetime;
+use crate::commands::plugins::PluginTool;
+use crate::commands::transform;
+use crate::commands::weather;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Rows shown per sheet by `describe_data_attachment`.
+const SAMPLE_ROWS: usize = 5;
+
+/// Result rows returned when the query gives no limit.
+const DEFAULT_LIMIT: u32 = 50;
+
+/// Most result rows a query may return.
+const MAX_LIMIT: u32 = 500;
+
+/// A cell value.
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Empty,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl Cell {
+    /// Reads a delimited-text field.
+    fn parse(field: &str) -> Self {
+        let field = field.trim();
+        if field.is_empty() {
+            Self::Empty
+        } else if let Some(number) = field.parse::<f64>().ok().filter(|n| n.is_finite()) {
+            Self::Number(number)
+        } else if field.eq_ignore_ascii_case("true") {
+            Self::Bool(true)
+        } else if field.eq_ignore_ascii_case("false") {
+            Self::Bool(false)
+        } else {
+            Self::Text(field.to_string())
+        }
+    }
+
+    fn from_data(data: &Data) -> Self {
+        match data {
+            Data::Empty => Self::Empty,
+            Data::Bool(value) => Self::Bool(*value),
+            #[allow(clippy::cast_precision_loss)] // spreadsheet integers fit in 2^52
+            Data::Int(value) => Self::Number(*value as f64),
+            Data::Float(value) => Self::Number(*value),
+            Data::DateTime(value) => value.as_datetime().map_or_else(
+                || Self::Number(value.as_f64()),
+                |datetime| Self::Text(datetime.format("%Y-%m-%d %H:%M:%S").to_string()),
+            ),
+            Data::String(value) | Data::DateTimeIso(value) | Data::DurationIso(value) => {
+                Self::parse(value)
+            }
+            Data::Error(error) => Self::Text(error.to_string()),
+        }
+    }
+
+    fn from_json(value: &Value) -> Self {
+        match value {
+            Value::Null => Self::Empty,
+            Value::Bool(value) => Self::Bool(*value),
+            Value::Number(number) => number.as_f64().map_or(Self::Empty, Self::Number),
+            Value::String(text) => Self::parse(text),
+            other => Self::Text(other.to_string()),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Empty => Value::Null,
+            Self::Bool(value) => Value::Bool(*value),
+            Self::Number(number) => json!(number),
+            Self::Text(text) => Value::String(text.clone()),
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Self::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Self::Empty => String::new(),
+            Self::Bool(value) => value.to_string(),
+            Self::Number(number) => number.to_string(),
+            Self::Text(text) => text.clone(),
+        }
+    }
+
+    /// Orders numbers numerically and everything else as text, ignoring
+    /// case; `None` when only one side is empty.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Empty, Self::Empty) => Some(Ordering::Equal),
+            (Self::Empty, _) | (_, Self::Empty) => None,
+            (Self::Number(a), Self::Number(b)) => a.partial_cmp(b),
+            (a, b) => Some(a.text().to_lowercase().cmp(&b.text().to_lowercase())),
+        }
+    }
+}
+
+/// A parsed sheet.
+#[derive(Debug, Clone, PartialEq)]
+struct Sheet {
+    name: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Sheet {
+    /// Builds a sheet from raw rows, the first holding the column names.
+    ///
+    /// Blank rows are dropped and short rows padded. Unnamed columns are
+    /// called `Column N`, and repeated names get a ` (2)`, ` (3)`… suffix.
+    fn new(name: String, rows: Vec<Vec<Cell>>) -> Self {
+        let mut rows = rows
+            .into_iter()
+            .filter(|row| row.iter().any(|cell| *cell != Cell::Empty));
+        let header = rows.next().unwrap_or_default();
+        let mut rows: Vec<Vec<Cell>> = rows.collect();
+        let width = rows
+            .iter()
+            .map(Vec::len)
+            .chain([header.len()])
+            .max()
+            .unwrap_or(0);
+        let mut columns: Vec<String> = Vec::with_capacity(width);
+        for index in 0..width {
+            let base = header
+                .get(index)
+                .map(Cell::text)
+                .filter(|text| !text.is_empty())
+                .unwrap_or_else(|| format!("Column {}", index + 1));
+            let mut name = base.clone();
+            let mut copy = 1;
+            while columns.contains(&name) {
+                copy += 1;
+                name = format!("{base} ({copy})");
+            }
+            columns.push(name);
+        }
+        for row in &mut rows {
+            row.resize(width, Cell::Empty);
+        }
+        Self {
+            name,
+            columns,
+            rows,
+        }
+    }
+
+    fn column(&self, name: &str) -> Result<usize, GibberError> {
+        self.columns
+            .iter()
+            .position(|column| column == name)
+            .or_else(|| {
+                self.columns
+                    .iter()
+                    .position(|column| column.eq_ignore_ascii_case(name))
+            })
+            .ok_or_else(|| {
+                GibberError::invalid(format!(
+                    "{} has no column \"{name}\"; its columns are {}",
+                    self.name,
+                    self.columns.join(", ")
+                ))
+            })
+    }
+
+    fn columns_or_all(&self, names: &[String]) -> Result<Vec<usize>, GibberError> {
+        if names.is_empty() {
+            return Ok((0..self.columns.len()).collect());
+        }
+        names.iter().map(|name| self.column(name)).collect()
+    }
+}
+
+fn invalid_file(e: impl std::fmt::Display) -> GibberError {
+    GibberError::new(
+        "INVALID_CONTENT",
+        format!("The spreadsheet cannot be read: {e}"),
+    )
+}
+
+fn read_delimited(name: &str, bytes: &[u8], delimiter: u8) -> Result<Sheet, GibberError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(bytes);
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        rows.push(
+            record
+                .map_err(invalid_file)?
+                .iter()
+                .map(Cell::parse)
+                .collect(),
+        );
+    }
+    Ok(Sheet::new(name.to_string(), rows))
+}
+
+/// Parses every sheet of a CSV, TSV, or workbook file.
+fn read_sheets(file_name: &str, bytes: &[u8]) -> Result<Vec<Sheet>, GibberError> {
+    let path = Path::new(file_name);
+    let stem = path.file_stem().map_or_else(
+        || file_name.to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("csv") => Ok(vec![read_delimited(&stem, bytes, b',')?]),
+        Some("tsv") => Ok(vec![read_delimited(&stem, bytes, b'\t')?]),
+        _ => {
+            let mut workbook =
+                calamine::open_workbook_auto_from_rs(Cursor::new(bytes)).map_err(invalid_file)?;
+            let mut sheets = Vec::new();
+            for name in workbook.sheet_names() {
+                let range = workbook.worksheet_range(&name).map_err(invalid_file)?;
+                let rows = range
+                    .rows()
+                    .map(|row| row.iter().map(Cell::from_data).collect())
+                    .collect();
+                sheets.push(Sheet::new(name, rows));
+            }
+            Ok(sheets)
+        }
+    }
+}
+
+/// Type of a column's values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    /// No values
+    Empty,
+    /// Numbers
+    Number,
+    /// Booleans
+    Bool,
+    /// Text, including dates
+    Text,
+    /// More than one of the above
+    Mixed,
+}
+
+/// A column of a sheet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataColumn {
+    /// Name from the first row
+    pub name: String,
+    /// Type of its values
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+    /// Non-empty values
+    pub values: u32,
+    /// Distinct non-empty values
+    pub distinct: u32,
+}
+
+/// A sheet as shown to the model.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSheet {
+    /// Sheet name; the file name for CSV and TSV
+    pub name: String,
+    /// Data rows, not counting the header
+    pub rows: u32,
+    /// Columns in order
+    pub columns: Vec<DataColumn>,
+    /// First rows, one value per column
+    pub sample: Vec<Vec<Value>>,
+}
+
+/// What `describe_data_attachment` returns.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDescription {
+    /// The attachment
+    pub attachment_id: String,
+    /// Original file name
+    pub file_name: String,
+    /// Sheets in workbook order
+    pub sheets: Vec<DataSheet>,
+}
+
+fn count(values: usize) -> u32 {
+    u32::try_from(values).unwrap_or(u32::MAX)
+}
+
+fn describe_sheet(sheet: &Sheet) -> DataSheet {
+    let columns = sheet
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let mut column_type = ColumnType::Empty;
+            let mut distinct = HashSet::new();
+            for cell in sheet.rows.iter().map(|row| &row[index]) {
+                let cell_type = match cell {
+                    Cell::Empty => continue,
+                    Cell::Bool(_) => ColumnType::Bool,
+                    Cell::Number(_) => ColumnType::Number,
+                    Cell::Text(_) => ColumnType::Text,
+                };
+                column_type = match column_type {
+                    ColumnType::Empty => cell_type,
+                    current if current == cell_type => current,
+                    _ => ColumnType::Mixed,
+                };
+                distinct.insert(cell.text());
+            }
+            let values = sheet
+                .rows
+                .iter()
+                .filter(|row| row[index] != Cell::Empty)
+                .count();
+            DataColumn {
+                name: name.clone(),
+                column_type,
+                values: count(values),
+                distinct: count(distinct.len()),
+            }
+        })
+        .collect();
+    DataSheet {
+        name: sheet.name.clone(),
+        rows: count(sheet.rows.len()),
+        columns,
+        sample: sheet
+            .rows
+            .iter()
+            .take(SAMPLE_ROWS)
+            .map(|row| row.iter().map(Cell::to_json).collect())
+            .collect(),
+    }
+}
+
+/// How a filter compares a column with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterOp {
+    /// Equal
+    Eq,
+    /// Not equal
+    Ne,
+    /// Less than
+    Lt,
+    /// Less than or equal
+    Le,
+    /// Greater than
+    Gt,
+    /// Greater than or equal
+    Ge,
+    /// Contains the value as text, ignoring case
+    Contains,
+}
+
+/// Keeps the rows whose `column` compares with `value` as `op` says.
+#[derive(Debug, Clone, PartialEq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataFilter {
+    /// Column name
+    pub column: String,
+    /// Comparison
+    pub op: FilterOp,
+    /// Value compared with; `null` matches empty cells
+    pub value: Value,
+}
+
+/// Function computed over each group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateFunction {
+    /// Rows, or non-empty values of the column
+    Count,
+    /// Sum of numbers
+    Sum,
+    /// Mean of numbers
+    Mean,
+    /// Smallest value
+    Min,
+    /// Largest value
+    Max,
+    /// Distinct non-empty values
+    Distinct,
+}
+
+impl AggregateFunction {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Sum => "sum",
+            Self::Mean => "mean",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Distinct => "distinct",
+        }
+    }
+}
+
+/// An aggregate column of a group-by result.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Aggregation {
+    /// What to compute
+    pub function: AggregateFunction,
+    /// Column it is computed over; only `count` may leave it out
+    #[serde(default)]
+    pub column: Option<String>,
+}
+
+/// What a query computes from the filtered rows.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum DataOperation {
+    /// The rows themselves
+    Rows {
+        /// Columns to return; all when empty
+        #[serde(default)]
+        columns: Vec<String>,
+    },
+    /// One row per distinct combination of the `by` columns
+    GroupBy {
+        /// Columns to group by
+        by: Vec<String>,
+        /// Columns to compute; a row count when empty
+        #[serde(default)]
+        aggregations: Vec<Aggregation>,
+    },
+    /// One row of summary statistics per column
+    Describe {
+        /// Columns to summarize; all when empty
+        #[serde(default)]
+        columns: Vec<String>,
+    },
+}
+
+/// A query over one sheet.
+#[derive(Debug, Clone, PartialEq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataQuery {
+    /// Sheet name; the first sheet when `None`
+    #[serde(default)]
+    pub sheet: Option<String>,
+    /// Filters every row must pass
+    #[serde(default)]
+    pub filters: Vec<DataFilter>,
+    /// What to compute
+    pub operation: DataOperation,
+    /// Result column to sort by
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    /// Sort largest first
+    #[serde(default)]
+    pub descending: bool,
+    /// Most rows to return, up to 500 (default: 50)
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// What `query_data_attachment` returns.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DataResult {
+    /// Column names
+    pub columns: Vec<String>,
+    /// Rows, one value per column
+    pub rows: Vec<Vec<Value>>,
+    /// Rows before the limit was applied
+    pub total_rows: u32,
+    /// Whether rows were left out by the limit
+    pub truncated: bool,
+}
+
+fn passes(cell: &Cell, op: FilterOp, value: &Cell) -> bool {
+    let ordering = cell.compare(value);
+    match op {
+        FilterOp::Eq => ordering == Some(Ordering::Equal),
+        FilterOp::Ne => ordering != Some(Ordering::Equal),
+        FilterOp::Lt => ordering == Some(Ordering::Less),
+        FilterOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        FilterOp::Gt => ordering == Some(Ordering::Greater),
+        FilterOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        FilterOp::Contains => {
+            *cell != Cell::Empty
+                && cell
+                    .text()
+                    .to_lowercase()
+                    .contains(&value.text().to_lowercase())
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)] // row counts are far below 2^52
+fn mean(numbers: &[f64]) -> Option<f64> {
+    (!numbers.is_empty()).then(|| numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+
+fn extreme(cells: &[&Cell], wanted: Ordering) -> Value {
+    cells
+        .iter()
+        .filter(|cell| ***cell != Cell::Empty)
+        .copied()
+        .reduce(|best, cell| {
+            if cell.compare(best) == Some(wanted) {
+                cell
+            } else {
+                best
+            }
+        })
+        .map_or(Value::Null, Cell::to_json)
+}
+
+fn aggregate(function: AggregateFunction, cells: &[&Cell]) -> Value {
+    let numbers: Vec<f64> = cells.iter().filter_map(|cell| cell.number()).collect();
+    match function {
+        AggregateFunction::Count => {
+            json!(cells.iter().filter(|cell| ***cell != Cell::Empty).count())
+        }
+        AggregateFunction::Sum => json!(numbers.iter().sum::<f64>()),
+        AggregateFunction::Mean => mean(&numbers).map_or(Value::Null, |mean| json!(mean)),
+        AggregateFunction::Min => extreme(cells, Ordering::Less),
+        AggregateFunction::Max => extreme(cells, Ordering::Greater),
+        AggregateFunction::Distinct => {
+            let distinct: HashSet<String> = cells
+                .iter()
+                .filter(|cell| ***cell != Cell::Empty)
+                .map(|cell| cell.text())
+                .collect();
+            json!(distinct.len())
+        }
+    }
+}
+
+fn group_by(
+    sheet: &Sheet,
+    rows: &[&Vec<Cell>],
+    by: &[String],
+    aggregations: &[Aggregation],
+) -> Result<(Vec<String>, Vec<Vec<Value>>), GibberError> {
+    if by.is_empty() {
+        return Err(GibberError::invalid("groupBy needs at least one column"));
+    }
+    let keys = sheet.columns_or_all(by)?;
+    let default = [Aggregation {
+        function: AggregateFunction::Count,
+        column: None,
+    }];
+    let aggregations = if aggregations.is_empty() {
+        &default[..]
+    } else {
+        aggregations
+    };
+    let mut computed = Vec::with_capacity(aggregations.len());
+    let mut columns: Vec<String> = keys.iter().map(|&key| sheet.columns[key].clone()).collect();
+    for aggregation in aggregations {
+        match (&aggregation.column, aggregation.function) {
+            (None, AggregateFunction::Count) => {
+                computed.push((aggregation.function, None));
+                columns.push("count".to_string());
+            }
+            (None, function) => {
+                return Err(GibberError::invalid(format!(
+                    "{} needs a column",
+                    function.as_str()
+                )));
+            }
+            (Some(column), function) => {
+                let index = sheet.column(column)?;
+                computed.push((function, Some(index)));
+                columns.push(format!("{}({})", function.as_str(), sheet.columns[index]));
+            }
+        }
+    }
+
+    // Groups keep the order their first row appears in.
+    let mut groups: Vec<Vec<&Vec<Cell>>> = Vec::new();
+    let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+    for row in rows {
+        let key: Vec<String> = keys.iter().map(|&key| row[key].text()).collect();
+        let group = *index.entry(key).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(*row);
+    }
+    let rows = groups
+        .iter()
+        .map(|members| {
+            let mut values: Vec<Value> =
+                keys.iter().map(|&key| members[0][key].to_json()).collect();
+            for (function, column) in &computed {
+                values.push(match column {
+                    None => json!(members.len()),
+                    Some(column) => {
+                        let cells: Vec<&Cell> = members.iter().map(|row| &row[*column]).collect();
+                        aggregate(*function, &cells)
+                    }
+                });
+            }
+            values
+        })
+        .collect();
+    Ok((columns, rows))
+}
+
+fn describe_columns(
+    sheet: &Sheet,
+    rows: &[&Vec<Cell>],
+    names: &[String],
+) -> Result<(Vec<String>, Vec<Vec<Value>>), GibberError> {
+    let columns = [
+        "column", "count", "distinct", "mean", "std", "min", "median", "max", "top",
+    ];
+    let mut described = Vec::new();
+    for index in sheet.columns_or_all(names)? {
+        let cells: Vec<&Cell> = rows.iter().map(|row| &row[index]).collect();
+        let mut numbers: Vec<f64> = cells.iter().filter_map(|cell| cell.number()).collect();
+        numbers.sort_by(f64::total_cmp);
+        let mean = mean(&numbers);
+        #[allow(clippy::cast_precision_loss)] // row counts are far below 2^52
+        let std = mean.filter(|_| numbers.len() > 1).map(|mean| {
+            let squares: f64 = numbers.iter().map(|n| (n - mean).powi(2)).sum();
+            (squares / (numbers.len() - 1) as f64).sqrt()
+        });
+        let median = (!numbers.is_empty()).then(|| {
+            let middle = numbers.len() / 2;
+            if numbers.len().is_multiple_of(2) {
+                f64::midpoint(numbers[middle - 1], numbers[middle])
+            } else {
+                numbers[middle]
+            }
+        });
+        let mut frequencies: HashMap<String, usize> = HashMap::new();
+        for cell in cells.iter().filter(|cell| ***cell != Cell::Empty) {
+            *frequencies.entry(cell.text()).or_default() += 1;
+        }
+        let top = frequencies
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map_or(Value::Null, |(value, _)| json!(value));
+        let optional = |value: Option<f64>| value.map_or(Value::Null, |value| json!(value));
+        described.push(vec![
+            json!(sheet.columns[index]),
+            aggregate(AggregateFunction::Count, &cells),
+            json!(frequencies.len()),
+            optional(mean),
+            optional(std),
+            extreme(&cells, Ordering::Less),
+            optional(median),
+            extreme(&cells, Ordering::Greater),
+            top,
+        ]);
+    }
+    Ok((columns.map(str::to_string).to_vec(), described))
+}
+
+/// Orders result values: numbers numerically, text as text, nulls last.
+fn compare_values(a: &Value, b: &Value, descending: bool) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => {
+            let ordering = Cell::from_json(a)
+                .compare(&Cell::from_json(b))
+                .unwrap_or(Ordering::Equal);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+    }
+}
+
+fn run_query(sheets: &[Sheet], query: &DataQuery) -> Result<DataResult, GibberError> {
+    let sheet = match &query.sheet {
+        Some(name) => sheets
+            .iter()
+            .find(|sheet| sheet.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| GibberError::new("NOT_FOUND", format!("No sheet named \"{name}\"")))?,
+        None => sheets
+            .first()
+            .ok_or_else(|| GibberError::new("NOT_FOUND", "The spreadsheet has no sheets"))?,
+    };
+    let filters = query
+        .filters
+        .iter()
+        .map(|filter| {
+            Ok((
+                sheet.column(&filter.column)?,
+                filter.op,
+                Cell::from_json(&filter.value),
+            ))
+        })
+        .collect::<Result<Vec<_>, GibberError>>()?;
+    let rows: Vec<&Vec<Cell>> = sheet
+        .rows
+        .iter()
+        .filter(|row| {
+            filters
+                .iter()
+                .all(|(column, op, value)| passes(&row[*column], *op, value))
+        })
+        .collect();
+
+    let (columns, mut rows) = match &query.operation {
+        DataOperation::Rows { columns } => {
+            let indices = sheet.columns_or_all(columns)?;
+            (
+                indices
+                    .iter()
+                    .map(|&index| sheet.columns[index].clone())
+                    .collect(),
+                rows.iter()
+                    .map(|row| indices.iter().map(|&index| row[index].to_json()).collect())
+                    .collect(),
+            )
+        }
+        DataOperation::GroupBy { by, aggregations } => group_by(sheet, &rows, by, aggregations)?,
+        DataOperation::Describe { columns } => describe_columns(sheet, &rows, columns)?,
+    };
+    if let Some(sort_by) = &query.sort_by {
+        let index = columns
+            .iter()
+            .position(|column| column.eq_ignore_ascii_case(sort_by))
+            .ok_or_else(|| {
+                GibberError::invalid(format!(
+                    "The result has no column \"{sort_by}\"; its columns are {}",
+                    columns.join(", ")
+                ))
+            })?;
+        rows.sort_by(|a, b| compare_values(&a[index], &b[index], query.descending));
+    }
+    let limit = usize::try_from(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+        .unwrap_or(usize::MAX);
+    let total_rows = rows.len();
+    rows.truncate(limit);
+    Ok(DataResult {
+        columns,
+        rows,
+        total_rows: count(total_rows),
+        truncated: total_rows > limit,
+    })
+}
+
+/// Loads a spreadsheet attachment and parses its sheets.
+fn load(app: &AppHandle, id: &str) -> Result<(Attachment, Vec<Sheet>), GibberError> {
+    let attachment = attachments::load_attachment(&app.state::<Database>().conn(), id)?
+        .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Attachment {id} not found")))?;
+    if attachment.kind != AttachmentKind::Spreadsheet {
+        return Err(GibberError::new(
+            "NOT_SPREADSHEET",
+            format!("{} is not a spreadsheet", attachment.file_name),
+        ));
+    }
+    let bytes = std::fs::read(app.state::<AttachmentStore>().file_path(&attachment))?;
+    let sheets = read_sheets(&attachment.file_name, &bytes)?;
+    Ok((attachment, sheets))
+}
+
+/// Returns the tool definitions to offer the model for spreadsheet and
+/// database attachments and for structured data, in the shape of plugin
+/// tools.
+///
+/// `describe_data` maps to `describe_data_attachment`, `query_data` to
+/// `query_data_attachment`, `describe_db` to `describe_attached_db`, and
+/// `query_db` to `query_attached_db`; these take the attachment ID as
+/// `attachmentId`. `transform_data` takes its arguments as the `request`
+/// of the command of the same name, `calculate` maps to the command of the
+/// same name, `convert` to `convert_units`, and `resolve_datetime` and
+/// `get_weather` to the commands of the same names.
+///
+/// # Example
+///
+/// ```typescript
+/// const tools = await invoke("get_data_tools");
+/// // when the model calls query_data:
+/// const { attachmentId, ...query } = JSON.parse(call.arguments);
+/// const result = await invoke("query_data_attachment", { id: attachmentId, query });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub fn get_data_tools() -> Vec<PluginTool> {
+    let attachment_id = json!({
+        "type": "string",
+        "description": "ID of the spreadsheet attachment"
+    });
+    let filter = json!({
+        "type": "object",
+        "properties": {
+            "column": { "type": "string" },
+            "op": { "enum": ["eq", "ne", "lt", "le", "gt", "ge", "contains"] },
+            "value": { "description": "Number, string, boolean, or null for empty cells" }
+        },
+        "required": ["column", "op", "value"]
+    });
+    let aggregation = json!({
+        "type": "object",
+        "properties": {
+            "function": { "enum": ["count", "sum", "mean", "min", "max", "distinct"] },
+            "column": { "type": "string", "description": "Omit to count rows" }
+        },
+        "required": ["function"]
+    });
+    let columns = json!({ "type": "array", "items": { "type": "string" } });
+    let query = json!({
+        "type": "object",
+        "properties": {
+            "attachmentId": attachment_id.clone(),
+            "sheet": { "type": "string", "description": "Defaults to the first sheet" },
+            "filters": { "type": "array", "items": filter },
+            "operation": {
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "properties": { "op": { "const": "rows" }, "columns": columns.clone() },
+                        "required": ["op"]
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "op": { "const": "groupBy" },
+                            "by": columns.clone(),
+                            "aggregations": { "type": "array", "items": aggregation }
+                        },
+                        "required": ["op", "by"]
+                    },
+                    {
+                        "type": "object",
+                        "properties": { "op": { "const": "describe" }, "columns": columns },
+                        "required": ["op"]
+                    }
+                ]
+            },
+            "sortBy": { "type": "string", "description": "A result column" },
+            "descending": { "type": "boolean" },
+            "limit": { "type": "integer", "minimum": 1, "maximum": MAX_LIMIT }
+        },
+        "required": ["attachmentId", "operation"]
+    });
+    let mut tools = vec![
+        PluginTool {
+            name: "describe_data".to_string(),
+            description: "Lists the sheets of a spreadsheet attachment with their columns, \
+                          column types, row counts, and sample rows."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "attachmentId": attachment_id },
+                "required": ["attachmentId"]
+            })
+            .to_string(),
+        },
+        PluginTool {
+            name: "query_data".to_string(),
+            description: "Runs a query over one sheet of a spreadsheet attachment: filtered rows, \
+                          group-by aggregations, or summary statistics per column."
+                .to_string(),
+            input_schema: query.to_string(),
+        },
+    ];
+    tools.extend(attached_db::tools());
+    tools.push(transform::tool());
+    tools.extend(calculator::tools());
+    tools.push(datetime::tool());
+    tools.push(weather::tool());
+    tools
+}
+
+/// Describes the sheets of a spreadsheet attachment for the model.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment doesn't
+/// exist, `NOT_SPREADSHEET` if it isn't a spreadsheet, or `INVALID_CONTENT`
+/// if it cannot be parsed.
+#[tauri::command]
+#[specta::specta]
+pub async fn describe_data_attachment(
+    app: AppHandle,
+    id: String,
+) -> Result<DataDescription, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<_, GibberError> {
+        let (attachment, sheets) = load(&app, &id)?;
+        Ok(DataDescription {
+            attachment_id: attachment.id,
+            file_name: attachment.file_name,
+            sheets: sheets.iter().map(describe_sheet).collect(),
+        })
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+/// Runs a query over a spreadsheet attachment.
+///
+/// Filters compare numbers numerically and text ignoring case.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment or
+/// sheet doesn't exist, `NOT_SPREADSHEET` if it isn't a spreadsheet,
+/// `INVALID_CONTENT` if it cannot be parsed, or `INVALID_INPUT` if the
+/// query names an unknown column.
+///
+/// # Example
+///
+/// ```typescript
+/// const revenue = await invoke("query_data_attachment", {
+///   id,
+///   query: {
+///     filters: [{ column: "year", op: "eq", value: 2026 }],
+///     operation: {
+///       op: "groupBy",
+///       by: ["region"],
+///       aggregations: [{ function: "sum", column: "revenue" }],
+///     },
+///     sortBy: "sum(revenue)",
+///     descending: true,
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn query_data_attachment(
+    app: AppHandle,
+    id: String,
+    query: DataQuery,
+) -> Result<DataResult, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<_, GibberError> {
+        let (_, sheets) = load(&app, &id)?;
+        run_query(&sheets, &query)
+    })
+    .await
+    .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALES: &str = "region,product,units,price\n\
+                         north,synth,3,499.5\n\
+                         south,drum machine,1,299\n\
+                         north,drum machine,2,299\n\
+                         ,,,\n\
+                         west,sampler,,899\n";
+
+    fn query(operation: DataOperation) -> DataQuery {
+        DataQuery {
+            sheet: None,
+            filters: Vec::new(),
+            operation,
+            sort_by: None,
+            descending: false,
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn test_csv_schema() {
+        let sheets = read_sheets("sales.csv", SALES.as_bytes()).unwrap();
+        let sheet = describe_sheet(&sheets[0]);
+        assert_eq!(sheet.name, "sales");
+        assert_eq!(sheet.rows, 4);
+        let units = &sheet.columns[2];
+        assert_eq!(units.column_type, ColumnType::Number);
+        assert_eq!((units.values, units.distinct), (3, 3));
+        assert_eq!(sheet.columns[1].column_type, ColumnType::Text);
+        assert_eq!(
+            sheet.sample[0],
+            vec![json!("north"), json!("synth"), json!(3.0), json!(499.5)]
+        );
+
+        let renamed = Sheet::new(
+            "s".to_string(),
+            vec![vec![Cell::parse("a"), Cell::parse("a")]],
+        );
+        assert_eq!(renamed.columns, vec!["a", "a (2)"]);
+    }
+
+    #[test]
+    fn test_queries() {
+        let sheets = read_sheets("sales.csv", SALES.as_bytes()).unwrap();
+
+        let mut grouped = query(DataOperation::GroupBy {
+            by: vec!["region".to_string()],
+            aggregations: vec![
+                Aggregation {
+                    function: AggregateFunction::Sum,
+                    column: Some("units".to_string()),
+                },
+                Aggregation {
+                    function: AggregateFunction::Count,
+                    column: None,
+                },
+            ],
+        });
+        grouped.sort_by = Some("sum(units)".to_string());
+        grouped.descending = true;
+        let result = run_query(&sheets, &grouped).unwrap();
+        assert_eq!(result.columns, vec!["region", "sum(units)", "count"]);
+        assert_eq!(result.rows[0], vec![json!("north"), json!(5.0), json!(2)]);
+        assert_eq!(result.rows[2], vec![json!("west"), json!(0.0), json!(1)]);
+
+        let mut filtered = query(DataOperation::Rows {
+            columns: vec!["product".to_string()],
+        });
+        filtered.filters = vec![DataFilter {
+            column: "Price".to_string(),
+            op: FilterOp::Lt,
+            value: json!(300),
+        }];
+        filtered.limit = Some(1);
+        let result = run_query(&sheets, &filtered).unwrap();
+        assert_eq!(result.rows, vec![vec![json!("drum machine")]]);
+        assert_eq!((result.total_rows, result.truncated), (2, true));
+
+        let described = run_query(
+            &sheets,
+            &query(DataOperation::Describe {
+                columns: vec!["price".to_string()],
+            }),
+        )
+        .unwrap();
+        assert_eq!(described.rows[0][3], json!(499.125));
+        assert_eq!(described.rows[0][6], json!(399.25));
+
+        let unknown = query(DataOperation::Rows {
+            columns: vec!["colour".to_string()],
+        });
+        assert_eq!(
+            run_query(&sheets, &unknown).unwrap_err().code(),
+            "INVALID_INPUT"
+        );
+    }
+}
@@ -0,0 +1,761 @@
+//! Locally running Stable Diffusion servers.
+//!
+//! The `local` image provider renders on a server on the user's machine or
+//! network, so image generation works fully offline. Two APIs are
+//! supported:
+//!
+//! - AUTOMATIC1111 (and forks like Forge) started with `--api`, default
+//!   `http://127.0.0.1:7860`
+//! - ComfyUI, default `http://127.0.0.1:8188`, driven with a built-in
+//!   text-to-image workflow
+//!
+//! While a render runs, the server's queue position and progress are
+//! reported through the image progress event. One local render runs at a
+//! time; `cancel_local_image` interrupts it on the server.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use base64::Engine;
+use futures_util::future::{self, Either};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Url};
+
+use crate::commands::images::ImageParams;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the [`StableDiffusionSettings`] document.
+pub const SETTINGS_KEY: &str = "stableDiffusion";
+
+/// Address AUTOMATIC1111 listens on by default.
+pub const DEFAULT_AUTOMATIC1111_URL: &str = "http://127.0.0.1:7860";
+
+/// How often queue position and progress are checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Local renders on modest GPUs can take several minutes.
+const RENDER_TIMEOUT: Duration = Duration::from_mins(15);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const DEFAULT_SIZE: (u32, u32) = (512, 512);
+const DEFAULT_STEPS: u32 = 25;
+const DEFAULT_SAMPLER: &str = "euler";
+
+fn render_cancelled() -> GibberError {
+    GibberError::new("CANCELLED", "The render was cancelled")
+}
+
+/// Server API flavour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum LocalBackend {
+    /// AUTOMATIC1111 web UI API
+    Automatic1111,
+    /// ComfyUI API
+    #[serde(rename = "comfyui")]
+    ComfyUi,
+}
+
+/// Local server preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StableDiffusionSettings {
+    /// API flavour of the server
+    pub backend: LocalBackend,
+    /// Base URL of the server
+    pub url: String,
+}
+
+impl Default for StableDiffusionSettings {
+    fn default() -> Self {
+        Self {
+            backend: LocalBackend::Automatic1111,
+            url: DEFAULT_AUTOMATIC1111_URL.to_string(),
+        }
+    }
+}
+
+impl StableDiffusionSettings {
+    pub(crate) fn validate(&self) -> Result<(), GibberError> {
+        match Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+            _ => Err(GibberError::new(
+                "INVALID_SETTINGS",
+                format!("Not an http(s) URL: {}", self.url),
+            )),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}{path}", self.url.trim_end_matches('/'))
+    }
+}
+
+pub(crate) fn load_settings(conn: &rusqlite::Connection) -> StableDiffusionSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Models and samplers the server offers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModels {
+    /// Checkpoint names, usable as the image `model`
+    pub models: Vec<String>,
+    /// Sampler names, usable as the image `sampler`
+    pub samplers: Vec<String>,
+}
+
+/// Where a render is, as reported by the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalProgress {
+    /// Jobs ahead in the queue; 0 once rendering
+    pub queue_position: Option<u32>,
+    /// Rendered fraction from 0 to 1, when known
+    pub fraction: Option<f64>,
+}
+
+struct ActiveRender {
+    cancelled: Arc<AtomicBool>,
+    prompt_id: Option<String>,
+}
+
+/// Managed handle of the running local render.
+#[derive(Default)]
+pub struct LocalRender(Mutex<Option<ActiveRender>>);
+
+impl LocalRender {
+    fn active(&self) -> MutexGuard<'_, Option<ActiveRender>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Clears the running render when the generation ends.
+struct RenderGuard<'a>(&'a LocalRender);
+
+impl Drop for RenderGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.active() = None;
+    }
+}
+
+fn dimensions(params: &ImageParams) -> (u32, u32) {
+    params
+        .size
+        .as_deref()
+        .and_then(|size| size.split_once(['x', 'X']))
+        .and_then(|(width, height)| Some((width.trim().parse().ok()?, height.trim().parse().ok()?)))
+        .unwrap_or(DEFAULT_SIZE)
+}
+
+fn seed(params: &ImageParams) -> u64 {
+    params
+        .seed
+        .unwrap_or_else(|| u64::try_from(db::now_millis()).unwrap_or_default() % 4_294_967_295)
+}
+
+/// Request body of an AUTOMATIC1111 `txt2img` call.
+fn automatic1111_body(params: &ImageParams) -> serde_json::Value {
+    let (width, height) = dimensions(params);
+    let mut body = serde_json::json!({
+        "prompt": params.prompt,
+        "negative_prompt": params.negative_prompt.as_deref().unwrap_or_default(),
+        "width": width,
+        "height": height,
+        "steps": params.steps.unwrap_or(DEFAULT_STEPS),
+        "batch_size": params.count.unwrap_or(1),
+        "seed": params.seed.map_or(-1, |seed| i64::try_from(seed).unwrap_or(-1)),
+        "send_images": true,
+        "save_images": false,
+    });
+    if let Some(sampler) = params.sampler.as_deref().filter(|s| !s.is_empty()) {
+        body["sampler_name"] = serde_json::json!(sampler);
+    }
+    if let Some(model) = params.model.as_deref().filter(|m| !m.is_empty()) {
+        body["override_settings"] = serde_json::json!({ "sd_model_checkpoint": model });
+    }
+    body
+}
+
+/// Text-to-image workflow in ComfyUI's API format.
+fn comfyui_workflow(params: &ImageParams, checkpoint: &str) -> serde_json::Value {
+    let (width, height) = dimensions(params);
+    serde_json::json!({
+        "checkpoint": {
+            "class_type": "CheckpointLoaderSimple",
+            "inputs": { "ckpt_name": checkpoint }
+        },
+        "latent": {
+            "class_type": "EmptyLatentImage",
+            "inputs": { "width": width, "height": height, "batch_size": params.count.unwrap_or(1) }
+        },
+        "positive": {
+            "class_type": "CLIPTextEncode",
+            "inputs": { "text": params.prompt, "clip": ["checkpoint", 1] }
+        },
+        "negative": {
+            "class_type": "CLIPTextEncode",
+            "inputs": {
+                "text": params.negative_prompt.as_deref().unwrap_or_default(),
+                "clip": ["checkpoint", 1]
+            }
+        },
+        "sampler": {
+            "class_type": "KSampler",
+            "inputs": {
+                "seed": seed(params),
+                "steps": params.steps.unwrap_or(DEFAULT_STEPS),
+                "cfg": 7.0,
+                "sampler_name": params.sampler.as_deref().filter(|s| !s.is_empty()).unwrap_or(DEFAULT_SAMPLER),
+                "scheduler": "normal",
+                "denoise": 1.0,
+                "model": ["checkpoint", 0],
+                "positive": ["positive", 0],
+                "negative": ["negative", 0],
+                "latent_image": ["latent", 0]
+            }
+        },
+        "decode": {
+            "class_type": "VAEDecode",
+            "inputs": { "samples": ["sampler", 0], "vae": ["checkpoint", 2] }
+        },
+        "save": {
+            "class_type": "SaveImage",
+            "inputs": { "filename_prefix": "gibber", "images": ["decode", 0] }
+        }
+    })
+}
+
+/// Position of a prompt in a ComfyUI `/queue` response: 0 while running,
+/// 1 for the next pending prompt, and so on.
+fn comfyui_queue_position(queue: &serde_json::Value, prompt_id: &str) -> Option<u32> {
+    let is_prompt = |entry: &&serde_json::Value| {
+        entry.get(1).and_then(serde_json::Value::as_str) == Some(prompt_id)
+    };
+    let entries = |name: &str| {
+        queue
+            .get(name)
+            .and_then(serde_json::Value::as_array)
+            .cloned()
+            .unwrap_or_default()
+    };
+    if entries("queue_running")
+        .iter()
+        .any(|entry| is_prompt(&entry))
+    {
+        return Some(0);
+    }
+    let mut pending = entries("queue_pending");
+    pending.sort_by_key(|entry| entry.get(0).and_then(serde_json::Value::as_i64));
+    pending
+        .iter()
+        .position(|entry| is_prompt(&entry))
+        .and_then(|index| u32::try_from(index + 1).ok())
+}
+
+/// Image files listed in a ComfyUI `/history/<id>` entry.
+fn comfyui_outputs(entry: &serde_json::Value) -> Vec<(String, String, String)> {
+    let Some(outputs) = entry.get("outputs").and_then(serde_json::Value::as_object) else {
+        return Vec::new();
+    };
+    outputs
+        .values()
+        .filter_map(|output| output.get("images")?.as_array())
+        .flatten()
+        .filter_map(|image| {
+            let field = |name| Some(image.get(name)?.as_str()?.to_string());
+            Some((
+                field("filename")?,
+                field("subfolder").unwrap_or_default(),
+                field("type")?,
+            ))
+        })
+        .collect()
+}
+
+async fn get_json(
+    app: &AppHandle,
+    client: &Client,
+    url: String,
+) -> Result<serde_json::Value, GibberError> {
+    let builder = client.get(url).timeout(REQUEST_TIMEOUT);
+    let response = network_activity::send(
+        app,
+        Service::StableDiffusion,
+        "stable-diffusion.status",
+        builder,
+    )
+    .await?;
+    if !response.status().is_success() {
+        return Err(GibberError::new(
+            "PROVIDER_ERROR",
+            format!("Server returned HTTP {}", response.status()),
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+async fn fail_message(response: reqwest::Response) -> GibberError {
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = ["detail", "error", "message"]
+        .iter()
+        .find_map(|field| body.get(field)?.as_str().map(str::to_string))
+        .or_else(|| body.pointer("/error/message")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| status.to_string());
+    GibberError::new("PROVIDER_ERROR", message)
+}
+
+/// Runs `render`, reporting through `poll` every [`POLL_INTERVAL`] until it
+/// finishes.
+async fn with_polling<T, R, P, F>(render: R, mut poll: P) -> T
+where
+    R: Future<Output = T>,
+    P: FnMut() -> F,
+    F: Future<Output = ()>,
+{
+    let poller = async {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            poll().await;
+        }
+    };
+    let render = std::pin::pin!(render);
+    let poller = std::pin::pin!(poller);
+    match future::select(render, poller).await {
+        Either::Left((result, _)) => result,
+        Either::Right(((), render)) => render.await,
+    }
+}
+
+async fn automatic1111(
+    app: &AppHandle,
+    client: &Client,
+    settings: &StableDiffusionSettings,
+    params: &ImageParams,
+    cancelled: &AtomicBool,
+    report: &(dyn Fn(LocalProgress) + Sync),
+) -> Result<Vec<Vec<u8>>, GibberError> {
+    let builder = client
+        .post(settings.endpoint("/sdapi/v1/txt2img"))
+        .timeout(RENDER_TIMEOUT)
+        .json(&automatic1111_body(params));
+    let render = network_activity::send(
+        app,
+        Service::StableDiffusion,
+        "stable-diffusion.render",
+        builder,
+    );
+    let progress_url = &settings.endpoint("/sdapi/v1/progress?skip_current_image=true");
+    let response = with_polling(render, move || async move {
+        if let Ok(progress) = get_json(app, client, progress_url.clone()).await {
+            report(LocalProgress {
+                queue_position: Some(0),
+                fraction: progress.get("progress").and_then(serde_json::Value::as_f64),
+            });
+        }
+    })
+    .await?;
+    if !response.status().is_success() {
+        return Err(fail_message(response).await);
+    }
+    let body: serde_json::Value = response.json().await?;
+    // An interrupted render still returns its partial images.
+    if cancelled.load(Ordering::Relaxed) {
+        return Err(render_cancelled());
+    }
+    body.get("images")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(serde_json::Value::as_str)
+        .map(|image| {
+            base64::engine::general_purpose::STANDARD
+                .decode(image)
+                .map_err(|e| GibberError::new("PARSE_ERROR", format!("Invalid image data: {e}")))
+        })
+        .collect()
+}
+
+async fn comfyui(
+    app: &AppHandle,
+    client: &Client,
+    settings: &StableDiffusionSettings,
+    params: &ImageParams,
+    cancelled: &AtomicBool,
+    report: &(dyn Fn(LocalProgress) + Sync),
+) -> Result<Vec<Vec<u8>>, GibberError> {
+    let checkpoint = match params.model.as_deref().filter(|m| !m.is_empty()) {
+        Some(model) => model.to_string(),
+        None => list_models(app, client, settings)
+            .await?
+            .models
+            .into_iter()
+            .next()
+            .ok_or_else(|| GibberError::new("NO_MODEL", "ComfyUI has no checkpoints installed"))?,
+    };
+    let body = serde_json::json!({
+        "prompt": comfyui_workflow(params, &checkpoint),
+        "client_id": "gibber-ai",
+    });
+    let builder = client
+        .post(settings.endpoint("/prompt"))
+        .timeout(REQUEST_TIMEOUT)
+        .json(&body);
+    let response = network_activity::send(
+        app,
+        Service::StableDiffusion,
+        "stable-diffusion.render",
+        builder,
+    )
+    .await?;
+    if !response.status().is_success() {
+        return Err(fail_message(response).await);
+    }
+    let queued: serde_json::Value = response.json().await?;
+    let prompt_id = queued
+        .get("prompt_id")
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| GibberError::new("PARSE_ERROR", "ComfyUI returned no prompt ID"))?
+        .to_string();
+    if let Some(render) = app.state::<LocalRender>().active().as_mut() {
+        render.prompt_id = Some(prompt_id.clone());
+    }
+
+    let deadline = tokio::time::Instant::now() + RENDER_TIMEOUT;
+    let outputs = loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(render_cancelled());
+        }
+        if tokio::time::Instant::now() > deadline {
+            return Err(GibberError::new("TIMEOUT", "The render took too long"));
+        }
+        let history = get_json(
+            app,
+            client,
+            settings.endpoint(&format!("/history/{prompt_id}")),
+        )
+        .await?;
+        if let Some(entry) = history.get(&prompt_id) {
+            if entry
+                .pointer("/status/status_str")
+                .and_then(serde_json::Value::as_str)
+                == Some("error")
+            {
+                return Err(GibberError::new(
+                    "PROVIDER_ERROR",
+                    "ComfyUI failed to render the workflow",
+                ));
+            }
+            break comfyui_outputs(entry);
+        }
+        if let Ok(queue) = get_json(app, client, settings.endpoint("/queue")).await {
+            report(LocalProgress {
+                queue_position: comfyui_queue_position(&queue, &prompt_id),
+                fraction: None,
+            });
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+
+    let mut images = Vec::new();
+    for (filename, subfolder, kind) in outputs {
+        let builder = client
+            .get(settings.endpoint("/view"))
+            .query(&[
+                ("filename", filename),
+                ("subfolder", subfolder),
+                ("type", kind),
+            ])
+            .timeout(REQUEST_TIMEOUT);
+        let response = network_activity::send(
+            app,
+            Service::StableDiffusion,
+            "stable-diffusion.download",
+            builder,
+        )
+        .await?;
+        if !response.status().is_success() {
+            return Err(fail_message(response).await);
+        }
+        images.push(response.bytes().await?.to_vec());
+    }
+    Ok(images)
+}
+
+/// Renders `params` on the configured local server.
+///
+/// `report` receives the server's queue position and progress while the
+/// render runs.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `BUSY` if a local render is
+/// already running, `UNAVAILABLE` if the server isn't running, `CANCELLED`
+/// if the render was cancelled, or `PROVIDER_ERROR` if the server fails.
+pub(crate) async fn generate(
+    app: &AppHandle,
+    params: &ImageParams,
+    report: &(dyn Fn(LocalProgress) + Sync),
+) -> Result<Vec<Vec<u8>>, GibberError> {
+    let settings = load_settings(&app.state::<Database>().conn());
+    let client = network::client(app, Service::StableDiffusion)
+        .map_err(|e| GibberError::new("NETWORK", e.to_string()))?;
+    let state = app.state::<LocalRender>();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut active = state.active();
+        if active.is_some() {
+            return Err(GibberError::new(
+                "BUSY",
+                "A local render is already running",
+            ));
+        }
+        *active = Some(ActiveRender {
+            cancelled: Arc::clone(&cancelled),
+            prompt_id: None,
+        });
+    }
+    let _guard = RenderGuard(&state);
+    match settings.backend {
+        LocalBackend::Automatic1111 => {
+            automatic1111(app, &client, &settings, params, &cancelled, report).await
+        }
+        LocalBackend::ComfyUi => comfyui(app, &client, &settings, params, &cancelled, report).await,
+    }
+}
+
+async fn list_models(
+    app: &AppHandle,
+    client: &Client,
+    settings: &StableDiffusionSettings,
+) -> Result<LocalModels, GibberError> {
+    let names = |value: serde_json::Value, field: &str| -> Vec<String> {
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.get(field)?.as_str().map(str::to_string))
+            .collect()
+    };
+    let options = |value: &serde_json::Value, pointer: &str| -> Vec<String> {
+        value
+            .pointer(pointer)
+            .and_then(serde_json::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect()
+    };
+    match settings.backend {
+        LocalBackend::Automatic1111 => Ok(LocalModels {
+            models: names(
+                get_json(app, client, settings.endpoint("/sdapi/v1/sd-models")).await?,
+                "title",
+            ),
+            samplers: names(
+                get_json(app, client, settings.endpoint("/sdapi/v1/samplers")).await?,
+                "name",
+            ),
+        }),
+        LocalBackend::ComfyUi => {
+            let checkpoints = get_json(
+                app,
+                client,
+                settings.endpoint("/object_info/CheckpointLoaderSimple"),
+            )
+            .await?;
+            let sampler = get_json(app, client, settings.endpoint("/object_info/KSampler")).await?;
+            Ok(LocalModels {
+                models: options(
+                    &checkpoints,
+                    "/CheckpointLoaderSimple/input/required/ckpt_name/0",
+                ),
+                samplers: options(&sampler, "/KSampler/input/required/sampler_name/0"),
+            })
+        }
+    }
+}
+
+/// Returns the local server preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_stable_diffusion_settings(db: State<'_, Database>) -> StableDiffusionSettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the local server preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_SETTINGS` if the URL
+/// isn't http(s), or `DATABASE` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_stable_diffusion_settings", {
+///   settings: { backend: "comfyui", url: "http://127.0.0.1:8188" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_stable_diffusion_settings(
+    db: State<'_, Database>,
+    settings: StableDiffusionSettings,
+) -> Result<StableDiffusionSettings, GibberError> {
+    settings.validate()?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&db.conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Lists the checkpoints and samplers of the local server.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNAVAILABLE` if the server
+/// isn't running, or `PROVIDER_ERROR` if it refuses.
+///
+/// # Example
+///
+/// ```typescript
+/// const { models, samplers } = await invoke("list_stable_diffusion_models");
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn list_stable_diffusion_models(app: AppHandle) -> Result<LocalModels, GibberError> {
+    let settings = load_settings(&app.state::<Database>().conn());
+    let client = network::client(&app, Service::StableDiffusion)
+        .map_err(|e| GibberError::new("NETWORK", e.to_string()))?;
+    list_models(&app, &client, &settings).await
+}
+
+/// Cancels the running local render.
+///
+/// # Returns
+///
+/// `true` if a render was running.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `UNAVAILABLE` if the server
+/// can't be reached to interrupt the render.
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_local_image(app: AppHandle) -> Result<bool, GibberError> {
+    let prompt_id = {
+        let state = app.state::<LocalRender>();
+        let active = state.active();
+        let Some(render) = active.as_ref() else {
+            return Ok(false);
+        };
+        render.cancelled.store(true, Ordering::Relaxed);
+        render.prompt_id.clone()
+    };
+    let settings = load_settings(&app.state::<Database>().conn());
+    let client = network::client(&app, Service::StableDiffusion)
+        .map_err(|e| GibberError::new("NETWORK", e.to_string()))?;
+    let builders = match settings.backend {
+        LocalBackend::Automatic1111 => vec![client.post(settings.endpoint("/sdapi/v1/interrupt"))],
+        // Drop the prompt if it's still queued, and stop it if it's running.
+        LocalBackend::ComfyUi => vec![
+            client
+                .post(settings.endpoint("/queue"))
+                .json(&serde_json::json!({ "delete": prompt_id.into_iter().collect::<Vec<_>>() })),
+            client.post(settings.endpoint("/interrupt")),
+        ],
+    };
+    for builder in builders {
+        network_activity::send(
+            &app,
+            Service::StableDiffusion,
+            "stable-diffusion.cancel",
+            builder.timeout(REQUEST_TIMEOUT),
+        )
+        .await?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::images::ImageProvider;
+
+    fn params() -> ImageParams {
+        ImageParams {
+            prompt: "A tape loop".to_string(),
+            provider: ImageProvider::Local,
+            model: Some("sdxl.safetensors".to_string()),
+            size: Some("768x512".to_string()),
+            count: Some(2),
+            negative_prompt: None,
+            seed: Some(42),
+            steps: None,
+            sampler: Some("DPM++ 2M".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_automatic1111_body() {
+        let body = automatic1111_body(&params());
+        assert_eq!(body["width"], 768);
+        assert_eq!(body["height"], 512);
+        assert_eq!(body["batch_size"], 2);
+        assert_eq!(body["seed"], 42);
+        assert_eq!(body["sampler_name"], "DPM++ 2M");
+        assert_eq!(
+            body["override_settings"]["sd_model_checkpoint"],
+            "sdxl.safetensors"
+        );
+    }
+
+    #[test]
+    fn test_comfyui_workflow_links_nodes() {
+        let workflow = comfyui_workflow(&params(), "sdxl.safetensors");
+        assert_eq!(
+            workflow["checkpoint"]["inputs"]["ckpt_name"],
+            "sdxl.safetensors"
+        );
+        assert_eq!(workflow["sampler"]["inputs"]["seed"], 42);
+        assert_eq!(workflow["sampler"]["inputs"]["latent_image"][0], "latent");
+        assert_eq!(workflow["save"]["inputs"]["images"][0], "decode");
+    }
+
+    #[test]
+    fn test_comfyui_queue_and_outputs() {
+        let queue = serde_json::json!({
+            "queue_running": [[3, "running", {}, {}, []]],
+            "queue_pending": [[5, "later", {}, {}, []], [4, "next", {}, {}, []]],
+        });
+        assert_eq!(comfyui_queue_position(&queue, "running"), Some(0));
+        assert_eq!(comfyui_queue_position(&queue, "next"), Some(1));
+        assert_eq!(comfyui_queue_position(&queue, "later"), Some(2));
+        assert_eq!(comfyui_queue_position(&queue, "gone"), None);
+
+        let entry = serde_json::json!({
+            "outputs": { "save": { "images": [{ "filename": "gibber_0001.png", "subfolder": "", "type": "output" }] } }
+        });
+        assert_eq!(
+            comfyui_outputs(&entry),
+            vec![(
+                "gibber_0001.png".to_string(),
+                String::new(),
+                "output".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Startup sequencing.
+//!
+//! Everything in the setup hook delays the first window, so setup only
+//! opens the database, applies settings, and registers managed state. Work
+//! that touches the disk or builds runtimes — pruning the attachment store,
+//! creating the plugin engine, resuming webhook deliveries — is deferred to
+//! the blocking thread pool with [`defer`], and each subsystem emits
+//! [`events::APP_READY`] when it is done. A webview that loads after some of
+//! them were emitted can catch up with `get_startup_status`.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::events;
+
+/// A part of the app that initializes on its own schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum Subsystem {
+    /// The app database, opened and migrated during setup
+    Database,
+    /// Orphan pruning and files the app was launched with
+    Attachments,
+    /// The WebAssembly engine running plugins
+    Plugins,
+    /// Webhook deliveries left over from the previous run
+    Webhooks,
+}
+
+impl Subsystem {
+    /// Every subsystem, in the order they usually become ready.
+    pub const ALL: [Self; 4] = [
+        Self::Database,
+        Self::Attachments,
+        Self::Plugins,
+        Self::Webhooks,
+    ];
+}
+
+/// Payload of [`events::APP_READY`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadyPayload {
+    /// The subsystem that is ready
+    pub subsystem: Subsystem,
+    /// Milliseconds from launch until it was ready
+    pub elapsed_ms: u64,
+}
+
+/// What `get_startup_status` returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStatus {
+    /// Subsystems that are ready, in the order they became ready
+    pub ready: Vec<ReadyPayload>,
+    /// Subsystems still initializing
+    pub pending: Vec<Subsystem>,
+}
+
+/// Managed record of which subsystems are ready.
+pub struct Startup {
+    launched: Instant,
+    ready: Mutex<Vec<ReadyPayload>>,
+}
+
+impl Startup {
+    /// Creates the record for an app launched at `launched`.
+    #[must_use]
+    pub const fn new(launched: Instant) -> Self {
+        Self {
+            launched,
+            ready: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<ReadyPayload>> {
+        self.ready.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records `subsystem` as ready at `now`. Returns `None` if it already
+    /// was.
+    fn record(&self, subsystem: Subsystem, now: Instant) -> Option<ReadyPayload> {
+        let mut ready = self.lock();
+        if ready.iter().any(|entry| entry.subsystem == subsystem) {
+            return None;
+        }
+        let elapsed = now.duration_since(self.launched).as_millis();
+        let payload = ReadyPayload {
+            subsystem,
+            elapsed_ms: u64::try_from(elapsed).unwrap_or(u64::MAX),
+        };
+        ready.push(payload);
+        Some(payload)
+    }
+
+    fn status(&self) -> StartupStatus {
+        let ready = self.lock().clone();
+        let pending = Subsystem::ALL
+            .into_iter()
+            .filter(|subsystem| !ready.iter().any(|entry| entry.subsystem == *subsystem))
+            .collect();
+        StartupStatus { ready, pending }
+    }
+}
+
+/// Records `subsystem` as ready and emits [`events::APP_READY`].
+///
+/// Must be called after [`Startup`] has been added to managed state.
+pub fn mark_ready(app: &AppHandle, subsystem: Subsystem) {
+    let Some(payload) = app.state::<Startup>().record(subsystem, Instant::now()) else {
+        return;
+    };
+    tracing::info!("{subsystem:?} ready {} ms after launch", payload.elapsed_ms);
+    events::emit_typed(app, events::APP_READY, &payload);
+}
+
+/// Runs `task` on the blocking thread pool, then marks `subsystem` ready.
+pub fn defer(app: &AppHandle, subsystem: Subsystem, task: fn(&AppHandle)) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        task(&app);
+        mark_ready(&app, subsystem);
+    });
+}
+
+/// Returns which subsystems are ready and which are still initializing.
+///
+/// # Example
+///
+/// ```typescript
+/// const unlisten = await listen("app://ready", ({ payload }) => markReady(payload));
+/// const { ready } = await invoke("get_startup_status");
+/// ready.forEach(markReady);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_startup_status(startup: State<'_, Startup>) -> StartupStatus {
+    startup.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_subsystems_are_ready_once() {
+        let launched = Instant::now();
+        let startup = Startup::new(launched);
+        let later = launched + Duration::from_millis(120);
+        let payload = startup.record(Subsystem::Plugins, later).unwrap();
+        assert_eq!(payload.elapsed_ms, 120);
+        assert!(startup
+            .record(Subsystem::Plugins, later + Duration::from_secs(1))
+            .is_none());
+    }
+
+    #[test]
+    fn test_status_lists_pending_subsystems() {
+        let launched = Instant::now();
+        let startup = Startup::new(launched);
+        startup.record(Subsystem::Database, launched).unwrap();
+        startup.record(Subsystem::Webhooks, launched).unwrap();
+        let status = startup.status();
+        assert_eq!(
+            status
+                .ready
+                .iter()
+                .map(|entry| entry.subsystem)
+                .collect::<Vec<_>>(),
+            vec![Subsystem::Database, Subsystem::Webhooks]
+        );
+        assert_eq!(
+            status.pending,
+            vec![Subsystem::Attachments, Subsystem::Plugins]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Recovery of generations cut off by a quit, crash, or error.
+//!
+//! While `stream_chat` runs, the request and the text generated so far are
+//! kept in the `stream_drafts` table, saved at most every
+//! [`SAVE_INTERVAL`]. A stream that completes or is cancelled deletes its
+//! draft, so drafts found later belong to streams that failed or that the
+//! app never finished. The user can resume one with `resume_chat_stream`,
+//! which asks the model to continue from the partial text, or discard it.
+
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::chat_stream::{StreamAcks, StreamChatRequest};
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Longest time generated text goes unsaved.
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A stream that was cut off.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamDraft {
+    /// Stream identifier chosen by the webview
+    pub stream_id: String,
+    /// Conversation the reply belongs to
+    pub conversation_id: Option<String>,
+    /// The request as sent
+    pub request: StreamChatRequest,
+    /// Text generated before the stream was cut off
+    pub partial: String,
+    /// Start time in Unix milliseconds
+    pub started_at: i64,
+    /// Last save in Unix milliseconds
+    pub updated_at: i64,
+}
+
+fn draft_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Option<StreamDraft>> {
+    let request: String = row.get(2)?;
+    let Ok(request) = serde_json::from_str(&request) else {
+        return Ok(None);
+    };
+    Ok(Some(StreamDraft {
+        stream_id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        request,
+        partial: row.get(3)?,
+        started_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    }))
+}
+
+/// Records a stream that is starting, with the text it continues from.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn begin(
+    conn: &Connection,
+    stream_id: &str,
+    request: &StreamChatRequest,
+    partial: &str,
+) -> rusqlite::Result<()> {
+    let now = db::now_millis();
+    conn.execute(
+        "INSERT OR REPLACE INTO stream_drafts
+         (stream_id, conversation_id, request, partial, started_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+        params![
+            stream_id,
+            request.conversation_id,
+            serde_json::to_string(request).unwrap_or_default(),
+            partial,
+            now,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Appends generated text to a stream's draft.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn append(conn: &Connection, stream_id: &str, text: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE stream_drafts SET partial = partial || ?2, updated_at = ?3 WHERE stream_id = ?1",
+        params![stream_id, text, db::now_millis()],
+    )?;
+    Ok(())
+}
+
+/// Deletes a stream's draft.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn finish(conn: &Connection, stream_id: &str) -> rusqlite::Result<bool> {
+    Ok(conn.execute(
+        "DELETE FROM stream_drafts WHERE stream_id = ?1",
+        [stream_id],
+    )? > 0)
+}
+
+/// Loads a stream's draft.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn load(conn: &Connection, stream_id: &str) -> rusqlite::Result<Option<StreamDraft>> {
+    Ok(conn
+        .query_row(
+            "SELECT stream_id, conversation_id, request, partial, started_at, updated_at
+             FROM stream_drafts WHERE stream_id = ?1",
+            [stream_id],
+            draft_from_row,
+        )
+        .optional()?
+        .flatten())
+}
+
+fn list(conn: &Connection) -> rusqlite::Result<Vec<StreamDraft>> {
+    let mut stmt = conn.prepare(
+        "SELECT stream_id, conversation_id, request, partial, started_at, updated_at
+         FROM stream_drafts ORDER BY started_at DESC",
+    )?;
+    let drafts = stmt
+        .query_map([], draft_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(drafts.into_iter().flatten().collect())
+}
+
+/// Lists streams that were cut off, newest first.
+///
+/// Streams still running are left out.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `DATABASE` if the drafts cannot be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const drafts = await invoke("list_interrupted_streams");
+/// drafts.forEach((d) => showResumable(d.conversationId, d.partial));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn list_interrupted_streams(app: AppHandle) -> Result<Vec<StreamDraft>, GibberError> {
+    let mut drafts = list(&app.state::<Database>().conn())?;
+    let live = app.state::<StreamAcks>();
+    drafts.retain(|draft| !live.contains(&draft.stream_id));
+    Ok(drafts)
+}
+
+/// Discards the draft of a stream that was cut off.
+///
+/// # Returns
+///
+/// Returns `true` if there was a draft.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `DATABASE` if the draft cannot be
+/// deleted.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn discard_interrupted_stream(
+    db: State<'_, Database>,
+    stream_id: &str,
+) -> Result<bool, GibberError> {
+    Ok(finish(&db.conn(), stream_id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ChatMessage, MessageRole};
+
+    fn request() -> StreamChatRequest {
+        StreamChatRequest {
+            model: None,
+            messages: vec![ChatMessage::new(MessageRole::User, "Write a verse")],
+            max_tokens: None,
+            temperature: None,
+            conversation_id: Some("c".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_drafts_collect_partial_text() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        begin(&conn, "s", &request(), "").unwrap();
+        append(&conn, "s", "Under the ").unwrap();
+        append(&conn, "s", "neon").unwrap();
+        let draft = load(&conn, "s").unwrap().unwrap();
+        assert_eq!(draft.partial, "Under the neon");
+        assert_eq!(draft.conversation_id.as_deref(), Some("c"));
+        assert_eq!(draft.request.messages[0].content, "Write a verse");
+    }
+
+    #[test]
+    fn test_finished_streams_leave_no_draft() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        begin(&conn, "s", &request(), "Under").unwrap();
+        assert_eq!(list(&conn).unwrap().len(), 1);
+        assert!(finish(&conn, "s").unwrap());
+        assert!(!finish(&conn, "s").unwrap());
+        assert!(list(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,414 @@
+//! Opt-in anonymous usage telemetry.
+//!
+//! Telemetry is off until the user turns it on. When enabled, the app keeps
+//! per-day counts of feature use (`workflow.run`) and error categories
+//! (`chat.RATE_LIMITED`) in a local queue — never content, IDs, paths, or
+//! anything typed by the user. Complete days are sent in one batch every
+//! few hours, without any install identifier; today's counts stay local
+//! until the day is over.
+//!
+//! `preview_telemetry` returns the next batch exactly as it would be sent,
+//! and `purge_telemetry` empties the queue. Turning telemetry off purges it
+//! too. Builds without a `GIBBER_TELEMETRY_URL` never send anything.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the telemetry preferences.
+pub const SETTINGS_KEY: &str = "telemetry";
+
+/// Collection endpoint, provided at build time.
+const ENDPOINT: Option<&str> = option_env!("GIBBER_TELEMETRY_URL");
+
+/// Version of the batch format.
+const BATCH_SCHEMA: u32 = 1;
+
+/// Delay between sending attempts.
+const FLUSH_INTERVAL: Duration = Duration::from_hours(6);
+
+/// Delay before the first attempt after launch.
+const FIRST_FLUSH_DELAY: Duration = Duration::from_mins(5);
+
+/// Queued days older than this are dropped unsent.
+const MAX_QUEUE_DAYS: i64 = 30;
+
+/// Longest accepted event name.
+const MAX_NAME_LEN: usize = 64;
+
+/// What a count measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetryKind {
+    /// A feature was used
+    Feature,
+    /// An operation failed with an error category
+    Error,
+}
+
+impl TelemetryKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Feature => "feature",
+            Self::Error => "error",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "error" => Self::Error,
+            _ => Self::Feature,
+        }
+    }
+}
+
+/// Telemetry preferences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TelemetrySettings {
+    /// Collect and send usage counts
+    pub enabled: bool,
+}
+
+/// Managed flag mirroring [`TelemetrySettings::enabled`], checked on every
+/// recorded event.
+#[derive(Default)]
+pub struct TelemetryState {
+    enabled: AtomicBool,
+}
+
+/// One queued count.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryCount {
+    /// What is counted
+    pub kind: TelemetryKind,
+    /// Event name
+    pub name: String,
+    /// UTC day as `YYYY-MM-DD`
+    pub day: String,
+    /// Occurrences that day
+    pub count: i64,
+}
+
+/// A batch as sent to the collection endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryBatch {
+    /// Batch format version
+    pub schema: u32,
+    /// App version
+    pub app_version: String,
+    /// Operating system family (`macos`, `windows`, `linux`)
+    pub os: &'static str,
+    /// Counts for complete days
+    pub counts: Vec<TelemetryCount>,
+}
+
+/// Returns `true` for names made only of ASCII letters, digits, `.` and `_`,
+/// so free-form text can't slip into a count.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+}
+
+fn today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+fn increment(
+    conn: &Connection,
+    kind: TelemetryKind,
+    name: &str,
+    day: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO telemetry_counts (kind, name, day, count) VALUES (?1, ?2, ?3, 1)
+         ON CONFLICT(kind, name, day) DO UPDATE SET count = count + 1",
+        params![kind.as_str(), name, day],
+    )?;
+    Ok(())
+}
+
+fn record(app: &AppHandle, kind: TelemetryKind, name: &str) {
+    let enabled = app
+        .try_state::<TelemetryState>()
+        .is_some_and(|state| state.enabled.load(Ordering::Relaxed));
+    if !enabled {
+        return;
+    }
+    if !is_valid_name(name) {
+        tracing::warn!(name, "rejected telemetry event name");
+        return;
+    }
+    if let Err(e) = increment(&app.state::<Database>().conn(), kind, name, &today()) {
+        tracing::warn!("failed to queue telemetry: {e}");
+    }
+}
+
+/// Counts one use of a feature, if telemetry is enabled.
+pub fn record_feature(app: &AppHandle, name: &str) {
+    record(app, TelemetryKind::Feature, name);
+}
+
+/// Counts one error of a category, if telemetry is enabled.
+///
+/// `code` must be an error code such as `RATE_LIMITED`, never a message.
+pub fn record_error(app: &AppHandle, area: &str, code: &str) {
+    record(app, TelemetryKind::Error, &format!("{area}.{code}"));
+}
+
+/// Returns queued counts for days before `today`, oldest first.
+fn complete_days(conn: &Connection, today: &str) -> rusqlite::Result<Vec<TelemetryCount>> {
+    let mut stmt = conn.prepare(
+        "SELECT kind, name, day, count FROM telemetry_counts WHERE day < ?1 ORDER BY day, kind, name",
+    )?;
+    let counts = stmt
+        .query_map([today], |row| {
+            let kind: String = row.get(0)?;
+            Ok(TelemetryCount {
+                kind: TelemetryKind::parse(&kind),
+                name: row.get(1)?,
+                day: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })?
+        .collect();
+    counts
+}
+
+fn batch(app: &AppHandle, counts: Vec<TelemetryCount>) -> TelemetryBatch {
+    TelemetryBatch {
+        schema: BATCH_SCHEMA,
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        counts,
+    }
+}
+
+fn purge(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM telemetry_counts", [])
+}
+
+fn drop_stale(conn: &Connection) -> rusqlite::Result<usize> {
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(MAX_QUEUE_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+    conn.execute("DELETE FROM telemetry_counts WHERE day < ?1", [cutoff])
+}
+
+/// Sends the counts of complete days and removes them from the queue.
+async fn flush(app: &AppHandle, endpoint: &str) -> Result<(), GibberError> {
+    let today = today();
+    let counts = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        drop_stale(&conn)?;
+        complete_days(&conn, &today)?
+    };
+    if counts.is_empty() {
+        return Ok(());
+    }
+    let builder = network::client(app, Service::Telemetry)?
+        .post(endpoint)
+        .json(&batch(app, counts));
+    let response = network_activity::send(app, Service::Telemetry, "telemetry.upload", builder)
+        .await
+        .map_err(|e| GibberError::new("NETWORK_ERROR", e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(GibberError::new(
+            "NETWORK_ERROR",
+            format!("Endpoint answered {}", response.status()),
+        ));
+    }
+    app.state::<Database>()
+        .conn()
+        .execute("DELETE FROM telemetry_counts WHERE day < ?1", [today])?;
+    Ok(())
+}
+
+fn load_settings(conn: &Connection) -> TelemetrySettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Loads the preferences and starts the background sender.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+pub fn start(app: &AppHandle) {
+    let settings = load_settings(&app.state::<Database>().conn());
+    app.manage(TelemetryState {
+        enabled: AtomicBool::new(settings.enabled),
+    });
+    let Some(endpoint) = ENDPOINT else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_FLUSH_DELAY).await;
+        loop {
+            if app
+                .state::<TelemetryState>()
+                .enabled
+                .load(Ordering::Relaxed)
+            {
+                if let Err(e) = flush(&app, endpoint).await {
+                    tracing::warn!("failed to send telemetry: {e}");
+                }
+            }
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+        }
+    });
+}
+
+/// Returns the telemetry preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_telemetry_settings(db: State<'_, Database>) -> TelemetrySettings {
+    load_settings(&db.conn())
+}
+
+/// Replaces the telemetry preferences.
+///
+/// Turning telemetry off also purges the queue.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_telemetry_settings", { settings: { enabled: true } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn set_telemetry_settings(
+    db: State<'_, Database>,
+    state: State<'_, TelemetryState>,
+    settings: TelemetrySettings,
+) -> Result<TelemetrySettings, GibberError> {
+    let conn = db.conn();
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&conn, SETTINGS_KEY, &value)?;
+    if !settings.enabled {
+        purge(&conn)?;
+    }
+    state.enabled.store(settings.enabled, Ordering::Relaxed);
+    Ok(settings)
+}
+
+/// Returns the next batch exactly as it would be sent.
+///
+/// Counts for today aren't included until the day is over.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the queue cannot be read.
+///
+/// # Example
+///
+/// ```typescript
+/// const batch = await invoke("preview_telemetry");
+/// showJson(batch);
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and State by value
+pub fn preview_telemetry(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<TelemetryBatch, GibberError> {
+    let counts = complete_days(&db.conn(), &today())?;
+    Ok(batch(&app, counts))
+}
+
+/// Deletes everything in the queue.
+///
+/// # Returns
+///
+/// The number of deleted counts.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn purge_telemetry(db: State<'_, Database>) -> Result<usize, GibberError> {
+    Ok(purge(&db.conn())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_validation() {
+        assert!(is_valid_name("workflow.run"));
+        assert!(is_valid_name("chat.RATE_LIMITED"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("chat.No API key stored"));
+        assert!(!is_valid_name("/home/ana/notes.md"));
+        assert!(!is_valid_name(&"a".repeat(MAX_NAME_LEN + 1)));
+    }
+
+    #[test]
+    fn test_counts_aggregate_per_day() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        increment(&conn, TelemetryKind::Feature, "workflow.run", "2026-10-12").unwrap();
+        increment(&conn, TelemetryKind::Feature, "workflow.run", "2026-10-12").unwrap();
+        increment(
+            &conn,
+            TelemetryKind::Error,
+            "chat.RATE_LIMITED",
+            "2026-10-13",
+        )
+        .unwrap();
+        increment(&conn, TelemetryKind::Feature, "workflow.run", "2026-10-14").unwrap();
+
+        let counts = complete_days(&conn, "2026-10-14").unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                TelemetryCount {
+                    kind: TelemetryKind::Feature,
+                    name: "workflow.run".to_string(),
+                    day: "2026-10-12".to_string(),
+                    count: 2,
+                },
+                TelemetryCount {
+                    kind: TelemetryKind::Error,
+                    name: "chat.RATE_LIMITED".to_string(),
+                    day: "2026-10-13".to_string(),
+                    count: 1,
+                },
+            ]
+        );
+        assert_eq!(purge(&conn).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(!TelemetrySettings::default().enabled);
+        let db = Database::open_in_memory().unwrap();
+        assert!(!load_settings(&db.conn()).enabled);
+    }
+}
@@ -0,0 +1,295 @@
+//! Line and word diffs of model output.
+//!
+//! Both sides are split into tokens (lines, or words with their trailing
+//! whitespace) and compared with a longest-common-subsequence table.
+//! Neighbouring tokens of the same kind are merged, so the result is a
+//! short list of spans ready to render. Inputs whose table would exceed
+//! [`MAX_CELLS`] are reported as one deletion and one insertion.
+//!
+//! [`diff_messages`] compares two stored answers, such as a regenerated
+//! response and the one it replaced, or the answers of two models to the
+//! same prompt.
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::conversations;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Largest LCS table computed, in cells.
+pub const MAX_CELLS: usize = 4_000_000;
+
+/// Unit of comparison.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    /// Words with their trailing whitespace
+    #[default]
+    Word,
+    /// Whole lines
+    Line,
+}
+
+/// What happened to a span of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffKind {
+    /// In both texts
+    Equal,
+    /// Only in the new text
+    Insert,
+    /// Only in the old text
+    Delete,
+}
+
+/// A run of tokens with the same fate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSpan {
+    /// What happened to the text
+    pub kind: DiffKind,
+    /// The text, including its line breaks or spacing
+    pub text: String,
+}
+
+/// Splits text into lines, keeping each line's terminator.
+pub fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Splits text into words, each keeping the whitespace that follows it.
+pub fn words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = false;
+    for (index, c) in text.char_indices() {
+        if c.is_whitespace() {
+            in_space = true;
+        } else if in_space {
+            tokens.push(&text[start..index]);
+            start = index;
+            in_space = false;
+        }
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn push(spans: &mut Vec<DiffSpan>, kind: DiffKind, text: &str) {
+    match spans.last_mut() {
+        Some(last) if last.kind == kind => last.text.push_str(text),
+        _ => spans.push(DiffSpan {
+            kind,
+            text: text.to_string(),
+        }),
+    }
+}
+
+/// Diffs two token lists.
+pub fn diff(old: &[&str], new: &[&str]) -> Vec<DiffSpan> {
+    // Common ends are cheap to strip and keep the table small.
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut spans = Vec::new();
+    for token in &old[..prefix] {
+        push(&mut spans, DiffKind::Equal, token);
+    }
+    let (rows, cols) = (old_mid.len(), new_mid.len());
+    if (rows + 1).saturating_mul(cols + 1) > MAX_CELLS {
+        for token in old_mid {
+            push(&mut spans, DiffKind::Delete, token);
+        }
+        for token in new_mid {
+            push(&mut spans, DiffKind::Insert, token);
+        }
+    } else {
+        // lengths[i][j]: LCS length of old_mid[i..] and new_mid[j..]
+        let width = cols + 1;
+        let mut lengths = vec![0u32; (rows + 1) * width];
+        for i in (0..rows).rev() {
+            for j in (0..cols).rev() {
+                lengths[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < rows && j < cols {
+            if old_mid[i] == new_mid[j] {
+                push(&mut spans, DiffKind::Equal, old_mid[i]);
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                push(&mut spans, DiffKind::Delete, old_mid[i]);
+                i += 1;
+            } else {
+                push(&mut spans, DiffKind::Insert, new_mid[j]);
+                j += 1;
+            }
+        }
+        for token in &old_mid[i..] {
+            push(&mut spans, DiffKind::Delete, token);
+        }
+        for token in &new_mid[j..] {
+            push(&mut spans, DiffKind::Insert, token);
+        }
+    }
+    for token in &old[old.len() - suffix..] {
+        push(&mut spans, DiffKind::Equal, token);
+    }
+    spans
+}
+
+/// Whether a diff found any change.
+pub fn has_changes(spans: &[DiffSpan]) -> bool {
+    spans.iter().any(|span| span.kind != DiffKind::Equal)
+}
+
+/// A diff with word counts for a summary line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageDiff {
+    /// Spans in reading order
+    pub spans: Vec<DiffSpan>,
+    /// Words only in the new text
+    pub inserted_words: usize,
+    /// Words only in the old text
+    pub deleted_words: usize,
+    /// Words in both
+    pub unchanged_words: usize,
+}
+
+/// Diffs two texts at the given granularity.
+pub fn diff_texts(old: &str, new: &str, granularity: Granularity) -> MessageDiff {
+    let spans = match granularity {
+        Granularity::Word => diff(&words(old), &words(new)),
+        Granularity::Line => diff(&lines(old), &lines(new)),
+    };
+    let count = |kind: DiffKind| -> usize {
+        spans
+            .iter()
+            .filter(|span| span.kind == kind)
+            .map(|span| span.text.split_whitespace().count())
+            .sum()
+    };
+    MessageDiff {
+        inserted_words: count(DiffKind::Insert),
+        deleted_words: count(DiffKind::Delete),
+        unchanged_words: count(DiffKind::Equal),
+        spans,
+    }
+}
+
+/// Diffs two stored messages, word by word unless `granularity` says
+/// otherwise.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if either message
+/// doesn't exist, or `DATABASE` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const { spans, insertedWords, deletedWords } = await invoke("diff_messages", {
+///   oldId: previous.id,
+///   newId: regenerated.id,
+///   granularity: "word",
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn diff_messages(
+    db: State<'_, Database>,
+    old_id: &str,
+    new_id: &str,
+    granularity: Option<Granularity>,
+) -> Result<MessageDiff, GibberError> {
+    let conn = db.conn();
+    let load = |id: &str| -> Result<String, GibberError> {
+        conversations::load_message(&conn, id)?
+            .map(|message| message.content)
+            .ok_or_else(|| GibberError::new("NOT_FOUND", format!("Message {id} not found")))
+    };
+    Ok(diff_texts(
+        &load(old_id)?,
+        &load(new_id)?,
+        granularity.unwrap_or_default(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(kind: DiffKind, text: &str) -> DiffSpan {
+        DiffSpan {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_word_diff_merges_runs() {
+        let old = "The quick brown fox jumps";
+        let new = "The quick red fox leaps high";
+        assert_eq!(
+            diff(&words(old), &words(new)),
+            [
+                span(DiffKind::Equal, "The quick "),
+                span(DiffKind::Delete, "brown "),
+                span(DiffKind::Insert, "red "),
+                span(DiffKind::Equal, "fox "),
+                span(DiffKind::Delete, "jumps"),
+                span(DiffKind::Insert, "leaps high"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_diff_and_identity() {
+        let spans = diff(&lines("a\nb\nc\n"), &lines("a\nc\nd\n"));
+        assert_eq!(
+            spans,
+            [
+                span(DiffKind::Equal, "a\n"),
+                span(DiffKind::Delete, "b\n"),
+                span(DiffKind::Equal, "c\n"),
+                span(DiffKind::Insert, "d\n"),
+            ]
+        );
+        assert!(has_changes(&spans));
+        assert!(!has_changes(&diff(&lines("same\n"), &lines("same\n"))));
+        assert_eq!(words("  lead  trail "), ["  ", "lead  ", "trail "]);
+    }
+
+    #[test]
+    fn test_diff_texts_counts_words() {
+        let diff = diff_texts(
+            "Use a 120 BPM kick.",
+            "Use a 124 BPM four-on-the-floor kick.",
+            Granularity::Word,
+        );
+        assert_eq!(diff.inserted_words, 2);
+        assert_eq!(diff.deleted_words, 1);
+        assert_eq!(diff.unchanged_words, 4);
+        let by_line = diff_texts("a\nb\n", "a\nc\n", Granularity::Line);
+        assert_eq!(by_line.spans.len(), 3);
+    }
+}
@@ -0,0 +1,405 @@
+//! Exact transformations of structured data.
+//!
+//! Models reshape JSON unreliably when they do it token by token, so
+//! `transform_data` does it for them: it parses JSON, YAML, or TOML, runs
+//! an optional jq filter over it with jaq, and writes the results in any
+//! of the three formats. With no filter it simply converts. The model gets
+//! it as the `transform_data` tool of `get_data_tools`.
+//!
+//! jaq can't be interrupted, so filters run on their own thread and a
+//! filter still running after the timeout is abandoned with `TIMEOUT`.
+//! Results are measured as they arrive: the filter stops with `TOO_LARGE`
+//! once they outgrow [`MAX_OUTPUT_BYTES`], and an abandoned filter stops
+//! at its next result.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use jaq_core::load::{Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, RcIter};
+use jaq_json::Val;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::commands::plugins::PluginTool;
+use crate::error::GibberError;
+
+/// Largest input accepted, in bytes.
+const MAX_INPUT_BYTES: usize = 5 * 1024 * 1024;
+
+/// Largest output returned, in bytes.
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Most filter results kept; the rest are dropped.
+const MAX_RESULTS: usize = 1_000;
+
+/// How long a filter may run.
+const FILTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A structured data format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum StructuredFormat {
+    /// JSON
+    Json,
+    /// YAML
+    Yaml,
+    /// TOML; documents must be tables
+    Toml,
+}
+
+impl StructuredFormat {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Yaml => "YAML",
+            Self::Toml => "TOML",
+        }
+    }
+}
+
+/// What `transform_data` does.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformRequest {
+    /// Document to transform
+    pub input: String,
+    /// Format of `input`
+    pub from: StructuredFormat,
+    /// Format of the output; the input format when `None`
+    #[serde(default)]
+    pub to: Option<StructuredFormat>,
+    /// jq filter to run; when `None` the document is only converted
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Write string results without quotes, like `jq -r`
+    #[serde(default)]
+    pub raw: bool,
+    /// Write JSON on one line
+    #[serde(default)]
+    pub compact: bool,
+}
+
+/// What `transform_data` returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformResult {
+    /// The results, one document each: JSON separated by newlines, YAML
+    /// by `---`, TOML by blank lines
+    pub output: String,
+    /// Results the filter produced
+    pub results: u32,
+    /// Whether results past the first 1000 were dropped
+    pub truncated: bool,
+}
+
+fn invalid_input(format: StructuredFormat, e: impl std::fmt::Display) -> GibberError {
+    GibberError::new(
+        "INVALID_CONTENT",
+        format!("The input is not valid {}: {e}", format.name()),
+    )
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(number) => json!(number),
+        toml::Value::Float(number) => json!(number),
+        toml::Value::Boolean(value) => Value::Bool(value),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn parse(input: &str, format: StructuredFormat) -> Result<Value, GibberError> {
+    match format {
+        StructuredFormat::Json => serde_json::from_str(input).map_err(|e| invalid_input(format, e)),
+        StructuredFormat::Yaml => serde_yaml::from_str(input).map_err(|e| invalid_input(format, e)),
+        StructuredFormat::Toml => input
+            .parse::<toml::Table>()
+            .map(|table| toml_to_json(toml::Value::Table(table)))
+            .map_err(|e| invalid_input(format, e)),
+    }
+}
+
+fn write(
+    value: &Value,
+    format: StructuredFormat,
+    raw: bool,
+    compact: bool,
+) -> Result<String, GibberError> {
+    if let (true, Value::String(text)) = (raw, value) {
+        return Ok(text.clone());
+    }
+    let invalid = |e: &dyn std::fmt::Display| {
+        GibberError::new(
+            "INVALID_OUTPUT",
+            format!("The result can't be written as {}: {e}", format.name()),
+        )
+    };
+    match format {
+        StructuredFormat::Json if compact => Ok(value.to_string()),
+        StructuredFormat::Json => serde_json::to_string_pretty(value).map_err(|e| invalid(&e)),
+        StructuredFormat::Yaml => serde_yaml::to_string(value)
+            .map(|yaml| yaml.trim_end().to_string())
+            .map_err(|e| invalid(&e)),
+        StructuredFormat::Toml if !value.is_object() => {
+            Err(invalid(&"TOML documents must be tables"))
+        }
+        StructuredFormat::Toml => toml::to_string_pretty(value)
+            .map(|toml| toml.trim_end().to_string())
+            .map_err(|e| invalid(&e)),
+    }
+}
+
+/// Reports jaq's load or compile errors, which come paired with the file.
+fn invalid_filter<F, E: std::fmt::Debug>(errors: Vec<(F, E)>) -> GibberError {
+    let errors: Vec<E> = errors.into_iter().map(|(_, e)| e).collect();
+    GibberError::new("INVALID_FILTER", format!("Invalid filter: {errors:?}"))
+}
+
+fn output_too_large() -> GibberError {
+    GibberError::new(
+        "TOO_LARGE",
+        format!(
+            "The output is larger than {} MB",
+            MAX_OUTPUT_BYTES / 1024 / 1024
+        ),
+    )
+}
+
+/// Runs a jq filter, keeping at most [`MAX_RESULTS`] results and giving up
+/// once they hold more than [`MAX_OUTPUT_BYTES`] of JSON or `abandoned` is
+/// set.
+fn run_filter(
+    code: &str,
+    input: Value,
+    abandoned: &AtomicBool,
+) -> Result<(Vec<Value>, bool), GibberError> {
+    let program = File { code, path: () };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader.load(&arena, program).map_err(invalid_filter)?;
+    let filter = Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(invalid_filter)?;
+    let inputs = RcIter::new(core::iter::empty());
+    let mut results = Vec::new();
+    let mut bytes = 0;
+    for output in filter.run((Ctx::new([], &inputs), Val::from(input))) {
+        if abandoned.load(Ordering::Relaxed) {
+            return Err(GibberError::new("CANCELLED", "The filter was abandoned"));
+        }
+        if results.len() == MAX_RESULTS {
+            return Ok((results, true));
+        }
+        let value =
+            Value::from(output.map_err(|e| GibberError::new("FILTER_FAILED", e.to_string()))?);
+        bytes += value.to_string().len();
+        if bytes > MAX_OUTPUT_BYTES {
+            return Err(output_too_large());
+        }
+        results.push(value);
+    }
+    Ok((results, false))
+}
+
+fn transform(request: TransformRequest) -> Result<TransformResult, GibberError> {
+    if request.input.len() > MAX_INPUT_BYTES {
+        return Err(GibberError::new(
+            "TOO_LARGE",
+            format!(
+                "The input is larger than {} MB",
+                MAX_INPUT_BYTES / 1024 / 1024
+            ),
+        ));
+    }
+    let to = request.to.unwrap_or(request.from);
+    let value = parse(&request.input, request.from)?;
+    let (values, truncated) = match request.filter.filter(|code| !code.trim().is_empty()) {
+        None => (vec![value], false),
+        Some(code) => {
+            let (sender, receiver) = mpsc::channel();
+            let abandoned = Arc::new(AtomicBool::new(false));
+            let flag = Arc::clone(&abandoned);
+            std::thread::spawn(move || {
+                let _ = sender.send(run_filter(&code, value, &flag));
+            });
+            match receiver.recv_timeout(FILTER_TIMEOUT) {
+                Ok(result) => result?,
+                Err(RecvTimeoutError::Timeout) => {
+                    abandoned.store(true, Ordering::Relaxed);
+                    return Err(GibberError::new(
+                        "TIMEOUT",
+                        format!(
+                            "The filter ran longer than {} seconds",
+                            FILTER_TIMEOUT.as_secs()
+                        ),
+                    ));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(GibberError::new("TASK_FAILED", "The filter crashed"));
+                }
+            }
+        }
+    };
+    let separator = match to {
+        StructuredFormat::Json => "\n",
+        StructuredFormat::Yaml => "\n---\n",
+        StructuredFormat::Toml => "\n\n",
+    };
+    let documents = values
+        .iter()
+        .map(|value| write(value, to, request.raw, request.compact))
+        .collect::<Result<Vec<_>, _>>()?;
+    let output = documents.join(separator);
+    if output.len() > MAX_OUTPUT_BYTES {
+        return Err(output_too_large());
+    }
+    Ok(TransformResult {
+        output,
+        results: u32::try_from(values.len()).unwrap_or(u32::MAX),
+        truncated,
+    })
+}
+
+/// Tool definition of `transform_data`, offered by `get_data_tools`.
+pub(crate) fn tool() -> PluginTool {
+    let format = json!({ "enum": ["json", "yaml", "toml"] });
+    PluginTool {
+        name: "transform_data".to_string(),
+        description: "Transforms JSON, YAML, or TOML exactly: runs a jq filter over the document \
+                      and/or converts it to another format."
+            .to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "input": { "type": "string", "description": "The document" },
+                "from": format.clone(),
+                "to": format,
+                "filter": {
+                    "type": "string",
+                    "description": "jq filter, e.g. .items | map(.name)"
+                },
+                "raw": { "type": "boolean", "description": "Write strings without quotes" },
+                "compact": { "type": "boolean", "description": "Write JSON on one line" }
+            },
+            "required": ["input", "from"]
+        })
+        .to_string(),
+    }
+}
+
+/// Runs a jq filter over a JSON, YAML, or TOML document and writes the
+/// results in the requested format.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_CONTENT` if the input doesn't
+/// parse, `INVALID_FILTER` if the filter doesn't compile, `FILTER_FAILED`
+/// if it fails on the input, `TIMEOUT` if it runs longer than 5 seconds,
+/// `INVALID_OUTPUT` if a result can't be written in the output format, or
+/// `TOO_LARGE` if the input exceeds 5 MB or the output 1 MB.
+///
+/// # Example
+///
+/// ```typescript
+/// const { output } = await invoke("transform_data", {
+///   request: { input: packageJson, from: "json", to: "yaml", filter: ".dependencies" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn transform_data(request: TransformRequest) -> Result<TransformResult, GibberError> {
+    tauri::async_runtime::spawn_blocking(move || transform(request))
+        .await
+        .map_err(|e| GibberError::new("TASK_FAILED", e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(input: &str, from: StructuredFormat, to: StructuredFormat) -> TransformRequest {
+        TransformRequest {
+            input: input.to_string(),
+            from,
+            to: Some(to),
+            filter: None,
+            raw: false,
+            compact: true,
+        }
+    }
+
+    #[test]
+    fn test_converts_between_formats() {
+        let toml = "title = \"Jam\"\nwhen = 2026-10-14\n\n[tempo]\nbpm = 120\n";
+        let json = transform(request(
+            toml,
+            StructuredFormat::Toml,
+            StructuredFormat::Json,
+        ))
+        .unwrap();
+        assert_eq!(
+            json.output,
+            r#"{"tempo":{"bpm":120},"title":"Jam","when":"2026-10-14"}"#
+        );
+
+        let yaml = transform(request(
+            &json.output,
+            StructuredFormat::Json,
+            StructuredFormat::Yaml,
+        ))
+        .unwrap();
+        assert!(yaml.output.contains("bpm: 120"));
+        let back = transform(request(
+            &yaml.output,
+            StructuredFormat::Yaml,
+            StructuredFormat::Toml,
+        ))
+        .unwrap();
+        assert!(back.output.contains("[tempo]\nbpm = 120"));
+
+        let list = request("[1, 2]", StructuredFormat::Json, StructuredFormat::Toml);
+        assert_eq!(transform(list).unwrap_err().code(), "INVALID_OUTPUT");
+        let broken = request("{", StructuredFormat::Json, StructuredFormat::Json);
+        assert_eq!(transform(broken).unwrap_err().code(), "INVALID_CONTENT");
+    }
+
+    #[test]
+    fn test_runs_jq_filters() {
+        let input = r#"{"tracks": [{"name": "kick", "bpm": 120}, {"name": "pad", "bpm": 90}]}"#;
+        let mut names = request(input, StructuredFormat::Json, StructuredFormat::Json);
+        names.filter = Some(".tracks[] | select(.bpm > 100) | .name".to_string());
+        names.raw = true;
+        let result = transform(names).unwrap();
+        assert_eq!((result.output.as_str(), result.results), ("kick", 1));
+
+        let mut sum = request(input, StructuredFormat::Json, StructuredFormat::Json);
+        sum.filter = Some("[.tracks[].bpm] | add".to_string());
+        assert_eq!(transform(sum).unwrap().output, "210");
+
+        let mut endless = request("null", StructuredFormat::Json, StructuredFormat::Json);
+        endless.filter = Some("range(1000000000)".to_string());
+        let result = transform(endless).unwrap();
+        assert!(result.truncated);
+        assert_eq!(result.results, 1_000);
+
+        let mut bulky = request("null", StructuredFormat::Json, StructuredFormat::Json);
+        bulky.filter = Some(r#"range(1000000000) | "x" * 10000"#.to_string());
+        assert_eq!(transform(bulky).unwrap_err().code(), "TOO_LARGE");
+
+        let mut invalid = request("null", StructuredFormat::Json, StructuredFormat::Json);
+        invalid.filter = Some(".[".to_string());
+        assert_eq!(transform(invalid).unwrap_err().code(), "INVALID_FILTER");
+    }
+}
@@ -0,0 +1,530 @@
+//! Translation of text and documents.
+//!
+//! The source language is detected locally with `whatlang`, so the prompt
+//! can name it and text already in the target language isn't sent at all.
+//! Requests go to `translationModel` from the general settings (falling
+//! back to `defaultModel`) with a prompt that asks for the translation
+//! only and for the formatting to be kept.
+//!
+//! `translate_attachment` translates a Markdown or plain text attachment
+//! in chunks of whole lines as a background job. Fenced code blocks and
+//! blank lines are copied as they are, and the whitespace around each
+//! chunk is put back after translation, so the document keeps its layout.
+//! The translation is stored as a new attachment next to the original.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use whatlang::Lang;
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::attachments::{self, Attachment, AttachmentKind, AttachmentStore};
+use crate::commands::jobs;
+use crate::commands::settings;
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Longest text `translate_text` accepts, in characters.
+pub const MAX_TEXT_CHARS: usize = 20_000;
+
+/// Largest chunk of a document sent in one request, in characters.
+const MAX_CHUNK_CHARS: usize = 6_000;
+
+/// Temperature of translation requests; low, for faithful output.
+const TEMPERATURE: f32 = 0.2;
+
+const TRANSLATE_PROMPT: &str = "You are a professional translator. Translate the user's text \
+     {source}into {target}. Keep the meaning, tone, and register. Keep the formatting exactly: \
+     Markdown, line breaks, lists, tables, links, HTML tags, placeholders such as {name} or %s, \
+     and inline code, which stays untranslated. Reply with the translation only, without \
+     notes, explanations, or quotation marks.";
+
+/// A language detected in text.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedLanguage {
+    /// ISO 639-3 code, e.g. `deu`
+    pub code: String,
+    /// English name, e.g. `German`
+    pub name: String,
+    /// Confidence between 0 and 1
+    pub confidence: f64,
+    /// Whether the text was long and clear enough to trust the result
+    pub reliable: bool,
+}
+
+/// What `translate_text` translates.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationRequest {
+    /// The text
+    pub text: String,
+    /// Target language, as a name (`German`) or ISO 639 code (`de`, `deu`)
+    pub target: String,
+    /// Source language; detected when `None`
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Model ID; the translation model from the settings when `None`
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// A translated text.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Translation {
+    /// The translation
+    pub text: String,
+    /// Language the text was detected in, when detection was needed
+    pub detected: Option<DetectedLanguage>,
+    /// Target language as understood
+    pub target: String,
+    /// The model that translated, or `None` if the text was already in the
+    /// target language
+    pub model: Option<String>,
+}
+
+/// A translated attachment.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslatedDocument {
+    /// The new attachment holding the translation
+    pub attachment: Attachment,
+    /// Language the original was detected in
+    pub detected: Option<DetectedLanguage>,
+    /// Chunks sent for translation
+    pub chunks: usize,
+}
+
+/// Detects the language of `text`.
+fn detect(text: &str) -> Option<DetectedLanguage> {
+    let info = whatlang::detect(text)?;
+    Some(DetectedLanguage {
+        code: info.lang().code().to_string(),
+        name: info.lang().eng_name().to_string(),
+        confidence: info.confidence(),
+        reliable: info.is_reliable(),
+    })
+}
+
+/// Two-letter codes of the languages `whatlang` knows, for targets given
+/// as ISO 639-1.
+const ISO_639_1: [(&str, Lang); 30] = [
+    ("ar", Lang::Ara),
+    ("bn", Lang::Ben),
+    ("cs", Lang::Ces),
+    ("da", Lang::Dan),
+    ("de", Lang::Deu),
+    ("el", Lang::Ell),
+    ("en", Lang::Eng),
+    ("es", Lang::Spa),
+    ("fa", Lang::Pes),
+    ("fi", Lang::Fin),
+    ("fr", Lang::Fra),
+    ("he", Lang::Heb),
+    ("hi", Lang::Hin),
+    ("hu", Lang::Hun),
+    ("id", Lang::Ind),
+    ("it", Lang::Ita),
+    ("ja", Lang::Jpn),
+    ("ko", Lang::Kor),
+    ("nl", Lang::Nld),
+    ("nb", Lang::Nob),
+    ("pl", Lang::Pol),
+    ("pt", Lang::Por),
+    ("ro", Lang::Ron),
+    ("ru", Lang::Rus),
+    ("sv", Lang::Swe),
+    ("th", Lang::Tha),
+    ("tr", Lang::Tur),
+    ("uk", Lang::Ukr),
+    ("vi", Lang::Vie),
+    ("zh", Lang::Cmn),
+];
+
+/// Finds the language a name or ISO 639-1/639-3 code refers to.
+fn language(name: &str) -> Option<Lang> {
+    let name = name.trim().to_lowercase();
+    let tag = name.split(['-', '_']).next().unwrap_or_default();
+    Lang::from_code(tag)
+        .or_else(|| {
+            ISO_639_1
+                .iter()
+                .find(|(code, _)| *code == tag)
+                .map(|(_, lang)| *lang)
+        })
+        .or_else(|| {
+            Lang::all()
+                .iter()
+                .copied()
+                .find(|lang| lang.eng_name().eq_ignore_ascii_case(&name))
+        })
+}
+
+/// The language named in prompts: the English name when known, else as
+/// given.
+fn display_name(name: &str) -> String {
+    language(name).map_or_else(
+        || name.trim().to_string(),
+        |lang| lang.eng_name().to_string(),
+    )
+}
+
+fn prompt(source: Option<&str>, target: &str) -> String {
+    let source = source
+        .map(|source| format!("from {source} "))
+        .unwrap_or_default();
+    TRANSLATE_PROMPT
+        .replacen("{source}", &source, 1)
+        .replacen("{target}", target, 1)
+}
+
+/// The model to translate with.
+fn translation_model(app: &AppHandle, requested: Option<&str>) -> Result<String, GibberError> {
+    if let Some(model) = requested.filter(|model| !model.trim().is_empty()) {
+        return Ok(model.to_string());
+    }
+    let settings = settings::load(&app.state::<Database>().conn())?;
+    Ok(if settings.translation_model.trim().is_empty() {
+        settings.default_model
+    } else {
+        settings.translation_model
+    })
+}
+
+/// Translates `text`, keeping its surrounding whitespace.
+async fn translate(
+    app: &AppHandle,
+    model: &str,
+    text: &str,
+    source: Option<&str>,
+    target: &str,
+) -> Result<String, GibberError> {
+    let content = text.trim();
+    if content.is_empty() {
+        return Ok(text.to_string());
+    }
+    let mut request = ChatRequest::new(
+        Some(model),
+        vec![
+            ChatMessage::new(MessageRole::System, prompt(source, target)),
+            ChatMessage::new(MessageRole::User, content),
+        ],
+    );
+    request.temperature = TEMPERATURE;
+    let completion = chat::complete(app, &request).await?;
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    Ok(format!("{leading}{}{trailing}", completion.content.trim()))
+}
+
+/// A piece of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Prose to translate
+    Text(String),
+    /// Code and blank lines, copied as they are
+    Verbatim(String),
+}
+
+/// Splits a document into chunks of whole lines no longer than
+/// `max_chars` (unless a single line is), with fenced code blocks and
+/// blank lines set apart.
+fn segments(document: &str, max_chars: usize) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut verbatim = String::new();
+    let mut fence: Option<&str> = None;
+    let flush_text = |text: &mut String, segments: &mut Vec<Segment>| {
+        if !text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(text)));
+        }
+    };
+    let flush_verbatim = |verbatim: &mut String, segments: &mut Vec<Segment>| {
+        if !verbatim.is_empty() {
+            segments.push(Segment::Verbatim(std::mem::take(verbatim)));
+        }
+    };
+    for line in document.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        let copied = match (fence, marker) {
+            (Some(open), Some(close)) if open == close => {
+                fence = None;
+                true
+            }
+            (Some(_), _) => true,
+            (None, Some(open)) => {
+                fence = Some(open);
+                true
+            }
+            (None, None) => line.trim().is_empty(),
+        };
+        if copied {
+            flush_text(&mut text, &mut segments);
+            verbatim.push_str(line);
+        } else {
+            flush_verbatim(&mut verbatim, &mut segments);
+            if !text.is_empty() && text.len() + line.len() > max_chars {
+                flush_text(&mut text, &mut segments);
+            }
+            text.push_str(line);
+        }
+    }
+    flush_text(&mut text, &mut segments);
+    flush_verbatim(&mut verbatim, &mut segments);
+    segments
+}
+
+/// A file name for the translation of `file_name`, e.g. `notes.de.md`.
+fn translated_file_name(file_name: &str, target: &str) -> String {
+    let tag: String = language(target)
+        .map_or(target, |lang| {
+            ISO_639_1
+                .iter()
+                .find(|(_, known)| *known == lang)
+                .map_or(lang.code(), |(code, _)| *code)
+        })
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase();
+    let tag = if tag.is_empty() {
+        "translated".to_string()
+    } else {
+        tag
+    };
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{stem}.{tag}.{extension}"),
+        _ => format!("{file_name}.{tag}.txt"),
+    }
+}
+
+/// Detects the language of `text`.
+///
+/// Returns `None` when the text has too few letters to tell.
+///
+/// # Example
+///
+/// ```typescript
+/// const language = await invoke("detect_language", { text: "Wie spät ist es?" });
+/// // { code: "deu", name: "German", confidence: 0.93, reliable: true }
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    detect(text)
+}
+
+/// Translates text into another language.
+///
+/// Text detected, reliably, as already being in the target language is
+/// returned unchanged without a request.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` for empty or overlong
+/// text or an empty target, or the code of the failed completion.
+///
+/// # Example
+///
+/// ```typescript
+/// const { text } = await invoke("translate_text", {
+///   request: { text: "Wo ist der Bahnhof?", target: "English" },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn translate_text(
+    app: AppHandle,
+    request: TranslationRequest,
+) -> Result<Translation, GibberError> {
+    if request.text.trim().is_empty() {
+        return Err(GibberError::invalid("There is no text to translate"));
+    }
+    if request.text.chars().count() > MAX_TEXT_CHARS {
+        return Err(GibberError::invalid(format!(
+            "The text is longer than {MAX_TEXT_CHARS} characters; attach it as a document"
+        )));
+    }
+    if request.target.trim().is_empty() {
+        return Err(GibberError::invalid("No target language was given"));
+    }
+    let target = display_name(&request.target);
+    let detected = match &request.source {
+        Some(_) => None,
+        None => detect(&request.text),
+    };
+    let already_target = detected.as_ref().is_some_and(|detected| {
+        detected.reliable
+            && language(&request.target).is_some_and(|lang| lang.code() == detected.code)
+    });
+    if already_target {
+        return Ok(Translation {
+            text: request.text,
+            detected,
+            target,
+            model: None,
+        });
+    }
+    let source = request.source.as_deref().map(display_name).or_else(|| {
+        detected
+            .as_ref()
+            .filter(|d| d.reliable)
+            .map(|d| d.name.clone())
+    });
+    let model = translation_model(&app, request.model.as_deref())?;
+    let text = translate(&app, &model, &request.text, source.as_deref(), &target).await?;
+    Ok(Translation {
+        text,
+        detected,
+        target,
+        model: Some(model),
+    })
+}
+
+/// Translates a Markdown or plain text attachment as a background job
+/// and stores the translation as a new attachment in the same
+/// conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the attachment
+/// doesn't exist, `NOT_TEXT` if it isn't a text document, `CANCELLED` if
+/// the job is cancelled, or the code of a failed completion.
+///
+/// # Example
+///
+/// ```typescript
+/// const { attachment } = await invoke("translate_attachment", {
+///   attachmentId, target: "Spanish", model: null,
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn translate_attachment(
+    app: AppHandle,
+    attachment_id: String,
+    target: String,
+    model: Option<String>,
+) -> Result<TranslatedDocument, GibberError> {
+    if target.trim().is_empty() {
+        return Err(GibberError::invalid("No target language was given"));
+    }
+    let attachment = attachments::load_attachment(&app.state::<Database>().conn(), &attachment_id)?
+        .ok_or_else(|| {
+            GibberError::new("NOT_FOUND", format!("Attachment {attachment_id} not found"))
+        })?;
+    if !matches!(
+        attachment.kind,
+        AttachmentKind::Markdown | AttachmentKind::Text
+    ) {
+        return Err(GibberError::new(
+            "NOT_TEXT",
+            format!("{} is not a text document", attachment.file_name),
+        ));
+    }
+    let document = std::fs::read_to_string(app.state::<AttachmentStore>().file_path(&attachment))?;
+    let model = translation_model(&app, model.as_deref())?;
+    let target_name = display_name(&target);
+    let detected = detect(&document);
+    let source = detected
+        .as_ref()
+        .filter(|d| d.reliable)
+        .map(|d| d.name.clone());
+
+    let segments = segments(&document, MAX_CHUNK_CHARS);
+    let total = segments
+        .iter()
+        .filter(|segment| matches!(segment, Segment::Text(_)))
+        .count();
+    let mut job = jobs::start(
+        &app,
+        "translate.document",
+        &format!("Translating {} into {target_name}", attachment.file_name),
+    );
+    let mut translated = String::with_capacity(document.len());
+    let mut done = 0;
+    for segment in segments {
+        match segment {
+            Segment::Verbatim(text) => translated.push_str(&text),
+            Segment::Text(text) => {
+                if job.is_cancelled() {
+                    job.finish(None, None);
+                    return Err(GibberError::new(
+                        "CANCELLED",
+                        "The translation was cancelled",
+                    ));
+                }
+                match translate(&app, &model, &text, source.as_deref(), &target_name).await {
+                    Ok(text) => translated.push_str(&text),
+                    Err(e) => {
+                        job.finish(Some(&e.to_string()), None);
+                        return Err(e);
+                    }
+                }
+                done += 1;
+                job.advance(done, total, Some(&format!("{done} of {total} chunks")));
+            }
+        }
+    }
+    let file_name = translated_file_name(&attachment.file_name, &target);
+    let stored = attachments::store_captured(
+        &app,
+        &file_name,
+        translated.as_bytes(),
+        attachment.conversation_id.as_deref(),
+    );
+    match &stored {
+        Ok(_) => job.finish(None, Some(&file_name)),
+        Err(e) => job.finish(Some(&e.to_string()), None),
+    }
+    Ok(TranslatedDocument {
+        attachment: stored?,
+        detected,
+        chunks: total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages_and_prompt() {
+        let detected = detect("Der schnelle braune Fuchs springt über den faulen Hund.").unwrap();
+        assert_eq!(detected.code, "deu");
+        assert_eq!(detected.name, "German");
+        assert_eq!(language("de-AT"), Some(Lang::Deu));
+        assert_eq!(language("spa"), Some(Lang::Spa));
+        assert_eq!(language("french"), Some(Lang::Fra));
+        assert_eq!(display_name("Klingon"), "Klingon");
+        assert!(prompt(Some("German"), "English").contains("text from German into English."));
+        assert!(prompt(None, "English").contains("text into English."));
+        assert_eq!(translated_file_name("notes.md", "German"), "notes.de.md");
+        assert_eq!(translated_file_name("README", "tlh"), "README.tlh.txt");
+    }
+
+    #[test]
+    fn test_segments_keep_code_and_blank_lines() {
+        let document = "# Title\n\nFirst line\nsecond line\n\n```rust\nlet x = 1;\n\n```\nEnd\n";
+        let parts = segments(document, 100);
+        assert_eq!(
+            parts,
+            vec![
+                Segment::Text("# Title\n".to_string()),
+                Segment::Verbatim("\n".to_string()),
+                Segment::Text("First line\nsecond line\n".to_string()),
+                Segment::Verbatim("\n```rust\nlet x = 1;\n\n```\n".to_string()),
+                Segment::Text("End\n".to_string()),
+            ]
+        );
+        let joined: String = parts
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) | Segment::Verbatim(text) => text.as_str(),
+            })
+            .collect();
+        assert_eq!(joined, document);
+        assert_eq!(segments("aaaa\nbbbb\ncccc\n", 10).len(), 2);
+    }
+}
@@ -0,0 +1,349 @@
+//! System tray icon and quick actions.
+//!
+//! The tray menu offers New chat, Quick capture, Ask about clipboard, Pause
+//! sync, and Quit. The tooltip doubles as a generation-in-progress
+//! indicator: every active generation, whether started by the frontend or by
+//! background work, holds a count that switches the tooltip to a
+//! "generating" state.
+//!
+//! When close-to-tray is enabled, closing the main window hides it instead
+//! of quitting; the window event hook in `run()` consults [`close_to_tray`].
+//! The flag mirrors the `closeToTray` setting.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use serde::Serialize;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::clipboard::{self, ClipboardAction};
+use crate::commands::events;
+use crate::commands::i18n;
+use crate::commands::quick_capture;
+use crate::commands::settings;
+use crate::error::GibberError;
+
+/// Identifier of the tray icon.
+const TRAY_ID: &str = "main";
+
+/// Tooltip shown while idle.
+const IDLE_TOOLTIP: &str = "Gibber AI";
+
+/// Menu item identifiers.
+const MENU_NEW_CHAT: &str = "new_chat";
+const MENU_QUICK_CAPTURE: &str = "quick_capture";
+const MENU_ASK_CLIPBOARD: &str = "ask_clipboard";
+const MENU_PAUSE_SYNC: &str = "pause_sync";
+const MENU_QUIT: &str = "quit";
+
+/// Shared tray state.
+pub struct TrayState {
+    close_to_tray: AtomicBool,
+    sync_paused: AtomicBool,
+    active_generations: AtomicUsize,
+}
+
+impl Default for TrayState {
+    fn default() -> Self {
+        Self {
+            close_to_tray: AtomicBool::new(true),
+            sync_paused: AtomicBool::new(false),
+            active_generations: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Snapshot of the tray state.
+#[derive(Debug, Clone, Copy, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayStatus {
+    /// Whether closing the main window hides it to the tray
+    pub close_to_tray: bool,
+    /// Whether background sync is paused
+    pub sync_paused: bool,
+    /// Whether any generation is in progress
+    pub generating: bool,
+}
+
+impl TrayState {
+    fn status(&self) -> TrayStatus {
+        TrayStatus {
+            close_to_tray: self.close_to_tray.load(Ordering::Relaxed),
+            sync_paused: self.sync_paused.load(Ordering::Relaxed),
+            generating: self.active_generations.load(Ordering::Relaxed) > 0,
+        }
+    }
+
+    /// Counts a generation in; `true` if it is the only one running.
+    fn begin_generation(&self) -> bool {
+        self.active_generations.fetch_add(1, Ordering::Relaxed) == 0
+    }
+
+    /// Counts a generation out, never going below zero; `true` if it was
+    /// the last one running.
+    fn end_generation(&self) -> bool {
+        self.active_generations
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok_and(|previous| previous == 1)
+    }
+}
+
+/// Marks a generation as in progress for as long as it is alive.
+///
+/// Background features hold one of these while waiting on the model so the
+/// tray indicator reflects work the user didn't start from the UI.
+pub struct GenerationGuard {
+    app: AppHandle,
+}
+
+impl GenerationGuard {
+    /// Starts tracking a generation.
+    pub fn new(app: &AppHandle) -> Self {
+        begin(app);
+        Self { app: app.clone() }
+    }
+}
+
+impl Drop for GenerationGuard {
+    fn drop(&mut self) {
+        end(&self.app);
+    }
+}
+
+fn begin(app: &AppHandle) {
+    if app.state::<TrayState>().begin_generation() {
+        refresh_indicator(app, true);
+    }
+}
+
+fn end(app: &AppHandle) {
+    if app.state::<TrayState>().end_generation() {
+        refresh_indicator(app, false);
+    }
+}
+
+fn refresh_indicator(app: &AppHandle, generating: bool) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let tooltip = if generating {
+            i18n::text(app, "tray-generating")
+        } else {
+            IDLE_TOOLTIP.to_string()
+        };
+        if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+            tracing::warn!("failed to update tooltip: {e}");
+        }
+    }
+    events::emit_typed(app, events::TRAY_GENERATING, &generating);
+}
+
+/// Returns whether closing the main window should hide it to the tray.
+pub fn close_to_tray(app: &AppHandle) -> bool {
+    app.state::<TrayState>()
+        .close_to_tray
+        .load(Ordering::Relaxed)
+}
+
+/// Returns whether background sync is paused from the tray.
+pub fn is_sync_paused(app: &AppHandle) -> bool {
+    app.state::<TrayState>().sync_paused.load(Ordering::Relaxed)
+}
+
+/// Pauses or resumes background sync and tells the frontend.
+pub(crate) fn set_sync_paused(app: &AppHandle, paused: bool) {
+    app.state::<TrayState>()
+        .sync_paused
+        .store(paused, Ordering::Relaxed);
+    events::emit_typed(app, events::TRAY_SYNC_PAUSED, &paused);
+}
+
+fn handle_menu_event(app: &AppHandle, event: &MenuEvent) {
+    let result = match event.id().as_ref() {
+        MENU_NEW_CHAT => quick_capture::focus_main_window(app).and_then(|()| {
+            events::emit_typed_to(
+                app,
+                quick_capture::MAIN_WINDOW_LABEL,
+                events::TRAY_NEW_CHAT,
+                &(),
+            )
+        }),
+        MENU_QUICK_CAPTURE => quick_capture::show(app),
+        MENU_ASK_CLIPBOARD => {
+            if let Err(e) = clipboard::ask(app, ClipboardAction::Explain) {
+                tracing::error!("asking about the clipboard failed: {e}");
+            }
+            Ok(())
+        }
+        MENU_PAUSE_SYNC => {
+            set_sync_paused(app, !is_sync_paused(app));
+            Ok(())
+        }
+        MENU_QUIT => {
+            app.exit(0);
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        tracing::error!("menu action failed: {e}");
+    }
+}
+
+/// Builds the tray menu in the current locale.
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let item = |id: &str, text_id: &str| {
+        MenuItem::with_id(app, id, i18n::text(app, text_id), true, None::<&str>)
+    };
+    let pause_sync = CheckMenuItem::with_id(
+        app,
+        MENU_PAUSE_SYNC,
+        i18n::text(app, "tray-pause-sync"),
+        true,
+        is_sync_paused(app),
+        None::<&str>,
+    )?;
+    Menu::with_items(
+        app,
+        &[
+            &item(MENU_NEW_CHAT, "tray-new-chat")?,
+            &item(MENU_QUICK_CAPTURE, "tray-quick-capture")?,
+            &item(MENU_ASK_CLIPBOARD, "tray-ask-clipboard")?,
+            &PredefinedMenuItem::separator(app)?,
+            &pause_sync,
+            &PredefinedMenuItem::separator(app)?,
+            &item(MENU_QUIT, "tray-quit")?,
+        ],
+    )
+}
+
+/// Rebuilds the tray menu after the locale changes.
+pub(crate) fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = build_menu(app).and_then(|menu| tray.set_menu(Some(menu))) {
+        tracing::warn!("failed to relabel tray menu: {e}");
+    }
+}
+
+/// Creates the tray icon and its menu.
+///
+/// Must be called after [`TrayState`] and the `i18n` localizer have been
+/// added to managed state.
+///
+/// # Errors
+///
+/// Returns an error if the menu or tray icon cannot be created.
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(IDLE_TOOLTIP)
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| handle_menu_event(app, &event))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                if let Err(e) = quick_capture::focus_main_window(tray.app_handle()) {
+                    tracing::warn!("failed to focus main window: {e}");
+                }
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Returns the current tray state.
+///
+/// # Example
+///
+/// ```typescript
+/// const { closeToTray, syncPaused, generating } = await invoke("get_tray_status");
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_tray_status(state: State<'_, TrayState>) -> TrayStatus {
+    state.status()
+}
+
+/// Sets the close-to-tray flag; called when the setting changes.
+pub fn set_close_to_tray_flag(app: &AppHandle, enabled: bool) {
+    app.state::<TrayState>()
+        .close_to_tray
+        .store(enabled, Ordering::Relaxed);
+}
+
+/// Enables or disables hiding the main window to the tray on close.
+///
+/// Shorthand for updating the `closeToTray` setting.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the setting cannot be stored.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and State by value
+pub fn set_close_to_tray(
+    app: AppHandle,
+    state: State<'_, TrayState>,
+    enabled: bool,
+) -> Result<TrayStatus, GibberError> {
+    settings::update(&app, &serde_json::json!({ "closeToTray": enabled }))?;
+    Ok(state.status())
+}
+
+/// Marks a frontend generation as started.
+///
+/// Every call must be paired with [`end_generation`].
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn begin_generation(app: AppHandle) {
+    begin(&app);
+}
+
+/// Marks a frontend generation as finished.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn end_generation(app: AppHandle) {
+    end(&app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_state() {
+        let status = TrayState::default().status();
+        assert!(status.close_to_tray);
+        assert!(!status.sync_paused);
+        assert!(!status.generating);
+    }
+
+    #[test]
+    fn test_generation_count_saturates() {
+        let state = TrayState::default();
+        assert!(state.begin_generation());
+        assert!(!state.begin_generation());
+        assert!(!state.end_generation());
+        assert!(state.end_generation());
+        assert!(!state.status().generating);
+
+        // An unpaired end leaves the count at zero instead of wrapping.
+        assert!(!state.end_generation());
+        assert!(!state.status().generating);
+        assert!(state.begin_generation());
+        assert!(state.status().generating);
+    }
+}
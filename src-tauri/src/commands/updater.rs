@@ -0,0 +1,449 @@
+//! Auto-updates with stable and beta channels.
+//!
+//! Each channel has its own update manifest. A background loop checks the
+//! selected channel periodically and, if enabled, downloads the update
+//! while emitting progress events; `restart_to_update` installs it. The
+//! updater plugin verifies every download against the public key baked in
+//! at build time (`GIBBER_UPDATER_PUBKEY`); builds without a key don't
+//! update.
+//!
+//! Releases roll out in stages: a manifest may carry a `rollout` percentage,
+//! and background checks only offer the update to installs whose stable
+//! bucket falls below it. Manual checks always see the newest release.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::commands::events;
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the update preferences.
+pub const SETTINGS_KEY: &str = "updater";
+
+/// Settings key holding the per-install rollout identifier.
+pub const INSTALL_ID_KEY: &str = "install_id";
+
+/// Public key for update signatures, provided at build time.
+const PUBKEY: Option<&str> = option_env!("GIBBER_UPDATER_PUBKEY");
+
+/// Delay before the first background check after launch.
+const FIRST_CHECK_DELAY: Duration = Duration::from_mins(1);
+
+/// Interval between background checks.
+const CHECK_INTERVAL: Duration = Duration::from_hours(6);
+
+/// Release channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateChannel {
+    /// Tested releases
+    #[default]
+    Stable,
+    /// Pre-releases
+    Beta,
+}
+
+impl UpdateChannel {
+    /// Manifest URL of the channel.
+    pub(crate) fn endpoint(self) -> &'static str {
+        match self {
+            Self::Stable => {
+                "https://github.com/EverythingSings/gibber-ai/releases/latest/download/latest.json"
+            }
+            Self::Beta => {
+                "https://github.com/EverythingSings/gibber-ai/releases/download/beta/latest.json"
+            }
+        }
+    }
+}
+
+/// Update preferences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UpdateSettings {
+    /// Channel to receive updates from
+    pub channel: UpdateChannel,
+    /// Check for updates in the background
+    pub auto_check: bool,
+    /// Download found updates in the background
+    pub auto_download: bool,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            auto_check: true,
+            auto_download: true,
+        }
+    }
+}
+
+/// An available update.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    /// Version of the update
+    pub version: String,
+    /// Version currently running
+    pub current_version: String,
+    /// Release notes
+    pub notes: Option<String>,
+    /// Publication date
+    pub date: Option<String>,
+    /// Channel the update came from
+    pub channel: UpdateChannel,
+}
+
+/// Payload of [`events::UPDATER_PROGRESS`].
+#[derive(Debug, Clone, Copy, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+#[specta(rename = "UpdateProgress")]
+pub struct ProgressPayload {
+    /// Bytes downloaded so far
+    pub downloaded: u64,
+    /// Total size, if the server reported it
+    pub content_length: Option<u64>,
+}
+
+#[derive(Default)]
+struct UpdaterInner {
+    found: Option<Update>,
+    downloaded: Option<(Update, Vec<u8>)>,
+    downloading: bool,
+}
+
+/// Found and downloaded updates.
+#[derive(Default)]
+pub struct UpdaterState(Mutex<UpdaterInner>);
+
+impl UpdaterState {
+    fn lock(&self) -> MutexGuard<'_, UpdaterInner> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Builds the updater plugin.
+pub fn plugin() -> tauri::plugin::TauriPlugin<tauri::Wry, tauri_plugin_updater::Config> {
+    let builder = tauri_plugin_updater::Builder::new();
+    match PUBKEY {
+        Some(key) => builder.pubkey(key).build(),
+        None => builder.build(),
+    }
+}
+
+/// Loads the stored update preferences, falling back to defaults.
+pub(crate) fn load_settings(app: &AppHandle) -> UpdateSettings {
+    db::read_setting(&app.state::<Database>().conn(), SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Returns this install's rollout bucket in `0..100`, creating it on first use.
+fn rollout_bucket(app: &AppHandle) -> u8 {
+    let db = app.state::<Database>();
+    let conn = db.conn();
+    let id = if let Some(id) = db::read_setting(&conn, INSTALL_ID_KEY).ok().flatten() {
+        id
+    } else {
+        let id = serde_json::to_string(&db::new_id()).unwrap_or_default();
+        if let Err(e) = db::write_setting(&conn, INSTALL_ID_KEY, &id) {
+            tracing::error!("failed to store install id: {e}");
+        }
+        id
+    };
+    bucket_for(&id)
+}
+
+/// Maps an install identifier to a stable bucket in `0..100`.
+fn bucket_for(install_id: &str) -> u8 {
+    let hex: String = install_id
+        .chars()
+        .filter(char::is_ascii_hexdigit)
+        .take(8)
+        .collect();
+    let value = u32::from_str_radix(&hex, 16).unwrap_or(0);
+    u8::try_from(value % 100).unwrap_or(0)
+}
+
+/// Returns whether a release with the given `rollout` percentage is offered
+/// to an install in `bucket`. Releases without a rollout go to everyone.
+fn in_rollout(rollout: Option<f64>, bucket: u8) -> bool {
+    !matches!(rollout, Some(percent) if f64::from(bucket) >= percent)
+}
+
+fn info(update: &Update, channel: UpdateChannel) -> UpdateInfo {
+    UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        date: update.date.map(|date| date.to_string()),
+        channel,
+    }
+}
+
+/// Checks the selected channel for an update.
+async fn check(app: &AppHandle, respect_rollout: bool) -> Result<Option<UpdateInfo>, GibberError> {
+    if PUBKEY.is_none() {
+        return Err(GibberError::new(
+            "DISABLED",
+            "Updates are disabled in this build",
+        ));
+    }
+    let settings = load_settings(app);
+    let endpoint = Url::parse(settings.channel.endpoint())
+        .map_err(|e| GibberError::new("UPDATE_FAILED", e.to_string()))?;
+    let mut builder = app.updater_builder().endpoints(vec![endpoint.clone()])?;
+    if let Some(proxy) = network::updater_proxy(app) {
+        builder = builder.proxy(proxy);
+    }
+    let updater = builder.build()?;
+    let started = Instant::now();
+    let result = updater.check().await;
+    network_activity::record(
+        app,
+        Service::Updater,
+        "updater.check",
+        &endpoint,
+        started,
+        None,
+        result.as_ref().err().map(ToString::to_string),
+    );
+    let Some(update) = result? else {
+        return Ok(None);
+    };
+    if respect_rollout {
+        let rollout = update
+            .raw_json
+            .get("rollout")
+            .and_then(serde_json::Value::as_f64);
+        if !in_rollout(rollout, rollout_bucket(app)) {
+            return Ok(None);
+        }
+    }
+
+    let info = info(&update, settings.channel);
+    app.state::<UpdaterState>().lock().found = Some(update);
+    events::emit_typed(app, events::UPDATER_AVAILABLE, &info);
+    Ok(Some(info))
+}
+
+/// Downloads the found update, emitting progress.
+async fn download(app: &AppHandle) -> Result<(), GibberError> {
+    let update = {
+        let state = app.state::<UpdaterState>();
+        let mut inner = state.lock();
+        if inner.downloading {
+            return Err(GibberError::new("BUSY", "An update is already downloading"));
+        }
+        let update = inner
+            .found
+            .clone()
+            .ok_or_else(|| GibberError::new("NO_UPDATE", "No update has been found"))?;
+        inner.downloading = true;
+        update
+    };
+
+    let mut downloaded = 0u64;
+    let started = Instant::now();
+    let result = update
+        .download(
+            |chunk, content_length| {
+                downloaded += u64::try_from(chunk).unwrap_or(0);
+                let payload = ProgressPayload {
+                    downloaded,
+                    content_length,
+                };
+                events::emit_typed(app, events::UPDATER_PROGRESS, &payload);
+            },
+            || {},
+        )
+        .await;
+    network_activity::record(
+        app,
+        Service::Updater,
+        "updater.download",
+        &update.download_url,
+        started,
+        Some(downloaded),
+        result.as_ref().err().map(ToString::to_string),
+    );
+
+    let state = app.state::<UpdaterState>();
+    let mut inner = state.lock();
+    inner.downloading = false;
+    let bytes = result?;
+    let info = info(&update, load_settings(app).channel);
+    inner.downloaded = Some((update, bytes));
+    drop(inner);
+    events::emit_typed(app, events::UPDATER_READY, &info);
+    Ok(())
+}
+
+/// Starts the background update loop.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if PUBKEY.is_none() {
+            return;
+        }
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        loop {
+            let settings = load_settings(&app);
+            let already_downloaded = app.state::<UpdaterState>().lock().downloaded.is_some();
+            if settings.auto_check && !already_downloaded {
+                match check(&app, true).await {
+                    Ok(Some(_)) if settings.auto_download => {
+                        if let Err(e) = download(&app).await {
+                            tracing::error!("download failed: {e}");
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("check failed: {e}"),
+                }
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Stores the update preferences.
+///
+/// Switching channels forgets any update found on the previous channel.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+pub fn save_settings(
+    app: &AppHandle,
+    settings: UpdateSettings,
+) -> Result<UpdateSettings, GibberError> {
+    if load_settings(app).channel != settings.channel {
+        let state = app.state::<UpdaterState>();
+        let mut inner = state.lock();
+        inner.found = None;
+        inner.downloaded = None;
+    }
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&app.state::<Database>().conn(), SETTINGS_KEY, &value)?;
+    Ok(settings)
+}
+
+/// Returns the update preferences.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn get_update_settings(app: AppHandle) -> UpdateSettings {
+    load_settings(&app)
+}
+
+/// Replaces the update preferences.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_update_settings", { settings: { ...current, channel: "beta" } });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and args by value
+pub fn set_update_settings(
+    app: AppHandle,
+    settings: UpdateSettings,
+) -> Result<UpdateSettings, GibberError> {
+    save_settings(&app, settings)
+}
+
+/// Checks the selected channel for an update now.
+///
+/// # Returns
+///
+/// The available update, or `None` if the app is up to date.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `DISABLED` in builds without an
+/// update key, or `UPDATE_FAILED` if the manifest cannot be fetched.
+#[tauri::command]
+#[specta::specta]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, GibberError> {
+    check(&app, false).await
+}
+
+/// Downloads the update found by the last check.
+///
+/// Progress arrives via `updater://progress`, completion via
+/// `updater://ready`.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NO_UPDATE` if nothing was found,
+/// `BUSY` if a download is running, or `UPDATE_FAILED` if the download or
+/// its signature verification fails.
+#[tauri::command]
+#[specta::specta]
+pub async fn download_update(app: AppHandle) -> Result<(), GibberError> {
+    download(&app).await
+}
+
+/// Installs the downloaded update and restarts the app.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_READY` if no update has been
+/// downloaded, or `UPDATE_FAILED` if installing fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and State by value
+pub fn restart_to_update(
+    app: AppHandle,
+    state: State<'_, UpdaterState>,
+) -> Result<(), GibberError> {
+    let (update, bytes) = state
+        .lock()
+        .downloaded
+        .take()
+        .ok_or_else(|| GibberError::new("NOT_READY", "No update has been downloaded"))?;
+    update.install(bytes)?;
+    app.restart();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_is_stable_and_bounded() {
+        let id = "\"3f2a9c1e-0000-4000-8000-000000000000\"";
+        assert_eq!(bucket_for(id), bucket_for(id));
+        assert!(bucket_for(id) < 100);
+        assert_eq!(bucket_for("\"00000064-rest\""), 0);
+    }
+
+    #[test]
+    fn test_rollout_gate() {
+        assert!(in_rollout(None, 99));
+        assert!(in_rollout(Some(10.0), 9));
+        assert!(!in_rollout(Some(10.0), 10));
+        assert!(!in_rollout(Some(0.0), 0));
+    }
+
+    #[test]
+    fn test_settings_default_to_stable() {
+        let settings: UpdateSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.channel, UpdateChannel::Stable);
+        assert!(settings.auto_check);
+    }
+}
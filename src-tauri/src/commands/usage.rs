@@ -0,0 +1,237 @@
+//! Recently-used and favorite ranking for pickers.
+//!
+//! Each use of a model or preset (presets carry the personas and
+//! templates) bumps a counter. Pickers list items by frecency: the use
+//! count weighted by how recently the item was last used, with favorites
+//! always on top. Chats streamed by `stream_chat` and conversations
+//! started from a preset are counted by the backend; pickers count other
+//! choices with `record_usage`.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Items returned when no limit is given.
+const DEFAULT_LIMIT: u32 = 20;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Recency weights as (maximum age in days, weight), newest first. Older
+/// items get [`OLDEST_WEIGHT`].
+const RECENCY_WEIGHTS: [(i64, u64); 4] = [(4, 100), (14, 70), (31, 50), (90, 30)];
+
+/// Weight of items last used more than 90 days ago.
+const OLDEST_WEIGHT: u64 = 10;
+
+/// What kind of item is ranked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageKind {
+    /// A model, by model ID
+    Model,
+    /// A new-chat preset, by preset ID
+    Preset,
+}
+
+impl UsageKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Model => "model",
+            Self::Preset => "preset",
+        }
+    }
+}
+
+/// A ranked item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedItem {
+    /// Model or preset ID
+    pub id: String,
+    /// Times used
+    pub uses: u32,
+    /// Last use in Unix milliseconds, if ever used
+    pub last_used_at: Option<i64>,
+    /// Whether the user marked it a favorite
+    pub favorite: bool,
+    /// Frecency score the list is ordered by
+    pub score: u64,
+}
+
+fn recency_weight(last_used_at: Option<i64>, now: i64) -> u64 {
+    let Some(last_used_at) = last_used_at else {
+        return 0;
+    };
+    let age_days = (now - last_used_at).max(0) / DAY_MS;
+    RECENCY_WEIGHTS
+        .iter()
+        .find(|(max_days, _)| age_days <= *max_days)
+        .map_or(OLDEST_WEIGHT, |(_, weight)| *weight)
+}
+
+/// Counts a use at `now`.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn bump(conn: &Connection, kind: UsageKind, id: &str, now: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO usage_stats (kind, item_id, uses, last_used_at, favorite)
+         VALUES (?1, ?2, 1, ?3, 0)
+         ON CONFLICT (kind, item_id) DO UPDATE SET uses = uses + 1, last_used_at = ?3",
+        params![kind.as_str(), id, now],
+    )?;
+    Ok(())
+}
+
+fn ranked(
+    conn: &Connection,
+    kind: UsageKind,
+    now: i64,
+    limit: usize,
+) -> rusqlite::Result<Vec<RankedItem>> {
+    let mut stmt = conn
+        .prepare("SELECT item_id, uses, last_used_at, favorite FROM usage_stats WHERE kind = ?1")?;
+    let mut items = stmt
+        .query_map([kind.as_str()], |row| {
+            let uses: u32 = row.get(1)?;
+            let last_used_at: Option<i64> = row.get(2)?;
+            Ok(RankedItem {
+                id: row.get(0)?,
+                uses,
+                last_used_at,
+                favorite: row.get(3)?,
+                score: u64::from(uses) * recency_weight(last_used_at, now),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    items.sort_by(|a, b| {
+        b.favorite
+            .cmp(&a.favorite)
+            .then(b.score.cmp(&a.score))
+            .then(b.last_used_at.cmp(&a.last_used_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    items.truncate(limit);
+    Ok(items)
+}
+
+/// Counts a use, logging rather than failing.
+pub fn record(app: &AppHandle, kind: UsageKind, id: &str) {
+    let Some(db) = app.try_state::<Database>() else {
+        return;
+    };
+    let conn = db.conn();
+    if let Err(e) = bump(&conn, kind, id, db::now_millis()) {
+        tracing::error!("failed to record use of {} {id}: {e}", kind.as_str());
+    }
+}
+
+/// Counts a use of an item chosen in a picker.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if `id` is empty, or
+/// `DATABASE` if the use cannot be stored.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn record_usage(db: State<'_, Database>, kind: UsageKind, id: &str) -> Result<(), GibberError> {
+    if id.trim().is_empty() {
+        return Err(GibberError::invalid("An item ID is required"));
+    }
+    Ok(bump(&db.conn(), kind, id, db::now_millis())?)
+}
+
+/// Lists items of a kind, favorites first, then by frecency.
+///
+/// Items never used or favorited aren't listed; pickers show them after
+/// the ranked ones.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const models = await invoke("get_ranked_usage", { kind: "model", limit: 5 });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_ranked_usage(
+    db: State<'_, Database>,
+    kind: UsageKind,
+    limit: Option<u32>,
+) -> Result<Vec<RankedItem>, GibberError> {
+    let limit = usize::try_from(limit.unwrap_or(DEFAULT_LIMIT)).unwrap_or(usize::MAX);
+    Ok(ranked(&db.conn(), kind, db::now_millis(), limit)?)
+}
+
+/// Marks an item a favorite, or unmarks it.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if `id` is empty, or
+/// `DATABASE` if the flag cannot be stored.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn set_favorite(
+    db: State<'_, Database>,
+    kind: UsageKind,
+    id: &str,
+    favorite: bool,
+) -> Result<(), GibberError> {
+    if id.trim().is_empty() {
+        return Err(GibberError::invalid("An item ID is required"));
+    }
+    db.conn().execute(
+        "INSERT INTO usage_stats (kind, item_id, uses, last_used_at, favorite)
+         VALUES (?1, ?2, 0, NULL, ?3)
+         ON CONFLICT (kind, item_id) DO UPDATE SET favorite = ?3",
+        params![kind.as_str(), id, favorite],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recency_weights_decay() {
+        let now = 200 * DAY_MS;
+        assert_eq!(recency_weight(Some(now), now), 100);
+        assert_eq!(recency_weight(Some(now - 10 * DAY_MS), now), 70);
+        assert_eq!(recency_weight(Some(now - 120 * DAY_MS), now), OLDEST_WEIGHT);
+        assert_eq!(recency_weight(None, now), 0);
+    }
+
+    #[test]
+    fn test_favorites_then_frecency() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        let now = 200 * DAY_MS;
+        for _ in 0..5 {
+            bump(&conn, UsageKind::Model, "old", now - 100 * DAY_MS).unwrap();
+        }
+        bump(&conn, UsageKind::Model, "recent", now).unwrap();
+        bump(&conn, UsageKind::Preset, "bass", now).unwrap();
+        conn.execute(
+            "INSERT INTO usage_stats (kind, item_id, uses, favorite) VALUES ('model', 'fav', 0, 1)",
+            [],
+        )
+        .unwrap();
+
+        let models = ranked(&conn, UsageKind::Model, now, 10).unwrap();
+        let ids: Vec<_> = models.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["fav", "recent", "old"]);
+        assert_eq!(models[2].uses, 5);
+        assert_eq!(ranked(&conn, UsageKind::Preset, now, 10).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,271 @@
+//! Current weather and forecasts from Open-Meteo.
+//!
+//! `get_weather`, also offered to the model as a tool, reports the
+//! conditions at a named place, or at the user's location when location
+//! context is on (see [`location`]). Temperatures are in °C, precipitation
+//! in mm, and wind in km/h; the calculator converts them when asked.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::commands::location::{self, Location, LocationSource};
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::commands::plugins::PluginTool;
+use crate::error::GibberError;
+
+/// Open-Meteo forecast endpoint.
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+/// Days forecast when the caller doesn't say.
+pub const DEFAULT_DAYS: u32 = 3;
+
+/// Most days Open-Meteo forecasts.
+pub const MAX_DAYS: u32 = 16;
+
+/// Time allowed for the forecast request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Conditions right now.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentWeather {
+    /// Air temperature in °C
+    pub temperature_c: f64,
+    /// Felt temperature in °C
+    pub feels_like_c: Option<f64>,
+    /// Precipitation over the last hour, in mm
+    pub precipitation_mm: Option<f64>,
+    /// Wind speed at 10 m, in km/h
+    pub wind_kmh: Option<f64>,
+    /// Weather description, e.g. `Light rain`
+    pub conditions: String,
+}
+
+/// The forecast for one day.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyForecast {
+    /// Local date, `YYYY-MM-DD`
+    pub date: String,
+    /// Weather description
+    pub conditions: String,
+    /// Highest temperature in °C
+    pub max_c: Option<f64>,
+    /// Lowest temperature in °C
+    pub min_c: Option<f64>,
+    /// Highest chance of precipitation during the day, in percent
+    pub precipitation_chance: Option<f64>,
+    /// Total precipitation in mm
+    pub precipitation_mm: Option<f64>,
+}
+
+/// What `get_weather` returns.
+#[derive(Debug, Clone, PartialEq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherReport {
+    /// Where the weather is for
+    pub location: Location,
+    /// Conditions now
+    pub current: CurrentWeather,
+    /// Forecast from today
+    pub daily: Vec<DailyForecast>,
+}
+
+/// Describes a WMO weather interpretation code.
+fn conditions(code: Option<u64>) -> String {
+    let text = match code {
+        Some(0) => "Clear sky",
+        Some(1) => "Mainly clear",
+        Some(2) => "Partly cloudy",
+        Some(3) => "Overcast",
+        Some(45 | 48) => "Fog",
+        Some(51) => "Light drizzle",
+        Some(53) => "Drizzle",
+        Some(55) => "Dense drizzle",
+        Some(56 | 57) => "Freezing drizzle",
+        Some(61) => "Light rain",
+        Some(63) => "Rain",
+        Some(65) => "Heavy rain",
+        Some(66 | 67) => "Freezing rain",
+        Some(71) => "Light snow",
+        Some(73) => "Snow",
+        Some(75) => "Heavy snow",
+        Some(77) => "Snow grains",
+        Some(80) => "Light showers",
+        Some(81) => "Showers",
+        Some(82) => "Violent showers",
+        Some(85 | 86) => "Snow showers",
+        Some(95) => "Thunderstorm",
+        Some(96 | 99) => "Thunderstorm with hail",
+        _ => "Unknown",
+    };
+    text.to_string()
+}
+
+/// Reads an Open-Meteo forecast response.
+fn parse_forecast(body: &Value) -> Option<(CurrentWeather, Vec<DailyForecast>)> {
+    let now = &body["current"];
+    let current = CurrentWeather {
+        temperature_c: now["temperature_2m"].as_f64()?,
+        feels_like_c: now["apparent_temperature"].as_f64(),
+        precipitation_mm: now["precipitation"].as_f64(),
+        wind_kmh: now["wind_speed_10m"].as_f64(),
+        conditions: conditions(now["weather_code"].as_u64()),
+    };
+    let daily = &body["daily"];
+    let at = |key: &str, day: usize| daily[key].get(day).and_then(Value::as_f64);
+    let days = daily["time"]
+        .as_array()?
+        .iter()
+        .enumerate()
+        .filter_map(|(day, date)| {
+            Some(DailyForecast {
+                date: date.as_str()?.to_string(),
+                conditions: conditions(daily["weather_code"].get(day).and_then(Value::as_u64)),
+                max_c: at("temperature_2m_max", day),
+                min_c: at("temperature_2m_min", day),
+                precipitation_chance: at("precipitation_probability_max", day),
+                precipitation_mm: at("precipitation_sum", day),
+            })
+        })
+        .collect();
+    Some((current, days))
+}
+
+/// Tool definition of `get_weather`, offered by `get_data_tools`.
+pub(crate) fn tool() -> PluginTool {
+    PluginTool {
+        name: "get_weather".to_string(),
+        description: "Gets the current weather and a daily forecast. Leave out the location for \
+                      the user's own, if they have shared it."
+            .to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "location": { "type": "string", "description": "Town or city" },
+                "days": { "type": "integer", "minimum": 1, "maximum": MAX_DAYS }
+            }
+        })
+        .to_string(),
+    }
+}
+
+/// Returns the current weather and a forecast of `days` days (3 by
+/// default, at most 16).
+///
+/// Without a `location` the user's location is used, which needs
+/// location context to be on.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` if no location is given
+/// and location context is off, `NOT_FOUND` if the place is unknown, or a
+/// network error.
+///
+/// # Example
+///
+/// ```typescript
+/// const { current, daily } = await invoke("get_weather", { location: null, days: 2 });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn get_weather(
+    app: AppHandle,
+    location: Option<String>,
+    days: Option<u32>,
+) -> Result<WeatherReport, GibberError> {
+    let place = location.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    let location = match place {
+        Some(place) => location::geocode(&app, place, LocationSource::Named).await?,
+        None => location::current(&app).await?.ok_or_else(|| {
+            GibberError::new(
+                "FORBIDDEN",
+                "The user hasn't shared their location; ask which place they mean",
+            )
+        })?,
+    };
+    let days = days.unwrap_or(DEFAULT_DAYS).clamp(1, MAX_DAYS).to_string();
+    let client = network::client(&app, Service::Weather)?;
+    let builder = client
+        .get(FORECAST_URL)
+        .query(&[
+            ("latitude", location.latitude.to_string()),
+            ("longitude", location.longitude.to_string()),
+            (
+                "current",
+                "temperature_2m,apparent_temperature,precipitation,weather_code,wind_speed_10m"
+                    .to_string(),
+            ),
+            (
+                "daily",
+                "weather_code,temperature_2m_max,temperature_2m_min,\
+                 precipitation_probability_max,precipitation_sum"
+                    .to_string(),
+            ),
+            ("timezone", "auto".to_string()),
+            ("forecast_days", days),
+        ])
+        .timeout(REQUEST_TIMEOUT);
+    let response =
+        network_activity::send(&app, Service::Weather, "weather.forecast", builder).await?;
+    if !response.status().is_success() {
+        return Err(GibberError::new(
+            "NETWORK",
+            format!("Open-Meteo returned HTTP {}", response.status()),
+        ));
+    }
+    let body: Value = response.json().await?;
+    let (current, daily) = parse_forecast(&body)
+        .ok_or_else(|| GibberError::new("PARSE_ERROR", "The forecast could not be read"))?;
+    Ok(WeatherReport {
+        location,
+        current,
+        daily,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_forecast() {
+        let body = json!({
+            "current": {
+                "temperature_2m": 11.4,
+                "apparent_temperature": 9.8,
+                "precipitation": 0.3,
+                "weather_code": 61,
+                "wind_speed_10m": 14.0
+            },
+            "daily": {
+                "time": ["2026-10-14", "2026-10-15"],
+                "weather_code": [61, 3],
+                "temperature_2m_max": [13.1, 15.0],
+                "temperature_2m_min": [7.2, null],
+                "precipitation_probability_max": [80, 10],
+                "precipitation_sum": [4.2, 0.0]
+            }
+        });
+        let (current, daily) = parse_forecast(&body).unwrap();
+        assert_eq!(current.conditions, "Light rain");
+        assert!(current.feels_like_c.is_some_and(|c| (c - 9.8).abs() < 1e-9));
+        assert_eq!(daily.len(), 2);
+        assert!(daily[0]
+            .precipitation_chance
+            .is_some_and(|p| (p - 80.0).abs() < 1e-9));
+        assert_eq!(daily[1].conditions, "Overcast");
+        assert!(daily[1].min_c.is_none());
+    }
+
+    #[test]
+    fn test_missing_current_fails() {
+        assert!(parse_forecast(&json!({ "daily": { "time": [] } })).is_none());
+        assert_eq!(conditions(None), "Unknown");
+        assert_eq!(conditions(Some(96)), "Thunderstorm with hail");
+    }
+}
@@ -0,0 +1,723 @@
+//! Webhook notifications for scheduled tasks and workflow runs.
+//!
+//! Users register URLs together with the events they care about. When a
+//! scheduled task or workflow run finishes, every matching webhook receives
+//! a JSON `POST`:
+//!
+//! ```json
+//! { "id": "…", "event": "task.succeeded", "createdAt": 1718000000000, "data": { … } }
+//! ```
+//!
+//! Requests are signed with the webhook's secret: `X-Gibber-Signature` holds
+//! `sha256=<hex>`, the HMAC-SHA256 of `<X-Gibber-Timestamp>.<body>`.
+//! Deliveries failing with a network error, `429` or a `5xx` are retried
+//! with exponential backoff; each one is recorded in a delivery log, and
+//! deliveries interrupted by a restart are resumed on the next launch.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::network::{self, Service};
+use crate::commands::network_activity;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Attempts made before a delivery is marked failed.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry; quadrupled for each further one.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+/// Time allowed for the receiver to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliveries kept in the log, across all webhooks.
+const LOG_CAPACITY: u32 = 500;
+
+fn not_found(id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("Webhook not found: {id}"))
+}
+
+/// Something a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum WebhookEvent {
+    /// A scheduled task ran successfully
+    #[serde(rename = "task.succeeded")]
+    TaskSucceeded,
+    /// A scheduled task failed
+    #[serde(rename = "task.failed")]
+    TaskFailed,
+    /// A workflow run finished successfully
+    #[serde(rename = "workflow.succeeded")]
+    WorkflowSucceeded,
+    /// A workflow run failed
+    #[serde(rename = "workflow.failed")]
+    WorkflowFailed,
+}
+
+impl WebhookEvent {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::TaskSucceeded => "task.succeeded",
+            Self::TaskFailed => "task.failed",
+            Self::WorkflowSucceeded => "workflow.succeeded",
+            Self::WorkflowFailed => "workflow.failed",
+        }
+    }
+}
+
+/// Delivery state of one event to one webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not yet accepted; retries may follow
+    Pending,
+    /// Accepted with a `2xx`
+    Delivered,
+    /// Gave up
+    Failed,
+}
+
+impl DeliveryStatus {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "delivered" => Self::Delivered,
+            "failed" => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// A registered webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    /// Unique identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// `http(s)://` URL receiving the events
+    pub url: String,
+    /// Key the payloads are signed with
+    pub secret: String,
+    /// Subscribed events
+    pub events: Vec<WebhookEvent>,
+    /// Whether events are sent
+    pub enabled: bool,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+}
+
+/// Fields for creating or updating a webhook.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookInput {
+    /// Display name
+    pub name: String,
+    /// `http(s)://` URL receiving the events
+    pub url: String,
+    /// Events to subscribe to; must not be empty
+    pub events: Vec<WebhookEvent>,
+    /// Whether events are sent (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+/// An entry of the delivery log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    /// Unique identifier, sent as `X-Gibber-Delivery`
+    pub id: String,
+    /// The receiving webhook
+    pub webhook_id: String,
+    /// The delivered event
+    pub event: WebhookEvent,
+    /// Delivery state
+    pub status: DeliveryStatus,
+    /// Attempts so far
+    pub attempts: u32,
+    /// HTTP status of the last answer
+    pub response_status: Option<u16>,
+    /// Error of the last attempt
+    pub last_error: Option<String>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// Time of the last attempt in Unix milliseconds
+    pub updated_at: i64,
+}
+
+const WEBHOOK_COLUMNS: &str = "id, name, url, secret, events, enabled, created_at";
+
+fn webhook_from_row(row: &Row<'_>) -> rusqlite::Result<Webhook> {
+    let events: String = row.get(4)?;
+    Ok(Webhook {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        url: row.get(2)?,
+        secret: row.get(3)?,
+        events: serde_json::from_str(&events).unwrap_or_default(),
+        enabled: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn list_all(conn: &Connection) -> rusqlite::Result<Vec<Webhook>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {WEBHOOK_COLUMNS} FROM webhooks ORDER BY created_at, rowid"
+    ))?;
+    let webhooks = stmt.query_map([], webhook_from_row)?.collect();
+    webhooks
+}
+
+fn load_webhook(conn: &Connection, id: &str) -> rusqlite::Result<Option<Webhook>> {
+    conn.query_row(
+        &format!("SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE id = ?1"),
+        [id],
+        webhook_from_row,
+    )
+    .optional()
+}
+
+const DELIVERY_COLUMNS: &str =
+    "id, webhook_id, event, status, attempts, response_status, last_error, created_at, updated_at";
+
+fn delivery_from_row(row: &Row<'_>) -> rusqlite::Result<WebhookDelivery> {
+    let event: String = row.get(2)?;
+    let status: String = row.get(3)?;
+    Ok(WebhookDelivery {
+        id: row.get(0)?,
+        webhook_id: row.get(1)?,
+        event: serde_json::from_value(serde_json::Value::String(event)).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        status: DeliveryStatus::parse(&status),
+        attempts: row.get(4)?,
+        response_status: row.get(5)?,
+        last_error: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn validate(input: &WebhookInput) -> Result<(), GibberError> {
+    if input.name.trim().is_empty() {
+        return Err(GibberError::new("INVALID_WEBHOOK", "The name is empty"));
+    }
+    let valid_url = tauri::Url::parse(&input.url)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+    if !valid_url {
+        return Err(GibberError::new(
+            "INVALID_URL",
+            format!("Not an http(s) URL: {}", input.url),
+        ));
+    }
+    if input.events.is_empty() {
+        return Err(GibberError::new(
+            "INVALID_WEBHOOK",
+            "Subscribe to at least one event",
+        ));
+    }
+    Ok(())
+}
+
+fn new_secret() -> String {
+    format!("whsec_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Returns the hex HMAC-SHA256 of `message` under `secret`.
+fn signature(secret: &str, message: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Delay before retrying after `attempts` failed attempts.
+fn retry_delay(attempts: u32) -> Duration {
+    RETRY_BASE_DELAY * 4u32.pow(attempts.saturating_sub(1))
+}
+
+/// Whether a receiver's answer is worth retrying.
+fn is_retryable(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+fn record_attempt(
+    conn: &Connection,
+    id: &str,
+    status: DeliveryStatus,
+    response_status: Option<u16>,
+    error: Option<&str>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE webhook_deliveries
+         SET status = ?1, attempts = attempts + 1, response_status = ?2, last_error = ?3, updated_at = ?4
+         WHERE id = ?5",
+        params![status.as_str(), response_status, error, db::now_millis(), id],
+    )?;
+    Ok(())
+}
+
+/// Sends a delivery until it is accepted or retries run out.
+async fn deliver(app: AppHandle, delivery_id: String) {
+    loop {
+        let pending = {
+            let db = app.state::<Database>();
+            let conn = db.conn();
+            conn.query_row(
+                "SELECT w.url, w.secret, d.event, d.payload, d.attempts
+                 FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id
+                 WHERE d.id = ?1 AND d.status = 'pending'",
+                [&delivery_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, u32>(4)?,
+                    ))
+                },
+            )
+            .optional()
+        };
+        let (url, secret, event, payload, attempts) = match pending {
+            Ok(Some(pending)) => pending,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("failed to load webhook delivery: {e}");
+                return;
+            }
+        };
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let signed = format!("{timestamp}.{payload}");
+        let result = match network::client(&app, Service::Webhooks) {
+            Ok(client) => {
+                let builder = client
+                    .post(&url)
+                    .timeout(REQUEST_TIMEOUT)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header("X-Gibber-Event", &event)
+                    .header("X-Gibber-Delivery", &delivery_id)
+                    .header("X-Gibber-Timestamp", timestamp.to_string())
+                    .header(
+                        "X-Gibber-Signature",
+                        format!("sha256={}", signature(&secret, signed.as_bytes())),
+                    )
+                    .body(payload);
+                network_activity::send(&app, Service::Webhooks, "webhooks.deliver", builder)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        let attempts = attempts + 1;
+        let (status, response_status, error) = match &result {
+            Ok(response) if response.status().is_success() => (
+                DeliveryStatus::Delivered,
+                Some(response.status().as_u16()),
+                None,
+            ),
+            Ok(response) => {
+                let code = response.status().as_u16();
+                let status = if is_retryable(code) && attempts < MAX_ATTEMPTS {
+                    DeliveryStatus::Pending
+                } else {
+                    DeliveryStatus::Failed
+                };
+                (status, Some(code), Some(format!("HTTP {code}")))
+            }
+            Err(e) => {
+                let status = if attempts < MAX_ATTEMPTS {
+                    DeliveryStatus::Pending
+                } else {
+                    DeliveryStatus::Failed
+                };
+                (status, None, Some(e.clone()))
+            }
+        };
+        if let Err(e) = record_attempt(
+            &app.state::<Database>().conn(),
+            &delivery_id,
+            status,
+            response_status,
+            error.as_deref(),
+        ) {
+            tracing::error!("failed to record webhook delivery: {e}");
+            return;
+        }
+        match status {
+            DeliveryStatus::Pending => tokio::time::sleep(retry_delay(attempts)).await,
+            DeliveryStatus::Delivered => return,
+            DeliveryStatus::Failed => {
+                tracing::warn!(%delivery_id, attempts, ?error, "webhook delivery failed");
+                return;
+            }
+        }
+    }
+}
+
+/// Queues a delivery of `event` to each enabled webhook subscribed to it.
+///
+/// Returns the new delivery ids.
+fn queue(
+    conn: &Connection,
+    event: WebhookEvent,
+    data: &serde_json::Value,
+) -> rusqlite::Result<Vec<String>> {
+    let now = db::now_millis();
+    let mut ids = Vec::new();
+    for webhook in list_all(conn)? {
+        if !webhook.enabled || !webhook.events.contains(&event) {
+            continue;
+        }
+        let id = db::new_id();
+        let payload = serde_json::json!({
+            "id": id,
+            "event": event,
+            "createdAt": now,
+            "data": data,
+        });
+        conn.execute(
+            "INSERT INTO webhook_deliveries (id, webhook_id, event, payload, status, attempts, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, 'pending', 0, ?5, ?5)",
+            params![id, webhook.id, event.as_str(), payload.to_string(), now],
+        )?;
+        ids.push(id);
+    }
+    if !ids.is_empty() {
+        conn.execute(
+            "DELETE FROM webhook_deliveries WHERE id NOT IN
+             (SELECT id FROM webhook_deliveries ORDER BY created_at DESC, rowid DESC LIMIT ?1)",
+            [LOG_CAPACITY],
+        )?;
+    }
+    Ok(ids)
+}
+
+/// Sends `event` with `data` to the subscribed webhooks in the background.
+pub(crate) fn dispatch(app: &AppHandle, event: WebhookEvent, data: &impl Serialize) {
+    let data = match serde_json::to_value(data) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::error!("failed to serialize webhook payload: {e}");
+            return;
+        }
+    };
+    let ids = queue(&app.state::<Database>().conn(), event, &data);
+    match ids {
+        Ok(ids) => {
+            for id in ids {
+                tauri::async_runtime::spawn(deliver(app.clone(), id));
+            }
+        }
+        Err(e) => tracing::error!("failed to queue webhook deliveries: {e}"),
+    }
+}
+
+/// Resumes deliveries interrupted by the previous run.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+pub fn start(app: &AppHandle) {
+    let pending: rusqlite::Result<Vec<String>> = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        conn.prepare("SELECT id FROM webhook_deliveries WHERE status = 'pending'")
+            .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+    };
+    match pending {
+        Ok(ids) => {
+            for id in ids {
+                tauri::async_runtime::spawn(deliver(app.clone(), id));
+            }
+        }
+        Err(e) => tracing::error!("failed to resume webhook deliveries: {e}"),
+    }
+}
+
+/// Lists registered webhooks, oldest first.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_webhooks(db: State<'_, Database>) -> Result<Vec<Webhook>, GibberError> {
+    Ok(list_all(&db.conn())?)
+}
+
+/// Registers a webhook with a freshly generated secret.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_URL` or `INVALID_WEBHOOK` if
+/// the input is invalid, or `DATABASE` if it cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const hook = await invoke("create_webhook", {
+///   input: {
+///     name: "Studio server",
+///     url: "https://studio.example.com/hooks/gibber",
+///     events: ["task.succeeded", "task.failed"],
+///   },
+/// });
+/// console.log(hook.secret); // verify X-Gibber-Signature with this
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn create_webhook(
+    db: State<'_, Database>,
+    input: WebhookInput,
+) -> Result<Webhook, GibberError> {
+    validate(&input)?;
+    let conn = db.conn();
+    let id = db::new_id();
+    conn.execute(
+        "INSERT INTO webhooks (id, name, url, secret, events, enabled, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id,
+            input.name.trim(),
+            input.url,
+            new_secret(),
+            serde_json::to_string(&input.events).unwrap_or_default(),
+            input.enabled,
+            db::now_millis(),
+        ],
+    )?;
+    load_webhook(&conn, &id)?.ok_or_else(|| not_found(&id))
+}
+
+/// Updates a webhook; its secret is kept.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the webhook doesn't
+/// exist, or `INVALID_URL`/`INVALID_WEBHOOK` if the input is invalid.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn update_webhook(
+    db: State<'_, Database>,
+    id: &str,
+    input: WebhookInput,
+) -> Result<Webhook, GibberError> {
+    validate(&input)?;
+    let conn = db.conn();
+    let updated = conn.execute(
+        "UPDATE webhooks SET name = ?1, url = ?2, events = ?3, enabled = ?4 WHERE id = ?5",
+        params![
+            input.name.trim(),
+            input.url,
+            serde_json::to_string(&input.events).unwrap_or_default(),
+            input.enabled,
+            id,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(not_found(id));
+    }
+    load_webhook(&conn, id)?.ok_or_else(|| not_found(id))
+}
+
+/// Replaces a webhook's secret, e.g. after it leaked.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the webhook doesn't exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn rotate_webhook_secret(db: State<'_, Database>, id: &str) -> Result<Webhook, GibberError> {
+    let conn = db.conn();
+    let updated = conn.execute(
+        "UPDATE webhooks SET secret = ?1 WHERE id = ?2",
+        params![new_secret(), id],
+    )?;
+    if updated == 0 {
+        return Err(not_found(id));
+    }
+    load_webhook(&conn, id)?.ok_or_else(|| not_found(id))
+}
+
+/// Deletes a webhook and its delivery log.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the webhook doesn't exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_webhook(db: State<'_, Database>, id: &str) -> Result<(), GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM webhooks WHERE id = ?1", [id])?;
+    if deleted == 0 {
+        return Err(not_found(id));
+    }
+    Ok(())
+}
+
+/// Lists recent deliveries, newest first, optionally for one webhook.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+///
+/// # Example
+///
+/// ```typescript
+/// const log = await invoke("list_webhook_deliveries", { webhookId: hook.id, limit: 50 });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn list_webhook_deliveries(
+    db: State<'_, Database>,
+    webhook_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<WebhookDelivery>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries
+         WHERE ?1 IS NULL OR webhook_id = ?1
+         ORDER BY created_at DESC, rowid DESC LIMIT ?2"
+    ))?;
+    let deliveries = stmt
+        .query_map(
+            params![webhook_id, limit.unwrap_or(100).min(LOG_CAPACITY)],
+            delivery_from_row,
+        )?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(deliveries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(url: &str, events: Vec<WebhookEvent>) -> WebhookInput {
+        WebhookInput {
+            name: "Studio".to_string(),
+            url: url.to_string(),
+            events,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_signature_matches_hmac_sha256() {
+        assert_eq!(
+            signature("key", b"The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(32));
+        assert!(is_retryable(503));
+        assert!(is_retryable(429));
+        assert!(!is_retryable(404));
+    }
+
+    #[test]
+    fn test_validate() {
+        let events = vec![WebhookEvent::TaskFailed];
+        assert!(validate(&input("https://example.com/hook", events.clone())).is_ok());
+        assert_eq!(
+            validate(&input("ftp://example.com", events))
+                .unwrap_err()
+                .code(),
+            "INVALID_URL"
+        );
+        assert_eq!(
+            validate(&input("https://example.com", Vec::new()))
+                .unwrap_err()
+                .code(),
+            "INVALID_WEBHOOK"
+        );
+    }
+
+    #[test]
+    fn test_queue_only_matching_webhooks() {
+        let db = Database::open_in_memory().unwrap();
+        let conn = db.conn();
+        for (url, events, enabled) in [
+            (
+                "https://a.example.com",
+                vec![WebhookEvent::TaskFailed],
+                true,
+            ),
+            (
+                "https://b.example.com",
+                vec![WebhookEvent::TaskSucceeded],
+                true,
+            ),
+            (
+                "https://c.example.com",
+                vec![WebhookEvent::TaskFailed],
+                false,
+            ),
+        ] {
+            conn.execute(
+                "INSERT INTO webhooks (id, name, url, secret, events, enabled, created_at)
+                 VALUES (?1, 'hook', ?2, 'secret', ?3, ?4, 0)",
+                params![
+                    db::new_id(),
+                    url,
+                    serde_json::to_string(&events).unwrap(),
+                    enabled
+                ],
+            )
+            .unwrap();
+        }
+
+        let data = serde_json::json!({ "taskId": "t1" });
+        let ids = queue(&conn, WebhookEvent::TaskFailed, &data).unwrap();
+        assert_eq!(ids.len(), 1);
+        let (event, payload): (String, String) = conn
+            .query_row(
+                "SELECT event, payload FROM webhook_deliveries WHERE id = ?1",
+                [&ids[0]],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(event, "task.failed");
+        let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(payload["data"]["taskId"], "t1");
+        assert_eq!(payload["event"], "task.failed");
+    }
+}
@@ -0,0 +1,329 @@
+//! Window registry and multi-window conversations.
+//!
+//! Window size, position, and maximized state are persisted across restarts
+//! by the window-state plugin. On top of that, any conversation can be opened
+//! in its own window; the [`WindowRegistry`] maps those windows to their
+//! conversations so backend events about a conversation reach the window
+//! showing it, and each window streams independently.
+//!
+//! Only the main and conversation windows may run sensitive commands such
+//! as reading credentials or importing settings; see [`require_trusted`]
+//! and the permission sets in `permissions/windows.toml`.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::commands::conversations;
+use crate::commands::events::{self, Event};
+use crate::commands::quick_capture::{self, MAIN_WINDOW_LABEL};
+use crate::db::Database;
+use crate::error::GibberError;
+
+/// Label prefix of conversation windows.
+const CONVERSATION_WINDOW_PREFIX: &str = "conversation-";
+
+/// Default size of a conversation window in logical pixels.
+const CONVERSATION_WINDOW_WIDTH: f64 = 720.0;
+const CONVERSATION_WINDOW_HEIGHT: f64 = 800.0;
+
+/// A conversation shown in its own window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationWindow {
+    /// Window label
+    pub label: String,
+    /// Conversation shown in the window
+    pub conversation_id: String,
+}
+
+/// Tracks which window shows which conversation.
+#[derive(Default)]
+pub struct WindowRegistry(Mutex<HashMap<String, String>>);
+
+impl WindowRegistry {
+    fn windows(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the label of the window showing a conversation, if any.
+    pub fn window_for(&self, conversation_id: &str) -> Option<String> {
+        self.windows()
+            .iter()
+            .find(|(_, id)| id.as_str() == conversation_id)
+            .map(|(label, _)| label.clone())
+    }
+
+    /// Forgets a window that has been destroyed.
+    pub fn remove(&self, label: &str) {
+        self.windows().remove(label);
+    }
+
+    fn insert(&self, label: String, conversation_id: String) {
+        self.windows().insert(label, conversation_id);
+    }
+
+    fn list(&self) -> Vec<ConversationWindow> {
+        let mut windows: Vec<_> = self
+            .windows()
+            .iter()
+            .map(|(label, conversation_id)| ConversationWindow {
+                label: label.clone(),
+                conversation_id: conversation_id.clone(),
+            })
+            .collect();
+        windows.sort_by(|a, b| a.label.cmp(&b.label));
+        windows
+    }
+}
+
+/// The conversation the user is looking at, as reported by the frontend.
+///
+/// Features acting on "the current conversation" from outside the UI (such
+/// as the screenshot shortcut) read it with [`CurrentConversation::get`].
+#[derive(Default)]
+pub struct CurrentConversation(Mutex<Option<String>>);
+
+impl CurrentConversation {
+    /// Returns the current conversation ID, if any.
+    pub fn get(&self) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Returns the window label used for a conversation.
+fn label_for(conversation_id: &str) -> String {
+    let safe: String = conversation_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{CONVERSATION_WINDOW_PREFIX}{safe}")
+}
+
+/// Whether the window labelled `label` may run sensitive commands.
+fn is_trusted(label: &str) -> bool {
+    label == MAIN_WINDOW_LABEL || label.starts_with(CONVERSATION_WINDOW_PREFIX)
+}
+
+/// Fails unless `window` is the main window or a conversation window.
+///
+/// Capabilities already keep other windows from invoking sensitive
+/// commands; those commands call this as well, so loosening a capability by
+/// mistake doesn't expose them.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` for any other window.
+pub fn require_trusted(window: &WebviewWindow) -> Result<(), GibberError> {
+    let label = window.label();
+    if is_trusted(label) {
+        return Ok(());
+    }
+    tracing::warn!(
+        label,
+        "refused a sensitive command from an untrusted window"
+    );
+    Err(GibberError::new(
+        "FORBIDDEN",
+        format!("The {label} window is not allowed to do this"),
+    ))
+}
+
+/// Builds the window-state plugin.
+///
+/// The quick-capture window is excluded; it always opens centered.
+#[cfg(desktop)]
+pub fn window_state_plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri_plugin_window_state::Builder::default()
+        .with_denylist(&[quick_capture::WINDOW_LABEL])
+        .build()
+}
+
+/// Emits an event to the window showing a conversation.
+///
+/// Falls back to the main window when the conversation has no window of its
+/// own, so exactly one view handles the event.
+///
+/// # Errors
+///
+/// Returns an error if the event cannot be emitted.
+pub fn emit_to_conversation<T: Serialize>(
+    app: &AppHandle,
+    conversation_id: &str,
+    event: Event<T>,
+    payload: &T,
+) -> tauri::Result<()> {
+    let label = app
+        .state::<WindowRegistry>()
+        .window_for(conversation_id)
+        .unwrap_or_else(|| MAIN_WINDOW_LABEL.to_string());
+    events::emit_typed_to(app, &label, event, payload)
+}
+
+/// Opens a conversation in its own window, or focuses the existing one.
+///
+/// A newly created window starts scrolled to `scroll_top` when given; the
+/// webview reads it from the `scrollTop` query parameter.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation doesn't
+/// exist, or `WINDOW` if the window cannot be created.
+pub fn show_conversation(
+    app: &AppHandle,
+    conversation_id: &str,
+    scroll_top: Option<f64>,
+) -> Result<ConversationWindow, GibberError> {
+    let db = app.state::<Database>();
+    if !conversations::conversation_exists(&db.conn(), conversation_id)? {
+        return Err(GibberError::new(
+            "NOT_FOUND",
+            format!("Conversation {conversation_id} not found"),
+        ));
+    }
+
+    let label = label_for(conversation_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        window.unminimize()?;
+        window.show()?;
+        window.set_focus()?;
+    } else {
+        let title = conversations::load_conversation(&db.conn(), conversation_id)?
+            .map_or_else(|| "Gibber AI".to_string(), |c| c.conversation.title);
+        let url = match scroll_top {
+            Some(scroll_top) => format!("conversation/{conversation_id}?scrollTop={scroll_top}"),
+            None => format!("conversation/{conversation_id}"),
+        };
+        WebviewWindowBuilder::new(app, &label, WebviewUrl::App(url.into()))
+            .title(title)
+            .inner_size(CONVERSATION_WINDOW_WIDTH, CONVERSATION_WINDOW_HEIGHT)
+            .min_inner_size(480.0, 400.0)
+            .build()?;
+    }
+    app.state::<WindowRegistry>()
+        .insert(label.clone(), conversation_id.to_string());
+    Ok(ConversationWindow {
+        label,
+        conversation_id: conversation_id.to_string(),
+    })
+}
+
+/// Opens a conversation in its own window, or focuses the existing one.
+///
+/// # Returns
+///
+/// The window showing the conversation.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the conversation doesn't
+/// exist, or `WINDOW` if the window cannot be created.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("open_conversation_window", { conversationId: id });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn open_conversation_window(
+    app: AppHandle,
+    conversation_id: String,
+) -> Result<ConversationWindow, GibberError> {
+    show_conversation(&app, &conversation_id, None)
+}
+
+/// Lists conversations that are open in their own windows.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_conversation_windows(registry: State<'_, WindowRegistry>) -> Vec<ConversationWindow> {
+    registry.list()
+}
+
+/// Closes the window showing a conversation, if there is one.
+///
+/// # Returns
+///
+/// Returns `true` if a window was closed.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the window cannot be closed.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle and State by value
+pub fn close_conversation_window(
+    app: AppHandle,
+    registry: State<'_, WindowRegistry>,
+    conversation_id: &str,
+) -> Result<bool, GibberError> {
+    let Some(label) = registry.window_for(conversation_id) else {
+        return Ok(false);
+    };
+    if let Some(window) = app.get_webview_window(&label) {
+        window.close()?;
+    }
+    registry.remove(&label);
+    Ok(true)
+}
+
+/// Records the conversation shown in the focused window.
+///
+/// Windows call this when they show a conversation or gain focus; pass
+/// `null` when no conversation is open.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("set_current_conversation", { conversationId: id });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn set_current_conversation(
+    current: State<'_, CurrentConversation>,
+    conversation_id: Option<String>,
+) {
+    *current.0.lock().unwrap_or_else(PoisonError::into_inner) = conversation_id;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_is_window_safe() {
+        assert_eq!(label_for("abc-123"), "conversation-abc-123");
+        assert_eq!(label_for("a/b c"), "conversation-a_b_c");
+    }
+
+    #[test]
+    fn test_registry_tracks_windows() {
+        let registry = WindowRegistry::default();
+        registry.insert("conversation-a".to_string(), "a".to_string());
+        assert_eq!(registry.window_for("a").as_deref(), Some("conversation-a"));
+        assert_eq!(registry.list().len(), 1);
+        registry.remove("conversation-a");
+        assert!(registry.window_for("a").is_none());
+    }
+
+    #[test]
+    fn test_only_app_windows_are_trusted() {
+        assert!(is_trusted(MAIN_WINDOW_LABEL));
+        assert!(is_trusted(&label_for("abc")));
+        assert!(!is_trusted(quick_capture::WINDOW_LABEL));
+    }
+}
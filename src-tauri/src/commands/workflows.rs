@@ -0,0 +1,834 @@
+//! Prompt workflows.
+//!
+//! A workflow is an ordered list of prompt steps where each step's output
+//! feeds into the next. Step prompts are templates with these placeholders:
+//!
+//! - `{{input}}` - output of the previous step (the run input for step one)
+//! - `{{initial}}` - the input the run was started with
+//! - `{{steps.<name>}}` - output of an earlier step by name
+//!
+//! A template without any placeholder gets the previous output appended
+//! after a blank line, so simple chains need no templating at all.
+//!
+//! A step may set `extract`, a JSON pointer (RFC 6901, `""` for the whole
+//! document) applied to the first JSON value found in the response; the
+//! selected value becomes the step output. Failed steps are retried with a
+//! linear backoff. Every run and step is recorded so the history can be
+//! reviewed later, and a desktop notification reports the finished run.
+
+use std::time::Duration;
+
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use crate::chat::{self, ChatMessage, ChatRequest, MessageRole};
+use crate::commands::events;
+use crate::commands::i18n;
+use crate::commands::notifications::{self, NotificationKind};
+use crate::commands::telemetry;
+use crate::commands::webhooks::{self, WebhookEvent};
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Upper bound for a step's `maxRetries`.
+const MAX_RETRIES_LIMIT: u32 = 5;
+
+/// Delay before the first retry; later retries wait proportionally longer.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+fn not_found(what: &str, id: &str) -> GibberError {
+    GibberError::new("NOT_FOUND", format!("{what} {id} not found"))
+}
+
+/// One prompt in a workflow.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStep {
+    /// Step name, unique within the workflow
+    pub name: String,
+    /// Prompt template for this step
+    pub prompt: String,
+    /// Optional system prompt
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Model override; defaults to the standard tier
+    #[serde(default)]
+    pub model: Option<String>,
+    /// JSON pointer selecting the step output from the response
+    #[serde(default)]
+    pub extract: Option<String>,
+    /// How many times to retry a failed step (default: 1)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+const fn default_max_retries() -> u32 {
+    1
+}
+
+/// A saved workflow.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Workflow {
+    /// Unique identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Steps in execution order
+    pub steps: Vec<WorkflowStep>,
+    /// Creation time in Unix milliseconds
+    pub created_at: i64,
+    /// Last update time in Unix milliseconds
+    pub updated_at: i64,
+}
+
+/// Fields for creating or updating a workflow.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowInput {
+    /// Display name
+    pub name: String,
+    /// Steps in execution order
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// Status of a run or a single step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkflowStatus {
+    /// Work is in progress
+    Running,
+    /// A failed attempt is about to be retried (events only)
+    Retrying,
+    /// Completed successfully
+    Succeeded,
+    /// Gave up after exhausting retries
+    Failed,
+}
+
+impl WorkflowStatus {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Retrying => "retrying",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "retrying" => Self::Retrying,
+            "succeeded" => Self::Succeeded,
+            "failed" => Self::Failed,
+            _ => Self::Running,
+        }
+    }
+}
+
+/// Recorded outcome of one step in a run.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowRunStep {
+    /// Position of the step in the workflow
+    pub step_index: i64,
+    /// Step name at the time of the run
+    pub name: String,
+    /// Step status
+    pub status: WorkflowStatus,
+    /// Number of attempts made
+    pub attempts: u32,
+    /// Step output, on success
+    pub output: Option<String>,
+    /// Last error, on failure
+    pub error: Option<String>,
+    /// Start time in Unix milliseconds
+    pub started_at: i64,
+    /// Finish time in Unix milliseconds
+    pub finished_at: Option<i64>,
+}
+
+/// A recorded workflow run.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowRun {
+    /// Unique identifier
+    pub id: String,
+    /// The workflow that ran
+    pub workflow_id: String,
+    /// Overall status
+    pub status: WorkflowStatus,
+    /// Input the run was started with
+    pub input: String,
+    /// Output of the last step, on success
+    pub output: Option<String>,
+    /// Error of the failing step, on failure
+    pub error: Option<String>,
+    /// Start time in Unix milliseconds
+    pub started_at: i64,
+    /// Finish time in Unix milliseconds
+    pub finished_at: Option<i64>,
+    /// Per-step records (empty in run listings)
+    pub steps: Vec<WorkflowRunStep>,
+}
+
+/// Payload of [`events::WORKFLOW_STEP`].
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StepEventPayload {
+    /// The run this step belongs to
+    pub run_id: String,
+    /// The workflow being run
+    pub workflow_id: String,
+    /// Position of the step in the workflow
+    pub step_index: usize,
+    /// Step name
+    pub name: String,
+    /// New step status
+    pub status: WorkflowStatus,
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+    /// Step output, when succeeded
+    pub output: Option<String>,
+    /// Error message, when retrying or failed
+    pub error: Option<String>,
+}
+
+/// Validates a workflow definition.
+fn validate_input(input: &WorkflowInput) -> Result<(), GibberError> {
+    if input.name.trim().is_empty() {
+        return Err(GibberError::invalid("Workflow name is required"));
+    }
+    if input.steps.is_empty() {
+        return Err(GibberError::invalid("A workflow needs at least one step"));
+    }
+    for (index, step) in input.steps.iter().enumerate() {
+        if step.name.trim().is_empty() {
+            return Err(GibberError::invalid(format!(
+                "Step {} has no name",
+                index + 1
+            )));
+        }
+        if input.steps[..index].iter().any(|s| s.name == step.name) {
+            return Err(GibberError::invalid(format!(
+                "Duplicate step name \"{}\"",
+                step.name
+            )));
+        }
+        if step.prompt.trim().is_empty() {
+            return Err(GibberError::invalid(format!(
+                "Step \"{}\" has no prompt",
+                step.name
+            )));
+        }
+        if step.max_retries > MAX_RETRIES_LIMIT {
+            return Err(GibberError::invalid(format!(
+                "Step \"{}\" may retry at most {MAX_RETRIES_LIMIT} times",
+                step.name
+            )));
+        }
+        if let Some(pointer) = &step.extract {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                return Err(GibberError::invalid(format!(
+                    "Step \"{}\" extract must be a JSON pointer such as \"/summary\"",
+                    step.name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fills a step template with run values.
+fn render_template(
+    template: &str,
+    input: &str,
+    initial: &str,
+    outputs: &[(String, String)],
+) -> String {
+    if !template.contains("{{") {
+        return if input.is_empty() {
+            template.to_string()
+        } else {
+            format!("{template}\n\n{input}")
+        };
+    }
+    let mut rendered = template
+        .replace("{{input}}", input)
+        .replace("{{initial}}", initial);
+    for (name, output) in outputs {
+        rendered = rendered.replace(&format!("{{{{steps.{name}}}}}"), output);
+    }
+    rendered
+}
+
+/// Finds the first JSON object or array in a model response.
+///
+/// Tries, in order: the whole response, fenced code blocks, and the span
+/// between the first opening and last closing bracket.
+pub(crate) fn find_json(text: &str) -> Option<Value> {
+    let parse = |candidate: &str| serde_json::from_str::<Value>(candidate.trim()).ok();
+
+    if let Some(value) = parse(text) {
+        return Some(value);
+    }
+    for block in text.split("```").skip(1).step_by(2) {
+        let body = block.split_once('\n').map_or(block, |(_, rest)| rest);
+        if let Some(value) = parse(body) {
+            return Some(value);
+        }
+    }
+    let start = text.find(['{', '['])?;
+    let end = text.rfind(['}', ']'])?;
+    (end > start).then(|| parse(&text[start..=end])).flatten()
+}
+
+/// Applies a step's JSON extraction to a response.
+fn extract_output(response: &str, pointer: &str) -> Result<String, String> {
+    let document = find_json(response).ok_or("Response did not contain JSON")?;
+    let selected = document
+        .pointer(pointer)
+        .ok_or_else(|| format!("JSON pointer {pointer} matched nothing"))?;
+    Ok(match selected {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    })
+}
+
+fn workflow_from_row(row: &Row<'_>) -> rusqlite::Result<Workflow> {
+    let steps: String = row.get(2)?;
+    Ok(Workflow {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        steps: serde_json::from_str(&steps)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e)))?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
+}
+
+fn run_from_row(row: &Row<'_>) -> rusqlite::Result<WorkflowRun> {
+    let status: String = row.get(2)?;
+    Ok(WorkflowRun {
+        id: row.get(0)?,
+        workflow_id: row.get(1)?,
+        status: WorkflowStatus::parse(&status),
+        input: row.get(3)?,
+        output: row.get(4)?,
+        error: row.get(5)?,
+        started_at: row.get(6)?,
+        finished_at: row.get(7)?,
+        steps: Vec::new(),
+    })
+}
+
+fn run_step_from_row(row: &Row<'_>) -> rusqlite::Result<WorkflowRunStep> {
+    let status: String = row.get(2)?;
+    Ok(WorkflowRunStep {
+        step_index: row.get(0)?,
+        name: row.get(1)?,
+        status: WorkflowStatus::parse(&status),
+        attempts: row.get(3)?,
+        output: row.get(4)?,
+        error: row.get(5)?,
+        started_at: row.get(6)?,
+        finished_at: row.get(7)?,
+    })
+}
+
+const WORKFLOW_COLUMNS: &str = "id, name, steps, created_at, updated_at";
+const RUN_COLUMNS: &str = "id, workflow_id, status, input, output, error, started_at, finished_at";
+
+pub(crate) fn load_workflow(conn: &Connection, id: &str) -> Result<Workflow, GibberError> {
+    conn.query_row(
+        &format!("SELECT {WORKFLOW_COLUMNS} FROM workflows WHERE id = ?1"),
+        [id],
+        workflow_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found("Workflow", id))
+}
+
+fn load_run(conn: &Connection, run_id: &str) -> Result<WorkflowRun, GibberError> {
+    let mut run = conn
+        .query_row(
+            &format!("SELECT {RUN_COLUMNS} FROM workflow_runs WHERE id = ?1"),
+            [run_id],
+            run_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| not_found("Workflow run", run_id))?;
+    let mut stmt = conn.prepare(
+        "SELECT step_index, name, status, attempts, output, error, started_at, finished_at
+         FROM workflow_run_steps WHERE run_id = ?1 ORDER BY step_index",
+    )?;
+    run.steps = stmt
+        .query_map([run_id], run_step_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(run)
+}
+
+/// Identifies the run a step belongs to.
+struct RunContext<'a> {
+    app: &'a AppHandle,
+    run_id: &'a str,
+    workflow_id: &'a str,
+    initial: &'a str,
+}
+
+impl RunContext<'_> {
+    fn emit_step(
+        &self,
+        index: usize,
+        step: &WorkflowStep,
+        status: WorkflowStatus,
+        attempt: u32,
+        result: Option<&Result<String, String>>,
+    ) {
+        let (output, error) = match result {
+            Some(Ok(output)) => (Some(output.clone()), None),
+            Some(Err(error)) => (None, Some(error.clone())),
+            None => (None, None),
+        };
+        let payload = StepEventPayload {
+            run_id: self.run_id.to_string(),
+            workflow_id: self.workflow_id.to_string(),
+            step_index: index,
+            name: step.name.clone(),
+            status,
+            attempt,
+            output,
+            error,
+        };
+        events::emit_typed(self.app, events::WORKFLOW_STEP, &payload);
+    }
+
+    fn record_step(
+        &self,
+        index: usize,
+        step: &WorkflowStep,
+        status: WorkflowStatus,
+        attempts: u32,
+        result: Option<&Result<String, String>>,
+    ) {
+        let (output, error) = match result {
+            Some(Ok(output)) => (Some(output.as_str()), None),
+            Some(Err(error)) => (None, Some(error.as_str())),
+            None => (None, None),
+        };
+        let now = db::now_millis();
+        let finished_at = (status != WorkflowStatus::Running).then_some(now);
+        let db = self.app.state::<Database>();
+        let conn = db.conn();
+        if let Err(e) = conn.execute(
+            "INSERT INTO workflow_run_steps (run_id, step_index, name, status, attempts, output, error, started_at, finished_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (run_id, step_index) DO UPDATE SET
+                 status = excluded.status, attempts = excluded.attempts,
+                 output = excluded.output, error = excluded.error, finished_at = excluded.finished_at",
+            params![
+                self.run_id,
+                i64::try_from(index).unwrap_or(i64::MAX),
+                step.name,
+                status.as_str(),
+                attempts,
+                output,
+                error,
+                now,
+                finished_at,
+            ],
+        ) {
+            tracing::error!(run_id = %self.run_id, step = index, "failed to record step: {e}");
+        }
+    }
+
+    /// Runs one step with retries and returns its output.
+    async fn run_step(
+        &self,
+        index: usize,
+        step: &WorkflowStep,
+        input: &str,
+        outputs: &[(String, String)],
+    ) -> Result<String, String> {
+        let prompt = render_template(&step.prompt, input, self.initial, outputs);
+        let mut messages = Vec::new();
+        if let Some(system) = step.system_prompt.as_deref().filter(|s| !s.is_empty()) {
+            messages.push(ChatMessage::new(MessageRole::System, system));
+        }
+        messages.push(ChatMessage::new(MessageRole::User, prompt));
+        let request = ChatRequest::new(step.model.as_deref(), messages);
+
+        self.record_step(index, step, WorkflowStatus::Running, 0, None);
+        let attempts = step.max_retries + 1;
+        let mut attempt = 1;
+        loop {
+            self.emit_step(index, step, WorkflowStatus::Running, attempt, None);
+            let result = chat::complete(self.app, &request)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|completion| match &step.extract {
+                    Some(pointer) => extract_output(&completion.content, pointer),
+                    None => Ok(completion.content),
+                });
+
+            if result.is_ok() || attempt >= attempts {
+                let status = if result.is_ok() {
+                    WorkflowStatus::Succeeded
+                } else {
+                    WorkflowStatus::Failed
+                };
+                self.record_step(index, step, status, attempt, Some(&result));
+                self.emit_step(index, step, status, attempt, Some(&result));
+                return result;
+            }
+
+            self.emit_step(
+                index,
+                step,
+                WorkflowStatus::Retrying,
+                attempt,
+                Some(&result),
+            );
+            tokio::time::sleep(RETRY_BASE_DELAY * attempt).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Executes every step of a workflow and records the run, sending a
+/// notification when it ends if `notify` is set.
+async fn execute(
+    app: &AppHandle,
+    workflow: &Workflow,
+    input: String,
+    notify: bool,
+) -> Result<WorkflowRun, GibberError> {
+    let run_id = db::new_id();
+    {
+        let db = app.state::<Database>();
+        db.conn().execute(
+            "INSERT INTO workflow_runs (id, workflow_id, status, input, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                run_id,
+                workflow.id,
+                WorkflowStatus::Running.as_str(),
+                input,
+                db::now_millis()
+            ],
+        )?;
+    }
+
+    let context = RunContext {
+        app,
+        run_id: &run_id,
+        workflow_id: &workflow.id,
+        initial: &input,
+    };
+    let mut previous = input.clone();
+    let mut outputs: Vec<(String, String)> = Vec::new();
+    let mut failure = None;
+    for (index, step) in workflow.steps.iter().enumerate() {
+        match context.run_step(index, step, &previous, &outputs).await {
+            Ok(output) => {
+                outputs.push((step.name.clone(), output.clone()));
+                previous = output;
+            }
+            Err(error) => {
+                failure = Some(format!("Step \"{}\" failed: {error}", step.name));
+                break;
+            }
+        }
+    }
+
+    let run = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        let (status, output) = match &failure {
+            None => (WorkflowStatus::Succeeded, Some(previous)),
+            Some(_) => (WorkflowStatus::Failed, None),
+        };
+        conn.execute(
+            "UPDATE workflow_runs SET status = ?1, output = ?2, error = ?3, finished_at = ?4 WHERE id = ?5",
+            params![status.as_str(), output, failure, db::now_millis(), run_id],
+        )?;
+        load_run(&conn, &run_id)?
+    };
+    events::emit_typed(app, events::WORKFLOW_RUN_COMPLETED, &run);
+    let event = if failure.is_none() {
+        WebhookEvent::WorkflowSucceeded
+    } else {
+        WebhookEvent::WorkflowFailed
+    };
+    webhooks::dispatch(app, event, &run);
+    if notify {
+        let body = match &failure {
+            None => i18n::text(app, "notification-workflow-finished"),
+            Some(error) => {
+                i18n::text_with(app, "notification-failed", &[("error", error.as_str())])
+            }
+        };
+        notifications::notify(app, NotificationKind::Workflow, &workflow.name, &body, None);
+    }
+    Ok(run)
+}
+
+/// Lists all saved workflows.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_workflows(db: State<'_, Database>) -> Result<Vec<Workflow>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {WORKFLOW_COLUMNS} FROM workflows ORDER BY name"
+    ))?;
+    let workflows = stmt
+        .query_map([], workflow_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(workflows)
+}
+
+/// Creates a workflow, or replaces an existing one when `id` is given.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `INVALID_INPUT` if the definition is
+/// invalid, `NOT_FOUND` if `id` doesn't exist, or `DATABASE` on failure.
+///
+/// # Example
+///
+/// ```typescript
+/// await invoke("save_workflow", {
+///   id: null,
+///   input: {
+///     name: "Idea to code",
+///     steps: [
+///       { name: "idea", prompt: "Describe a techno track about {{input}}" },
+///       { name: "code", prompt: "Write Gibber code for: {{input}}" },
+///     ],
+///   },
+/// });
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State and args by value
+pub fn save_workflow(
+    db: State<'_, Database>,
+    id: Option<String>,
+    input: WorkflowInput,
+) -> Result<Workflow, GibberError> {
+    validate_input(&input)?;
+    let steps = serde_json::to_string(&input.steps)
+        .map_err(|e| GibberError::new("SERIALIZATION", e.to_string()))?;
+    let now = db::now_millis();
+    let conn = db.conn();
+    let id = if let Some(id) = id {
+        let updated = conn.execute(
+            "UPDATE workflows SET name = ?1, steps = ?2, updated_at = ?3 WHERE id = ?4",
+            params![input.name, steps, now, id],
+        )?;
+        if updated == 0 {
+            return Err(not_found("Workflow", &id));
+        }
+        id
+    } else {
+        let id = db::new_id();
+        conn.execute(
+            "INSERT INTO workflows (id, name, steps, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![id, input.name, steps, now],
+        )?;
+        id
+    };
+    load_workflow(&conn, &id)
+}
+
+/// Deletes a workflow and its run history.
+///
+/// # Returns
+///
+/// Returns `true` if the workflow was deleted, `false` if it didn't exist.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the delete fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn delete_workflow(db: State<'_, Database>, id: &str) -> Result<bool, GibberError> {
+    let deleted = db
+        .conn()
+        .execute("DELETE FROM workflows WHERE id = ?1", [id])?;
+    Ok(deleted > 0)
+}
+
+/// Runs a workflow to completion.
+///
+/// Progress is reported through [`events::WORKFLOW_STEP`] while the command is
+/// pending.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the workflow doesn't
+/// exist, or `DATABASE` if the run cannot be recorded. Step failures are
+/// reported in the returned run rather than as an error.
+///
+/// # Example
+///
+/// ```typescript
+/// const unlisten = await listen("workflow://step", (e) => console.log(e.payload));
+/// const run = await invoke("run_workflow", { id, input: "rainy night" });
+/// ```
+#[tauri::command]
+#[specta::specta]
+pub async fn run_workflow(
+    app: AppHandle,
+    id: String,
+    input: String,
+) -> Result<WorkflowRun, GibberError> {
+    let workflow = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        load_workflow(&conn, &id)?
+    };
+    telemetry::record_feature(&app, "workflow.run");
+    execute(&app, &workflow, input, true).await
+}
+
+/// Runs a saved workflow for a scheduled task, which notifies the user
+/// itself.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the workflow doesn't
+/// exist, or `DATABASE` if the run can't be recorded. Failing steps are
+/// reported in the returned run.
+pub(crate) async fn run_scheduled(
+    app: &AppHandle,
+    id: &str,
+    input: String,
+) -> Result<WorkflowRun, GibberError> {
+    let workflow = {
+        let db = app.state::<Database>();
+        let conn = db.conn();
+        load_workflow(&conn, id)?
+    };
+    execute(app, &workflow, input, false).await
+}
+
+/// Lists past runs of a workflow, newest first, without step details.
+///
+/// # Errors
+///
+/// Returns a `GibberError` if the query fails.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn list_workflow_runs(
+    db: State<'_, Database>,
+    workflow_id: &str,
+) -> Result<Vec<WorkflowRun>, GibberError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {RUN_COLUMNS} FROM workflow_runs WHERE workflow_id = ?1 ORDER BY started_at DESC"
+    ))?;
+    let runs = stmt
+        .query_map([workflow_id], run_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(runs)
+}
+
+/// Retrieves a run with its per-step records.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `NOT_FOUND` if the run doesn't exist.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require State by value
+pub fn get_workflow_run(db: State<'_, Database>, run_id: &str) -> Result<WorkflowRun, GibberError> {
+    load_run(&db.conn(), run_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(name: &str, prompt: &str) -> WorkflowStep {
+        WorkflowStep {
+            name: name.to_string(),
+            prompt: prompt.to_string(),
+            system_prompt: None,
+            model: None,
+            extract: None,
+            max_retries: 1,
+        }
+    }
+
+    #[test]
+    fn test_render_template_placeholders() {
+        let outputs = vec![("idea".to_string(), "a drone".to_string())];
+        let rendered = render_template(
+            "Code {{input}} from {{initial}} ({{steps.idea}})",
+            "prev",
+            "start",
+            &outputs,
+        );
+        assert_eq!(rendered, "Code prev from start (a drone)");
+    }
+
+    #[test]
+    fn test_render_template_appends_without_placeholders() {
+        assert_eq!(
+            render_template("Summarize", "text", "text", &[]),
+            "Summarize\n\ntext"
+        );
+        assert_eq!(render_template("Summarize", "", "", &[]), "Summarize");
+    }
+
+    #[test]
+    fn test_extract_from_fenced_block() {
+        let response = "Here you go:\n```json\n{\"bpm\": 120, \"key\": \"C\"}\n```\nEnjoy!";
+        assert_eq!(extract_output(response, "/bpm").unwrap(), "120");
+        assert_eq!(extract_output(response, "/key").unwrap(), "C");
+    }
+
+    #[test]
+    fn test_extract_from_embedded_json() {
+        let response = "The answer is {\"items\": [\"a\", \"b\"]} as requested.";
+        assert_eq!(extract_output(response, "/items/1").unwrap(), "b");
+        assert!(extract_output(response, "/missing").is_err());
+        assert!(extract_output("no json here", "").is_err());
+    }
+
+    #[test]
+    fn test_validate_input() {
+        let valid = WorkflowInput {
+            name: "Chain".to_string(),
+            steps: vec![step("a", "x"), step("b", "y")],
+        };
+        assert!(validate_input(&valid).is_ok());
+
+        let duplicate = WorkflowInput {
+            name: "Chain".to_string(),
+            steps: vec![step("a", "x"), step("a", "y")],
+        };
+        assert_eq!(
+            validate_input(&duplicate).unwrap_err().code(),
+            "INVALID_INPUT"
+        );
+
+        let mut bad_pointer = step("a", "x");
+        bad_pointer.extract = Some("summary".to_string());
+        let invalid = WorkflowInput {
+            name: "Chain".to_string(),
+            steps: vec![bad_pointer],
+        };
+        assert!(validate_input(&invalid).is_err());
+    }
+}
@@ -0,0 +1,656 @@
+//! Local WebSocket bridge for external integrations.
+//!
+//! When enabled, `ws://127.0.0.1:<port>/?token=<token>` streams the app
+//! events listed in [`BRIDGED_EVENTS`] to every connected client as
+//! `{ "type": "event", "event", "payload" }` frames and accepts a small set
+//! of JSON commands (see [`BridgeCommand`]), so stream decks and automation
+//! tools can follow and drive the app. Connections without the token are
+//! refused during the handshake. The settings stay on this machine.
+//!
+//! Each command may carry an `id`, echoed in its
+//! `{ "type": "result", "id", "ok", "result" | "error" }` reply.
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Listener, Manager, WebviewWindow};
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::{Message, WebSocket};
+
+use crate::commands::api_server;
+use crate::commands::events;
+use crate::commands::outbox;
+use crate::commands::quick_capture;
+use crate::commands::scheduler;
+use crate::commands::tray;
+use crate::commands::windows;
+use crate::commands::workflows;
+use crate::db::{self, Database};
+use crate::error::GibberError;
+
+/// Settings key holding the bridge preferences.
+pub const SETTINGS_KEY: &str = "wsBridge";
+
+/// Events forwarded to bridge clients.
+pub const BRIDGED_EVENTS: [&str; 8] = [
+    events::CONVERSATION_MESSAGE.name(),
+    events::GENERATION_DELTA.name(),
+    events::TRAY_GENERATING.name(),
+    events::OUTBOX_SENT.name(),
+    events::NETWORK_STATUS.name(),
+    events::SCHEDULER_RUN_COMPLETED.name(),
+    events::WORKFLOW_STEP.name(),
+    events::WORKFLOW_RUN_COMPLETED.name(),
+];
+
+/// Port used until the user picks another.
+const DEFAULT_PORT: u16 = 4892;
+
+/// How often idle loops check for shutdown and queued frames.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bridge preferences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BridgeSettings {
+    /// Accept connections
+    pub enabled: bool,
+    /// Port on `127.0.0.1`
+    pub port: u16,
+    /// Token clients must pass as the `token` query parameter
+    pub token: String,
+}
+
+impl Default for BridgeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: String::new(),
+        }
+    }
+}
+
+/// Bridge state as shown in the settings UI.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeStatus {
+    /// Stored preferences
+    pub settings: BridgeSettings,
+    /// URL clients should connect to, while the bridge runs
+    pub url: Option<String>,
+    /// Connected clients
+    pub clients: usize,
+}
+
+/// A command sent by a bridge client.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(
+    tag = "command",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum BridgeCommand {
+    /// Answers with `"pong"`
+    Ping,
+    /// Focuses the main window and starts a new chat
+    NewChat,
+    /// Queues a message for a reply, like `enqueue_message`
+    SendMessage {
+        /// Conversation to append to
+        conversation_id: String,
+        /// Message text
+        content: String,
+        /// Model override
+        #[serde(default)]
+        model: Option<String>,
+    },
+    /// Runs a scheduled task now
+    RunTask {
+        /// Task ID
+        task_id: String,
+    },
+    /// Runs a workflow
+    RunWorkflow {
+        /// Workflow ID
+        workflow_id: String,
+        /// Workflow input
+        #[serde(default)]
+        input: String,
+    },
+    /// Pauses or resumes background sync
+    SetSyncPaused {
+        /// Whether sync should be paused
+        paused: bool,
+    },
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(flatten)]
+    command: BridgeCommand,
+}
+
+struct RunningBridge {
+    stop: Arc<AtomicBool>,
+    /// Token new connections must present, swapped in place when only the
+    /// token changes
+    token: Arc<Mutex<String>>,
+    /// The accept loop, which owns the listener
+    listener: JoinHandle<()>,
+    port: u16,
+}
+
+impl RunningBridge {
+    /// Binds `port` and hands each connection to `handle` on its own thread,
+    /// with the current token and the stop flag.
+    fn bind<F>(port: u16, token: &str, handle: F) -> Result<Self, GibberError>
+    where
+        F: Fn(TcpStream, &str, &AtomicBool) + Clone + Send + 'static,
+    {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .map_err(|e| GibberError::new("BIND_FAILED", e.to_string()))?;
+        let port = listener.local_addr().map_or(port, |addr| addr.port());
+        let stop = Arc::new(AtomicBool::new(false));
+        let token = Arc::new(Mutex::new(token.to_string()));
+        let (flag, current) = (stop.clone(), token.clone());
+        let accept_loop = std::thread::spawn(move || {
+            while !flag.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let token = current
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .clone();
+                        let (handle, stop) = (handle.clone(), flag.clone());
+                        std::thread::spawn(move || handle(stream, &token, &stop));
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        std::thread::sleep(POLL_INTERVAL);
+                    }
+                    Err(e) => tracing::warn!("bridge accept failed: {e}"),
+                }
+            }
+        });
+        Ok(Self {
+            stop,
+            token,
+            listener: accept_loop,
+            port,
+        })
+    }
+
+    fn set_token(&self, token: &str) {
+        *self.token.lock().unwrap_or_else(PoisonError::into_inner) = token.to_string();
+    }
+
+    /// Stops the bridge and waits for the accept loop to drop the listener,
+    /// so the port can be bound again right away.
+    fn shutdown(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.listener.join();
+    }
+}
+
+/// A connected client's frame queue, and a handle that dies with the
+/// thread serving it.
+struct Client {
+    frames: Sender<String>,
+    alive: Weak<()>,
+}
+
+#[derive(Default)]
+struct BridgeInner {
+    running: Option<RunningBridge>,
+    clients: Vec<Client>,
+}
+
+/// Managed bridge state.
+#[derive(Default)]
+pub struct BridgeState(Mutex<BridgeInner>);
+
+impl BridgeState {
+    fn lock(&self) -> MutexGuard<'_, BridgeInner> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Queues a frame for every client, dropping disconnected ones.
+    fn broadcast(&self, frame: &str) {
+        self.lock()
+            .clients
+            .retain(|client| client.frames.send(frame.to_string()).is_ok());
+    }
+
+    /// Drops disconnected clients and counts the rest.
+    fn connected_clients(&self) -> usize {
+        let mut inner = self.lock();
+        inner
+            .clients
+            .retain(|client| client.alive.strong_count() > 0);
+        inner.clients.len()
+    }
+}
+
+fn load_settings(conn: &rusqlite::Connection) -> BridgeSettings {
+    db::read_setting(conn, SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+fn event_frame(event: &str, payload: &str) -> String {
+    let payload: Value = serde_json::from_str(payload).unwrap_or(Value::Null);
+    json!({ "type": "event", "event": event, "payload": payload }).to_string()
+}
+
+fn result_frame(id: Option<&Value>, result: Result<Value, Value>) -> String {
+    match result {
+        Ok(result) => json!({ "type": "result", "id": id, "ok": true, "result": result }),
+        Err(error) => json!({ "type": "result", "id": id, "ok": false, "error": error }),
+    }
+    .to_string()
+}
+
+fn outcome<T: Serialize, E: Serialize>(result: Result<T, E>) -> Result<Value, Value> {
+    match result {
+        Ok(value) => Ok(serde_json::to_value(value).unwrap_or(Value::Null)),
+        Err(error) => Err(serde_json::to_value(error).unwrap_or(Value::Null)),
+    }
+}
+
+fn query_token(request: &Request) -> Option<&str> {
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+}
+
+/// Runs a command; slow ones answer later through `reply`.
+fn dispatch(app: &AppHandle, frame: &str, reply: &Sender<String>) {
+    let envelope: Envelope = match serde_json::from_str(frame) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            let error = json!({ "code": "INVALID_COMMAND", "message": e.to_string() });
+            let _ = reply.send(result_frame(None, Err(error)));
+            return;
+        }
+    };
+    let id = envelope.id;
+    let result = match envelope.command {
+        BridgeCommand::Ping => Ok(json!("pong")),
+        BridgeCommand::NewChat => outcome(
+            quick_capture::focus_main_window(app)
+                .and_then(|()| {
+                    events::emit_typed_to(
+                        app,
+                        quick_capture::MAIN_WINDOW_LABEL,
+                        events::TRAY_NEW_CHAT,
+                        &(),
+                    )
+                })
+                .map_err(|e| json!({ "code": "WINDOW", "message": e.to_string() })),
+        ),
+        BridgeCommand::SendMessage {
+            conversation_id,
+            content,
+            model,
+        } => outcome(outbox::enqueue_message(
+            app.clone(),
+            conversation_id,
+            content,
+            model,
+        )),
+        BridgeCommand::SetSyncPaused { paused } => {
+            tray::set_sync_paused(app, paused);
+            Ok(json!(paused))
+        }
+        BridgeCommand::RunTask { task_id } => {
+            let (app, reply) = (app.clone(), reply.clone());
+            tauri::async_runtime::spawn(async move {
+                let result = scheduler::run_scheduled_task_now(app, task_id).await;
+                let _ = reply.send(result_frame(id.as_ref(), outcome(result)));
+            });
+            return;
+        }
+        BridgeCommand::RunWorkflow { workflow_id, input } => {
+            let (app, reply) = (app.clone(), reply.clone());
+            tauri::async_runtime::spawn(async move {
+                let result = workflows::run_workflow(app, workflow_id, input).await;
+                let _ = reply.send(result_frame(id.as_ref(), outcome(result)));
+            });
+            return;
+        }
+    };
+    let _ = reply.send(result_frame(id.as_ref(), result));
+}
+
+/// Serves one client until it disconnects or the bridge stops.
+fn serve_client(
+    app: &AppHandle,
+    mut socket: WebSocket<TcpStream>,
+    frames: &Receiver<String>,
+    reply: &Sender<String>,
+    stop: &AtomicBool,
+) {
+    if let Err(e) = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+        tracing::warn!("failed to configure bridge socket: {e}");
+        return;
+    }
+    while !stop.load(Ordering::Relaxed) {
+        while let Ok(frame) = frames.try_recv() {
+            if socket.send(Message::Text(frame)).is_err() {
+                return;
+            }
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => dispatch(app, &text, reply),
+            Ok(Message::Close(_)) => return,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+    }
+    let _ = socket.close(None);
+}
+
+/// Performs the handshake and serves the client on the current thread.
+fn accept(app: &AppHandle, stream: TcpStream, token: &str, stop: &AtomicBool) {
+    #[allow(clippy::result_large_err)] // tungstenite's callback signature
+    let authorize = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        if query_token(request).is_some_and(|given| api_server::tokens_match(token, given)) {
+            Ok(response)
+        } else {
+            let mut refusal = ErrorResponse::new(Some("Missing or invalid token".to_string()));
+            *refusal.status_mut() = tungstenite::http::StatusCode::UNAUTHORIZED;
+            Err(refusal)
+        }
+    };
+    if let Err(e) = stream.set_nonblocking(false) {
+        tracing::warn!("failed to configure bridge socket: {e}");
+        return;
+    }
+    let socket = match tungstenite::accept_hdr(stream, authorize) {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::warn!("refused bridge connection: {e}");
+            return;
+        }
+    };
+    let (sender, frames) = mpsc::channel();
+    let alive = Arc::new(());
+    app.state::<BridgeState>().lock().clients.push(Client {
+        frames: sender.clone(),
+        alive: Arc::downgrade(&alive),
+    });
+    serve_client(app, socket, &frames, &sender, stop);
+}
+
+/// Binds the bridge and accepts clients on a background thread.
+fn serve(app: &AppHandle, settings: &BridgeSettings) -> Result<RunningBridge, GibberError> {
+    let app = app.clone();
+    let running = RunningBridge::bind(
+        settings.port,
+        &settings.token,
+        move |stream, token, stop| {
+            accept(&app, stream, token, stop);
+        },
+    )?;
+    tracing::info!(port = running.port, "WebSocket bridge listening");
+    Ok(running)
+}
+
+/// Brings `running` in line with `settings`: a bridge already on the right
+/// port only gets the new token, so re-saving never has to rebind it.
+/// Otherwise the new bridge is bound before the old one stops, so a port
+/// that can't be bound leaves the current bridge running. Returns whether
+/// the previous bridge was stopped.
+fn reconcile(
+    running: &mut Option<RunningBridge>,
+    settings: &BridgeSettings,
+    serve: impl FnOnce(&BridgeSettings) -> Result<RunningBridge, GibberError>,
+) -> Result<bool, GibberError> {
+    if let Some(current) = running.as_ref() {
+        if settings.enabled && current.port == settings.port {
+            current.set_token(&settings.token);
+            return Ok(false);
+        }
+    }
+    let next = if settings.enabled {
+        Some(serve(settings)?)
+    } else {
+        None
+    };
+    Ok(std::mem::replace(running, next)
+        .map(RunningBridge::shutdown)
+        .is_some())
+}
+
+/// Starts, stops, or updates the bridge to match `settings`.
+fn apply(app: &AppHandle, settings: &BridgeSettings) -> Result<(), GibberError> {
+    let state = app.state::<BridgeState>();
+    let mut inner = state.lock();
+    let result = reconcile(&mut inner.running, settings, |settings| {
+        serve(app, settings)
+    });
+    if !matches!(result, Ok(false)) {
+        inner.clients.clear();
+    }
+    result.map(|_| ())
+}
+
+fn status(app: &AppHandle) -> BridgeStatus {
+    let settings = load_settings(&app.state::<Database>().conn());
+    let state = app.state::<BridgeState>();
+    let clients = state.connected_clients();
+    let url = state
+        .lock()
+        .running
+        .as_ref()
+        .map(|running| format!("ws://127.0.0.1:{}/", running.port));
+    BridgeStatus {
+        settings,
+        url,
+        clients,
+    }
+}
+
+/// Manages the bridge state, forwards app events to clients, and starts the
+/// bridge if it is enabled.
+///
+/// Must be called after the [`Database`] has been added to managed state.
+pub fn start(app: &AppHandle) {
+    app.manage(BridgeState::default());
+    for event in BRIDGED_EVENTS {
+        let handle = app.clone();
+        app.listen_any(event, move |e| {
+            handle
+                .state::<BridgeState>()
+                .broadcast(&event_frame(event, e.payload()));
+        });
+    }
+    let settings = load_settings(&app.state::<Database>().conn());
+    if let Err(e) = apply(app, &settings) {
+        tracing::error!("failed to start WebSocket bridge: {e}");
+    }
+}
+
+/// Returns the bridge preferences, address, and client count.
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require AppHandle by value
+pub fn get_bridge_status(app: AppHandle) -> BridgeStatus {
+    status(&app)
+}
+
+/// Replaces the bridge preferences and starts or stops the bridge.
+///
+/// A token is generated the first time the bridge is enabled. Nothing is
+/// saved if the bridge can't start. When the port stays the same, the
+/// running bridge is kept and only new connections need the new token.
+///
+/// # Errors
+///
+/// Returns a `GibberError` with code `FORBIDDEN` outside the main and
+/// conversation windows, `INVALID_PORT` for ports below 1024, `BIND_FAILED`
+/// if the port is taken, or `DATABASE` if the settings cannot be stored.
+///
+/// # Example
+///
+/// ```typescript
+/// const { url, settings } = await invoke("set_bridge_settings", {
+///   settings: { enabled: true, port: 4892, token: "" },
+/// });
+/// const ws = new WebSocket(`${url}?token=${settings.token}`);
+/// ws.send(JSON.stringify({ id: 1, command: "runTask", taskId }));
+/// ```
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::needless_pass_by_value)] // Tauri commands require handles and args by value
+pub fn set_bridge_settings(
+    window: WebviewWindow,
+    app: AppHandle,
+    mut settings: BridgeSettings,
+) -> Result<BridgeStatus, GibberError> {
+    windows::require_trusted(&window)?;
+    if settings.port < 1024 {
+        return Err(GibberError::new(
+            "INVALID_PORT",
+            format!("Port {} is reserved; use 1024 or above", settings.port),
+        ));
+    }
+    if settings.enabled && settings.token.is_empty() {
+        settings.token = api_server::new_token();
+    }
+    apply(&app, &settings)?;
+    let value = serde_json::to_string(&settings).unwrap_or_default();
+    db::write_setting(&app.state::<Database>().conn(), SETTINGS_KEY, &value)?;
+    Ok(status(&app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_parse_with_ids() {
+        let envelope: Envelope = serde_json::from_str(
+            r#"{"id": 7, "command": "sendMessage", "conversationId": "c1", "content": "hi"}"#,
+        )
+        .unwrap();
+        assert_eq!(envelope.id, Some(json!(7)));
+        assert_eq!(
+            envelope.command,
+            BridgeCommand::SendMessage {
+                conversation_id: "c1".to_string(),
+                content: "hi".to_string(),
+                model: None,
+            }
+        );
+        let envelope: Envelope = serde_json::from_str(r#"{"command": "ping"}"#).unwrap();
+        assert_eq!(envelope.command, BridgeCommand::Ping);
+        assert!(serde_json::from_str::<Envelope>(r#"{"command": "deleteAll"}"#).is_err());
+    }
+
+    #[test]
+    fn test_frames() {
+        assert_eq!(
+            serde_json::from_str::<Value>(&event_frame("tray://generating", "true")).unwrap(),
+            json!({ "type": "event", "event": "tray://generating", "payload": true })
+        );
+        let frame: Value =
+            serde_json::from_str(&result_frame(Some(&json!("a")), Err(json!("boom")))).unwrap();
+        assert_eq!(frame["ok"], false);
+        assert_eq!(frame["id"], "a");
+    }
+
+    #[test]
+    fn test_broadcast_drops_closed_clients() {
+        let state = BridgeState::default();
+        let alive = Arc::new(());
+        let (open, frames) = mpsc::channel();
+        let (closed, _) = mpsc::channel::<String>();
+        state
+            .lock()
+            .clients
+            .extend([open, closed].map(|frames| Client {
+                frames,
+                alive: Arc::downgrade(&alive),
+            }));
+
+        state.broadcast("frame");
+        assert_eq!(state.lock().clients.len(), 1);
+        assert_eq!(frames.try_recv().unwrap(), "frame");
+    }
+
+    #[test]
+    fn test_client_count_leaves_out_finished_clients() {
+        let state = BridgeState::default();
+        let (serving, finished) = (Arc::new(()), Arc::new(()));
+        let (sender, _frames) = mpsc::channel::<String>();
+        for alive in [&serving, &finished] {
+            state.lock().clients.push(Client {
+                frames: sender.clone(),
+                alive: Arc::downgrade(alive),
+            });
+        }
+        assert_eq!(state.connected_clients(), 2);
+
+        drop(finished);
+        assert_eq!(state.connected_clients(), 1);
+    }
+
+    #[test]
+    fn test_reapplying_settings_on_the_same_port() {
+        let bind = |settings: &BridgeSettings| {
+            RunningBridge::bind(settings.port, &settings.token, |_, _, _| {})
+        };
+        let mut running = None;
+        let mut settings = BridgeSettings {
+            enabled: true,
+            port: 0,
+            token: "first".to_string(),
+        };
+        assert!(!reconcile(&mut running, &settings, bind).unwrap());
+        settings.port = running.as_ref().unwrap().port;
+
+        // A new token on the same port keeps the listener.
+        settings.token = "second".to_string();
+        assert!(!reconcile(&mut running, &settings, bind).unwrap());
+        let current = running.as_ref().unwrap();
+        assert_eq!(*current.token.lock().unwrap(), "second");
+        assert_eq!(current.port, settings.port);
+
+        // A port that can't be bound leaves the running bridge in place.
+        let taken = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let moved = BridgeSettings {
+            port: taken.local_addr().unwrap().port(),
+            ..settings.clone()
+        };
+        assert!(reconcile(&mut running, &moved, bind).is_err());
+        assert_eq!(running.as_ref().unwrap().port, settings.port);
+
+        // Stopping releases the port before it is bound again.
+        settings.enabled = false;
+        assert!(reconcile(&mut running, &settings, bind).unwrap());
+        assert!(running.is_none());
+        settings.enabled = true;
+        reconcile(&mut running, &settings, bind).unwrap();
+        running.take().unwrap().shutdown();
+        RunningBridge::bind(settings.port, "third", |_, _, _| {})
+            .unwrap()
+            .shutdown();
+    }
+}
@@ -0,0 +1,606 @@
+//! SQLite persistence for the Gibber AI backend.
+//!
+//! A single database file lives in the app data directory and is shared by
+//! every backend subsystem through Tauri's managed state. Schema changes are
+//! applied as ordered migrations tracked with `PRAGMA user_version`.
+
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use rusqlite::{Connection, OptionalExtension};
+
+/// File name of the database inside the app data directory.
+pub const DATABASE_FILE: &str = "gibber-ai.db";
+
+/// Ordered schema migrations.
+///
+/// Entries are append-only: a shipped migration must never be edited,
+/// because `user_version` records how many of them have already run.
+const MIGRATIONS: &[&str] = &[
+    // 1: conversations and their messages
+    "CREATE TABLE conversations (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        model TEXT,
+        system_prompt TEXT,
+        source TEXT NOT NULL DEFAULT 'chat',
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE messages (
+        id TEXT PRIMARY KEY,
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        role TEXT NOT NULL,
+        content TEXT NOT NULL,
+        model TEXT,
+        prompt_tokens INTEGER,
+        completion_tokens INTEGER,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_messages_conversation ON messages(conversation_id, created_at);",
+    // 2: scheduled prompts
+    "CREATE TABLE scheduled_tasks (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        prompt TEXT NOT NULL,
+        system_prompt TEXT,
+        model TEXT,
+        cron TEXT NOT NULL,
+        enabled INTEGER NOT NULL DEFAULT 1,
+        notify INTEGER NOT NULL DEFAULT 1,
+        last_run_at INTEGER,
+        last_status TEXT,
+        last_error TEXT,
+        last_conversation_id TEXT,
+        next_run_at INTEGER,
+        created_at INTEGER NOT NULL
+    );",
+    // 3: prompt workflows and their run history
+    "CREATE TABLE workflows (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        steps TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE workflow_runs (
+        id TEXT PRIMARY KEY,
+        workflow_id TEXT NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+        status TEXT NOT NULL,
+        input TEXT NOT NULL,
+        output TEXT,
+        error TEXT,
+        started_at INTEGER NOT NULL,
+        finished_at INTEGER
+    );
+    CREATE TABLE workflow_run_steps (
+        run_id TEXT NOT NULL REFERENCES workflow_runs(id) ON DELETE CASCADE,
+        step_index INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        output TEXT,
+        error TEXT,
+        started_at INTEGER NOT NULL,
+        finished_at INTEGER,
+        PRIMARY KEY (run_id, step_index)
+    );
+    CREATE INDEX idx_workflow_runs_workflow ON workflow_runs(workflow_id, started_at);",
+    // 4: key-value app settings, values stored as JSON
+    "CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );",
+    // 5: attachment metadata; files live in the attachments directory
+    "CREATE TABLE attachments (
+        id TEXT PRIMARY KEY,
+        conversation_id TEXT REFERENCES conversations(id) ON DELETE CASCADE,
+        file_name TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        mime_type TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        origin TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_attachments_conversation ON attachments(conversation_id, created_at);",
+    // 6: opt-in telemetry queue, one row per event and day
+    "CREATE TABLE telemetry_counts (
+        kind TEXT NOT NULL,
+        name TEXT NOT NULL,
+        day TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (kind, name, day)
+    );",
+    // 7: chat requests queued while offline
+    "CREATE TABLE outbox (
+        id TEXT PRIMARY KEY,
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        message_id TEXT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+        model TEXT,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        last_error TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );",
+    // 8: webhooks for completed runs and their delivery log
+    "CREATE TABLE webhooks (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        events TEXT NOT NULL,
+        enabled INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE webhook_deliveries (
+        id TEXT PRIMARY KEY,
+        webhook_id TEXT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+        event TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        response_status INTEGER,
+        last_error TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, created_at);",
+    // 9: installed WebAssembly plugins and their storage namespaces
+    "CREATE TABLE plugins (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        version TEXT NOT NULL,
+        description TEXT NOT NULL,
+        requested TEXT NOT NULL,
+        granted TEXT NOT NULL,
+        tools TEXT NOT NULL,
+        enabled INTEGER NOT NULL,
+        installed_at INTEGER NOT NULL
+    );
+    CREATE TABLE plugin_storage (
+        plugin_id TEXT NOT NULL REFERENCES plugins(id) ON DELETE CASCADE,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (plugin_id, key)
+    );",
+    // 10: user automation scripts
+    "CREATE TABLE automations (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        script TEXT NOT NULL,
+        triggered_by TEXT NOT NULL,
+        enabled INTEGER NOT NULL,
+        last_run_at INTEGER,
+        last_status TEXT,
+        last_error TEXT,
+        next_run_at INTEGER,
+        created_at INTEGER NOT NULL
+    );",
+    // 11: encrypted conversation shares, kept after the conversation is
+    // deleted so they can still be revoked
+    "CREATE TABLE shares (
+        id TEXT PRIMARY KEY,
+        conversation_id TEXT NOT NULL,
+        backend TEXT NOT NULL,
+        url TEXT NOT NULL,
+        link TEXT NOT NULL,
+        delete_token TEXT,
+        expires_at INTEGER,
+        created_at INTEGER NOT NULL,
+        revoked_at INTEGER
+    );
+    CREATE INDEX idx_shares_conversation ON shares(conversation_id, created_at);",
+    // 12: read-it-later queue
+    "CREATE TABLE reading_list (
+        id TEXT PRIMARY KEY,
+        url TEXT NOT NULL UNIQUE,
+        title TEXT,
+        source TEXT NOT NULL,
+        status TEXT NOT NULL,
+        summary TEXT,
+        content TEXT,
+        error TEXT,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        added_at INTEGER NOT NULL,
+        summarized_at INTEGER
+    );
+    CREATE INDEX idx_reading_list_status ON reading_list(status, added_at);",
+    // 13: feed subscriptions and their entries
+    "CREATE TABLE feeds (
+        id TEXT PRIMARY KEY,
+        url TEXT NOT NULL UNIQUE,
+        title TEXT,
+        prompt TEXT,
+        enabled INTEGER NOT NULL DEFAULT 1,
+        last_polled_at INTEGER,
+        last_error TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE feed_items (
+        id TEXT PRIMARY KEY,
+        feed_id TEXT NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+        guid TEXT NOT NULL,
+        title TEXT NOT NULL,
+        link TEXT,
+        summary TEXT,
+        published_at INTEGER,
+        fetched_at INTEGER NOT NULL,
+        digested_at INTEGER,
+        UNIQUE (feed_id, guid)
+    );
+    CREATE INDEX idx_feed_items_digest ON feed_items(feed_id, digested_at);",
+    // 14: image generation parameters, one row per generating message
+    "CREATE TABLE image_generations (
+        id TEXT PRIMARY KEY,
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        message_id TEXT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+        params TEXT NOT NULL,
+        model TEXT NOT NULL,
+        attachment_ids TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE UNIQUE INDEX idx_image_generations_message ON image_generations(message_id);",
+    // 15: conversations tagged as holding sensitive data
+    "CREATE TABLE sensitive_conversations (
+        conversation_id TEXT PRIMARY KEY REFERENCES conversations(id) ON DELETE CASCADE,
+        tagged_at INTEGER NOT NULL
+    );",
+    // 16: append-only audit log of what autonomous features did
+    "CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        actor TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        target TEXT NOT NULL,
+        detail TEXT,
+        error TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_audit_log_actor ON audit_log(actor, id);
+    CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+    BEGIN SELECT RAISE(ABORT, 'the audit log is append-only'); END;
+    CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+    BEGIN SELECT RAISE(ABORT, 'the audit log is append-only'); END;",
+    // 17: cost of each completion, for spend caps
+    "CREATE TABLE spend_ledger (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        provider TEXT NOT NULL,
+        model TEXT NOT NULL,
+        conversation_id TEXT,
+        prompt_tokens INTEGER,
+        completion_tokens INTEGER,
+        cost_usd REAL NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_spend_ledger_created ON spend_ledger(created_at);",
+    // 18: latency and reliability samples per model
+    "CREATE TABLE model_metrics (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        model TEXT NOT NULL,
+        provider TEXT,
+        ttft_ms INTEGER,
+        duration_ms INTEGER NOT NULL,
+        completion_tokens INTEGER,
+        tokens_per_second REAL,
+        error TEXT,
+        route_error INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_model_metrics_created ON model_metrics(created_at);",
+    // 19: cached deterministic completions by request hash
+    "CREATE TABLE response_cache (
+        key TEXT PRIMARY KEY,
+        content TEXT NOT NULL,
+        model TEXT NOT NULL,
+        provider TEXT,
+        prompt_tokens INTEGER,
+        completion_tokens INTEGER,
+        hits INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL,
+        last_hit_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_response_cache_last_hit ON response_cache(last_hit_at);",
+    // 20: prompt regression tests, runs, and results
+    "CREATE TABLE prompt_tests (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        system TEXT,
+        prompt TEXT NOT NULL,
+        criteria TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE prompt_test_runs (
+        id TEXT PRIMARY KEY,
+        models TEXT NOT NULL,
+        passed INTEGER NOT NULL,
+        failed INTEGER NOT NULL,
+        started_at INTEGER NOT NULL,
+        finished_at INTEGER
+    );
+    CREATE TABLE prompt_test_results (
+        run_id TEXT NOT NULL REFERENCES prompt_test_runs(id) ON DELETE CASCADE,
+        test_id TEXT NOT NULL REFERENCES prompt_tests(id) ON DELETE CASCADE,
+        model TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        output TEXT,
+        passed INTEGER NOT NULL,
+        failures TEXT NOT NULL,
+        error TEXT,
+        duration_ms INTEGER NOT NULL,
+        PRIMARY KEY (run_id, test_id, model)
+    );
+    CREATE INDEX idx_prompt_test_results_test ON prompt_test_results(test_id, model);",
+    // 21: A/B prompt experiments with enrollments and ratings
+    "CREATE TABLE experiments (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        variant_a TEXT NOT NULL,
+        variant_b TEXT NOT NULL,
+        active INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE experiment_assignments (
+        conversation_id TEXT PRIMARY KEY REFERENCES conversations(id) ON DELETE CASCADE,
+        experiment_id TEXT NOT NULL REFERENCES experiments(id) ON DELETE CASCADE,
+        variant TEXT NOT NULL,
+        assigned_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_experiment_assignments_experiment
+        ON experiment_assignments(experiment_id, variant);
+    CREATE TABLE experiment_ratings (
+        message_id TEXT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+        experiment_id TEXT NOT NULL REFERENCES experiments(id) ON DELETE CASCADE,
+        variant TEXT NOT NULL,
+        rating INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_experiment_ratings_experiment ON experiment_ratings(experiment_id, variant);",
+    // 22: thumbs-up and thumbs-down feedback with the context of the answer
+    "CREATE TABLE message_feedback (
+        message_id TEXT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        rating INTEGER NOT NULL,
+        comment TEXT,
+        model TEXT,
+        system_prompt TEXT,
+        context TEXT NOT NULL,
+        completion TEXT NOT NULL,
+        rated_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_message_feedback_rated ON message_feedback(rated_at);",
+    // 23: free-form conversation tags
+    "CREATE TABLE conversation_tags (
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (conversation_id, tag)
+    );
+    CREATE INDEX idx_conversation_tags_tag ON conversation_tags(tag);",
+    // 24: background jobs
+    "CREATE TABLE jobs (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        label TEXT NOT NULL,
+        status TEXT NOT NULL,
+        progress REAL NOT NULL DEFAULT 0,
+        message TEXT,
+        error TEXT,
+        created_at INTEGER NOT NULL,
+        finished_at INTEGER
+    );
+    CREATE INDEX idx_jobs_created ON jobs(created_at);",
+    // 25: partial replies of streams still running or cut off
+    "CREATE TABLE stream_drafts (
+        stream_id TEXT PRIMARY KEY,
+        conversation_id TEXT,
+        request TEXT NOT NULL,
+        partial TEXT NOT NULL DEFAULT '',
+        started_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );",
+    // 26: new-chat presets and the conversations started from them
+    "CREATE TABLE presets (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        persona TEXT,
+        model TEXT,
+        knowledge_base TEXT,
+        tools TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE conversation_presets (
+        conversation_id TEXT PRIMARY KEY REFERENCES conversations(id) ON DELETE CASCADE,
+        preset_id TEXT NOT NULL REFERENCES presets(id) ON DELETE CASCADE
+    );",
+    // 27: how often and how recently models and presets were used
+    "CREATE TABLE usage_stats (
+        kind TEXT NOT NULL,
+        item_id TEXT NOT NULL,
+        uses INTEGER NOT NULL,
+        last_used_at INTEGER,
+        favorite INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (kind, item_id)
+    );",
+    // 28: ECB reference rates for currency conversion
+    "CREATE TABLE exchange_rates (
+        currency TEXT PRIMARY KEY,
+        per_euro TEXT NOT NULL,
+        published TEXT,
+        fetched_at INTEGER NOT NULL
+    );",
+    // 29: Nostr article drafts, and synced drafts deleted since the last sync
+    "CREATE TABLE nostr_drafts (
+        id TEXT PRIMARY KEY,
+        identifier TEXT NOT NULL UNIQUE,
+        title TEXT NOT NULL,
+        summary TEXT NOT NULL,
+        image TEXT,
+        content TEXT NOT NULL,
+        hashtags TEXT NOT NULL,
+        published_event_id TEXT,
+        published_at INTEGER,
+        synced_at INTEGER,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE nostr_deleted_drafts (
+        identifier TEXT PRIMARY KEY,
+        deleted_at INTEGER NOT NULL
+    );",
+    // 30: scheduled tasks that run a workflow
+    "ALTER TABLE scheduled_tasks ADD COLUMN workflow_id TEXT;
+    ALTER TABLE scheduled_tasks ADD COLUMN last_workflow_run_id TEXT;",
+];
+
+/// Shared handle to the application database.
+///
+/// The connection is guarded by a mutex; callers should hold the guard only
+/// for the duration of their queries and never across an `.await`.
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+impl Database {
+    /// Opens (or creates) the database at `path` and applies pending migrations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or a migration fails.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens a fresh in-memory database with the full schema applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a migration fails.
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        migrate(&conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Locks and returns the underlying connection.
+    ///
+    /// A poisoned lock is recovered rather than propagated: SQLite keeps the
+    /// connection consistent even if a previous holder panicked.
+    pub fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Applies every migration newer than the database's `user_version`.
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let applied: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let applied = usize::try_from(applied).unwrap_or(0);
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(
+            None,
+            "user_version",
+            i64::try_from(index + 1).unwrap_or(i64::MAX),
+        )?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Reads a raw JSON setting value.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn read_setting(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+        row.get(0)
+    })
+    .optional()
+}
+
+/// Stores a raw JSON setting value, replacing any previous one.
+///
+/// # Errors
+///
+/// Returns an error if the statement fails.
+pub fn write_setting(conn: &Connection, key: &str, value: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        rusqlite::params![key, value, now_millis()],
+    )?;
+    Ok(())
+}
+
+/// Returns every stored setting as `(key, raw JSON value)`, ordered by key.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub fn list_settings(conn: &Connection) -> rusqlite::Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings ORDER BY key")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect();
+    rows
+}
+
+/// Returns the current time as Unix milliseconds, matching `Date.now()` on the frontend.
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Generates a new random identifier for database rows.
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_set_user_version() {
+        let db = Database::open_in_memory().expect("Should open");
+        let version: i64 = db
+            .conn()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .expect("Should read version");
+        assert_eq!(version, i64::try_from(MIGRATIONS.len()).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let db = Database::open_in_memory().expect("Should open");
+        migrate(&db.conn()).expect("Re-running migrations should be a no-op");
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let db = Database::open_in_memory().expect("Should open");
+        let conn = db.conn();
+        assert_eq!(read_setting(&conn, "theme").unwrap(), None);
+        write_setting(&conn, "theme", "\"dark\"").unwrap();
+        write_setting(&conn, "theme", "\"light\"").unwrap();
+        assert_eq!(
+            read_setting(&conn, "theme").unwrap().as_deref(),
+            Some("\"light\"")
+        );
+    }
+
+    #[test]
+    fn test_new_id_is_unique() {
+        assert_ne!(new_id(), new_id());
+    }
+}
@@ -0,0 +1,171 @@
+//! Error type shared by all commands.
+//!
+//! Every command fails with a [`GibberError`], which reaches the frontend
+//! as `{ message, code }`: the code is stable and meant for programmatic
+//! handling, the message is meant for people. Failures common to many
+//! modules have their own variant, and errors of the libraries the backend
+//! builds on convert with `?`. Codes specific to one feature
+//! (`INVALID_PLUGIN`, `BUDGET_EXCEEDED`, ...) go through
+//! [`GibberError::new`].
+
+use serde::{Serialize, Serializer};
+
+/// Error returned by commands and the modules behind them.
+#[derive(Debug, thiserror::Error)]
+pub enum GibberError {
+    /// Something the operation needs doesn't exist (`NOT_FOUND`)
+    #[error("{0}")]
+    NotFound(String),
+    /// An argument is invalid (`INVALID_INPUT`)
+    #[error("{0}")]
+    InvalidInput(String),
+    /// The operation was cancelled (`CANCELLED`)
+    #[error("{0}")]
+    Cancelled(String),
+    /// A database query failed (`DATABASE`)
+    #[error("{0}")]
+    Database(#[from] rusqlite::Error),
+    /// Reading or writing a file failed (`IO`)
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    /// An HTTP request failed (`NETWORK_ERROR`), or its response couldn't be
+    /// decoded (`PARSE_ERROR`)
+    #[error("{0}")]
+    Http(#[from] reqwest::Error),
+    /// The system keyring failed; the code names the keyring error
+    /// (`NO_ENTRY`, `NO_STORAGE_ACCESS`, ...)
+    #[error("{0}")]
+    Keyring(#[from] keyring::Error),
+    /// A window or other Tauri operation failed (`WINDOW`)
+    #[error("{0}")]
+    Tauri(#[from] tauri::Error),
+    /// Any other failure, with a code of its own
+    #[error("{message}")]
+    Other {
+        /// Error code for programmatic handling
+        code: String,
+        /// Human-readable error message
+        message: String,
+    },
+}
+
+impl GibberError {
+    /// Creates an error with `code`, using the dedicated variant for codes
+    /// that have one.
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match code {
+            "NOT_FOUND" => Self::NotFound(message),
+            "INVALID_INPUT" => Self::InvalidInput(message),
+            "CANCELLED" => Self::Cancelled(message),
+            _ => Self::Other {
+                code: code.to_string(),
+                message,
+            },
+        }
+    }
+
+    /// Creates an `INVALID_INPUT` error.
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::InvalidInput(message.into())
+    }
+
+    /// Returns the stable error code.
+    #[must_use]
+    pub fn code(&self) -> &str {
+        match self {
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::InvalidInput(_) => "INVALID_INPUT",
+            Self::Cancelled(_) => "CANCELLED",
+            Self::Database(_) => "DATABASE",
+            Self::Io(_) => "IO",
+            Self::Http(err) if err.is_decode() => "PARSE_ERROR",
+            Self::Http(_) => "NETWORK_ERROR",
+            Self::Keyring(err) => match err {
+                keyring::Error::NoEntry => "NO_ENTRY",
+                keyring::Error::Ambiguous(_) => "AMBIGUOUS",
+                keyring::Error::BadEncoding(_) => "BAD_ENCODING",
+                keyring::Error::TooLong(_, _) => "TOO_LONG",
+                keyring::Error::Invalid(_, _) => "INVALID",
+                keyring::Error::NoStorageAccess(_) => "NO_STORAGE_ACCESS",
+                keyring::Error::PlatformFailure(_) => "PLATFORM_FAILURE",
+                _ => "UNKNOWN",
+            },
+            Self::Tauri(_) => "WINDOW",
+            Self::Other { code, .. } => code,
+        }
+    }
+}
+
+impl From<zip::result::ZipError> for GibberError {
+    fn from(err: zip::result::ZipError) -> Self {
+        Self::new("IO", err.to_string())
+    }
+}
+
+impl From<xcap::XCapError> for GibberError {
+    fn from(err: xcap::XCapError) -> Self {
+        Self::new("CAPTURE_FAILED", err.to_string())
+    }
+}
+
+impl From<tauri_plugin_updater::Error> for GibberError {
+    fn from(err: tauri_plugin_updater::Error) -> Self {
+        Self::new("UPDATE_FAILED", err.to_string())
+    }
+}
+
+/// What a [`GibberError`] looks like once serialized.
+#[derive(Serialize, specta::Type)]
+struct ErrorPayload {
+    message: String,
+    code: String,
+}
+
+impl Serialize for GibberError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorPayload {
+            message: self.to_string(),
+            code: self.code().to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl specta::Type for GibberError {
+    fn inline(
+        types: &mut specta::TypeCollection,
+        generics: specta::Generics,
+    ) -> specta::datatype::DataType {
+        <ErrorPayload as specta::Type>::inline(types, generics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_as_message_and_code() {
+        let error = GibberError::new("BUDGET_EXCEEDED", "Daily budget reached");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "message": "Daily budget reached", "code": "BUDGET_EXCEEDED" })
+        );
+    }
+
+    #[test]
+    fn test_common_codes_use_their_variants() {
+        assert!(matches!(
+            GibberError::new("NOT_FOUND", "gone"),
+            GibberError::NotFound(_)
+        ));
+        assert_eq!(GibberError::invalid("bad").code(), "INVALID_INPUT");
+        let database = GibberError::from(rusqlite::Error::QueryReturnedNoRows);
+        assert_eq!(database.code(), "DATABASE");
+        assert_eq!(
+            GibberError::from(keyring::Error::NoEntry).code(),
+            "NO_ENTRY"
+        );
+    }
+}
@@ -3,7 +3,16 @@
 //! This module contains the Tauri application setup and command handlers
 //! for the Gibber AI desktop application.
 
+mod chat;
+#[cfg(feature = "cli")]
+pub mod cli;
 mod commands;
+mod db;
+mod error;
+#[cfg(feature = "cli")]
+mod native_host;
+
+use tauri::{DragDropEvent, Manager, RunEvent, WindowEvent};
 
 /// Greets the user with a personalized message.
 ///
@@ -26,29 +35,458 @@ mod commands;
 /// // Returns: "Hello, World! You've been greeted from Rust!"
 /// ```
 #[tauri::command]
+#[specta::specta]
 fn greet(name: &str) -> String {
     format!("Hello, {name}! You've been greeted from Rust!")
 }
 
+/// The generated TypeScript bindings, committed and rewritten by debug
+/// builds.
+#[cfg(debug_assertions)]
+const BINDINGS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../src/lib/bindings.ts");
+
+/// Collects every typed command, plus the event payloads, for the invoke
+/// handler and the generated TypeScript bindings.
+#[allow(clippy::too_many_lines)] // one line per command
+fn bindings() -> tauri_specta::Builder<tauri::Wry> {
+    tauri_specta::Builder::<tauri::Wry>::new()
+        .commands(tauri_specta::collect_commands![
+            greet,
+            commands::credentials::get_api_key,
+            commands::credentials::set_api_key,
+            commands::credentials::delete_api_key,
+            commands::conversations::list_conversations,
+            commands::conversations::get_conversation,
+            commands::conversations::export_conversation,
+            commands::conversations::create_conversation,
+            commands::conversations::append_message,
+            commands::conversations::delete_conversation,
+            commands::scheduler::list_scheduled_tasks,
+            commands::scheduler::create_scheduled_task,
+            commands::scheduler::update_scheduled_task,
+            commands::scheduler::delete_scheduled_task,
+            commands::scheduler::run_scheduled_task_now,
+            commands::workflows::list_workflows,
+            commands::workflows::save_workflow,
+            commands::workflows::delete_workflow,
+            commands::workflows::run_workflow,
+            commands::workflows::list_workflow_runs,
+            commands::workflows::get_workflow_run,
+            commands::quick_capture::show_quick_capture,
+            commands::quick_capture::hide_quick_capture,
+            commands::quick_capture::submit_quick_capture,
+            commands::tray::get_tray_status,
+            commands::tray::set_close_to_tray,
+            commands::tray::begin_generation,
+            commands::tray::end_generation,
+            commands::instance::take_launch_args,
+            commands::deep_link::take_pending_navigation,
+            commands::windows::open_conversation_window,
+            commands::windows::list_conversation_windows,
+            commands::windows::close_conversation_window,
+            commands::windows::set_current_conversation,
+            commands::notifications::get_notification_settings,
+            commands::notifications::set_notification_settings,
+            commands::notifications::notify_generation_complete,
+            commands::clipboard::get_clipboard_settings,
+            commands::clipboard::set_clipboard_settings,
+            commands::clipboard::get_clipboard_capture,
+            commands::clipboard::clear_clipboard_capture,
+            commands::clipboard::ask_about_clipboard,
+            commands::attachments::stage_attachment,
+            commands::attachments::list_pending_attachments,
+            commands::attachments::list_conversation_attachments,
+            commands::attachments::attach_to_conversation,
+            commands::attachments::read_attachment_text,
+            commands::attachments::delete_attachment,
+            commands::screenshot::capture_screenshot,
+            commands::screenshot::list_capture_sources,
+            commands::updater::get_update_settings,
+            commands::updater::set_update_settings,
+            commands::updater::check_for_updates,
+            commands::updater::download_update,
+            commands::updater::restart_to_update,
+            commands::shortcuts::get_shortcuts,
+            commands::shortcuts::set_shortcuts,
+            commands::shortcuts::reset_shortcuts,
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::settings::export_settings,
+            commands::settings::preview_settings_import,
+            commands::settings::import_settings,
+            commands::settings::reset_settings,
+            commands::logs::query_logs,
+            commands::logs::open_log_folder,
+            commands::diagnostics::generate_diagnostics,
+            commands::telemetry::get_telemetry_settings,
+            commands::telemetry::set_telemetry_settings,
+            commands::telemetry::preview_telemetry,
+            commands::telemetry::purge_telemetry,
+            commands::crash_reports::list_crash_reports,
+            commands::crash_reports::delete_crash_reports,
+            commands::crash_reports::submit_crash_report,
+            commands::network::get_network_settings,
+            commands::network::set_network_settings,
+            commands::network_activity::get_network_activity,
+            commands::network_activity::clear_network_activity,
+            commands::outbox::get_network_status,
+            commands::outbox::enqueue_message,
+            commands::outbox::list_outbox,
+            commands::outbox::retry_outbox_entry,
+            commands::outbox::discard_outbox_entry,
+            commands::api_server::get_api_server_status,
+            commands::api_server::set_api_server_settings,
+            commands::api_server::regenerate_api_server_token,
+            commands::ws_bridge::get_bridge_status,
+            commands::ws_bridge::set_bridge_settings,
+            commands::browser_bridge::get_browser_bridge_settings,
+            commands::browser_bridge::set_origin_access,
+            commands::browser_bridge::set_browser_bridge_save_conversations,
+            commands::webhooks::list_webhooks,
+            commands::webhooks::create_webhook,
+            commands::webhooks::update_webhook,
+            commands::webhooks::rotate_webhook_secret,
+            commands::webhooks::delete_webhook,
+            commands::webhooks::list_webhook_deliveries,
+            commands::email::open_email_draft,
+            commands::action_items::extract_action_items,
+            commands::action_items::create_action_items,
+            commands::git_assist::list_git_repositories,
+            commands::git_assist::grant_git_repository,
+            commands::git_assist::revoke_git_repository,
+            commands::git_assist::git_assist,
+            commands::plugins::list_plugins,
+            commands::plugins::install_plugin,
+            commands::plugins::set_plugin_enabled,
+            commands::plugins::set_plugin_capabilities,
+            commands::plugins::uninstall_plugin,
+            commands::plugins::call_plugin_tool,
+            commands::automations::list_automations,
+            commands::automations::create_automation,
+            commands::automations::update_automation,
+            commands::automations::delete_automation,
+            commands::automations::run_automation,
+            commands::i18n::get_locale_strings,
+            commands::i18n::set_locale,
+            commands::markdown::render_markdown,
+            commands::markdown::get_highlight_css,
+            commands::code_blocks::extract_code_blocks,
+            commands::code_blocks::save_code_block,
+            commands::sharing::list_shares,
+            commands::sharing::get_share_settings,
+            commands::sharing::set_share_settings,
+            commands::sharing::create_share_link,
+            commands::sharing::revoke_share,
+            commands::deidentify::deidentify_conversation,
+            commands::reading_list::add_to_reading_list,
+            commands::reading_list::add_clipboard_to_reading_list,
+            commands::reading_list::list_reading_list,
+            commands::reading_list::retry_reading_item,
+            commands::reading_list::delete_reading_item,
+            commands::reading_list::search_library,
+            commands::feeds::get_feed_settings,
+            commands::feeds::set_feed_settings,
+            commands::feeds::list_feeds,
+            commands::feeds::subscribe_feed,
+            commands::feeds::update_feed,
+            commands::feeds::unsubscribe_feed,
+            commands::feeds::list_feed_items,
+            commands::feeds::refresh_feeds,
+            commands::feeds::generate_feed_digest,
+            commands::nostr_digest::get_nostr_digest_settings,
+            commands::nostr_digest::set_nostr_digest_settings,
+            commands::nostr_digest::generate_nostr_digest,
+            commands::images::generate_image,
+            commands::images::get_image_generation,
+            commands::stable_diffusion::get_stable_diffusion_settings,
+            commands::stable_diffusion::set_stable_diffusion_settings,
+            commands::stable_diffusion::list_stable_diffusion_models,
+            commands::stable_diffusion::cancel_local_image,
+            commands::image_edit::edit_image_attachment,
+            commands::image_metadata::prepare_image_upload,
+            commands::routing_policy::get_routing_policy,
+            commands::routing_policy::set_routing_policy,
+            commands::routing_policy::set_conversation_sensitive,
+            commands::routing_policy::list_sensitive_conversations,
+            commands::routing_policy::check_conversation_route,
+            commands::privacy::get_privacy_fields,
+            commands::audit::get_audit_log,
+            commands::audit::export_audit_log,
+            commands::rate_limit::get_rate_limits,
+            commands::rate_limit::set_rate_limits,
+            commands::rate_limit::get_rate_limit_state,
+            commands::rate_limit::acquire_rate_limit,
+            commands::spend::get_spend_caps,
+            commands::spend::set_spend_caps,
+            commands::spend::get_spend_summary,
+            commands::spend::override_spend_cap,
+            commands::spend::record_spend,
+            commands::model_metrics::get_model_metrics,
+            commands::model_metrics::record_model_metric,
+            commands::model_metrics::rank_models,
+            commands::response_cache::get_response_cache_stats,
+            commands::response_cache::clear_response_cache,
+            commands::prompt_tests::list_prompt_tests,
+            commands::prompt_tests::save_prompt_test,
+            commands::prompt_tests::delete_prompt_test,
+            commands::prompt_tests::run_prompt_tests,
+            commands::prompt_tests::list_prompt_test_runs,
+            commands::prompt_tests::get_prompt_test_run,
+            commands::experiments::list_experiments,
+            commands::experiments::save_experiment,
+            commands::experiments::set_experiment_active,
+            commands::experiments::delete_experiment,
+            commands::experiments::get_experiment_assignment,
+            commands::experiments::rate_experiment_message,
+            commands::experiments::get_experiment_stats,
+            commands::feedback::rate_message,
+            commands::feedback::list_feedback,
+            commands::feedback::export_feedback,
+            commands::conversations::get_conversation_tags,
+            commands::conversations::set_conversation_tags,
+            commands::datasets::export_dataset,
+            commands::text_diff::diff_messages,
+            commands::chat_stream::stream_chat,
+            commands::chat_stream::ack_chat_stream,
+            commands::jobs::list_jobs,
+            commands::jobs::cancel_job,
+            commands::startup::get_startup_status,
+            commands::conversations::get_messages,
+            commands::events::get_event_schema,
+            commands::session::update_session_window,
+            commands::session::get_restorable_session,
+            commands::session::restore_session,
+            commands::session::discard_session,
+            commands::chat_stream::resume_chat_stream,
+            commands::stream_recovery::list_interrupted_streams,
+            commands::stream_recovery::discard_interrupted_stream,
+            commands::presets::list_presets,
+            commands::presets::save_preset,
+            commands::presets::delete_preset,
+            commands::presets::start_preset_conversation,
+            commands::presets::get_conversation_preset,
+            commands::palette::get_command_palette_actions,
+            commands::usage::record_usage,
+            commands::usage::get_ranked_usage,
+            commands::usage::set_favorite,
+            commands::conversation_stats::get_conversation_stats,
+            commands::paste::prepare_paste,
+            commands::spreadsheets::get_data_tools,
+            commands::spreadsheets::describe_data_attachment,
+            commands::spreadsheets::query_data_attachment,
+            commands::attached_db::describe_attached_db,
+            commands::attached_db::query_attached_db,
+            commands::transform::transform_data,
+            commands::calculator::calculate,
+            commands::calculator::convert_units,
+            commands::datetime::get_datetime_context,
+            commands::datetime::resolve_datetime,
+            commands::location::get_location_settings,
+            commands::location::set_location_settings,
+            commands::location::get_location_context,
+            commands::weather::get_weather,
+            commands::translation::detect_language,
+            commands::translation::translate_text,
+            commands::translation::translate_attachment,
+            commands::rewrite::rewrite_text,
+            commands::rewrite::rewrite_clipboard,
+            commands::external_input::take_external_input,
+            commands::external_input::run_external_input,
+            commands::external_input::install_text_services,
+            commands::external_input::remove_text_services,
+            commands::nostr_composer::get_nostr_composer_settings,
+            commands::nostr_composer::set_nostr_composer_settings,
+            commands::nostr_composer::list_nostr_drafts,
+            commands::nostr_composer::save_nostr_draft,
+            commands::nostr_composer::delete_nostr_draft,
+            commands::nostr_composer::revise_nostr_draft,
+            commands::nostr_composer::publish_nostr_draft,
+            commands::nostr_composer::sync_nostr_drafts,
+            commands::media::get_media_settings,
+            commands::media::set_media_settings,
+            commands::media::upload_media,
+            commands::media::upload_media_file,
+            commands::audio_export::export_conversation_audio,
+            commands::flashcards::generate_flashcards,
+            commands::flashcards::export_flashcards,
+        ])
+        .typ::<commands::events::EventPayloads>()
+        .error_handling(tauri_specta::ErrorHandlingMode::Throw)
+}
+
+/// TypeScript exporter for the bindings; IDs and timestamps fit in a
+/// `number`.
+#[cfg(debug_assertions)]
+fn typescript() -> specta_typescript::Typescript {
+    specta_typescript::Typescript::default().bigint(specta_typescript::BigIntExportBehavior::Number)
+}
+
 /// Runs the Tauri application.
 ///
 /// This function initializes the Tauri runtime, registers plugins,
+/// opens the application database, starts background services,
 /// and starts the application event loop.
 ///
+/// Setup is kept to what the first window needs; slower initialization is
+/// deferred until after it shows (see `commands::startup`).
+///
+/// Debug builds regenerate the TypeScript bindings at `src/lib/bindings.ts`
+/// on every launch, logging an error if they can't be written.
+///
 /// # Panics
 ///
 /// Panics if the Tauri application fails to initialize or run.
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
+#[allow(clippy::too_many_lines)] // every plugin and managed state is set up here
 pub fn run() {
-    tauri::Builder::default()
+    let launched = std::time::Instant::now();
+    let bindings = bindings();
+    #[cfg(debug_assertions)]
+    let exported = bindings
+        .export(typescript(), BINDINGS_PATH)
+        .map_err(|e| e.to_string());
+    let typed_handler = bindings.invoke_handler();
+    let raw_handler: fn(tauri::ipc::Invoke<tauri::Wry>) -> bool =
+        tauri::generate_handler![commands::attachments::read_attachment_bytes];
+    let builder = tauri::Builder::default();
+    // Single-instance must be registered before any other plugin.
+    #[cfg(desktop)]
+    let builder = builder
+        .plugin(commands::instance::plugin())
+        .plugin(commands::windows::window_state_plugin());
+
+    builder
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_keyring::init())
-        .invoke_handler(tauri::generate_handler![
-            greet,
-            commands::credentials::get_api_key,
-            commands::credentials::set_api_key,
-            commands::credentials::delete_api_key,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(commands::updater::plugin())
+        .setup(move |app| {
+            use commands::startup::{self, Subsystem};
+
+            commands::logs::init(app.handle())?;
+            #[cfg(debug_assertions)]
+            if let Err(e) = &exported {
+                tracing::error!("failed to export TypeScript bindings: {e}");
+            }
+            commands::crash_reports::init(app.handle())?;
+            app.manage(startup::Startup::new(launched));
+            let data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&data_dir)?;
+            app.manage(db::Database::open(&data_dir.join(db::DATABASE_FILE))?);
+            startup::mark_ready(app.handle(), Subsystem::Database);
+            commands::network::init(app.handle());
+            app.manage(commands::network_activity::NetworkActivity::default());
+            app.manage(commands::tray::TrayState::default());
+            app.manage(commands::i18n::Localizer::default());
+            let settings = commands::settings::load(&app.state::<db::Database>().conn())?;
+            commands::settings::apply(app.handle(), &settings);
+            app.manage(commands::instance::LaunchArgs::from_env());
+            app.manage(commands::windows::WindowRegistry::default());
+            app.manage(commands::windows::CurrentConversation::default());
+            app.manage(commands::updater::UpdaterState::default());
+            app.manage(commands::notifications::LastNotification::default());
+            app.manage(commands::stable_diffusion::LocalRender::default());
+            app.manage(commands::rate_limit::RateLimiter::default());
+            app.manage(commands::spend::SpendGuard::default());
+            app.manage(commands::chat_stream::StreamAcks::default());
+            app.manage(commands::external_input::PendingInput::default());
+            app.manage(commands::location::LocationCache::default());
+            commands::jobs::init(app.handle());
+            commands::session::init(app.handle())?;
+            commands::attachments::init(app.handle())?;
+            commands::plugins::init(app.handle())?;
+            commands::scheduler::start(app.handle().clone());
+            commands::clipboard::start(app.handle());
+            commands::updater::start(app.handle().clone());
+            commands::telemetry::start(app.handle());
+            commands::outbox::start(app.handle());
+            commands::api_server::start(app.handle());
+            commands::ws_bridge::start(app.handle());
+            commands::deep_link::init(app.handle())?;
+            #[cfg(desktop)]
+            {
+                commands::shortcuts::init(app.handle())?;
+                commands::tray::init(app.handle())?;
+            }
+            commands::automations::start(app.handle());
+            commands::reading_list::start(app.handle());
+            commands::feeds::start(app.handle());
+            commands::nostr_digest::start(app.handle());
+            startup::defer(
+                app.handle(),
+                Subsystem::Attachments,
+                commands::attachments::resume,
+            );
+            startup::defer(app.handle(), Subsystem::Plugins, commands::plugins::warm_up);
+            startup::defer(app.handle(), Subsystem::Webhooks, commands::webhooks::start);
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            WindowEvent::CloseRequested { api, .. }
+                if window.label() == commands::quick_capture::MAIN_WINDOW_LABEL
+                    && commands::tray::close_to_tray(window.app_handle()) =>
+            {
+                api.prevent_close();
+                if let Err(e) = window.hide() {
+                    tracing::warn!("failed to hide main window: {e}");
+                }
+            }
+            WindowEvent::Focused(true) => {
+                commands::notifications::handle_focus(window.app_handle());
+            }
+            WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => {
+                commands::attachments::stage_paths(
+                    window.app_handle(),
+                    paths,
+                    commands::attachments::AttachmentOrigin::Drop,
+                    Some(window.label()),
+                );
+            }
+            WindowEvent::Destroyed => {
+                window
+                    .state::<commands::windows::WindowRegistry>()
+                    .remove(window.label());
+                commands::session::window_destroyed(window.app_handle(), window.label());
+            }
+            _ => {}
+        })
+        .invoke_handler(move |invoke| {
+            // Returns raw bytes, which the bindings can't describe.
+            if invoke.message.command() == "read_attachment_bytes" {
+                raw_handler(invoke)
+            } else {
+                typed_handler(invoke)
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                commands::session::mark_closed(app);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails when `src/lib/bindings.ts` no longer matches the commands;
+    /// run with `UPDATE_BINDINGS=1` to rewrite it.
+    #[test]
+    fn test_bindings_are_current() {
+        let exported = bindings().export_str(typescript()).unwrap();
+        assert!(exported.contains("getEventSchema"));
+        assert!(exported.contains("EventPayloads"));
+        if std::env::var_os("UPDATE_BINDINGS").is_some() {
+            std::fs::write(BINDINGS_PATH, &exported).unwrap();
+        }
+        let committed = std::fs::read_to_string(BINDINGS_PATH).unwrap_or_default();
+        assert!(
+            committed == exported,
+            "src/lib/bindings.ts is stale; rerun this test with UPDATE_BINDINGS=1"
+        );
+    }
 }
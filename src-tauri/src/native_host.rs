@@ -0,0 +1,380 @@
+//! Native-messaging host for the companion browser extension.
+//!
+//! The browser starts the `gibber` executable itself, passing the calling
+//! extension's origin (Chromium) or the host manifest path and extension
+//! ID (Firefox), and exchanges length-prefixed JSON messages over
+//! stdin/stdout until the extension disconnects. `gibber
+//! native-host-manifest` prints the manifest to install for a browser.
+//!
+//! Requests carry the page URL; its origin must be allowed in the app's
+//! browser bridge settings (see [`browser_bridge`]) before any page text is
+//! sent to a model. Unknown origins are recorded as pending and refused.
+//!
+//! ```json
+//! { "id": 1, "type": "summarize", "url": "https://…", "title": "…", "text": "…", "selection": null }
+//! { "id": 2, "type": "ask", "url": "https://…", "text": "…", "prompt": "What is the BPM?" }
+//! ```
+
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::chat::{ChatMessage, ChatRequest, MessageRole};
+use crate::cli::{self, CliError};
+use crate::commands::browser_bridge::{self, OriginAccess};
+use crate::commands::conversations::{self, NewConversation};
+use crate::db::Database;
+
+/// Name of the host in browser manifests.
+pub const HOST_NAME: &str = "art.everythingsings.gibber_ai";
+
+/// Largest accepted message from the browser.
+const MAX_MESSAGE_BYTES: u32 = 16 * 1024 * 1024;
+
+/// Page text beyond this many characters is cut before prompting.
+const MAX_CONTENT_CHARS: usize = 100_000;
+
+const SUMMARIZE_PROMPT: &str =
+    "Summarize the web page below for the user in a few short paragraphs. \
+     If a selection is given, focus on it.";
+
+const ASK_PROMPT: &str = "Answer the user's question using the web page below.";
+
+/// Browser whose manifest to print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    /// Chrome, Edge, Brave and other Chromium browsers
+    Chromium,
+    /// Firefox
+    Firefox,
+}
+
+impl Browser {
+    /// Parses a `--browser` value.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "chrome" | "chromium" | "edge" | "brave" => Some(Self::Chromium),
+            "firefox" => Some(Self::Firefox),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the calling extension if the browser launched this process.
+pub fn caller(args: &[String]) -> Option<String> {
+    if let Some(origin) = args
+        .iter()
+        .find(|arg| arg.starts_with("chrome-extension://"))
+    {
+        return Some(origin.clone());
+    }
+    match args {
+        [manifest, extension, ..]
+            if Path::new(manifest)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+                && extension.contains('@') =>
+        {
+            Some(extension.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the host manifest for `browser`, pointing at `executable`.
+pub fn manifest(browser: Browser, extension_id: &str, executable: &str) -> Value {
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "Gibber AI",
+        "path": executable,
+        "type": "stdio",
+    });
+    match browser {
+        Browser::Chromium => {
+            manifest["allowed_origins"] = json!([format!("chrome-extension://{extension_id}/")]);
+        }
+        Browser::Firefox => manifest["allowed_extensions"] = json!([extension_id]),
+    }
+    manifest
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum HostRequest {
+    Ping,
+    Summarize {
+        url: String,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        selection: Option<String>,
+        #[serde(default)]
+        model: Option<String>,
+    },
+    Ask {
+        url: String,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        selection: Option<String>,
+        prompt: String,
+        #[serde(default)]
+        model: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(flatten)]
+    request: HostRequest,
+}
+
+/// Reads one message; `None` once the browser closes the pipe.
+fn read_message(input: &mut impl Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let length = u32::from_ne_bytes(length);
+    if length > MAX_MESSAGE_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("message of {length} bytes is too large"),
+        ));
+    }
+    let length = usize::try_from(length)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let mut message = vec![0; length];
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> std::io::Result<()> {
+    let bytes = message.to_string().into_bytes();
+    let length = u32::try_from(bytes.len())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    output.write_all(&length.to_ne_bytes())?;
+    output.write_all(&bytes)?;
+    output.flush()
+}
+
+/// Checks the page's origin against the stored permissions, recording
+/// unknown origins as pending.
+fn check_origin(db: &Database, url: &str) -> Result<(), CliError> {
+    let origin = browser_bridge::origin_of(url)
+        .ok_or_else(|| CliError::new("INVALID_ORIGIN", format!("No origin for {url}")))?;
+    let conn = db.conn();
+    let mut settings = browser_bridge::load_settings(&conn);
+    let access = if let Some(access) = settings.origins.get(&origin) {
+        *access
+    } else {
+        settings
+            .origins
+            .insert(origin.clone(), OriginAccess::Pending);
+        browser_bridge::save_settings(&conn, &settings)?;
+        OriginAccess::Pending
+    };
+    match access {
+        OriginAccess::Allowed => Ok(()),
+        OriginAccess::Denied => Err(CliError::new(
+            "ORIGIN_DENIED",
+            format!("{origin} is blocked in Gibber AI"),
+        )),
+        OriginAccess::Pending => Err(CliError::new(
+            "ORIGIN_NOT_ALLOWED",
+            format!("Allow {origin} in Gibber AI's browser settings first"),
+        )),
+    }
+}
+
+fn page_content(url: &str, title: Option<&str>, text: &str, selection: Option<&str>) -> String {
+    let mut content = format!("URL: {url}\n");
+    if let Some(title) = title {
+        let _ = writeln!(content, "Title: {title}");
+    }
+    if let Some(selection) = selection.filter(|s| !s.trim().is_empty()) {
+        content.push_str("\nSelection:\n");
+        content.extend(selection.chars().take(MAX_CONTENT_CHARS));
+        content.push('\n');
+    }
+    content.push_str("\nPage text:\n");
+    content.extend(text.chars().take(MAX_CONTENT_CHARS));
+    content
+}
+
+async fn handle(db: &Database, request: HostRequest) -> Result<Value, CliError> {
+    let (url, title, content, system, model) = match request {
+        HostRequest::Ping => return Ok(json!({ "reply": "pong" })),
+        HostRequest::Summarize {
+            url,
+            title,
+            text,
+            selection,
+            model,
+        } => {
+            let content = page_content(&url, title.as_deref(), &text, selection.as_deref());
+            (url, title, content, SUMMARIZE_PROMPT, model)
+        }
+        HostRequest::Ask {
+            url,
+            title,
+            text,
+            selection,
+            prompt,
+            model,
+        } => {
+            let page = page_content(&url, title.as_deref(), &text, selection.as_deref());
+            (
+                url,
+                title,
+                format!("{page}\n\nQuestion: {prompt}"),
+                ASK_PROMPT,
+                model,
+            )
+        }
+    };
+    check_origin(db, &url)?;
+
+    let request = ChatRequest::new(
+        model.as_deref(),
+        vec![
+            ChatMessage::new(MessageRole::System, system),
+            ChatMessage::new(MessageRole::User, content.clone()),
+        ],
+    );
+    let completion = cli::complete(db, &request).await?;
+    let save = browser_bridge::load_settings(&db.conn()).save_conversations;
+    let conversation_id = if save {
+        let conversation = NewConversation {
+            title: title.unwrap_or_else(|| conversations::title_from_prompt(&url)),
+            model,
+            system_prompt: Some(system.to_string()),
+            source: Some("browser".to_string()),
+        };
+        Some(cli::save_exchange(db, &conversation, content, &completion)?)
+    } else {
+        None
+    };
+    Ok(json!({
+        "reply": completion.content,
+        "model": completion.model,
+        "conversationId": conversation_id,
+    }))
+}
+
+/// Answers messages until the browser disconnects.
+///
+/// # Errors
+///
+/// Returns a `CliError` with code `IO` if stdin or stdout fail.
+pub fn run(db: &Database) -> Result<(), CliError> {
+    let mut input = std::io::stdin().lock();
+    let mut output = std::io::stdout().lock();
+    while let Some(message) = read_message(&mut input)? {
+        let reply = match serde_json::from_slice::<Envelope>(&message) {
+            Ok(envelope) => {
+                let id = envelope.id;
+                match tauri::async_runtime::block_on(handle(db, envelope.request)) {
+                    Ok(mut result) => {
+                        result["id"] = json!(id);
+                        result["ok"] = json!(true);
+                        result
+                    }
+                    Err(e) => {
+                        json!({ "id": id, "ok": false, "code": e.code, "message": e.message })
+                    }
+                }
+            }
+            Err(e) => json!({ "ok": false, "code": "INVALID_MESSAGE", "message": e.to_string() }),
+        };
+        write_message(&mut output, &reply)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::browser_bridge::BrowserBridgeSettings;
+
+    #[test]
+    fn test_caller_detects_browser_launches() {
+        let chrome = vec!["chrome-extension://abcdef/".to_string()];
+        assert_eq!(
+            caller(&chrome).as_deref(),
+            Some("chrome-extension://abcdef/")
+        );
+        let firefox = vec![
+            "/usr/lib/mozilla/native-messaging-hosts/art.everythingsings.gibber_ai.json"
+                .to_string(),
+            "gibber@everythingsings.art".to_string(),
+        ];
+        assert_eq!(
+            caller(&firefox).as_deref(),
+            Some("gibber@everythingsings.art")
+        );
+        assert_eq!(caller(&["ask".to_string(), "hi".to_string()]), None);
+    }
+
+    #[test]
+    fn test_messages_round_trip() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &json!({ "type": "ping" })).unwrap();
+        let mut reader = buffer.as_slice();
+        let message = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(message, br#"{"type":"ping"}"#);
+        assert!(read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_origins_must_be_allowed() {
+        let db = Database::open_in_memory().unwrap();
+        let url = "https://example.com/track";
+        assert_eq!(
+            check_origin(&db, url).unwrap_err().code,
+            "ORIGIN_NOT_ALLOWED"
+        );
+        let pending = browser_bridge::load_settings(&db.conn());
+        assert_eq!(
+            pending.origins.get("https://example.com"),
+            Some(&OriginAccess::Pending)
+        );
+
+        let mut settings = BrowserBridgeSettings::default();
+        settings
+            .origins
+            .insert("https://example.com".to_string(), OriginAccess::Allowed);
+        browser_bridge::save_settings(&db.conn(), &settings).unwrap();
+        assert!(check_origin(&db, url).is_ok());
+    }
+
+    #[test]
+    fn test_manifest_per_browser() {
+        let chrome = manifest(Browser::Chromium, "abcdef", "/usr/bin/gibber");
+        assert_eq!(chrome["allowed_origins"][0], "chrome-extension://abcdef/");
+        let firefox = manifest(
+            Browser::Firefox,
+            "gibber@everythingsings.art",
+            "/usr/bin/gibber",
+        );
+        assert_eq!(
+            firefox["allowed_extensions"][0],
+            "gibber@everythingsings.art"
+        );
+    }
+}
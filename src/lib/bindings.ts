@@ -0,0 +1,9694 @@
+
+// This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
+
+/** user-defined commands **/
+
+
+export const commands = {
+/**
+ * Greets the user with a personalized message.
+ * 
+ * This is a sample command demonstrating Tauri's IPC mechanism.
+ * It will be replaced with actual Gibber AI commands in later phases.
+ * 
+ * # Arguments
+ * 
+ * * `name` - The name to include in the greeting
+ * 
+ * # Returns
+ * 
+ * A greeting string formatted with the provided name
+ * 
+ * # Example
+ * 
+ * ```ignore
+ * // From the frontend:
+ * const message = await invoke("greet", { name: "World" });
+ * // Returns: "Hello, World! You've been greeted from Rust!"
+ * ```
+ */
+async greet(name: string) : Promise<string> {
+    return await TAURI_INVOKE("greet", { name });
+},
+/**
+ * Retrieves an API key from the system keyring.
+ * 
+ * # Arguments
+ * 
+ * * `app` - The Tauri application handle
+ * * `service` - The service identifier (e.g., "openrouter", "nostr")
+ * 
+ * # Returns
+ * 
+ * The API key if found, or `None` if the key doesn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, or if the keyring operation fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * // From the frontend:
+ * const apiKey = await invoke("get_api_key", { service: "openrouter" });
+ * if (apiKey) {
+ * console.log(`API key length: ${apiKey.length}`);
+ * }
+ * ```
+ */
+async getApiKey(service: string) : Promise<string | null> {
+    return await TAURI_INVOKE("get_api_key", { service });
+},
+/**
+ * Stores an API key in the system keyring.
+ * 
+ * If a key already exists for the given service, it will be overwritten.
+ * 
+ * # Arguments
+ * 
+ * * `app` - The Tauri application handle
+ * * `service` - The service identifier (e.g., "openrouter", "nostr")
+ * * `key` - The API key to store
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, or if the keyring operation fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * // From the frontend:
+ * await invoke("set_api_key", { service: "openrouter", key: "sk-..." });
+ * ```
+ */
+async setApiKey(service: string, key: string) : Promise<null> {
+    return await TAURI_INVOKE("set_api_key", { service, key });
+},
+/**
+ * Deletes an API key from the system keyring.
+ * 
+ * # Arguments
+ * 
+ * * `app` - The Tauri application handle
+ * * `service` - The service identifier (e.g., "openrouter", "nostr")
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the key was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, or if the keyring operation fails (other than key
+ * not found).
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * // From the frontend:
+ * const deleted = await invoke("delete_api_key", { service: "openrouter" });
+ * console.log(deleted ? "Key deleted" : "Key not found");
+ * ```
+ */
+async deleteApiKey(service: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_api_key", { service });
+},
+/**
+ * Lists all conversations, most recently updated first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const conversations = await invoke("list_conversations");
+ * ```
+ */
+async listConversations() : Promise<Conversation[]> {
+    return await TAURI_INVOKE("list_conversations");
+},
+/**
+ * Retrieves a conversation with all of its messages.
+ * 
+ * Long conversations are better opened with [`get_messages`], which
+ * loads them a page at a time.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, or `DATABASE` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const conversation = await invoke("get_conversation", { id });
+ * ```
+ */
+async getConversation(id: string) : Promise<ConversationWithMessages> {
+    return await TAURI_INVOKE("get_conversation", { id });
+},
+/**
+ * Exports a conversation as JSON (the default) or as an HTML page with
+ * rendered math, code, and diagrams.
+ * 
+ * With `deidentify`, names, emails, IP addresses, and secrets are replaced
+ * by placeholders first; `deidentify_conversation` previews the result.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, or `DATABASE` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const html = await invoke("export_conversation", {
+ * id,
+ * format: "html",
+ * deidentify: { names: ["Ana Lopez"] },
+ * });
+ * ```
+ */
+async exportConversation(id: string, format: ExportFormat | null, deidentify: DeidentifyOptions | null) : Promise<string> {
+    return await TAURI_INVOKE("export_conversation", { id, format, deidentify });
+},
+/**
+ * Creates an empty conversation.
+ * 
+ * A chat conversation without a system prompt is enrolled in the active
+ * A/B experiment, if any, and gets the prompt of its variant (see
+ * [`experiments`]).
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the insert fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const conversation = await invoke("create_conversation", {
+ * input: { title: "Ambient drone", model: "anthropic/claude-sonnet-4" },
+ * });
+ * ```
+ */
+async createConversation(input: NewConversation) : Promise<Conversation> {
+    return await TAURI_INVOKE("create_conversation", { input });
+},
+/**
+ * Appends a message to an existing conversation.
+ * 
+ * The stored message is broadcast as [`events::CONVERSATION_MESSAGE`].
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, or `DATABASE` if the insert fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("append_message", {
+ * conversationId: id,
+ * message: { role: "user", content: "Add a hi-hat" },
+ * });
+ * ```
+ */
+async appendMessage(conversationId: string, message: NewMessage) : Promise<StoredMessage> {
+    return await TAURI_INVOKE("append_message", { conversationId, message });
+},
+/**
+ * Deletes a conversation and its messages.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the conversation was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deleteConversation(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_conversation", { id });
+},
+/**
+ * Lists all scheduled tasks.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const tasks = await invoke("list_scheduled_tasks");
+ * ```
+ */
+async listScheduledTasks() : Promise<ScheduledTask[]> {
+    return await TAURI_INVOKE("list_scheduled_tasks");
+},
+/**
+ * Creates a scheduled task.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SCHEDULE` if the cron
+ * expression doesn't parse, `INVALID_INPUT` for empty fields, `NOT_FOUND`
+ * if the workflow doesn't exist, or `DATABASE` if the insert fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("create_scheduled_task", {
+ * input: { name: "Morning idea", prompt: "Suggest a drum pattern", cron: "0 8 * * *" },
+ * });
+ * ```
+ */
+async createScheduledTask(input: ScheduledTaskInput) : Promise<ScheduledTask> {
+    return await TAURI_INVOKE("create_scheduled_task", { input });
+},
+/**
+ * Replaces the configuration of a scheduled task.
+ * 
+ * The next run time is recomputed from the new cron expression.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the task doesn't
+ * exist, or the same validation errors as [`create_scheduled_task`].
+ */
+async updateScheduledTask(id: string, input: ScheduledTaskInput) : Promise<ScheduledTask> {
+    return await TAURI_INVOKE("update_scheduled_task", { id, input });
+},
+/**
+ * Deletes a scheduled task. Conversations it produced are kept.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the task was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deleteScheduledTask(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_scheduled_task", { id });
+},
+/**
+ * Runs a scheduled task immediately, outside of its schedule.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the task doesn't exist.
+ * Failures of the run itself are reported in the returned payload.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const result = await invoke("run_scheduled_task_now", { id });
+ * if (result.status === "succeeded") openConversation(result.conversationId);
+ * ```
+ */
+async runScheduledTaskNow(id: string) : Promise<RunCompletedPayload> {
+    return await TAURI_INVOKE("run_scheduled_task_now", { id });
+},
+/**
+ * Lists all saved workflows.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listWorkflows() : Promise<Workflow[]> {
+    return await TAURI_INVOKE("list_workflows");
+},
+/**
+ * Creates a workflow, or replaces an existing one when `id` is given.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if the definition is
+ * invalid, `NOT_FOUND` if `id` doesn't exist, or `DATABASE` on failure.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("save_workflow", {
+ * id: null,
+ * input: {
+ * name: "Idea to code",
+ * steps: [
+ * { name: "idea", prompt: "Describe a techno track about {{input}}" },
+ * { name: "code", prompt: "Write Gibber code for: {{input}}" },
+ * ],
+ * },
+ * });
+ * ```
+ */
+async saveWorkflow(id: string | null, input: WorkflowInput) : Promise<Workflow> {
+    return await TAURI_INVOKE("save_workflow", { id, input });
+},
+/**
+ * Deletes a workflow and its run history.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the workflow was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deleteWorkflow(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_workflow", { id });
+},
+/**
+ * Runs a workflow to completion.
+ * 
+ * Progress is reported through [`events::WORKFLOW_STEP`] while the command is
+ * pending.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the workflow doesn't
+ * exist, or `DATABASE` if the run cannot be recorded. Step failures are
+ * reported in the returned run rather than as an error.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const unlisten = await listen("workflow://step", (e) => console.log(e.payload));
+ * const run = await invoke("run_workflow", { id, input: "rainy night" });
+ * ```
+ */
+async runWorkflow(id: string, input: string) : Promise<WorkflowRun> {
+    return await TAURI_INVOKE("run_workflow", { id, input });
+},
+/**
+ * Lists past runs of a workflow, newest first, without step details.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listWorkflowRuns(workflowId: string) : Promise<WorkflowRun[]> {
+    return await TAURI_INVOKE("list_workflow_runs", { workflowId });
+},
+/**
+ * Retrieves a run with its per-step records.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the run doesn't exist.
+ */
+async getWorkflowRun(runId: string) : Promise<WorkflowRun> {
+    return await TAURI_INVOKE("get_workflow_run", { runId });
+},
+/**
+ * Shows the quick-capture window.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the window cannot be shown.
+ */
+async showQuickCapture() : Promise<null> {
+    return await TAURI_INVOKE("show_quick_capture");
+},
+/**
+ * Hides the quick-capture window.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the window cannot be hidden.
+ */
+async hideQuickCapture() : Promise<null> {
+    return await TAURI_INVOKE("hide_quick_capture");
+},
+/**
+ * Submits a prompt typed into the quick-capture window.
+ * 
+ * Creates a conversation containing the prompt, hides the capture window,
+ * focuses the main window, and emits [`events::QUICK_CAPTURE_SUBMITTED`] to it
+ * so it can stream the answer.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `EMPTY_PROMPT` if the prompt is
+ * blank, `DATABASE` if the conversation cannot be stored, or `WINDOW` if the
+ * windows cannot be updated.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * // From the quick-capture window:
+ * await invoke("submit_quick_capture", { prompt: "A slow ambient pad" });
+ * 
+ * // In the main window:
+ * await listen("quick-capture://submitted", (e) => openAndAnswer(e.payload.conversationId));
+ * ```
+ */
+async submitQuickCapture(prompt: string, model: string | null) : Promise<Conversation> {
+    return await TAURI_INVOKE("submit_quick_capture", { prompt, model });
+},
+/**
+ * Returns the current tray state.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { closeToTray, syncPaused, generating } = await invoke("get_tray_status");
+ * ```
+ */
+async getTrayStatus() : Promise<TrayStatus> {
+    return await TAURI_INVOKE("get_tray_status");
+},
+/**
+ * Enables or disables hiding the main window to the tray on close.
+ * 
+ * Shorthand for updating the `closeToTray` setting.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the setting cannot be stored.
+ */
+async setCloseToTray(enabled: boolean) : Promise<TrayStatus> {
+    return await TAURI_INVOKE("set_close_to_tray", { enabled });
+},
+/**
+ * Marks a frontend generation as started.
+ * 
+ * Every call must be paired with [`end_generation`].
+ */
+async beginGeneration() : Promise<void> {
+    await TAURI_INVOKE("begin_generation");
+},
+/**
+ * Marks a frontend generation as finished.
+ */
+async endGeneration() : Promise<void> {
+    await TAURI_INVOKE("end_generation");
+},
+/**
+ * Returns the arguments of the first launch, once.
+ * 
+ * Later calls return `None`, so a reloaded frontend doesn't act on the same
+ * launch twice. Arguments of later launches arrive via
+ * [`events::INSTANCE_FORWARDED`].
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const args = await invoke("take_launch_args");
+ * if (args) handleLaunch(args);
+ * ```
+ */
+async takeLaunchArgs() : Promise<ForwardedArgs | null> {
+    return await TAURI_INVOKE("take_launch_args");
+},
+/**
+ * Returns navigations from links that arrived before the frontend was ready.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * for (const navigation of await invoke("take_pending_navigation")) navigate(navigation);
+ * await listen("deep-link://navigate", (e) => navigate(e.payload));
+ * ```
+ */
+async takePendingNavigation() : Promise<Navigation[]> {
+    return await TAURI_INVOKE("take_pending_navigation");
+},
+/**
+ * Opens a conversation in its own window, or focuses the existing one.
+ * 
+ * # Returns
+ * 
+ * The window showing the conversation.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation doesn't
+ * exist, or `WINDOW` if the window cannot be created.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("open_conversation_window", { conversationId: id });
+ * ```
+ */
+async openConversationWindow(conversationId: string) : Promise<ConversationWindow> {
+    return await TAURI_INVOKE("open_conversation_window", { conversationId });
+},
+/**
+ * Lists conversations that are open in their own windows.
+ */
+async listConversationWindows() : Promise<ConversationWindow[]> {
+    return await TAURI_INVOKE("list_conversation_windows");
+},
+/**
+ * Closes the window showing a conversation, if there is one.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if a window was closed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the window cannot be closed.
+ */
+async closeConversationWindow(conversationId: string) : Promise<boolean> {
+    return await TAURI_INVOKE("close_conversation_window", { conversationId });
+},
+/**
+ * Records the conversation shown in the focused window.
+ * 
+ * Windows call this when they show a conversation or gain focus; pass
+ * `null` when no conversation is open.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_current_conversation", { conversationId: id });
+ * ```
+ */
+async setCurrentConversation(conversationId: string | null) : Promise<void> {
+    await TAURI_INVOKE("set_current_conversation", { conversationId });
+},
+/**
+ * Returns the notification preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be read.
+ */
+async getNotificationSettings() : Promise<NotificationSettings> {
+    return await TAURI_INVOKE("get_notification_settings");
+},
+/**
+ * Replaces the notification preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_notification_settings", {
+ * settings: { ...current, scheduledTask: false },
+ * });
+ * ```
+ */
+async setNotificationSettings(settings: NotificationSettings) : Promise<NotificationSettings> {
+    return await TAURI_INVOKE("set_notification_settings", { settings });
+},
+/**
+ * Reports a finished frontend generation so it can be notified.
+ * 
+ * Does nothing when generation notifications are disabled or the app is
+ * focused.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("notify_generation_complete", { conversationId, title, content });
+ * ```
+ */
+async notifyGenerationComplete(conversationId: string, title: string, content: string) : Promise<void> {
+    await TAURI_INVOKE("notify_generation_complete", { conversationId, title, content });
+},
+/**
+ * Returns the clipboard watcher preferences.
+ */
+async getClipboardSettings() : Promise<ClipboardSettings> {
+    return await TAURI_INVOKE("get_clipboard_settings");
+},
+/**
+ * Replaces the clipboard watcher preferences; applies immediately.
+ * 
+ * Disabling the watcher also drops the current capture.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_clipboard_settings", { settings: { ...current, enabled: true } });
+ * ```
+ */
+async setClipboardSettings(settings: ClipboardSettings) : Promise<ClipboardSettings> {
+    return await TAURI_INVOKE("set_clipboard_settings", { settings });
+},
+/**
+ * Returns a summary of the newest capture, if any.
+ */
+async getClipboardCapture() : Promise<CapturedClip | null> {
+    return await TAURI_INVOKE("get_clipboard_capture");
+},
+/**
+ * Drops the newest capture without asking about it.
+ */
+async clearClipboardCapture() : Promise<void> {
+    await TAURI_INVOKE("clear_clipboard_capture");
+},
+/**
+ * Asks Gibber about the newest capture.
+ * 
+ * # Errors
+ * 
+ * See [`ask`].
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("ask_about_clipboard", { action: "summarize" });
+ * ```
+ */
+async askAboutClipboard(action: ClipboardAction | null) : Promise<Conversation> {
+    return await TAURI_INVOKE("ask_about_clipboard", { action });
+},
+/**
+ * Stages a file picked in the UI.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the file is rejected or cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const attachment = await invoke("stage_attachment", { path });
+ * ```
+ */
+async stageAttachment(path: string) : Promise<Attachment> {
+    return await TAURI_INVOKE("stage_attachment", { path });
+},
+/**
+ * Lists attachments not yet attached to a conversation, oldest first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listPendingAttachments() : Promise<Attachment[]> {
+    return await TAURI_INVOKE("list_pending_attachments");
+},
+/**
+ * Lists the attachments of a conversation.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listConversationAttachments(conversationId: string) : Promise<Attachment[]> {
+    return await TAURI_INVOKE("list_conversation_attachments", { conversationId });
+},
+/**
+ * Attaches a staged attachment to a conversation.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment or
+ * conversation doesn't exist.
+ */
+async attachToConversation(attachmentId: string, conversationId: string) : Promise<Attachment> {
+    return await TAURI_INVOKE("attach_to_conversation", { attachmentId, conversationId });
+},
+/**
+ * Reads the text of a Markdown or plain text attachment.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment
+ * doesn't exist or `NOT_TEXT` if it isn't a text document.
+ */
+async readAttachmentText(id: string) : Promise<string> {
+    return await TAURI_INVOKE("read_attachment_text", { id });
+},
+/**
+ * Deletes an attachment and its file.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the attachment existed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the row cannot be deleted.
+ */
+async deleteAttachment(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_attachment", { id });
+},
+/**
+ * Captures a screenshot into a conversation.
+ * 
+ * Attaches to `conversationId` if given, otherwise to the current
+ * conversation; with neither, the screenshot stays pending.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the screen or window
+ * doesn't exist, `INVALID_REGION` for regions outside the screen, or
+ * `CAPTURE_FAILED` if the platform refuses the capture (e.g. missing
+ * screen-recording permission on macOS).
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("capture_screenshot", { target: { mode: "screen" } });
+ * await invoke("capture_screenshot", {
+ * target: { mode: "region", x: 0, y: 0, width: 800, height: 600 },
+ * });
+ * ```
+ */
+async captureScreenshot(target: CaptureTarget, conversationId: string | null) : Promise<Attachment> {
+    return await TAURI_INVOKE("capture_screenshot", { target, conversationId });
+},
+/**
+ * Lists capturable screens and windows.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if they cannot be enumerated.
+ */
+async listCaptureSources() : Promise<CaptureSources> {
+    return await TAURI_INVOKE("list_capture_sources");
+},
+/**
+ * Returns the update preferences.
+ */
+async getUpdateSettings() : Promise<UpdateSettings> {
+    return await TAURI_INVOKE("get_update_settings");
+},
+/**
+ * Replaces the update preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_update_settings", { settings: { ...current, channel: "beta" } });
+ * ```
+ */
+async setUpdateSettings(settings: UpdateSettings) : Promise<UpdateSettings> {
+    return await TAURI_INVOKE("set_update_settings", { settings });
+},
+/**
+ * Checks the selected channel for an update now.
+ * 
+ * # Returns
+ * 
+ * The available update, or `None` if the app is up to date.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `DISABLED` in builds without an
+ * update key, or `UPDATE_FAILED` if the manifest cannot be fetched.
+ */
+async checkForUpdates() : Promise<UpdateInfo | null> {
+    return await TAURI_INVOKE("check_for_updates");
+},
+/**
+ * Downloads the update found by the last check.
+ * 
+ * Progress arrives via `updater://progress`, completion via
+ * `updater://ready`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NO_UPDATE` if nothing was found,
+ * `BUSY` if a download is running, or `UPDATE_FAILED` if the download or
+ * its signature verification fails.
+ */
+async downloadUpdate() : Promise<null> {
+    return await TAURI_INVOKE("download_update");
+},
+/**
+ * Installs the downloaded update and restarts the app.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_READY` if no update has been
+ * downloaded, or `UPDATE_FAILED` if installing fails.
+ */
+async restartToUpdate() : Promise<null> {
+    return await TAURI_INVOKE("restart_to_update");
+},
+/**
+ * Returns the current shortcut bindings.
+ */
+async getShortcuts() : Promise<ShortcutSettings> {
+    return await TAURI_INVOKE("get_shortcuts");
+},
+/**
+ * Replaces the shortcut bindings without a restart.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SHORTCUT` or `CONFLICT` if the
+ * bindings are rejected before anything changes, or `UNAVAILABLE` if the OS
+ * refuses one (the previous bindings are then restored).
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_shortcuts", {
+ * settings: { ...current, pushToTalk: "CommandOrControl+Shift+Period" },
+ * });
+ * ```
+ */
+async setShortcuts(settings: ShortcutSettings) : Promise<ShortcutSettings> {
+    return await TAURI_INVOKE("set_shortcuts", { settings });
+},
+/**
+ * Restores the default shortcut bindings.
+ * 
+ * # Errors
+ * 
+ * See [`set_shortcuts`].
+ */
+async resetShortcuts() : Promise<ShortcutSettings> {
+    return await TAURI_INVOKE("reset_shortcuts");
+},
+/**
+ * Returns the settings document.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const settings = await invoke("get_settings");
+ * ```
+ */
+async getSettings() : Promise<Settings> {
+    return await TAURI_INVOKE("get_settings");
+},
+/**
+ * Updates some settings, leaving the rest unchanged.
+ * 
+ * # Returns
+ * 
+ * The full document after the update.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` if the patch is
+ * rejected; nothing is saved in that case.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("update_settings", { patch: { theme: "dark", temperature: 0.4 } });
+ * await listen("settings://changed", (e) => applySettings(e.payload));
+ * ```
+ */
+async updateSettings(patch: JsonValue) : Promise<Settings> {
+    return await TAURI_INVOKE("update_settings", { patch });
+},
+/**
+ * Writes all non-secret settings to a JSON file.
+ * 
+ * # Returns
+ * 
+ * The keys of the exported documents.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, or `IO` if the file cannot be written.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("export_settings", { path: "/Users/me/gibber-settings.json" });
+ * ```
+ */
+async exportSettings(path: string) : Promise<string[]> {
+    return await TAURI_INVOKE("export_settings", { path });
+},
+/**
+ * Shows what importing a settings file would change, without applying it.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `IO` if the file cannot be read,
+ * `INVALID_FILE` if it isn't a settings export, or `INVALID_SETTINGS`
+ * (`INVALID_SHORTCUT`, `CONFLICT` for shortcuts) if a document in it is
+ * invalid.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { changes, skipped } = await invoke("preview_settings_import", { path });
+ * for (const { key, current, incoming } of changes) showDiff(key, current, incoming);
+ * ```
+ */
+async previewSettingsImport(path: string) : Promise<ImportPlan> {
+    return await TAURI_INVOKE("preview_settings_import", { path });
+},
+/**
+ * Imports a settings file and applies it immediately.
+ * 
+ * The whole file is validated first; nothing changes if any document is
+ * invalid.
+ * 
+ * # Returns
+ * 
+ * The changes that were applied.
+ * 
+ * # Errors
+ * 
+ * See [`preview_settings_import`]. Also fails with `FORBIDDEN` outside the
+ * main and conversation windows, or if the OS refuses one of the imported
+ * shortcuts, in which case nothing else is changed.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("import_settings", { path });
+ * ```
+ */
+async importSettings(path: string) : Promise<ImportPlan> {
+    return await TAURI_INVOKE("import_settings", { path });
+},
+/**
+ * Restores every setting to its factory default.
+ * 
+ * Credentials in the OS keychain and machine-local documents (telemetry
+ * consent, network setup) are left alone.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, or if the defaults cannot be stored or the default
+ * shortcuts cannot be registered.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const settings = await invoke("reset_settings");
+ * ```
+ */
+async resetSettings() : Promise<Settings> {
+    return await TAURI_INVOKE("reset_settings");
+},
+/**
+ * Returns recent log entries, newest first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `IO` if the log files cannot be read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const entries = await invoke("query_logs", {
+ * query: { level: "warn", module: "updater", limit: 50 },
+ * });
+ * ```
+ */
+async queryLogs(query: LogQuery) : Promise<LogEntry[]> {
+    return await TAURI_INVOKE("query_logs", { query });
+},
+/**
+ * Opens the log folder in the system file manager.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `OPEN_FAILED` if the folder cannot be
+ * opened.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("open_log_folder");
+ * ```
+ */
+async openLogFolder() : Promise<null> {
+    return await TAURI_INVOKE("open_log_folder");
+},
+/**
+ * Writes a diagnostics zip for attaching to a bug report.
+ * 
+ * Saves to `path` if given, otherwise to the downloads folder as
+ * `gibber-ai-diagnostics-<timestamp>.zip`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, or `IO` if the bundle cannot be written. Failing
+ * checks are recorded in the report rather than returned.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { path } = await invoke("generate_diagnostics", { path: null });
+ * ```
+ */
+async generateDiagnostics(path: string | null) : Promise<DiagnosticsBundle> {
+    return await TAURI_INVOKE("generate_diagnostics", { path });
+},
+/**
+ * Returns the telemetry preferences.
+ */
+async getTelemetrySettings() : Promise<TelemetrySettings> {
+    return await TAURI_INVOKE("get_telemetry_settings");
+},
+/**
+ * Replaces the telemetry preferences.
+ * 
+ * Turning telemetry off also purges the queue.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_telemetry_settings", { settings: { enabled: true } });
+ * ```
+ */
+async setTelemetrySettings(settings: TelemetrySettings) : Promise<TelemetrySettings> {
+    return await TAURI_INVOKE("set_telemetry_settings", { settings });
+},
+/**
+ * Returns the next batch exactly as it would be sent.
+ * 
+ * Counts for today aren't included until the day is over.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the queue cannot be read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const batch = await invoke("preview_telemetry");
+ * showJson(batch);
+ * ```
+ */
+async previewTelemetry() : Promise<TelemetryBatch> {
+    return await TAURI_INVOKE("preview_telemetry");
+},
+/**
+ * Deletes everything in the queue.
+ * 
+ * # Returns
+ * 
+ * The number of deleted counts.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async purgeTelemetry() : Promise<number> {
+    return await TAURI_INVOKE("purge_telemetry");
+},
+/**
+ * Lists stored crash reports, newest first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the reports directory cannot be read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const reports = await invoke("list_crash_reports");
+ * if (reports.some((r) => !r.submittedAt)) askToSubmit(reports);
+ * ```
+ */
+async listCrashReports() : Promise<CrashReport[]> {
+    return await TAURI_INVOKE("list_crash_reports");
+},
+/**
+ * Deletes crash reports; all of them when `ids` is omitted.
+ * 
+ * # Returns
+ * 
+ * The number of deleted reports.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if a report file cannot be removed.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("delete_crash_reports", { ids: [report.id] });
+ * await invoke("delete_crash_reports", { ids: null });
+ * ```
+ */
+async deleteCrashReports(ids: string[] | null) : Promise<number> {
+    return await TAURI_INVOKE("delete_crash_reports", { ids });
+},
+/**
+ * Uploads one crash report after the user agreed to send it.
+ * 
+ * # Returns
+ * 
+ * The report with `submittedAt` set.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the report doesn't
+ * exist, `UNAVAILABLE` if this build has no upload endpoint, or
+ * `NETWORK_ERROR` if the upload fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * if (await confirm("Send this crash report?")) {
+ * await invoke("submit_crash_report", { id: report.id });
+ * }
+ * ```
+ */
+async submitCrashReport(id: string) : Promise<CrashReport> {
+    return await TAURI_INVOKE("submit_crash_report", { id });
+},
+/**
+ * Returns the network preferences.
+ */
+async getNetworkSettings() : Promise<NetworkSettings> {
+    return await TAURI_INVOKE("get_network_settings");
+},
+/**
+ * Replaces the network preferences; applies to the next request.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_PROXY` or
+ * `INVALID_CERTIFICATE` if the settings are rejected (nothing is saved in
+ * that case), or `DATABASE` if they cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_network_settings", {
+ * settings: {
+ * proxy: { mode: "manual", url: "socks5h://127.0.0.1:9050" },
+ * overrides: { updater: { mode: "direct" } },
+ * caCertificates: ["/etc/ssl/corp-root.pem"],
+ * },
+ * });
+ * ```
+ */
+async setNetworkSettings(settings: NetworkSettings) : Promise<NetworkSettings> {
+    return await TAURI_INVOKE("set_network_settings", { settings });
+},
+/**
+ * Returns recent outbound requests, newest first.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const requests = await invoke("get_network_activity");
+ * const hosts = new Set(requests.map((r) => r.host));
+ * ```
+ */
+async getNetworkActivity() : Promise<NetworkRequest[]> {
+    return await TAURI_INVOKE("get_network_activity");
+},
+/**
+ * Forgets all recorded requests.
+ */
+async clearNetworkActivity() : Promise<void> {
+    await TAURI_INVOKE("clear_network_activity");
+},
+/**
+ * Returns whether the app is online.
+ */
+async getNetworkStatus() : Promise<NetworkStatus> {
+    return await TAURI_INVOKE("get_network_status");
+},
+/**
+ * Stores a user message and queues the request for its reply.
+ * 
+ * The request is sent right away when online, otherwise as soon as
+ * connectivity returns.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, or `DATABASE` if the message cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const entry = await invoke("enqueue_message", {
+ * conversationId,
+ * content: "Make the bassline swing more",
+ * model: null,
+ * });
+ * await listen("outbox://sent", (e) => appendMessage(e.payload.message));
+ * ```
+ */
+async enqueueMessage(conversationId: string, content: string, model: string | null) : Promise<OutboxEntry> {
+    return await TAURI_INVOKE("enqueue_message", { conversationId, content, model });
+},
+/**
+ * Lists queued requests, oldest first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listOutbox() : Promise<OutboxEntry[]> {
+    return await TAURI_INVOKE("list_outbox");
+},
+/**
+ * Queues a failed request again.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the entry doesn't exist.
+ */
+async retryOutboxEntry(id: string) : Promise<OutboxEntry> {
+    return await TAURI_INVOKE("retry_outbox_entry", { id });
+},
+/**
+ * Removes a request from the outbox; its user message stays in the
+ * conversation.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the entry was removed, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async discardOutboxEntry(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("discard_outbox_entry", { id });
+},
+/**
+ * Returns the server preferences and address.
+ */
+async getApiServerStatus() : Promise<ApiServerStatus> {
+    return await TAURI_INVOKE("get_api_server_status");
+},
+/**
+ * Replaces the server preferences and starts or stops the server.
+ * 
+ * A token is generated the first time the server is enabled. Nothing is
+ * saved if the server can't start, and a running server keeps running.
+ * Changing only the token doesn't restart the server.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, `INVALID_PORT` for ports below 1024,
+ * `BIND_FAILED` if the port is taken, or `DATABASE` if the settings
+ * cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { settings, baseUrl } = await invoke("set_api_server_settings", {
+ * settings: { enabled: true, port: 4891, token: "", saveConversations: true },
+ * });
+ * // OPENAI_BASE_URL=baseUrl OPENAI_API_KEY=settings.token
+ * ```
+ */
+async setApiServerSettings(settings: ApiServerSettings) : Promise<ApiServerStatus> {
+    return await TAURI_INVOKE("set_api_server_settings", { settings });
+},
+/**
+ * Replaces the token; clients using the old one are rejected.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, or `DATABASE` if the token cannot be stored.
+ */
+async regenerateApiServerToken() : Promise<ApiServerStatus> {
+    return await TAURI_INVOKE("regenerate_api_server_token");
+},
+/**
+ * Returns the bridge preferences, address, and client count.
+ */
+async getBridgeStatus() : Promise<BridgeStatus> {
+    return await TAURI_INVOKE("get_bridge_status");
+},
+/**
+ * Replaces the bridge preferences and starts or stops the bridge.
+ * 
+ * A token is generated the first time the bridge is enabled. Nothing is
+ * saved if the bridge can't start. When the port stays the same, the
+ * running bridge is kept and only new connections need the new token.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, `INVALID_PORT` for ports below 1024, `BIND_FAILED`
+ * if the port is taken, or `DATABASE` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { url, settings } = await invoke("set_bridge_settings", {
+ * settings: { enabled: true, port: 4892, token: "" },
+ * });
+ * const ws = new WebSocket(`${url}?token=${settings.token}`);
+ * ws.send(JSON.stringify({ id: 1, command: "runTask", taskId }));
+ * ```
+ */
+async setBridgeSettings(settings: BridgeSettings) : Promise<BridgeStatus> {
+    return await TAURI_INVOKE("set_bridge_settings", { settings });
+},
+/**
+ * Returns the browser bridge permissions.
+ */
+async getBrowserBridgeSettings() : Promise<BrowserBridgeSettings> {
+    return await TAURI_INVOKE("get_browser_bridge_settings");
+},
+/**
+ * Sets the access of an origin; `null` forgets it.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, `INVALID_ORIGIN` if `origin` isn't an origin, or
+ * `DATABASE` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_origin_access", {
+ * origin: "https://en.wikipedia.org",
+ * access: "allowed",
+ * });
+ * ```
+ */
+async setOriginAccess(origin: string, access: OriginAccess | null) : Promise<BrowserBridgeSettings> {
+    return await TAURI_INVOKE("set_origin_access", { origin, access });
+},
+/**
+ * Sets whether bridge requests are stored as conversations.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be stored.
+ */
+async setBrowserBridgeSaveConversations(enabled: boolean) : Promise<BrowserBridgeSettings> {
+    return await TAURI_INVOKE("set_browser_bridge_save_conversations", { enabled });
+},
+/**
+ * Lists registered webhooks, oldest first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listWebhooks() : Promise<Webhook[]> {
+    return await TAURI_INVOKE("list_webhooks");
+},
+/**
+ * Registers a webhook with a freshly generated secret.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_URL` or `INVALID_WEBHOOK` if
+ * the input is invalid, or `DATABASE` if it cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const hook = await invoke("create_webhook", {
+ * input: {
+ * name: "Studio server",
+ * url: "https://studio.example.com/hooks/gibber",
+ * events: ["task.succeeded", "task.failed"],
+ * },
+ * });
+ * console.log(hook.secret); // verify X-Gibber-Signature with this
+ * ```
+ */
+async createWebhook(input: WebhookInput) : Promise<Webhook> {
+    return await TAURI_INVOKE("create_webhook", { input });
+},
+/**
+ * Updates a webhook; its secret is kept.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the webhook doesn't
+ * exist, or `INVALID_URL`/`INVALID_WEBHOOK` if the input is invalid.
+ */
+async updateWebhook(id: string, input: WebhookInput) : Promise<Webhook> {
+    return await TAURI_INVOKE("update_webhook", { id, input });
+},
+/**
+ * Replaces a webhook's secret, e.g. after it leaked.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the webhook doesn't exist.
+ */
+async rotateWebhookSecret(id: string) : Promise<Webhook> {
+    return await TAURI_INVOKE("rotate_webhook_secret", { id });
+},
+/**
+ * Deletes a webhook and its delivery log.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the webhook doesn't exist.
+ */
+async deleteWebhook(id: string) : Promise<null> {
+    return await TAURI_INVOKE("delete_webhook", { id });
+},
+/**
+ * Lists recent deliveries, newest first, optionally for one webhook.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const log = await invoke("list_webhook_deliveries", { webhookId: hook.id, limit: 50 });
+ * ```
+ */
+async listWebhookDeliveries(webhookId: string | null, limit: number | null) : Promise<WebhookDelivery[]> {
+    return await TAURI_INVOKE("list_webhook_deliveries", { webhookId, limit });
+},
+/**
+ * Opens a draft in the default mail client.
+ * 
+ * The draft opens as a `mailto:` URL unless it has attachments or the URL
+ * would be too long, in which case an `.eml` file is written to the cache
+ * directory and opened instead.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_ADDRESS` for a malformed
+ * recipient, `NOT_FOUND` if an attached conversation doesn't exist, `IO` if
+ * the draft cannot be written, or `OPEN_FAILED` if no mail client handles
+ * it.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("open_email_draft", {
+ * draft: {
+ * to: ["band@example.com"],
+ * subject: "Setlist ideas",
+ * body: message.content,
+ * conversationIds: [conversationId],
+ * },
+ * });
+ * ```
+ */
+async openEmailDraft(draft: EmailDraft) : Promise<OpenedDraft> {
+    return await TAURI_INVOKE("open_email_draft", { draft });
+},
+/**
+ * Extracts action items from a conversation for review.
+ * 
+ * Nothing is created; pass the confirmed items to [`create_action_items`].
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, `PARSE_ERROR` if the reply holds no items, or the
+ * `GibberError` code if the request fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const items = await invoke("extract_action_items", { conversationId, model: null });
+ * // show items for editing, then:
+ * await invoke("create_action_items", { items: confirmed });
+ * ```
+ */
+async extractActionItems(conversationId: string, model: string | null) : Promise<ActionItem[]> {
+    return await TAURI_INVOKE("extract_action_items", { conversationId, model });
+},
+/**
+ * Adds confirmed action items to the user's calendar.
+ * 
+ * `method` defaults to [`CalendarMethod::Native`] on macOS and
+ * [`CalendarMethod::Ics`] elsewhere.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_ITEM` if an item lacks a
+ * title, has an unreadable due date, or is an event without a date;
+ * `UNSUPPORTED` for the native method outside macOS; `CALENDAR_FAILED` if
+ * Calendar or Reminders refuse the items; or `IO`/`OPEN_FAILED` if the
+ * `.ics` file cannot be written or opened.
+ */
+async createActionItems(items: ActionItem[], method: CalendarMethod | null) : Promise<CreatedActionItems> {
+    return await TAURI_INVOKE("create_action_items", { items, method });
+},
+/**
+ * Lists the repositories the app may read.
+ */
+async listGitRepositories() : Promise<GitSettings> {
+    return await TAURI_INVOKE("list_git_repositories");
+},
+/**
+ * Grants access to the git work tree containing `path`.
+ * 
+ * The work tree root is stored, so any folder inside the repository works.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` outside the main and
+ * conversation windows, `NOT_A_REPOSITORY` if `path` isn't inside a git
+ * work tree, `GIT_UNAVAILABLE` if git isn't installed, or `DATABASE` if
+ * the grant cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const folder = await open({ directory: true });
+ * await invoke("grant_git_repository", { path: folder });
+ * ```
+ */
+async grantGitRepository(path: string) : Promise<GitSettings> {
+    return await TAURI_INVOKE("grant_git_repository", { path });
+},
+/**
+ * Revokes access to a repository.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be stored.
+ */
+async revokeGitRepository(path: string) : Promise<GitSettings> {
+    return await TAURI_INVOKE("revoke_git_repository", { path });
+},
+/**
+ * Asks the model for a commit message or a review of a repository's changes.
+ * 
+ * Without a selection the unstaged work tree changes are used.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_GRANTED` if the repository hasn't
+ * been granted, `INVALID_RANGE` for a malformed range, `GIT_FAILED` if git
+ * reports an error, `NO_CHANGES` if the diff is empty after filtering, or
+ * the `GibberError` code if the request fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const result = await invoke("git_assist", {
+ * repository: "/Users/ana/code/gibber-ai",
+ * task: "commitMessage",
+ * selection: { staged: true },
+ * model: null,
+ * });
+ * ```
+ */
+async gitAssist(repository: string, task: GitTask, selection: DiffSelection | null, model: string | null) : Promise<GitAssistResult> {
+    return await TAURI_INVOKE("git_assist", { repository, task, selection, model });
+},
+/**
+ * Lists installed plugins.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listPlugins() : Promise<PluginInfo[]> {
+    return await TAURI_INVOKE("list_plugins");
+},
+/**
+ * Installs or updates a plugin from a folder with `plugin.json` and
+ * `plugin.wasm`.
+ * 
+ * New plugins start disabled with no capabilities granted.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_PLUGIN` if the manifest or
+ * component is invalid, `PLUGIN_TRAPPED` if listing its tools fails, or
+ * `IO` if the files cannot be copied.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const folder = await open({ directory: true });
+ * const plugin = await invoke("install_plugin", { path: folder });
+ * ```
+ */
+async installPlugin(path: string) : Promise<PluginInfo> {
+    return await TAURI_INVOKE("install_plugin", { path });
+},
+/**
+ * Enables or disables a plugin.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the plugin isn't installed.
+ */
+async setPluginEnabled(id: string, enabled: boolean) : Promise<PluginInfo> {
+    return await TAURI_INVOKE("set_plugin_enabled", { id, enabled });
+},
+/**
+ * Sets the capabilities granted to a plugin.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the plugin isn't
+ * installed, or `NOT_REQUESTED` if the grant exceeds what its manifest
+ * asks for.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_plugin_capabilities", {
+ * id: plugin.id,
+ * granted: { network: [], storage: true },
+ * });
+ * ```
+ */
+async setPluginCapabilities(id: string, granted: Capabilities) : Promise<PluginInfo> {
+    return await TAURI_INVOKE("set_plugin_capabilities", { id, granted });
+},
+/**
+ * Removes a plugin, its files and its storage.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the plugin isn't
+ * installed, or `IO` if its files cannot be removed.
+ */
+async uninstallPlugin(id: string) : Promise<null> {
+    return await TAURI_INVOKE("uninstall_plugin", { id });
+},
+/**
+ * Runs a tool of an enabled plugin.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the plugin or tool
+ * doesn't exist, `DISABLED` if the plugin is disabled, `PLUGIN_FAILED` if
+ * the tool reports an error or returns invalid JSON, or `PLUGIN_TRAPPED`
+ * if it crashes or runs out of fuel or memory.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const voicings = await invoke("call_plugin_tool", {
+ * id: "art.example.chords",
+ * tool: "lookup-chords",
+ * input: { chord: "Cmaj7" },
+ * });
+ * ```
+ */
+async callPluginTool(id: string, tool: string, input: JsonValue) : Promise<JsonValue> {
+    return await TAURI_INVOKE("call_plugin_tool", { id, tool, input });
+},
+/**
+ * Lists all automations.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const automations = await invoke("list_automations");
+ * ```
+ */
+async listAutomations() : Promise<Automation[]> {
+    return await TAURI_INVOKE("list_automations");
+},
+/**
+ * Saves a new automation and activates its trigger.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT`, `INVALID_SCRIPT`
+ * (with the parse error), `INVALID_SCHEDULE`, `INVALID_SHORTCUT`, or
+ * `CONFLICT` if the hotkey is taken.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("create_automation", {
+ * input: {
+ * name: "Summarize on hotkey",
+ * script: 'let id = current_conversation(); if id != () { print(prompt("Summarize")); }',
+ * trigger: { kind: "hotkey", accelerator: "CommandOrControl+Alt+S" },
+ * },
+ * });
+ * ```
+ */
+async createAutomation(input: AutomationInput) : Promise<Automation> {
+    return await TAURI_INVOKE("create_automation", { input });
+},
+/**
+ * Replaces an automation's name, script, and trigger.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the automation
+ * doesn't exist, or any error of [`create_automation`].
+ */
+async updateAutomation(id: string, input: AutomationInput) : Promise<Automation> {
+    return await TAURI_INVOKE("update_automation", { id, input });
+},
+/**
+ * Deletes an automation and releases its hotkey.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the automation was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deleteAutomation(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_automation", { id });
+},
+/**
+ * Runs an automation immediately, whatever its trigger, and waits for it.
+ * 
+ * A failing script is not an error: the run is returned with status
+ * `failed`, its error, and any output printed before it failed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the automation
+ * doesn't exist or `BUSY` if it is already running.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const run = await invoke("run_automation", { id });
+ * console.log(run.output.join("\n"));
+ * ```
+ */
+async runAutomation(id: string) : Promise<AutomationRun> {
+    return await TAURI_INVOKE("run_automation", { id });
+},
+/**
+ * Returns every string of a locale.
+ * 
+ * Without `language`, returns the locale in use.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { locale, strings } = await invoke("get_locale_strings");
+ * ```
+ */
+async getLocaleStrings(language: string | null) : Promise<LocaleStrings> {
+    return await TAURI_INVOKE("get_locale_strings", { language });
+},
+/**
+ * Sets the UI language and returns its strings.
+ * 
+ * Shorthand for updating the `language` setting.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `UNSUPPORTED_LOCALE` if no catalog
+ * matches the tag, or a settings error code if it cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_locale", { language: "system" });
+ * await listen("i18n://locale-changed", (e) => applyStrings(e.payload.strings));
+ * ```
+ */
+async setLocale(language: string) : Promise<LocaleStrings> {
+    return await TAURI_INVOKE("set_locale", { language });
+},
+/**
+ * Renders markdown, such as model output, to HTML that is safe to insert
+ * into the page.
+ * 
+ * Code blocks are highlighted unless `highlight` is `false`; LaTeX math is
+ * rendered when `math` is `true`.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * element.innerHTML = await invoke("render_markdown", { markdown: message.content, math: true });
+ * ```
+ */
+async renderMarkdown(markdown: string, highlight: boolean | null, math: boolean | null) : Promise<string> {
+    return await TAURI_INVOKE("render_markdown", { markdown, highlight, math });
+},
+/**
+ * Returns the stylesheet for a highlighting theme.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `UNKNOWN_THEME`, listing the
+ * available themes, if there is no theme by that name.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * style.textContent = await invoke("get_highlight_css", { theme: "base16-ocean.dark" });
+ * ```
+ */
+async getHighlightCss(theme: string | null) : Promise<string> {
+    return await TAURI_INVOKE("get_highlight_css", { theme });
+},
+/**
+ * Lists the fenced code blocks of a stored message.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the message doesn't
+ * exist, or `DATABASE` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const blocks = await invoke("extract_code_blocks", { messageId });
+ * ```
+ */
+async extractCodeBlocks(messageId: string) : Promise<CodeBlock[]> {
+    return await TAURI_INVOKE("extract_code_blocks", { messageId });
+},
+/**
+ * Asks where to save a code block of a message and writes it there.
+ * 
+ * # Returns
+ * 
+ * The path written, or `null` if the dialog was cancelled.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the message or block
+ * doesn't exist, `DIALOG` if the dialog fails, or `IO` if the file can't
+ * be written.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const path = await invoke("save_code_block", { messageId, index: 0 });
+ * ```
+ */
+async saveCodeBlock(messageId: string, index: number) : Promise<string | null> {
+    return await TAURI_INVOKE("save_code_block", { messageId, index });
+},
+/**
+ * Lists shares, newest first, optionally only those of one conversation.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const shares = await invoke("list_shares", { conversationId });
+ * ```
+ */
+async listShares(conversationId: string | null) : Promise<Share[]> {
+    return await TAURI_INVOKE("list_shares", { conversationId });
+},
+/**
+ * Returns the sharing preferences.
+ */
+async getShareSettings() : Promise<ShareSettings> {
+    return await TAURI_INVOKE("get_share_settings");
+},
+/**
+ * Replaces the sharing preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` if the backend URL
+ * isn't http(s), or `DATABASE` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_share_settings", {
+ * settings: { backend: { kind: "paste", url: "https://paste.example.org" } },
+ * });
+ * ```
+ */
+async setShareSettings(settings: ShareSettings) : Promise<ShareSettings> {
+    return await TAURI_INVOKE("set_share_settings", { settings });
+},
+/**
+ * Encrypts a conversation, uploads it, and records the share.
+ * 
+ * The upload expires after `expiresInHours` when the backend supports it.
+ * With `deidentify`, identifying values are replaced before encryption.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation doesn't
+ * exist, `NETWORK` or `UPLOAD` if the backend can't be reached or refuses
+ * the file, or `DATABASE` if a query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const share = await invoke("create_share_link", { conversationId, expiresInHours: 72 });
+ * await navigator.clipboard.writeText(share.link);
+ * ```
+ */
+async createShareLink(conversationId: string, expiresInHours: number | null, deidentify: DeidentifyOptions | null) : Promise<Share> {
+    return await TAURI_INVOKE("create_share_link", { conversationId, expiresInHours, deidentify });
+},
+/**
+ * Deletes a shared upload from its backend and marks the share revoked.
+ * 
+ * Uploads the backend no longer has count as revoked.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the share doesn't exist,
+ * `REVOKE` if the backend refuses the deletion or no management token was
+ * given at upload, `NETWORK` if it can't be reached, or `DATABASE` if a
+ * query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const share = await invoke("revoke_share", { id });
+ * ```
+ */
+async revokeShare(id: string) : Promise<Share> {
+    return await TAURI_INVOKE("revoke_share", { id });
+},
+/**
+ * Previews the de-identification of a conversation without changing it.
+ * 
+ * # Returns
+ * 
+ * The conversation as it would be exported or shared, and every replaced
+ * value with its placeholder.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, or `DATABASE` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { conversation, replacements } = await invoke("deidentify_conversation", {
+ * id,
+ * options: { names: ["Ana Lopez", "Acme"] },
+ * });
+ * ```
+ */
+async deidentifyConversation(id: string, options: DeidentifyOptions | null) : Promise<DeidentifiedConversation> {
+    return await TAURI_INVOKE("deidentify_conversation", { id, options });
+},
+/**
+ * Saves a URL to read later; it is summarized in the background.
+ * 
+ * Saving a URL again returns the existing item.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_URL` for anything but
+ * http(s) URLs, or `DATABASE` if the insert fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const item = await invoke("add_to_reading_list", { url: "https://example.com/post" });
+ * await listen("reading-list://updated", (e) => upsertItem(e.payload));
+ * ```
+ */
+async addToReadingList(url: string) : Promise<ReadingItem> {
+    return await TAURI_INVOKE("add_to_reading_list", { url });
+},
+/**
+ * Saves the URL in the clipboard watcher's latest capture.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `EMPTY` if no text has been
+ * captured, or `INVALID_URL` if the text isn't an http(s) URL; the
+ * capture is used up either way.
+ */
+async addClipboardToReadingList() : Promise<ReadingItem> {
+    return await TAURI_INVOKE("add_clipboard_to_reading_list");
+},
+/**
+ * Lists saved items, newest first, optionally only those with `status`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const unread = await invoke("list_reading_list", { status: "summarized" });
+ * ```
+ */
+async listReadingList(status: ReadingStatus | null) : Promise<ReadingItem[]> {
+    return await TAURI_INVOKE("list_reading_list", { status });
+},
+/**
+ * Queues an item again, for example after it failed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the item doesn't
+ * exist, or `DATABASE` if the update fails.
+ */
+async retryReadingItem(id: string) : Promise<ReadingItem> {
+    return await TAURI_INVOKE("retry_reading_item", { id });
+},
+/**
+ * Removes an item.
+ * 
+ * # Returns
+ * 
+ * `true` if the item existed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deleteReadingItem(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_reading_item", { id });
+},
+/**
+ * Searches conversations and reading list items together.
+ * 
+ * # Returns
+ * 
+ * Hits of both kinds, most recently updated first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if a query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const hits = await invoke("search_library", { query: "granular synthesis" });
+ * ```
+ */
+async searchLibrary(query: string) : Promise<SearchHit[]> {
+    return await TAURI_INVOKE("search_library", { query });
+},
+/**
+ * Returns the feed preferences.
+ */
+async getFeedSettings() : Promise<FeedSettings> {
+    return await TAURI_INVOKE("get_feed_settings");
+},
+/**
+ * Replaces the feed preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` if a value is out of
+ * range, or `DATABASE` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_feed_settings", {
+ * settings: { pollIntervalMinutes: 30, dailyDigest: true, digestHour: 7 },
+ * });
+ * ```
+ */
+async setFeedSettings(settings: FeedSettings) : Promise<FeedSettings> {
+    return await TAURI_INVOKE("set_feed_settings", { settings });
+},
+/**
+ * Lists subscribed feeds.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listFeeds() : Promise<Feed[]> {
+    return await TAURI_INVOKE("list_feeds");
+},
+/**
+ * Subscribes to a feed and stores its current entries.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_URL` for anything but http(s)
+ * URLs, `ALREADY_SUBSCRIBED` for a known feed, `NETWORK` if it can't be
+ * downloaded, or `INVALID_FEED` if it isn't RSS, Atom, or JSON Feed.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const feed = await invoke("subscribe_feed", {
+ * url: "https://example.com/feed.xml",
+ * prompt: "List only releases and breaking changes.",
+ * });
+ * ```
+ */
+async subscribeFeed(url: string, prompt: string | null) : Promise<Feed> {
+    return await TAURI_INVOKE("subscribe_feed", { url, prompt });
+},
+/**
+ * Changes a feed's prompt or pauses it.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the feed doesn't exist, or
+ * `DATABASE` if the update fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("update_feed", { id, update: { prompt: "Only security advisories." } });
+ * ```
+ */
+async updateFeed(id: string, update: FeedUpdate) : Promise<Feed> {
+    return await TAURI_INVOKE("update_feed", { id, update });
+},
+/**
+ * Unsubscribes from a feed and drops its entries.
+ * 
+ * # Returns
+ * 
+ * `true` if the feed existed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async unsubscribeFeed(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("unsubscribe_feed", { id });
+},
+/**
+ * Lists stored entries, newest first, of one feed or of all feeds.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const items = await invoke("list_feed_items", { feedId, limit: 50 });
+ * ```
+ */
+async listFeedItems(feedId: string | null, limit: number | null) : Promise<FeedItem[]> {
+    return await TAURI_INVOKE("list_feed_items", { feedId, limit });
+},
+/**
+ * Polls every enabled feed now.
+ * 
+ * # Returns
+ * 
+ * The number of new entries across all feeds.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the feeds can't be listed; failures of single
+ * feeds are recorded on the feed instead.
+ */
+async refreshFeeds() : Promise<number> {
+    return await TAURI_INVOKE("refresh_feeds");
+},
+/**
+ * Generates a digest of all entries not digested yet.
+ * 
+ * # Returns
+ * 
+ * The ID of the digest conversation, or `null` if there was nothing new.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with the chat error code if a summary fails, or
+ * `DATABASE` if the digest cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const conversationId = await invoke("generate_feed_digest");
+ * ```
+ */
+async generateFeedDigest() : Promise<string | null> {
+    return await TAURI_INVOKE("generate_feed_digest");
+},
+/**
+ * Returns the Nostr digest preferences.
+ */
+async getNostrDigestSettings() : Promise<NostrDigestSettings> {
+    return await TAURI_INVOKE("get_nostr_digest_settings");
+},
+/**
+ * Replaces the Nostr digest preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_KEY`, `INVALID_RELAY`,
+ * or `INVALID_SETTINGS` naming the bad value, or `DATABASE` if the
+ * settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_nostr_digest_settings", {
+ * settings: { pubkey: "npub1…", relays: ["wss://nos.lol"], dailyDigest: true, digestHour: 21 },
+ * });
+ * ```
+ */
+async setNostrDigestSettings(settings: NostrDigestSettings) : Promise<NostrDigestSettings> {
+    return await TAURI_INVOKE("set_nostr_digest_settings", { settings });
+},
+/**
+ * Generates a digest of the follows' notes from the last day now.
+ * 
+ * # Returns
+ * 
+ * The ID of the recurring digest conversation, or `null` if the follows
+ * posted nothing.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_KEY` if no valid public
+ * key is set, `NO_CONTACTS` if its contact list can't be found, `NETWORK`
+ * or `PROXY` if the relays can't be reached, or the chat error code if the
+ * summary fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const conversationId = await invoke("generate_nostr_digest");
+ * ```
+ */
+async generateNostrDigest() : Promise<string | null> {
+    return await TAURI_INVOKE("generate_nostr_digest");
+},
+/**
+ * Generates images for a conversation.
+ * 
+ * Images are attached to the conversation as they are stored, and an
+ * assistant message with the parameters records the generation. Progress
+ * arrives via `images://progress`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` for bad parameters,
+ * `NOT_FOUND` if the conversation doesn't exist, `NO_API_KEY` if the
+ * provider's key isn't stored, `UNAUTHORIZED`, `RATE_LIMITED`, `REJECTED`,
+ * or `PROVIDER_ERROR` if the provider refuses, `NETWORK` if it can't be
+ * reached, `POLICY_BLOCKED` if the conversation is sensitive and the
+ * provider isn't allowed to receive it, or `UNAVAILABLE`, `BUSY`, or
+ * `CANCELLED` for local renders.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await listen("images://progress", (e) => updateProgress(e.payload));
+ * const generation = await invoke("generate_image", {
+ * conversationId,
+ * params: { prompt: "A modular synth at dusk", provider: "openai", size: "1536x1024" },
+ * });
+ * ```
+ */
+async generateImage(conversationId: string, params: ImageParams) : Promise<ImageGeneration> {
+    return await TAURI_INVOKE("generate_image", { conversationId, params });
+},
+/**
+ * Returns the generation recorded by a message, if it has one.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const generation = await invoke("get_image_generation", { messageId });
+ * if (generation) await invoke("generate_image", { conversationId, params: generation.params });
+ * ```
+ */
+async getImageGeneration(messageId: string) : Promise<ImageGeneration | null> {
+    return await TAURI_INVOKE("get_image_generation", { messageId });
+},
+/**
+ * Returns the local server preferences.
+ */
+async getStableDiffusionSettings() : Promise<StableDiffusionSettings> {
+    return await TAURI_INVOKE("get_stable_diffusion_settings");
+},
+/**
+ * Replaces the local server preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` if the URL
+ * isn't http(s), or `DATABASE` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_stable_diffusion_settings", {
+ * settings: { backend: "comfyui", url: "http://127.0.0.1:8188" },
+ * });
+ * ```
+ */
+async setStableDiffusionSettings(settings: StableDiffusionSettings) : Promise<StableDiffusionSettings> {
+    return await TAURI_INVOKE("set_stable_diffusion_settings", { settings });
+},
+/**
+ * Lists the checkpoints and samplers of the local server.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `UNAVAILABLE` if the server
+ * isn't running, or `PROVIDER_ERROR` if it refuses.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { models, samplers } = await invoke("list_stable_diffusion_models");
+ * ```
+ */
+async listStableDiffusionModels() : Promise<LocalModels> {
+    return await TAURI_INVOKE("list_stable_diffusion_models");
+},
+/**
+ * Cancels the running local render.
+ * 
+ * # Returns
+ * 
+ * `true` if a render was running.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `UNAVAILABLE` if the server
+ * can't be reached to interrupt the render.
+ */
+async cancelLocalImage() : Promise<boolean> {
+    return await TAURI_INVOKE("cancel_local_image");
+},
+/**
+ * Edits an image attachment in place and returns it updated.
+ * 
+ * The edited image replaces the original, which isn't kept.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment
+ * doesn't exist, `NOT_IMAGE` if it isn't an image, `INVALID_OPERATION` for
+ * an operation that doesn't fit the image, or `DECODE_FAILED` if the image
+ * can't be read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const attachment = await invoke("edit_image_attachment", {
+ * id,
+ * operations: [
+ * { op: "crop", x: 0, y: 80, width: 1280, height: 640 },
+ * { op: "redact", regions: [{ x: 40, y: 12, width: 300, height: 28 }] },
+ * { op: "resize", width: 1024 },
+ * ],
+ * });
+ * ```
+ */
+async editImageAttachment(id: string, operations: ImageOperation[]) : Promise<Attachment> {
+    return await TAURI_INVOKE("edit_image_attachment", { id, operations });
+},
+/**
+ * Returns an image attachment as sent to vision models, with metadata
+ * removed unless the user turned that off.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment
+ * doesn't exist, `NOT_IMAGE` if it isn't an image, or `INVALID_IMAGE` if
+ * its metadata can't be removed.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { dataUrl } = await invoke("prepare_image_upload", { id: attachment.id });
+ * content.push({ type: "image_url", image_url: { url: dataUrl } });
+ * ```
+ */
+async prepareImageUpload(id: string) : Promise<ImageUpload> {
+    return await TAURI_INVOKE("prepare_image_upload", { id });
+},
+/**
+ * Returns the routing policy.
+ */
+async getRoutingPolicy() : Promise<RoutingPolicy> {
+    return await TAURI_INVOKE("get_routing_policy");
+},
+/**
+ * Replaces the routing policy.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` for an empty
+ * entry or a model listed as a provider, or `DATABASE` if the policy
+ * cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_routing_policy", {
+ * policy: { blockedProviders: ["openai"], blockedModels: ["google/gemini-2.5-pro"] },
+ * });
+ * ```
+ */
+async setRoutingPolicy(policy: RoutingPolicy) : Promise<RoutingPolicy> {
+    return await TAURI_INVOKE("set_routing_policy", { policy });
+},
+/**
+ * Tags or untags a conversation as sensitive.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the
+ * conversation doesn't exist.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_conversation_sensitive", { conversationId, sensitive: true });
+ * ```
+ */
+async setConversationSensitive(conversationId: string, sensitive: boolean) : Promise<null> {
+    return await TAURI_INVOKE("set_conversation_sensitive", { conversationId, sensitive });
+},
+/**
+ * Lists the IDs of the conversations tagged as sensitive.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listSensitiveConversations() : Promise<string[]> {
+    return await TAURI_INVOKE("list_sensitive_conversations");
+},
+/**
+ * Checks that a chat request from the frontend may be sent.
+ * 
+ * Refusals are also announced as `routing-policy://blocked`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `POLICY_BLOCKED` if the
+ * conversation is sensitive and the model isn't allowed to receive it.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("check_conversation_route", { conversationId, model });
+ * ```
+ */
+async checkConversationRoute(conversationId: string, model: string) : Promise<null> {
+    return await TAURI_INVOKE("check_conversation_route", { conversationId, model });
+},
+/**
+ * Returns the fields the frontend should add to requests for `provider`,
+ * empty unless zero data retention is on.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const privacy = await invoke("get_privacy_fields", { provider: "openrouter" });
+ * const body = { ...request, ...privacy };
+ * ```
+ */
+async getPrivacyFields(provider: string) : Promise<Partial<{ [key in string]: JsonValue }>> {
+    return await TAURI_INVOKE("get_privacy_fields", { provider });
+},
+/**
+ * Returns a page of the audit log, newest first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const page = await invoke("get_audit_log", { query: { actor: "plugin:", limit: 100 } });
+ * const older = await invoke("get_audit_log", { query: { before: page.at(-1).id } });
+ * ```
+ */
+async getAuditLog(query: AuditQuery | null) : Promise<AuditEntry[]> {
+    return await TAURI_INVOKE("get_audit_log", { query });
+},
+/**
+ * Writes the whole audit log to a JSON Lines file, oldest first.
+ * 
+ * # Returns
+ * 
+ * The number of entries written.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `IO` if the file cannot be written.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("export_audit_log", { path: "/Users/me/gibber-audit.jsonl" });
+ * ```
+ */
+async exportAuditLog(path: string) : Promise<number> {
+    return await TAURI_INVOKE("export_audit_log", { path });
+},
+/**
+ * Returns the rate limits.
+ */
+async getRateLimits() : Promise<RateLimitSettings> {
+    return await TAURI_INVOKE("get_rate_limits");
+},
+/**
+ * Replaces the rate limits.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` for a limit of
+ * zero, or `DATABASE` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_rate_limits", {
+ * settings: { limits: { openrouter: { requestsPerMinute: 20, burst: 5 } } },
+ * });
+ * ```
+ */
+async setRateLimits(settings: RateLimitSettings) : Promise<RateLimitSettings> {
+    return await TAURI_INVOKE("set_rate_limits", { settings });
+},
+/**
+ * Returns what is left of each provider's budget.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const budgets = await invoke("get_rate_limit_state");
+ * const openrouter = budgets.find((b) => b.provider === "openrouter");
+ * ```
+ */
+async getRateLimitState() : Promise<RateLimitState[]> {
+    return await TAURI_INVOKE("get_rate_limit_state");
+},
+/**
+ * Waits for a token of `provider`'s budget before the frontend sends a
+ * request there.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `RATE_LIMITED` if the budget won't
+ * allow a request within two minutes.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("acquire_rate_limit", { provider: "openrouter" });
+ * const response = await fetch(url, init);
+ * ```
+ */
+async acquireRateLimit(provider: string) : Promise<null> {
+    return await TAURI_INVOKE("acquire_rate_limit", { provider });
+},
+/**
+ * Returns the spend caps.
+ */
+async getSpendCaps() : Promise<SpendCaps> {
+    return await TAURI_INVOKE("get_spend_caps");
+},
+/**
+ * Replaces the spend caps.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` for a negative cap,
+ * or `DATABASE` if the caps cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_spend_caps", { caps: { dailyUsd: 2, monthlyUsd: 25 } });
+ * ```
+ */
+async setSpendCaps(caps: SpendCaps) : Promise<SpendCaps> {
+    return await TAURI_INVOKE("set_spend_caps", { caps });
+},
+/**
+ * Returns today's and this month's spending with the caps.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the ledger cannot be read.
+ */
+async getSpendSummary() : Promise<SpendSummary> {
+    return await TAURI_INVOKE("get_spend_summary");
+},
+/**
+ * Lets requests through despite the caps for `minutes` (default: 60,
+ * at most a day), or ends an override when `minutes` is 0.
+ * 
+ * # Returns
+ * 
+ * When the override ends, in Unix milliseconds.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await listen("budget://exceeded", async ({ payload }) => {
+ * if (await confirm(`Go over the ${payload.period} cap for an hour?`)) {
+ * await invoke("override_spend_cap", { minutes: 60 });
+ * }
+ * });
+ * ```
+ */
+async overrideSpendCap(minutes: number | null) : Promise<number | null> {
+    return await TAURI_INVOKE("override_spend_cap", { minutes });
+},
+/**
+ * Adds the cost of a completion the frontend made to the ledger.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` for a negative cost, or
+ * `DATABASE` if the entry cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("record_spend", {
+ * model: response.model,
+ * conversationId,
+ * usage: { prompt_tokens: 812, completion_tokens: 240 },
+ * costUsd: response.usage.cost,
+ * });
+ * ```
+ */
+async recordSpend(model: string, conversationId: string | null, usage: TokenUsage | null, costUsd: number) : Promise<null> {
+    return await TAURI_INVOKE("record_spend", { model, conversationId, usage, costUsd });
+},
+/**
+ * Summarizes the samples of the last `days` days (default: 7), per model
+ * and provider.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const metrics = await invoke("get_model_metrics", { days: 30 });
+ * const flaky = metrics.filter((m) => m.requests >= 10 && m.errorRate > 0.1);
+ * ```
+ */
+async getModelMetrics(days: number | null, model: string | null) : Promise<ModelMetrics[]> {
+    return await TAURI_INVOKE("get_model_metrics", { days, model });
+},
+/**
+ * Adds a sample of a completion the frontend made.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if the model is
+ * missing, or `DATABASE` if the sample cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("record_model_metric", {
+ * sample: { model, provider: chunk.provider, ttftMs, durationMs, completionTokens },
+ * });
+ * ```
+ */
+async recordModelMetric(sample: Sample) : Promise<null> {
+    return await TAURI_INVOKE("record_model_metric", { sample });
+},
+/**
+ * Orders failover candidates by their health over the last week.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const [first, ...fallbacks] = await invoke("rank_models", {
+ * models: ["anthropic/claude-sonnet-4", "openai/gpt-4o", "google/gemini-2.5-pro"],
+ * });
+ * ```
+ */
+async rankModels(models: string[]) : Promise<string[]> {
+    return await TAURI_INVOKE("rank_models", { models });
+},
+/**
+ * Returns the size of the response cache.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async getResponseCacheStats() : Promise<CacheStats> {
+    return await TAURI_INVOKE("get_response_cache_stats");
+},
+/**
+ * Empties the response cache.
+ * 
+ * # Returns
+ * 
+ * The number of entries removed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async clearResponseCache() : Promise<number> {
+    return await TAURI_INVOKE("clear_response_cache");
+},
+/**
+ * Lists all prompt tests by name.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listPromptTests() : Promise<PromptTest[]> {
+    return await TAURI_INVOKE("list_prompt_tests");
+},
+/**
+ * Creates a prompt test, or replaces an existing one when `id` is given.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if the test has no
+ * name, prompt, or criteria or has an invalid pattern, `NOT_FOUND` if `id`
+ * doesn't exist, or `DATABASE` on failure.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("save_prompt_test", {
+ * id: null,
+ * input: {
+ * name: "Tempo as JSON",
+ * prompt: "Reply with the tempo of a typical house track as {\"bpm\": n}",
+ * criteria: [{ kind: "json" }, { kind: "matches", pattern: "1[12][0-9]" }],
+ * },
+ * });
+ * ```
+ */
+async savePromptTest(id: string | null, input: PromptTestInput) : Promise<PromptTest> {
+    return await TAURI_INVOKE("save_prompt_test", { id, input });
+},
+/**
+ * Deletes a prompt test and its results.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the test was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deletePromptTest(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_prompt_test", { id });
+},
+/**
+ * Runs prompt tests against one or more models.
+ * 
+ * `test_ids` defaults to every test. Progress is reported through
+ * [`events::PROMPT_TESTS_PROGRESS`]; request failures are reported in the
+ * results rather than as an error.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if no model or
+ * more than eight are given or there are no tests, `NOT_FOUND` for an
+ * unknown test, or `DATABASE` if the run cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const run = await invoke("run_prompt_tests", {
+ * testIds: null,
+ * models: ["anthropic/claude-sonnet-4", "openai/gpt-4o-mini"],
+ * });
+ * const regressions = run.results.filter((r) => r.regression);
+ * ```
+ */
+async runPromptTests(testIds: string[] | null, models: string[]) : Promise<PromptTestRun> {
+    return await TAURI_INVOKE("run_prompt_tests", { testIds, models });
+},
+/**
+ * Lists past runs, newest first, without their results.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listPromptTestRuns() : Promise<PromptTestRun[]> {
+    return await TAURI_INVOKE("list_prompt_test_runs");
+},
+/**
+ * Retrieves a run with its results, compared with the run before it.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the run doesn't
+ * exist.
+ */
+async getPromptTestRun(runId: string) : Promise<PromptTestRun> {
+    return await TAURI_INVOKE("get_prompt_test_run", { runId });
+},
+/**
+ * Lists experiments, newest first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listExperiments() : Promise<Experiment[]> {
+    return await TAURI_INVOKE("list_experiments");
+},
+/**
+ * Creates an experiment, or replaces the variants of an existing one when
+ * `id` is given. New experiments start inactive.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if a name, label,
+ * or prompt is missing or the variants are identical, `NOT_FOUND` if `id`
+ * doesn't exist, or `DATABASE` on failure.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("save_experiment", {
+ * id: null,
+ * input: {
+ * name: "Terse producer",
+ * variantA: { label: "Current", systemPrompt: currentPrompt },
+ * variantB: { label: "Terse", systemPrompt: "Answer in three sentences or fewer." },
+ * },
+ * });
+ * ```
+ */
+async saveExperiment(id: string | null, input: ExperimentInput) : Promise<Experiment> {
+    return await TAURI_INVOKE("save_experiment", { id, input });
+},
+/**
+ * Starts or stops enrolling new conversations in an experiment. Starting
+ * one stops any other.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the experiment
+ * doesn't exist.
+ */
+async setExperimentActive(id: string, active: boolean) : Promise<Experiment> {
+    return await TAURI_INVOKE("set_experiment_active", { id, active });
+},
+/**
+ * Deletes an experiment with its enrollments and ratings. Conversations
+ * keep the prompt they were given.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the experiment was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deleteExperiment(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_experiment", { id });
+},
+/**
+ * Retrieves the variant a conversation was given, if it is enrolled.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async getExperimentAssignment(conversationId: string) : Promise<Assignment | null> {
+    return await TAURI_INVOKE("get_experiment_assignment", { conversationId });
+},
+/**
+ * Rates an answer in an enrolled conversation. `thumbs_up: null` removes
+ * the rating.
+ * 
+ * Ratings given with `rate_message` are counted too; this command only
+ * records the experiment side.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the message
+ * doesn't exist, `NOT_ENROLLED` if its conversation isn't in an
+ * experiment, or `INVALID_INPUT` if it isn't an assistant message.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("rate_experiment_message", { messageId: id, thumbsUp: true });
+ * ```
+ */
+async rateExperimentMessage(messageId: string, thumbsUp: boolean | null) : Promise<null> {
+    return await TAURI_INVOKE("rate_experiment_message", { messageId, thumbsUp });
+},
+/**
+ * Compares the variants of an experiment.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the experiment
+ * doesn't exist.
+ */
+async getExperimentStats(id: string) : Promise<ExperimentStats> {
+    return await TAURI_INVOKE("get_experiment_stats", { id });
+},
+/**
+ * Rates an answer. `rating: null` removes the rating.
+ * 
+ * # Returns
+ * 
+ * The stored feedback, or `null` once removed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the message doesn't
+ * exist, `INVALID_INPUT` if it isn't an assistant message, or `DATABASE`
+ * on failure.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("rate_message", {
+ * messageId: id,
+ * rating: "down",
+ * comment: "Wrong time signature",
+ * });
+ * ```
+ */
+async rateMessage(messageId: string, rating: Rating | null, comment: string | null) : Promise<Feedback | null> {
+    return await TAURI_INVOKE("rate_message", { messageId, rating, comment });
+},
+/**
+ * Lists feedback, newest first, optionally for one conversation.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listFeedback(conversationId: string | null) : Promise<Feedback[]> {
+    return await TAURI_INVOKE("list_feedback", { conversationId });
+},
+/**
+ * Writes feedback to a JSON Lines dataset, oldest first.
+ * 
+ * # Returns
+ * 
+ * The number of records written.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `IO` if the file cannot be written.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("export_feedback", { path: "/Users/me/feedback.jsonl", format: "kto" });
+ * ```
+ */
+async exportFeedback(path: string, format: FeedbackFormat | null) : Promise<number> {
+    return await TAURI_INVOKE("export_feedback", { path, format });
+},
+/**
+ * Retrieves a conversation's tags.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async getConversationTags(conversationId: string) : Promise<string[]> {
+    return await TAURI_INVOKE("get_conversation_tags", { conversationId });
+},
+/**
+ * Replaces a conversation's tags. Tags are case-insensitive and stored
+ * lowercase.
+ * 
+ * # Returns
+ * 
+ * The tags as stored.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_conversation_tags", { conversationId: id, tags: ["mixing", "good"] });
+ * ```
+ */
+async setConversationTags(conversationId: string, tags: string[]) : Promise<string[]> {
+    return await TAURI_INVOKE("set_conversation_tags", { conversationId, tags });
+},
+/**
+ * Exports conversations as a fine-tuning dataset.
+ * 
+ * The export runs as an `export.dataset` job (see [`jobs`]), so its
+ * progress arrives as `job://progress` events and it can be cancelled
+ * with `cancel_job`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if the validation
+ * fraction is out of range or has no file, `IO` if a file cannot be
+ * written, `CANCELLED` if the job was cancelled, or `DATABASE` if a query
+ * fails.
+ * 
+ * [`jobs`]: crate::commands::jobs
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const summary = await invoke("export_dataset", {
+ * request: {
+ * format: "sharegpt",
+ * filter: { tags: ["mixing"], rating: "up" },
+ * redact: { names: ["Ana Lopez"] },
+ * validationFraction: 0.1,
+ * trainPath: "/Users/me/mixing.train.jsonl",
+ * validationPath: "/Users/me/mixing.val.jsonl",
+ * },
+ * });
+ * ```
+ */
+async exportDataset(request: DatasetRequest) : Promise<DatasetSummary> {
+    return await TAURI_INVOKE("export_dataset", { request });
+},
+/**
+ * Diffs two stored messages, word by word unless `granularity` says
+ * otherwise.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if either message
+ * doesn't exist, or `DATABASE` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { spans, insertedWords, deletedWords } = await invoke("diff_messages", {
+ * oldId: previous.id,
+ * newId: regenerated.id,
+ * granularity: "word",
+ * });
+ * ```
+ */
+async diffMessages(oldId: string, newId: string, granularity: Granularity | null) : Promise<MessageDiff> {
+    return await TAURI_INVOKE("diff_messages", { oldId, newId, granularity });
+},
+/**
+ * Streams a chat completion to the webview.
+ * 
+ * The text arrives in batches as [`events::CHAT_DELTA`] for `stream_id`; the
+ * returned completion holds all of it. Acknowledge each batch with
+ * `ack_chat_stream` so batches stay small while the webview keeps up.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with the codes of the backend chat client
+ * (`NO_API_KEY`, `RATE_LIMITED`, `BUDGET_EXCEEDED`, ...). Batches emitted
+ * before a failure stand.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const streamId = crypto.randomUUID();
+ * const unlisten = await listen("chat://delta", ({ payload }) => {
+ * if (payload.streamId !== streamId) return;
+ * appendText(payload.text);
+ * invoke("ack_chat_stream", { streamId, seq: payload.seq });
+ * });
+ * const reply = await invoke("stream_chat", {
+ * streamId,
+ * request: { messages: [{ role: "user", content: "Write a chorus" }] },
+ * });
+ * unlisten();
+ * ```
+ */
+async streamChat(streamId: string, request: StreamChatRequest) : Promise<StreamedCompletion> {
+    return await TAURI_INVOKE("stream_chat", { streamId, request });
+},
+/**
+ * Acknowledges that the webview has rendered batches of a stream up to
+ * `seq`.
+ */
+async ackChatStream(streamId: string, seq: number) : Promise<void> {
+    await TAURI_INVOKE("ack_chat_stream", { streamId, seq });
+},
+/**
+ * Lists jobs, newest first. Running jobs carry their live progress.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const running = await invoke("list_jobs", { status: "running" });
+ * await listen("job://progress", ({ payload }) => updateJob(payload));
+ * ```
+ */
+async listJobs(status: JobStatus | null, limit: number | null) : Promise<Job[]> {
+    return await TAURI_INVOKE("list_jobs", { status, limit });
+},
+/**
+ * Asks a running job to stop.
+ * 
+ * The job stops at its next checkpoint and is then reported as
+ * cancelled. Returns `false` if no job with this ID is running.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("cancel_job", { id: job.id });
+ * ```
+ */
+async cancelJob(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("cancel_job", { id });
+},
+/**
+ * Returns which subsystems are ready and which are still initializing.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const unlisten = await listen("app://ready", ({ payload }) => markReady(payload));
+ * const { ready } = await invoke("get_startup_status");
+ * ready.forEach(markReady);
+ * ```
+ */
+async getStartupStatus() : Promise<StartupStatus> {
+    return await TAURI_INVOKE("get_startup_status");
+},
+/**
+ * Retrieves a page of a conversation's messages, newest page first.
+ * 
+ * Without a `cursor` this returns the latest `limit` messages (100 by
+ * default, at most 500). Pass the page's `nextCursor` to get the messages
+ * before it; it is `null` once the first message has been returned.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, `INVALID_CURSOR` if the cursor isn't one of its
+ * messages, or `DATABASE` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * let page = await invoke("get_messages", { conversationId: id, limit: 50 });
+ * while (page.nextCursor && userScrolledToTop()) {
+ * page = await invoke("get_messages", { conversationId: id, cursor: page.nextCursor });
+ * prependMessages(page.messages);
+ * }
+ * ```
+ */
+async getMessages(conversationId: string, cursor: string | null, limit: number | null) : Promise<MessagePage> {
+    return await TAURI_INVOKE("get_messages", { conversationId, cursor, limit });
+},
+/**
+ * Lists every event with the Rust type of its payload.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const schema = await invoke("get_event_schema");
+ * const missing = schema.filter(({ name }) => !(name in listeners));
+ * ```
+ */
+async getEventSchema() : Promise<EventSchema[]> {
+    return await TAURI_INVOKE("get_event_schema");
+},
+/**
+ * Records what the calling window shows.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` for windows other than
+ * the main and conversation windows.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("update_session_window", { conversationId: id, scrollTop: list.scrollTop });
+ * ```
+ */
+async updateSessionWindow(conversationId: string | null, scrollTop: number) : Promise<null> {
+    return await TAURI_INVOKE("update_session_window", { conversationId, scrollTop });
+},
+/**
+ * Returns the session cut short by a crash, if the previous run ended
+ * that way and it hasn't been restored or discarded.
+ */
+async getRestorableSession() : Promise<RestorableSession | null> {
+    return await TAURI_INVOKE("get_restorable_session");
+},
+/**
+ * Reopens the windows of the session cut short by a crash.
+ * 
+ * Conversation windows open at their saved scroll position. The main
+ * window applies its own entry, the first of the returned windows.
+ * 
+ * # Returns
+ * 
+ * The restored session, or `None` if there was nothing to restore.
+ * Conversations deleted since are skipped.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `WINDOW` if a window cannot be
+ * created.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const restored = await invoke("restore_session");
+ * const main = restored?.windows.find((w) => w.label === "main");
+ * if (main?.conversationId) openConversation(main.conversationId, main.scrollTop);
+ * ```
+ */
+async restoreSession() : Promise<RestorableSession | null> {
+    return await TAURI_INVOKE("restore_session");
+},
+/**
+ * Discards the session cut short by a crash without restoring it.
+ */
+async discardSession() : Promise<void> {
+    await TAURI_INVOKE("discard_session");
+},
+/**
+ * Resumes a stream that a quit or crash cut off.
+ * 
+ * The model is sent the original request, the partial reply, and an
+ * instruction to continue it. Only the new text arrives as
+ * [`events::CHAT_DELTA`] for `stream_id`; the returned completion starts
+ * with the partial reply.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the stream has no
+ * draft, `STREAM_RUNNING` if it is still running, or the codes of
+ * `stream_chat`.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const [draft] = await invoke("list_interrupted_streams");
+ * const reply = await invoke("resume_chat_stream", { streamId: draft.streamId });
+ * ```
+ */
+async resumeChatStream(streamId: string) : Promise<StreamedCompletion> {
+    return await TAURI_INVOKE("resume_chat_stream", { streamId });
+},
+/**
+ * Lists streams that were cut off, newest first.
+ * 
+ * Streams still running are left out.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `DATABASE` if the drafts cannot be read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const drafts = await invoke("list_interrupted_streams");
+ * drafts.forEach((d) => showResumable(d.conversationId, d.partial));
+ * ```
+ */
+async listInterruptedStreams() : Promise<StreamDraft[]> {
+    return await TAURI_INVOKE("list_interrupted_streams");
+},
+/**
+ * Discards the draft of a stream that was cut off.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if there was a draft.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `DATABASE` if the draft cannot be
+ * deleted.
+ */
+async discardInterruptedStream(streamId: string) : Promise<boolean> {
+    return await TAURI_INVOKE("discard_interrupted_stream", { streamId });
+},
+/**
+ * Lists all presets by name.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listPresets() : Promise<Preset[]> {
+    return await TAURI_INVOKE("list_presets");
+},
+/**
+ * Creates a preset, or replaces an existing one when `id` is given.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if the name is empty
+ * or a tool isn't named `<plugin id>/<tool name>`, `NOT_FOUND` if `id`
+ * doesn't exist, or `DATABASE` on failure.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("save_preset", {
+ * id: null,
+ * input: {
+ * name: "Code review",
+ * persona: "You are a meticulous reviewer of Gibber patches.",
+ * model: "anthropic/claude-sonnet-4",
+ * tools: ["dev.gibber.git/diff"],
+ * },
+ * });
+ * ```
+ */
+async savePreset(id: string | null, input: PresetInput) : Promise<Preset> {
+    return await TAURI_INVOKE("save_preset", { id, input });
+},
+/**
+ * Deletes a preset.
+ * 
+ * Conversations started from it keep their persona and model.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the preset was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deletePreset(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_preset", { id });
+},
+/**
+ * Starts a conversation configured by a preset.
+ * 
+ * The conversation is titled `title`, or after the preset when `None`.
+ * It isn't enrolled in a prompt experiment, whose variant would override
+ * the preset's persona or model.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the preset doesn't
+ * exist, or `DATABASE` on failure.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { conversation, preset } = await invoke("start_preset_conversation", {
+ * presetId: id,
+ * title: null,
+ * });
+ * ```
+ */
+async startPresetConversation(presetId: string, title: string | null) : Promise<PresetConversation> {
+    return await TAURI_INVOKE("start_preset_conversation", { presetId, title });
+},
+/**
+ * Returns the preset a conversation was started from, if it still exists.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async getConversationPreset(conversationId: string) : Promise<Preset | null> {
+    return await TAURI_INVOKE("get_conversation_preset", { conversationId });
+},
+/**
+ * Returns the command palette entries matching `query`.
+ * 
+ * Without a query, the actions come first, then presets by name, then
+ * conversations by recent activity.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `DATABASE` if the records cannot be
+ * read.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const items = await invoke("get_command_palette_actions", { query: "bss", limit: 20 });
+ * items.forEach((item) => render(item.title, item.matches));
+ * ```
+ */
+async getCommandPaletteActions(query: string | null, limit: number | null) : Promise<PaletteItem[]> {
+    return await TAURI_INVOKE("get_command_palette_actions", { query, limit });
+},
+/**
+ * Counts a use of an item chosen in a picker.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if `id` is empty, or
+ * `DATABASE` if the use cannot be stored.
+ */
+async recordUsage(kind: UsageKind, id: string) : Promise<null> {
+    return await TAURI_INVOKE("record_usage", { kind, id });
+},
+/**
+ * Lists items of a kind, favorites first, then by frecency.
+ * 
+ * Items never used or favorited aren't listed; pickers show them after
+ * the ranked ones.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const models = await invoke("get_ranked_usage", { kind: "model", limit: 5 });
+ * ```
+ */
+async getRankedUsage(kind: UsageKind, limit: number | null) : Promise<RankedItem[]> {
+    return await TAURI_INVOKE("get_ranked_usage", { kind, limit });
+},
+/**
+ * Marks an item a favorite, or unmarks it.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if `id` is empty, or
+ * `DATABASE` if the flag cannot be stored.
+ */
+async setFavorite(kind: UsageKind, id: string, favorite: boolean) : Promise<null> {
+    return await TAURI_INVOKE("set_favorite", { kind, id, favorite });
+},
+/**
+ * Returns statistics for a conversation.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, or `DATABASE` if a query fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const stats = await invoke("get_conversation_stats", { id });
+ * console.log(`${stats.messages} messages, $${stats.costUsd.toFixed(2)}`);
+ * ```
+ */
+async getConversationStats(id: string) : Promise<ConversationStats> {
+    return await TAURI_INVOKE("get_conversation_stats", { id });
+},
+/**
+ * Formats pasted text for a prompt.
+ * 
+ * `budget` is the most tokens the paste may use; 8000 when `None`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `TASK_FAILED` if the background task
+ * fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const pasted = event.clipboardData.getData("text/plain");
+ * const { text, truncated } = await invoke("prepare_paste", { text: pasted, budget: 4000 });
+ * insertAtCursor(text);
+ * ```
+ */
+async preparePaste(text: string, budget: number | null) : Promise<PreparedPaste> {
+    return await TAURI_INVOKE("prepare_paste", { text, budget });
+},
+/**
+ * Returns the tool definitions to offer the model for spreadsheet and
+ * database attachments and for structured data, in the shape of plugin
+ * tools.
+ * 
+ * `describe_data` maps to `describe_data_attachment`, `query_data` to
+ * `query_data_attachment`, `describe_db` to `describe_attached_db`, and
+ * `query_db` to `query_attached_db`; these take the attachment ID as
+ * `attachmentId`. `transform_data` takes its arguments as the `request`
+ * of the command of the same name, `calculate` maps to the command of the
+ * same name, `convert` to `convert_units`, and `resolve_datetime` and
+ * `get_weather` to the commands of the same names.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const tools = await invoke("get_data_tools");
+ * // when the model calls query_data:
+ * const { attachmentId, ...query } = JSON.parse(call.arguments);
+ * const result = await invoke("query_data_attachment", { id: attachmentId, query });
+ * ```
+ */
+async getDataTools() : Promise<PluginTool[]> {
+    return await TAURI_INVOKE("get_data_tools");
+},
+/**
+ * Describes the sheets of a spreadsheet attachment for the model.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment doesn't
+ * exist, `NOT_SPREADSHEET` if it isn't a spreadsheet, or `INVALID_CONTENT`
+ * if it cannot be parsed.
+ */
+async describeDataAttachment(id: string) : Promise<DataDescription> {
+    return await TAURI_INVOKE("describe_data_attachment", { id });
+},
+/**
+ * Runs a query over a spreadsheet attachment.
+ * 
+ * Filters compare numbers numerically and text ignoring case.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment or
+ * sheet doesn't exist, `NOT_SPREADSHEET` if it isn't a spreadsheet,
+ * `INVALID_CONTENT` if it cannot be parsed, or `INVALID_INPUT` if the
+ * query names an unknown column.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const revenue = await invoke("query_data_attachment", {
+ * id,
+ * query: {
+ * filters: [{ column: "year", op: "eq", value: 2026 }],
+ * operation: {
+ * op: "groupBy",
+ * by: ["region"],
+ * aggregations: [{ function: "sum", column: "revenue" }],
+ * },
+ * sortBy: "sum(revenue)",
+ * descending: true,
+ * },
+ * });
+ * ```
+ */
+async queryDataAttachment(id: string, query: DataQuery) : Promise<DataResult> {
+    return await TAURI_INVOKE("query_data_attachment", { id, query });
+},
+/**
+ * Lists the tables and views of a database attachment.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment doesn't
+ * exist, `NOT_DATABASE` if it isn't a SQLite database, or `DATABASE` if
+ * its schema cannot be read.
+ */
+async describeAttachedDb(id: string) : Promise<DbSchema> {
+    return await TAURI_INVOKE("describe_attached_db", { id });
+},
+/**
+ * Runs a read-only query against a database attachment.
+ * 
+ * At most `limit` rows (default 100, at most 1000) and 256 KB of JSON are
+ * returned, and the query is interrupted after 10 seconds. Every query,
+ * successful or not, is recorded in the audit log.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment doesn't
+ * exist, `NOT_DATABASE` if it isn't a SQLite database, `FORBIDDEN` if the
+ * statement could write, `INVALID_QUERY` if SQLite rejects it, or
+ * `TIMEOUT` if it runs too long.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { columns, rows } = await invoke("query_attached_db", {
+ * id,
+ * sql: "SELECT artist, COUNT(*) FROM tracks GROUP BY artist ORDER BY 2 DESC",
+ * limit: 10,
+ * });
+ * ```
+ */
+async queryAttachedDb(id: string, sql: string, limit: number | null) : Promise<DbQueryResult> {
+    return await TAURI_INVOKE("query_attached_db", { id, sql, limit });
+},
+/**
+ * Runs a jq filter over a JSON, YAML, or TOML document and writes the
+ * results in the requested format.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_CONTENT` if the input doesn't
+ * parse, `INVALID_FILTER` if the filter doesn't compile, `FILTER_FAILED`
+ * if it fails on the input, `TIMEOUT` if it runs longer than 5 seconds,
+ * `INVALID_OUTPUT` if a result can't be written in the output format, or
+ * `TOO_LARGE` if the input exceeds 5 MB or the output 1 MB.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { output } = await invoke("transform_data", {
+ * request: { input: packageJson, from: "json", to: "yaml", filter: ".dependencies" },
+ * });
+ * ```
+ */
+async transformData(request: TransformRequest) : Promise<TransformResult> {
+    return await TAURI_INVOKE("transform_data", { request });
+},
+/**
+ * Evaluates an arithmetic expression with arbitrary precision.
+ * 
+ * Results are rounded to 30 fractional digits. Evaluation runs on a
+ * blocking thread.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_EXPRESSION` if the
+ * expression doesn't parse, divides by zero, has no real value, or would
+ * produce a result of more than 100,000 digits.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { result } = await invoke("calculate", { expression: "0.1 + 0.2" }); // "0.3"
+ * ```
+ */
+async calculate(expression: string) : Promise<Calculation> {
+    return await TAURI_INVOKE("calculate", { expression });
+},
+/**
+ * Converts an amount between units or currencies.
+ * 
+ * `amount` may be an expression. Three-letter codes that aren't units
+ * are taken as currencies.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_EXPRESSION` if the amount
+ * doesn't evaluate, `UNKNOWN_UNIT` for units or currencies that aren't
+ * known, `INVALID_INPUT` for units of different dimensions, or a network
+ * error if exchange rates are needed and none could ever be fetched.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("convert_units", { amount: "26.2", from: "mi", to: "km" });
+ * await invoke("convert_units", { amount: "120", from: "USD", to: "EUR" });
+ * ```
+ */
+async convertUnits(amount: string, from: string, to: string) : Promise<Conversion> {
+    return await TAURI_INVOKE("convert_units", { amount, from, to });
+},
+/**
+ * Returns the date and time line for system prompts, or `None` if
+ * `datetimeContext` is off in the general settings.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const context = await invoke("get_datetime_context");
+ * if (context) system = `${system}\n\n${context.prompt}`;
+ * ```
+ */
+async getDatetimeContext() : Promise<DateTimeContext | null> {
+    return await TAURI_INVOKE("get_datetime_context");
+},
+/**
+ * Resolves a date or time phrase to a concrete local date and time.
+ * 
+ * `reference` is the RFC 3339 time to resolve against, and sets the
+ * timezone; it defaults to now in the local timezone. Slashed dates are
+ * read in the order of the OS locale.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if the phrase or
+ * `reference` can't be understood.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { datetime } = await invoke("resolve_datetime", { text: "next Tuesday at 3pm" });
+ * ```
+ */
+async resolveDatetime(text: string, reference: string | null) : Promise<ResolvedDateTime> {
+    return await TAURI_INVOKE("resolve_datetime", { text, reference });
+},
+/**
+ * Returns the location preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be read.
+ */
+async getLocationSettings() : Promise<LocationSettings> {
+    return await TAURI_INVOKE("get_location_settings");
+},
+/**
+ * Replaces the location preferences and forgets the cached location.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_location_settings", { settings: { enabled: true, place: "Lyon" } });
+ * ```
+ */
+async setLocationSettings(settings: LocationSettings) : Promise<LocationSettings> {
+    return await TAURI_INVOKE("set_location_settings", { settings });
+},
+/**
+ * Returns the location line for system prompts, or `None` if location
+ * context is off.
+ * 
+ * The first call after a settings change looks the location up.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the place or IP
+ * address can't be located, or a network error.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const context = await invoke("get_location_context");
+ * if (context) system = `${system}\n\n${context.prompt}`;
+ * ```
+ */
+async getLocationContext() : Promise<LocationContext | null> {
+    return await TAURI_INVOKE("get_location_context");
+},
+/**
+ * Returns the current weather and a forecast of `days` days (3 by
+ * default, at most 16).
+ * 
+ * Without a `location` the user's location is used, which needs
+ * location context to be on.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` if no location is given
+ * and location context is off, `NOT_FOUND` if the place is unknown, or a
+ * network error.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { current, daily } = await invoke("get_weather", { location: null, days: 2 });
+ * ```
+ */
+async getWeather(location: string | null, days: number | null) : Promise<WeatherReport> {
+    return await TAURI_INVOKE("get_weather", { location, days });
+},
+/**
+ * Detects the language of `text`.
+ * 
+ * Returns `None` when the text has too few letters to tell.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const language = await invoke("detect_language", { text: "Wie spät ist es?" });
+ * // { code: "deu", name: "German", confidence: 0.93, reliable: true }
+ * ```
+ */
+async detectLanguage(text: string) : Promise<DetectedLanguage | null> {
+    return await TAURI_INVOKE("detect_language", { text });
+},
+/**
+ * Translates text into another language.
+ * 
+ * Text detected, reliably, as already being in the target language is
+ * returned unchanged without a request.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` for empty or overlong
+ * text or an empty target, or the code of the failed completion.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { text } = await invoke("translate_text", {
+ * request: { text: "Wo ist der Bahnhof?", target: "English" },
+ * });
+ * ```
+ */
+async translateText(request: TranslationRequest) : Promise<Translation> {
+    return await TAURI_INVOKE("translate_text", { request });
+},
+/**
+ * Translates a Markdown or plain text attachment as a background job
+ * and stores the translation as a new attachment in the same
+ * conversation.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment
+ * doesn't exist, `NOT_TEXT` if it isn't a text document, `CANCELLED` if
+ * the job is cancelled, or the code of a failed completion.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { attachment } = await invoke("translate_attachment", {
+ * attachmentId, target: "Spanish", model: null,
+ * });
+ * ```
+ */
+async translateAttachment(attachmentId: string, target: string, model: string | null) : Promise<TranslatedDocument> {
+    return await TAURI_INVOKE("translate_attachment", { attachmentId, target, model });
+},
+/**
+ * Rewrites `text` in the given mode with the default model, or `model`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` for blank text,
+ * `TOO_LARGE` for text over 20,000 characters, or the code of the
+ * failed completion.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { text, changed } = await invoke("rewrite_text", {
+ * text: "their going to the studio tomorow",
+ * mode: "fixGrammar",
+ * model: null,
+ * });
+ * ```
+ */
+async rewriteText(text: string, mode: RewriteMode, model: string | null) : Promise<Rewrite> {
+    return await TAURI_INVOKE("rewrite_text", { text, mode, model });
+},
+/**
+ * Rewrites the clipboard watcher's latest text capture, copying the
+ * result back to the clipboard when `copy` is set.
+ * 
+ * The capture is used up, and a copied result isn't offered again as a
+ * new capture.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `EMPTY` if no text has been
+ * captured, `CLIPBOARD` if the result can't be copied, or an error as
+ * [`rewrite_text`] does.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("rewrite_clipboard", { mode: "shorten", copy: true });
+ * ```
+ */
+async rewriteClipboard(mode: RewriteMode, copy: boolean) : Promise<Rewrite> {
+    return await TAURI_INVOKE("rewrite_clipboard", { mode, copy });
+},
+/**
+ * Returns the text received from another app, once.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await listen("external-input://received", async () => {
+ * const input = await invoke("take_external_input");
+ * if (input) showInput(input);
+ * });
+ * ```
+ */
+async takeExternalInput() : Promise<ExternalInput | null> {
+    return await TAURI_INVOKE("take_external_input");
+},
+/**
+ * Runs a quick action on text from another app.
+ * 
+ * Rewrites and translations are returned to the caller, and copied to
+ * the clipboard when `copy` is set. `ask` creates a conversation, hides
+ * the capture window, and hands the conversation to the main window
+ * with [`events::QUICK_CAPTURE_SUBMITTED`].
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` for blank text,
+ * `TOO_LARGE` for text over 20,000 characters, `CLIPBOARD` if the result
+ * can't be copied, or an error as `rewrite_text`, `translate_text`, or
+ * `submit_quick_capture` do.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const result = await invoke("run_external_input", {
+ * request: { text: input.text, action: "translate", target: null, copy: true },
+ * });
+ * ```
+ */
+async runExternalInput(request: QuickActionRequest) : Promise<QuickActionResult> {
+    return await TAURI_INVOKE("run_external_input", { request });
+},
+/**
+ * Adds a Services menu entry for each quick action and returns their
+ * menu titles.
+ * 
+ * Installing again overwrites the entries, which updates them after the
+ * link format changes.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `UNSUPPORTED` outside macOS, or `IO`
+ * if the workflows can't be written.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const titles = await invoke("install_text_services");
+ * ```
+ */
+async installTextServices() : Promise<string[]> {
+    return await TAURI_INVOKE("install_text_services");
+},
+/**
+ * Removes the Services menu entries added by `install_text_services`.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `UNSUPPORTED` outside macOS, or `IO`
+ * if a workflow can't be deleted.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("remove_text_services");
+ * ```
+ */
+async removeTextServices() : Promise<null> {
+    return await TAURI_INVOKE("remove_text_services");
+},
+/**
+ * Returns the composer preferences.
+ */
+async getNostrComposerSettings() : Promise<ComposerSettings> {
+    return await TAURI_INVOKE("get_nostr_composer_settings");
+},
+/**
+ * Replaces the composer preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_RELAY` or
+ * `INVALID_SETTINGS` naming the bad value, or `DATABASE` if the settings
+ * cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_nostr_composer_settings", {
+ * settings: { relays: ["wss://nos.lol"], syncDrafts: true },
+ * });
+ * ```
+ */
+async setNostrComposerSettings(settings: ComposerSettings) : Promise<ComposerSettings> {
+    return await TAURI_INVOKE("set_nostr_composer_settings", { settings });
+},
+/**
+ * Lists the drafts, most recently edited first.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the query fails.
+ */
+async listNostrDrafts() : Promise<Draft[]> {
+    return await TAURI_INVOKE("list_nostr_drafts");
+},
+/**
+ * Creates a draft, or replaces an existing one when `id` is given.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` if the article is
+ * over 200,000 characters, `NOT_FOUND` if `id` doesn't exist, or
+ * `DATABASE` on failure.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const draft = await invoke("save_nostr_draft", {
+ * id: null,
+ * input: { title: "Patching a West Coast voice", content: "# Patching…", hashtags: ["synths"] },
+ * });
+ * ```
+ */
+async saveNostrDraft(id: string | null, input: DraftInput) : Promise<Draft> {
+    return await TAURI_INVOKE("save_nostr_draft", { id, input });
+},
+/**
+ * Deletes a draft locally.
+ * 
+ * A published article stays on the relays, and so does a synced copy of
+ * the draft until the next sync from this device blanks it.
+ * 
+ * # Returns
+ * 
+ * Returns `true` if the draft was deleted, `false` if it didn't exist.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` if the delete fails.
+ */
+async deleteNostrDraft(id: string) : Promise<boolean> {
+    return await TAURI_INVOKE("delete_nostr_draft", { id });
+},
+/**
+ * Asks a model to revise a draft, or the `selection` within it, and
+ * returns the result without changing the draft.
+ * 
+ * Titles and summaries are always written for the whole article.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the draft doesn't
+ * exist, `INVALID_INPUT` if there is no text to revise or a custom
+ * instruction is blank, or the code of the failed completion.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { text } = await invoke("revise_nostr_draft", {
+ * id: draft.id,
+ * revision: { kind: "custom", instruction: "Add a short conclusion" },
+ * selection: null,
+ * model: null,
+ * });
+ * ```
+ */
+async reviseNostrDraft(id: string, revision: Revision, selection: string | null, model: string | null) : Promise<DraftRevision> {
+    return await TAURI_INVOKE("revise_nostr_draft", { id, revision, selection, model });
+},
+/**
+ * Signs a draft as a NIP-23 article and publishes it to the composer's
+ * relays.
+ * 
+ * The first publication time is kept, so republishing an edited draft
+ * updates the article in place.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the draft doesn't
+ * exist, `INVALID_INPUT` if it has no title or content, `NO_KEY` if no
+ * secret key is stored, or `PUBLISH_FAILED` if no relay accepted it.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { naddr, relays } = await invoke("publish_nostr_draft", { id: draft.id });
+ * ```
+ */
+async publishNostrDraft(id: string) : Promise<PublishedArticle> {
+    return await TAURI_INVOKE("publish_nostr_draft", { id });
+},
+/**
+ * Syncs drafts with other devices through the relays.
+ * 
+ * Drafts changed since the last sync are published as draft wraps
+ * encrypted to the user's own key, and deleted drafts are blanked.
+ * Newer drafts from the relays replace local ones, and drafts blanked
+ * elsewhere are removed.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `FORBIDDEN` if draft sync is off,
+ * `NO_KEY` if no secret key is stored, or a relay error.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { uploaded, downloaded } = await invoke("sync_nostr_drafts");
+ * ```
+ */
+async syncNostrDrafts() : Promise<SyncReport> {
+    return await TAURI_INVOKE("sync_nostr_drafts");
+},
+/**
+ * Returns the media preferences.
+ */
+async getMediaSettings() : Promise<MediaSettings> {
+    return await TAURI_INVOKE("get_media_settings");
+},
+/**
+ * Replaces the media preferences.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_SETTINGS` naming the bad
+ * server, or `DATABASE` if the settings cannot be stored.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * await invoke("set_media_settings", {
+ * settings: {
+ * servers: [
+ * { url: "https://blossom.primal.net", protocol: "blossom" },
+ * { url: "https://nostr.build", protocol: "nip96" },
+ * ],
+ * },
+ * });
+ * ```
+ */
+async setMediaSettings(settings: MediaSettings) : Promise<MediaSettings> {
+    return await TAURI_INVOKE("set_media_settings", { settings });
+},
+/**
+ * Uploads an image attachment and returns its URL.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the attachment
+ * doesn't exist, or an error as [`upload_media_file`] does.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { url, imeta } = await invoke("upload_media", { attachmentId });
+ * ```
+ */
+async uploadMedia(attachmentId: string) : Promise<UploadedMedia> {
+    return await TAURI_INVOKE("upload_media", { attachmentId });
+},
+/**
+ * Uploads an image or audio file from disk and returns its URL.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `UNSUPPORTED_TYPE` for other files,
+ * `TOO_LARGE` over 50 MB, `IO` if the file can't be read, `NO_KEY` if no
+ * Nostr secret key is stored, or `UPLOAD_FAILED` or `HASH_MISMATCH` if
+ * no server stored it intact.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { url } = await invoke("upload_media_file", { path: "/Users/me/loop.ogg" });
+ * ```
+ */
+async uploadMediaFile(path: string) : Promise<UploadedMedia> {
+    return await TAURI_INVOKE("upload_media_file", { path });
+},
+/**
+ * Reads a conversation aloud into an audio file, with a voice per role.
+ * 
+ * The export runs as an `export.audio` job (see [`jobs`]), so its
+ * progress arrives as `job://progress` events and it can be cancelled
+ * with `cancel_job`. The file only appears once it is complete.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the conversation
+ * doesn't exist, `INVALID_INPUT` for an unknown voice, `EMPTY` if there
+ * is nothing to read, `TOO_LARGE` over 100,000 characters,
+ * `POLICY_BLOCKED` if the routing policy keeps the conversation from
+ * OpenAI, `NO_API_KEY` if no OpenAI key is stored, `CANCELLED` if the job
+ * was cancelled, `IO` if the file cannot be written, or the error of a
+ * failed speech request.
+ * 
+ * [`jobs`]: crate::commands::jobs
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { segments } = await invoke("export_conversation_audio", {
+ * request: {
+ * conversationId,
+ * path: "/Users/me/mixing-session.mp3",
+ * format: "mp3",
+ * voices: { user: "coral", assistant: "onyx" },
+ * },
+ * });
+ * ```
+ */
+async exportConversationAudio(request: AudioExportRequest) : Promise<AudioExport> {
+    return await TAURI_INVOKE("export_conversation_audio", { request });
+},
+/**
+ * Generates flashcards about a conversation or document for review.
+ * 
+ * Nothing is written; pass the confirmed cards to [`export_flashcards`].
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `NOT_FOUND` if the source doesn't
+ * exist, `NOT_TEXT` if the attachment isn't a text document, `EMPTY` if
+ * the source has no text, `TOO_LARGE` over 100,000 characters,
+ * `PARSE_ERROR` if the reply holds no cards, or the `GibberError` code if
+ * the request fails.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const cards = await invoke("generate_flashcards", {
+ * source: { kind: "document", attachmentId },
+ * count: 30,
+ * model: null,
+ * });
+ * // show cards for editing, then:
+ * await invoke("export_flashcards", {
+ * request: { cards: confirmed, deck: "Synthesis::Filters", path: "/Users/me/filters.apkg" },
+ * });
+ * ```
+ */
+async generateFlashcards(source: FlashcardSource, count: number | null, model: string | null) : Promise<Flashcard[]> {
+    return await TAURI_INVOKE("generate_flashcards", { source, count, model });
+},
+/**
+ * Writes flashcards as an Anki deck.
+ * 
+ * # Errors
+ * 
+ * Returns a `GibberError` with code `INVALID_INPUT` for a blank or overly
+ * long deck name, no cards or more than 5,000, or a card without a
+ * question or answer; `IO` if the file cannot be written; or `DATABASE`
+ * if the package's collection cannot be built.
+ * 
+ * # Example
+ * 
+ * ```typescript
+ * const { count } = await invoke("export_flashcards", {
+ * request: { cards, deck: "Mixing", path: "/Users/me/mixing.txt", format: "tsv" },
+ * });
+ * ```
+ */
+async exportFlashcards(request: FlashcardExportRequest) : Promise<FlashcardExport> {
+    return await TAURI_INVOKE("export_flashcards", { request });
+}
+}
+
+/** user-defined events **/
+
+
+
+/** user-defined constants **/
+
+
+
+/** user-defined types **/
+
+/**
+ * A task or appointment found in a conversation.
+ */
+export type ActionItem = { 
+/**
+ * Short title
+ */
+title: string; 
+/**
+ * Extra details
+ */
+notes?: string | null; 
+/**
+ * Event or reminder
+ */
+kind: ActionItemKind; 
+/**
+ * Local due date, `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM`; required for events
+ */
+due?: string | null; 
+/**
+ * Event length; 30 minutes when unset
+ */
+durationMinutes?: number | null }
+/**
+ * Where an item goes.
+ */
+export type ActionItemKind = 
+/**
+ * A calendar event
+ */
+"event" | 
+/**
+ * A reminder or to-do
+ */
+"reminder"
+/**
+ * Payload of [`events::NOTIFICATION_ACTIVATED`].
+ */
+export type ActivatedPayload = { 
+/**
+ * Kind of the notification that was followed
+ */
+kind: NotificationKind; 
+/**
+ * Conversation the notification was about, if any
+ */
+conversationId: string | null }
+/**
+ * Function computed over each group.
+ */
+export type AggregateFunction = 
+/**
+ * Rows, or non-empty values of the column
+ */
+"count" | 
+/**
+ * Sum of numbers
+ */
+"sum" | 
+/**
+ * Mean of numbers
+ */
+"mean" | 
+/**
+ * Smallest value
+ */
+"min" | 
+/**
+ * Largest value
+ */
+"max" | 
+/**
+ * Distinct non-empty values
+ */
+"distinct"
+/**
+ * An aggregate column of a group-by result.
+ */
+export type Aggregation = { 
+/**
+ * What to compute
+ */
+function: AggregateFunction; 
+/**
+ * Column it is computed over; only `count` may leave it out
+ */
+column?: string | null }
+/**
+ * API server preferences.
+ */
+export type ApiServerSettings = { 
+/**
+ * Listen for requests
+ */
+enabled: boolean; 
+/**
+ * Port on `127.0.0.1`
+ */
+port: number; 
+/**
+ * Bearer token clients must send; generated on first enable
+ */
+token: string; 
+/**
+ * Store requests and replies as conversations
+ */
+saveConversations: boolean }
+/**
+ * Server state as shown in the settings UI.
+ */
+export type ApiServerStatus = { 
+/**
+ * Stored preferences
+ */
+settings: ApiServerSettings; 
+/**
+ * Base URL clients should use, while the server runs
+ */
+baseUrl: string | null }
+/**
+ * Payload of [`events::CLIPBOARD_ASK`].
+ */
+export type AskPayload = { 
+/**
+ * Conversation created for the question
+ */
+conversationId: string; 
+/**
+ * Copied image as a PNG data URL, for image captures
+ */
+imageDataUrl: string | null }
+/**
+ * The variant a conversation was given.
+ */
+export type Assignment = { 
+/**
+ * Experiment the conversation is enrolled in
+ */
+experimentId: string; 
+/**
+ * Variant it was given
+ */
+variant: Variant; 
+/**
+ * Label of that variant
+ */
+label: string }
+/**
+ * A stored attachment.
+ */
+export type Attachment = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Conversation the attachment belongs to; `None` while pending
+ */
+conversationId: string | null; 
+/**
+ * Original file name
+ */
+fileName: string; 
+/**
+ * Attachment type
+ */
+kind: AttachmentKind; 
+/**
+ * MIME type
+ */
+mimeType: string; 
+/**
+ * Size in bytes
+ */
+size: number; 
+/**
+ * How the attachment entered the app (see [`AttachmentOrigin`])
+ */
+origin: string; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * Supported attachment types.
+ */
+export type AttachmentKind = 
+/**
+ * Markdown document
+ */
+"markdown" | 
+/**
+ * Plain text document
+ */
+"text" | 
+/**
+ * PDF document
+ */
+"pdf" | 
+/**
+ * PNG, JPEG, WebP, or GIF image
+ */
+"image" | 
+/**
+ * CSV, TSV, Excel, or OpenDocument spreadsheet
+ */
+"spreadsheet" | 
+/**
+ * SQLite database
+ */
+"database"
+/**
+ * A finished audio export.
+ */
+export type AudioExport = { 
+/**
+ * The file written
+ */
+path: string; 
+/**
+ * Its format
+ */
+format: AudioFormat; 
+/**
+ * Messages read aloud
+ */
+messages: number; 
+/**
+ * Speech requests made; long messages take several
+ */
+segments: number; 
+/**
+ * Characters read aloud
+ */
+characters: number }
+/**
+ * What to export and how.
+ */
+export type AudioExportRequest = { 
+/**
+ * Conversation to read aloud
+ */
+conversationId: string; 
+/**
+ * File to write
+ */
+path: string; 
+/**
+ * File format (default: MP3)
+ */
+format?: AudioFormat; 
+/**
+ * Voice of each role
+ */
+voices?: RoleVoices; 
+/**
+ * Speech model; `gpt-4o-mini-tts` when `None`
+ */
+model?: string | null; 
+/**
+ * Whether system prompts are read too (default: no)
+ */
+includeSystem?: boolean }
+/**
+ * File formats a conversation is read into.
+ */
+export type AudioFormat = 
+/**
+ * MP3
+ */
+"mp3" | 
+/**
+ * Opus in an Ogg container
+ */
+"ogg" | 
+/**
+ * 16-bit mono WAV at 24 kHz
+ */
+"wav"
+/**
+ * A recorded operation.
+ */
+export type AuditEntry = { 
+/**
+ * Sequence number; higher is newer
+ */
+id: number; 
+/**
+ * Who performed it
+ */
+actor: string; 
+/**
+ * What kind of operation it was
+ */
+kind: AuditKind; 
+/**
+ * Tool name, file path, command line, or URL
+ */
+target: string; 
+/**
+ * Extra context
+ */
+detail: JsonValue | null; 
+/**
+ * Error message if the operation failed
+ */
+error: string | null; 
+/**
+ * Time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * What kind of operation was performed.
+ */
+export type AuditKind = 
+/**
+ * A tool was invoked
+ */
+"tool" | 
+/**
+ * A file was read
+ */
+"fileRead" | 
+/**
+ * A file was written
+ */
+"fileWrite" | 
+/**
+ * A program was run
+ */
+"shell" | 
+/**
+ * A network request was sent
+ */
+"network"
+/**
+ * Filters for [`get_audit_log`].
+ */
+export type AuditQuery = { 
+/**
+ * Only this actor, or actors starting with it when it ends in `:`
+ */
+actor: string | null; 
+/**
+ * Only this kind
+ */
+kind: AuditKind | null; 
+/**
+ * Only entries older than this ID, for paging
+ */
+before: number | null; 
+/**
+ * Page size (default and maximum: 500)
+ */
+limit: number | null }
+/**
+ * A saved automation script.
+ */
+export type Automation = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * Rhai source
+ */
+script: string; 
+/**
+ * What starts the script
+ */
+trigger: AutomationTrigger; 
+/**
+ * Whether the trigger is active; disabled scripts can still be run by hand
+ */
+enabled: boolean; 
+/**
+ * Time of the last run in Unix milliseconds
+ */
+lastRunAt: number | null; 
+/**
+ * Outcome of the last run
+ */
+lastStatus: RunStatus | null; 
+/**
+ * Error message of the last failed run
+ */
+lastError: string | null; 
+/**
+ * Next scheduled run in Unix milliseconds
+ */
+nextRunAt: number | null; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * Fields for creating or updating an automation.
+ */
+export type AutomationInput = { 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * Rhai source
+ */
+script: string; 
+/**
+ * What starts the script
+ */
+trigger: AutomationTrigger; 
+/**
+ * Whether the trigger is active (default: true)
+ */
+enabled?: boolean }
+/**
+ * Outcome of one run; payload of [`events::AUTOMATIONS_RUN_COMPLETED`].
+ */
+export type AutomationRun = { 
+/**
+ * The automation that ran
+ */
+automationId: string; 
+/**
+ * What started the run
+ */
+trigger: string; 
+/**
+ * Outcome of the run
+ */
+status: RunStatus; 
+/**
+ * The script's final value, unless it was `()`
+ */
+result: string | null; 
+/**
+ * Lines written with `print`
+ */
+output: string[]; 
+/**
+ * Error message, on failure
+ */
+error: string | null; 
+/**
+ * Start time in Unix milliseconds
+ */
+startedAt: number; 
+/**
+ * End time in Unix milliseconds
+ */
+finishedAt: number }
+/**
+ * What starts an automation.
+ */
+export type AutomationTrigger = 
+/**
+ * Only the "run" button
+ */
+{ kind: "manual" } | 
+/**
+ * A global shortcut
+ */
+{ kind: "hotkey"; accelerator: string } | 
+/**
+ * A cron schedule, as for scheduled tasks
+ */
+{ kind: "schedule"; cron: string } | 
+/**
+ * Every message appended to a conversation
+ */
+{ kind: "newMessage"; role?: MessageRole | null }
+/**
+ * Payload of [`events::ROUTING_POLICY_BLOCKED`].
+ */
+export type BlockedPayload = { 
+/**
+ * Conversation the request was for
+ */
+conversationId: string; 
+/**
+ * Gateway the request would have gone through
+ */
+gateway: string; 
+/**
+ * Model the request was for
+ */
+model: string; 
+/**
+ * Why it was refused
+ */
+message: string }
+/**
+ * Bridge preferences.
+ */
+export type BridgeSettings = { 
+/**
+ * Accept connections
+ */
+enabled: boolean; 
+/**
+ * Port on `127.0.0.1`
+ */
+port: number; 
+/**
+ * Token clients must pass as the `token` query parameter
+ */
+token: string }
+/**
+ * Bridge state as shown in the settings UI.
+ */
+export type BridgeStatus = { 
+/**
+ * Stored preferences
+ */
+settings: BridgeSettings; 
+/**
+ * URL clients should connect to, while the bridge runs
+ */
+url: string | null; 
+/**
+ * Connected clients
+ */
+clients: number }
+/**
+ * Browser bridge preferences.
+ */
+export type BrowserBridgeSettings = { 
+/**
+ * Access by origin, e.g. `https://example.com`
+ */
+origins: Partial<{ [key in string]: OriginAccess }>; 
+/**
+ * Store requests and replies as conversations
+ */
+saveConversations: boolean }
+/**
+ * Size and usefulness of the cache.
+ */
+export type CacheStats = { 
+/**
+ * Stored responses
+ */
+entries: number; 
+/**
+ * Requests answered from the cache
+ */
+hits: number; 
+/**
+ * Size of the stored response text
+ */
+bytes: number }
+/**
+ * What `calculate` returns.
+ */
+export type Calculation = { 
+/**
+ * The value, in plain or scientific decimal notation
+ */
+result: string; 
+/**
+ * Whether part of the computation used 64-bit floats
+ */
+approximate: boolean }
+/**
+ * How items are added.
+ */
+export type CalendarMethod = 
+/**
+ * Added through Calendar and Reminders (macOS)
+ */
+"native" | 
+/**
+ * Written to an `.ics` file opened in the calendar app
+ */
+"ics"
+/**
+ * What a plugin may do beyond computing.
+ */
+export type Capabilities = { 
+/**
+ * Hosts reachable over HTTP; `*.example.com` matches subdomains
+ */
+network: string[]; 
+/**
+ * Access to a private key-value namespace
+ */
+storage: boolean }
+/**
+ * A screen or window that can be captured.
+ */
+export type CaptureSource = { 
+/**
+ * Monitor or window ID
+ */
+id: number; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * Width in physical pixels
+ */
+width: number; 
+/**
+ * Height in physical pixels
+ */
+height: number }
+/**
+ * Screens and windows that can be captured.
+ */
+export type CaptureSources = { 
+/**
+ * Connected screens
+ */
+screens: CaptureSource[]; 
+/**
+ * Visible windows of other applications, topmost first
+ */
+windows: CaptureSource[] }
+/**
+ * What to capture.
+ */
+export type CaptureTarget = 
+/**
+ * A whole screen; the primary one unless `monitor` is given
+ */
+{ mode: "screen"; monitor: number | null } | 
+/**
+ * A single window; the topmost non-Gibber window unless `window` is given
+ */
+{ mode: "window"; window: number | null } | 
+/**
+ * A rectangle of a screen, in physical pixels relative to its top-left
+ */
+{ mode: "region"; monitor: number | null; x: number; y: number; width: number; height: number }
+/**
+ * Summary of a capture, as sent to the frontend.
+ */
+export type CapturedClip = 
+/**
+ * Copied text
+ */
+{ kind: "text"; preview: string; chars: number } | 
+/**
+ * Copied image
+ */
+{ kind: "image"; width: number; height: number }
+/**
+ * A single message sent to the model.
+ */
+export type ChatMessage = { 
+/**
+ * The role of the message sender
+ */
+role: MessageRole; 
+/**
+ * The message content
+ */
+content: string }
+/**
+ * What a question about the clipboard should do.
+ */
+export type ClipboardAction = 
+/**
+ * Explain the content
+ */
+"explain" | 
+/**
+ * Summarize the content
+ */
+"summarize"
+/**
+ * Clipboard watcher preferences.
+ */
+export type ClipboardSettings = { 
+/**
+ * Whether the watcher runs at all
+ */
+enabled: boolean; 
+/**
+ * Capture copied text
+ */
+watchText: boolean; 
+/**
+ * Capture copied images
+ */
+watchImages: boolean; 
+/**
+ * Ignore text shorter than this many characters
+ */
+minTextChars: number; 
+/**
+ * Application names whose copies are ignored (case-insensitive)
+ */
+excludedApps: string[] }
+/**
+ * A fenced code block of a message.
+ */
+export type CodeBlock = { 
+/**
+ * Position among the message's code blocks, from 0
+ */
+index: number; 
+/**
+ * Language from the fence info string, if any
+ */
+language: string | null; 
+/**
+ * The code
+ */
+code: string; 
+/**
+ * File extension for the language, without the dot
+ */
+extension: string }
+/**
+ * Type of a column's values.
+ */
+export type ColumnType = 
+/**
+ * No values
+ */
+"empty" | 
+/**
+ * Numbers
+ */
+"number" | 
+/**
+ * Booleans
+ */
+"bool" | 
+/**
+ * Text, including dates
+ */
+"text" | 
+/**
+ * More than one of the above
+ */
+"mixed"
+/**
+ * Composer preferences.
+ */
+export type ComposerSettings = { 
+/**
+ * Relays articles and synced drafts are published to
+ */
+relays: string[]; 
+/**
+ * Whether drafts are synced through the relays, encrypted
+ */
+syncDrafts: boolean; 
+/**
+ * Model for revisions; the default model when `None`
+ */
+model: string | null }
+/**
+ * Summary of a stored conversation.
+ */
+export type Conversation = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display title
+ */
+title: string; 
+/**
+ * Model used by default for this conversation
+ */
+model: string | null; 
+/**
+ * System prompt for this conversation
+ */
+systemPrompt: string | null; 
+/**
+ * What created the conversation (e.g. "chat", "scheduler")
+ */
+source: string; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Last update time in Unix milliseconds
+ */
+updatedAt: number }
+/**
+ * What `get_conversation_stats` returns.
+ */
+export type ConversationStats = { 
+/**
+ * The conversation
+ */
+conversationId: string; 
+/**
+ * All messages
+ */
+messages: number; 
+/**
+ * Messages written by the user
+ */
+userMessages: number; 
+/**
+ * Messages generated by a model
+ */
+assistantMessages: number; 
+/**
+ * Prompt tokens over all messages
+ */
+promptTokens: number; 
+/**
+ * Completion tokens over all messages
+ */
+completionTokens: number; 
+/**
+ * Cost in US dollars recorded for the conversation
+ */
+costUsd: number; 
+/**
+ * Models used, most used first
+ */
+models: ModelUsage[]; 
+/**
+ * Time of the first message in Unix milliseconds
+ */
+firstMessageAt: number | null; 
+/**
+ * Time of the last message in Unix milliseconds
+ */
+lastMessageAt: number | null; 
+/**
+ * Milliseconds from the first message to the last
+ */
+durationMs: number; 
+/**
+ * Longest pauses, longest first
+ */
+longestGaps: MessageGap[] }
+/**
+ * A conversation shown in its own window.
+ */
+export type ConversationWindow = { 
+/**
+ * Window label
+ */
+label: string; 
+/**
+ * Conversation shown in the window
+ */
+conversationId: string }
+/**
+ * A conversation with all of its messages.
+ */
+export type ConversationWithMessages = 
+/**
+ * The conversation metadata
+ */
+({ 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display title
+ */
+title: string; 
+/**
+ * Model used by default for this conversation
+ */
+model: string | null; 
+/**
+ * System prompt for this conversation
+ */
+systemPrompt: string | null; 
+/**
+ * What created the conversation (e.g. "chat", "scheduler")
+ */
+source: string; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Last update time in Unix milliseconds
+ */
+updatedAt: number }) & { 
+/**
+ * Messages in chronological order
+ */
+messages: StoredMessage[] }
+/**
+ * What `convert_units` returns.
+ */
+export type Conversion = { 
+/**
+ * Converted amount
+ */
+result: string; 
+/**
+ * Target unit or currency as understood
+ */
+unit: string; 
+/**
+ * What was converted: `length`, `currency`, ...
+ */
+dimension: string; 
+/**
+ * Whether the amount or conversion used 64-bit floats
+ */
+approximate: boolean; 
+/**
+ * Publication date of the exchange rates used
+ */
+ratesDate: string | null; 
+/**
+ * Whether the rates are older than a day because the ECB couldn't be
+ * reached
+ */
+staleRates: boolean }
+/**
+ * A stored crash report.
+ */
+export type CrashReport = { 
+/**
+ * Unique identifier, also the file stem
+ */
+id: string; 
+/**
+ * Crash time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * App version that crashed
+ */
+appVersion: string; 
+/**
+ * Operating system family
+ */
+os: string; 
+/**
+ * CPU architecture
+ */
+arch: string; 
+/**
+ * Name of the panicking thread
+ */
+thread: string | null; 
+/**
+ * Panic message
+ */
+message: string; 
+/**
+ * Source location of the panic
+ */
+location: string | null; 
+/**
+ * Captured backtrace
+ */
+backtrace: string; 
+/**
+ * Upload time in Unix milliseconds, if submitted
+ */
+submittedAt?: number | null }
+/**
+ * Result of [`create_action_items`].
+ */
+export type CreatedActionItems = { 
+/**
+ * How the items were added
+ */
+method: CalendarMethod; 
+/**
+ * Number of items added
+ */
+count: number; 
+/**
+ * The `.ics` file, for [`CalendarMethod::Ics`]
+ */
+path: string | null }
+/**
+ * A condition an answer must meet.
+ */
+export type Criterion = 
+/**
+ * The answer contains `text`
+ */
+{ kind: "contains"; text: string; caseSensitive?: boolean } | 
+/**
+ * The answer doesn't contain `text`
+ */
+{ kind: "notContains"; text: string; caseSensitive?: boolean } | 
+/**
+ * The answer matches a regular expression
+ */
+{ kind: "matches"; pattern: string } | 
+/**
+ * The answer is exactly `text`, ignoring surrounding whitespace
+ */
+{ kind: "equals"; text: string } | 
+/**
+ * The answer is at most `chars` characters long
+ */
+{ kind: "maxChars"; chars: number } | 
+/**
+ * The answer parses as JSON, optionally inside a code fence
+ */
+{ kind: "json" }
+/**
+ * Conditions right now.
+ */
+export type CurrentWeather = { 
+/**
+ * Air temperature in °C
+ */
+temperatureC: number; 
+/**
+ * Felt temperature in °C
+ */
+feelsLikeC: number | null; 
+/**
+ * Precipitation over the last hour, in mm
+ */
+precipitationMm: number | null; 
+/**
+ * Wind speed at 10 m, in km/h
+ */
+windKmh: number | null; 
+/**
+ * Weather description, e.g. `Light rain`
+ */
+conditions: string }
+/**
+ * The forecast for one day.
+ */
+export type DailyForecast = { 
+/**
+ * Local date, `YYYY-MM-DD`
+ */
+date: string; 
+/**
+ * Weather description
+ */
+conditions: string; 
+/**
+ * Highest temperature in °C
+ */
+maxC: number | null; 
+/**
+ * Lowest temperature in °C
+ */
+minC: number | null; 
+/**
+ * Highest chance of precipitation during the day, in percent
+ */
+precipitationChance: number | null; 
+/**
+ * Total precipitation in mm
+ */
+precipitationMm: number | null }
+/**
+ * A column of a sheet.
+ */
+export type DataColumn = { 
+/**
+ * Name from the first row
+ */
+name: string; 
+/**
+ * Type of its values
+ */
+type: ColumnType; 
+/**
+ * Non-empty values
+ */
+values: number; 
+/**
+ * Distinct non-empty values
+ */
+distinct: number }
+/**
+ * What `describe_data_attachment` returns.
+ */
+export type DataDescription = { 
+/**
+ * The attachment
+ */
+attachmentId: string; 
+/**
+ * Original file name
+ */
+fileName: string; 
+/**
+ * Sheets in workbook order
+ */
+sheets: DataSheet[] }
+/**
+ * Keeps the rows whose `column` compares with `value` as `op` says.
+ */
+export type DataFilter = { 
+/**
+ * Column name
+ */
+column: string; 
+/**
+ * Comparison
+ */
+op: FilterOp; 
+/**
+ * Value compared with; `null` matches empty cells
+ */
+value: JsonValue }
+/**
+ * What a query computes from the filtered rows.
+ */
+export type DataOperation = 
+/**
+ * The rows themselves
+ */
+{ op: "rows"; columns?: string[] } | 
+/**
+ * One row per distinct combination of the `by` columns
+ */
+{ op: "groupBy"; by: string[]; aggregations?: Aggregation[] } | 
+/**
+ * One row of summary statistics per column
+ */
+{ op: "describe"; columns?: string[] }
+/**
+ * A query over one sheet.
+ */
+export type DataQuery = { 
+/**
+ * Sheet name; the first sheet when `None`
+ */
+sheet?: string | null; 
+/**
+ * Filters every row must pass
+ */
+filters?: DataFilter[]; 
+/**
+ * What to compute
+ */
+operation: DataOperation; 
+/**
+ * Result column to sort by
+ */
+sortBy?: string | null; 
+/**
+ * Sort largest first
+ */
+descending?: boolean; 
+/**
+ * Most rows to return, up to 500 (default: 50)
+ */
+limit?: number | null }
+/**
+ * What `query_data_attachment` returns.
+ */
+export type DataResult = { 
+/**
+ * Column names
+ */
+columns: string[]; 
+/**
+ * Rows, one value per column
+ */
+rows: JsonValue[][]; 
+/**
+ * Rows before the limit was applied
+ */
+totalRows: number; 
+/**
+ * Whether rows were left out by the limit
+ */
+truncated: boolean }
+/**
+ * A sheet as shown to the model.
+ */
+export type DataSheet = { 
+/**
+ * Sheet name; the file name for CSV and TSV
+ */
+name: string; 
+/**
+ * Data rows, not counting the header
+ */
+rows: number; 
+/**
+ * Columns in order
+ */
+columns: DataColumn[]; 
+/**
+ * First rows, one value per column
+ */
+sample: JsonValue[][] }
+/**
+ * Which conversations go into a dataset. Empty lists don't filter.
+ */
+export type DatasetFilter = { 
+/**
+ * Conversations to consider; all of them when empty
+ */
+conversationIds: string[]; 
+/**
+ * Keep conversations with any of these tags
+ */
+tags: string[]; 
+/**
+ * Keep conversations answered only by these models
+ */
+models: string[]; 
+/**
+ * Keep conversations with at least one answer rated this way and none
+ * rated the other way
+ */
+rating: Rating | null }
+/**
+ * Record layout of the exported files.
+ */
+export type DatasetFormat = 
+/**
+ * OpenAI chat fine-tuning records
+ */
+"openai" | 
+/**
+ * ShareGPT records, as read by most open-source trainers
+ */
+"sharegpt"
+/**
+ * What [`export_dataset`] writes.
+ */
+export type DatasetRequest = { 
+/**
+ * Record layout
+ */
+format?: DatasetFormat; 
+/**
+ * Conversation selection
+ */
+filter?: DatasetFilter; 
+/**
+ * De-identify messages with these options; left as is when `None`
+ */
+redact?: DeidentifyOptions | null; 
+/**
+ * Share of conversations for the validation file, 0 to 0.5
+ */
+validationFraction?: number; 
+/**
+ * Seed of the split; change it to draw a different validation set
+ */
+seed?: number; 
+/**
+ * Training file
+ */
+trainPath: string; 
+/**
+ * Validation file; required when `validationFraction` is above 0
+ */
+validationPath?: string | null }
+/**
+ * Result of [`export_dataset`].
+ */
+export type DatasetSummary = { 
+/**
+ * Records written to the training file
+ */
+train: number; 
+/**
+ * Records written to the validation file
+ */
+validation: number; 
+/**
+ * Selected conversations left out by the filter
+ */
+filtered: number; 
+/**
+ * Conversations left out because they have no answer
+ */
+empty: number; 
+/**
+ * Values replaced by de-identification
+ */
+redacted: number }
+/**
+ * The moment a prompt was sent, as told to the model.
+ */
+export type DateTimeContext = { 
+/**
+ * Local time as RFC 3339
+ */
+now: string; 
+/**
+ * IANA timezone name, or the UTC offset if it can't be determined
+ */
+timezone: string; 
+/**
+ * OS locale as a BCP 47 tag
+ */
+locale: string | null; 
+/**
+ * The line added to the system prompt
+ */
+prompt: string }
+/**
+ * What `query_attached_db` returns.
+ */
+export type DbQueryResult = { 
+/**
+ * Column names
+ */
+columns: string[]; 
+/**
+ * Rows, one value per column; blobs are described, not returned
+ */
+rows: JsonValue[][]; 
+/**
+ * Whether rows were left out by the row or size limit
+ */
+truncated: boolean; 
+/**
+ * Time the query took in milliseconds
+ */
+elapsedMs: number }
+/**
+ * What `describe_attached_db` returns.
+ */
+export type DbSchema = { 
+/**
+ * The attachment
+ */
+attachmentId: string; 
+/**
+ * Original file name
+ */
+fileName: string; 
+/**
+ * Tables and views by name
+ */
+tables: DbTable[] }
+/**
+ * A table or view of an attached database.
+ */
+export type DbTable = { 
+/**
+ * Table or view name
+ */
+name: string; 
+/**
+ * `table` or `view`
+ */
+kind: string; 
+/**
+ * `CREATE` statement, which lists the columns
+ */
+sql: string | null; 
+/**
+ * Rows in the table; `None` for views
+ */
+rows: number | null }
+/**
+ * Payload of [`events::DEEP_LINK_ERROR`].
+ */
+export type DeepLinkError = { 
+/**
+ * The link that failed
+ */
+url: string; 
+/**
+ * Human-readable error message
+ */
+message: string }
+/**
+ * A de-identified conversation and what was replaced in it.
+ */
+export type DeidentifiedConversation = { 
+/**
+ * The conversation with values replaced
+ */
+conversation: ConversationWithMessages; 
+/**
+ * Replacements in order of first appearance
+ */
+replacements: Replacement[] }
+/**
+ * What to replace beyond the built-in patterns.
+ */
+export type DeidentifyOptions = { 
+/**
+ * Names of people, companies, or places to replace
+ */
+names: string[] }
+/**
+ * Delivery state of one event to one webhook.
+ */
+export type DeliveryStatus = 
+/**
+ * Not yet accepted; retries may follow
+ */
+"pending" | 
+/**
+ * Accepted with a `2xx`
+ */
+"delivered" | 
+/**
+ * Gave up
+ */
+"failed"
+/**
+ * Payload of [`events::CHAT_DELTA`].
+ */
+export type DeltaPayload = { 
+/**
+ * Stream the text belongs to
+ */
+streamId: string; 
+/**
+ * Batch number, from 1; pass it to `ack_chat_stream`
+ */
+seq: number; 
+/**
+ * Text since the previous batch
+ */
+text: string }
+/**
+ * A language detected in text.
+ */
+export type DetectedLanguage = { 
+/**
+ * ISO 639-3 code, e.g. `deu`
+ */
+code: string; 
+/**
+ * English name, e.g. `German`
+ */
+name: string; 
+/**
+ * Confidence between 0 and 1
+ */
+confidence: number; 
+/**
+ * Whether the text was long and clear enough to trust the result
+ */
+reliable: boolean }
+/**
+ * A written diagnostics bundle.
+ */
+export type DiagnosticsBundle = { 
+/**
+ * Path of the zip file
+ */
+path: string; 
+/**
+ * Size of the zip file in bytes
+ */
+size: number }
+/**
+ * What happened to a span of text.
+ */
+export type DiffKind = 
+/**
+ * In both texts
+ */
+"equal" | 
+/**
+ * Only in the new text
+ */
+"insert" | 
+/**
+ * Only in the old text
+ */
+"delete"
+/**
+ * Which changes to read.
+ */
+export type DiffSelection = { 
+/**
+ * Only staged changes (`git diff --staged`)
+ */
+staged: boolean; 
+/**
+ * A revision or range such as `main..HEAD`, instead of the work tree
+ */
+range: string | null }
+/**
+ * A run of tokens with the same fate.
+ */
+export type DiffSpan = { 
+/**
+ * What happened to the text
+ */
+kind: DiffKind; 
+/**
+ * The text, including its line breaks or spacing
+ */
+text: string }
+/**
+ * A stored article draft.
+ */
+export type Draft = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * The article's NIP-23 `d` tag, stable across edits and devices
+ */
+identifier: string; 
+/**
+ * Article title
+ */
+title: string; 
+/**
+ * Short summary shown in previews
+ */
+summary: string; 
+/**
+ * URL of the header image
+ */
+image: string | null; 
+/**
+ * The article, in Markdown
+ */
+content: string; 
+/**
+ * Hashtags, without `#`
+ */
+hashtags: string[]; 
+/**
+ * ID of the last published version
+ */
+publishedEventId: string | null; 
+/**
+ * First publication time in Unix seconds
+ */
+publishedAt: number | null; 
+/**
+ * Last sync time in Unix milliseconds
+ */
+syncedAt: number | null; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Last update time in Unix milliseconds
+ */
+updatedAt: number }
+/**
+ * Fields for creating or replacing a draft.
+ */
+export type DraftInput = { 
+/**
+ * Article title
+ */
+title?: string; 
+/**
+ * Short summary shown in previews
+ */
+summary?: string; 
+/**
+ * URL of the header image
+ */
+image?: string | null; 
+/**
+ * The article, in Markdown
+ */
+content?: string; 
+/**
+ * Hashtags, with or without `#`
+ */
+hashtags?: string[] }
+/**
+ * How a draft was handed to the mail client.
+ */
+export type DraftMethod = 
+/**
+ * As a `mailto:` URL
+ */
+"mailto" | 
+/**
+ * As an `.eml` file
+ */
+"eml"
+/**
+ * A model's revision, for the writer to accept or discard.
+ */
+export type DraftRevision = { 
+/**
+ * The revised text, title, or summary
+ */
+text: string; 
+/**
+ * The model that wrote it
+ */
+model: string }
+/**
+ * A draft to open.
+ */
+export type EmailDraft = { 
+/**
+ * Recipients
+ */
+to: string[]; 
+/**
+ * Carbon-copy recipients
+ */
+cc: string[]; 
+/**
+ * Subject line
+ */
+subject: string; 
+/**
+ * Plain-text body, usually a model response
+ */
+body: string; 
+/**
+ * Conversations to attach as export files
+ */
+conversationIds: string[] }
+/**
+ * Kind of a replaced value.
+ */
+export type EntityKind = 
+/**
+ * A name from the caller's list
+ */
+"name" | 
+/**
+ * An email address
+ */
+"email" | 
+/**
+ * An IPv4 or IPv6 address
+ */
+"ipAddress" | 
+/**
+ * An API key, token, or private key
+ */
+"secret"
+/**
+ * Payload type of every event, keyed by event name.
+ * 
+ * Never built; it only exists so the generated bindings can type
+ * `listen` calls.
+ */
+export type EventPayloads = { "attachment://staged": StagedPayload; "attachment://rejected": RejectedPayload; "automations://run-completed": AutomationRun; "chat://delta": DeltaPayload; "clipboard://captured": CapturedClip; "clipboard://ask": AskPayload; "conversation://message": StoredMessage; "deep-link://navigate": Navigation; "deep-link://error": DeepLinkError; "external-input://received": null; "feeds://digest": string; "i18n://locale-changed": LocaleStrings; "images://progress": ImageProgress; "instance://forwarded": ForwardedArgs; "job://progress": Job; "network://status": NetworkStatus; "notification://activated": ActivatedPayload; "nostr-digest://digest": string; "outbox://changed": OutboxEntry[]; "outbox://sent": SentPayload; "prompt-tests://progress": PromptTestProgress; "quick-capture://submitted": SubmittedPayload; "rate-limit://queued": QueuedPayload; "reading-list://updated": ReadingItem; "routing-policy://blocked": BlockedPayload; "scheduler://run-completed": RunCompletedPayload; "settings://changed": Settings; "shortcut://push-to-talk": boolean; "budget://exceeded": ExceededPayload; "app://ready": ReadyPayload; "tray://new-chat": null; "tray://sync-paused": boolean; "tray://generating": boolean; "updater://available": UpdateInfo; "updater://progress": UpdateProgress; "updater://ready": UpdateInfo; "workflow://step": StepEventPayload; "workflow://run-completed": WorkflowRun; "generation://delta": JsonValue }
+/**
+ * One declared event, as listed by `get_event_schema`.
+ */
+export type EventSchema = { 
+/**
+ * Name the event is emitted under
+ */
+name: string; 
+/**
+ * Rust type of the payload
+ */
+payload: string; 
+/**
+ * What the event announces
+ */
+description: string }
+/**
+ * Payload of [`events::BUDGET_EXCEEDED`].
+ */
+export type ExceededPayload = { 
+/**
+ * Cap that would be broken
+ */
+period: SpendPeriod; 
+/**
+ * The cap
+ */
+capUsd: number; 
+/**
+ * Spent so far in the period
+ */
+spentUsd: number; 
+/**
+ * Projected cost of the refused request
+ */
+projectedUsd: number; 
+/**
+ * Model the request was for
+ */
+model: string; 
+/**
+ * Conversation the request was for
+ */
+conversationId: string | null }
+/**
+ * A saved experiment.
+ */
+export type Experiment = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * First variant
+ */
+variantA: VariantConfig; 
+/**
+ * Second variant
+ */
+variantB: VariantConfig; 
+/**
+ * Whether new conversations are enrolled
+ */
+active: boolean; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * Fields for creating or replacing an experiment.
+ */
+export type ExperimentInput = { 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * First variant
+ */
+variantA: VariantConfig; 
+/**
+ * Second variant
+ */
+variantB: VariantConfig }
+/**
+ * Result of [`get_experiment_stats`].
+ */
+export type ExperimentStats = { 
+/**
+ * The experiment
+ */
+experimentId: string; 
+/**
+ * Totals for A and B
+ */
+variants: [VariantStats, VariantStats]; 
+/**
+ * Two-proportion z-score of B's approval against A's
+ */
+zScore: number | null; 
+/**
+ * Two-sided p-value of the z-score
+ */
+pValue: number | null; 
+/**
+ * Whether the difference is significant at the 95% level
+ */
+significant: boolean }
+/**
+ * File formats a conversation exports to.
+ */
+export type ExportFormat = 
+/**
+ * The JSON export file, which can be imported again
+ */
+"json" | 
+/**
+ * A standalone HTML page for reading, sharing or printing to PDF
+ */
+"html"
+/**
+ * Text received from another app.
+ */
+export type ExternalInput = { 
+/**
+ * The selected text
+ */
+text: string; 
+/**
+ * The action requested with it; the user picks one when absent
+ */
+action: QuickAction | null; 
+/**
+ * Where it came from
+ */
+source: InputSource }
+/**
+ * A subscribed feed.
+ */
+export type Feed = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Feed document URL
+ */
+url: string; 
+/**
+ * Title from the feed
+ */
+title: string | null; 
+/**
+ * Summarization prompt for digests; the default is used when `None`
+ */
+prompt: string | null; 
+/**
+ * Whether the feed is polled
+ */
+enabled: boolean; 
+/**
+ * Last poll in Unix milliseconds
+ */
+lastPolledAt: number | null; 
+/**
+ * Error of the last poll, if it failed
+ */
+lastError: string | null; 
+/**
+ * Subscription time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * An entry of a feed.
+ */
+export type FeedItem = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Feed the entry belongs to
+ */
+feedId: string; 
+/**
+ * Entry GUID from the feed
+ */
+guid: string; 
+/**
+ * Entry title
+ */
+title: string; 
+/**
+ * Link to the entry
+ */
+link: string | null; 
+/**
+ * Plain-text summary
+ */
+summary: string | null; 
+/**
+ * Publication time in Unix milliseconds
+ */
+publishedAt: number | null; 
+/**
+ * When the entry was first seen, in Unix milliseconds
+ */
+fetchedAt: number; 
+/**
+ * When the entry was included in a digest, in Unix milliseconds
+ */
+digestedAt: number | null }
+/**
+ * Polling and digest preferences.
+ */
+export type FeedSettings = { 
+/**
+ * Minutes between polls of each feed
+ */
+pollIntervalMinutes: number; 
+/**
+ * Whether a digest is generated every day
+ */
+dailyDigest: boolean; 
+/**
+ * Local hour (0-23) after which the daily digest is generated
+ */
+digestHour: number; 
+/**
+ * Model for digests; defaults to the standard tier
+ */
+model: string | null }
+/**
+ * Fields that can be changed on a subscription.
+ */
+export type FeedUpdate = { 
+/**
+ * Summarization prompt; empty restores the default
+ */
+prompt?: string | null; 
+/**
+ * Whether the feed is polled
+ */
+enabled?: boolean | null }
+/**
+ * Stored feedback on one answer.
+ */
+export type Feedback = { 
+/**
+ * Rated assistant message
+ */
+messageId: string; 
+/**
+ * Its conversation
+ */
+conversationId: string; 
+/**
+ * The rating
+ */
+rating: Rating; 
+/**
+ * Optional note on what was good or bad
+ */
+comment: string | null; 
+/**
+ * Model that wrote the answer
+ */
+model: string | null; 
+/**
+ * System prompt of the conversation when rated
+ */
+systemPrompt: string | null; 
+/**
+ * Messages before the answer, system prompt excluded
+ */
+context: ChatMessage[]; 
+/**
+ * The rated answer
+ */
+completion: string; 
+/**
+ * Last rating time in Unix milliseconds
+ */
+ratedAt: number }
+/**
+ * Dataset layouts of [`export_feedback`].
+ */
+export type FeedbackFormat = 
+/**
+ * Thumbs-up answers as chat fine-tuning records
+ */
+"messages" | 
+/**
+ * Every rated answer with a boolean label
+ */
+"kto"
+/**
+ * How a filter compares a column with its value.
+ */
+export type FilterOp = 
+/**
+ * Equal
+ */
+"eq" | 
+/**
+ * Not equal
+ */
+"ne" | 
+/**
+ * Less than
+ */
+"lt" | 
+/**
+ * Less than or equal
+ */
+"le" | 
+/**
+ * Greater than
+ */
+"gt" | 
+/**
+ * Greater than or equal
+ */
+"ge" | 
+/**
+ * Contains the value as text, ignoring case
+ */
+"contains"
+/**
+ * A question and its answer.
+ */
+export type Flashcard = { 
+/**
+ * The question
+ */
+front: string; 
+/**
+ * The answer
+ */
+back: string; 
+/**
+ * Topic tags, without spaces
+ */
+tags?: string[] }
+/**
+ * A written deck.
+ */
+export type FlashcardExport = { 
+/**
+ * The file written
+ */
+path: string; 
+/**
+ * Its format
+ */
+format: FlashcardFormat; 
+/**
+ * Cards in the deck
+ */
+count: number }
+/**
+ * A deck to export.
+ */
+export type FlashcardExportRequest = { 
+/**
+ * The cards
+ */
+cards: Flashcard[]; 
+/**
+ * Deck name; `::` separates subdecks
+ */
+deck: string; 
+/**
+ * File to write
+ */
+path: string; 
+/**
+ * File format (default: `.apkg`)
+ */
+format?: FlashcardFormat }
+/**
+ * File formats a deck exports to.
+ */
+export type FlashcardFormat = 
+/**
+ * An Anki package (`.apkg`)
+ */
+"apkg" | 
+/**
+ * Tab-separated text (`.txt`)
+ */
+"tsv"
+/**
+ * Where the cards come from.
+ */
+export type FlashcardSource = 
+/**
+ * A conversation's messages
+ */
+{ kind: "conversation"; conversationId: string } | 
+/**
+ * A Markdown or plain text attachment, such as a knowledge base
+ * document
+ */
+{ kind: "document"; attachmentId: string }
+/**
+ * Arguments of a launch, classified.
+ */
+export type ForwardedArgs = { 
+/**
+ * File paths, made absolute against the launching working directory
+ */
+files: string[]; 
+/**
+ * Flags such as `--minimized`, passed through verbatim
+ */
+flags: string[] }
+/**
+ * The model's answer and what it was based on.
+ */
+export type GitAssistResult = { 
+/**
+ * Commit message or review
+ */
+text: string; 
+/**
+ * Model that answered
+ */
+model: string; 
+/**
+ * Files included in the diff
+ */
+files: string[]; 
+/**
+ * Binary files left out
+ */
+binaryFiles: string[]; 
+/**
+ * Files left out because of the size cap
+ */
+omittedFiles: string[] }
+/**
+ * Granted repositories.
+ */
+export type GitSettings = { 
+/**
+ * Work tree roots the app may read
+ */
+repositories: string[] }
+/**
+ * What to ask the model for.
+ */
+export type GitTask = 
+/**
+ * A commit message for the changes
+ */
+"commitMessage" | 
+/**
+ * A review of the changes
+ */
+"review"
+/**
+ * Unit of comparison.
+ */
+export type Granularity = 
+/**
+ * Words with their trailing whitespace
+ */
+"word" | 
+/**
+ * Whole lines
+ */
+"line"
+/**
+ * A stored generation.
+ */
+export type ImageGeneration = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Conversation the images belong to
+ */
+conversationId: string; 
+/**
+ * Assistant message recording the generation
+ */
+messageId: string; 
+/**
+ * Parameters used
+ */
+params: ImageParams; 
+/**
+ * Model that served the request
+ */
+model: string; 
+/**
+ * The generated images
+ */
+attachmentIds: string[]; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * One edit, applied in list order.
+ */
+export type ImageOperation = 
+/**
+ * Keep only a rectangle
+ */
+{ op: "crop"; rect: Rect } | 
+/**
+ * Scale; with one dimension the other keeps the aspect ratio
+ */
+{ op: "resize"; width: number | null; height: number | null } | 
+/**
+ * Rotate clockwise by a multiple of 90 degrees
+ */
+{ op: "rotate"; degrees: number } | 
+/**
+ * Paint solid boxes over regions
+ */
+{ op: "redact"; regions: Rect[]; color?: string | null }
+/**
+ * Parameters of an image generation, stored with its message.
+ */
+export type ImageParams = { 
+/**
+ * What to draw
+ */
+prompt: string; 
+/**
+ * Service to generate with
+ */
+provider: ImageProvider; 
+/**
+ * Model; the provider's default when `None`
+ */
+model?: string | null; 
+/**
+ * Size as `WIDTHxHEIGHT`, e.g. `1024x1024`
+ */
+size?: string | null; 
+/**
+ * Number of images, 1 to 4 (default: 1)
+ */
+count?: number | null; 
+/**
+ * What to avoid, for providers that support it
+ */
+negativePrompt?: string | null; 
+/**
+ * Seed, for providers that support it
+ */
+seed?: number | null; 
+/**
+ * Sampling steps, for the local provider
+ */
+steps?: number | null; 
+/**
+ * Sampler name, for the local provider
+ */
+sampler?: string | null }
+/**
+ * Payload of [`events::IMAGES_PROGRESS`].
+ */
+export type ImageProgress = { 
+/**
+ * Conversation the generation is for
+ */
+conversationId: string; 
+/**
+ * Current stage
+ */
+stage: ImageStage; 
+/**
+ * Images stored so far
+ */
+completed: number; 
+/**
+ * Images requested
+ */
+total: number; 
+/**
+ * Newest stored image, for `received`
+ */
+attachment: Attachment | null; 
+/**
+ * Error message, for `failed`
+ */
+error: string | null; 
+/**
+ * Jobs ahead in the local queue, for `queued`
+ */
+queuePosition: number | null; 
+/**
+ * Rendered fraction from 0 to 1, for `rendering` when known
+ */
+fraction: number | null }
+/**
+ * Services that generate images.
+ */
+export type ImageProvider = 
+/**
+ * OpenRouter models with image output
+ */
+"openrouter" | 
+/**
+ * OpenAI Images API
+ */
+"openai" | 
+/**
+ * Stability AI Stable Image API
+ */
+"stability" | 
+/**
+ * Local Stable Diffusion server
+ */
+"local"
+/**
+ * Stage of a generation, reported by [`events::IMAGES_PROGRESS`].
+ */
+export type ImageStage = 
+/**
+ * The request is being sent
+ */
+"started" | 
+/**
+ * Waiting in the local server's queue
+ */
+"queued" | 
+/**
+ * The local server is rendering
+ */
+"rendering" | 
+/**
+ * An image was received and stored
+ */
+"received" | 
+/**
+ * All images are stored
+ */
+"completed" | 
+/**
+ * The generation failed
+ */
+"failed"
+/**
+ * An image attachment ready to send to a provider.
+ */
+export type ImageUpload = { 
+/**
+ * Attachment the image came from
+ */
+attachmentId: string; 
+/**
+ * MIME type
+ */
+mimeType: string; 
+/**
+ * Image as a `data:` URL
+ */
+dataUrl: string; 
+/**
+ * Whether metadata was removed
+ */
+metadataStripped: boolean }
+/**
+ * What an import changes.
+ */
+export type ImportPlan = { 
+/**
+ * Documents that differ from the current ones
+ */
+changes: SettingsChange[]; 
+/**
+ * Keys in the file that this version doesn't recognize and won't import
+ */
+skipped: string[] }
+/**
+ * Where text from another app came from.
+ */
+export type InputSource = 
+/**
+ * The macOS Services menu
+ */
+"services" | 
+/**
+ * A `gibber://input` link opened some other way
+ */
+"link"
+/**
+ * A background job.
+ */
+export type Job = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * What kind of work this is, e.g. `export.dataset`
+ */
+kind: string; 
+/**
+ * Description for the UI
+ */
+label: string; 
+/**
+ * Current status
+ */
+status: JobStatus; 
+/**
+ * Percent done, 0 to 100
+ */
+progress: number; 
+/**
+ * What the job is doing now, or what it did
+ */
+message: string | null; 
+/**
+ * Why the job failed
+ */
+error: string | null; 
+/**
+ * Start time (milliseconds since the Unix epoch)
+ */
+createdAt: number; 
+/**
+ * End time (milliseconds since the Unix epoch)
+ */
+finishedAt: number | null }
+/**
+ * Where a job is in its life.
+ */
+export type JobStatus = 
+/**
+ * Still working
+ */
+"running" | 
+/**
+ * Finished successfully
+ */
+"completed" | 
+/**
+ * Stopped with an error
+ */
+"failed" | 
+/**
+ * Stopped at the user's request
+ */
+"cancelled" | 
+/**
+ * Still running when the app quit
+ */
+"interrupted"
+export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>
+/**
+ * Server API flavour.
+ */
+export type LocalBackend = 
+/**
+ * AUTOMATIC1111 web UI API
+ */
+"automatic1111" | 
+/**
+ * ComfyUI API
+ */
+"comfyui"
+/**
+ * Models and samplers the server offers.
+ */
+export type LocalModels = { 
+/**
+ * Checkpoint names, usable as the image `model`
+ */
+models: string[]; 
+/**
+ * Sampler names, usable as the image `sampler`
+ */
+samplers: string[] }
+/**
+ * A catalog the user can choose.
+ */
+export type LocaleInfo = { 
+/**
+ * BCP 47 tag
+ */
+tag: string; 
+/**
+ * Name in its own language
+ */
+name: string }
+/**
+ * Every string of a locale, for the UI.
+ */
+export type LocaleStrings = { 
+/**
+ * Catalog in use
+ */
+locale: string; 
+/**
+ * The `language` setting: a tag or `system`
+ */
+language: string; 
+/**
+ * Locale reported by the OS, if any
+ */
+systemLocale: string | null; 
+/**
+ * Catalogs that can be chosen
+ */
+available: LocaleInfo[]; 
+/**
+ * Messages by ID; variables are left as `{$name}` for the UI to fill
+ */
+strings: Partial<{ [key in string]: string }> }
+/**
+ * A coarse location.
+ */
+export type Location = { 
+/**
+ * Town or city
+ */
+name: string; 
+/**
+ * State or region
+ */
+region: string | null; 
+/**
+ * Country name
+ */
+country: string | null; 
+/**
+ * Latitude, rounded to 0.1°
+ */
+latitude: number; 
+/**
+ * Longitude, rounded to 0.1°
+ */
+longitude: number; 
+/**
+ * Where the location came from
+ */
+source: LocationSource }
+/**
+ * What `get_location_context` returns.
+ */
+export type LocationContext = { 
+/**
+ * The location told to models
+ */
+location: Location; 
+/**
+ * The line added to the system prompt
+ */
+prompt: string }
+/**
+ * Location preferences.
+ */
+export type LocationSettings = { 
+/**
+ * Share the approximate location with models and the weather tool
+ */
+enabled: boolean; 
+/**
+ * Town or city to use; approximated from the IP address when `None`
+ */
+place: string | null }
+/**
+ * Where a location came from.
+ */
+export type LocationSource = 
+/**
+ * The place set in the settings
+ */
+"manual" | 
+/**
+ * The public IP address
+ */
+"ip" | 
+/**
+ * A place named in a request
+ */
+"named"
+/**
+ * One log entry.
+ */
+export type LogEntry = { 
+/**
+ * RFC 3339 timestamp
+ */
+timestamp: string; 
+/**
+ * Severity
+ */
+level: LogLevel; 
+/**
+ * Rust module path that logged the entry
+ */
+target: string; 
+/**
+ * Log message
+ */
+message: string; 
+/**
+ * Structured fields besides the message
+ */
+fields: Partial<{ [key in string]: JsonValue }> }
+/**
+ * Severity of a log entry.
+ */
+export type LogLevel = 
+/**
+ * Very verbose diagnostics
+ */
+"trace" | 
+/**
+ * Diagnostics
+ */
+"debug" | 
+/**
+ * Normal operation
+ */
+"info" | 
+/**
+ * Something went wrong but the app carried on
+ */
+"warn" | 
+/**
+ * An operation failed
+ */
+"error"
+/**
+ * Filter for `query_logs`.
+ */
+export type LogQuery = { 
+/**
+ * Only entries at this severity or above
+ */
+level: LogLevel | null; 
+/**
+ * Only entries from this module (e.g. `"scheduler"`)
+ */
+module: string | null; 
+/**
+ * Only entries whose message contains this text, ignoring case
+ */
+search: string | null; 
+/**
+ * Maximum number of entries (default 200, at most 5000)
+ */
+limit: number | null }
+/**
+ * Upload protocol of a media server.
+ */
+export type MediaProtocol = 
+/**
+ * NIP-96 HTTP file storage
+ */
+"nip96" | 
+/**
+ * Blossom blob storage
+ */
+"blossom"
+/**
+ * A media server.
+ */
+export type MediaServer = { 
+/**
+ * Base URL, e.g. `https://nostr.build`
+ */
+url: string; 
+/**
+ * How files are uploaded to it
+ */
+protocol: MediaProtocol }
+/**
+ * Media preferences.
+ */
+export type MediaSettings = { 
+/**
+ * Servers to upload to, in order of preference
+ */
+servers: MediaServer[] }
+/**
+ * A diff with word counts for a summary line.
+ */
+export type MessageDiff = { 
+/**
+ * Spans in reading order
+ */
+spans: DiffSpan[]; 
+/**
+ * Words only in the new text
+ */
+insertedWords: number; 
+/**
+ * Words only in the old text
+ */
+deletedWords: number; 
+/**
+ * Words in both
+ */
+unchangedWords: number }
+/**
+ * A pause between two consecutive messages.
+ */
+export type MessageGap = { 
+/**
+ * Message before the pause
+ */
+afterMessageId: string; 
+/**
+ * Message after the pause
+ */
+beforeMessageId: string; 
+/**
+ * Length of the pause in milliseconds
+ */
+gapMs: number }
+/**
+ * A page of a conversation's messages, from [`get_messages`].
+ */
+export type MessagePage = { 
+/**
+ * Messages in chronological order
+ */
+messages: StoredMessage[]; 
+/**
+ * Cursor for the page of older messages; `None` on the first page
+ */
+nextCursor: string | null; 
+/**
+ * Messages in the whole conversation
+ */
+total: number }
+/**
+ * Role of a message in the conversation.
+ */
+export type MessageRole = 
+/**
+ * System instructions
+ */
+"system" | 
+/**
+ * Message written by the user
+ */
+"user" | 
+/**
+ * Message generated by the model
+ */
+"assistant"
+/**
+ * Summary of a model's samples through one provider.
+ */
+export type ModelMetrics = { 
+/**
+ * Model ID
+ */
+model: string; 
+/**
+ * Upstream provider, if reported
+ */
+provider: string | null; 
+/**
+ * Requests sampled
+ */
+requests: number; 
+/**
+ * Requests that failed because of the route
+ */
+errors: number; 
+/**
+ * `errors / requests`
+ */
+errorRate: number; 
+/**
+ * Mean time to first token of streamed requests
+ */
+avgTtftMs: number | null; 
+/**
+ * Mean request duration
+ */
+avgDurationMs: number; 
+/**
+ * Mean generation speed
+ */
+avgTokensPerSecond: number | null; 
+/**
+ * Time of the last sample in Unix milliseconds
+ */
+lastSeenAt: number }
+/**
+ * Use of one model within a conversation.
+ */
+export type ModelUsage = { 
+/**
+ * Model ID
+ */
+model: string; 
+/**
+ * Messages it generated
+ */
+messages: number }
+/**
+ * Navigation instruction for the frontend.
+ */
+export type Navigation = 
+/**
+ * Show a new conversation; answer it if a prompt was given
+ */
+{ kind: "newConversation"; conversationId: string | null; prompt: string; model: string | null } | 
+/**
+ * Show an existing conversation
+ */
+{ kind: "openConversation"; conversationId: string } | 
+/**
+ * Start an import of the given URL
+ */
+{ kind: "import"; url: string } | 
+/**
+ * Show the reading list with the saved item
+ */
+{ kind: "readingList"; itemId: string }
+/**
+ * A recorded outbound request.
+ */
+export type NetworkRequest = { 
+/**
+ * Start time in Unix milliseconds
+ */
+startedAt: number; 
+/**
+ * Client the request went through
+ */
+service: Service; 
+/**
+ * What the request was for, e.g. `chat.completion`
+ */
+purpose: string; 
+/**
+ * HTTP method
+ */
+method: string; 
+/**
+ * Host name contacted
+ */
+host: string; 
+/**
+ * HTTP status, if a response arrived
+ */
+status: number | null; 
+/**
+ * Request body size
+ */
+bytesSent: number; 
+/**
+ * Response size announced by the server
+ */
+bytesReceived: number | null; 
+/**
+ * Time until the response headers arrived
+ */
+durationMs: number; 
+/**
+ * Transport error, if the request failed
+ */
+error: string | null }
+/**
+ * Network preferences.
+ */
+export type NetworkSettings = { 
+/**
+ * Proxy for every service without an override
+ */
+proxy: ProxyConfig; 
+/**
+ * Per-service proxies
+ */
+overrides: Partial<{ [key in Service]: ProxyConfig }>; 
+/**
+ * Paths of PEM files with extra trusted CA certificates
+ */
+caCertificates: string[] }
+/**
+ * Payload of [`events::NETWORK_STATUS`].
+ */
+export type NetworkStatus = { 
+/**
+ * Whether OpenRouter is reachable
+ */
+online: boolean }
+/**
+ * Fields for creating a conversation.
+ */
+export type NewConversation = { 
+/**
+ * Display title
+ */
+title: string; 
+/**
+ * Default model
+ */
+model?: string | null; 
+/**
+ * System prompt
+ */
+systemPrompt?: string | null; 
+/**
+ * Origin of the conversation; defaults to "chat"
+ */
+source?: string | null }
+/**
+ * Fields for appending a message.
+ */
+export type NewMessage = { 
+/**
+ * The role of the message sender
+ */
+role: MessageRole; 
+/**
+ * The message content
+ */
+content: string; 
+/**
+ * Model that generated the message
+ */
+model?: string | null; 
+/**
+ * Token usage for the message
+ */
+usage?: TokenUsage | null }
+/**
+ * Digest preferences.
+ */
+export type NostrDigestSettings = { 
+/**
+ * Public key whose follows are digested, as hex or `npub`
+ */
+pubkey: string; 
+/**
+ * Relays to read from
+ */
+relays: string[]; 
+/**
+ * Whether a digest is generated every day
+ */
+dailyDigest: boolean; 
+/**
+ * Local hour (0-23) after which the daily digest is generated
+ */
+digestHour: number; 
+/**
+ * Model for digests; defaults to the standard tier
+ */
+model: string | null; 
+/**
+ * Instructions replacing the default digest prompt
+ */
+prompt: string | null }
+/**
+ * The kind of event a notification reports.
+ */
+export type NotificationKind = 
+/**
+ * A chat generation finished
+ */
+"generation" | 
+/**
+ * A scheduled task ran
+ */
+"scheduledTask" | 
+/**
+ * A workflow run finished
+ */
+"workflow" | 
+/**
+ * New clipboard content can be asked about
+ */
+"clipboard"
+/**
+ * Per-event notification preferences.
+ */
+export type NotificationSettings = { 
+/**
+ * Master switch for all notifications
+ */
+enabled: boolean; 
+/**
+ * Only notify while no app window is focused
+ */
+onlyWhenUnfocused: boolean; 
+/**
+ * Notify when a chat generation finishes
+ */
+generation: boolean; 
+/**
+ * Notify when a scheduled task runs
+ */
+scheduledTask: boolean; 
+/**
+ * Notify when a workflow run finishes
+ */
+workflow: boolean; 
+/**
+ * Offer to ask about newly copied content
+ */
+clipboard: boolean }
+/**
+ * Result of [`open_email_draft`].
+ */
+export type OpenedDraft = { 
+/**
+ * How the draft was opened
+ */
+method: DraftMethod; 
+/**
+ * The written `.eml` file, for [`DraftMethod::Eml`]
+ */
+path: string | null }
+/**
+ * Whether pages of an origin may be sent to Gibber.
+ */
+export type OriginAccess = 
+/**
+ * Pages may be sent
+ */
+"allowed" | 
+/**
+ * Pages are refused
+ */
+"denied" | 
+/**
+ * Seen but not yet decided; refused
+ */
+"pending"
+/**
+ * A queued request for a reply.
+ */
+export type OutboxEntry = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Conversation to answer in
+ */
+conversationId: string; 
+/**
+ * The user message awaiting a reply
+ */
+messageId: string; 
+/**
+ * Model override; the conversation's model otherwise
+ */
+model: string | null; 
+/**
+ * Delivery state
+ */
+status: OutboxStatus; 
+/**
+ * Delivery attempts so far
+ */
+attempts: number; 
+/**
+ * Error of the last attempt
+ */
+lastError: string | null; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * Delivery state of a queued request.
+ */
+export type OutboxStatus = 
+/**
+ * Waiting for connectivity
+ */
+"queued" | 
+/**
+ * Being sent now
+ */
+"sending" | 
+/**
+ * Rejected by the provider; needs a retry or discard
+ */
+"failed"
+/**
+ * An entry of the command palette.
+ */
+export type PaletteItem = { 
+/**
+ * What the item refers to
+ */
+kind: PaletteKind; 
+/**
+ * Action name or record ID
+ */
+id: string; 
+/**
+ * Text shown and matched
+ */
+title: string; 
+/**
+ * Secondary text, such as a preset's persona
+ */
+subtitle: string | null; 
+/**
+ * Character indices of `title` matched by the query, for highlighting
+ */
+matches: number[] }
+/**
+ * What a palette item refers to.
+ */
+export type PaletteKind = 
+/**
+ * An app action; the ID names it
+ */
+"action" | 
+/**
+ * A new-chat preset; the ID is the preset's
+ */
+"preset" | 
+/**
+ * A conversation; the ID is the conversation's
+ */
+"conversation"
+/**
+ * What a paste was recognized as.
+ */
+export type PasteKind = 
+/**
+ * Prose or anything unrecognized; left as is
+ */
+"plain" | 
+/**
+ * Source code
+ */
+"code" | 
+/**
+ * A JSON document
+ */
+"json" | 
+/**
+ * Comma-, tab-, or semicolon-separated values
+ */
+"csv" | 
+/**
+ * Log output
+ */
+"log"
+/**
+ * An installed plugin.
+ */
+export type PluginInfo = { 
+/**
+ * Reverse-domain identifier from the manifest
+ */
+id: string; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * Version from the manifest
+ */
+version: string; 
+/**
+ * Short description
+ */
+description: string; 
+/**
+ * Capabilities the manifest asks for
+ */
+requested: Capabilities; 
+/**
+ * Capabilities the user granted
+ */
+granted: Capabilities; 
+/**
+ * Tools the plugin offers
+ */
+tools: PluginTool[]; 
+/**
+ * Whether its tools can be called
+ */
+enabled: boolean; 
+/**
+ * Install or update time in Unix milliseconds
+ */
+installedAt: number }
+/**
+ * A tool offered by a plugin.
+ */
+export type PluginTool = { 
+/**
+ * Name, unique within the plugin
+ */
+name: string; 
+/**
+ * What the tool does
+ */
+description: string; 
+/**
+ * JSON schema of the input
+ */
+inputSchema: string }
+/**
+ * What `prepare_paste` returns.
+ */
+export type PreparedPaste = { 
+/**
+ * What the paste was recognized as
+ */
+kind: PasteKind; 
+/**
+ * Language of the fence, if one was added
+ */
+language: string | null; 
+/**
+ * Text to insert into the prompt
+ */
+text: string; 
+/**
+ * Whether part of the paste was cut to fit the budget
+ */
+truncated: boolean; 
+/**
+ * Estimated tokens of `text`
+ */
+estimatedTokens: number }
+/**
+ * A saved preset.
+ */
+export type Preset = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * System prompt describing the persona
+ */
+persona: string | null; 
+/**
+ * Model ID; the default model when `None`
+ */
+model: string | null; 
+/**
+ * Knowledge base the conversation draws on
+ */
+knowledgeBase: string | null; 
+/**
+ * Tools the model may call
+ */
+tools: string[]; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Last update time in Unix milliseconds
+ */
+updatedAt: number }
+/**
+ * A conversation started from a preset.
+ */
+export type PresetConversation = { 
+/**
+ * The new conversation
+ */
+conversation: Conversation; 
+/**
+ * The preset it was started from
+ */
+preset: Preset }
+/**
+ * Fields for creating or replacing a preset.
+ */
+export type PresetInput = { 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * System prompt describing the persona
+ */
+persona?: string | null; 
+/**
+ * Model ID
+ */
+model?: string | null; 
+/**
+ * Knowledge base the conversation draws on
+ */
+knowledgeBase?: string | null; 
+/**
+ * Tools the model may call
+ */
+tools?: string[] }
+/**
+ * A saved prompt test.
+ */
+export type PromptTest = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * System prompt, if any
+ */
+system: string | null; 
+/**
+ * User prompt
+ */
+prompt: string; 
+/**
+ * Conditions the answer must meet
+ */
+criteria: Criterion[]; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Last update time in Unix milliseconds
+ */
+updatedAt: number }
+/**
+ * Fields for creating or replacing a prompt test.
+ */
+export type PromptTestInput = { 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * System prompt, if any
+ */
+system?: string | null; 
+/**
+ * User prompt
+ */
+prompt: string; 
+/**
+ * Conditions the answer must meet
+ */
+criteria: Criterion[] }
+/**
+ * Payload of [`events::PROMPT_TESTS_PROGRESS`].
+ */
+export type PromptTestProgress = { 
+/**
+ * Run in progress
+ */
+runId: string; 
+/**
+ * Test and model pairs finished
+ */
+completed: number; 
+/**
+ * Test and model pairs in the run
+ */
+total: number }
+/**
+ * One test answered by one model.
+ */
+export type PromptTestResult = { 
+/**
+ * Test that was run
+ */
+testId: string; 
+/**
+ * Model that answered
+ */
+model: string; 
+/**
+ * The answer, if the request succeeded
+ */
+output: string | null; 
+/**
+ * Whether every criterion passed
+ */
+passed: boolean; 
+/**
+ * Why criteria failed
+ */
+failures: string[]; 
+/**
+ * Request error, if the model couldn't be reached
+ */
+error: string | null; 
+/**
+ * Request duration
+ */
+durationMs: number; 
+/**
+ * Outcome in the previous run with this test and model
+ */
+previouslyPassed: boolean | null; 
+/**
+ * Passed last time, failed now
+ */
+regression: boolean; 
+/**
+ * Line diff against the previous answer, when it changed
+ */
+diff: DiffSpan[] | null }
+/**
+ * A batch of prompt tests.
+ */
+export type PromptTestRun = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Models the tests were sent to
+ */
+models: string[]; 
+/**
+ * Results that passed
+ */
+passed: number; 
+/**
+ * Results that failed
+ */
+failed: number; 
+/**
+ * Start time in Unix milliseconds
+ */
+startedAt: number; 
+/**
+ * End time in Unix milliseconds, once finished
+ */
+finishedAt: number | null; 
+/**
+ * Per-test results; empty in run listings
+ */
+results: PromptTestResult[] }
+/**
+ * How to reach the network.
+ */
+export type ProxyConfig = 
+/**
+ * Use the OS proxy settings and `HTTP(S)_PROXY` variables
+ */
+{ mode: "system" } | 
+/**
+ * Connect directly, ignoring any system proxy
+ */
+{ mode: "direct" } | 
+/**
+ * Route through a proxy URL
+ */
+{ mode: "manual"; url: string; noProxy?: string | null }
+/**
+ * A published article.
+ */
+export type PublishedArticle = { 
+/**
+ * The draft, with its publication recorded
+ */
+draft: Draft; 
+/**
+ * ID of the signed event
+ */
+eventId: string; 
+/**
+ * NIP-19 `naddr` of the article, for links that survive edits
+ */
+naddr: string; 
+/**
+ * What each relay answered
+ */
+relays: RelayResult[] }
+/**
+ * Payload of [`events::RATE_LIMIT_QUEUED`].
+ */
+export type QueuedPayload = { 
+/**
+ * Provider name
+ */
+provider: string; 
+/**
+ * How long the request waits
+ */
+waitMs: number }
+/**
+ * What to do with text from another app.
+ */
+export type QuickAction = 
+/**
+ * Start a conversation with the text as the prompt
+ */
+"ask" | 
+/**
+ * Correct spelling, grammar, and punctuation
+ */
+"fixGrammar" | 
+/**
+ * Plainer words and shorter sentences
+ */
+"simplify" | 
+/**
+ * A formal, professional tone
+ */
+"formalize" | 
+/**
+ * Fewer words, same points
+ */
+"shorten" | 
+/**
+ * Translate into the user's language
+ */
+"translate"
+/**
+ * A quick action to run on text from another app.
+ */
+export type QuickActionRequest = { 
+/**
+ * The text, possibly edited in the quick-capture window
+ */
+text: string; 
+/**
+ * What to do with it
+ */
+action: QuickAction; 
+/**
+ * Language to translate into; the system language when absent
+ */
+target?: string | null; 
+/**
+ * Model to use instead of the default
+ */
+model?: string | null; 
+/**
+ * Whether to copy a rewrite or translation to the clipboard
+ */
+copy?: boolean }
+/**
+ * The outcome of a quick action.
+ */
+export type QuickActionResult = 
+/**
+ * The rewritten text
+ */
+({ kind: "rewrite" } & Rewrite) | 
+/**
+ * The translated text
+ */
+{ kind: "translation"; translation: Translation; copied: boolean } | 
+/**
+ * A conversation was started in the main window
+ */
+{ kind: "conversation"; conversationId: string }
+/**
+ * A ranked item.
+ */
+export type RankedItem = { 
+/**
+ * Model or preset ID
+ */
+id: string; 
+/**
+ * Times used
+ */
+uses: number; 
+/**
+ * Last use in Unix milliseconds, if ever used
+ */
+lastUsedAt: number | null; 
+/**
+ * Whether the user marked it a favorite
+ */
+favorite: boolean; 
+/**
+ * Frecency score the list is ordered by
+ */
+score: number }
+/**
+ * A provider's budget.
+ */
+export type RateLimit = { 
+/**
+ * Sustained requests per minute
+ */
+requestsPerMinute: number; 
+/**
+ * Requests that may be sent at once after a quiet period
+ */
+burst: number }
+/**
+ * Rate limits by provider name.
+ */
+export type RateLimitSettings = { 
+/**
+ * Budget per provider; providers without one aren't limited
+ */
+limits: Partial<{ [key in string]: RateLimit }> }
+/**
+ * What is left of a provider's budget.
+ */
+export type RateLimitState = { 
+/**
+ * Provider name
+ */
+provider: string; 
+/**
+ * Configured budget
+ */
+limit: RateLimit; 
+/**
+ * Requests that can be sent right now
+ */
+available: number; 
+/**
+ * Requests waiting for a token
+ */
+queued: number; 
+/**
+ * Time until the next token, or until a provider-imposed pause ends
+ */
+waitMs: number }
+/**
+ * A thumbs-up or thumbs-down.
+ */
+export type Rating = 
+/**
+ * A good answer
+ */
+"up" | 
+/**
+ * A bad answer
+ */
+"down"
+/**
+ * A saved URL.
+ */
+export type ReadingItem = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * The page URL
+ */
+url: string; 
+/**
+ * Page title, once fetched
+ */
+title: string | null; 
+/**
+ * How the URL arrived: "manual", "deep-link", or "clipboard"
+ */
+source: string; 
+/**
+ * Queue status
+ */
+status: ReadingStatus; 
+/**
+ * Model summary
+ */
+summary: string | null; 
+/**
+ * Extracted page text
+ */
+content: string | null; 
+/**
+ * Last failure
+ */
+error: string | null; 
+/**
+ * Fetch and summary attempts so far
+ */
+attempts: number; 
+/**
+ * When the URL was added, in Unix milliseconds
+ */
+addedAt: number; 
+/**
+ * When the summary was stored, in Unix milliseconds
+ */
+summarizedAt: number | null }
+/**
+ * Where an item is in the queue.
+ */
+export type ReadingStatus = 
+/**
+ * Waiting to be fetched and summarized
+ */
+"queued" | 
+/**
+ * Summarized
+ */
+"summarized" | 
+/**
+ * Gave up after repeated failures
+ */
+"failed"
+/**
+ * Payload of [`events::APP_READY`].
+ */
+export type ReadyPayload = { 
+/**
+ * The subsystem that is ready
+ */
+subsystem: Subsystem; 
+/**
+ * Milliseconds from launch until it was ready
+ */
+elapsedMs: number }
+/**
+ * A rectangle in image pixels, relative to the top-left corner.
+ */
+export type Rect = { 
+/**
+ * Left edge
+ */
+x: number; 
+/**
+ * Top edge
+ */
+y: number; 
+/**
+ * Width
+ */
+width: number; 
+/**
+ * Height
+ */
+height: number }
+/**
+ * Payload of [`events::ATTACHMENT_REJECTED`].
+ */
+export type RejectedPayload = { 
+/**
+ * The rejected file
+ */
+path: string; 
+/**
+ * Why it was rejected
+ */
+message: string; 
+/**
+ * Error code for programmatic handling
+ */
+code: string }
+/**
+ * What a relay said about a published event.
+ */
+export type RelayResult = { 
+/**
+ * The relay URL
+ */
+relay: string; 
+/**
+ * Whether the relay stored the event
+ */
+accepted: boolean; 
+/**
+ * The relay's message, or the connection error
+ */
+message: string }
+/**
+ * One replaced value.
+ */
+export type Replacement = { 
+/**
+ * What the value is
+ */
+kind: EntityKind; 
+/**
+ * The value as first found
+ */
+original: string; 
+/**
+ * Text it was replaced with
+ */
+placeholder: string; 
+/**
+ * How often it was replaced
+ */
+occurrences: number }
+/**
+ * A phrase resolved by `resolve_datetime`.
+ */
+export type ResolvedDateTime = { 
+/**
+ * RFC 3339 timestamp; midnight when the phrase names no time
+ */
+datetime: string; 
+/**
+ * `YYYY-MM-DD`
+ */
+date: string; 
+/**
+ * `HH:MM`, when the phrase names a time
+ */
+time: string | null; 
+/**
+ * English weekday name
+ */
+weekday: string; 
+/**
+ * Timezone the phrase was resolved in
+ */
+timezone: string }
+/**
+ * A session cut short by a crash.
+ */
+export type RestorableSession = { 
+/**
+ * Windows that were open, the main window first
+ */
+windows: SessionWindow[]; 
+/**
+ * Generations that were streaming and are lost
+ */
+interrupted: SessionGeneration[]; 
+/**
+ * Last save in Unix milliseconds
+ */
+savedAt: number }
+/**
+ * How a model should revise a draft.
+ */
+export type Revision = 
+/**
+ * Clearer writing, better flow and structure
+ */
+{ kind: "improve" } | 
+/**
+ * Spelling, grammar, and punctuation only
+ */
+{ kind: "proofread" } | 
+/**
+ * Fewer words, same points
+ */
+{ kind: "shorten" } | 
+/**
+ * More detail where the text is thin
+ */
+{ kind: "expand" } | 
+/**
+ * Suggest a title
+ */
+{ kind: "title" } | 
+/**
+ * Suggest a preview summary
+ */
+{ kind: "summary" } | 
+/**
+ * Follow the writer's own instruction
+ */
+{ kind: "custom"; instruction: string }
+/**
+ * A rewritten text.
+ */
+export type Rewrite = { 
+/**
+ * The rewritten text
+ */
+text: string; 
+/**
+ * How it was rewritten
+ */
+mode: RewriteMode; 
+/**
+ * The model that rewrote it
+ */
+model: string; 
+/**
+ * Whether the text differs from the original, ignoring surrounding
+ * whitespace
+ */
+changed: boolean; 
+/**
+ * Whether the result was copied to the clipboard
+ */
+copied: boolean }
+/**
+ * How to rewrite.
+ */
+export type RewriteMode = 
+/**
+ * Correct spelling, grammar, and punctuation only
+ */
+"fixGrammar" | 
+/**
+ * Plainer words and shorter sentences
+ */
+"simplify" | 
+/**
+ * A formal, professional tone
+ */
+"formalize" | 
+/**
+ * Fewer words, same points
+ */
+"shorten"
+/**
+ * The voice of each role.
+ */
+export type RoleVoices = { 
+/**
+ * Voice of the user's messages
+ */
+user: string; 
+/**
+ * Voice of the model's answers
+ */
+assistant: string; 
+/**
+ * Voice of system prompts, when they are read
+ */
+system: string }
+/**
+ * Providers and models that must not receive sensitive conversations.
+ */
+export type RoutingPolicy = { 
+/**
+ * Provider or gateway names, e.g. `openai`
+ */
+blockedProviders: string[]; 
+/**
+ * Model IDs, e.g. `openai/gpt-4o`; variants like `:online` match too
+ */
+blockedModels: string[] }
+/**
+ * Payload of [`events::SCHEDULER_RUN_COMPLETED`].
+ */
+export type RunCompletedPayload = { 
+/**
+ * The task that ran
+ */
+taskId: string; 
+/**
+ * Outcome of the run
+ */
+status: RunStatus; 
+/**
+ * Conversation holding the result, on success
+ */
+conversationId: string | null; 
+/**
+ * Workflow run, for tasks that run a workflow
+ */
+workflowRunId: string | null; 
+/**
+ * Error message, on failure
+ */
+error: string | null }
+/**
+ * Outcome of the most recent run of a task.
+ */
+export type RunStatus = 
+/**
+ * The prompt ran and its result was stored
+ */
+"succeeded" | 
+/**
+ * The prompt could not be completed
+ */
+"failed"
+/**
+ * One completion's measurements.
+ */
+export type Sample = { 
+/**
+ * Model requested
+ */
+model: string; 
+/**
+ * Upstream provider that served it, when known
+ */
+provider?: string | null; 
+/**
+ * Time to the first streamed token
+ */
+ttftMs?: number | null; 
+/**
+ * Time until the response was complete or failed
+ */
+durationMs: number; 
+/**
+ * Tokens generated
+ */
+completionTokens?: number | null; 
+/**
+ * Error code if the request failed
+ */
+error?: string | null }
+/**
+ * A saved prompt that runs on a schedule.
+ */
+export type ScheduledTask = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display name, also used as the title of result conversations
+ */
+name: string; 
+/**
+ * The user prompt sent to the model
+ */
+prompt: string; 
+/**
+ * Optional system prompt
+ */
+systemPrompt: string | null; 
+/**
+ * Model override; defaults to the standard tier
+ */
+model: string | null; 
+/**
+ * Cron expression describing when the task runs
+ */
+cron: string; 
+/**
+ * Whether the task is active
+ */
+enabled: boolean; 
+/**
+ * Whether to send a desktop notification on completion
+ */
+notify: boolean; 
+/**
+ * Time of the last run in Unix milliseconds
+ */
+lastRunAt: number | null; 
+/**
+ * Outcome of the last run
+ */
+lastStatus: RunStatus | null; 
+/**
+ * Error message of the last failed run
+ */
+lastError: string | null; 
+/**
+ * Conversation produced by the last successful run
+ */
+lastConversationId: string | null; 
+/**
+ * Next scheduled run in Unix milliseconds
+ */
+nextRunAt: number | null; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Workflow run with the prompt as its input, instead of the model
+ */
+workflowId: string | null; 
+/**
+ * Workflow run started by the last run
+ */
+lastWorkflowRunId: string | null }
+/**
+ * Fields for creating or updating a scheduled task.
+ */
+export type ScheduledTaskInput = { 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * The user prompt sent to the model
+ */
+prompt: string; 
+/**
+ * Optional system prompt
+ */
+systemPrompt?: string | null; 
+/**
+ * Model override
+ */
+model?: string | null; 
+/**
+ * Cron expression
+ */
+cron: string; 
+/**
+ * Whether the task is active (default: true)
+ */
+enabled?: boolean; 
+/**
+ * Whether to notify on completion (default: true)
+ */
+notify?: boolean; 
+/**
+ * Workflow to run instead of asking the model; the prompt becomes its
+ * input and may be empty
+ */
+workflowId?: string | null }
+/**
+ * A search result.
+ */
+export type SearchHit = 
+/**
+ * A matching conversation
+ */
+{ kind: "conversation"; id: string; title: string; updatedAt: number } | 
+/**
+ * A matching reading list item
+ */
+{ kind: "readingItem"; id: string; title: string; url: string; updatedAt: number }
+/**
+ * Payload of [`events::OUTBOX_SENT`].
+ */
+export type SentPayload = { 
+/**
+ * The delivered entry
+ */
+entryId: string; 
+/**
+ * The stored reply
+ */
+message: StoredMessage }
+/**
+ * Something the backend talks to, with its own client.
+ */
+export type Service = 
+/**
+ * OpenRouter chat completions
+ */
+"openrouter" | 
+/**
+ * Update checks and downloads
+ */
+"updater" | 
+/**
+ * Nostr relays and media servers
+ */
+"nostr" | 
+/**
+ * Telemetry batches
+ */
+"telemetry" | 
+/**
+ * Crash report uploads
+ */
+"crashReports" | 
+/**
+ * Diagnostics connectivity checks
+ */
+"diagnostics" | 
+/**
+ * Webhook deliveries
+ */
+"webhooks" | 
+/**
+ * Requests made by plugins
+ */
+"plugins" | 
+/**
+ * Diagram rendering for exports
+ */
+"diagrams" | 
+/**
+ * Encrypted conversation uploads
+ */
+"sharing" | 
+/**
+ * Pages fetched for the reading list
+ */
+"readingList" | 
+/**
+ * RSS and Atom feed polling
+ */
+"feeds" | 
+/**
+ * Image generation providers other than OpenRouter
+ */
+"images" | 
+/**
+ * Local Stable Diffusion server
+ */
+"stableDiffusion" | 
+/**
+ * Currency exchange rates for the calculator
+ */
+"exchangeRates" | 
+/**
+ * Weather forecasts, place lookups, and IP geolocation
+ */
+"weather" | 
+/**
+ * NIP-96 and Blossom media servers for image and audio uploads
+ */
+"mediaServers" | 
+/**
+ * Text-to-speech for audio exports
+ */
+"speech"
+/**
+ * A generation streaming when the session was saved.
+ */
+export type SessionGeneration = { 
+/**
+ * Stream identifier chosen by the webview
+ */
+streamId: string; 
+/**
+ * Conversation the reply belongs to
+ */
+conversationId: string | null; 
+/**
+ * Start time in Unix milliseconds
+ */
+startedAt: number }
+/**
+ * A window of a session.
+ */
+export type SessionWindow = { 
+/**
+ * Window label
+ */
+label: string; 
+/**
+ * Conversation shown in the window
+ */
+conversationId: string | null; 
+/**
+ * Scroll offset of the conversation in CSS pixels
+ */
+scrollTop: number }
+/**
+ * The general settings document.
+ */
+export type Settings = { 
+/**
+ * Color theme
+ */
+theme: Theme; 
+/**
+ * UI language as a BCP 47 tag, or `system` to follow the OS
+ */
+language: string; 
+/**
+ * Model used for new conversations
+ */
+defaultModel: string; 
+/**
+ * Sampling temperature for new conversations
+ */
+temperature: number; 
+/**
+ * Response token limit for new conversations
+ */
+maxTokens: number; 
+/**
+ * Hide the main window to the tray instead of quitting on close
+ */
+closeToTray: boolean; 
+/**
+ * Send the message on Enter (Shift+Enter inserts a newline)
+ */
+sendOnEnter: boolean; 
+/**
+ * Kroki server rendering diagrams in HTML exports; empty exports them
+ * as code
+ */
+diagramServer: string; 
+/**
+ * Remove EXIF, GPS, and text metadata from images sent to providers
+ */
+stripImageMetadata: boolean; 
+/**
+ * Ask providers not to store or train on requests
+ */
+zeroDataRetention: boolean; 
+/**
+ * Answer repeated temperature-0 requests from a local cache
+ */
+cacheDeterministicResponses: boolean; 
+/**
+ * Tell models the current date, time, timezone, and locale
+ */
+datetimeContext: boolean; 
+/**
+ * Model used for translations; empty uses `default_model`
+ */
+translationModel: string }
+/**
+ * A document an import would replace.
+ */
+export type SettingsChange = { 
+/**
+ * Settings key
+ */
+key: string; 
+/**
+ * Document in effect now
+ */
+current: JsonValue | null; 
+/**
+ * Document from the file
+ */
+incoming: JsonValue }
+/**
+ * A conversation shared as an encrypted upload.
+ */
+export type Share = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Conversation that was shared; it may have been deleted since
+ */
+conversationId: string; 
+/**
+ * Backend kind, e.g. "paste"
+ */
+backend: string; 
+/**
+ * URL of the encrypted upload
+ */
+url: string; 
+/**
+ * Link to hand out, with the key in the fragment
+ */
+link: string; 
+/**
+ * When the backend drops the upload, in Unix milliseconds
+ */
+expiresAt: number | null; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * When the share was revoked, in Unix milliseconds
+ */
+revokedAt: number | null }
+/**
+ * Where encrypted conversations are uploaded.
+ */
+export type ShareBackend = 
+/**
+ * A 0x0.st-compatible paste service
+ */
+{ kind: "paste"; url: string }
+/**
+ * Sharing preferences.
+ */
+export type ShareSettings = { 
+/**
+ * Upload target for new shares
+ */
+backend: ShareBackend }
+/**
+ * Shortcut bindings; `None` leaves an action unbound.
+ */
+export type ShortcutSettings = { 
+/**
+ * Binding for [`ShortcutAction::QuickCapture`]
+ */
+quickCapture: string | null; 
+/**
+ * Binding for [`ShortcutAction::PushToTalk`]
+ */
+pushToTalk: string | null; 
+/**
+ * Binding for [`ShortcutAction::Screenshot`]
+ */
+screenshot: string | null; 
+/**
+ * Binding for [`ShortcutAction::ToggleWindow`]
+ */
+toggleWindow: string | null }
+/**
+ * Spending limits in US dollars; `None` means no limit.
+ */
+export type SpendCaps = { 
+/**
+ * Limit per local calendar day
+ */
+dailyUsd: number | null; 
+/**
+ * Limit per local calendar month
+ */
+monthlyUsd: number | null }
+/**
+ * Which cap a request would break.
+ */
+export type SpendPeriod = 
+/**
+ * The daily cap
+ */
+"daily" | 
+/**
+ * The monthly cap
+ */
+"monthly"
+/**
+ * Spend so far and the caps.
+ */
+export type SpendSummary = { 
+/**
+ * Spent today
+ */
+todayUsd: number; 
+/**
+ * Spent this month
+ */
+monthUsd: number; 
+/**
+ * The caps
+ */
+caps: SpendCaps; 
+/**
+ * When an override ends, in Unix milliseconds
+ */
+overrideUntil: number | null }
+/**
+ * Local server preferences.
+ */
+export type StableDiffusionSettings = { 
+/**
+ * API flavour of the server
+ */
+backend: LocalBackend; 
+/**
+ * Base URL of the server
+ */
+url: string }
+/**
+ * Payload of [`events::ATTACHMENT_STAGED`].
+ */
+export type StagedPayload = { 
+/**
+ * The staged attachment
+ */
+attachment: Attachment; 
+/**
+ * Window the file was dropped onto, for drops
+ */
+window: string | null }
+/**
+ * What `get_startup_status` returns.
+ */
+export type StartupStatus = { 
+/**
+ * Subsystems that are ready, in the order they became ready
+ */
+ready: ReadyPayload[]; 
+/**
+ * Subsystems still initializing
+ */
+pending: Subsystem[] }
+/**
+ * Payload of [`events::WORKFLOW_STEP`].
+ */
+export type StepEventPayload = { 
+/**
+ * The run this step belongs to
+ */
+runId: string; 
+/**
+ * The workflow being run
+ */
+workflowId: string; 
+/**
+ * Position of the step in the workflow
+ */
+stepIndex: number; 
+/**
+ * Step name
+ */
+name: string; 
+/**
+ * New step status
+ */
+status: WorkflowStatus; 
+/**
+ * Attempt number, starting at 1
+ */
+attempt: number; 
+/**
+ * Step output, when succeeded
+ */
+output: string | null; 
+/**
+ * Error message, when retrying or failed
+ */
+error: string | null }
+/**
+ * A stored message.
+ */
+export type StoredMessage = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Owning conversation
+ */
+conversationId: string; 
+/**
+ * The role of the message sender
+ */
+role: MessageRole; 
+/**
+ * The message content
+ */
+content: string; 
+/**
+ * Model that generated the message (assistant messages only)
+ */
+model: string | null; 
+/**
+ * Prompt tokens consumed, when known
+ */
+promptTokens: number | null; 
+/**
+ * Completion tokens generated, when known
+ */
+completionTokens: number | null; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * What to send.
+ */
+export type StreamChatRequest = { 
+/**
+ * Model ID; the default model when `None`
+ */
+model?: string | null; 
+/**
+ * The conversation so far
+ */
+messages: ChatMessage[]; 
+/**
+ * Maximum tokens to generate
+ */
+maxTokens?: number | null; 
+/**
+ * Temperature for randomness
+ */
+temperature?: number | null; 
+/**
+ * Conversation the request is for, checked against the routing policy
+ */
+conversationId?: string | null }
+/**
+ * A stream that was cut off.
+ */
+export type StreamDraft = { 
+/**
+ * Stream identifier chosen by the webview
+ */
+streamId: string; 
+/**
+ * Conversation the reply belongs to
+ */
+conversationId: string | null; 
+/**
+ * The request as sent
+ */
+request: StreamChatRequest; 
+/**
+ * Text generated before the stream was cut off
+ */
+partial: string; 
+/**
+ * Start time in Unix milliseconds
+ */
+startedAt: number; 
+/**
+ * Last save in Unix milliseconds
+ */
+updatedAt: number }
+/**
+ * The finished reply.
+ */
+export type StreamedCompletion = { 
+/**
+ * The whole generated text
+ */
+content: string; 
+/**
+ * The model that served the request
+ */
+model: string; 
+/**
+ * Token usage, when reported
+ */
+usage: TokenUsage | null; 
+/**
+ * Cost in US dollars, when reported
+ */
+costUsd: number | null; 
+/**
+ * Upstream provider, when reported
+ */
+provider: string | null; 
+/**
+ * Batches emitted
+ */
+batches: number }
+/**
+ * A structured data format.
+ */
+export type StructuredFormat = 
+/**
+ * JSON
+ */
+"json" | 
+/**
+ * YAML
+ */
+"yaml" | 
+/**
+ * TOML; documents must be tables
+ */
+"toml"
+/**
+ * Payload of [`events::QUICK_CAPTURE_SUBMITTED`].
+ */
+export type SubmittedPayload = { 
+/**
+ * The conversation created for the prompt
+ */
+conversationId: string; 
+/**
+ * The submitted prompt
+ */
+prompt: string; 
+/**
+ * Model requested for the answer, if any
+ */
+model: string | null }
+/**
+ * A part of the app that initializes on its own schedule.
+ */
+export type Subsystem = 
+/**
+ * The app database, opened and migrated during setup
+ */
+"database" | 
+/**
+ * Orphan pruning and files the app was launched with
+ */
+"attachments" | 
+/**
+ * The WebAssembly engine running plugins
+ */
+"plugins" | 
+/**
+ * Webhook deliveries left over from the previous run
+ */
+"webhooks"
+/**
+ * Outcome of a draft sync.
+ */
+export type SyncReport = { 
+/**
+ * Drafts published to the relays
+ */
+uploaded: number; 
+/**
+ * Drafts created or updated from the relays
+ */
+downloaded: number; 
+/**
+ * Drafts removed because they were deleted on another device
+ */
+removed: number; 
+/**
+ * Drafts too large to encrypt, left out
+ */
+skipped: number }
+/**
+ * A batch as sent to the collection endpoint.
+ */
+export type TelemetryBatch = { 
+/**
+ * Batch format version
+ */
+schema: number; 
+/**
+ * App version
+ */
+appVersion: string; 
+/**
+ * Operating system family (`macos`, `windows`, `linux`)
+ */
+os: string; 
+/**
+ * Counts for complete days
+ */
+counts: TelemetryCount[] }
+/**
+ * One queued count.
+ */
+export type TelemetryCount = { 
+/**
+ * What is counted
+ */
+kind: TelemetryKind; 
+/**
+ * Event name
+ */
+name: string; 
+/**
+ * UTC day as `YYYY-MM-DD`
+ */
+day: string; 
+/**
+ * Occurrences that day
+ */
+count: number }
+/**
+ * What a count measures.
+ */
+export type TelemetryKind = 
+/**
+ * A feature was used
+ */
+"feature" | 
+/**
+ * An operation failed with an error category
+ */
+"error"
+/**
+ * Telemetry preferences.
+ */
+export type TelemetrySettings = { 
+/**
+ * Collect and send usage counts
+ */
+enabled: boolean }
+/**
+ * Color theme.
+ */
+export type Theme = 
+/**
+ * Follow the OS
+ */
+"system" | 
+/**
+ * Light theme
+ */
+"light" | 
+/**
+ * Dark theme
+ */
+"dark"
+/**
+ * Token usage statistics reported by the provider.
+ */
+export type TokenUsage = { 
+/**
+ * Tokens in the prompt
+ */
+prompt_tokens: number; 
+/**
+ * Tokens in the completion
+ */
+completion_tokens: number }
+/**
+ * What `transform_data` does.
+ */
+export type TransformRequest = { 
+/**
+ * Document to transform
+ */
+input: string; 
+/**
+ * Format of `input`
+ */
+from: StructuredFormat; 
+/**
+ * Format of the output; the input format when `None`
+ */
+to?: StructuredFormat | null; 
+/**
+ * jq filter to run; when `None` the document is only converted
+ */
+filter?: string | null; 
+/**
+ * Write string results without quotes, like `jq -r`
+ */
+raw?: boolean; 
+/**
+ * Write JSON on one line
+ */
+compact?: boolean }
+/**
+ * What `transform_data` returns.
+ */
+export type TransformResult = { 
+/**
+ * The results, one document each: JSON separated by newlines, YAML
+ * by `---`, TOML by blank lines
+ */
+output: string; 
+/**
+ * Results the filter produced
+ */
+results: number; 
+/**
+ * Whether results past the first 1000 were dropped
+ */
+truncated: boolean }
+/**
+ * A translated attachment.
+ */
+export type TranslatedDocument = { 
+/**
+ * The new attachment holding the translation
+ */
+attachment: Attachment; 
+/**
+ * Language the original was detected in
+ */
+detected: DetectedLanguage | null; 
+/**
+ * Chunks sent for translation
+ */
+chunks: number }
+/**
+ * A translated text.
+ */
+export type Translation = { 
+/**
+ * The translation
+ */
+text: string; 
+/**
+ * Language the text was detected in, when detection was needed
+ */
+detected: DetectedLanguage | null; 
+/**
+ * Target language as understood
+ */
+target: string; 
+/**
+ * The model that translated, or `None` if the text was already in the
+ * target language
+ */
+model: string | null }
+/**
+ * What `translate_text` translates.
+ */
+export type TranslationRequest = { 
+/**
+ * The text
+ */
+text: string; 
+/**
+ * Target language, as a name (`German`) or ISO 639 code (`de`, `deu`)
+ */
+target: string; 
+/**
+ * Source language; detected when `None`
+ */
+source?: string | null; 
+/**
+ * Model ID; the translation model from the settings when `None`
+ */
+model?: string | null }
+/**
+ * Snapshot of the tray state.
+ */
+export type TrayStatus = { 
+/**
+ * Whether closing the main window hides it to the tray
+ */
+closeToTray: boolean; 
+/**
+ * Whether background sync is paused
+ */
+syncPaused: boolean; 
+/**
+ * Whether any generation is in progress
+ */
+generating: boolean }
+/**
+ * Release channel.
+ */
+export type UpdateChannel = 
+/**
+ * Tested releases
+ */
+"stable" | 
+/**
+ * Pre-releases
+ */
+"beta"
+/**
+ * An available update.
+ */
+export type UpdateInfo = { 
+/**
+ * Version of the update
+ */
+version: string; 
+/**
+ * Version currently running
+ */
+currentVersion: string; 
+/**
+ * Release notes
+ */
+notes: string | null; 
+/**
+ * Publication date
+ */
+date: string | null; 
+/**
+ * Channel the update came from
+ */
+channel: UpdateChannel }
+/**
+ * Payload of [`events::UPDATER_PROGRESS`].
+ */
+export type UpdateProgress = { 
+/**
+ * Bytes downloaded so far
+ */
+downloaded: number; 
+/**
+ * Total size, if the server reported it
+ */
+contentLength: number | null }
+/**
+ * Update preferences.
+ */
+export type UpdateSettings = { 
+/**
+ * Channel to receive updates from
+ */
+channel: UpdateChannel; 
+/**
+ * Check for updates in the background
+ */
+autoCheck: boolean; 
+/**
+ * Download found updates in the background
+ */
+autoDownload: boolean }
+/**
+ * An uploaded file.
+ */
+export type UploadedMedia = { 
+/**
+ * Public URL of the file
+ */
+url: string; 
+/**
+ * Hex SHA-256 of the file as uploaded
+ */
+sha256: string; 
+/**
+ * MIME type
+ */
+mimeType: string; 
+/**
+ * Size in bytes
+ */
+size: number; 
+/**
+ * The server that stored it
+ */
+server: string; 
+/**
+ * NIP-92 `imeta` tag describing the file, for the event embedding it
+ */
+imeta: string[] }
+/**
+ * What kind of item is ranked.
+ */
+export type UsageKind = 
+/**
+ * A model, by model ID
+ */
+"model" | 
+/**
+ * A new-chat preset, by preset ID
+ */
+"preset"
+/**
+ * One side of an experiment.
+ */
+export type Variant = 
+/**
+ * The first variant, usually the current prompt
+ */
+"a" | 
+/**
+ * The second variant, usually the candidate
+ */
+"b"
+/**
+ * What a variant changes about a conversation.
+ */
+export type VariantConfig = { 
+/**
+ * Short name shown in reports
+ */
+label: string; 
+/**
+ * System prompt of enrolled conversations
+ */
+systemPrompt: string; 
+/**
+ * Model of enrolled conversations; keeps the chosen model when `None`
+ */
+model?: string | null }
+/**
+ * Totals for one variant.
+ */
+export type VariantStats = { 
+/**
+ * The variant
+ */
+variant: Variant; 
+/**
+ * Its label
+ */
+label: string; 
+/**
+ * Conversations enrolled with it
+ */
+conversations: number; 
+/**
+ * Thumbs-up ratings
+ */
+thumbsUp: number; 
+/**
+ * Thumbs-down ratings
+ */
+thumbsDown: number; 
+/**
+ * Share of ratings that are thumbs-up, once there are any
+ */
+approval: number | null }
+/**
+ * What `get_weather` returns.
+ */
+export type WeatherReport = { 
+/**
+ * Where the weather is for
+ */
+location: Location; 
+/**
+ * Conditions now
+ */
+current: CurrentWeather; 
+/**
+ * Forecast from today
+ */
+daily: DailyForecast[] }
+/**
+ * A registered webhook.
+ */
+export type Webhook = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * `http(s)://` URL receiving the events
+ */
+url: string; 
+/**
+ * Key the payloads are signed with
+ */
+secret: string; 
+/**
+ * Subscribed events
+ */
+events: WebhookEvent[]; 
+/**
+ * Whether events are sent
+ */
+enabled: boolean; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number }
+/**
+ * An entry of the delivery log.
+ */
+export type WebhookDelivery = { 
+/**
+ * Unique identifier, sent as `X-Gibber-Delivery`
+ */
+id: string; 
+/**
+ * The receiving webhook
+ */
+webhookId: string; 
+/**
+ * The delivered event
+ */
+event: WebhookEvent; 
+/**
+ * Delivery state
+ */
+status: DeliveryStatus; 
+/**
+ * Attempts so far
+ */
+attempts: number; 
+/**
+ * HTTP status of the last answer
+ */
+responseStatus: number | null; 
+/**
+ * Error of the last attempt
+ */
+lastError: string | null; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Time of the last attempt in Unix milliseconds
+ */
+updatedAt: number }
+/**
+ * Something a webhook can subscribe to.
+ */
+export type WebhookEvent = 
+/**
+ * A scheduled task ran successfully
+ */
+"task.succeeded" | 
+/**
+ * A scheduled task failed
+ */
+"task.failed" | 
+/**
+ * A workflow run finished successfully
+ */
+"workflow.succeeded" | 
+/**
+ * A workflow run failed
+ */
+"workflow.failed"
+/**
+ * Fields for creating or updating a webhook.
+ */
+export type WebhookInput = { 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * `http(s)://` URL receiving the events
+ */
+url: string; 
+/**
+ * Events to subscribe to; must not be empty
+ */
+events: WebhookEvent[]; 
+/**
+ * Whether events are sent (default: true)
+ */
+enabled?: boolean }
+/**
+ * A saved workflow.
+ */
+export type Workflow = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * Steps in execution order
+ */
+steps: WorkflowStep[]; 
+/**
+ * Creation time in Unix milliseconds
+ */
+createdAt: number; 
+/**
+ * Last update time in Unix milliseconds
+ */
+updatedAt: number }
+/**
+ * Fields for creating or updating a workflow.
+ */
+export type WorkflowInput = { 
+/**
+ * Display name
+ */
+name: string; 
+/**
+ * Steps in execution order
+ */
+steps: WorkflowStep[] }
+/**
+ * A recorded workflow run.
+ */
+export type WorkflowRun = { 
+/**
+ * Unique identifier
+ */
+id: string; 
+/**
+ * The workflow that ran
+ */
+workflowId: string; 
+/**
+ * Overall status
+ */
+status: WorkflowStatus; 
+/**
+ * Input the run was started with
+ */
+input: string; 
+/**
+ * Output of the last step, on success
+ */
+output: string | null; 
+/**
+ * Error of the failing step, on failure
+ */
+error: string | null; 
+/**
+ * Start time in Unix milliseconds
+ */
+startedAt: number; 
+/**
+ * Finish time in Unix milliseconds
+ */
+finishedAt: number | null; 
+/**
+ * Per-step records (empty in run listings)
+ */
+steps: WorkflowRunStep[] }
+/**
+ * Recorded outcome of one step in a run.
+ */
+export type WorkflowRunStep = { 
+/**
+ * Position of the step in the workflow
+ */
+stepIndex: number; 
+/**
+ * Step name at the time of the run
+ */
+name: string; 
+/**
+ * Step status
+ */
+status: WorkflowStatus; 
+/**
+ * Number of attempts made
+ */
+attempts: number; 
+/**
+ * Step output, on success
+ */
+output: string | null; 
+/**
+ * Last error, on failure
+ */
+error: string | null; 
+/**
+ * Start time in Unix milliseconds
+ */
+startedAt: number; 
+/**
+ * Finish time in Unix milliseconds
+ */
+finishedAt: number | null }
+/**
+ * Status of a run or a single step.
+ */
+export type WorkflowStatus = 
+/**
+ * Work is in progress
+ */
+"running" | 
+/**
+ * A failed attempt is about to be retried (events only)
+ */
+"retrying" | 
+/**
+ * Completed successfully
+ */
+"succeeded" | 
+/**
+ * Gave up after exhausting retries
+ */
+"failed"
+/**
+ * One prompt in a workflow.
+ */
+export type WorkflowStep = { 
+/**
+ * Step name, unique within the workflow
+ */
+name: string; 
+/**
+ * Prompt template for this step
+ */
+prompt: string; 
+/**
+ * Optional system prompt
+ */
+systemPrompt?: string | null; 
+/**
+ * Model override; defaults to the standard tier
+ */
+model?: string | null; 
+/**
+ * JSON pointer selecting the step output from the response
+ */
+extract?: string | null; 
+/**
+ * How many times to retry a failed step (default: 1)
+ */
+maxRetries?: number }
+
+/** tauri-specta globals **/
+
+import {
+	invoke as TAURI_INVOKE,
+	Channel as TAURI_CHANNEL,
+} from "@tauri-apps/api/core";
+import * as TAURI_API_EVENT from "@tauri-apps/api/event";
+import { type WebviewWindow as __WebviewWindow__ } from "@tauri-apps/api/webviewWindow";
+
+type __EventObj__<T> = {
+	listen: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.listen<T>>;
+	once: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.once<T>>;
+	emit: null extends T
+		? (payload?: T) => ReturnType<typeof TAURI_API_EVENT.emit>
+		: (payload: T) => ReturnType<typeof TAURI_API_EVENT.emit>;
+};
+
+export type Result<T, E> =
+	| { status: "ok"; data: T }
+	| { status: "error"; error: E };
+
+function __makeEvents__<T extends Record<string, any>>(
+	mappings: Record<keyof T, string>,
+) {
+	return new Proxy(
+		{} as unknown as {
+			[K in keyof T]: __EventObj__<T[K]> & {
+				(handle: __WebviewWindow__): __EventObj__<T[K]>;
+			};
+		},
+		{
+			get: (_, event) => {
+				const name = mappings[event as keyof T];
+
+				return new Proxy((() => {}) as any, {
+					apply: (_, __, [window]: [__WebviewWindow__]) => ({
+						listen: (arg: any) => window.listen(name, arg),
+						once: (arg: any) => window.once(name, arg),
+						emit: (arg: any) => window.emit(name, arg),
+					}),
+					get: (_, command: keyof __EventObj__<any>) => {
+						switch (command) {
+							case "listen":
+								return (arg: any) => TAURI_API_EVENT.listen(name, arg);
+							case "once":
+								return (arg: any) => TAURI_API_EVENT.once(name, arg);
+							case "emit":
+								return (arg: any) => TAURI_API_EVENT.emit(name, arg);
+						}
+					},
+				});
+			},
+		},
+	);
+}
@@ -1,13 +1,13 @@
 /**
  * API functions for secure credential storage.
  *
- * These functions wrap Tauri IPC commands for storing and retrieving
+ * These functions wrap the generated command bindings for storing and retrieving
  * API keys using the operating system's secure keyring.
  *
  * @module credentials/api
  */
 
-import { invoke } from "@tauri-apps/api/core";
+import { commands } from "$lib/bindings";
 import type { ServiceId } from "./types";
 
 /**
@@ -26,8 +26,7 @@ import type { ServiceId } from "./types";
  * ```
  */
 export const getApiKey = async (service: ServiceId): Promise<string | null> => {
-  const result = await invoke<string | null>("get_api_key", { service });
-  return result;
+  return commands.getApiKey(service);
 };
 
 /**
@@ -45,7 +44,7 @@ export const getApiKey = async (service: ServiceId): Promise<string | null> => {
  * ```
  */
 export const setApiKey = async (service: ServiceId, key: string): Promise<void> => {
-  await invoke("set_api_key", { service, key });
+  await commands.setApiKey(service, key);
 };
 
 /**
@@ -62,7 +61,7 @@ export const setApiKey = async (service: ServiceId, key: string): Promise<void>
  * ```
  */
 export const deleteApiKey = async (service: ServiceId): Promise<boolean> => {
-  return invoke<boolean>("delete_api_key", { service });
+  return commands.deleteApiKey(service);
 };
 
 /**
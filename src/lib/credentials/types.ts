@@ -12,7 +12,7 @@ export type ServiceId = "openrouter" | "nostr" | "mastodon";
 
 /**
  * Error returned from credential operations.
- * Matches the Rust CredentialError struct.
+ * Matches how the Rust GibberError serializes.
  */
 export interface CredentialError {
   /** Human-readable error message */
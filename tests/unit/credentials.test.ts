@@ -1,17 +1,27 @@
 /**
  * Unit tests for the credentials module.
  *
- * Tests cover the TypeScript API functions that wrap Tauri IPC commands.
- * Tauri's invoke is mocked in setup.ts.
+ * Tests cover the TypeScript API functions that wrap the generated command
+ * bindings, which are mocked here.
  */
 
 import { describe, it, expect, vi, beforeEach } from "vitest";
-import { invoke } from "@tauri-apps/api/core";
+import { commands } from "$lib/bindings";
 import { getApiKey, setApiKey, deleteApiKey, hasApiKey } from "$lib/credentials";
 import { isCredentialError } from "$lib/credentials/types";
 
-// Type the mock for better intellisense
-const mockInvoke = vi.mocked(invoke);
+vi.mock("$lib/bindings", () => ({
+  commands: {
+    getApiKey: vi.fn(),
+    setApiKey: vi.fn(),
+    deleteApiKey: vi.fn(),
+  },
+}));
+
+// Type the mocks for better intellisense
+const mockGetApiKey = vi.mocked(commands.getApiKey);
+const mockSetApiKey = vi.mocked(commands.setApiKey);
+const mockDeleteApiKey = vi.mocked(commands.deleteApiKey);
 
 describe("credentials module", () => {
   beforeEach(() => {
@@ -19,48 +29,43 @@ describe("credentials module", () => {
   });
 
   describe("getApiKey", () => {
-    it("should call invoke with correct command and service", async () => {
-      mockInvoke.mockResolvedValueOnce("test-api-key");
+    it("should call the command with the service", async () => {
+      mockGetApiKey.mockResolvedValueOnce("test-api-key");
 
       const result = await getApiKey("openrouter");
 
-      expect(mockInvoke).toHaveBeenCalledWith("get_api_key", {
-        service: "openrouter",
-      });
+      expect(mockGetApiKey).toHaveBeenCalledWith("openrouter");
       expect(result).toBe("test-api-key");
     });
 
     it("should return null when no key exists", async () => {
-      mockInvoke.mockResolvedValueOnce(null);
+      mockGetApiKey.mockResolvedValueOnce(null);
 
       const result = await getApiKey("openrouter");
 
       expect(result).toBeNull();
     });
 
-    it("should propagate errors from invoke", async () => {
+    it("should propagate errors from the command", async () => {
       const error = { message: "Storage error", code: "PLATFORM_FAILURE" };
-      mockInvoke.mockRejectedValueOnce(error);
+      mockGetApiKey.mockRejectedValueOnce(error);
 
       await expect(getApiKey("openrouter")).rejects.toEqual(error);
     });
   });
 
   describe("setApiKey", () => {
-    it("should call invoke with correct command, service, and key", async () => {
-      mockInvoke.mockResolvedValueOnce(undefined);
+    it("should call the command with the service and key", async () => {
+      mockSetApiKey.mockResolvedValueOnce(null);
 
       await setApiKey("openrouter", "sk-test-key");
 
-      expect(mockInvoke).toHaveBeenCalledWith("set_api_key", {
-        service: "openrouter",
-        key: "sk-test-key",
-      });
+      expect(mockSetApiKey).toHaveBeenCalledWith("openrouter", "sk-test-key");
     });
 
-    it("should propagate errors from invoke", async () => {
+    it("should propagate errors from the command", async () => {
       const error = { message: "Write failed", code: "NO_STORAGE_ACCESS" };
-      mockInvoke.mockRejectedValueOnce(error);
+      mockSetApiKey.mockRejectedValueOnce(error);
 
       await expect(setApiKey("openrouter", "sk-key")).rejects.toEqual(error);
     });
@@ -68,18 +73,16 @@ describe("credentials module", () => {
 
   describe("deleteApiKey", () => {
     it("should return true when key was deleted", async () => {
-      mockInvoke.mockResolvedValueOnce(true);
+      mockDeleteApiKey.mockResolvedValueOnce(true);
 
       const result = await deleteApiKey("openrouter");
 
-      expect(mockInvoke).toHaveBeenCalledWith("delete_api_key", {
-        service: "openrouter",
-      });
+      expect(mockDeleteApiKey).toHaveBeenCalledWith("openrouter");
       expect(result).toBe(true);
     });
 
     it("should return false when key did not exist", async () => {
-      mockInvoke.mockResolvedValueOnce(false);
+      mockDeleteApiKey.mockResolvedValueOnce(false);
 
       const result = await deleteApiKey("openrouter");
 
@@ -89,7 +92,7 @@ describe("credentials module", () => {
 
   describe("hasApiKey", () => {
     it("should return true when key exists", async () => {
-      mockInvoke.mockResolvedValueOnce("some-key");
+      mockGetApiKey.mockResolvedValueOnce("some-key");
 
       const result = await hasApiKey("openrouter");
 
@@ -97,7 +100,7 @@ describe("credentials module", () => {
     });
 
     it("should return false when key does not exist", async () => {
-      mockInvoke.mockResolvedValueOnce(null);
+      mockGetApiKey.mockResolvedValueOnce(null);
 
       const result = await hasApiKey("openrouter");
 
@@ -141,7 +144,7 @@ describe("credentials module", () => {
 
   describe("service type safety", () => {
     it("should only accept valid service identifiers", async () => {
-      mockInvoke.mockResolvedValue(null);
+      mockGetApiKey.mockResolvedValue(null);
 
       // These should compile without errors
       await getApiKey("openrouter");